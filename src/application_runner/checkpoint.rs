@@ -0,0 +1,237 @@
+//! Module implementing the checkpoint of a run, in order to resume it after a crash
+//!
+//! After each verification, its result is appended to the checkpoint file (one json line per
+//! verification, after a header line identifying the period and the dataset). A run resumed
+//! from the checkpoint (see [Checkpoint::resume]) skips the verifications already completed and
+//! takes over their results.
+//!
+//! The file is written line by line. A last line truncated by a crash is ignored when the
+//! checkpoint is resumed.
+use anyhow::{anyhow, bail, Context};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// First line of the checkpoint file, identifying the run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointHeader {
+    /// Period of the run
+    pub period: String,
+    /// Location of the dataset
+    pub dataset: String,
+}
+
+/// Result of a completed verification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointEntry {
+    /// Id of the verification
+    pub id: String,
+    /// Errors of the verification
+    pub errors: Vec<String>,
+    /// Failures of the verification
+    pub failures: Vec<String>,
+}
+
+/// Checkpoint of a run, persisted in a file
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    path: PathBuf,
+    header: CheckpointHeader,
+    entries: HashMap<String, CheckpointEntry>,
+}
+
+impl CheckpointHeader {
+    /// New header for the period and the dataset in `dataset`
+    pub fn new(period: &str, dataset: &Path) -> Self {
+        Self {
+            period: period.to_string(),
+            dataset: fs::canonicalize(dataset)
+                .unwrap_or_else(|_| dataset.to_path_buf())
+                .to_string_lossy()
+                .to_string(),
+        }
+    }
+}
+
+/// Append the value to the file as json line
+fn append_line<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(f, "{}", serde_json::to_string(value)?)?;
+    Ok(())
+}
+
+impl Checkpoint {
+    /// Create a new checkpoint in the file `path`
+    ///
+    /// An existing file is overwritten
+    pub fn create(path: &Path, header: CheckpointHeader) -> anyhow::Result<Self> {
+        if let Some(p) = path.parent() {
+            fs::create_dir_all(p).with_context(|| format!("Cannot create directory {:?}", p))?;
+        }
+        fs::write(path, format!("{}\n", serde_json::to_string(&header)?))
+            .with_context(|| format!("Cannot create the checkpoint {:?}", path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            header,
+            entries: HashMap::new(),
+        })
+    }
+
+    /// Resume the checkpoint stored in the file `path`
+    ///
+    /// If the file does not exist, a new checkpoint is created. If a verification is recorded
+    /// more than once, the last entry is taken.
+    ///
+    /// # Error
+    /// If the file cannot be read, or if the checkpoint was created for another period or
+    /// another dataset
+    pub fn resume(path: &Path, header: CheckpointHeader) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Self::create(path, header);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read the checkpoint {:?}", path))?;
+        let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+        let found: CheckpointHeader = match lines.next() {
+            Some(l) => serde_json::from_str(l).map_err(|e| {
+                anyhow!(e).context(format!("Header of the checkpoint {:?} not valid", path))
+            })?,
+            None => bail!(format!("The checkpoint {:?} is empty", path)),
+        };
+        if found != header {
+            bail!(format!(
+                "The checkpoint {:?} was created for the {} of the dataset {}, and not for the {} of the dataset {}",
+                path, found.period, found.dataset, header.period, header.dataset
+            ))
+        }
+        let lines: Vec<&str> = lines.collect();
+        let mut entries = HashMap::new();
+        for (i, l) in lines.iter().enumerate() {
+            match serde_json::from_str::<CheckpointEntry>(l) {
+                Ok(e) => {
+                    entries.insert(e.id.clone(), e);
+                }
+                Err(e) if i == lines.len() - 1 => {
+                    warn!(
+                        "Last entry of the checkpoint {:?} ignored (truncated?): {}",
+                        path, e
+                    )
+                }
+                Err(e) => {
+                    return Err(anyhow!(e).context(format!(
+                        "Entry {} of the checkpoint {:?} not valid",
+                        i, path
+                    )))
+                }
+            }
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            header,
+            entries,
+        })
+    }
+
+    /// Record the result of the verification with the given id
+    ///
+    /// The entry is appended to the file immediately
+    pub fn record(
+        &mut self,
+        id: &str,
+        errors: &[String],
+        failures: &[String],
+    ) -> anyhow::Result<()> {
+        let entry = CheckpointEntry {
+            id: id.to_string(),
+            errors: errors.to_vec(),
+            failures: failures.to_vec(),
+        };
+        append_line(&self.path, &entry)
+            .with_context(|| format!("Cannot write {} to the checkpoint {:?}", id, self.path))?;
+        self.entries.insert(id.to_string(), entry);
+        Ok(())
+    }
+
+    /// Entry of the verification with the given id. `None` if not completed
+    pub fn entry(&self, id: &str) -> Option<&CheckpointEntry> {
+        self.entries.get(id)
+    }
+
+    /// Ids of the completed verifications (sorted)
+    pub fn completed_ids(&self) -> Vec<String> {
+        let mut res: Vec<String> = self.entries.keys().cloned().collect();
+        res.sort();
+        res
+    }
+
+    /// Header of the checkpoint
+    pub fn header(&self) -> &CheckpointHeader {
+        &self.header
+    }
+
+    /// Location of the checkpoint file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint() {
+        let path = std::env::temp_dir().join("rust_verifier_test_checkpoint.jsonl");
+        let header = CheckpointHeader::new("setup", Path::new("./toto"));
+        let mut checkpoint = Checkpoint::create(&path, header.clone()).unwrap();
+        assert!(checkpoint.completed_ids().is_empty());
+        checkpoint
+            .record("02.01", &[], &["failure".to_string()])
+            .unwrap();
+        checkpoint.record("01.01", &[], &[]).unwrap();
+        let mut checkpoint = Checkpoint::resume(&path, header.clone()).unwrap();
+        assert_eq!(checkpoint.completed_ids(), vec!["01.01", "02.01"]);
+        assert_eq!(
+            checkpoint.entry("02.01").unwrap().failures,
+            vec!["failure".to_string()]
+        );
+        checkpoint.record("02.01", &[], &[]).unwrap();
+        let checkpoint = Checkpoint::resume(&path, header.clone()).unwrap();
+        assert!(checkpoint.entry("02.01").unwrap().failures.is_empty());
+        assert!(checkpoint.entry("03.01").is_none());
+        assert!(
+            Checkpoint::resume(&path, CheckpointHeader::new("tally", Path::new("./toto"))).is_err()
+        );
+        assert!(
+            Checkpoint::resume(&path, CheckpointHeader::new("setup", Path::new("./tata"))).is_err()
+        );
+        let _ = Checkpoint::create(&path, header.clone()).unwrap();
+        assert!(Checkpoint::resume(&path, header)
+            .unwrap()
+            .completed_ids()
+            .is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_checkpoint() {
+        let path = std::env::temp_dir().join("rust_verifier_test_checkpoint_truncated.jsonl");
+        let header = CheckpointHeader::new("tally", Path::new("./toto"));
+        let mut checkpoint = Checkpoint::create(&path, header.clone()).unwrap();
+        checkpoint.record("01.01", &[], &[]).unwrap();
+        checkpoint.record("05.21", &[], &[]).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &content[..content.len() - 10]).unwrap();
+        let checkpoint = Checkpoint::resume(&path, header.clone()).unwrap();
+        assert_eq!(checkpoint.completed_ids(), vec!["01.01"]);
+        let lines: Vec<&str> = content.lines().collect();
+        fs::write(&path, format!("{}\n{{\n{}\n", lines[0], lines[1])).unwrap();
+        assert!(Checkpoint::resume(&path, header).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
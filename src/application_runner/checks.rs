@@ -1,16 +1,21 @@
 use std::path::Path;
 
 use crate::{
-    config::Config as VerifierConfig,
-    verification::{meta_data::VerificationMetaDataList, VerificationPeriod},
+    config::Config as VerifierConfig, direct_trust::check_direct_trust_certificates,
+    file_structure::data_source::DataSource, verification::VerificationPeriod,
 };
 use anyhow::{anyhow, ensure};
+use log::{error, warn};
 
 /// Check some elements at start of the application.
 ///
 /// Must be caled by the application at the beginning. If error, then cannot continue
+///
+/// The problems of the direct trust certificates are logged. They don't stop the application,
+/// since only the verifications of the signatures are concerned
 pub fn start_check(config: &'static VerifierConfig) -> anyhow::Result<()> {
-    let md_list_check = VerificationMetaDataList::load(config.get_verification_list_str());
+    // The metadata remain cached in the configuration for the run
+    let md_list_check = config.verification_metadata();
     ensure!(
         md_list_check.is_ok(),
         format!(
@@ -21,26 +26,40 @@ pub fn start_check(config: &'static VerifierConfig) -> anyhow::Result<()> {
     config
         .keystore()
         .map_err(|e| anyhow!("Cannot read keystore").context(e))?;
+    for (ca, problem) in check_direct_trust_certificates(config) {
+        match problem.is_blocking() {
+            true => error!("Direct trust certificate {}: {}", String::from(ca), problem),
+            false => warn!("Direct trust certificate {}: {}", String::from(ca), problem),
+        }
+    }
     Ok(())
 }
 
-pub fn is_directory_tally(path: &Path) -> anyhow::Result<bool> {
+/// Test if the dataset in `path`, read from the source, contains a tally directory
+///
+/// Error if the dataset or its setup directory does not exist
+pub fn is_directory_tally(source: &dyn DataSource, path: &Path) -> anyhow::Result<bool> {
     ensure!(
-        path.is_dir(),
+        source.is_dir(path),
         format!("Giveen directory {:?} does not exist", path)
     );
     ensure!(
-        path.join(VerifierConfig::setup_dir_name()).is_dir(),
+        source.is_dir(&path.join(VerifierConfig::setup_dir_name())),
         format!(
             "The setup directory {:?} does not exist",
             path.join(VerifierConfig::setup_dir_name())
         )
     );
-    Ok(path.join(VerifierConfig::tally_dir_name()).is_dir())
+    Ok(source.is_dir(&path.join(VerifierConfig::tally_dir_name())))
 }
 
-pub fn check_verification_dir(period: &VerificationPeriod, path: &Path) -> anyhow::Result<()> {
-    let is_tally = is_directory_tally(path)?;
+/// Check that the dataset in `path`, read from the source, can be verified for the period
+pub fn check_verification_dir(
+    period: &VerificationPeriod,
+    source: &dyn DataSource,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let is_tally = is_directory_tally(source, path)?;
     match period.is_tally() {
         true => match is_tally {
             true => Ok(()),
@@ -56,7 +75,7 @@ pub fn check_verification_dir(period: &VerificationPeriod, path: &Path) -> anyho
 #[cfg(test)]
 mod test {
     use super::{VerificationPeriod, *};
-    use crate::config::test::test_datasets_path;
+    use crate::{config::test::test_datasets_path, file_structure::data_source::LocalDataSource};
     use std::path::{Path, PathBuf};
 
     pub(crate) fn dataset_setup_path() -> PathBuf {
@@ -69,21 +88,24 @@ mod test {
 
     #[test]
     fn test_is_directory_tally() {
-        assert!(is_directory_tally(Path::new("./toto")).is_err());
-        assert!(is_directory_tally(Path::new(".")).is_err());
-        assert!(!is_directory_tally(&dataset_setup_path()).unwrap());
-        assert!(is_directory_tally(&dataset_tally_path()).unwrap());
+        assert!(is_directory_tally(&LocalDataSource, Path::new("./toto")).is_err());
+        assert!(is_directory_tally(&LocalDataSource, Path::new(".")).is_err());
+        assert!(!is_directory_tally(&LocalDataSource, &dataset_setup_path()).unwrap());
+        assert!(is_directory_tally(&LocalDataSource, &dataset_tally_path()).unwrap());
     }
 
     #[test]
     fn test_check_verification_dir() {
-        assert!(check_verification_dir(&VerificationPeriod::Setup, Path::new("./toto")).is_err());
-        assert!(check_verification_dir(&VerificationPeriod::Tally, Path::new("./toto")).is_err());
-        assert!(check_verification_dir(&VerificationPeriod::Setup, Path::new(".")).is_err());
-        assert!(check_verification_dir(&VerificationPeriod::Tally, Path::new(".")).is_err());
-        assert!(check_verification_dir(&VerificationPeriod::Setup, &dataset_setup_path()).is_ok());
-        assert!(check_verification_dir(&VerificationPeriod::Tally, &dataset_setup_path()).is_err());
-        assert!(check_verification_dir(&VerificationPeriod::Setup, &dataset_tally_path()).is_ok());
-        assert!(check_verification_dir(&VerificationPeriod::Tally, &dataset_tally_path()).is_ok());
+        let check = |period: &VerificationPeriod, path: &Path| {
+            check_verification_dir(period, &LocalDataSource, path)
+        };
+        assert!(check(&VerificationPeriod::Setup, Path::new("./toto")).is_err());
+        assert!(check(&VerificationPeriod::Tally, Path::new("./toto")).is_err());
+        assert!(check(&VerificationPeriod::Setup, Path::new(".")).is_err());
+        assert!(check(&VerificationPeriod::Tally, Path::new(".")).is_err());
+        assert!(check(&VerificationPeriod::Setup, &dataset_setup_path()).is_ok());
+        assert!(check(&VerificationPeriod::Tally, &dataset_setup_path()).is_err());
+        assert!(check(&VerificationPeriod::Setup, &dataset_tally_path()).is_ok());
+        assert!(check(&VerificationPeriod::Tally, &dataset_tally_path()).is_ok());
     }
 }
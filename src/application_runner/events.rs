@@ -0,0 +1,266 @@
+//! Module implementing the stream of the events of a run
+//!
+//! A subscriber (e.g. a GUI) receives all the observable behaviour of the runner through one
+//! channel (see [super::Runner::subscribe]): the start and the end of the run and of each
+//! verification, and the log records. Each [RunnerEvent] has a timestamp, so that the
+//! subscriber can render a timeline without own bookkeeping.
+//!
+//! The log records are forwarded with [forward_log_record]. The logger initialized with
+//! [super::init_logger] forwards them automatically. An application with its own logger must
+//! call [forward_log_record] for each record.
+//!
+//! The progress of the hashing of the big xml files (e.g. eCH-0110) is forwarded with
+//! [forward_hash_progress], so that the subscriber can show it without parsing the log records.
+
+use crate::verification::{
+    query::ResultStatus,
+    result::{VerificationResult, VerificationResultTrait},
+    verifications::RunObserver,
+    VerificationPeriod,
+};
+use chrono::{Local, NaiveDateTime};
+use log::{Level, Record};
+use std::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex, OnceLock, PoisonError, Weak,
+    },
+    time::Duration,
+};
+
+/// Event of a run, with the time when it occured
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunnerEvent {
+    /// Local time of the event
+    pub timestamp: NaiveDateTime,
+    pub kind: RunnerEventKind,
+}
+
+/// Kinds of the events of a run
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunnerEventKind {
+    /// The run starts with the given number of verifications
+    RunStarted {
+        period: VerificationPeriod,
+        verifications: usize,
+        excluded: Vec<String>,
+    },
+    /// The verification starts
+    VerificationStarted { id: String },
+    /// The verification is finished with the given status and numbers of errors and failures
+    VerificationFinished {
+        id: String,
+        status: ResultStatus,
+        errors: usize,
+        failures: usize,
+    },
+    /// Log record
+    Log { level: Level, message: String },
+    /// Progress of the hashing of the xml file `file`: `processed` bytes of `total`
+    HashProgress {
+        file: String,
+        processed: u64,
+        total: u64,
+    },
+    /// The run is finished. `stopped_by` is the verification whose errors stopped the run, if
+    /// any (see [crate::verification::suite::ExecutionPolicy])
+    RunFinished {
+        duration: Duration,
+        stopped_by: Option<String>,
+    },
+}
+
+impl RunnerEvent {
+    /// Event of the given kind, occuring now
+    pub fn now(kind: RunnerEventKind) -> Self {
+        Self {
+            timestamp: Local::now().naive_local(),
+            kind,
+        }
+    }
+}
+
+/// Stream sending the events to the subscribers
+///
+/// The subscribers whose receiver is dropped are removed at the next event
+#[derive(Debug, Default)]
+pub struct EventStream {
+    senders: Mutex<Vec<Sender<RunnerEvent>>>,
+}
+
+/// Streams receiving the log records and the progress of the hashing. The streams are removed
+/// when they are dropped
+static LOG_STREAMS: OnceLock<Mutex<Vec<Weak<EventStream>>>> = OnceLock::new();
+
+fn log_streams() -> &'static Mutex<Vec<Weak<EventStream>>> {
+    LOG_STREAMS.get_or_init(|| Mutex::new(vec![]))
+}
+
+impl EventStream {
+    /// New stream, receiving the log records and the progress of the hashing (see
+    /// [forward_log_record] and [forward_hash_progress])
+    pub fn new() -> Arc<Self> {
+        let res = Arc::new(Self::default());
+        let mut streams = log_streams().lock().unwrap_or_else(PoisonError::into_inner);
+        streams.retain(|s| s.strong_count() > 0);
+        streams.push(Arc::downgrade(&res));
+        res
+    }
+
+    /// Subscribe to the events. The events are received in the order they are sent
+    pub fn subscribe(&self) -> Receiver<RunnerEvent> {
+        let (sender, receiver) = channel();
+        self.senders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+        receiver
+    }
+
+    /// Number of the subscribers
+    pub fn subscribers(&self) -> usize {
+        self.senders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Send the event of the given kind to all the subscribers
+    pub fn send(&self, kind: RunnerEventKind) {
+        let mut senders = self.senders.lock().unwrap_or_else(PoisonError::into_inner);
+        if senders.is_empty() {
+            return;
+        }
+        let event = RunnerEvent::now(kind);
+        senders.retain(|s| s.send(event.clone()).is_ok());
+    }
+}
+
+impl RunObserver for EventStream {
+    fn on_start(&self, id: &str) {
+        self.send(RunnerEventKind::VerificationStarted { id: id.to_string() })
+    }
+
+    fn on_finished(&self, id: &str, result: &VerificationResult) {
+        self.send(RunnerEventKind::VerificationFinished {
+            id: id.to_string(),
+            status: ResultStatus::of(result),
+            errors: result.errors().len(),
+            failures: result.failures().len(),
+        })
+    }
+}
+
+/// Streams that are not dropped
+fn live_streams() -> Vec<Arc<EventStream>> {
+    log_streams()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .filter_map(|s| s.upgrade())
+        .collect()
+}
+
+/// Forward the log record to the subscribers of all the streams
+pub fn forward_log_record(record: &Record) {
+    for s in live_streams().iter() {
+        s.send(RunnerEventKind::Log {
+            level: record.level(),
+            message: record.args().to_string(),
+        })
+    }
+}
+
+/// Forward the progress of the hashing of the xml file `file` to the subscribers of all the
+/// streams
+pub fn forward_hash_progress(file: &str, processed: u64, total: u64) {
+    for s in live_streams().iter() {
+        s.send(RunnerEventKind::HashProgress {
+            file: file.to_string(),
+            processed,
+            total,
+        })
+    }
+}
+
+/// Appender of log4rs forwarding the log records to the streams (see [forward_log_record])
+#[cfg(feature = "native")]
+#[derive(Debug)]
+pub(super) struct EventStreamAppender;
+
+#[cfg(feature = "native")]
+impl log4rs::append::Append for EventStreamAppender {
+    fn append(&self, record: &Record) -> anyhow::Result<()> {
+        forward_log_record(record);
+        Ok(())
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::verification::result::{create_verification_failure, VerificationEvent};
+    use anyhow::anyhow;
+    use log::debug;
+
+    #[test]
+    fn test_stream() {
+        let stream = EventStream::new();
+        let receiver = stream.subscribe();
+        let dropped = stream.subscribe();
+        drop(dropped);
+        assert_eq!(stream.subscribers(), 2);
+        stream.on_start("01.01");
+        assert_eq!(stream.subscribers(), 1);
+        let mut result = VerificationResult::new();
+        result.push(create_verification_failure!("failure"));
+        stream.on_finished("01.01", &result);
+        forward_log_record(
+            &Record::builder()
+                .args(format_args!("message of the test"))
+                .level(Level::Warn)
+                .build(),
+        );
+        // The log records of the tests running in parallel can be received too
+        let (logs, events): (Vec<RunnerEventKind>, Vec<RunnerEventKind>) = receiver
+            .try_iter()
+            .map(|e| e.kind)
+            .partition(|k| matches!(k, RunnerEventKind::Log { .. }));
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0],
+            RunnerEventKind::VerificationStarted {
+                id: "01.01".to_string()
+            }
+        );
+        assert_eq!(
+            events[1],
+            RunnerEventKind::VerificationFinished {
+                id: "01.01".to_string(),
+                status: ResultStatus::Failures,
+                errors: 0,
+                failures: 1
+            }
+        );
+        assert!(logs.contains(&RunnerEventKind::Log {
+            level: Level::Warn,
+            message: "message of the test".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_hash_progress() {
+        let stream = EventStream::new();
+        let receiver = stream.subscribe();
+        forward_hash_progress("file of the test", 50, 100);
+        let expected = RunnerEventKind::HashProgress {
+            file: "file of the test".to_string(),
+            processed: 50,
+            total: 100,
+        };
+        // The progress of the tests running in parallel can be received too
+        assert!(receiver.try_iter().any(|e| e.kind == expected));
+    }
+}
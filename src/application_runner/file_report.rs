@@ -0,0 +1,208 @@
+//! Module implementing the inverse view of the report of a run: the verifications per data file
+//!
+//! The [FileAccessReport] answers the question "was this file checked, and by which
+//! verifications?". It is built from the accesses to the files recorded during the run (see
+//! [crate::file_structure::file_access]) and from the outcome of the verifications.
+
+use super::report::VerificationReport;
+use crate::{file_structure::file_access::FileAccesses, verification::query::ResultStatus};
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Verification having accessed a file, with its outcome
+#[derive(Debug, Clone, Serialize)]
+pub struct FileVerificationReport {
+    pub id: String,
+    pub name: String,
+    pub status: ResultStatus,
+}
+
+/// Verifications having accessed a file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReport {
+    /// Path of the file, relative to the dataset
+    pub path: String,
+    /// Empty if the file is not accessed by any verification
+    pub verifications: Vec<FileVerificationReport>,
+}
+
+/// Report of the verifications per data file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileAccessReport {
+    /// Location of the dataset
+    pub dataset: String,
+    pub files: Vec<FileReport>,
+}
+
+impl FileReport {
+    /// The file is accessed by at least one verification
+    pub fn is_checked(&self) -> bool {
+        !self.verifications.is_empty()
+    }
+}
+
+impl FileAccessReport {
+    /// Report of the accesses to the files of the dataset in `dataset`
+    ///
+    /// `files` are the files of the dataset. They are listed in the report, also if they are not
+    /// accessed. The accessed files that are not in `files` are listed too.
+    pub fn new(
+        dataset: &Path,
+        accesses: &FileAccesses,
+        verifications: &[VerificationReport],
+        files: &[PathBuf],
+    ) -> Self {
+        let verifications: BTreeMap<&str, &VerificationReport> =
+            verifications.iter().map(|v| (v.id.as_str(), v)).collect();
+        let mut paths: Vec<&PathBuf> = files.iter().chain(accesses.keys()).collect();
+        paths.sort();
+        paths.dedup();
+        let files = paths
+            .into_iter()
+            .map(|p| FileReport {
+                path: p
+                    .strip_prefix(dataset)
+                    .unwrap_or(p)
+                    .to_string_lossy()
+                    .to_string(),
+                verifications: accesses
+                    .get(p)
+                    .map(|ids| {
+                        ids.iter()
+                            .map(|id| FileVerificationReport {
+                                id: id.clone(),
+                                name: verifications
+                                    .get(id.as_str())
+                                    .map(|v| v.name.clone())
+                                    .unwrap_or_default(),
+                                status: verifications
+                                    .get(id.as_str())
+                                    .map(|v| v.status)
+                                    .unwrap_or(ResultStatus::NotRun),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+            .collect();
+        Self {
+            dataset: dataset.to_string_lossy().to_string(),
+            files,
+        }
+    }
+
+    /// The report of the file with the given path (relative to the dataset)
+    pub fn file(&self, path: &str) -> Option<&FileReport> {
+        self.files.iter().find(|f| f.path == path)
+    }
+
+    /// The files not accessed by any verification
+    pub fn unchecked_files(&self) -> Vec<&FileReport> {
+        self.files.iter().filter(|f| !f.is_checked()).collect()
+    }
+
+    /// The report as json string
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| anyhow!(e).context("Cannot serialize the file report"))
+    }
+
+    /// The report as text, one block per file
+    pub fn to_text(&self) -> String {
+        let mut res = format!("Verifications per file of the dataset {}\n", self.dataset);
+        for f in self.files.iter() {
+            let _ = writeln!(res, "{}", f.path);
+            if !f.is_checked() {
+                let _ = writeln!(res, "  not checked");
+            }
+            for v in f.verifications.iter() {
+                let _ = writeln!(res, "  {} {}: {:?}", v.id, v.name, v.status);
+            }
+        }
+        res
+    }
+
+    /// Save the report, as json if the extension of the file is `json`, as text else
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(p) = path.parent() {
+            fs::create_dir_all(p).with_context(|| format!("Cannot create directory {:?}", p))?;
+        }
+        let content = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => self.to_json()?,
+            _ => self.to_text(),
+        };
+        fs::write(path, content)
+            .with_context(|| format!("Cannot write the file report to {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::verification::meta_data::VerificationImpact;
+    use std::collections::BTreeSet;
+
+    fn verification(id: &str, name: &str, status: ResultStatus) -> VerificationReport {
+        VerificationReport {
+            id: id.to_string(),
+            name: name.to_string(),
+            category: "consistency".to_string(),
+            impact: VerificationImpact::Blocking,
+            status,
+            duration: None,
+            memory: None,
+            errors: vec![],
+            failures: vec![],
+            sub_results: vec![],
+            entities: vec![],
+            codes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_file_report() {
+        let root = Path::new("/data");
+        let mut accesses = FileAccesses::new();
+        accesses.insert(
+            root.join("setup/a.json"),
+            BTreeSet::from(["03.01".to_string(), "01.01".to_string()]),
+        );
+        accesses.insert(
+            root.join("setup/c.json"),
+            BTreeSet::from(["01.01".to_string()]),
+        );
+        let verifications = vec![
+            verification("01.01", "VerifySetupCompleteness", ResultStatus::Ok),
+            verification(
+                "03.01",
+                "VerifyEncryptionGroupConsistency",
+                ResultStatus::Failures,
+            ),
+        ];
+        let report = FileAccessReport::new(
+            root,
+            &accesses,
+            &verifications,
+            &[root.join("setup/b.json"), root.join("setup/a.json")],
+        );
+        assert_eq!(report.files.len(), 3);
+        let a = report.file("setup/a.json").unwrap();
+        assert_eq!(a.verifications.len(), 2);
+        assert_eq!(a.verifications[0].id, "01.01");
+        assert_eq!(a.verifications[1].status, ResultStatus::Failures);
+        assert!(report.file("setup/c.json").unwrap().is_checked());
+        assert_eq!(report.unchecked_files().len(), 1);
+        assert_eq!(report.unchecked_files()[0].path, "setup/b.json");
+        let text = report.to_text();
+        assert!(text.contains("setup/b.json\n  not checked\n"));
+        assert!(text.contains("  03.01 VerifyEncryptionGroupConsistency: Failures\n"));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["files"][0]["verifications"][1]["status"], "Failures");
+    }
+}
@@ -0,0 +1,490 @@
+//! Module implementing the report of the verifications as standalone HTML file
+//!
+//! The HTML report renders a [RunReport] for the auditors: the summary of the run, a table of the
+//! verifications linked to one detail section per verification, the fingerprint of the dataset and
+//! the direct trust certificates. The styles are embedded, so that the file can be delivered
+//! without other resources.
+
+use super::report::{RunReport, VerificationReport};
+use crate::{direct_trust::CertificateInfo, verification::query::ResultStatus};
+use anyhow::Context;
+use std::{fmt::Write, fs, path::Path};
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1em}\
+th,td{border:1px solid #bbb;padding:0.3em 0.6em;text-align:left;vertical-align:top}\
+th{background:#eee}\
+.ok{color:#1a7f37}.errors{color:#b35900}.failures{color:#cf222e}.not-run{color:#777}\
+section{border-top:1px solid #bbb;margin-top:1.5em}\
+code{word-break:break-all}";
+
+/// Report of the verifications as HTML
+pub struct HtmlReport<'a> {
+    report: &'a RunReport,
+    fingerprint: Option<String>,
+    certificates: Vec<CertificateInfo>,
+}
+
+/// Escape the special characters of HTML
+fn escape(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+/// Id of the detail section of the verification
+fn anchor(verification_id: &str) -> String {
+    format!("verification-{}", escape(verification_id))
+}
+
+/// Class and label of the status
+fn status_class_and_label(status: &ResultStatus) -> (&'static str, &'static str) {
+    match status {
+        ResultStatus::NotRun => ("not-run", "Not run"),
+        ResultStatus::Ok => ("ok", "Ok"),
+        ResultStatus::Errors => ("errors", "Errors"),
+        ResultStatus::Failures => ("failures", "Failures"),
+    }
+}
+
+/// List of the values, with the given heading. Nothing is written if the list is empty
+fn write_list(res: &mut String, heading: &str, values: &[String]) {
+    if values.is_empty() {
+        return;
+    }
+    let _ = writeln!(res, "<h4>{} ({})</h4>\n<ul>", escape(heading), values.len());
+    for v in values.iter() {
+        let _ = writeln!(res, "<li>{}</li>", escape(v));
+    }
+    res.push_str("</ul>\n");
+}
+
+impl<'a> HtmlReport<'a> {
+    /// HTML report of the run report
+    pub fn new(report: &'a RunReport) -> Self {
+        Self {
+            report,
+            fingerprint: None,
+            certificates: vec![],
+        }
+    }
+
+    /// Set the fingerprint of the dataset
+    ///
+    /// The fingerprint is the digest of the [crate::file_structure::manifest::Manifest]
+    pub fn with_fingerprint(mut self, fingerprint: &str) -> Self {
+        self.fingerprint = Some(fingerprint.to_string());
+        self
+    }
+
+    /// Set the information of the direct trust certificates
+    pub fn with_certificates(mut self, certificates: Vec<CertificateInfo>) -> Self {
+        self.certificates = certificates;
+        self
+    }
+
+    fn write_header(&self, res: &mut String) {
+        let r = self.report;
+        let number = |n: usize| r.locale.format_number(n as u64);
+        let _ = writeln!(
+            res,
+            "<h1>Report of the verifications of the {}</h1>",
+            escape(&r.period)
+        );
+        if r.partial {
+            res.push_str("<p class=\"errors\">Partial report: the run is not finished</p>\n");
+        }
+        res.push_str("<table>\n");
+        let mut row = |label: &str, value: &str| {
+            let _ = writeln!(res, "<tr><th>{}</th><td>{}</td></tr>", label, value);
+        };
+        row("Dataset", &escape(&r.dataset));
+        row(
+            "Fingerprint of the dataset",
+            &match &self.fingerprint {
+                Some(f) => format!("<code>{}</code> (SHA-256 of the manifest)", escape(f)),
+                None => "not computed".to_string(),
+            },
+        );
+        row("Created", &escape(&r.locale.format_datetime(&r.created)));
+        if let Some(d) = &r.duration {
+            row("Duration", &escape(&d.to_string()));
+        }
+        row(
+            "Verifications",
+            &format!(
+                "{} (<span class=\"ok\">ok: {}</span>, <span class=\"errors\">errors: {}</span>, \
+                 <span class=\"failures\">failures: {}</span>, \
+                 <span class=\"not-run\">not run: {}</span>)",
+                number(r.summary.total),
+                number(r.summary.ok),
+                number(r.summary.errors),
+                number(r.summary.failures),
+                number(r.summary.not_run)
+            ),
+        );
+        row(
+            "Outcome",
+            &format!(
+                "{} (informational verifications not ok: {})",
+                r.summary.outcome,
+                number(r.summary.informational)
+            ),
+        );
+        if !r.excluded.is_empty() {
+            row("Excluded", &escape(&r.excluded.join(" ")));
+        }
+        if !r.not_implemented.is_empty() {
+            row("Not implemented", &escape(&r.not_implemented.join(" ")));
+        }
+        if !r.blocking_not_run.is_empty() {
+            row(
+                "Blocking verifications not run",
+                &escape(&r.blocking_not_run.join(" ")),
+            );
+        }
+        if !r.statistics.slowest.is_empty() {
+            row(
+                "Total duration of the verifications",
+                &escape(&r.statistics.total_duration.to_string()),
+            );
+            row("Slowest verifications", &escape(&r.slowest_to_string()));
+        }
+        let failures_by_category = r.failures_by_category_to_string(number);
+        if !failures_by_category.is_empty() {
+            row("Failures per category", &escape(&failures_by_category));
+        }
+        for m in r.concurrent_modifications.iter() {
+            row("Modification during the run", &escape(m));
+        }
+        if let Some(h) = &r.journal_head {
+            row(
+                "Head of the journal",
+                &format!(
+                    "{} records, hash <code>{}</code>",
+                    number(h.records),
+                    escape(&h.hash)
+                ),
+            );
+        }
+        for rel in r.relaxations.iter() {
+            row("Relaxation", &escape(&rel.to_string()));
+        }
+        for s in r.schemas.iter() {
+            if let Some(p) = &s.overridden_by {
+                row(
+                    &format!("Schema {} {}", escape(&s.name), escape(&s.version)),
+                    &format!(
+                        "overridden by {} (SHA-256 <code>{}</code>)",
+                        escape(&p.to_string_lossy()),
+                        escape(&s.sha256)
+                    ),
+                );
+            }
+        }
+        res.push_str("</table>\n");
+    }
+
+    fn write_summary_table(&self, res: &mut String) {
+        let number = |n: usize| self.report.locale.format_number(n as u64);
+        res.push_str(
+            "<h2>Verifications</h2>\n<table>\n<tr><th>Id</th><th>Name</th><th>Category</th>\
+             <th>Status</th><th>Duration</th><th>Errors</th><th>Failures</th></tr>\n",
+        );
+        for v in self.report.verifications.iter() {
+            let (class, label) = status_class_and_label(&v.status);
+            let _ = writeln!(
+                res,
+                "<tr><td><a href=\"#{}\">{}</a></td><td>{}</td><td>{}</td>\
+                 <td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                anchor(&v.id),
+                escape(&v.id),
+                escape(&v.name),
+                escape(&v.category),
+                class,
+                label,
+                v.duration.map(|d| d.to_string()).unwrap_or_default(),
+                number(v.errors.len()),
+                number(v.failures.len())
+            );
+        }
+        res.push_str("</table>\n");
+    }
+
+    fn write_verification(&self, res: &mut String, v: &VerificationReport) {
+        let number = |n: usize| self.report.locale.format_number(n as u64);
+        let (class, label) = status_class_and_label(&v.status);
+        let _ = writeln!(
+            res,
+            "<section id=\"{}\">\n<h3>{} {}</h3>\n<p>Category: {}. Status: <span class=\"{}\">{}\
+             </span>{}. <a href=\"#verifications\">Back to the list</a></p>",
+            anchor(&v.id),
+            escape(&v.id),
+            escape(&v.name),
+            escape(&v.category),
+            class,
+            label,
+            v.duration
+                .map(|d| format!(". Duration: {}", d))
+                .unwrap_or_default()
+        );
+        if !v.sub_results.is_empty() {
+            res.push_str("<table>\n<tr><th>Items</th><th>Ok</th><th>Total</th></tr>\n");
+            for s in v.sub_results.iter() {
+                let _ = writeln!(
+                    res,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&s.kind),
+                    number(s.ok),
+                    number(s.total)
+                );
+            }
+            res.push_str("</table>\n");
+        }
+        if !v.codes.is_empty() {
+            let codes: Vec<String> = v
+                .codes
+                .iter()
+                .map(|(c, n)| format!("{}: {}", c, number(*n)))
+                .collect();
+            let _ = writeln!(res, "<p>Codes: {}</p>", escape(&codes.join(", ")));
+        }
+        write_list(res, "Errors", &v.errors);
+        write_list(res, "Failures", &v.failures);
+        for e in v.entities.iter() {
+            write_list(res, &format!("Failures of the {}", e.entity), &e.failures);
+        }
+        res.push_str("</section>\n");
+    }
+
+    fn write_certificates(&self, res: &mut String) {
+        if self.certificates.is_empty() {
+            return;
+        }
+        res.push_str(
+            "<h2>Direct trust certificates</h2>\n<table>\n<tr><th>Authority</th><th>File</th>\
+             <th>Subject</th><th>Serial</th><th>Valid from</th><th>Valid to</th>\
+             <th>Problems</th></tr>\n",
+        );
+        for c in self.certificates.iter() {
+            let opt = |v: &Option<String>| escape(v.as_deref().unwrap_or("-"));
+            let problems: Vec<String> = c.problems.iter().map(|p| escape(&p.to_string())).collect();
+            let _ = writeln!(
+                res,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td>\
+                 <td>{}</td><td class=\"{}\">{}</td></tr>",
+                escape(&String::from(c.authority)),
+                escape(&c.path.to_string_lossy()),
+                opt(&c.subject),
+                opt(&c.serial),
+                opt(&c.not_before),
+                opt(&c.not_after),
+                match c.problems.iter().any(|p| p.is_blocking()) {
+                    true => "failures",
+                    false => "ok",
+                },
+                match problems.is_empty() {
+                    true => "none".to_string(),
+                    false => problems.join("<br>"),
+                }
+            );
+        }
+        res.push_str("</table>\n");
+    }
+
+    fn write_vcs_statistics(&self, res: &mut String) {
+        if self.report.vcs_statistics.is_empty() {
+            return;
+        }
+        let with_ballot_boxes = self
+            .report
+            .vcs_statistics
+            .iter()
+            .any(|s| s.ballot_box.is_some());
+        res.push_str(
+            "<h2>Verification card sets</h2>\n<table>\n<tr><th>VCS</th><th>Ballot box</th>\
+             <th>Voting cards (context)</th><th>Verification cards</th><th>Chunks</th>\
+             <th>Files</th><th>Bytes</th>",
+        );
+        if with_ballot_boxes {
+            res.push_str("<th>Ballot box files</th><th>Ballot box bytes</th><th>Votes</th>");
+        }
+        res.push_str("</tr>\n");
+        let locale = &self.report.locale;
+        let number = |n: Option<usize>| {
+            n.map(|n| locale.format_number(n as u64))
+                .unwrap_or("-".to_string())
+        };
+        for s in self.report.vcs_statistics.iter() {
+            let _ = write!(
+                res,
+                "<tr><td><code>{}</code></td><td><code>{}</code></td><td>{}</td><td>{}</td>\
+                 <td>{}</td><td>{}</td><td>{}</td>",
+                escape(&s.name),
+                escape(s.ballot_box_id.as_deref().unwrap_or("-")),
+                number(s.number_of_voting_cards),
+                number(s.verification_cards),
+                number(Some(s.chunks)),
+                number(Some(s.files)),
+                locale.format_number(s.bytes)
+            );
+            if with_ballot_boxes {
+                let _ = match s.ballot_box.as_ref().filter(|bb| bb.found) {
+                    Some(bb) => write!(
+                        res,
+                        "<td>{}</td><td>{}</td><td>{}</td>",
+                        number(Some(bb.files)),
+                        locale.format_number(bb.bytes),
+                        number(bb.votes)
+                    ),
+                    None => write!(res, "<td colspan=\"3\">not found</td>"),
+                };
+            }
+            res.push_str("</tr>\n");
+        }
+        res.push_str("</table>\n");
+    }
+
+    /// The report as standalone HTML document
+    pub fn to_html(&self) -> String {
+        let mut res = format!(
+            "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Report of the verifications of the {}</title>\n<style>{}</style>\n\
+             </head>\n<body>\n",
+            escape(&self.report.locale.to_string()),
+            escape(&self.report.period),
+            STYLE
+        );
+        self.write_header(&mut res);
+        res.push_str("<div id=\"verifications\">\n");
+        self.write_summary_table(&mut res);
+        res.push_str("</div>\n<h2>Details</h2>\n");
+        for v in self.report.verifications.iter() {
+            self.write_verification(&mut res, v);
+        }
+        self.write_certificates(&mut res);
+        self.write_vcs_statistics(&mut res);
+        if !self.report.custom_summaries.is_empty() {
+            res.push_str("<h2>Custom summaries</h2>\n");
+            for (name, summary) in self.report.custom_summaries.iter() {
+                let _ = writeln!(
+                    res,
+                    "<h4>{}</h4>\n<pre>{}</pre>",
+                    escape(name),
+                    escape(&serde_json::to_string_pretty(summary).unwrap_or_default())
+                );
+            }
+        }
+        if !self.report.manual_checks.is_empty() {
+            res.push_str("<h2>Manual verifications</h2>\n");
+            for c in self.report.manual_checks.iter() {
+                let _ = writeln!(res, "<pre>{}</pre>", escape(&c.to_text()));
+            }
+        }
+        res.push_str("</body>\n</html>\n");
+        res
+    }
+
+    /// Save the report as HTML file
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(p) = path.parent() {
+            fs::create_dir_all(p).with_context(|| format!("Cannot create directory {:?}", p))?;
+        }
+        fs::write(path, self.to_html())
+            .with_context(|| format!("Cannot write the report to {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        application_runner::report::EntityFailures,
+        config::test::{test_dataset_setup_path, test_datasets_path},
+        direct_trust::CertificateAuthority,
+        verification::{
+            meta_data::VerificationImpact,
+            result::{Entity, EntityKind, FailureCode},
+            VerificationPeriod,
+        },
+    };
+    use std::{collections::BTreeMap, env::temp_dir};
+
+    fn verification(id: &str, status: ResultStatus, failures: Vec<String>) -> VerificationReport {
+        VerificationReport {
+            id: id.to_string(),
+            name: format!("Verification {}", id),
+            category: "consistency".to_string(),
+            impact: VerificationImpact::Blocking,
+            status,
+            duration: None,
+            memory: None,
+            errors: vec![],
+            failures,
+            sub_results: vec![],
+            entities: vec![],
+            codes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">'b' & c</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&#39;b&#39; &amp; c&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        let mut failed = verification(
+            "03.12",
+            ResultStatus::Failures,
+            vec!["Wrong card <vc_1>".to_string()],
+        );
+        failed.entities = vec![EntityFailures {
+            entity: Entity::new(EntityKind::VerificationCardSet, "vcs_1"),
+            failures: failed.failures.clone(),
+        }];
+        failed.codes = BTreeMap::from([(FailureCode::Inconsistency, 1)]);
+        let report = RunReport::new(
+            &VerificationPeriod::Setup,
+            &test_dataset_setup_path(),
+            vec![verification("01.01", ResultStatus::Ok, vec![]), failed],
+        );
+        let certificate = CertificateInfo::new(
+            CertificateAuthority::Canton,
+            &test_datasets_path().join("direct-trust").join("canton.cer"),
+        );
+        let html = HtmlReport::new(&report)
+            .with_fingerprint("abcdef")
+            .with_certificates(vec![certificate])
+            .to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(html.contains("<a href=\"#verification-03.12\">03.12</a>"));
+        assert!(html.contains("<section id=\"verification-03.12\">"));
+        assert!(html.contains("<code>abcdef</code>"));
+        assert!(html.contains("<li>Wrong card &lt;vc_1&gt;</li>"));
+        assert!(html.contains("Failures of the verification card set vcs_1 (1)"));
+        assert!(html.contains("Codes: inconsistency: 1"));
+        assert!(html.contains("<tr><th>Failures per category</th><td>consistency: 1</td></tr>"));
+        assert!(html.contains("<td>canton</td>"));
+        assert!(!html.contains("<vc_1>"));
+        let path = temp_dir().join("rust_verifier_test_html_report.html");
+        HtmlReport::new(&report).save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("not computed"));
+        assert!(!saved.contains("Direct trust certificates"));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,331 @@
+//! Module implementing the incremental verification of the tally
+//!
+//! During the election, the ballot boxes arrive incrementally. The state of the incremental
+//! verification ([IncrementalTallyState]) records the fingerprints of the delivery and the
+//! failures of each verified ballot box. At the next run, the fingerprints of the delivery are
+//! compared with the recorded ones:
+//! - If only ballot box directories were added or changed, only these ballot boxes are verified
+//! - If files outside of the ballot boxes changed (e.g. the setup or the results of the tally),
+//!   all the ballot boxes are verified
+//!
+//! The selection applies only to the verifications of scope
+//! [VerificationScope::BallotBox]. The verifications of the whole dataset (e.g. the totals of the
+//! cast votes or the completeness) always verify all the ballot boxes.
+//!
+//! The failures recorded for the ballot boxes that are not verified again are taken over in the
+//! report of the run (see [IncrementalTallyState::cumulative_report]), so that the report covers
+//! all the ballot boxes. Only the failures of the verifications per ballot box are recorded.
+//!
+//! The errors cannot be attributed to the ballot boxes. Then the state is not updated after a
+//! run with errors (or not finished), so that the new ballot boxes are verified again at the
+//! next run.
+
+use super::report::RunReport;
+use crate::{
+    file_structure::{
+        fingerprint::Fingerprints,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectory, VerificationDirectoryTrait,
+    },
+    verification::{
+        meta_data::{VerificationMetaDataList, VerificationScope},
+        result::EntityKind,
+    },
+};
+use anyhow::{anyhow, Context};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// State of the incremental verification of the tally, persisted in a json file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncrementalTallyState {
+    /// Fingerprints of the delivery at the last recorded run
+    pub fingerprints: Fingerprints,
+    /// Failures of the verified ballot boxes, per name of ballot box, then per id of
+    /// verification. The ballot boxes without failure have an empty map
+    pub ballot_boxes: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+/// Names of the ballot box directories of the delivery
+fn ballot_box_names(dir: &VerificationDirectory) -> Vec<String> {
+    match dir.tally() {
+        Some(t) => t.bb_directories().iter().map(|d| d.get_name()).collect(),
+        None => vec![],
+    }
+}
+
+impl IncrementalTallyState {
+    /// Load the state from the json file `path`
+    ///
+    /// If the file does not exist (first run), the state is empty
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            info!(
+                "Incremental tally: no state in {:?}. All the ballot boxes are verified",
+                path
+            );
+            return Ok(Self::default());
+        }
+        let s = fs::read_to_string(path).with_context(|| {
+            format!("Cannot read the state of the incremental tally {:?}", path)
+        })?;
+        serde_json::from_str(&s).map_err(|e| {
+            anyhow!(e).context(format!(
+                "State of the incremental tally in {:?} not valid",
+                path
+            ))
+        })
+    }
+
+    /// Save the state to the json file `path`
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(p) = path.parent() {
+            fs::create_dir_all(p).with_context(|| format!("Cannot create directory {:?}", p))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?).with_context(|| {
+            format!(
+                "Cannot write the state of the incremental tally to {:?}",
+                path
+            )
+        })
+    }
+
+    /// Names of the ballot boxes already verified (sorted)
+    pub fn verified_ballot_boxes(&self) -> Vec<String> {
+        self.ballot_boxes.keys().cloned().collect()
+    }
+
+    /// Calculate the ballot boxes to verify for the delivery in the tally directory `dir`
+    ///
+    /// The fingerprints of the delivery are compared with the recorded fingerprints. The
+    /// changes are logged.
+    ///
+    /// # Return
+    /// - `None` if all the ballot boxes have to be verified, because no run is recorded or
+    ///   because files outside of the ballot box directories changed
+    /// - The names of the added or changed ballot boxes else (can be empty if nothing changed)
+    pub fn ballot_box_selection(
+        &self,
+        dir: &VerificationDirectory,
+    ) -> anyhow::Result<Option<Vec<String>>> {
+        if self.fingerprints.is_empty() {
+            return Ok(None);
+        }
+        let current = dir
+            .fingerprints()
+            .context("Cannot calculate the fingerprints of the delivery")?;
+        let diff = current.diff(&self.fingerprints);
+        if diff.is_empty() {
+            info!("Incremental tally: no change since the last run");
+            return Ok(Some(vec![]));
+        }
+        for c in diff.to_strings() {
+            info!("Incremental tally: {}", c);
+        }
+        let outside = diff.changed_files_outside_ballot_boxes();
+        if !outside.is_empty() {
+            warn!(
+                "Incremental tally: {} files outside of the ballot boxes changed. All the ballot boxes are verified",
+                outside.len()
+            );
+            return Ok(None);
+        }
+        let names = ballot_box_names(dir);
+        let selection: Vec<String> = diff
+            .changed_bb_directories()
+            .into_iter()
+            .filter(|n| names.contains(n))
+            .collect();
+        info!(
+            "Incremental tally: only the new or changed ballot boxes are verified: {}",
+            selection.join(", ")
+        );
+        Ok(Some(selection))
+    }
+
+    /// Cumulative report of the run on the delivery in the tally directory `dir`
+    ///
+    /// The failures recorded for the ballot boxes of the delivery that are not in `selection`
+    /// are taken over in the report (see [RunReport::with_ballot_boxes_taken_over]). If
+    /// `selection` is `None`, all the ballot boxes were verified and nothing is taken over.
+    pub fn cumulative_report(
+        &self,
+        report: RunReport,
+        selection: Option<&[String]>,
+        dir: &VerificationDirectory,
+    ) -> RunReport {
+        let names = ballot_box_names(dir);
+        let (verified, taken_over) = match selection {
+            Some(selection) => (
+                names
+                    .iter()
+                    .filter(|n| selection.contains(n))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+                self.ballot_boxes
+                    .iter()
+                    .filter(|(n, _)| names.contains(n) && !selection.contains(n))
+                    .map(|(n, f)| (n.clone(), f.clone()))
+                    .collect(),
+            ),
+            None => (names, BTreeMap::new()),
+        };
+        report.with_ballot_boxes_taken_over(&verified, &taken_over)
+    }
+
+    /// Update the state with the cumulative report of the run on the delivery in the tally
+    /// directory `dir`
+    ///
+    /// The failures of the ballot boxes are read from the entities of the report, for the
+    /// verifications of scope [VerificationScope::BallotBox] in `metadata` (the other
+    /// verifications are run on all the ballot boxes at each run). The ballot boxes no more in
+    /// the delivery are removed.
+    ///
+    /// # Return
+    /// `false` if the state is not updated, because the report has errors or verifications not
+    /// run
+    pub fn update(
+        &mut self,
+        dir: &VerificationDirectory,
+        report: &RunReport,
+        metadata: &VerificationMetaDataList,
+    ) -> anyhow::Result<bool> {
+        if report.summary.errors > 0 || report.summary.not_run > 0 {
+            return Ok(false);
+        }
+        self.fingerprints = dir
+            .fingerprints()
+            .context("Cannot calculate the fingerprints of the delivery")?;
+        self.ballot_boxes = ballot_box_names(dir)
+            .into_iter()
+            .map(|n| (n, BTreeMap::new()))
+            .collect();
+        for v in report.verifications.iter().filter(|v| {
+            metadata
+                .meta_data_from_id(&v.id)
+                .map_or(false, |m| m.scope() == VerificationScope::BallotBox)
+        }) {
+            for e in v
+                .entities
+                .iter()
+                .filter(|e| e.entity.kind == EntityKind::BallotBox)
+            {
+                if let Some(failures) = self.ballot_boxes.get_mut(&e.entity.id) {
+                    failures
+                        .entry(v.id.clone())
+                        .or_default()
+                        .extend(e.failures.iter().cloned());
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        application_runner::report::{EntityFailures, VerificationReport},
+        config::test::{get_test_verifier_tally_dir, test_dataset_tally_path, CONFIG_TEST},
+        verification::{
+            meta_data::VerificationImpact, query::ResultStatus, result::Entity, VerificationPeriod,
+        },
+    };
+    use std::env::temp_dir;
+
+    fn report_with_failure(bb: &str) -> RunReport {
+        RunReport::new(
+            &VerificationPeriod::Tally,
+            &test_dataset_tally_path(),
+            vec![VerificationReport {
+                id: "10.01".to_string(),
+                name: "VerifyOnlineControlComponents".to_string(),
+                category: "evidence".to_string(),
+                impact: VerificationImpact::Blocking,
+                status: ResultStatus::Failures,
+                duration: None,
+                memory: None,
+                errors: vec![],
+                failures: vec!["failure".to_string()],
+                sub_results: vec![],
+                entities: vec![EntityFailures {
+                    entity: Entity::new(EntityKind::BallotBox, bb),
+                    failures: vec!["failure".to_string()],
+                }],
+                codes: BTreeMap::new(),
+            }],
+        )
+    }
+
+    #[test]
+    fn test_incremental() {
+        let path = temp_dir().join("rust_verifier_test_incremental_tally.json");
+        let _ = fs::remove_file(&path);
+        let mut state = IncrementalTallyState::load(&path).unwrap();
+        assert_eq!(
+            state
+                .ballot_box_selection(&get_test_verifier_tally_dir())
+                .unwrap(),
+            None
+        );
+        let names = ballot_box_names(&get_test_verifier_tally_dir());
+        assert!(!names.is_empty());
+        let report = state.cumulative_report(
+            report_with_failure(&names[0]),
+            None,
+            &get_test_verifier_tally_dir(),
+        );
+        assert!(report
+            .incremental_tally
+            .as_ref()
+            .unwrap()
+            .taken_over
+            .is_empty());
+        let metadata = CONFIG_TEST.verification_metadata().unwrap();
+        assert!(state
+            .update(&get_test_verifier_tally_dir(), &report, metadata)
+            .unwrap());
+        state.save(&path).unwrap();
+        let state = IncrementalTallyState::load(&path).unwrap();
+        assert_eq!(state.verified_ballot_boxes(), names);
+        assert_eq!(
+            state.ballot_boxes[&names[0]]["10.01"],
+            vec!["failure".to_string()]
+        );
+        // No change: nothing is verified and the failures are taken over
+        assert_eq!(
+            state
+                .ballot_box_selection(&get_test_verifier_tally_dir())
+                .unwrap(),
+            Some(vec![])
+        );
+        let mut report = report_with_failure(&names[0]);
+        report.verifications[0].status = ResultStatus::Ok;
+        report.verifications[0].failures.clear();
+        report.verifications[0].entities.clear();
+        let report = state.cumulative_report(report, Some(&[]), &get_test_verifier_tally_dir());
+        assert_eq!(report.verifications[0].status, ResultStatus::Failures);
+        assert_eq!(report.verifications[0].failures, vec!["failure"]);
+        assert_eq!(report.incremental_tally.unwrap().taken_over, names);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_not_updated_with_errors() {
+        let mut report = report_with_failure("bb");
+        report.verifications[0].status = ResultStatus::Errors;
+        report.verifications[0].errors = vec!["error".to_string()];
+        report.summary.errors = 1;
+        let mut state = IncrementalTallyState::default();
+        assert!(!state
+            .update(
+                &get_test_verifier_tally_dir(),
+                &report,
+                CONFIG_TEST.verification_metadata().unwrap()
+            )
+            .unwrap());
+        assert_eq!(state, IncrementalTallyState::default());
+    }
+}
@@ -0,0 +1,488 @@
+//! Module implementing the journal of the runs of the verifications
+//!
+//! The journal keeps all the attempts of the verifications. If a verification is run again
+//! (e.g. after resetting the runner), the new attempt is added to the history of the
+//! verification and the previous attempts are not overwritten.
+//!
+//! The attempts are recorded in an append-only log, where each record contains the hash of the
+//! previous record (hash chain). The log can be written to a file (one json record per line).
+//!
+//! The chain is not keyed: [RunJournal::verify_integrity] and [RunJournal::verify_log_file] detect
+//! a record modified, deleted or moved inside the log, but not the deletion of the last records
+//! or a chain recalculated from the modified records. These are only detected against the head
+//! of the chain ([JournalHead]) exported in the report of the run, which must be kept apart from
+//! the log.
+use anyhow::{anyhow, bail, Context};
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Hash used as previous hash for the first record of the log
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One attempt to run a verification
+#[derive(Debug, Clone)]
+pub struct VerificationAttempt {
+    /// Number of the attempt for the verification (starting with 1)
+    pub attempt: usize,
+    /// Time when the attempt finished
+    pub finished_at: SystemTime,
+    /// Errors of the attempt
+    pub errors: Vec<String>,
+    /// Failures of the attempt
+    pub failures: Vec<String>,
+}
+
+/// Record of the hash-chained log of the journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalRecord {
+    /// Position of the record in the log (starting with 0)
+    pub index: usize,
+    /// Id of the verification
+    pub id: String,
+    /// Number of the attempt for the verification
+    pub attempt: usize,
+    /// Time when the attempt finished, in milliseconds since the Unix epoch
+    pub finished_at_ms: u64,
+    /// Errors of the attempt
+    pub errors: Vec<String>,
+    /// Failures of the attempt
+    pub failures: Vec<String>,
+    /// Hash of the previous record
+    pub previous_hash: String,
+    /// Hash of the record, including the hash of the previous record
+    pub hash: String,
+}
+
+/// Head of the hash chain of the log: number of records and hash of the last record
+///
+/// The head is exported in the report of the run, so that a truncated or recalculated log can be
+/// detected (see [RunJournal::verify_log_file])
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalHead {
+    /// Number of records in the log
+    pub records: usize,
+    /// Hash of the last record (only zeros if the log is empty)
+    pub hash: String,
+}
+
+/// Journal of the attempts of all the verifications
+#[derive(Debug, Clone, Default)]
+pub struct RunJournal {
+    attempts: HashMap<String, Vec<VerificationAttempt>>,
+    records: Vec<JournalRecord>,
+    log_file: Option<PathBuf>,
+}
+
+impl VerificationAttempt {
+    /// The attempt was successful (no error and no failure)
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty() && self.failures.is_empty()
+    }
+
+    /// Short description of the outcome
+    pub fn outcome(&self) -> &'static str {
+        match (self.errors.is_empty(), self.failures.is_empty()) {
+            (true, true) => "ok",
+            (false, true) => "errors",
+            (true, false) => "failures",
+            (false, false) => "errors and failures",
+        }
+    }
+}
+
+impl JournalRecord {
+    /// Calculate the hash of the record (all the fields except the hash)
+    pub fn compute_hash(&self) -> String {
+        let content = serde_json::to_string(&(
+            self.index,
+            &self.id,
+            self.attempt,
+            self.finished_at_ms,
+            &self.errors,
+            &self.failures,
+            &self.previous_hash,
+        ))
+        // The serialization of strings and numbers cannot fail
+        .unwrap_or_default();
+        HEXLOWER.encode(&Sha256::digest(content.as_bytes()))
+    }
+
+    /// The attempt corresponding to the record
+    fn to_attempt(&self) -> VerificationAttempt {
+        VerificationAttempt {
+            attempt: self.attempt,
+            finished_at: UNIX_EPOCH + Duration::from_millis(self.finished_at_ms),
+            errors: self.errors.clone(),
+            failures: self.failures.clone(),
+        }
+    }
+}
+
+/// Verify the hash chain of the records
+///
+/// # Error
+/// If a record is not at the right position, if a record doesn't reference the hash of the previous
+/// record, or if the hash of a record is not correct
+fn verify_records(records: &[JournalRecord]) -> anyhow::Result<()> {
+    let mut previous_hash = GENESIS_HASH;
+    for (i, r) in records.iter().enumerate() {
+        if r.index != i {
+            bail!(format!(
+                "Record {} found at position {} of the journal",
+                r.index, i
+            ))
+        }
+        if r.previous_hash != previous_hash {
+            bail!(format!(
+                "The record {} doesn't reference the previous record of the journal",
+                i
+            ))
+        }
+        if r.hash != r.compute_hash() {
+            bail!(format!(
+                "The hash of the record {} of the journal is wrong",
+                i
+            ))
+        }
+        previous_hash = &r.hash;
+    }
+    Ok(())
+}
+
+/// Head of the hash chain of the records
+fn head_of(records: &[JournalRecord]) -> JournalHead {
+    JournalHead {
+        records: records.len(),
+        hash: records
+            .last()
+            .map(|r| r.hash.clone())
+            .unwrap_or_else(|| GENESIS_HASH.to_string()),
+    }
+}
+
+/// Verify the records against the expected head of the chain
+///
+/// # Error
+/// If the number of records or the hash of the last record differ from the head
+fn verify_head(records: &[JournalRecord], expected: &JournalHead) -> anyhow::Result<()> {
+    let head = head_of(records);
+    if head.records != expected.records {
+        bail!(format!(
+            "The journal contains {} records, {} expected",
+            head.records, expected.records
+        ))
+    }
+    if head.hash != expected.hash {
+        bail!("The head of the journal doesn't correspond to the expected head")
+    }
+    Ok(())
+}
+
+/// Read the records of the log file
+fn read_records(path: &Path) -> anyhow::Result<Vec<JournalRecord>> {
+    fs::read_to_string(path)
+        .with_context(|| format!("Cannot read the journal {:?}", path))?
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .enumerate()
+        .map(|(i, l)| {
+            serde_json::from_str(l)
+                .map_err(|e| anyhow!(e).context(format!("Record {} of the journal not valid", i)))
+        })
+        .collect()
+}
+
+impl RunJournal {
+    /// New empty journal
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the journal stored in the log file
+    ///
+    /// The existing records are loaded and their integrity is verified. The new records
+    /// are appended to the file. If the file does not exist, the journal is empty.
+    ///
+    /// # Error
+    /// If the file cannot be read or if the integrity of the records is not given
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut res = Self::new();
+        if path.exists() {
+            let records = read_records(path)?;
+            verify_records(&records).with_context(|| format!("Journal {:?} corrupted", path))?;
+            for r in records.iter() {
+                res.attempts
+                    .entry(r.id.clone())
+                    .or_default()
+                    .push(r.to_attempt());
+            }
+            res.records = records;
+        }
+        res.log_file = Some(path.to_path_buf());
+        Ok(res)
+    }
+
+    /// Verify the integrity of the log file
+    ///
+    /// If `expected_head` is given (e.g. from the report of the run), the log must end with this
+    /// head. Without it, the deletion of the last records or a recalculated chain is not detected.
+    ///
+    /// Return the number of records
+    pub fn verify_log_file(
+        path: &Path,
+        expected_head: Option<&JournalHead>,
+    ) -> anyhow::Result<usize> {
+        let records = read_records(path)?;
+        verify_records(&records)?;
+        if let Some(h) = expected_head {
+            verify_head(&records, h)?;
+        }
+        Ok(records.len())
+    }
+
+    /// Record a new attempt for the verification with the given id
+    ///
+    /// The record is appended to the log file, if any, before the attempt is added to the journal.
+    ///
+    /// Return the number of the attempt
+    ///
+    /// # Error
+    /// If the record cannot be written to the log file. The attempt is then not recorded, so that
+    /// the hash chain in memory does not diverge from the log file
+    pub fn record(
+        &mut self,
+        id: &str,
+        errors: &[String],
+        failures: &[String],
+    ) -> anyhow::Result<usize> {
+        let attempt = self.history(id).len() + 1;
+        let finished_at = SystemTime::now();
+        let mut record = JournalRecord {
+            index: self.records.len(),
+            id: id.to_string(),
+            attempt,
+            finished_at_ms: finished_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            errors: errors.to_vec(),
+            failures: failures.to_vec(),
+            previous_hash: self.head().hash,
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash();
+        if let Some(path) = &self.log_file {
+            append_record(path, &record).with_context(|| {
+                format!(
+                    "Record {} cannot be written to the journal {:?}",
+                    record.index, path
+                )
+            })?;
+        }
+        self.attempts
+            .entry(id.to_string())
+            .or_default()
+            .push(VerificationAttempt {
+                attempt,
+                finished_at,
+                errors: errors.to_vec(),
+                failures: failures.to_vec(),
+            });
+        self.records.push(record);
+        Ok(attempt)
+    }
+
+    /// Records of the hash-chained log, the oldest first
+    pub fn records(&self) -> &[JournalRecord] {
+        &self.records
+    }
+
+    /// Head of the hash chain of the records
+    pub fn head(&self) -> JournalHead {
+        head_of(&self.records)
+    }
+
+    /// Verify the integrity of the hash chain of the records in memory
+    ///
+    /// Like for [RunJournal::verify_log_file], the deletion of the last records is only detected
+    /// with the expected head
+    pub fn verify_integrity(&self, expected_head: Option<&JournalHead>) -> anyhow::Result<()> {
+        verify_records(&self.records)?;
+        match expected_head {
+            Some(h) => verify_head(&self.records, h),
+            None => Ok(()),
+        }
+    }
+
+    /// History of the attempts for the verification with the given id, the oldest first
+    pub fn history(&self, id: &str) -> &[VerificationAttempt] {
+        self.attempts.get(id).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// Last attempt for the verification with the given id
+    pub fn latest(&self, id: &str) -> Option<&VerificationAttempt> {
+        self.history(id).last()
+    }
+
+    /// Ids of the verifications recorded in the journal (sorted)
+    pub fn ids(&self) -> Vec<String> {
+        let mut res: Vec<String> = self.attempts.keys().cloned().collect();
+        res.sort();
+        res
+    }
+
+    /// Ids of the verifications that have been run more than once (sorted)
+    pub fn rerun_ids(&self) -> Vec<String> {
+        let mut res: Vec<String> = self
+            .attempts
+            .iter()
+            .filter(|(_, v)| v.len() > 1)
+            .map(|(k, _)| k.clone())
+            .collect();
+        res.sort();
+        res
+    }
+
+    /// Description of the evolution of the attempts of a verification, e.g. `failures -> ok`
+    pub fn history_to_string(&self, id: &str) -> String {
+        self.history(id)
+            .iter()
+            .map(|a| a.outcome())
+            .collect::<Vec<&str>>()
+            .join(" -> ")
+    }
+
+    /// Is the journal empty
+    pub fn is_empty(&self) -> bool {
+        self.attempts.is_empty()
+    }
+}
+
+/// Append the record to the log file as json line
+fn append_record(path: &Path, record: &JournalRecord) -> anyhow::Result<()> {
+    if let Some(p) = path.parent() {
+        fs::create_dir_all(p)?;
+    }
+    let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(f, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut journal = RunJournal::new();
+        assert!(journal.is_empty());
+        assert_eq!(
+            journal
+                .record("01.01", &[], &["failure".to_string()])
+                .unwrap(),
+            1
+        );
+        assert_eq!(journal.record("02.01", &[], &[]).unwrap(), 1);
+        assert_eq!(journal.record("01.01", &[], &[]).unwrap(), 2);
+        assert_eq!(journal.ids(), vec!["01.01", "02.01"]);
+        assert_eq!(journal.rerun_ids(), vec!["01.01"]);
+        let history = journal.history("01.01");
+        assert_eq!(history.len(), 2);
+        assert!(!history[0].is_ok());
+        assert_eq!(history[0].failures, vec!["failure".to_string()]);
+        assert!(history[1].is_ok());
+        assert_eq!(journal.latest("01.01").unwrap().attempt, 2);
+        assert_eq!(journal.history_to_string("01.01"), "failures -> ok");
+        assert!(journal.history("03.01").is_empty());
+        assert!(journal.latest("03.01").is_none());
+    }
+
+    #[test]
+    fn test_hash_chain() {
+        let mut journal = RunJournal::new();
+        journal
+            .record("01.01", &[], &["failure".to_string()])
+            .unwrap();
+        journal
+            .record("02.01", &["error".to_string()], &[])
+            .unwrap();
+        journal.record("01.01", &[], &[]).unwrap();
+        assert_eq!(journal.records().len(), 3);
+        assert_eq!(journal.records()[0].previous_hash, GENESIS_HASH);
+        assert_eq!(
+            journal.records()[2].previous_hash,
+            journal.records()[1].hash
+        );
+        assert!(journal.verify_integrity(None).is_ok());
+        let mut tampered = journal.clone();
+        tampered.records[1].errors.clear();
+        assert!(tampered.verify_integrity(None).is_err());
+        let mut tampered = journal.clone();
+        tampered.records[1].errors.clear();
+        tampered.records[1].hash = tampered.records[1].compute_hash();
+        assert!(tampered.verify_integrity(None).is_err());
+        let mut tampered = journal.clone();
+        tampered.records.remove(0);
+        assert!(tampered.verify_integrity(None).is_err());
+        let head = journal.head();
+        assert_eq!(head.records, 3);
+        assert_eq!(head.hash, journal.records()[2].hash);
+        assert!(journal.verify_integrity(Some(&head)).is_ok());
+        // Deleting the last record keeps a valid chain, only detected with the head
+        let mut tampered = journal.clone();
+        tampered.records.pop();
+        assert!(tampered.verify_integrity(None).is_ok());
+        assert!(tampered.verify_integrity(Some(&head)).is_err());
+        // A recalculated chain is only detected with the head
+        let mut tampered = journal.clone();
+        tampered.records[2].failures.push("failure".to_string());
+        tampered.records[2].hash = tampered.records[2].compute_hash();
+        assert!(tampered.verify_integrity(None).is_ok());
+        assert!(tampered.verify_integrity(Some(&head)).is_err());
+    }
+
+    #[test]
+    fn test_log_file() {
+        let path = std::env::temp_dir().join("rust_verifier_test_journal.jsonl");
+        let _ = fs::remove_file(&path);
+        let mut journal = RunJournal::open(&path).unwrap();
+        assert!(journal.is_empty());
+        journal
+            .record("01.01", &[], &["failure".to_string()])
+            .unwrap();
+        journal.record("02.01", &[], &[]).unwrap();
+        let mut journal = RunJournal::open(&path).unwrap();
+        assert_eq!(journal.records().len(), 2);
+        assert_eq!(journal.record("01.01", &[], &[]).unwrap(), 2);
+        let head = journal.head();
+        assert_eq!(RunJournal::verify_log_file(&path, Some(&head)).unwrap(), 3);
+        let content = fs::read_to_string(&path).unwrap();
+        let truncated: Vec<&str> = content.lines().take(2).collect();
+        fs::write(&path, format!("{}\n", truncated.join("\n"))).unwrap();
+        assert_eq!(RunJournal::verify_log_file(&path, None).unwrap(), 2);
+        assert!(RunJournal::verify_log_file(&path, Some(&head)).is_err());
+        fs::write(&path, content.replace("failure", "toto")).unwrap();
+        assert!(RunJournal::verify_log_file(&path, None).is_err());
+        assert!(RunJournal::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_log_file_not_writable() {
+        let file = std::env::temp_dir().join("rust_verifier_test_journal_not_dir");
+        fs::write(&file, "").unwrap();
+        let mut journal = RunJournal::open(&file.join("journal.jsonl")).unwrap();
+        assert!(journal.record("01.01", &[], &[]).is_err());
+        assert!(journal.is_empty());
+        assert!(journal.records().is_empty());
+        fs::remove_file(&file).unwrap();
+    }
+}
@@ -1,29 +1,77 @@
 //! Module implementing common functionalities for all Verifier applications (console and GUI)
 
+mod checkpoint;
 mod checks;
+mod events;
+mod file_report;
+mod html_report;
+mod incremental_tally;
+mod journal;
+mod partial_delivery;
+mod report;
 mod runner;
+#[cfg(any(test, feature = "service"))]
+pub mod service;
 
+#[cfg(feature = "native")]
 use crate::config::Config as VerifierConfig;
+#[cfg(feature = "native")]
+use anyhow::Context;
+#[cfg(feature = "native")]
 use log::LevelFilter;
+#[cfg(feature = "native")]
 use log4rs::{
     append::{console::ConsoleAppender, file::FileAppender},
     config::{Appender, Config, Root},
     encode::pattern::PatternEncoder,
 };
 
+pub use crate::data_structures::dataset_version::{DatasetVersion, DateTimeParsingMode};
+pub use crate::data_structures::{
+    embedded_xsd_text, override_schema, override_schema_from_file, schema_resources, xsd_text,
+    SchemaKind, SchemaResource, SchemaVersion,
+};
+pub use crate::direct_trust::{
+    direct_trust_certificate_infos, CertificateAuthority, CertificateInfo, CertificateProblem,
+};
+pub use checkpoint::{Checkpoint, CheckpointEntry, CheckpointHeader};
 pub use checks::{check_verification_dir, is_directory_tally, start_check};
-pub use runner::{no_action_after_fn, no_action_before_fn, RunParallel, Runner};
+pub use events::{
+    forward_hash_progress, forward_log_record, EventStream, RunnerEvent, RunnerEventKind,
+};
+pub use file_report::{FileAccessReport, FileReport, FileVerificationReport};
+pub use html_report::HtmlReport;
+pub use incremental_tally::IncrementalTallyState;
+pub use journal::{JournalHead, JournalRecord, RunJournal, VerificationAttempt};
+pub use partial_delivery::{record_fingerprints, vcs_selection_for_partial_delivery};
+pub use report::{
+    EntityFailures, IncrementalTallyRun, ReportPostProcessor, RunOutcome, RunReport,
+    RunReportSummary, VerificationReport,
+};
+pub use runner::{
+    no_action_after_fn, no_action_before_fn, CategoryParallelism, PartialReportHandle, RunParallel,
+    Runner,
+};
 
 /// Init the logger with or without stdout
-pub fn init_logger(config: &'static VerifierConfig, level: LevelFilter, with_console: bool) {
+///
+/// # Error
+/// If the log file cannot be created or if a logger is already initialized
+#[cfg(feature = "native")]
+pub fn init_logger(
+    config: &'static VerifierConfig,
+    level: LevelFilter,
+    with_console: bool,
+) -> anyhow::Result<()> {
     // File logger
     let file = FileAppender::builder()
         .encoder(Box::new(PatternEncoder::new("{d} {l} - {m}{n}")))
         .build(config.log_file_path())
-        .unwrap();
-    let mut root_builder = Root::builder().appender("file");
-    let mut config_builder =
-        Config::builder().appender(Appender::builder().build("file", Box::new(file)));
+        .with_context(|| format!("Cannot create the log file {:?}", config.log_file_path()))?;
+    let mut root_builder = Root::builder().appender("file").appender("events");
+    let mut config_builder = Config::builder()
+        .appender(Appender::builder().build("file", Box::new(file)))
+        .appender(Appender::builder().build("events", Box::new(events::EventStreamAppender)));
 
     // Console logger
     if with_console {
@@ -35,6 +83,9 @@ pub fn init_logger(config: &'static VerifierConfig, level: LevelFilter, with_con
             config_builder.appender(Appender::builder().build("stdout", Box::new(stdout)));
     }
 
-    let config = config_builder.build(root_builder.build(level)).unwrap();
-    let _handle = log4rs::init_config(config).unwrap();
+    let config = config_builder
+        .build(root_builder.build(level))
+        .context("Cannot build the configuration of the logger")?;
+    log4rs::init_config(config).context("Cannot initialize the logger")?;
+    Ok(())
 }
@@ -0,0 +1,94 @@
+//! Module implementing the verification of partial deliveries
+//!
+//! A corrected re-delivery of the setup can arrive after the preparation of the tally has begun.
+//! The fingerprints of the new delivery are compared with the fingerprints recorded for the
+//! previous delivery. If only VCS directories changed, only these directories have to be verified.
+
+use crate::file_structure::{fingerprint::Fingerprints, VerificationDirectory};
+use anyhow::Context;
+use log::{info, warn};
+use std::path::Path;
+
+/// Record the fingerprints of the delivery in the file `fingerprints_path`
+pub fn record_fingerprints(
+    dir: &VerificationDirectory,
+    fingerprints_path: &Path,
+) -> anyhow::Result<()> {
+    let fingerprints = dir
+        .fingerprints()
+        .context("Cannot calculate the fingerprints of the delivery")?;
+    fingerprints.save(fingerprints_path)?;
+    info!(
+        "Fingerprints of {} files recorded in {:?}",
+        fingerprints.len(),
+        fingerprints_path
+    );
+    Ok(())
+}
+
+/// Calculate the VCS directories to verify for a partial delivery of the setup
+///
+/// The fingerprints of the delivery in the setup directory `dir` are compared with the
+/// fingerprints recorded in the file `fingerprints_path`. The changes are logged.
+///
+/// # Return
+/// - `None` if the whole delivery has to be verified, because files outside of the VCS directories changed
+/// - The names of the changed VCS directories else (can be empty if nothing changed)
+pub fn vcs_selection_for_partial_delivery(
+    dir: &VerificationDirectory,
+    fingerprints_path: &Path,
+) -> anyhow::Result<Option<Vec<String>>> {
+    let recorded = Fingerprints::load(fingerprints_path)?;
+    let current = dir
+        .fingerprints()
+        .context("Cannot calculate the fingerprints of the delivery")?;
+    let diff = current.diff(&recorded);
+    if diff.is_empty() {
+        info!("Partial delivery: no change since the recorded delivery");
+        return Ok(Some(vec![]));
+    }
+    for c in diff.to_strings() {
+        info!("Partial delivery: {}", c);
+    }
+    let context_files = diff.changed_context_files();
+    if !context_files.is_empty() {
+        warn!(
+            "Partial delivery: {} files outside of the VCS directories changed. All the delivery is verified",
+            context_files.len()
+        );
+        return Ok(None);
+    }
+    let vcs = diff.changed_vcs_directories();
+    info!(
+        "Partial delivery: only the changed VCS directories are verified: {}",
+        vcs.join(", ")
+    );
+    Ok(Some(vcs))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::test::get_test_verifier_setup_dir;
+    use std::{env::temp_dir, fs};
+
+    #[test]
+    fn test_no_change() {
+        let path = temp_dir().join("rust_verifier_test_partial_no_change.json");
+        record_fingerprints(&get_test_verifier_setup_dir(), &path).unwrap();
+        assert_eq!(
+            vcs_selection_for_partial_delivery(&get_test_verifier_setup_dir(), &path).unwrap(),
+            Some(vec![])
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_recorded_not_exist() {
+        assert!(vcs_selection_for_partial_delivery(
+            &get_test_verifier_setup_dir(),
+            Path::new("./toto.json")
+        )
+        .is_err());
+    }
+}
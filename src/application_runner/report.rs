@@ -0,0 +1,1184 @@
+//! Module implementing the machine-readable report of a run of the verifications
+//!
+//! The [RunReport] aggregates the outcome of every verification of the suite and can be
+//! serialized to a json file, so that downstream tooling can consume the results. It can also be
+//! rendered as text or csv, where the numbers and the dates are formatted according to the
+//! [ReportLocale] of the report.
+//!
+//! The overall outcome of the run ([RunOutcome]) takes the impact of the verifications into
+//! account (see [crate::verification::meta_data::VerificationImpact]): the failures of the
+//! informational verifications don't make the run fail.
+//!
+//! The statistics of the suite ([VerificationSuiteResult]: number of verifications per status,
+//! slowest verifications and failures per category) are computed from the verifications of the
+//! report, so that they remain consistent when the report is updated (e.g. with the journal).
+//!
+//! The report contains also the statistics of the verification card sets (see
+//! [crate::file_structure::statistics]), to cross-check the dataset against the delivery notes.
+//!
+//! For an incremental verification of the tally (see [super::IncrementalTallyState]), the failures
+//! of the ballot boxes verified in previous runs are taken over, so that the report is
+//! cumulative.
+//!
+//! The embedders can add their own summaries to the report with a [ReportPostProcessor]
+//! registered on the runner, without changing this module.
+
+use super::journal::{JournalHead, JournalRecord};
+use crate::{
+    config::Relaxation,
+    data_structures::{schema_resources, SchemaResource, SchemaVersion},
+    file_structure::{statistics::VCSStatistics, VerificationDirectory},
+    human_format::{HumanDuration, ReportLocale},
+    memory_stats::MemoryStats,
+    verification::{
+        manual::ManualCheck,
+        meta_data::VerificationImpact,
+        query::{ResultQuery, ResultStatus},
+        result::{
+            csv_field, group_by_entity, Entity, EntityKind, FailureCode, SubResultsSummary,
+            VerificationResultTrait,
+        },
+        suite::{VerificationSuite, VerificationSuiteResult},
+        verifications::Verification,
+        VerificationPeriod,
+    },
+};
+use anyhow::{anyhow, Context};
+use chrono::{Local, NaiveDateTime};
+use log::warn;
+use serde::{Serialize, Serializer};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write},
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+/// Outcome of a verification in the report
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationReport {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+    pub impact: VerificationImpact,
+    pub status: ResultStatus,
+    /// `None` if the verification is not run
+    pub duration: Option<HumanDuration>,
+    /// Memory used by the verification (see [crate::memory_stats]). `None` if the verification
+    /// is not run
+    pub memory: Option<MemoryStats>,
+    pub errors: Vec<String>,
+    pub failures: Vec<String>,
+    /// Number of items (chunks, ballot boxes, etc.) verified successfully, for the
+    /// verifications over many items
+    pub sub_results: Vec<SubResultsSummary>,
+    /// Failures grouped per entity, for the failures annotated with entities
+    pub entities: Vec<EntityFailures>,
+    /// Number of errors and failures per code, for the automated triage
+    pub codes: BTreeMap<FailureCode, usize>,
+}
+
+/// Failures of a verification concerning an entity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntityFailures {
+    pub entity: Entity,
+    pub failures: Vec<String>,
+}
+
+/// Overall outcome of a run, for the acceptance procedure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// All the verifications are ok
+    Passed,
+    /// All the blocking verifications are ok, but informational verifications are not ok
+    PassedWithRemarks,
+    /// A blocking verification has errors or failures
+    Failed,
+    /// No blocking verification has errors or failures, but blocking verifications are not run,
+    /// are excluded or are not implemented
+    #[default]
+    Incomplete,
+}
+
+impl Display for RunOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Passed => write!(f, "passed"),
+            Self::PassedWithRemarks => write!(f, "passed with remarks"),
+            Self::Failed => write!(f, "failed"),
+            Self::Incomplete => write!(f, "incomplete"),
+        }
+    }
+}
+
+/// Number of verifications per status
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RunReportSummary {
+    pub total: usize,
+    pub ok: usize,
+    pub errors: usize,
+    pub failures: usize,
+    pub not_run: usize,
+    /// Number of informational verifications with errors or failures
+    pub informational: usize,
+    pub outcome: RunOutcome,
+}
+
+/// Ballot boxes of an incremental verification of the tally (see [super::IncrementalTallyState])
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IncrementalTallyRun {
+    /// Ballot boxes verified in the run
+    pub verified: Vec<String>,
+    /// Ballot boxes verified in previous runs, whose failures are taken over
+    pub taken_over: Vec<String>,
+}
+
+/// Report of a run of the verifications
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub period: String,
+    /// Location of the dataset
+    pub dataset: String,
+    /// Creation of the report (local time)
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created: NaiveDateTime,
+    /// Locale of the numbers and the dates in the text and csv formats
+    pub locale: ReportLocale,
+    /// Duration of the run. `None` if the run is not finished
+    pub duration: Option<HumanDuration>,
+    /// The report is a snapshot during the run (see [super::PartialReportHandle])
+    pub partial: bool,
+    /// Ids of the verifications excluded from the run
+    pub excluded: Vec<String>,
+    /// Ids of the verifications of the period that are not implemented
+    pub not_implemented: Vec<String>,
+    /// Ids of the blocking verifications excluded or not implemented. The run is then incomplete
+    pub blocking_not_run: Vec<String>,
+    /// Modifications of the dataset detected during the run
+    pub concurrent_modifications: Vec<String>,
+    /// Head of the hash chain of the journal at the creation of the report, to verify the log
+    /// file of the journal afterwards (see [super::RunJournal::verify_log_file])
+    pub journal_head: Option<JournalHead>,
+    /// Relaxations of the verifications by the strictness profile of the configuration
+    pub relaxations: Vec<Relaxation>,
+    /// XSD against which the XML files are validated
+    pub schemas: Vec<SchemaResource>,
+    pub summary: RunReportSummary,
+    /// Statistics of the verifications (durations and failures per category)
+    pub statistics: VerificationSuiteResult,
+    pub verifications: Vec<VerificationReport>,
+    /// Data of the manual verifications, to be checked by the auditors
+    pub manual_checks: Vec<ManualCheck>,
+    /// Statistics per verification card set, to cross-check against the delivery notes
+    pub vcs_statistics: Vec<VCSStatistics>,
+    /// Ballot boxes of the run, for an incremental verification of the tally
+    pub incremental_tally: Option<IncrementalTallyRun>,
+    /// Summaries added by the post-processors (see [ReportPostProcessor]), per name
+    pub custom_summaries: BTreeMap<String, serde_json::Value>,
+}
+
+/// Hook to post-process the report of a run before it is rendered
+///
+/// The post-processors are registered on the runner (see
+/// [super::Runner::add_report_post_processor]) and called by [super::Runner::report] with the
+/// suite of the verifications, e.g. to compute a summary specific to a canton and to add it with
+/// [RunReport::add_custom_summary].
+pub trait ReportPostProcessor: Send + Sync {
+    /// Name of the post-processor, for the logs
+    fn name(&self) -> &str;
+
+    /// Post-process the report
+    ///
+    /// # Error
+    /// If the post-processing fails. The error is logged and the changes of the post-processor
+    /// are discarded
+    fn post_process(&self, suite: &VerificationSuite, report: &mut RunReport)
+        -> anyhow::Result<()>;
+}
+
+/// Serialize the datetime in the format ISO 8601 (without time zone)
+fn serialize_datetime<S>(value: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+impl VerificationReport {
+    /// Report of the verification
+    pub fn new(verification: &Verification<'_, VerificationDirectory>) -> Self {
+        Self {
+            id: verification.id().clone(),
+            name: verification.meta_data().name().to_string(),
+            category: verification.meta_data().category().to_string(),
+            impact: verification.meta_data().impact(),
+            status: ResultStatus::of(verification),
+            duration: verification.duration(),
+            memory: verification.memory().copied(),
+            errors: verification.errors_to_string(),
+            failures: verification.failures_to_string(),
+            sub_results: verification.sub_results_summary(),
+            entities: group_by_entity(verification.failures())
+                .into_iter()
+                .map(|(entity, failures)| EntityFailures { entity, failures })
+                .collect(),
+            codes: verification.count_by_code(),
+        }
+    }
+}
+
+impl RunReportSummary {
+    /// Summary of the verifications, `blocking_not_run` being the number of the blocking
+    /// verifications excluded or not implemented
+    fn new(verifications: &[VerificationReport], blocking_not_run: usize) -> Self {
+        let count = |s: ResultStatus| verifications.iter().filter(|v| v.status == s).count();
+        let count_impact = |impact: VerificationImpact, statuses: &[ResultStatus]| {
+            verifications
+                .iter()
+                .filter(|v| v.impact == impact && statuses.contains(&v.status))
+                .count()
+        };
+        let not_ok = [ResultStatus::Errors, ResultStatus::Failures];
+        let informational = count_impact(VerificationImpact::Informational, &not_ok);
+        let outcome = if count_impact(VerificationImpact::Blocking, &not_ok) > 0 {
+            RunOutcome::Failed
+        } else if count_impact(VerificationImpact::Blocking, &[ResultStatus::NotRun]) > 0
+            || blocking_not_run > 0
+        {
+            RunOutcome::Incomplete
+        } else if informational > 0 {
+            RunOutcome::PassedWithRemarks
+        } else {
+            RunOutcome::Passed
+        };
+        Self {
+            total: verifications.len(),
+            ok: count(ResultStatus::Ok),
+            errors: count(ResultStatus::Errors),
+            failures: count(ResultStatus::Failures),
+            not_run: count(ResultStatus::NotRun),
+            informational,
+            outcome,
+        }
+    }
+}
+
+impl RunReport {
+    /// Report of the given verifications, run on the dataset in `dataset`
+    pub fn new(
+        period: &VerificationPeriod,
+        dataset: &Path,
+        verifications: Vec<VerificationReport>,
+    ) -> Self {
+        let mut res = Self {
+            period: period.to_string(),
+            dataset: dataset.to_string_lossy().to_string(),
+            created: Local::now().naive_local(),
+            locale: ReportLocale::default(),
+            duration: None,
+            partial: false,
+            excluded: vec![],
+            not_implemented: vec![],
+            blocking_not_run: vec![],
+            concurrent_modifications: vec![],
+            journal_head: None,
+            relaxations: vec![],
+            schemas: schema_resources(SchemaVersion::default()),
+            summary: RunReportSummary::default(),
+            statistics: VerificationSuiteResult::default(),
+            verifications,
+            manual_checks: vec![],
+            vcs_statistics: vec![],
+            incremental_tally: None,
+            custom_summaries: BTreeMap::new(),
+        };
+        res.update_summary();
+        res
+    }
+
+    /// Report of the verifications of the suite, run on the dataset in `dataset`
+    ///
+    /// The excluded verifications of the suite are listed in the report. The blocking
+    /// verifications excluded or not implemented make the run incomplete
+    pub fn from_suite(suite: &VerificationSuite, dataset: &Path) -> Self {
+        let verifications = suite
+            .query(&ResultQuery::new())
+            .iter()
+            .map(|v| VerificationReport::new(v.verification()))
+            .collect();
+        Self::new(suite.period(), dataset, verifications)
+            .with_excluded(suite.exclusion())
+            .with_not_implemented(suite.not_implemented())
+            .with_blocking_not_run(&suite.blocking_not_run())
+    }
+
+    /// Compute the summary and the statistics from the verifications
+    fn update_summary(&mut self) {
+        self.summary = RunReportSummary::new(&self.verifications, self.blocking_not_run.len());
+        let mut statistics = VerificationSuiteResult {
+            excluded: self.excluded.len(),
+            not_implemented: self.not_implemented.len(),
+            ..VerificationSuiteResult::default()
+        };
+        for v in self.verifications.iter() {
+            statistics.push(&v.id, &v.category, v.status, v.duration, v.failures.len());
+        }
+        self.statistics = statistics;
+    }
+
+    /// Set the duration of the run
+    pub fn with_duration(mut self, duration: Option<HumanDuration>) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Set the ids of the excluded verifications
+    pub fn with_excluded(mut self, excluded: &[String]) -> Self {
+        self.excluded = excluded.to_vec();
+        self.update_summary();
+        self
+    }
+
+    /// Set the ids of the verifications not implemented
+    pub fn with_not_implemented(mut self, not_implemented: &[String]) -> Self {
+        self.not_implemented = not_implemented.to_vec();
+        self.update_summary();
+        self
+    }
+
+    /// Set the ids of the blocking verifications excluded or not implemented
+    pub fn with_blocking_not_run(mut self, blocking_not_run: &[String]) -> Self {
+        self.blocking_not_run = blocking_not_run.to_vec();
+        self.update_summary();
+        self
+    }
+
+    /// Set the modifications of the dataset detected during the run
+    pub fn with_concurrent_modifications(mut self, modifications: &[String]) -> Self {
+        self.concurrent_modifications = modifications.to_vec();
+        self
+    }
+
+    /// Set the head of the hash chain of the journal
+    pub fn with_journal_head(mut self, head: JournalHead) -> Self {
+        self.journal_head = Some(head);
+        self
+    }
+
+    /// Set the relaxations of the verifications (see [crate::config::StrictnessProfile])
+    pub fn with_relaxations(mut self, relaxations: &[Relaxation]) -> Self {
+        self.relaxations = relaxations.to_vec();
+        self
+    }
+
+    /// Set the data of the manual verifications
+    pub fn with_manual_checks(mut self, manual_checks: Vec<ManualCheck>) -> Self {
+        self.manual_checks = manual_checks;
+        self
+    }
+
+    /// Set the statistics of the verification card sets
+    pub fn with_vcs_statistics(mut self, vcs_statistics: Vec<VCSStatistics>) -> Self {
+        self.vcs_statistics = vcs_statistics;
+        self
+    }
+
+    /// Add a summary computed outside the verifier (see [ReportPostProcessor])
+    ///
+    /// A summary with the same name is replaced
+    pub fn add_custom_summary(&mut self, name: &str, summary: serde_json::Value) {
+        self.custom_summaries.insert(name.to_string(), summary);
+    }
+
+    /// Apply the post-processors to the report, in the order of the list
+    ///
+    /// If a post-processor fails, the error is logged and its changes are discarded
+    pub fn post_process(
+        mut self,
+        suite: &VerificationSuite,
+        post_processors: &[Arc<dyn ReportPostProcessor>],
+    ) -> Self {
+        for p in post_processors.iter() {
+            let mut report = self.clone();
+            match p.post_process(suite, &mut report) {
+                Ok(()) => self = report,
+                Err(e) => warn!(
+                    "The post-processing of the report with {} failed: {:#}",
+                    p.name(),
+                    e
+                ),
+            }
+        }
+        self
+    }
+
+    /// Set the locale of the numbers and the dates in the text and csv formats
+    pub fn with_locale(mut self, locale: ReportLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Update the verifications with the outcome of the journal records, the latest record of a
+    /// verification winning
+    ///
+    /// The durations, the memory, the sub-results and the codes of the updated verifications are
+    /// not known from the journal and are removed. The records of the verifications not in the
+    /// report are ignored.
+    pub fn with_journal_records(mut self, records: &[JournalRecord]) -> Self {
+        for r in records.iter() {
+            if let Some(v) = self.verifications.iter_mut().find(|v| v.id == r.id) {
+                v.status = match (r.errors.is_empty(), r.failures.is_empty()) {
+                    (true, true) => ResultStatus::Ok,
+                    (false, _) => ResultStatus::Errors,
+                    (true, false) => ResultStatus::Failures,
+                };
+                v.duration = None;
+                v.memory = None;
+                v.errors = r.errors.clone();
+                v.failures = r.failures.clone();
+                v.sub_results = vec![];
+                v.entities = vec![];
+                v.codes = BTreeMap::new();
+            }
+        }
+        self.update_summary();
+        self
+    }
+
+    /// Take over the failures of the ballot boxes verified in previous runs (incremental
+    /// verification of the tally)
+    ///
+    /// `taken_over` contains the failures per name of ballot box, then per id of verification.
+    /// The failures are added to the verifications of the report, which get the status
+    /// [ResultStatus::Failures] if they were ok. The failures of the verifications not run or
+    /// not in the report are ignored.
+    pub fn with_ballot_boxes_taken_over(
+        mut self,
+        verified: &[String],
+        taken_over: &BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    ) -> Self {
+        for (bb, failures_per_id) in taken_over.iter() {
+            for (id, failures) in failures_per_id.iter().filter(|(_, f)| !f.is_empty()) {
+                let v = match self
+                    .verifications
+                    .iter_mut()
+                    .find(|v| &v.id == id && v.status != ResultStatus::NotRun)
+                {
+                    Some(v) => v,
+                    None => continue,
+                };
+                if v.status == ResultStatus::Ok {
+                    v.status = ResultStatus::Failures;
+                }
+                v.failures.extend(failures.iter().cloned());
+                v.entities.push(EntityFailures {
+                    entity: Entity::new(EntityKind::BallotBox, bb),
+                    failures: failures.clone(),
+                });
+                v.entities.sort_by(|a, b| a.entity.cmp(&b.entity));
+            }
+        }
+        self.incremental_tally = Some(IncrementalTallyRun {
+            verified: verified.to_vec(),
+            taken_over: taken_over.keys().cloned().collect(),
+        });
+        self.update_summary();
+        self
+    }
+
+    /// Failures of all the verifications grouped per entity, as pairs (id of the verification,
+    /// failure)
+    ///
+    /// The entities are sorted (see [Entity]) and the failures of an entity are in the order of
+    /// the verifications
+    pub fn failures_by_entity(&self) -> BTreeMap<Entity, Vec<(String, String)>> {
+        let mut res: BTreeMap<Entity, Vec<(String, String)>> = BTreeMap::new();
+        for v in self.verifications.iter() {
+            for e in v.entities.iter() {
+                res.entry(e.entity.clone())
+                    .or_default()
+                    .extend(e.failures.iter().map(|f| (v.id.clone(), f.clone())));
+            }
+        }
+        res
+    }
+
+    /// Period of the run
+    pub fn period(&self) -> anyhow::Result<VerificationPeriod> {
+        VerificationPeriod::try_from(self.period.as_str())
+    }
+
+    /// The slowest verifications with their duration, e.g. `05.01 (12.3s), 03.02 (4.1s)`
+    pub(super) fn slowest_to_string(&self) -> String {
+        self.statistics
+            .slowest
+            .iter()
+            .map(|s| format!("{} ({})", s.id, s.duration))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The categories having failures with their number of failures, e.g. `consistency: 2`
+    ///
+    /// Empty if no verification has failures
+    pub(super) fn failures_by_category_to_string(
+        &self,
+        number: impl Fn(usize) -> String,
+    ) -> String {
+        self.statistics
+            .failures_by_category
+            .iter()
+            .filter(|(_, n)| **n > 0)
+            .map(|(c, n)| format!("{}: {}", c, number(*n)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The report as json string
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| anyhow!(e).context("Cannot serialize the report"))
+    }
+
+    /// The report as text, with the summary and one block per verification
+    pub fn to_text(&self) -> String {
+        let number = |n: usize| self.locale.format_number(n as u64);
+        let mut res = format!(
+            "Report of the verifications of the {} of the dataset {}\n",
+            self.period, self.dataset
+        );
+        let _ = writeln!(
+            res,
+            "Created: {}",
+            self.locale.format_datetime(&self.created)
+        );
+        if let Some(d) = &self.duration {
+            let _ = writeln!(res, "Duration: {}", d);
+        }
+        if self.partial {
+            let _ = writeln!(res, "Partial report: the run is not finished");
+        }
+        let _ = writeln!(
+            res,
+            "Verifications: {} (ok: {}, errors: {}, failures: {}, not run: {})",
+            number(self.summary.total),
+            number(self.summary.ok),
+            number(self.summary.errors),
+            number(self.summary.failures),
+            number(self.summary.not_run)
+        );
+        let _ = writeln!(
+            res,
+            "Outcome: {} (informational verifications not ok: {})",
+            self.summary.outcome,
+            number(self.summary.informational)
+        );
+        if !self.excluded.is_empty() {
+            let _ = writeln!(res, "Excluded: {}", self.excluded.join(" "));
+        }
+        if !self.not_implemented.is_empty() {
+            let _ = writeln!(res, "Not implemented: {}", self.not_implemented.join(" "));
+        }
+        if !self.blocking_not_run.is_empty() {
+            let _ = writeln!(
+                res,
+                "Blocking verifications not run: {}",
+                self.blocking_not_run.join(" ")
+            );
+        }
+        if !self.statistics.slowest.is_empty() {
+            let _ = writeln!(
+                res,
+                "Total duration of the verifications: {}",
+                self.statistics.total_duration
+            );
+            let _ = writeln!(res, "Slowest verifications: {}", self.slowest_to_string());
+        }
+        let failures_by_category = self.failures_by_category_to_string(number);
+        if !failures_by_category.is_empty() {
+            let _ = writeln!(res, "Failures per category: {}", failures_by_category);
+        }
+        if let Some(i) = &self.incremental_tally {
+            let _ = writeln!(
+                res,
+                "Incremental tally: ballot boxes verified: {}; taken over from previous runs: {}",
+                i.verified.join(" "),
+                i.taken_over.join(" ")
+            );
+        }
+        for m in self.concurrent_modifications.iter() {
+            let _ = writeln!(res, "Modification during the run: {}", m);
+        }
+        if let Some(h) = &self.journal_head {
+            let _ = writeln!(
+                res,
+                "Head of the journal: {} records, hash {}",
+                h.records, h.hash
+            );
+        }
+        for r in self.relaxations.iter() {
+            let _ = writeln!(res, "Relaxation: {}", r);
+        }
+        for (s, p) in self
+            .schemas
+            .iter()
+            .filter_map(|s| s.overridden_by.as_ref().map(|p| (s, p)))
+        {
+            let _ = writeln!(
+                res,
+                "Schema {} {} overridden by {:?} (SHA-256 {})",
+                s.name, s.version, p, s.sha256
+            );
+        }
+        for v in self.verifications.iter() {
+            let _ = write!(res, "{} {}", v.id, v.name);
+            if v.impact == VerificationImpact::Informational {
+                let _ = write!(res, " [informational]");
+            }
+            let _ = write!(res, ": {:?}", v.status);
+            match (&v.duration, &v.memory) {
+                (Some(d), Some(m)) if !m.to_string().is_empty() => {
+                    let _ = write!(res, " ({}, {})", d, m);
+                }
+                (Some(d), _) => {
+                    let _ = write!(res, " ({})", d);
+                }
+                (None, _) => (),
+            }
+            let _ = writeln!(
+                res,
+                " - {} errors, {} failures",
+                number(v.errors.len()),
+                number(v.failures.len())
+            );
+            for s in v.sub_results.iter() {
+                let _ = writeln!(res, "  {}: {}/{} ok", s.kind, number(s.ok), number(s.total));
+            }
+            for e in v.errors.iter() {
+                let _ = writeln!(res, "  error: {}", e);
+            }
+            for f in v.failures.iter() {
+                let _ = writeln!(res, "  failure: {}", f);
+            }
+        }
+        let failures_by_entity = self.failures_by_entity();
+        if !failures_by_entity.is_empty() {
+            let _ = writeln!(res, "Failures per entity:");
+            for (entity, failures) in failures_by_entity.iter() {
+                let _ = writeln!(res, "  {}:", entity);
+                for (id, f) in failures.iter() {
+                    let _ = writeln!(res, "    {}: {}", id, f);
+                }
+            }
+        }
+        if !self.vcs_statistics.is_empty() {
+            let _ = writeln!(res, "Verification card sets:");
+            let optional = |n: Option<usize>| n.map(number).unwrap_or("-".to_string());
+            for s in self.vcs_statistics.iter() {
+                let _ = write!(
+                    res,
+                    "  {}: ballot box {}, voting cards {} (context), verification cards {}, \
+                     chunks {}, files {}, bytes {}",
+                    s.name,
+                    s.ballot_box_id.as_deref().unwrap_or("-"),
+                    optional(s.number_of_voting_cards),
+                    optional(s.verification_cards),
+                    number(s.chunks),
+                    number(s.files),
+                    self.locale.format_number(s.bytes)
+                );
+                match &s.ballot_box {
+                    Some(bb) if bb.found => {
+                        let _ = write!(
+                            res,
+                            ", ballot box files {}, ballot box bytes {}, votes {}",
+                            number(bb.files),
+                            self.locale.format_number(bb.bytes),
+                            optional(bb.votes)
+                        );
+                    }
+                    Some(_) => {
+                        let _ = write!(res, ", ballot box not found");
+                    }
+                    None => (),
+                }
+                res.push('\n');
+            }
+        }
+        if !self.custom_summaries.is_empty() {
+            let _ = writeln!(res, "Custom summaries:");
+            for (name, summary) in self.custom_summaries.iter() {
+                let _ = writeln!(res, "  {}: {}", name, summary);
+            }
+        }
+        if !self.manual_checks.is_empty() {
+            let _ = writeln!(res, "Manual verifications:");
+            for c in self.manual_checks.iter() {
+                res.push_str(&c.to_text());
+            }
+        }
+        res
+    }
+
+    /// The report as csv (with header), one line per verification, separated by `,`
+    pub fn to_csv(&self) -> String {
+        let mut res = String::from("id,name,category,status,duration,errors,failures\n");
+        for v in self.verifications.iter() {
+            let _ = writeln!(
+                res,
+                "{},{},{},{:?},{},{},{}",
+                csv_field(&v.id),
+                csv_field(&v.name),
+                csv_field(&v.category),
+                v.status,
+                csv_field(&v.duration.map(|d| d.to_string()).unwrap_or_default()),
+                csv_field(&self.locale.format_number(v.errors.len() as u64)),
+                csv_field(&self.locale.format_number(v.failures.len() as u64))
+            );
+        }
+        res
+    }
+
+    /// Save the report, as json if the extension of the file is `json`, as csv if the extension
+    /// is `csv`, as text else
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(p) = path.parent() {
+            fs::create_dir_all(p).with_context(|| format!("Cannot create directory {:?}", p))?;
+        }
+        let content = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => self.to_json()?,
+            Some("csv") => self.to_csv(),
+            _ => self.to_text(),
+        };
+        fs::write(path, content).with_context(|| format!("Cannot write the report to {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::file_structure::{
+        setup_directory::SetupDirectoryTrait, statistics::vcs_statistics,
+        VerificationDirectoryTrait,
+    };
+    use crate::{
+        config::{
+            test::{test_dataset_setup_path, test_dataset_tally_path, CONFIG_TEST},
+            StrictnessProfile,
+        },
+        verification::{
+            meta_data::VerificationMetaDataList,
+            result::{
+                create_verification_failure, EntityKind, VerificationEvent, VerificationResult,
+            },
+            suite::VerificationList,
+            VerificationContext,
+        },
+    };
+    use chrono::NaiveDate;
+    use log::debug;
+    use std::{env::temp_dir, time::Duration};
+
+    fn ok(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+        VerificationResult::new()
+    }
+
+    fn failure(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+        let mut result = VerificationResult::new();
+        result.push(create_verification_failure!("Wrong number of cards"));
+        result.push(
+            create_verification_failure!("Wrong number of cards in vcs_2")
+                .with_code(FailureCode::Inconsistency)
+                .with_entity(EntityKind::VerificationCardSet, "vcs_2"),
+        );
+        result
+    }
+
+    #[test]
+    fn test_report() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut list = VerificationList(vec![
+            Verification::new(
+                "01.01",
+                "VerifySetupCompleteness",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+            Verification::new(
+                "X.01.01",
+                "VerifyVerificationDataCoverCards",
+                failure,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+            Verification::new(
+                "03.01",
+                "VerifyEncryptionGroupConsistency",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+        ]);
+        let dir =
+            VerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        list.0[0].run(&dir);
+        list.0[1].run(&dir);
+        let report = RunReport::new(
+            &VerificationPeriod::Setup,
+            &test_dataset_setup_path(),
+            list.query(&ResultQuery::new())
+                .iter()
+                .map(|v| VerificationReport::new(v.verification()))
+                .collect(),
+        )
+        .with_duration(Some(HumanDuration(Duration::from_secs(2))))
+        .with_excluded(&["05.01".to_string()])
+        .with_relaxations(
+            &StrictnessProfile {
+                name: "XY".to_string(),
+                total_voters_tolerance: 2,
+                ..Default::default()
+            }
+            .relaxations(),
+        );
+        assert_eq!(report.period().unwrap(), VerificationPeriod::Setup);
+        assert_eq!(
+            report.summary,
+            RunReportSummary {
+                total: 3,
+                ok: 1,
+                errors: 0,
+                failures: 1,
+                not_run: 1,
+                informational: 0,
+                outcome: RunOutcome::Failed
+            }
+        );
+        assert_eq!(report.verifications[1].failures.len(), 2);
+        assert_eq!(
+            report.failures_by_entity()[&Entity::new(EntityKind::VerificationCardSet, "vcs_2")],
+            vec![(
+                "X.01.01".to_string(),
+                "Wrong number of cards in vcs_2".to_string()
+            )]
+        );
+        assert!(report.to_text().contains(
+            "Failures per entity:\n  verification card set vcs_2:\n    X.01.01: Wrong number of cards in vcs_2\n"
+        ));
+        assert!(report.to_text().contains(
+            "Relaxation: total_voters_tolerance = 2 (profile \"XY\", verifications 03.13)\n"
+        ));
+        assert!(report.verifications[0].duration.is_some());
+        assert!(report.verifications[2].duration.is_none());
+        assert_eq!((report.statistics.passed, report.statistics.failed), (1, 1));
+        assert_eq!(report.statistics.excluded, 1);
+        assert_eq!(report.statistics.failures_by_category["completness"], 2);
+        assert_eq!(report.statistics.slowest.len(), 2);
+        assert!(report
+            .to_text()
+            .contains("Failures per category: completness: 2\n"));
+        assert!(report.to_text().contains("Slowest verifications: 01."));
+        let path = temp_dir().join("rust_verifier_test_report.json");
+        report.save(&path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["period"], "setup");
+        assert_eq!(json["duration"]["formatted"], "2.0s");
+        assert_eq!(json["excluded"][0], "05.01");
+        assert_eq!(json["relaxations"][0]["verifications"][0], "03.13");
+        assert_eq!(json["schemas"][0]["name"], "eCH-0006");
+        assert_eq!(json["verifications"][1]["status"], "Failures");
+        assert_eq!(json["verifications"][1]["category"], "completness");
+        assert_eq!(
+            json["verifications"][1]["entities"][0]["entity"]["kind"],
+            "verification_card_set"
+        );
+        assert_eq!(json["verifications"][1]["codes"]["inconsistency"], 1);
+        assert_eq!(json["verifications"][1]["codes"]["unclassified"], 1);
+        assert_eq!(json["locale"], "en");
+        assert_eq!(json["statistics"]["failed"], 1);
+        assert_eq!(json["statistics"]["failures_by_category"]["completness"], 2);
+        assert!(json["created"].as_str().is_some());
+        fs::remove_file(&path).unwrap();
+    }
+
+    struct CantonSummary;
+
+    impl ReportPostProcessor for CantonSummary {
+        fn name(&self) -> &str {
+            "canton"
+        }
+
+        fn post_process(
+            &self,
+            suite: &VerificationSuite,
+            report: &mut RunReport,
+        ) -> anyhow::Result<()> {
+            report.add_custom_summary(
+                "canton",
+                serde_json::json!({ "verifications": suite.len(), "ok": report.summary.ok }),
+            );
+            Ok(())
+        }
+    }
+
+    struct FailingSummary;
+
+    impl ReportPostProcessor for FailingSummary {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn post_process(
+            &self,
+            _suite: &VerificationSuite,
+            report: &mut RunReport,
+        ) -> anyhow::Result<()> {
+            report.add_custom_summary("failing", serde_json::json!("partial"));
+            report.verifications.clear();
+            Err(anyhow!("Data of the canton missing"))
+        }
+    }
+
+    #[test]
+    fn test_post_process() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let suite = VerificationSuite::new(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &[],
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        let post_processors: Vec<Arc<dyn ReportPostProcessor>> =
+            vec![Arc::new(FailingSummary), Arc::new(CantonSummary)];
+        let report = RunReport::from_suite(&suite, &test_dataset_setup_path())
+            .post_process(&suite, &post_processors);
+        assert_eq!(report.verifications.len(), suite.len());
+        assert_eq!(report.not_implemented, *suite.not_implemented());
+        assert_eq!(report.statistics, suite.suite_result());
+        assert_eq!(report.custom_summaries.len(), 1);
+        assert_eq!(
+            report.custom_summaries["canton"]["verifications"],
+            suite.len()
+        );
+        assert!(report.to_text().contains(&format!(
+            "Custom summaries:\n  canton: {{\"ok\":0,\"verifications\":{}}}\n",
+            suite.len()
+        )));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["custom_summaries"]["canton"]["ok"], 0);
+    }
+
+    #[test]
+    fn test_outcome() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut list = VerificationList(vec![
+            Verification::new(
+                "01.01",
+                "VerifySetupCompleteness",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+            Verification::new(
+                "X.04.02",
+                "VerifySetupNoDuplicateChunks",
+                failure,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+        ]);
+        let dir =
+            VerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        list.0[1].run(&dir);
+        let report_of = |list: &VerificationList| {
+            RunReport::new(
+                &VerificationPeriod::Setup,
+                &test_dataset_setup_path(),
+                list.query(&ResultQuery::new())
+                    .iter()
+                    .map(|v| VerificationReport::new(v.verification()))
+                    .collect(),
+            )
+        };
+        assert_eq!(report_of(&list).summary.outcome, RunOutcome::Incomplete);
+        list.0[0].run(&dir);
+        let report = report_of(&list);
+        assert_eq!(report.summary.failures, 1);
+        assert_eq!(report.summary.informational, 1);
+        assert_eq!(report.summary.outcome, RunOutcome::PassedWithRemarks);
+        assert!(report
+            .to_text()
+            .contains("Outcome: passed with remarks (informational verifications not ok: 1)"));
+        assert!(report
+            .to_text()
+            .contains("X.04.02 VerifySetupNoDuplicateChunks [informational]: Failures"));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["summary"]["outcome"], "passed_with_remarks");
+        assert_eq!(json["verifications"][1]["impact"], "informational");
+        let report = report.with_blocking_not_run(&["02.01".to_string()]);
+        assert_eq!(report.summary.outcome, RunOutcome::Incomplete);
+        assert!(report
+            .to_text()
+            .contains("Blocking verifications not run: 02.01"));
+    }
+
+    #[test]
+    fn test_ballot_boxes_taken_over() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut list = VerificationList(vec![
+            Verification::new(
+                "10.01",
+                "VerifyOnlineControlComponents",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+            Verification::new(
+                "08.02",
+                "VerifyCiphertextsConsistency",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+        ]);
+        let dir =
+            VerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        list.0[0].run(&dir);
+        let report = RunReport::new(
+            &VerificationPeriod::Tally,
+            &test_dataset_tally_path(),
+            list.query(&ResultQuery::new())
+                .iter()
+                .map(|v| VerificationReport::new(v.verification()))
+                .collect(),
+        );
+        let taken_over = BTreeMap::from([
+            (
+                "bb_1".to_string(),
+                BTreeMap::from([
+                    ("10.01".to_string(), vec!["Failure of bb_1".to_string()]),
+                    ("08.02".to_string(), vec!["Not run".to_string()]),
+                ]),
+            ),
+            ("bb_2".to_string(), BTreeMap::new()),
+        ]);
+        let report = report.with_ballot_boxes_taken_over(&["bb_3".to_string()], &taken_over);
+        assert_eq!(report.verifications[0].status, ResultStatus::Failures);
+        assert_eq!(report.verifications[0].failures, vec!["Failure of bb_1"]);
+        assert_eq!(report.verifications[1].status, ResultStatus::NotRun);
+        assert!(report.verifications[1].failures.is_empty());
+        assert_eq!(report.summary.failures, 1);
+        assert_eq!(
+            report.failures_by_entity()[&Entity::new(EntityKind::BallotBox, "bb_1")],
+            vec![("10.01".to_string(), "Failure of bb_1".to_string())]
+        );
+        assert_eq!(
+            report.incremental_tally,
+            Some(IncrementalTallyRun {
+                verified: vec!["bb_3".to_string()],
+                taken_over: vec!["bb_1".to_string(), "bb_2".to_string()]
+            })
+        );
+        assert!(report.to_text().contains(
+            "Incremental tally: ballot boxes verified: bb_3; taken over from previous runs: bb_1 bb_2\n"
+        ));
+    }
+
+    #[test]
+    fn test_vcs_statistics() {
+        let dir =
+            VerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let report = RunReport::new(
+            &VerificationPeriod::Setup,
+            &test_dataset_setup_path(),
+            vec![],
+        )
+        .with_vcs_statistics(vcs_statistics(&dir));
+        let name = &report.vcs_statistics[0].name;
+        assert!(report
+            .to_text()
+            .lines()
+            .any(|l| l.starts_with(&format!("  {}: ballot box ", name))));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json["vcs_statistics"].as_array().unwrap().len(),
+            dir.unwrap_setup().vcs_directories().len()
+        );
+    }
+
+    #[test]
+    fn test_report_locale() {
+        let mut report = RunReport::new(
+            &VerificationPeriod::Tally,
+            &test_dataset_setup_path(),
+            vec![],
+        )
+        .with_locale(ReportLocale::DeCh);
+        report.created = NaiveDate::from_ymd_opt(2024, 2, 15)
+            .unwrap()
+            .and_hms_opt(21, 11, 5)
+            .unwrap();
+        report.summary.total = 1234;
+        report.summary.not_run = 1234;
+        report.verifications.push(VerificationReport {
+            id: "10.01".to_string(),
+            name: "VerifyOnlineControlComponents".to_string(),
+            category: "evidence".to_string(),
+            impact: VerificationImpact::Blocking,
+            status: ResultStatus::Failures,
+            duration: Some(HumanDuration(Duration::from_secs(2))),
+            memory: None,
+            errors: vec![],
+            failures: vec!["proof, not ok".to_string(); 2500],
+            sub_results: vec![SubResultsSummary {
+                kind: "chunk".to_string(),
+                total: 1234,
+                ok: 1233,
+            }],
+            entities: vec![],
+            codes: BTreeMap::new(),
+        });
+        let text = report.to_text();
+        assert!(text.contains("Created: 15.02.2024 21:11:05"));
+        assert!(
+            text.contains("Verifications: 1'234 (ok: 0, errors: 0, failures: 0, not run: 1'234)")
+        );
+        assert!(text.contains(
+            "10.01 VerifyOnlineControlComponents: Failures (2.0s) - 0 errors, 2'500 failures"
+        ));
+        assert!(text.contains("  chunk: 1'233/1'234 ok"));
+        assert_eq!(
+            report.to_csv().lines().nth(1).unwrap(),
+            "10.01,VerifyOnlineControlComponents,evidence,Failures,2.0s,0,2'500"
+        );
+        let report = report.with_manual_checks(vec![ManualCheck {
+            id: "M.01".to_string(),
+            name: "ElectionEventDates".to_string(),
+            description: "The dates are correct".to_string(),
+            values: vec![("Start".to_string(), "2024-02-15 08:00:00".to_string())],
+            errors: vec![],
+        }]);
+        assert!(report.to_text().ends_with(
+            "Manual verifications:\nM.01 ElectionEventDates: The dates are correct\n  Start: 2024-02-15 08:00:00\n"
+        ));
+        let report = report.with_locale(ReportLocale::En);
+        assert!(report.to_text().contains("Created: 2024-02-15 21:11:05"));
+        assert!(report.to_csv().ends_with(",0,\"2,500\"\n"));
+    }
+}
@@ -1,31 +1,82 @@
 use anyhow::anyhow;
 //use futures::{stream::FuturesUnordered, StreamExt};
+use super::{
+    checkpoint::{ Checkpoint, CheckpointHeader },
+    events::{ EventStream, RunnerEvent, RunnerEventKind },
+    file_report::FileAccessReport,
+    journal::RunJournal,
+    report::{ ReportPostProcessor, RunReport },
+};
 use crate::{
     config::Config as VerifierConfig,
-    file_structure::VerificationDirectory,
+    data_structures::dataset_version::DatasetVersion,
+    file_structure::{
+        data_source::{ local_source, DataSource },
+        file_access::{ take_file_accesses, FileAccesses },
+        file_group::take_concurrent_modifications,
+        statistics::vcs_statistics,
+        VerificationDirectory,
+    },
+    human_format::HumanDuration,
     verification::{
+        manual::{ extract_manual_checks, ManualCheck },
         meta_data::VerificationMetaDataList,
-        result::VerificationResultTrait,
-        suite::VerificationSuite,
+        query::{ ResultQuery, ResultView },
+        result::{ VerificationResult, VerificationResultTrait },
+        suite::{ ExecutionPolicy, VerificationSelection, VerificationSuite },
+        verifications::{ RunObserver, Verification },
+        VerificationCategory,
         VerificationPeriod,
     },
 };
+use chrono::Local;
 use log::{ info, warn };
 //use std::future::Future;
 use rayon::prelude::*;
-use std::{ iter::zip, sync::Mutex };
+use std::{
+    collections::HashMap,
+    sync::{ atomic::{ AtomicBool, Ordering }, mpsc::Receiver, Arc, Mutex, PoisonError },
+    thread,
+};
 use std::{ path::{ Path, PathBuf }, time::{ Duration, SystemTime } };
 
 pub fn no_action_before_fn(_: &str) {}
 pub fn no_action_after_fn(_: &str, _: Vec<String>, _: Vec<String>) {}
 
+/// Export the failed items of the verification, logging a warning in case of problem
+fn export_failed_items(verification: &Verification<'_, VerificationDirectory>) {
+    if let Err(e) = verification.export_failed_items() {
+        warn!("Failed items of verification {} cannot be exported: {}", verification.id(), e);
+    }
+}
+
+/// Detect the version of the dataset in the source, to verify the format of the datetimes
+///
+/// If it cannot be detected, a warning is logged and the format of the datetimes is not verified
+fn detect_dataset_version(path: &Path, source: &Arc<dyn DataSource>) -> Option<DatasetVersion> {
+    match DatasetVersion::detect(source, path) {
+        Ok(v) => {
+            info!("Dataset {} detected in {:?}", v, path);
+            Some(v)
+        }
+        Err(e) => {
+            warn!("{}. The format of the datetimes is not verified", e);
+            None
+        }
+    }
+}
+
 /// Strategy to run the tests
 pub trait RunStrategy<'a> {
     /// Run function
+    ///
+    /// The verifications already finished (e.g. restored from a checkpoint) are not run again.
+    /// The observer is notified at the start and at the end of each verification
     fn run(
         &self,
         verifications: &'a mut VerificationSuite<'a>,
         dir_path: &Path,
+        observer: &dyn RunObserver,
         action_before: impl Fn(&str) + Send + Sync,
         action_after: impl Fn(&str, Vec<String>, Vec<String>) + Send + Sync
     );
@@ -35,22 +86,88 @@ pub trait RunStrategy<'a> {
 pub struct RunSequential;
 
 /// Strategy to run the tests concurrently
-pub struct RunParallel;
+///
+/// The verifications are grouped per category. The categories run concurrently, each of them
+/// within its own thread pool, whose size is given by the [CategoryParallelism]
+#[derive(Debug, Clone, Default)]
+pub struct RunParallel {
+    parallelism: CategoryParallelism,
+}
+
+/// Maximum number of verifications running concurrently per category
+///
+/// IO-bound verifications (e.g. completness) and CPU-bound verifications (e.g. evidence)
+/// do not scale the same way. Per default, each category can use all the available cores.
+#[derive(Debug, Clone)]
+pub struct CategoryParallelism {
+    default: usize,
+    limits: HashMap<VerificationCategory, usize>,
+}
+
+impl Default for CategoryParallelism {
+    fn default() -> Self {
+        Self::new(rayon::current_num_threads())
+    }
+}
+
+impl CategoryParallelism {
+    /// New structure with the default limit for all the categories
+    ///
+    /// A limit of 0 is interpreted as 1
+    pub fn new(default: usize) -> Self {
+        Self {
+            default: default.max(1),
+            limits: HashMap::new(),
+        }
+    }
+
+    /// Set the limit for the given category
+    ///
+    /// A limit of 0 is interpreted as 1
+    pub fn with_limit(mut self, category: VerificationCategory, limit: usize) -> Self {
+        self.limits.insert(category, limit.max(1));
+        self
+    }
+
+    /// Limit for the given category
+    pub fn limit(&self, category: &VerificationCategory) -> usize {
+        *self.limits.get(category).unwrap_or(&self.default)
+    }
+}
+
+impl RunParallel {
+    /// New parallel strategy with the given limits per category
+    pub fn new(parallelism: CategoryParallelism) -> Self {
+        Self { parallelism }
+    }
+
+    /// Limits per category
+    pub fn parallelism(&self) -> &CategoryParallelism {
+        &self.parallelism
+    }
+}
 
 impl<'a> RunStrategy<'a> for RunSequential {
     fn run(
         &self,
         verifications: &'a mut VerificationSuite<'a>,
         dir_path: &Path,
+        observer: &dyn RunObserver,
         action_before: impl Fn(&str) + Send + Sync,
         action_after: impl Fn(&str, Vec<String>, Vec<String>) + Send + Sync
     ) {
-        let directory = VerificationDirectory::new(verifications.period(), dir_path);
-        let it = verifications.list.0.iter_mut();
+        let directories = verifications.verification_directories(dir_path);
+        let policy = verifications.execution_policy();
+        let version = verifications.dataset_version();
+        let it = verifications.list.0.iter_mut().filter(|v| !v.is_finished());
         for v in it {
             action_before(v.id());
-            v.run(&directory);
+            v.run_with_observer(directories.get(v.meta_data().scope()), version, observer);
+            export_failed_items(v);
             action_after(v.id(), v.errors_to_string(), v.failures_to_string());
+            if policy.must_stop(v.result()) {
+                break;
+            }
         }
     }
 }
@@ -60,25 +177,130 @@ impl<'a> RunStrategy<'a> for RunParallel {
         &self,
         verifications: &'a mut VerificationSuite<'a>,
         dir_path: &Path,
+        observer: &dyn RunObserver,
         action_before: impl Fn(&str) + Send + Sync,
         action_after: impl Fn(&str, Vec<String>, Vec<String>) + Send + Sync
     ) {
-        let directory = VerificationDirectory::new(verifications.period(), dir_path);
-        let dirs = vec![directory; verifications.len()];
-        zip(verifications.list.0.iter_mut().map(Mutex::new), dirs)
-            .par_bridge()
-            .for_each(|(vm, d)| {
-                let mut v = vm.lock().unwrap();
-                action_before(v.id());
-                v.run(&d);
-                action_after(v.id(), v.errors_to_string(), v.failures_to_string());
-            });
+        let directories = verifications.verification_directories(dir_path);
+        let policy = verifications.execution_policy();
+        let version = verifications.dataset_version();
+        let mut categories: HashMap<
+            VerificationCategory,
+            Vec<&mut Verification<'a, VerificationDirectory>>
+        > = HashMap::new();
+        for v in verifications.list.0.iter_mut().filter(|v| !v.is_finished()) {
+            categories.entry(*v.meta_data().category()).or_default().push(v);
+        }
+        let directories = &directories;
+        let action_before = &action_before;
+        let action_after = &action_after;
+        // Set by the first verification stopping the run (see ExecutionPolicy)
+        let stop = &AtomicBool::new(false);
+        thread::scope(|s| {
+            for (category, verifs) in categories {
+                let limit = self.parallelism.limit(&category);
+                s.spawn(move || {
+                    let run_verifs = move || {
+                        verifs.into_par_iter().for_each(|v| {
+                            if stop.load(Ordering::SeqCst) {
+                                return;
+                            }
+                            action_before(v.id());
+                            v.run_with_observer(
+                                directories.get(v.meta_data().scope()),
+                                version,
+                                observer
+                            );
+                            export_failed_items(v);
+                            action_after(v.id(), v.errors_to_string(), v.failures_to_string());
+                            if policy.must_stop(v.result()) {
+                                stop.store(true, Ordering::SeqCst);
+                            }
+                        })
+                    };
+                    match rayon::ThreadPoolBuilder::new().num_threads(limit).build() {
+                        Ok(pool) => pool.install(run_verifs),
+                        Err(e) => {
+                            warn!(
+                                "Cannot create the thread pool for category {:?}: {}. Run in the global pool",
+                                category,
+                                e
+                            );
+                            run_verifs()
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// Handle to create partial reports during a run
+///
+/// The runner is borrowed during the run, but the handle can be cloned and sent to another
+/// thread (e.g. a timer writing the partial report regularly). The partial report is the report
+/// at the start of the run (with the verifications restored from the checkpoint), updated with
+/// the attempts recorded in the journal since then (see [RunReport::with_journal_records]).
+#[derive(Clone)]
+pub struct PartialReportHandle {
+    journal: Arc<Mutex<RunJournal>>,
+    start: Arc<Mutex<Option<(RunReport, usize)>>>,
+}
+
+impl PartialReportHandle {
+    fn new(journal: Arc<Mutex<RunJournal>>) -> Self {
+        Self {
+            journal,
+            start: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Record the start of a run with the report at the start
+    fn start(&self, report: RunReport) {
+        let first_record = self
+            .journal
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .records()
+            .len();
+        *self.start.lock().unwrap_or_else(PoisonError::into_inner) = Some((report, first_record));
+    }
+
+    /// Forget the run
+    fn clear(&self) {
+        *self.start.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Current snapshot of the results of the run. `None` if the run is not started
+    ///
+    /// The verifications running or not started are reported as not run. The report is marked as
+    /// partial, also if the run is finished (use [Runner::report] for the final report)
+    pub fn report(&self) -> Option<RunReport> {
+        let start = self.start.lock().unwrap_or_else(PoisonError::into_inner);
+        let (report, first_record) = start.as_ref()?;
+        let journal = self.journal.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut res = report
+            .clone()
+            .with_journal_records(journal.records().get(*first_record..).unwrap_or(&[]))
+            .with_journal_head(journal.head());
+        res.created = Local::now().naive_local();
+        res.partial = true;
+        Some(res)
     }
 }
 
 /// Structure defining the runner
 ///
 /// The runner can run only once. The runner has to be reseted to restart.
+///
+/// The outcomes of all the runs are kept in a [RunJournal], which is not cleared by
+/// [Runner::reset]. Then the history of the verifications run several times is available.
+///
+/// With a checkpoint (see [Runner::set_checkpoint_file]), the result of each verification is
+/// persisted as soon as it is finished, so that a run interrupted by a crash can be resumed.
+///
+/// Partial reports can be created during the run with a [PartialReportHandle] (see
+/// [Runner::partial_report_handle]).
 pub struct Runner<'a, T: RunStrategy<'a>> {
     path: PathBuf,
     verifications: Box<VerificationSuite<'a>>,
@@ -89,6 +311,14 @@ pub struct Runner<'a, T: RunStrategy<'a>> {
     action_before: Box<dyn Fn(&str) + Send + Sync>,
     #[allow(clippy::type_complexity)]
     action_after: Box<dyn Fn(&str, Vec<String>, Vec<String>) + Send + Sync>,
+    observers: Vec<Arc<dyn RunObserver>>,
+    events: Arc<EventStream>,
+    report_post_processors: Vec<Arc<dyn ReportPostProcessor>>,
+    journal: Arc<Mutex<RunJournal>>,
+    partial_report: PartialReportHandle,
+    checkpoint: Option<Arc<Mutex<Checkpoint>>>,
+    concurrent_modifications: Vec<String>,
+    file_accesses: FileAccesses,
 }
 
 impl<'a, T> Runner<'a, T> where T: RunStrategy<'a> {
@@ -96,6 +326,12 @@ impl<'a, T> Runner<'a, T> where T: RunStrategy<'a> {
     ///
     /// path represents the location where the directory setup and tally are stored
     /// period ist the verification period
+    ///
+    /// The version of the dataset is detected and given to the verifications (see
+    /// [DatasetVersion::detect]). If it cannot be detected, a warning is logged and the format of
+    /// the datetimes is not verified.
+    ///
+    /// Return an error if the suite of verifications cannot be built (see [VerificationSuite::new])
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         path: &Path,
@@ -106,31 +342,117 @@ impl<'a, T> Runner<'a, T> where T: RunStrategy<'a> {
         config: &'static VerifierConfig,
         action_before: impl Fn(&str) + Send + Sync + 'static,
         action_after: impl Fn(&str, Vec<String>, Vec<String>) + Send + Sync + 'static
-    ) -> Runner<'a, T> {
-        Runner {
+    ) -> anyhow::Result<Runner<'a, T>> {
+        Self::new_with_source(
+            path,
+            local_source(),
+            period,
+            metadata,
+            exclusion,
+            run_strategy,
+            config,
+            action_before,
+            action_after
+        )
+    }
+
+    /// Create a new runner reading the files of the dataset from `source` (e.g. the source of a
+    /// [crate::file_structure::zip_archive::ZipDataset])
+    ///
+    /// See [Runner::new]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_source(
+        path: &Path,
+        source: Arc<dyn DataSource>,
+        period: &VerificationPeriod,
+        metadata: &'a VerificationMetaDataList,
+        exclusion: &[String],
+        run_strategy: T,
+        config: &'static VerifierConfig,
+        action_before: impl Fn(&str) + Send + Sync + 'static,
+        action_after: impl Fn(&str, Vec<String>, Vec<String>) + Send + Sync + 'static
+    ) -> anyhow::Result<Runner<'a, T>> {
+        let version = detect_dataset_version(path, &source);
+        let mut verifications = Box::new(
+            VerificationSuite::new(period, metadata, exclusion, config)?
+        );
+        verifications.set_data_source(source);
+        verifications.set_dataset_version(version);
+        let journal = Arc::new(Mutex::new(RunJournal::new()));
+        let events = EventStream::new();
+        Ok(Runner {
             path: path.to_path_buf(),
-            verifications: Box::new(VerificationSuite::new(period, metadata, exclusion, config)),
+            verifications,
             start_time: None,
             duration: None,
             run_strategy,
             config,
             action_before: Box::new(action_before),
             action_after: Box::new(action_after),
-        }
+            observers: vec![events.clone()],
+            events,
+            report_post_processors: vec![],
+            partial_report: PartialReportHandle::new(journal.clone()),
+            journal,
+            checkpoint: None,
+            concurrent_modifications: vec![],
+            file_accesses: FileAccesses::new(),
+        })
+    }
+
+    /// Create a new runner, detecting the period from the dataset read from `source`
+    ///
+    /// See [VerificationPeriod::detect]. Use [Runner::new_with_source] if the period is known.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_detected_period(
+        path: &Path,
+        source: Arc<dyn DataSource>,
+        metadata: &'a VerificationMetaDataList,
+        exclusion: &[String],
+        run_strategy: T,
+        config: &'static VerifierConfig,
+        action_before: impl Fn(&str) + Send + Sync + 'static,
+        action_after: impl Fn(&str, Vec<String>, Vec<String>) + Send + Sync + 'static
+    ) -> anyhow::Result<Runner<'a, T>> {
+        let period = VerificationPeriod::detect(&source, path)?;
+        info!("Period {} detected from the dataset {:?}", period, path);
+        Self::new_with_source(
+            path,
+            source,
+            &period,
+            metadata,
+            exclusion,
+            run_strategy,
+            config,
+            action_before,
+            action_after
+        )
     }
 
     /// Reset the verifications
-    pub fn reset(&'a mut self, metadata_list: &'a VerificationMetaDataList) {
+    ///
+    /// The journal is not reset, so that the previous attempts remain in the history
+    pub fn reset(&'a mut self, metadata_list: &'a VerificationMetaDataList) -> anyhow::Result<()> {
         self.start_time = None;
         self.duration = None;
+        self.concurrent_modifications = vec![];
+        self.file_accesses = FileAccesses::new();
+        self.partial_report.clear();
+        let policy = self.execution_policy();
+        let source = self.verifications.data_source().clone();
+        let version = self.verifications.dataset_version();
         self.verifications = Box::new(
             VerificationSuite::new(
                 self.period(),
                 metadata_list,
                 self.verifications.exclusion(),
                 self.config
-            )
+            )?
         );
+        self.verifications.set_execution_policy(policy);
+        self.verifications.set_data_source(source);
+        self.verifications.set_dataset_version(version);
+        Ok(())
     }
 
     /// Run all tests
@@ -146,7 +468,15 @@ impl<'a, T> Runner<'a, T> where T: RunStrategy<'a> {
                 anyhow!(format!("Runner is already running. Cannot be started before resetting it"))
             );
         }
-        self.start_time = Some(SystemTime::now());
+        let start_time = SystemTime::now();
+        self.start_time = Some(start_time);
+        // Forget the modifications and the accesses registered before the run
+        take_concurrent_modifications();
+        take_file_accesses(&self.path);
+        self.partial_report.start(
+            RunReport::from_suite(&self.verifications, &self.path)
+                .with_relaxations(&self.config.strictness().relaxations())
+        );
         info!(
             "Start all verifications ({} verifications; {} excluded)",
             self.verifications.len(),
@@ -155,28 +485,313 @@ impl<'a, T> Runner<'a, T> where T: RunStrategy<'a> {
         for id in self.verifications.exclusion().iter() {
             warn!(
                 "Verification {} ({}) skipped",
-                metadata_list.meta_data_from_id(id).unwrap().name(),
+                metadata_list.meta_data_from_id(id).map_or("", |m| m.name()),
                 id
             );
         }
+        for r in self.config.strictness().relaxations().iter() {
+            warn!("Verification relaxed: {}", r);
+        }
+        self.events.send(RunnerEventKind::RunStarted {
+            period: *self.period(),
+            verifications: self.verifications.len(),
+            excluded: self.verifications.exclusion().clone(),
+        });
         let len = self.verifications.len();
+        let resumed = self.verifications.list.0.iter().filter(|v| v.is_finished()).count();
+        if resumed > 0 {
+            info!("{} verifications resumed from the checkpoint", resumed);
+        }
         {
+            let journal = &self.journal;
+            let checkpoint = &self.checkpoint;
+            let action_after = &self.action_after;
             self.run_strategy.run(
                 &mut self.verifications,
                 &self.path,
+                &self.observers,
                 &self.action_before,
-                &self.action_after
+                |id: &str, errors: Vec<String>, failures: Vec<String>| {
+                    if let Err(e) = journal
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .record(id, &errors, &failures)
+                    {
+                        warn!("{:#}", e);
+                    }
+                    if let Some(c) = checkpoint {
+                        let mut c = c.lock().unwrap_or_else(PoisonError::into_inner);
+                        if let Err(e) = c.record(id, &errors, &failures) {
+                            warn!("{:#}", e);
+                        }
+                    }
+                    action_after(id, errors, failures)
+                }
+            );
+        }
+        let duration = start_time.elapsed().unwrap_or_default();
+        self.duration = Some(duration);
+        info!(
+            "{} verifications run (duration: {})",
+            len - resumed,
+            HumanDuration(duration)
+        );
+        self.concurrent_modifications = take_concurrent_modifications();
+        self.file_accesses = take_file_accesses(&self.path);
+        for m in self.concurrent_modifications.iter() {
+            warn!("The dataset was modified during the verification: {}", m);
+        }
+        if let Some(v) = self.verifications.stopped_by() {
+            warn!(
+                "Run stopped after the errors of verification {} ({}): {} verifications not run",
+                v.meta_data().name(),
+                v.id(),
+                self.verifications.list.0.iter().filter(|v| !v.is_finished()).count()
             );
         }
-        self.duration = Some(self.start_time.unwrap().elapsed().unwrap());
-        info!("{} verifications run (duration: {}s)", &len, self.duration.unwrap().as_secs_f32());
+        let journal = self.journal.lock().unwrap_or_else(PoisonError::into_inner);
+        for id in journal.rerun_ids() {
+            info!(
+                "Verification {} run {} times: {}",
+                id,
+                journal.history(&id).len(),
+                journal.history_to_string(&id)
+            );
+        }
+        self.events.send(RunnerEventKind::RunFinished {
+            duration,
+            stopped_by: self.stopped_by(),
+        });
         None
     }
 
+    /// Run only the verification with the given id (e.g. `05.04`) and return its result
+    ///
+    /// The attempt is recorded in the journal and the failed items are exported, like in
+    /// [Runner::run_all]. The state of the runner (start time, duration) is not changed.
+    ///
+    /// # Error
+    /// If the runner is running, or if the verification is not in the suite (see
+    /// [VerificationSuite::run_single])
+    pub fn run_single(&mut self, id: &str) -> anyhow::Result<&VerificationResult> {
+        if self.is_running() {
+            return Err(anyhow!("Runner is already running. Cannot run {}", id));
+        }
+        let directories = self.verifications.verification_directories(&self.path);
+        if self.verifications.find_by_id(id).is_some() {
+            (self.action_before)(id);
+            self.observers.on_start(id);
+        }
+        self.verifications.run_single(id, &directories)?;
+        let v = self
+            .verifications
+            .find_by_id(id)
+            .ok_or_else(|| anyhow!("Verification {} not found after its run", id))?;
+        self.observers.on_finished(id, v.result());
+        export_failed_items(v);
+        self.journal
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record(id, &v.errors_to_string(), &v.failures_to_string())?;
+        (self.action_after)(id, v.errors_to_string(), v.failures_to_string());
+        Ok(v.result())
+    }
+
+    /// Subscribe to the events of the runner (see [RunnerEvent])
+    ///
+    /// The events of the run, of the verifications and the log records are received through
+    /// the channel, until the receiver is dropped. The subscription is kept by [Runner::reset]
+    pub fn subscribe(&self) -> Receiver<RunnerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Add an observer notified at the start and at the end of each verification
+    ///
+    /// The observers are kept by [Runner::reset]. They must be added before running the
+    /// verifications
+    pub fn add_observer(&mut self, observer: Arc<dyn RunObserver>) {
+        self.observers.push(observer)
+    }
+
+    /// Run only the selected verifications (see [VerificationSuite::select])
+    ///
+    /// The verifications not selected are excluded, so that the selection is kept by
+    /// [Runner::reset]
+    ///
+    /// # Error
+    /// If the selection contains unknown ids, ids of the other period or of verifications not
+    /// implemented, or if it selects no verification
+    pub fn select(&mut self, selection: &VerificationSelection) -> anyhow::Result<()> {
+        let metadata = self.config.verification_metadata()?;
+        self.verifications.select(selection, metadata)?;
+        Ok(())
+    }
+
+    /// Add a post-processor of the report (see [ReportPostProcessor])
+    ///
+    /// The post-processors are called by [Runner::report] in the order of their registration.
+    /// They are kept by [Runner::reset]
+    pub fn add_report_post_processor(&mut self, post_processor: Arc<dyn ReportPostProcessor>) {
+        self.report_post_processors.push(post_processor)
+    }
+
+    /// Set the execution policy of the run (see [ExecutionPolicy])
+    ///
+    /// With [ExecutionPolicy::FailFast], the run stops after the first verification with
+    /// errors, e.g. to abort the expensive verifications if the dataset is obviously broken.
+    /// The policy is kept by [Runner::reset]
+    pub fn set_execution_policy(&mut self, policy: ExecutionPolicy) {
+        self.verifications.set_execution_policy(policy)
+    }
+
+    /// Execution policy of the run
+    pub fn execution_policy(&self) -> ExecutionPolicy {
+        self.verifications.execution_policy()
+    }
+
+    /// Id of the verification whose errors stopped the run (see [ExecutionPolicy::FailFast])
+    ///
+    /// `None` if the run was not stopped
+    pub fn stopped_by(&self) -> Option<String> {
+        self.verifications.stopped_by().map(|v| v.id().clone())
+    }
+
+    /// The whole verification directory, read from the source of the runner
+    pub fn verification_directory(&self) -> VerificationDirectory {
+        VerificationDirectory::new_with_source(
+            self.period(),
+            &self.path,
+            self.verifications.data_source().clone()
+        )
+    }
+
+    /// Verify only the VCS directories with the given names (partial delivery)
+    pub fn set_vcs_selection(&mut self, names: Vec<String>) {
+        self.verifications.set_vcs_selection(names)
+    }
+
+    /// Verify only the ballot box directories with the given names (incremental verification
+    /// of the tally, see [super::IncrementalTallyState])
+    pub fn set_bb_selection(&mut self, names: Vec<String>) {
+        self.verifications.set_bb_selection(names)
+    }
+
+    /// Keep the journal in the given log file
+    ///
+    /// The existing records of the file are loaded and verified (see [RunJournal::open]). The
+    /// journal must be set before running the verifications
+    pub fn set_journal_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        // The journal is replaced in place, since it is shared with the partial reports
+        *self.journal.lock().unwrap_or_else(PoisonError::into_inner) = RunJournal::open(path)?;
+        Ok(())
+    }
+
+    /// Persist the result of each verification in the checkpoint file `path`
+    ///
+    /// If `resume` is true, the verifications completed in the existing checkpoint are restored
+    /// and are not run again (see [Checkpoint::resume]). Else a new checkpoint is created. The
+    /// checkpoint must be set before running the verifications.
+    ///
+    /// Return the number of restored verifications
+    ///
+    /// # Error
+    /// If the checkpoint cannot be created or read, or if it was created for another period or
+    /// another dataset
+    pub fn set_checkpoint_file(&mut self, path: &Path, resume: bool) -> anyhow::Result<usize> {
+        if !self.can_be_started() {
+            return Err(anyhow!("The checkpoint must be set before running the verifications"));
+        }
+        let header = CheckpointHeader::new(&self.period().to_string(), &self.path);
+        let checkpoint = match resume {
+            true => Checkpoint::resume(path, header)?,
+            false => Checkpoint::create(path, header)?,
+        };
+        let mut restored = 0;
+        for v in self.verifications.list.0.iter_mut() {
+            if let Some(e) = checkpoint.entry(v.id()) {
+                v.restore(&e.errors, &e.failures);
+                restored += 1;
+            }
+        }
+        self.checkpoint = Some(Arc::new(Mutex::new(checkpoint)));
+        Ok(restored)
+    }
+
+    /// Modifications of the dataset detected during the run
+    ///
+    /// The results of the verifications are not reliable if the list is not empty
+    pub fn concurrent_modifications(&self) -> &[String] {
+        &self.concurrent_modifications
+    }
+
+    /// Copy of the journal containing all the attempts since the creation of the runner
+    pub fn journal(&self) -> RunJournal {
+        self.journal.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Report of the run of the verifications, with the data of the manual verifications and the
+    /// statistics of the verification card sets (see [RunReport])
+    ///
+    /// The report is post-processed with the registered post-processors (see
+    /// [Runner::add_report_post_processor])
+    pub fn report(&self) -> RunReport {
+        let dir = self.verification_directory();
+        RunReport::from_suite(&self.verifications, &self.path)
+            .with_duration(self.duration())
+            .with_vcs_statistics(vcs_statistics(&dir))
+            .with_concurrent_modifications(&self.concurrent_modifications)
+            .with_journal_head(self.journal.lock().unwrap_or_else(PoisonError::into_inner).head())
+            .with_relaxations(&self.config.strictness().relaxations())
+            .with_manual_checks(self.manual_checks())
+            .post_process(&self.verifications, &self.report_post_processors)
+    }
+
+    /// Handle to create partial reports during the run (see [PartialReportHandle])
+    ///
+    /// The handle must be taken before running the verifications, since the runner is borrowed
+    /// during the run. It remains valid after [Runner::reset]
+    pub fn partial_report_handle(&self) -> PartialReportHandle {
+        self.partial_report.clone()
+    }
+
+    /// Data of the manual verifications, extracted from the dataset (see [extract_manual_checks])
+    pub fn manual_checks(&self) -> Vec<ManualCheck> {
+        extract_manual_checks(
+            &self.verification_directory(),
+            self.period()
+        )
+    }
+
+    /// Report of the verifications per file of the dataset (see [FileAccessReport])
+    pub fn file_access_report(&self) -> FileAccessReport {
+        let files: Vec<PathBuf> = self
+            .verification_directory()
+            .files()
+            .iter()
+            .map(|f| f.get_path())
+            .collect();
+        FileAccessReport::new(
+            &self.path,
+            &self.file_accesses,
+            &self.report().verifications,
+            &files
+        )
+    }
+
+    /// Query the results of the verifications (see [ResultQuery])
+    pub fn query(&self, query: &ResultQuery) -> Vec<ResultView<'_, 'a>> {
+        self.verifications.query(query)
+    }
+
     pub fn verifications_mut(&'a mut self) -> &'a mut VerificationSuite<'a> {
         &mut self.verifications
     }
 
+    /// Duration of the run. `None` if not finished
+    pub fn duration(&self) -> Option<HumanDuration> {
+        self.duration.map(HumanDuration)
+    }
+
     pub fn is_finished(&self) -> bool {
         self.duration.is_some()
     }
@@ -193,3 +808,166 @@ impl<'a, T> Runner<'a, T> where T: RunStrategy<'a> {
         self.verifications.period()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        application_runner::report::VerificationReport,
+        config::test::{ test_dataset_setup_path, CONFIG_TEST },
+        verification::{
+            meta_data::VerificationImpact,
+            query::ResultStatus,
+            result::{ create_verification_error, VerificationEvent },
+            suite::VerificationList,
+            VerificationContext,
+        },
+    };
+    use log::debug;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_category_parallelism() {
+        let parallelism = CategoryParallelism::new(8)
+            .with_limit(VerificationCategory::Completness, 2)
+            .with_limit(VerificationCategory::Integrity, 0);
+        assert_eq!(parallelism.limit(&VerificationCategory::Completness), 2);
+        assert_eq!(parallelism.limit(&VerificationCategory::Integrity), 1);
+        assert_eq!(parallelism.limit(&VerificationCategory::Evidence), 8);
+        assert_eq!(CategoryParallelism::new(0).limit(&VerificationCategory::Evidence), 1);
+    }
+
+    #[test]
+    fn test_partial_report() {
+        let journal = Arc::new(Mutex::new(RunJournal::new()));
+        journal.lock().unwrap().record("01.01", &[], &["before the run".to_string()]);
+        let handle = PartialReportHandle::new(journal.clone());
+        assert!(handle.report().is_none());
+        let verification = |id: &str| VerificationReport {
+            id: id.to_string(),
+            name: id.to_string(),
+            category: "completness".to_string(),
+            impact: VerificationImpact::Blocking,
+            status: ResultStatus::NotRun,
+            duration: None,
+            memory: None,
+            errors: vec![],
+            failures: vec![],
+            sub_results: vec![],
+            entities: vec![],
+            codes: BTreeMap::new(),
+        };
+        handle.start(
+            RunReport::new(
+                &VerificationPeriod::Setup,
+                Path::new("."),
+                vec![verification("01.01"), verification("X.01.01")]
+            )
+        );
+        let report = handle.report().unwrap();
+        assert!(report.partial);
+        assert_eq!(report.summary.not_run, 2);
+        journal.lock().unwrap().record("X.01.01", &[], &["failure".to_string()]);
+        let report = handle.report().unwrap();
+        assert_eq!(report.summary.not_run, 1);
+        assert_eq!(report.summary.failures, 1);
+        assert_eq!(report.verifications[1].failures, vec!["failure".to_string()]);
+        assert!(report.to_text().contains("Partial report: the run is not finished"));
+        handle.clear();
+        assert!(handle.report().is_none());
+    }
+
+    #[test]
+    fn test_category_parallelism_default() {
+        let parallelism = CategoryParallelism::default();
+        assert_eq!(
+            parallelism.limit(&VerificationCategory::Evidence),
+            rayon::current_num_threads()
+        );
+    }
+
+    fn error(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+        let mut result = VerificationResult::new();
+        result.push(create_verification_error!("Cannot read the file"));
+        result
+    }
+
+    fn ok(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+        VerificationResult::new()
+    }
+
+    /// Ids of the verifications run by the strategy with the given policy
+    ///
+    /// The first verification has errors
+    fn run_ids<'a>(
+        strategy: impl RunStrategy<'a>,
+        suite: &'a mut VerificationSuite<'a>,
+        metadata: &'a VerificationMetaDataList,
+        policy: ExecutionPolicy
+    ) -> Vec<String> {
+        suite.list = Box::new(
+            VerificationList(
+                vec![
+                    Verification::new(
+                        "01.01",
+                        "VerifySetupCompleteness",
+                        error,
+                        metadata,
+                        &CONFIG_TEST
+                    ).unwrap(),
+                    Verification::new(
+                        "X.01.01",
+                        "VerifyVerificationDataCoverCards",
+                        ok,
+                        metadata,
+                        &CONFIG_TEST
+                    ).unwrap()
+                ]
+            )
+        );
+        suite.set_execution_policy(policy);
+        let ids = Mutex::new(vec![]);
+        strategy.run(
+            suite,
+            &test_dataset_setup_path(),
+            &Vec::<Arc<dyn RunObserver>>::new(),
+            no_action_before_fn,
+            |id: &str, _, _| ids.lock().unwrap().push(id.to_string())
+        );
+        ids.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_fail_fast() {
+        let metadata = VerificationMetaDataList::load(
+            CONFIG_TEST.get_verification_list_str()
+        ).unwrap();
+        let new_suite = || {
+            VerificationSuite::new(
+                &VerificationPeriod::Setup,
+                &metadata,
+                &[],
+                &CONFIG_TEST
+            ).unwrap()
+        };
+        let (mut s1, mut s2, mut s3, mut s4) = (new_suite(), new_suite(), new_suite(), new_suite());
+        assert_eq!(
+            run_ids(RunSequential, &mut s1, &metadata, ExecutionPolicy::Continue),
+            vec!["01.01", "X.01.01"]
+        );
+        assert_eq!(
+            run_ids(RunSequential, &mut s2, &metadata, ExecutionPolicy::FailFast),
+            vec!["01.01"]
+        );
+        // One thread, so that the verifications of the category run in order
+        let parallel = || RunParallel::new(CategoryParallelism::new(1));
+        assert_eq!(
+            run_ids(parallel(), &mut s3, &metadata, ExecutionPolicy::Continue),
+            vec!["01.01", "X.01.01"]
+        );
+        assert_eq!(
+            run_ids(parallel(), &mut s4, &metadata, ExecutionPolicy::FailFast),
+            vec!["01.01"]
+        );
+    }
+}
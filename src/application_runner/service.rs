@@ -0,0 +1,901 @@
+//! Module implementing the verifier as long-running service
+//!
+//! The service (feature `service`) listens on a local TCP socket and exposes the verifier with
+//! JSON-RPC 2.0, so that an application not written in Rust (e.g. the GUI) can use the
+//! verifier over a stable protocol. Each request, response and notification is a json object on
+//! one line.
+//!
+//! The socket is reachable by all the users of the machine. The service is created with a token
+//! (e.g. generated by the application starting the service), and the first request of each
+//! connection must be the method `authenticate` with this token. A connection failing to
+//! authenticate is closed.
+//!
+//! # Methods
+//! | Method | Parameters | Result |
+//! | ------ | ---------- | ------ |
+//! | `authenticate` | `token` | `null` |
+//! | `verifications` | `period` (optional) | List of the verifications (see [MetaDataEntry]) |
+//! | `open_dataset` | `dataset` | Period, version and unrecognized files of the dataset |
+//! | `start` | `dataset`, `period` (optional), `exclusion` (optional) | Id of the run (`run`) |
+//! | `progress` | `run`, `from` (optional) | State of the run and events since the event `from` |
+//! | `subscribe` | `run`, `from` (optional) | State of the run at the end of the run |
+//! | `report` | `run` | Report of the run (see [super::RunReport]) |
+//! | `shutdown` | | Stop the service |
+//!
+//! The dataset is a directory or a ZIP archive (not encrypted) under the root of the datasets of
+//! the service. A relative path is relative to this root, and a path outside of it is rejected.
+//! Its layout is normalized (see [crate::file_structure::normalization]). If the period is not
+//! given, it is detected from the dataset. Only one run can be executed at a time, since the
+//! statistics of the file accesses are collected for the whole process.
+//!
+//! The XML files are always validated with the schemas embedded in the verifier: a run is not
+//! started if a schema is overridden in the process (see [super::override_schema]).
+//!
+//! With `subscribe`, the events of the run (see [super::RunnerEvent]) are sent as notifications
+//! `event` with the parameters `run` and `event`, until the end of the run. The response is sent
+//! after the last event. With `progress`, the client polls the events, giving the number of the
+//! first event to fetch (`next` of the previous response). The report of a run not finished is a
+//! partial report (see [super::PartialReportHandle]).
+
+use super::{
+    check_verification_dir,
+    events::{RunnerEvent, RunnerEventKind},
+    no_action_after_fn, no_action_before_fn, PartialReportHandle, RunParallel, Runner,
+};
+use crate::{
+    config::Config as VerifierConfig,
+    data_structures::{dataset_version::DatasetVersion, schema_resources, SchemaVersion},
+    file_structure::{
+        data_source::local_source,
+        normalization::NormalizedDataset,
+        zip_archive::{is_zip_archive, ZipDataset},
+    },
+    verification::{
+        meta_data::{MetaDataEntry, MetaDataQuery},
+        suite::query_verification_metadata,
+        VerificationPeriod,
+    },
+};
+use anyhow::anyhow;
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+const JSONRPC_VERSION: &str = "2.0";
+
+/// Error codes of JSON-RPC
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Error of the verifier (e.g. the dataset cannot be read)
+const SERVER_ERROR: i64 = -32000;
+/// The connection is not authenticated
+const UNAUTHORIZED: i64 = -32001;
+
+/// Interval to check the new events of a run for the subscriptions
+const SUBSCRIPTION_INTERVAL: Duration = Duration::from_millis(100);
+
+/// State of a run of the service
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunState {
+    #[default]
+    Running,
+    /// The run is finished. The failures of the verifications are given in the report
+    Finished,
+    /// The run cannot be executed (e.g. the dataset is not valid)
+    Failed,
+}
+
+/// Data of a run, updated by the thread of the run
+#[derive(Default)]
+struct RunData {
+    state: RunState,
+    /// Events of the run as json (see [event_to_json])
+    events: Vec<Value>,
+    partial: Option<PartialReportHandle>,
+    report: Option<Value>,
+    error: Option<String>,
+}
+
+/// Error returned in the response
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl std::fmt::Display) -> Self {
+        Self {
+            code,
+            message: format!("{:#}", message),
+        }
+    }
+}
+
+/// Service of the verifier
+pub struct VerifierService {
+    config: &'static VerifierConfig,
+    token: String,
+    dataset_root: PathBuf,
+    runs: Mutex<Vec<Arc<Mutex<RunData>>>>,
+    stopped: AtomicBool,
+}
+
+/// Bind a listener on the local machine (`127.0.0.1`). With the port 0, a free port is taken
+pub fn bind_local(port: u16) -> anyhow::Result<TcpListener> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpListener::bind(addr).map_err(|e| anyhow!(e).context(format!("Cannot bind to {}", addr)))
+}
+
+/// Event as json object, with the kind of the event in the field `kind`
+fn event_to_json(event: &RunnerEvent) -> Value {
+    let timestamp = event.timestamp.format("%Y-%m-%dT%H:%M:%S%.3f").to_string();
+    match &event.kind {
+        RunnerEventKind::RunStarted {
+            period,
+            verifications,
+            excluded,
+        } => json!({
+            "timestamp": timestamp,
+            "kind": "run_started",
+            "period": period.to_string(),
+            "verifications": verifications,
+            "excluded": excluded,
+        }),
+        RunnerEventKind::VerificationStarted { id } => json!({
+            "timestamp": timestamp,
+            "kind": "verification_started",
+            "id": id,
+        }),
+        RunnerEventKind::VerificationFinished {
+            id,
+            status,
+            errors,
+            failures,
+        } => json!({
+            "timestamp": timestamp,
+            "kind": "verification_finished",
+            "id": id,
+            "status": status,
+            "errors": errors,
+            "failures": failures,
+        }),
+        RunnerEventKind::Log { level, message } => json!({
+            "timestamp": timestamp,
+            "kind": "log",
+            "level": level.to_string(),
+            "message": message,
+        }),
+        RunnerEventKind::HashProgress {
+            file,
+            processed,
+            total,
+        } => json!({
+            "timestamp": timestamp,
+            "kind": "hash_progress",
+            "file": file,
+            "processed": processed,
+            "total": total,
+        }),
+        RunnerEventKind::RunFinished {
+            duration,
+            stopped_by,
+        } => json!({
+            "timestamp": timestamp,
+            "kind": "run_finished",
+            "duration_ms": duration.as_millis() as u64,
+            "stopped_by": stopped_by,
+        }),
+    }
+}
+
+/// Optional string parameter
+fn str_param<'a>(params: &'a Value, name: &str) -> Result<Option<&'a str>, RpcError> {
+    match params.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("The parameter {} must be a string", name),
+        )),
+    }
+}
+
+fn mandatory_str_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    str_param(params, name)?
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("The parameter {} is missing", name)))
+}
+
+/// Optional number parameter
+fn usize_param(params: &Value, name: &str) -> Result<Option<usize>, RpcError> {
+    match params.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => v.as_u64().map(|n| Some(n as usize)).ok_or_else(|| {
+            RpcError::new(
+                INVALID_PARAMS,
+                format!("The parameter {} must be a positive integer", name),
+            )
+        }),
+    }
+}
+
+/// Compare the tokens in a time not depending on the position of the first difference
+fn token_eq(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn period_param(params: &Value) -> Result<Option<VerificationPeriod>, RpcError> {
+    str_param(params, "period")?
+        .map(|p| VerificationPeriod::try_from(p).map_err(|e| RpcError::new(INVALID_PARAMS, e)))
+        .transpose()
+}
+
+/// Open the dataset, extracting the ZIP archive in memory if necessary and normalizing its
+/// layout
+///
+/// The files of the dataset are read from the source of the normalized dataset
+fn open_dataset(dataset: &Path) -> anyhow::Result<NormalizedDataset> {
+    let normalized = match is_zip_archive(dataset) {
+        true => {
+            let zip = ZipDataset::open(dataset, None)?;
+            NormalizedDataset::open(zip.root(), zip.source())
+        }
+        false => NormalizedDataset::open(dataset, local_source()),
+    };
+    normalized.report().log();
+    Ok(normalized)
+}
+
+/// Execute the run and return the report as json
+///
+/// The events and the handle of the partial report are given in `data`
+fn execute_run(
+    dataset: &Path,
+    period: Option<&VerificationPeriod>,
+    exclusion: &[String],
+    config: &'static VerifierConfig,
+    data: &Arc<Mutex<RunData>>,
+) -> anyhow::Result<Value> {
+    let normalized = open_dataset(dataset)?;
+    let dir = normalized.root().to_path_buf();
+    if let Some(p) = period {
+        check_verification_dir(p, normalized.source().as_ref(), &dir)?;
+    }
+    let metadata = config.verification_metadata()?;
+    let (res, collector) = {
+        let mut runner = match period {
+            Some(p) => Runner::new_with_source(
+                &dir,
+                normalized.source(),
+                p,
+                metadata,
+                exclusion,
+                RunParallel::default(),
+                config,
+                no_action_before_fn,
+                no_action_after_fn,
+            )?,
+            None => Runner::new_with_detected_period(
+                &dir,
+                normalized.source(),
+                metadata,
+                exclusion,
+                RunParallel::default(),
+                config,
+                no_action_before_fn,
+                no_action_after_fn,
+            )?,
+        };
+        // The events are received until the runner is dropped
+        let events = runner.subscribe();
+        let events_data = data.clone();
+        let collector = thread::spawn(move || {
+            for e in events.iter() {
+                events_data
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .events
+                    .push(event_to_json(&e));
+            }
+        });
+        data.lock().unwrap_or_else(PoisonError::into_inner).partial =
+            Some(runner.partial_report_handle());
+        let res = match runner.run_all(metadata) {
+            Some(e) => Err(e),
+            None => serde_json::to_value(runner.report()).map_err(|e| anyhow!(e)),
+        };
+        (res, collector)
+    };
+    let _ = collector.join();
+    res
+}
+
+impl VerifierService {
+    /// New service with the configuration of the verifier
+    ///
+    /// `token` authenticates the connections. Only the datasets under `dataset_root` can be
+    /// opened and verified
+    ///
+    /// # Error
+    /// If the token is empty or if the root of the datasets is not a directory
+    pub fn new(
+        config: &'static VerifierConfig,
+        token: &str,
+        dataset_root: &Path,
+    ) -> anyhow::Result<Arc<Self>> {
+        if token.is_empty() {
+            return Err(anyhow!("The token of the service cannot be empty"));
+        }
+        let dataset_root = dataset_root.canonicalize().map_err(|e| {
+            anyhow!(e).context(format!("Root of the datasets {:?} not valid", dataset_root))
+        })?;
+        if !dataset_root.is_dir() {
+            return Err(anyhow!(
+                "The root of the datasets {:?} is not a directory",
+                dataset_root
+            ));
+        }
+        Ok(Arc::new(Self {
+            config,
+            token: token.to_string(),
+            dataset_root,
+            runs: Mutex::new(vec![]),
+            stopped: AtomicBool::new(false),
+        }))
+    }
+
+    /// The service is stopped (method `shutdown`)
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Serve the connections of the listener until the service is stopped
+    ///
+    /// Each connection is served in its own thread
+    pub fn serve(self: &Arc<Self>, listener: TcpListener) -> anyhow::Result<()> {
+        let addr = listener.local_addr()?;
+        info!("Verifier service listening on {}", addr);
+        for stream in listener.incoming() {
+            if self.is_stopped() {
+                break;
+            }
+            match stream {
+                Ok(s) => {
+                    let service = self.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = service.handle_connection(s, addr) {
+                            warn!("Connection to the service closed: {:#}", e)
+                        }
+                    });
+                }
+                Err(e) => warn!("Connection to the service failed: {}", e),
+            }
+        }
+        info!("Verifier service stopped");
+        Ok(())
+    }
+
+    /// Handle the requests of the connection until it is closed
+    ///
+    /// The first request must authenticate the connection (see [VerifierService::authenticate]).
+    /// `addr` is the address of the listener, to wake it up after the shutdown
+    fn handle_connection(
+        self: &Arc<Self>,
+        stream: TcpStream,
+        addr: SocketAddr,
+    ) -> anyhow::Result<()> {
+        let reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        let mut authenticated = false;
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !authenticated {
+                let response = self.authenticate(&line);
+                write_line(&mut writer, &response)?;
+                match response.get("error") {
+                    Some(e) => return Err(anyhow!("Authentication failed: {}", e["message"])),
+                    None => authenticated = true,
+                }
+                continue;
+            }
+            let response = {
+                let mut notify = |v: &Value| write_line(&mut writer, v);
+                self.handle_request(&line, &mut notify)
+            };
+            write_line(&mut writer, &response)?;
+            if self.is_stopped() {
+                // The listener is blocked until the next connection
+                let _ = TcpStream::connect(addr);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle the request authenticating a connection (method `authenticate`) and return the
+    /// response
+    ///
+    /// Every other request, or a wrong token, gives an error
+    fn authenticate(&self, request: &str) -> Value {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(v) => v,
+            Err(e) => return error_response(Value::Null, RpcError::new(PARSE_ERROR, e)),
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        if request.get("method").and_then(|m| m.as_str()) != Some("authenticate") {
+            return error_response(
+                id,
+                RpcError::new(UNAUTHORIZED, "The connection must be authenticated first"),
+            );
+        }
+        let params = request.get("params").unwrap_or(&Value::Null);
+        match mandatory_str_param(params, "token") {
+            Ok(t) if token_eq(&self.token, t) => {
+                json!({"jsonrpc": JSONRPC_VERSION, "id": id, "result": Value::Null})
+            }
+            Ok(_) => error_response(id, RpcError::new(UNAUTHORIZED, "Wrong token")),
+            Err(e) => error_response(id, e),
+        }
+    }
+
+    /// Handle the request (a JSON-RPC request as string) and return the response
+    ///
+    /// The notifications (method `subscribe`) are sent with `notify`. The request is not
+    /// authenticated: the function is the interface for the application embedding the service
+    pub fn handle_request(
+        self: &Arc<Self>,
+        request: &str,
+        notify: &mut dyn FnMut(&Value) -> std::io::Result<()>,
+    ) -> Value {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(v) => v,
+            Err(e) => return error_response(Value::Null, RpcError::new(PARSE_ERROR, e)),
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = match request.get("method").and_then(|m| m.as_str()) {
+            Some(m) => m,
+            None => {
+                return error_response(id, RpcError::new(INVALID_REQUEST, "The method is missing"))
+            }
+        };
+        let params = request.get("params").unwrap_or(&Value::Null);
+        let res = match method {
+            "verifications" => self.verifications(params),
+            "open_dataset" => self.dataset_info(params),
+            "start" => self.start(params),
+            "progress" => self.progress(params),
+            "subscribe" => self.subscribe(params, notify),
+            "report" => self.report(params),
+            "shutdown" => {
+                info!("Shutdown of the verifier service requested");
+                self.stopped.store(true, Ordering::SeqCst);
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method {}", method),
+            )),
+        };
+        match res {
+            Ok(r) => json!({"jsonrpc": JSONRPC_VERSION, "id": id, "result": r}),
+            Err(e) => error_response(id, e),
+        }
+    }
+
+    fn verifications(&self, params: &Value) -> Result<Value, RpcError> {
+        let mut query = MetaDataQuery::new();
+        if let Some(p) = period_param(params)? {
+            query = query.with_period(p);
+        }
+        let entries: Vec<MetaDataEntry> = query_verification_metadata(&query, &[], self.config)
+            .map_err(|e| RpcError::new(SERVER_ERROR, e))?;
+        serde_json::to_value(entries).map_err(|e| RpcError::new(SERVER_ERROR, e))
+    }
+
+    /// Path of the dataset given by the parameter `dataset`
+    ///
+    /// A relative path is relative to the root of the datasets. Error if the dataset does not
+    /// exist or if it is not under the root of the datasets
+    fn dataset_path(&self, params: &Value) -> Result<PathBuf, RpcError> {
+        let dataset = mandatory_str_param(params, "dataset")?;
+        let path = self
+            .dataset_root
+            .join(dataset)
+            .canonicalize()
+            .map_err(|e| {
+                RpcError::new(
+                    INVALID_PARAMS,
+                    anyhow!(e).context(format!("The dataset {} cannot be found", dataset)),
+                )
+            })?;
+        match path.starts_with(&self.dataset_root) {
+            true => Ok(path),
+            false => Err(RpcError::new(
+                INVALID_PARAMS,
+                format!(
+                    "The dataset {} is not under the root of the datasets of the service",
+                    dataset
+                ),
+            )),
+        }
+    }
+
+    fn dataset_info(&self, params: &Value) -> Result<Value, RpcError> {
+        let dataset = self.dataset_path(params)?;
+        let normalized = open_dataset(&dataset).map_err(|e| RpcError::new(SERVER_ERROR, e))?;
+        let (dir, source) = (normalized.root(), normalized.source());
+        let period =
+            VerificationPeriod::detect(&source, dir).map_err(|e| RpcError::new(SERVER_ERROR, e))?;
+        let version =
+            DatasetVersion::detect(&source, dir).map_err(|e| RpcError::new(SERVER_ERROR, e))?;
+        Ok(json!({
+            "period": period.to_string(),
+            "version": version.to_string(),
+            "unrecognized_files": normalized.report().unrecognized_files,
+        }))
+    }
+
+    fn start(&self, params: &Value) -> Result<Value, RpcError> {
+        let dataset = self.dataset_path(params)?;
+        let period = period_param(params)?;
+        let exclusion: Vec<String> = match params.get("exclusion") {
+            None | Some(Value::Null) => vec![],
+            Some(v) => serde_json::from_value(v.clone()).map_err(|e| {
+                RpcError::new(
+                    INVALID_PARAMS,
+                    anyhow!(e).context("The exclusion must be an array of ids"),
+                )
+            })?,
+        };
+        let mut runs = self.runs.lock().unwrap_or_else(PoisonError::into_inner);
+        if runs
+            .iter()
+            .any(|r| r.lock().unwrap_or_else(PoisonError::into_inner).state == RunState::Running)
+        {
+            return Err(RpcError::new(SERVER_ERROR, "A run is already running"));
+        }
+        if let Some(s) = [SchemaVersion::V1_2, SchemaVersion::V1_3]
+            .into_iter()
+            .flat_map(schema_resources)
+            .find(|s| s.overridden_by.is_some())
+        {
+            return Err(RpcError::new(
+                SERVER_ERROR,
+                format!(
+                    "The schema {} is overridden. Only the embedded schemas can be used",
+                    s.name
+                ),
+            ));
+        }
+        let data = Arc::new(Mutex::new(RunData::default()));
+        runs.push(data.clone());
+        let run = runs.len() - 1;
+        info!("Start of the run {} for the dataset {:?}", run, dataset);
+        let config = self.config;
+        thread::spawn(move || {
+            let res = execute_run(&dataset, period.as_ref(), &exclusion, config, &data);
+            let mut data = data.lock().unwrap_or_else(PoisonError::into_inner);
+            match res {
+                Ok(report) => {
+                    data.report = Some(report);
+                    data.state = RunState::Finished;
+                }
+                Err(e) => {
+                    warn!("The run {} failed: {:#}", run, e);
+                    data.error = Some(format!("{:#}", e));
+                    data.state = RunState::Failed;
+                }
+            }
+        });
+        Ok(json!({ "run": run }))
+    }
+
+    /// Run given by the parameter `run`
+    fn run(&self, params: &Value) -> Result<(usize, Arc<Mutex<RunData>>), RpcError> {
+        let run = usize_param(params, "run")?
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "The parameter run is missing"))?;
+        self.runs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(run)
+            .map(|r| (run, r.clone()))
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Unknown run {}", run)))
+    }
+
+    fn progress(&self, params: &Value) -> Result<Value, RpcError> {
+        let (_, run) = self.run(params)?;
+        let from = usize_param(params, "from")?.unwrap_or(0);
+        let data = run.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(json!({
+            "state": data.state,
+            "events": data.events.get(from..).unwrap_or(&[]),
+            "next": data.events.len(),
+            "error": data.error,
+        }))
+    }
+
+    fn subscribe(
+        &self,
+        params: &Value,
+        notify: &mut dyn FnMut(&Value) -> std::io::Result<()>,
+    ) -> Result<Value, RpcError> {
+        let (id, run) = self.run(params)?;
+        let mut next = usize_param(params, "from")?.unwrap_or(0);
+        loop {
+            // The events are complete when the state is not running
+            let (events, state, error) = {
+                let data = run.lock().unwrap_or_else(PoisonError::into_inner);
+                (
+                    data.events.get(next..).unwrap_or(&[]).to_vec(),
+                    data.state,
+                    data.error.clone(),
+                )
+            };
+            for e in events {
+                next += 1;
+                notify(&json!({
+                    "jsonrpc": JSONRPC_VERSION,
+                    "method": "event",
+                    "params": {"run": id, "event": e},
+                }))
+                .map_err(|e| RpcError::new(SERVER_ERROR, e))?;
+            }
+            if state != RunState::Running {
+                return Ok(json!({"state": state, "next": next, "error": error}));
+            }
+            thread::sleep(SUBSCRIPTION_INTERVAL);
+        }
+    }
+
+    fn report(&self, params: &Value) -> Result<Value, RpcError> {
+        let (id, run) = self.run(params)?;
+        let data = run.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(r) = &data.report {
+            return Ok(r.clone());
+        }
+        if let Some(e) = &data.error {
+            return Err(RpcError::new(
+                SERVER_ERROR,
+                format!("The run {} failed: {}", id, e),
+            ));
+        }
+        match data.partial.as_ref().and_then(|h| h.report()) {
+            Some(r) => serde_json::to_value(r).map_err(|e| RpcError::new(SERVER_ERROR, e)),
+            None => Ok(Value::Null),
+        }
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": JSONRPC_VERSION,
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+fn write_line(writer: &mut impl Write, value: &Value) -> std::io::Result<()> {
+    writeln!(writer, "{}", value)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::test::{test_datasets_path, CONFIG_TEST};
+
+    const TOKEN: &str = "test-token";
+
+    fn service() -> Arc<VerifierService> {
+        VerifierService::new(&CONFIG_TEST, TOKEN, &test_datasets_path()).unwrap()
+    }
+
+    fn request(service: &Arc<VerifierService>, request: Value) -> Value {
+        service.handle_request(&request.to_string(), &mut |_| Ok(()))
+    }
+
+    #[test]
+    fn test_errors() {
+        let service = service();
+        let res = service.handle_request("{toto", &mut |_| Ok(()));
+        assert_eq!(res["error"]["code"], PARSE_ERROR);
+        let res = request(
+            &service,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "toto"}),
+        );
+        assert_eq!(res["id"], 1);
+        assert_eq!(res["error"]["code"], METHOD_NOT_FOUND);
+        let res = request(
+            &service,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "start"}),
+        );
+        assert_eq!(res["error"]["code"], INVALID_PARAMS);
+        let res = request(
+            &service,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "progress", "params": {"run": 0}}),
+        );
+        assert_eq!(res["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(VerifierService::new(&CONFIG_TEST, "", &test_datasets_path()).is_err());
+        assert!(
+            VerifierService::new(&CONFIG_TEST, TOKEN, &test_datasets_path().join("toto")).is_err()
+        );
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let service = service();
+        let res = service.authenticate(
+            &json!({"jsonrpc": "2.0", "id": 1, "method": "verifications"}).to_string(),
+        );
+        assert_eq!(res["error"]["code"], UNAUTHORIZED);
+        let res = service.authenticate(
+            &json!({"jsonrpc": "2.0", "id": 2, "method": "authenticate",
+                "params": {"token": "wrong"}})
+            .to_string(),
+        );
+        assert_eq!(res["error"]["code"], UNAUTHORIZED);
+        let res = service.authenticate(
+            &json!({"jsonrpc": "2.0", "id": 3, "method": "authenticate",
+                "params": {"token": TOKEN}})
+            .to_string(),
+        );
+        assert!(res.get("error").is_none());
+        assert!(res["result"].is_null());
+        assert!(token_eq(TOKEN, TOKEN));
+        assert!(!token_eq(TOKEN, "test-toke"));
+    }
+
+    #[test]
+    fn test_dataset_outside_root() {
+        let service = service();
+        for dataset in [
+            json!(".."),
+            json!(test_datasets_path().parent().unwrap().join("src")),
+            json!("toto"),
+        ] {
+            let res = request(
+                &service,
+                json!({"jsonrpc": "2.0", "id": 1, "method": "open_dataset",
+                    "params": {"dataset": dataset}}),
+            );
+            assert_eq!(res["error"]["code"], INVALID_PARAMS);
+            let res = request(
+                &service,
+                json!({"jsonrpc": "2.0", "id": 2, "method": "start",
+                    "params": {"dataset": dataset}}),
+            );
+            assert_eq!(res["error"]["code"], INVALID_PARAMS);
+        }
+        let res = request(
+            &service,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "open_dataset",
+                "params": {"dataset": "dataset-setup"}}),
+        );
+        assert_eq!(res["result"]["period"], "setup");
+    }
+
+    #[test]
+    fn test_verifications() {
+        let service = service();
+        let res = request(
+            &service,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "verifications",
+                "params": {"period": "setup"}}),
+        );
+        assert_eq!(
+            res["result"].as_array().unwrap().len(),
+            CONFIG_TEST
+                .verification_metadata()
+                .unwrap()
+                .id_list_for_period(&VerificationPeriod::Setup)
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_open_dataset() {
+        let service = service();
+        let res = request(
+            &service,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "open_dataset",
+                "params": {"dataset": "dataset-setup"}}),
+        );
+        assert_eq!(res["result"]["period"], "setup");
+        assert_eq!(res["result"]["unrecognized_files"], json!([]));
+    }
+
+    fn read_line(reader: &mut impl BufRead) -> Value {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn test_run() {
+        let service = service();
+        let listener = bind_local(0).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = {
+            let service = service.clone();
+            thread::spawn(move || service.serve(listener))
+        };
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        let mut send = |v: Value, reader: &mut BufReader<TcpStream>| {
+            write_line(&mut writer, &v).unwrap();
+            read_line(reader)
+        };
+        let res = send(
+            json!({"jsonrpc": "2.0", "id": 0, "method": "authenticate",
+                "params": {"token": TOKEN}}),
+            &mut reader,
+        );
+        assert!(res["result"].is_null());
+        // Only the verification 01.01 is run
+        let exclusion: Vec<String> = CONFIG_TEST
+            .verification_metadata()
+            .unwrap()
+            .id_list_for_period(&VerificationPeriod::Setup)
+            .into_iter()
+            .filter(|id| id != "01.01")
+            .collect();
+        let res = send(
+            json!({"jsonrpc": "2.0", "id": 1, "method": "start", "params": {
+                "dataset": "dataset-setup", "period": "setup", "exclusion": exclusion}}),
+            &mut reader,
+        );
+        let run = res["result"]["run"].clone();
+        // Notifications until the response of the subscription
+        let mut res = send(
+            json!({"jsonrpc": "2.0", "id": 2, "method": "subscribe", "params": {"run": run}}),
+            &mut reader,
+        );
+        let mut events = vec![];
+        while res["method"] == "event" {
+            events.push(res["params"]["event"]["kind"].clone());
+            res = read_line(&mut reader);
+        }
+        assert_eq!(res["id"], 2);
+        assert_eq!(res["result"]["state"], "finished");
+        assert!(events.contains(&json!("verification_finished")));
+        let res = send(
+            json!({"jsonrpc": "2.0", "id": 3, "method": "report", "params": {"run": run}}),
+            &mut reader,
+        );
+        assert_eq!(res["result"]["verifications"][0]["id"], "01.01");
+        let res = send(
+            json!({"jsonrpc": "2.0", "id": 4, "method": "shutdown"}),
+            &mut reader,
+        );
+        assert!(res["result"].is_null());
+        server.join().unwrap().unwrap();
+        assert!(service.is_stopped());
+    }
+}
@@ -0,0 +1,192 @@
+//! Module implementing the batches of modular exponentiations for the verifications of the proofs
+//!
+//! The verification of a Schnorr or exponentiation proof is dominated by the exponentiations
+//! `g^z` and `y^(-e)`. The exponentiations of the generator `g` of the group, which is the same
+//! for all the proofs of the election event, are computed with a precomputed table
+//! ([FixedBaseTable]): the exponentiation needs then only one multiplication per window of the
+//! exponent and no squaring. The table is computed once per group and kept for the whole run.
+//!
+//! The exponentiations of a batch are computed in parallel.
+
+use crate::crypto_helpers::mod_pow;
+use rayon::prelude::*;
+use rug::Integer;
+use rust_ev_crypto_primitives::EncryptionParameters;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+/// Size in bits of the windows of the fixed-base tables
+///
+/// The table contains `2^FIXED_BASE_WINDOW` powers per window. With 4 bits, the table of a
+/// group of 3072 bits has about 12'000 elements (less than 5 MB)
+const FIXED_BASE_WINDOW: usize = 4;
+
+/// Table of the powers of a fixed base modulo `p`, for the exponents up to a maximal size
+pub(crate) struct FixedBaseTable {
+    base: Integer,
+    p: Integer,
+    /// `table[j][d]` is `base^(d * 2^(FIXED_BASE_WINDOW * j)) mod p`
+    table: Vec<Vec<Integer>>,
+}
+
+impl FixedBaseTable {
+    /// Precompute the table of `base` modulo `p` for the exponents of at most `max_bits` bits
+    pub fn new(base: &Integer, p: &Integer, max_bits: usize) -> Self {
+        let nb_windows = max_bits.div_ceil(FIXED_BASE_WINDOW);
+        let mut table = Vec::with_capacity(nb_windows);
+        let mut base_j = Integer::from(base % p);
+        for _ in 0..nb_windows {
+            let mut row = Vec::with_capacity(1 << FIXED_BASE_WINDOW);
+            row.push(Integer::from(1));
+            for d in 1..(1 << FIXED_BASE_WINDOW) {
+                row.push(Integer::from(&row[d - 1] * &base_j) % p);
+            }
+            base_j = Integer::from(&row[(1 << FIXED_BASE_WINDOW) - 1] * &base_j) % p;
+            table.push(row);
+        }
+        Self {
+            base: base.clone(),
+            p: p.clone(),
+            table,
+        }
+    }
+
+    /// Maximal size in bits of the exponents
+    fn max_bits(&self) -> usize {
+        self.table.len() * FIXED_BASE_WINDOW
+    }
+
+    /// `base^e mod p`
+    ///
+    /// Return `None` if the exponent is negative or too large for the table
+    pub fn pow(&self, e: &Integer) -> Option<Integer> {
+        if e.is_negative() || e.significant_bits() as usize > self.max_bits() {
+            return None;
+        }
+        let mut res = Integer::from(1);
+        for (j, row) in self.table.iter().enumerate() {
+            let d = (0..FIXED_BASE_WINDOW)
+                .filter(|k| e.get_bit((j * FIXED_BASE_WINDOW + k) as u32))
+                .fold(0usize, |acc, k| acc | (1 << k));
+            if d != 0 {
+                res *= &row[d];
+                res %= &self.p;
+            }
+        }
+        Some(res)
+    }
+}
+
+/// Tables already computed, shared between the verifications
+fn fixed_base_tables() -> &'static Mutex<Vec<Arc<FixedBaseTable>>> {
+    static TABLES: OnceLock<Mutex<Vec<Arc<FixedBaseTable>>>> = OnceLock::new();
+    TABLES.get_or_init(|| Mutex::new(vec![]))
+}
+
+/// Table of `base` modulo `p` for the exponents of at most `max_bits` bits
+///
+/// The table is computed at the first call and then taken from the cache
+fn fixed_base_table(base: &Integer, p: &Integer, max_bits: usize) -> Arc<FixedBaseTable> {
+    let mut tables = fixed_base_tables()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(t) = tables
+        .iter()
+        .find(|t| &t.base == base && &t.p == p && t.max_bits() >= max_bits)
+    {
+        return t.clone();
+    }
+    let table = Arc::new(FixedBaseTable::new(base, p, max_bits));
+    tables.push(table.clone());
+    table
+}
+
+/// Modular exponentiations in the group of the encryption parameters
+///
+/// The exponentiations of the generator use the fixed-base table of the group. The other
+/// exponentiations are computed directly.
+pub(crate) struct BatchExponentiation {
+    p: Integer,
+    g_table: Arc<FixedBaseTable>,
+}
+
+impl BatchExponentiation {
+    /// Exponentiations for the group of the encryption parameters, with exponents in `Z_q`
+    pub fn new(eg: &EncryptionParameters) -> Self {
+        Self {
+            p: eg.p().clone(),
+            g_table: fixed_base_table(eg.g(), eg.p(), eg.q().significant_bits() as usize),
+        }
+    }
+
+    /// `b^e mod p`, where `e` can be negative (inverse of `b` modulo `p`)
+    pub fn pow(&self, b: &Integer, e: &Integer) -> anyhow::Result<Integer> {
+        if b == &self.g_table.base {
+            if let Some(res) = self.g_table.pow(e) {
+                return Ok(res);
+            }
+        }
+        mod_pow(b, e, &self.p)
+    }
+
+    /// `b^e mod p` for all the pairs `(b, e)`, computed in parallel
+    ///
+    /// Return an error if an exponentiation fails
+    pub fn pow_all(&self, pairs: &[(&Integer, &Integer)]) -> anyhow::Result<Vec<Integer>> {
+        pairs.par_iter().map(|(b, e)| self.pow(b, e)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::test::test_dataset_setup_path,
+        data_structures::{
+            setup::election_event_context_payload::ElectionEventContextPayload, VerifierDataDecode,
+        },
+    };
+    use std::fs;
+
+    #[test]
+    fn test_fixed_base_table() {
+        let p = Integer::from(23);
+        let table = FixedBaseTable::new(&Integer::from(2), &p, 8);
+        for e in 0..256 {
+            assert_eq!(
+                table.pow(&Integer::from(e)).unwrap(),
+                Integer::from(2).pow_mod(&Integer::from(e), &p).unwrap()
+            );
+        }
+        assert!(table.pow(&Integer::from(256)).is_none());
+        assert!(table.pow(&Integer::from(-1)).is_none());
+    }
+
+    #[test]
+    fn test_batch_exponentiation() {
+        let payload = ElectionEventContextPayload::from_json(
+            &fs::read_to_string(
+                test_dataset_setup_path()
+                    .join("setup")
+                    .join("electionEventContextPayload.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let eg = &payload.encryption_group;
+        let exp = BatchExponentiation::new(eg);
+        let e = Integer::from(eg.q() - 12345);
+        let minus_e = Integer::from(-&e);
+        let y = Integer::from(eg.g() * eg.g()) % eg.p();
+        let res = exp
+            .pow_all(&[(eg.g(), &e), (&y, &e), (eg.g(), &minus_e)])
+            .unwrap();
+        assert_eq!(res[0], mod_pow(eg.g(), &e, eg.p()).unwrap());
+        assert_eq!(res[1], mod_pow(&y, &e, eg.p()).unwrap());
+        assert_eq!(res[2], mod_pow(eg.g(), &minus_e, eg.p()).unwrap());
+        // The table is computed once per group
+        assert!(Arc::ptr_eq(
+            &exp.g_table,
+            &BatchExponentiation::new(eg).g_table
+        ));
+    }
+}
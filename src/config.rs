@@ -2,9 +2,24 @@
 
 use super::consts;
 use super::resources::VERIFICATION_LIST;
-use anyhow::{Context, Result};
+use crate::data_structures::dataset_version::DateTimeParsingMode;
+#[cfg(feature = "native")]
+use crate::{
+    direct_trust::{check_certificate_file, CertificateAuthority, CertificateProblem},
+    verification::meta_data::VerificationMetaDataList,
+};
+use anyhow::{anyhow, Context, Result};
+#[cfg(feature = "native")]
 use rust_ev_crypto_primitives::{CertificateExtension, Keystore};
-use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native")]
+use std::sync::Arc;
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
 // Directory structure
 pub const SETUP_DIR_NAME: &str = "setup";
@@ -15,7 +30,13 @@ const BB_DIR_NAME: &str = "ballot_boxes";
 // Program structure
 const LOG_DIR_NAME: &str = "log";
 const LOG_FILE_NAME: &str = "log.txt";
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+const REPORT_DIR_NAME: &str = "report";
 const DIRECT_TRUST_DIR_NAME: &str = "direct-trust";
+const DIRECT_TRUST_CERTIFICATE_EXTENSION: &str = "cer";
+const DIRECTORY_SCAN_THREADS: usize = 16;
+const DEDUPLICATION_MAX_IDS_IN_MEMORY: usize = 1_000_000;
+const FILE_STREAMING_THRESHOLD: u64 = 20 * 1024 * 1024;
 // const KEYSTORE_FILE_NAME: &str = "public_keys_keystore_verifier.p12";
 // const KEYSTORE_PASSWORD_FILE_NAME: &str = "public_keys_keystore_verifier_pw.txt";
 
@@ -30,7 +51,28 @@ const DIRECT_TRUST_DIR_NAME: &str = "direct-trust";
 ///     static ref CONFIG: Config = Config::new("..");
 ///  }
 /// ```
-pub struct Config(&'static str);
+///
+/// The keystore is read once and shared (see [Config::keystore]), so that the verifications
+/// running in parallel use the same parsed certificates. The same way, the metadata of the
+/// verifications are parsed only when they are needed the first time (see
+/// [Config::verification_metadata]). The direct trust certificate of each certificate authority
+/// is also checked only once (see [Config::certificate_problems]). The keystore, the metadata and
+/// the checks of the certificates are only available with the feature `native`.
+///
+/// The plausibility checks use the [StrictnessProfile] of the configuration (see
+/// [Config::strictness]) and the datetimes are verified with the [DateTimeParsingMode] of the
+/// configuration (see [Config::datetime_parsing_mode]).
+pub struct Config {
+    root_dir: &'static str,
+    #[cfg(feature = "native")]
+    keystore: OnceLock<Arc<Keystore>>,
+    #[cfg(feature = "native")]
+    metadata: OnceLock<VerificationMetaDataList>,
+    #[cfg(feature = "native")]
+    certificate_problems: [OnceLock<Vec<CertificateProblem>>; 8],
+    strictness: OnceLock<StrictnessProfile>,
+    datetime_parsing_mode: OnceLock<DateTimeParsingMode>,
+}
 
 /// New config with root_dir equal "."
 impl Default for Config {
@@ -42,12 +84,22 @@ impl Default for Config {
 impl Config {
     /// New Config
     pub fn new(root_dir: &'static str) -> Self {
-        Config(root_dir)
+        Config {
+            root_dir,
+            #[cfg(feature = "native")]
+            keystore: OnceLock::new(),
+            #[cfg(feature = "native")]
+            metadata: OnceLock::new(),
+            #[cfg(feature = "native")]
+            certificate_problems: CertificateAuthority::all().map(|_| OnceLock::new()),
+            strictness: OnceLock::new(),
+            datetime_parsing_mode: OnceLock::new(),
+        }
     }
 
     /// Path of the root directory of the programm
     pub fn root_dir_path(&self) -> PathBuf {
-        Path::new(self.0).to_path_buf()
+        Path::new(self.root_dir).to_path_buf()
     }
 
     /// Maximum number of voting options according to the specification
@@ -76,6 +128,33 @@ impl Config {
         consts::MAXIMUM_ACTUAL_VOTING_OPTION_LENGTH
     }
 
+    /// Order of the control components in the mix net according to the specification
+    ///
+    /// Used if the election event context does not define the order
+    pub fn default_mixing_order() -> Vec<usize> {
+        consts::DEFAULT_MIXING_ORDER.to_vec()
+    }
+
+    /// Maximum number of threads used to scan the subdirectories of the dataset
+    ///
+    /// The scan is IO-bound, so that the number is independent of the number of cores
+    pub fn directory_scan_threads() -> usize {
+        DIRECTORY_SCAN_THREADS
+    }
+
+    /// Maximum number of ids kept in memory by the search of duplicated ids
+    ///
+    /// Above, the ids are written in sorted runs to temporary files
+    pub fn deduplication_max_ids_in_memory() -> usize {
+        DEDUPLICATION_MAX_IDS_IN_MEMORY
+    }
+
+    /// Size in bytes above which a json file is decoded while streaming it (see
+    /// [crate::file_structure::FileReadMode])
+    pub fn file_streaming_threshold() -> u64 {
+        FILE_STREAMING_THRESHOLD
+    }
+
     /// The name of the setup directory
     pub fn setup_dir_name() -> &'static str {
         SETUP_DIR_NAME
@@ -101,11 +180,29 @@ impl Config {
         self.root_dir_path().join(LOG_DIR_NAME).join(LOG_FILE_NAME)
     }
 
+    /// The path to the hash-chained journal of the verifications
+    pub fn journal_file_path(&self) -> PathBuf {
+        self.root_dir_path()
+            .join(LOG_DIR_NAME)
+            .join(JOURNAL_FILE_NAME)
+    }
+
+    /// The path to the directory where the reports (e.g. failed items) are stored
+    pub fn report_dir_path(&self) -> PathBuf {
+        self.root_dir_path().join(REPORT_DIR_NAME)
+    }
+
     /// The path to the directory where direct trust keystore is stored
     fn direct_trust_dir_path(&self) -> PathBuf {
         self.root_dir_path().join(DIRECT_TRUST_DIR_NAME)
     }
 
+    /// The path to the direct trust certificate of the given certificate authority
+    pub fn direct_trust_certificate_path(&self, ca: &str) -> PathBuf {
+        self.direct_trust_dir_path()
+            .join(format!("{}.{}", ca, DIRECT_TRUST_CERTIFICATE_EXTENSION))
+    }
+
     /*
     pub fn direct_trust_keystore_path(&self) -> PathBuf {
         self.direct_trust_dir_path().join(KEYSTORE_FILE_NAME)
@@ -121,10 +218,188 @@ impl Config {
         VERIFICATION_LIST
     }
 
+    /// The metadata of all the verifications
+    ///
+    /// The list of verifications is parsed at the first call and cached. The next calls return
+    /// the same list, so that the applications do not need to keep it.
+    #[cfg(feature = "native")]
+    pub fn verification_metadata(&self) -> Result<&VerificationMetaDataList> {
+        if let Some(m) = self.metadata.get() {
+            return Ok(m);
+        }
+        let m = VerificationMetaDataList::load(self.get_verification_list_str())?;
+        Ok(self.metadata.get_or_init(|| m))
+    }
+
     /// Get the keystore
-    pub fn keystore(&self) -> Result<Keystore> {
-        Keystore::from_directory(&self.direct_trust_dir_path(), &CertificateExtension::Cer)
-            .context("Problem reading the keystore")
+    ///
+    /// The keystore is read at the first call. The next calls return a handle on the same
+    /// keystore, which can be shared between threads. If the keystore cannot be read, it is
+    /// read again at the next call.
+    #[cfg(feature = "native")]
+    pub fn keystore(&self) -> Result<Arc<Keystore>> {
+        if let Some(ks) = self.keystore.get() {
+            return Ok(ks.clone());
+        }
+        let ks =
+            Keystore::from_directory(&self.direct_trust_dir_path(), &CertificateExtension::Cer)
+                .context("Problem reading the keystore")?;
+        Ok(self.keystore.get_or_init(|| Arc::new(ks)).clone())
+    }
+
+    /// Problems of the direct trust certificate of the certificate authority (see
+    /// [check_certificate_file])
+    ///
+    /// The certificate is checked at the first call for the certificate authority. The next calls
+    /// return the same problems, so that the certificate is not read again for each signature
+    #[cfg(feature = "native")]
+    pub fn certificate_problems(&self, ca: CertificateAuthority) -> &[CertificateProblem] {
+        let index = CertificateAuthority::all()
+            .iter()
+            .position(|c| c == &ca)
+            .unwrap_or_default();
+        self.certificate_problems[index].get_or_init(|| {
+            check_certificate_file(ca, &self.direct_trust_certificate_path(&String::from(ca)))
+        })
+    }
+
+    /// Set the strictness profile used by the plausibility checks
+    ///
+    /// The profile can be set only once, before the verifications are run. Return an error if
+    /// the profile is already set or already used by a verification
+    pub fn set_strictness(&self, profile: StrictnessProfile) -> Result<()> {
+        self.strictness
+            .set(profile)
+            .map_err(|_| anyhow!("The strictness profile is already set"))
+    }
+
+    /// The strictness profile used by the plausibility checks
+    ///
+    /// [StrictnessProfile::default] (no tolerance) if no profile is set
+    pub fn strictness(&self) -> &StrictnessProfile {
+        self.strictness.get_or_init(StrictnessProfile::default)
+    }
+
+    /// Set the behavior by a datetime in the format of another version than the version of the
+    /// dataset
+    ///
+    /// The mode can be set only once, before the verifications are run. Return an error if the
+    /// mode is already set or already used by a verification
+    pub fn set_datetime_parsing_mode(&self, mode: DateTimeParsingMode) -> Result<()> {
+        self.datetime_parsing_mode
+            .set(mode)
+            .map_err(|_| anyhow!("The parsing mode of the datetimes is already set"))
+    }
+
+    /// The behavior by a datetime in the format of another version than the version of the
+    /// dataset
+    ///
+    /// [DateTimeParsingMode::Lenient] if no mode is set
+    pub fn datetime_parsing_mode(&self) -> DateTimeParsingMode {
+        *self
+            .datetime_parsing_mode
+            .get_or_init(DateTimeParsingMode::default)
+    }
+}
+
+/// Tolerances of the plausibility checks (counts, dates)
+///
+/// Per default, the checks are strict (no tolerance). Some cantons need tolerances, e.g. if the
+/// test votes are excluded from the results. Each tolerance different from the default is a
+/// relaxation of the verifications using it (see [StrictnessProfile::relaxations]), which is
+/// listed in the report of the run.
+///
+/// The profile can be read from a json file, where the missing fields take the default value:
+/// ```json
+/// { "name": "canton XY", "cast_votes_tolerance": 5 }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrictnessProfile {
+    /// Name of the profile (e.g. the canton)
+    pub name: String,
+    /// Accepted difference between the total of voters and the sum of the voting cards (03.13)
+    pub total_voters_tolerance: usize,
+    /// Accepted difference between the cast votes in eCH-0222 and the confirmed votes (X.08.01)
+    pub cast_votes_tolerance: usize,
+    /// Number of days the date of the seed can differ from the contest date (X.05.01)
+    pub seed_date_tolerance_days: u32,
+    /// Abbreviation of the canton expected in the seed (X.05.01). If empty, the canton of the seed
+    /// is not compared
+    pub seed_canton: String,
+}
+
+/// Relaxation of verifications by a [StrictnessProfile]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Relaxation {
+    /// Name of the profile
+    pub profile: String,
+    /// Name of the tolerance in the profile
+    pub parameter: String,
+    pub value: String,
+    /// Ids of the relaxed verifications
+    pub verifications: Vec<String>,
+}
+
+impl Display for Relaxation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} = {} (profile \"{}\", verifications {})",
+            self.parameter,
+            self.value,
+            self.profile,
+            self.verifications.join(" ")
+        )
+    }
+}
+
+impl StrictnessProfile {
+    /// Read the profile from a json file
+    pub fn load(path: &Path) -> Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read the strictness profile in {:?}", path))?;
+        serde_json::from_str(&s)
+            .map_err(|e| anyhow!(e).context(format!("Strictness profile in {:?} not valid", path)))
+    }
+
+    /// The profile has no tolerance
+    pub fn is_strict(&self) -> bool {
+        self.relaxations().is_empty()
+    }
+
+    /// The relaxations of the profile, i.e. the tolerances different from the default
+    pub fn relaxations(&self) -> Vec<Relaxation> {
+        let default = Self::default();
+        [
+            (
+                "total_voters_tolerance",
+                self.total_voters_tolerance != default.total_voters_tolerance,
+                self.total_voters_tolerance.to_string(),
+                "03.13",
+            ),
+            (
+                "cast_votes_tolerance",
+                self.cast_votes_tolerance != default.cast_votes_tolerance,
+                self.cast_votes_tolerance.to_string(),
+                "X.08.01",
+            ),
+            (
+                "seed_date_tolerance_days",
+                self.seed_date_tolerance_days != default.seed_date_tolerance_days,
+                self.seed_date_tolerance_days.to_string(),
+                "X.05.01",
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, relaxed, _, _)| *relaxed)
+        .map(|(parameter, _, value, id)| Relaxation {
+            profile: self.name.clone(),
+            parameter: parameter.to_string(),
+            value,
+            verifications: vec![id.to_string()],
+        })
+        .collect()
     }
 }
 
@@ -181,7 +456,94 @@ pub(crate) mod test {
         let c = Config::default();
         assert_eq!(c.root_dir_path(), Path::new("."));
         assert_eq!(c.log_file_path(), Path::new("./log/log.txt"));
+        assert_eq!(c.report_dir_path(), Path::new("./report"));
         assert_eq!(c.direct_trust_dir_path(), Path::new("./direct-trust"));
+        assert_eq!(
+            c.direct_trust_certificate_path("canton"),
+            Path::new("./direct-trust/canton.cer")
+        );
         assert!(!c.get_verification_list_str().is_empty());
     }
+
+    #[test]
+    fn test_keystore_shared() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Keystore>();
+        assert_send_sync::<Config>();
+        let ks = CONFIG_TEST.keystore().unwrap();
+        assert!(Arc::ptr_eq(&ks, &CONFIG_TEST.keystore().unwrap()));
+        let handles: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| CONFIG_TEST.keystore().unwrap()))
+            .collect();
+        for h in handles {
+            let other = h.join().unwrap();
+            assert!(Arc::ptr_eq(&ks, &other));
+            assert!(other.certificate("canton").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_certificate_problems() {
+        let c = Config::default();
+        for ca in CertificateAuthority::all() {
+            let problems = c.certificate_problems(ca);
+            assert_eq!(
+                problems,
+                check_certificate_file(ca, &c.direct_trust_certificate_path(&String::from(ca)))
+            );
+            assert!(std::ptr::eq(problems, c.certificate_problems(ca)));
+        }
+    }
+
+    #[test]
+    fn test_verification_metadata() {
+        let c = Config::default();
+        let metadata = c.verification_metadata().unwrap();
+        assert!(metadata.get("01.01").is_some());
+        assert!(std::ptr::eq(metadata, c.verification_metadata().unwrap()));
+    }
+
+    #[test]
+    fn test_datetime_parsing_mode() {
+        let c = Config::default();
+        assert_eq!(c.datetime_parsing_mode(), DateTimeParsingMode::Lenient);
+        assert!(c
+            .set_datetime_parsing_mode(DateTimeParsingMode::Strict)
+            .is_err());
+        let c = Config::default();
+        c.set_datetime_parsing_mode(DateTimeParsingMode::Strict)
+            .unwrap();
+        assert_eq!(c.datetime_parsing_mode(), DateTimeParsingMode::Strict);
+    }
+
+    #[test]
+    fn test_strictness() {
+        let c = Config::default();
+        assert!(c.strictness().is_strict());
+        assert!(c.set_strictness(StrictnessProfile::default()).is_err());
+        let c = Config::default();
+        let profile = StrictnessProfile {
+            name: "test".to_string(),
+            cast_votes_tolerance: 2,
+            ..Default::default()
+        };
+        c.set_strictness(profile.clone()).unwrap();
+        assert_eq!(c.strictness(), &profile);
+        let relaxations = profile.relaxations();
+        assert_eq!(relaxations.len(), 1);
+        assert_eq!(relaxations[0].parameter, "cast_votes_tolerance");
+        assert_eq!(relaxations[0].verifications, vec!["X.08.01"]);
+    }
+
+    #[test]
+    fn test_load_strictness() {
+        let path = std::env::temp_dir().join("rust_verifier_test_strictness.json");
+        fs::write(&path, r#"{ "name": "XY", "total_voters_tolerance": 3 }"#).unwrap();
+        let profile = StrictnessProfile::load(&path).unwrap();
+        assert_eq!(profile.total_voters_tolerance, 3);
+        assert_eq!(profile.cast_votes_tolerance, 0);
+        fs::write(&path, r#"{ "total_voter_tolerance": 3 }"#).unwrap();
+        assert!(StrictnessProfile::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
 }
@@ -4,3 +4,4 @@ pub const MAXIMUM_NUMBER_OF_SELECTABLE_VOTING_OPTIONS: usize = 120;
 pub const MAXIMUM_NUMBER_OF_WRITE_IN_OPTIONS: usize = 15;
 pub const MAXIMUM_WRITE_IN_OPTION_LENGTH: usize = 500;
 pub const MAXIMUM_ACTUAL_VOTING_OPTION_LENGTH: usize = 50;
+pub const DEFAULT_MIXING_ORDER: [usize; 4] = [1, 2, 3, 4];
@@ -0,0 +1,754 @@
+//! Module implementing helpers for the cryptographic rules of the specification, that are
+//! shared between the verifications
+
+#[cfg(feature = "native")]
+use crate::batch_exponentiation::BatchExponentiation;
+use crate::data_structures::common_types::{
+    DecryptionProof, ExponentiatedEncryptedElement, PlaintextEqualityProof,
+};
+use anyhow::{anyhow, bail, Context};
+use chrono::NaiveDate;
+use rug::Integer;
+use rust_ev_crypto_primitives::{EncryptionParameters, HashableMessage};
+use std::fmt::Display;
+
+const SEED_DATE_FORMAT: &str = "%Y%m%d";
+
+/// Seed used to generate the encryption parameters
+///
+/// According to the specification, the seed is derived from the election event as
+/// `<canton>_<date>_<event>`, where:
+/// - `canton` is the abbreviation of the canton (two upper case letters)
+/// - `date` is the date of the election event (format `yyyyMMdd`)
+/// - `event` is the type of the election event (two upper case letters) followed by
+/// an index of two digits (e.g. `TT04`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seed {
+    pub canton: String,
+    pub date: NaiveDate,
+    pub event: String,
+}
+
+impl Seed {
+    /// Derive the seed string from the election event data
+    pub fn derive(canton: &str, date: &NaiveDate, event: &str) -> String {
+        format!("{}_{}_{}", canton, date.format(SEED_DATE_FORMAT), event)
+    }
+}
+
+impl TryFrom<&str> for Seed {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let parts: Vec<&str> = value.split('_').collect();
+        if parts.len() != 3 {
+            bail!(format!(
+                "The seed {} must have the form <canton>_<date>_<event>",
+                value
+            ))
+        }
+        let canton = parts[0];
+        if canton.len() != 2 || !canton.chars().all(|c| c.is_ascii_uppercase()) {
+            bail!(format!(
+                "The canton {} in seed {} must be two upper case letters",
+                canton, value
+            ))
+        }
+        let date = NaiveDate::parse_from_str(parts[1], SEED_DATE_FORMAT)
+            .map_err(|e| anyhow!(e))
+            .with_context(|| format!("The date in seed {} is not valid", value))?;
+        let event = parts[2];
+        let (event_type, event_index) = event.split_at(event.len().min(2));
+        if event.len() != 4
+            || !event_type.chars().all(|c| c.is_ascii_uppercase())
+            || !event_index.chars().all(|c| c.is_ascii_digit())
+        {
+            bail!(format!(
+                "The event {} in seed {} must be two upper case letters followed by two digits",
+                event, value
+            ))
+        }
+        Ok(Self {
+            canton: canton.to_string(),
+            date,
+            event: event.to_string(),
+        })
+    }
+}
+
+impl Display for Seed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::derive(&self.canton, &self.date, &self.event))
+    }
+}
+
+/// Context of a Schnorr proof of knowledge of a secret key, defining the auxiliary information
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchnorrProofContext {
+    /// Choice return codes encryption key `CCR_j` of the control component `node_id`
+    ChoiceReturnCodes { node_id: usize },
+    /// Election key `CCM_j` of the control component `node_id`
+    ElectionKey { node_id: usize },
+    /// Key of the electoral board
+    ElectoralBoard,
+}
+
+impl SchnorrProofContext {
+    /// Auxiliary information `i_aux` of the proof for the election event `ee_id`
+    pub fn i_aux(&self, ee_id: &str) -> Vec<String> {
+        match self {
+            Self::ChoiceReturnCodes { node_id } => vec![
+                ee_id.to_string(),
+                "GenKeysCCR".to_string(),
+                node_id.to_string(),
+            ],
+            Self::ElectionKey { node_id } => vec![
+                ee_id.to_string(),
+                "SetupTallyCCM".to_string(),
+                node_id.to_string(),
+            ],
+            Self::ElectoralBoard => vec![ee_id.to_string(), "SetupTallyEB".to_string()],
+        }
+    }
+}
+
+/// Context of a decryption proof, defining the auxiliary information
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecryptionProofContext {
+    /// Partial decryption of the online control component `node_id` (algorithm MixDecOnline)
+    OnlineControlComponent { node_id: usize },
+    /// Final decryption of the tally control component (algorithm MixDecOffline)
+    TallyControlComponent,
+}
+
+impl DecryptionProofContext {
+    /// Auxiliary information `i_aux` of the proof for the election event `ee_id` and the
+    /// ballot box `bb_id`
+    pub fn i_aux(&self, ee_id: &str, bb_id: &str) -> Vec<String> {
+        match self {
+            Self::OnlineControlComponent { node_id } => vec![
+                ee_id.to_string(),
+                bb_id.to_string(),
+                "MixDecOnline".to_string(),
+                node_id.to_string(),
+            ],
+            Self::TallyControlComponent => vec![
+                ee_id.to_string(),
+                bb_id.to_string(),
+                "MixDecOffline".to_string(),
+            ],
+        }
+    }
+}
+
+/// Auxiliary information `i_aux` of the exponentiation proof of the encrypted partial choice
+/// return codes of the verification card `vc_id`, calculated by the control component `node_id`
+/// (algorithm GenEncLongCodeShares)
+pub fn exponentiation_pcc_i_aux(ee_id: &str, vc_id: &str, node_id: usize) -> Vec<String> {
+    vec![
+        ee_id.to_string(),
+        vc_id.to_string(),
+        "GenEncLongCodeShares".to_string(),
+        node_id.to_string(),
+    ]
+}
+
+/// Auxiliary information `i_aux` of the proofs of the vote of the verification card `vc_id`
+/// (algorithm CreateVote)
+pub fn create_vote_i_aux(ee_id: &str, vc_id: &str) -> Vec<String> {
+    vec![
+        ee_id.to_string(),
+        vc_id.to_string(),
+        "CreateVote".to_string(),
+    ]
+}
+
+/// Statement of the plaintext equality proof of a vote: the ciphertexts `c` and `c_prime`,
+/// encrypted with the public keys `h` and `h_prime`, have the same plaintext
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaintextEqualityStatement {
+    /// Ciphertext (gamma, phi) under `h`
+    pub c: (Integer, Integer),
+    /// Ciphertext (gamma, phi) under `h_prime`
+    pub c_prime: (Integer, Integer),
+    pub h: Integer,
+    pub h_prime: Integer,
+}
+
+impl PlaintextEqualityStatement {
+    /// Statement for a confirmed vote (algorithm VerifyBallotCCR)
+    ///
+    /// - `c` is the exponentiated encrypted vote, under the first element of the election public
+    /// key `el_pk`
+    /// - `c_prime` is the gamma of the encrypted partial choice return codes with the product of
+    /// its phis, under the product of the first `psi` elements of the choice return codes
+    /// encryption public key `pk_ccr`, where `psi` is the number of phis
+    ///
+    /// Return an error if the sizes of the inputs are not consistent
+    pub fn for_vote(
+        eg: &EncryptionParameters,
+        exponentiated_encrypted_vote: &ExponentiatedEncryptedElement,
+        encrypted_pcc: &ExponentiatedEncryptedElement,
+        el_pk: &[Integer],
+        pk_ccr: &[Integer],
+    ) -> anyhow::Result<Self> {
+        let psi = encrypted_pcc.phis.len();
+        if exponentiated_encrypted_vote.phis.len() != 1 || el_pk.is_empty() {
+            bail!(format!(
+                "The exponentiated encrypted vote has {} phis (expected 1) and the election public key {} elements",
+                exponentiated_encrypted_vote.phis.len(),
+                el_pk.len()
+            ))
+        }
+        if psi == 0 || pk_ccr.len() < psi {
+            bail!(format!(
+                "The encrypted partial choice return codes have {} phis, but the choice return codes encryption public key has {} elements",
+                psi,
+                pk_ccr.len()
+            ))
+        }
+        let p = eg.p();
+        let product = |values: &[Integer]| {
+            values
+                .iter()
+                .fold(Integer::from(1), |acc, v| Integer::from(acc * v) % p)
+        };
+        Ok(Self {
+            c: (
+                exponentiated_encrypted_vote.gamma.clone(),
+                exponentiated_encrypted_vote.phis[0].clone(),
+            ),
+            c_prime: (encrypted_pcc.gamma.clone(), product(&encrypted_pcc.phis)),
+            h: el_pk[0].clone(),
+            h_prime: product(&pk_ccr[..psi]),
+        })
+    }
+}
+
+/// Bases `gs` and images `ys` of the exponentiation proof of the encrypted partial choice
+/// return codes
+///
+/// The bases are `(g, gamma, phi_0, ...)` of the encrypted partial choice return codes and the
+/// images `(K_j_id, gamma', phi'_0, ...)` with the voter choice return code generation public key
+/// `K_j_id` and the exponentiated encrypted partial choice return codes
+pub fn exponentiation_pcc_bases_and_images(
+    eg: &EncryptionParameters,
+    encrypted_pcc: &ExponentiatedEncryptedElement,
+    k_j_id: &Integer,
+    exponentiated_pcc: &ExponentiatedEncryptedElement,
+) -> (Vec<Integer>, Vec<Integer>) {
+    let mut gs = vec![eg.g().clone(), encrypted_pcc.gamma.clone()];
+    gs.extend(encrypted_pcc.phis.iter().cloned());
+    let mut ys = vec![k_j_id.clone(), exponentiated_pcc.gamma.clone()];
+    ys.extend(exponentiated_pcc.phis.iter().cloned());
+    (gs, ys)
+}
+
+/// Bases `gs` and images `ys` of the exponentiation proof of a confirmed vote (algorithm
+/// VerifyBallotCCR)
+///
+/// The bases are `(g, gamma, phi_0)` of the encrypted vote and the images `(K_id, gamma',
+/// phi'_0)` with the verification card public key `K_id` and the exponentiated encrypted vote
+///
+/// Return an error if the encrypted vote or the exponentiated encrypted vote has no phi
+#[cfg(feature = "native")]
+pub fn exponentiation_vote_bases_and_images(
+    eg: &EncryptionParameters,
+    encrypted_vote: &ExponentiatedEncryptedElement,
+    k_id: &Integer,
+    exponentiated_encrypted_vote: &ExponentiatedEncryptedElement,
+) -> anyhow::Result<(Vec<Integer>, Vec<Integer>)> {
+    match (
+        encrypted_vote.phis.first(),
+        exponentiated_encrypted_vote.phis.first(),
+    ) {
+        (Some(phi), Some(phi_prime)) => Ok((
+            vec![eg.g().clone(), encrypted_vote.gamma.clone(), phi.clone()],
+            vec![
+                k_id.clone(),
+                exponentiated_encrypted_vote.gamma.clone(),
+                phi_prime.clone(),
+            ],
+        )),
+        _ => bail!(format!(
+            "The encrypted vote has {} phis and the exponentiated encrypted vote {} phis (expected at least 1)",
+            encrypted_vote.phis.len(),
+            exponentiated_encrypted_vote.phis.len()
+        )),
+    }
+}
+
+/// `b^e mod p`, where `e` can be negative (inverse of `b` modulo `p`)
+pub(crate) fn mod_pow(b: &Integer, e: &Integer, p: &Integer) -> anyhow::Result<Integer> {
+    b.clone()
+        .pow_mod(e, p)
+        .map_err(|_| anyhow!("The element is not invertible modulo p"))
+}
+
+/// Auxiliary information `h_aux` of the hash of a proof: the name of the proof, followed by
+/// `i_aux` if not empty
+#[cfg(feature = "native")]
+fn proof_h_aux<'a>(name: &'a str, i_aux: &'a [String]) -> HashableMessage<'a> {
+    let mut h_aux = vec![HashableMessage::from(name)];
+    if !i_aux.is_empty() {
+        h_aux.push(HashableMessage::from(
+            i_aux.iter().map(HashableMessage::from).collect::<Vec<_>>(),
+        ));
+    }
+    HashableMessage::from(h_aux)
+}
+
+/// Verify the Schnorr proof `(e, z)` of knowledge of the discrete logarithm of `y` (algorithm
+/// VerifySchnorr)
+///
+/// The exponentiations are computed with `exp` (see [BatchExponentiation]), that must be
+/// created for the same encryption parameters
+#[cfg(feature = "native")]
+pub(crate) fn verify_schnorr_with(
+    exp: &BatchExponentiation,
+    eg: &EncryptionParameters,
+    proof: (&Integer, &Integer),
+    y: &Integer,
+    i_aux: &[String],
+) -> anyhow::Result<bool> {
+    let (e, z) = proof;
+    let p = eg.p();
+    let powers = exp.pow_all(&[(eg.g(), z), (y, &Integer::from(-e))])?;
+    let c_prime = Integer::from(&powers[0] * &powers[1]) % p;
+    let e_prime = HashableMessage::from(vec![
+        HashableMessage::from(vec![
+            HashableMessage::from(p),
+            HashableMessage::from(eg.q()),
+            HashableMessage::from(eg.g()),
+        ]),
+        HashableMessage::from(y),
+        HashableMessage::from(&c_prime),
+        proof_h_aux("SchnorrProof", i_aux),
+    ])
+    .hash()
+    .into_mp_integer();
+    Ok(&e_prime == e)
+}
+
+/// Verify the exponentiation proof `(e, z)` that the `ys` are the `gs` exponentiated with the
+/// same secret exponent (algorithm VerifyExponentiation)
+///
+/// The exponentiations of all the bases and images are computed as one batch with `exp` (see
+/// [BatchExponentiation]), that must be created for the same encryption parameters.
+///
+/// Return an error if `gs` is empty or has not the size of `ys`
+#[cfg(feature = "native")]
+pub(crate) fn verify_exponentiation_with(
+    exp: &BatchExponentiation,
+    eg: &EncryptionParameters,
+    gs: &[Integer],
+    ys: &[Integer],
+    proof: (&Integer, &Integer),
+    i_aux: &[String],
+) -> anyhow::Result<bool> {
+    if gs.is_empty() || gs.len() != ys.len() {
+        bail!(format!(
+            "The exponentiation proof has {} bases and {} images",
+            gs.len(),
+            ys.len()
+        ))
+    }
+    let (e, z) = proof;
+    let p = eg.p();
+    let minus_e = Integer::from(-e);
+    let pairs: Vec<(&Integer, &Integer)> = gs
+        .iter()
+        .map(|g| (g, z))
+        .chain(ys.iter().map(|y| (y, &minus_e)))
+        .collect();
+    let powers = exp.pow_all(&pairs)?;
+    let (xs, ys_minus_e) = powers.split_at(gs.len());
+    let c_prime: Vec<Integer> = xs
+        .iter()
+        .zip(ys_minus_e.iter())
+        .map(|(x, y)| Integer::from(x * y) % p)
+        .collect();
+    let gs = gs.to_vec();
+    let ys = ys.to_vec();
+    let e_prime = HashableMessage::from(vec![
+        HashableMessage::from(vec![
+            HashableMessage::from(p),
+            HashableMessage::from(eg.q()),
+            HashableMessage::from(&gs),
+        ]),
+        HashableMessage::from(&ys),
+        HashableMessage::from(&c_prime),
+        proof_h_aux("ExponentiationProof", i_aux),
+    ])
+    .hash()
+    .into_mp_integer();
+    Ok(&e_prime == e)
+}
+
+/// Verify the proof of the (partial) decryption of a ciphertext (algorithm VerifyDecryption)
+///
+/// The proof shows that the message `m` is the decryption of the ciphertext with the secret key
+/// corresponding to the first `l` elements of `pk`, where `l` is the number of phis of the
+/// ciphertext. For a partial decryption, `m` contains the phis of the partially decrypted
+/// ciphertext.
+///
+/// Return an error if the inputs are not consistent (e.g. wrong sizes)
+pub fn verify_decryption(
+    eg: &EncryptionParameters,
+    ciphertext: &ExponentiatedEncryptedElement,
+    pk: &[Integer],
+    m: &[Integer],
+    proof: &DecryptionProof,
+    i_aux: &[String],
+) -> anyhow::Result<bool> {
+    let l = ciphertext.phis.len();
+    if l == 0 || m.len() != l || proof.z.len() != l {
+        bail!(format!(
+            "The ciphertext has {} phis, the message {} elements and the proof {} elements",
+            l,
+            m.len(),
+            proof.z.len()
+        ))
+    }
+    if pk.len() < l {
+        bail!(format!(
+            "The public key has {} elements, but the ciphertext has {} phis",
+            pk.len(),
+            l
+        ))
+    }
+    let p = eg.p();
+    let gamma = &ciphertext.gamma;
+    let mut y: Vec<Integer> = pk[..l].to_vec();
+    for (phi, m_i) in ciphertext.phis.iter().zip(m.iter()) {
+        y.push(Integer::from(phi * mod_pow(m_i, &Integer::from(-1), p)?) % p);
+    }
+    let mut x = vec![];
+    for base in [eg.g(), gamma] {
+        for z_i in proof.z.iter() {
+            x.push(mod_pow(base, z_i, p)?);
+        }
+    }
+    let minus_e = Integer::from(-&proof.e);
+    let mut c_prime = vec![];
+    for (x_i, y_i) in x.iter().zip(y.iter()) {
+        c_prime.push(Integer::from(x_i * mod_pow(y_i, &minus_e, p)?) % p);
+    }
+    let mut h_aux = vec![
+        HashableMessage::from("DecryptionProof"),
+        HashableMessage::from(&ciphertext.phis),
+        HashableMessage::from(m.iter().map(HashableMessage::from).collect::<Vec<_>>()),
+    ];
+    if !i_aux.is_empty() {
+        h_aux.push(HashableMessage::from(
+            i_aux.iter().map(HashableMessage::from).collect::<Vec<_>>(),
+        ));
+    }
+    let e_prime = HashableMessage::from(vec![
+        HashableMessage::from(vec![
+            HashableMessage::from(p),
+            HashableMessage::from(eg.q()),
+            HashableMessage::from(eg.g()),
+            HashableMessage::from(gamma),
+        ]),
+        HashableMessage::from(&y),
+        HashableMessage::from(&c_prime),
+        HashableMessage::from(h_aux),
+    ])
+    .hash()
+    .into_mp_integer();
+    Ok(e_prime == proof.e)
+}
+
+/// Verify the proof that two ciphertexts encrypted with different public keys have the same
+/// plaintext (algorithm VerifyPlaintextEquality)
+///
+/// Return an error if the proof has not two elements `z` or if an element is not invertible
+pub fn verify_plaintext_equality(
+    eg: &EncryptionParameters,
+    statement: &PlaintextEqualityStatement,
+    proof: &PlaintextEqualityProof,
+    i_aux: &[String],
+) -> anyhow::Result<bool> {
+    if proof.z.len() != 2 {
+        bail!(format!(
+            "The plaintext equality proof has {} elements z (expected 2)",
+            proof.z.len()
+        ))
+    }
+    let p = eg.p();
+    let (c_0, c_1) = (&statement.c.0, &statement.c.1);
+    let (c_prime_0, c_prime_1) = (&statement.c_prime.0, &statement.c_prime.1);
+    let (h, h_prime) = (&statement.h, &statement.h_prime);
+    let y = vec![
+        c_0.clone(),
+        c_prime_0.clone(),
+        Integer::from(c_1 * mod_pow(c_prime_1, &Integer::from(-1), p)?) % p,
+    ];
+    let x = vec![
+        mod_pow(eg.g(), &proof.z[0], p)?,
+        mod_pow(eg.g(), &proof.z[1], p)?,
+        Integer::from(
+            mod_pow(h, &proof.z[0], p)? * mod_pow(h_prime, &Integer::from(-&proof.z[1]), p)?,
+        ) % p,
+    ];
+    let minus_e = Integer::from(-&proof.e);
+    let mut c_prime_prime = vec![];
+    for (x_i, y_i) in x.iter().zip(y.iter()) {
+        c_prime_prime.push(Integer::from(x_i * mod_pow(y_i, &minus_e, p)?) % p);
+    }
+    let mut h_aux = vec![
+        HashableMessage::from("PlaintextEqualityProof"),
+        HashableMessage::from(c_1),
+        HashableMessage::from(c_prime_1),
+    ];
+    if !i_aux.is_empty() {
+        h_aux.push(HashableMessage::from(
+            i_aux.iter().map(HashableMessage::from).collect::<Vec<_>>(),
+        ));
+    }
+    let e_prime = HashableMessage::from(vec![
+        HashableMessage::from(vec![
+            HashableMessage::from(p),
+            HashableMessage::from(eg.q()),
+            HashableMessage::from(eg.g()),
+            HashableMessage::from(h),
+            HashableMessage::from(h_prime),
+        ]),
+        HashableMessage::from(&y),
+        HashableMessage::from(&c_prime_prime),
+        HashableMessage::from(h_aux),
+    ])
+    .hash()
+    .into_mp_integer();
+    Ok(e_prime == proof.e)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::test::{
+            test_ballot_box_path, test_dataset_setup_path, test_dataset_tally_path,
+            test_verification_card_set_path,
+        },
+        data_structures::{
+            common_types::Proof,
+            setup::{
+                control_component_code_shares_payload::ControlComponentCodeSharesPayload,
+                control_component_public_keys_payload::ControlComponentPublicKeysPayload,
+                setup_component_public_keys_payload::SetupComponentPublicKeysPayload,
+                setup_component_verification_data_payload::SetupComponentVerificationDataPayload,
+            },
+            tally::{
+                control_component_ballot_box_payload::ControlComponentBallotBoxPayload,
+                control_component_shuffle_payload::ControlComponentShufflePayload,
+            },
+            VerifierDataDecode,
+        },
+    };
+    use rust_ev_crypto_primitives::{verify_exponentiation, verify_schnorr};
+    use std::fs;
+
+    #[test]
+    fn test_seed() {
+        let seed = Seed::try_from("NE_20231124_TT04").unwrap();
+        assert_eq!(seed.canton, "NE");
+        assert_eq!(seed.date, NaiveDate::from_ymd_opt(2023, 11, 24).unwrap());
+        assert_eq!(seed.event, "TT04");
+        assert_eq!(seed.to_string(), "NE_20231124_TT04");
+        assert_eq!(
+            Seed::derive(
+                "NE",
+                &NaiveDate::from_ymd_opt(2023, 11, 24).unwrap(),
+                "TT04"
+            ),
+            "NE_20231124_TT04"
+        );
+    }
+
+    #[test]
+    fn test_seed_wrong() {
+        assert!(Seed::try_from("NE_20231124").is_err());
+        assert!(Seed::try_from("NEU_20231124_TT04").is_err());
+        assert!(Seed::try_from("ne_20231124_TT04").is_err());
+        assert!(Seed::try_from("NE_20231324_TT04").is_err());
+        assert!(Seed::try_from("NE_20231124_TT4").is_err());
+        assert!(Seed::try_from("NE_20231124_T004").is_err());
+        assert!(Seed::try_from("NE_20231124_TT04_1").is_err());
+    }
+
+    #[test]
+    fn test_verify_schnorr_with() {
+        let payload = ControlComponentPublicKeysPayload::from_json(
+            &fs::read_to_string(
+                test_dataset_setup_path()
+                    .join("setup")
+                    .join("controlComponentPublicKeysPayload.2.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let eg = &payload.encryption_group;
+        let exp = BatchExponentiation::new(eg);
+        let keys = &payload.control_component_public_keys;
+        let i_aux = SchnorrProofContext::ChoiceReturnCodes {
+            node_id: keys.node_id,
+        }
+        .i_aux(&payload.election_event_id);
+        for (pk, pi) in keys
+            .ccrj_choice_return_codes_encryption_public_key
+            .iter()
+            .zip(keys.ccrj_schnorr_proofs.iter())
+        {
+            let proof = Proof::from(pi);
+            assert!(verify_schnorr_with(&exp, eg, proof.as_tuple(), pk, &i_aux).unwrap());
+            assert_eq!(
+                verify_schnorr_with(&exp, eg, proof.as_tuple(), pk, &i_aux[..2]).unwrap(),
+                verify_schnorr(eg, proof.as_tuple(), pk, &i_aux[..2].to_vec()).unwrap()
+            );
+        }
+        let proof = Proof::from(&keys.ccrj_schnorr_proofs[0]);
+        assert!(!verify_schnorr_with(
+            &exp,
+            eg,
+            proof.as_tuple(),
+            &keys.ccrj_choice_return_codes_encryption_public_key[1],
+            &i_aux
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_verify_exponentiation_with() {
+        let cc_payload = ControlComponentCodeSharesPayload::from_json(
+            &fs::read_to_string(
+                test_verification_card_set_path().join("controlComponentCodeSharesPayload.0.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let setup_payload = SetupComponentVerificationDataPayload::from_json(
+            &fs::read_to_string(
+                test_verification_card_set_path()
+                    .join("setupComponentVerificationDataPayload.0.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let cc = &cc_payload[0];
+        let eg = &cc.encryption_group;
+        let exp = BatchExponentiation::new(eg);
+        for share in cc.control_component_code_shares.iter().take(5) {
+            let setup_verif = setup_payload
+                .setup_component_verification_data
+                .iter()
+                .find(|d| d.verification_card_id == share.verification_card_id)
+                .unwrap();
+            let (gs, ys) = exponentiation_pcc_bases_and_images(
+                eg,
+                &setup_verif.encrypted_hashed_squared_partial_choice_return_codes,
+                &share.voter_choice_return_code_generation_public_key[0],
+                &share.exponentiated_encrypted_partial_choice_return_codes,
+            );
+            let proof = share
+                .encrypted_partial_choice_return_code_exponentiation_proof
+                .as_tuple();
+            let i_aux = exponentiation_pcc_i_aux(
+                &cc.election_event_id,
+                &share.verification_card_id,
+                cc.node_id,
+            );
+            assert!(verify_exponentiation_with(&exp, eg, &gs, &ys, proof, &i_aux).unwrap());
+            assert_eq!(
+                verify_exponentiation_with(&exp, eg, &gs, &ys, proof, &i_aux[..3]).unwrap(),
+                verify_exponentiation(eg, &gs, &ys, proof, &i_aux[..3].to_vec()).unwrap()
+            );
+            assert!(verify_exponentiation_with(&exp, eg, &gs[1..], &ys, proof, &i_aux).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_decryption() {
+        let payload = ControlComponentShufflePayload::from_json(
+            &fs::read_to_string(
+                test_ballot_box_path().join("controlComponentShufflePayload_1.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let cc_pk = ControlComponentPublicKeysPayload::from_json(
+            &fs::read_to_string(
+                test_dataset_tally_path()
+                    .join("setup")
+                    .join("controlComponentPublicKeysPayload.1.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .control_component_public_keys
+        .ccmj_election_public_key;
+        let i_aux = DecryptionProofContext::OnlineControlComponent { node_id: 1 }
+            .i_aux(&payload.election_event_id, &payload.ballot_box_id);
+        let ciphertext = &payload.verifiable_shuffle.shuffled_ciphertexts[0];
+        let m = &payload.verifiable_decryptions.ciphertexts[0].phis;
+        let mut proof = payload.verifiable_decryptions.decryption_proofs[0].clone();
+        let eg = &payload.encryption_group;
+        assert!(verify_decryption(eg, ciphertext, &cc_pk, m, &proof, &i_aux).unwrap());
+        assert!(!verify_decryption(eg, ciphertext, &cc_pk, m, &proof, &i_aux[..3]).unwrap());
+        assert!(verify_decryption(eg, ciphertext, &cc_pk, &m[1..], &proof, &i_aux).is_err());
+        assert!(verify_decryption(eg, ciphertext, &cc_pk[..1], m, &proof, &i_aux).is_err());
+        proof.e += 1;
+        assert!(!verify_decryption(eg, ciphertext, &cc_pk, m, &proof, &i_aux).unwrap());
+    }
+
+    #[test]
+    fn test_verify_plaintext_equality() {
+        let payload = ControlComponentBallotBoxPayload::from_json(
+            &fs::read_to_string(
+                test_ballot_box_path().join("controlComponentBallotBoxPayload_1.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let keys = SetupComponentPublicKeysPayload::from_json(
+            &fs::read_to_string(
+                test_dataset_tally_path()
+                    .join("setup")
+                    .join("setupComponentPublicKeysPayload.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .setup_component_public_keys;
+        let eg = &payload.encryption_group;
+        let vote = &payload.confirmed_encrypted_votes[0];
+        let statement = PlaintextEqualityStatement::for_vote(
+            eg,
+            &vote.exponentiated_encrypted_vote,
+            &vote.encrypted_partial_choice_return_codes,
+            &keys.election_public_key,
+            &keys.choice_return_codes_encryption_public_key,
+        )
+        .unwrap();
+        let i_aux = create_vote_i_aux(
+            &payload.election_event_id,
+            &vote.context_ids.verification_card_id,
+        );
+        let mut proof = vote.plaintext_equality_proof.clone();
+        assert!(verify_plaintext_equality(eg, &statement, &proof, &i_aux).unwrap());
+        assert!(!verify_plaintext_equality(eg, &statement, &proof, &i_aux[..2]).unwrap());
+        assert!(PlaintextEqualityStatement::for_vote(
+            eg,
+            &vote.encrypted_vote,
+            &vote.encrypted_partial_choice_return_codes,
+            &keys.election_public_key,
+            &keys.choice_return_codes_encryption_public_key,
+        )
+        .is_err());
+        proof.e += 1;
+        assert!(!verify_plaintext_equality(eg, &statement, &proof, &i_aux).unwrap());
+        proof.z.pop();
+        assert!(verify_plaintext_equality(eg, &statement, &proof, &i_aux).is_err());
+    }
+}
@@ -1,6 +1,9 @@
 //! Type that are used in many structures
 
 use super::{deserialize_seq_string_base64_to_seq_integer, deserialize_string_base64_to_integer};
+#[cfg(feature = "native")]
+use anyhow::anyhow;
+use anyhow::Context;
 use rug::Integer;
 use rust_ev_crypto_primitives::{ByteArray, Decode, EncryptionParameters, HashableMessage};
 use serde::Deserialize;
@@ -41,8 +44,34 @@ pub struct Signature {
 
 impl Signature {
     /// Get the signature as ByteArray
-    pub fn get_signature(&self) -> ByteArray {
-        ByteArray::base64_decode(&self.signature_contents).unwrap()
+    ///
+    /// Error if the contents of the signature are not valid base64
+    pub fn get_signature(&self) -> anyhow::Result<ByteArray> {
+        ByteArray::base64_decode(&self.signature_contents)
+            .context("The contents of the signature are not valid base64")
+    }
+
+    /// Signatures of a json payload, without decoding the other data of the payload
+    ///
+    /// The payload is an object with a signature, or a list of objects with a signature (e.g.
+    /// the control component code shares). Return the contents of the signatures
+    #[cfg(feature = "native")]
+    pub fn signatures_of_json(s: &str) -> anyhow::Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct Signed {
+            signature: Signature,
+        }
+        match s.trim_start().starts_with('[') {
+            true => serde_json::from_str::<Vec<Signed>>(s).map(|l| {
+                l.into_iter()
+                    .map(|e| e.signature.signature_contents)
+                    .collect()
+            }),
+            false => {
+                serde_json::from_str::<Signed>(s).map(|e| vec![e.signature.signature_contents])
+            }
+        }
+        .map_err(|e| anyhow!(e).context("Cannot read the signatures of the payload"))
     }
 }
 
@@ -109,6 +138,21 @@ impl<'a> From<&'a DecryptionProof> for HashableMessage<'a> {
     }
 }
 
+/// A plaintext equality proof (e,(z_0,z_1))
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlaintextEqualityProof {
+    #[serde(deserialize_with = "deserialize_string_base64_to_integer")]
+    pub e: Integer,
+    #[serde(deserialize_with = "deserialize_seq_string_base64_to_seq_integer")]
+    pub z: Vec<Integer>,
+}
+
+impl<'a> From<&'a PlaintextEqualityProof> for HashableMessage<'a> {
+    fn from(value: &'a PlaintextEqualityProof) -> Self {
+        Self::from(vec![Self::from(&(value.e)), Self::from(&(value.z))])
+    }
+}
+
 /// A exponentieted encrypted element (gamman, phi)
 #[derive(Deserialize, Debug, Clone)]
 pub struct ExponentiatedEncryptedElement {
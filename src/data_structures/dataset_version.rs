@@ -0,0 +1,211 @@
+//! Module implementing the version of the dataset and the parsing of the datetimes depending on it
+//!
+//! The datasets of version 1 contain naive datetimes (e.g. `2023-12-06T07:00:00`), whereas the
+//! newer versions contain the offset (e.g. `2023-12-06T07:00:00+01:00`). The version is detected
+//! from the dataset ([DatasetVersion::detect]) by the runner and given to the verifications in
+//! their context ([crate::verification::VerificationContext::dataset_version]).
+//!
+//! The datetimes are parsed in the format of any version. The datetimes with offset are converted
+//! to the local time, like the naive datetimes of the version 1. The datetimes in the format of
+//! another version than the version of the dataset ([datetimes_of_other_version]) are accepted
+//! with a warning in the lenient mode (default) and rejected in the strict mode (see
+//! [crate::config::Config::set_datetime_parsing_mode]).
+
+#[cfg(feature = "native")]
+use crate::file_structure::{
+    data_source::DataSource,
+    setup_directory::{SetupDirectory, SetupDirectoryTrait},
+};
+use anyhow::anyhow;
+#[cfg(feature = "native")]
+use anyhow::{bail, Context};
+use chrono::{DateTime, NaiveDateTime};
+use std::fmt::Display;
+#[cfg(feature = "native")]
+use std::{path::Path, sync::Arc};
+
+const NAIVE_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Version of the dataset, defining the format of the datetimes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatasetVersion {
+    /// Naive datetimes
+    V1,
+    /// Datetimes with offset
+    V2,
+}
+
+/// Behavior by a datetime in the format of another version than the version of the dataset
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum DateTimeParsingMode {
+    /// The datetime is rejected
+    Strict,
+    /// The datetime is accepted with a warning
+    #[default]
+    Lenient,
+}
+
+impl DatasetVersion {
+    /// Parse the datetime and return the version corresponding to its format
+    fn parse(value: &str) -> anyhow::Result<(NaiveDateTime, Self)> {
+        if let Ok(d) = NaiveDateTime::parse_from_str(value, NAIVE_DATETIME_FORMAT) {
+            return Ok((d, Self::V1));
+        }
+        DateTime::parse_from_rfc3339(value)
+            .map(|d| (d.naive_local(), Self::V2))
+            .map_err(|e| anyhow!(format!("{} is not a valid datetime: {}", value, e)))
+    }
+
+    /// Version corresponding to the format of the datetime
+    #[cfg(feature = "native")]
+    pub fn from_datetime(value: &str) -> anyhow::Result<Self> {
+        Self::parse(value).map(|(_, v)| v)
+    }
+
+    /// Detect the version of the dataset located in `path`, read from the source
+    ///
+    /// The version is given by the format of the start time in the election event context
+    #[cfg(feature = "native")]
+    pub fn detect(source: &Arc<dyn DataSource>, path: &Path) -> anyhow::Result<Self> {
+        let data = SetupDirectory::new_with_source(path, source.clone())
+            .election_event_context_payload_file()
+            .read_data()
+            .context("Cannot detect the version of the dataset")?;
+        let json: serde_json::Value = serde_json::from_str(&data)
+            .map_err(|e| anyhow!(e))
+            .context("Cannot detect the version of the dataset")?;
+        match json["electionEventContext"]["startTime"].as_str() {
+            Some(s) => Self::from_datetime(s).context("Cannot detect the version of the dataset"),
+            None => bail!("Cannot detect the version of the dataset: the start time is missing"),
+        }
+    }
+}
+
+impl Display for DatasetVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V1 => write!(f, "version 1 (naive datetimes)"),
+            Self::V2 => write!(f, "version 2 (datetimes with offset)"),
+        }
+    }
+}
+
+/// Parse a datetime, with or without offset. A datetime with offset is converted to its local
+/// time.
+///
+/// The format of the datetimes of the XML files is given by their schema and does not depend on
+/// the version of the dataset. The format of the datetimes of the payloads is verified against
+/// the version of the dataset with [datetimes_of_other_version].
+pub(super) fn parse_datetime(value: &str) -> anyhow::Result<NaiveDateTime> {
+    DatasetVersion::parse(value).map(|(res, _)| res)
+}
+
+/// The datetimes of the election event context (content of the payload) that are not in the
+/// format of `version`
+///
+/// Return a message per datetime
+///
+/// # Error
+/// If the content is not valid json or if a datetime is not valid
+#[cfg(feature = "native")]
+pub fn datetimes_of_other_version(
+    payload: &str,
+    version: DatasetVersion,
+) -> anyhow::Result<Vec<String>> {
+    let json: serde_json::Value = serde_json::from_str(payload).map_err(|e| anyhow!(e))?;
+    let context = &json["electionEventContext"];
+    let mut values = vec![&context["startTime"], &context["finishTime"]];
+    if let Some(vcs_contexts) = context["verificationCardSetContexts"].as_array() {
+        for c in vcs_contexts.iter() {
+            values.push(&c["ballotBoxStartTime"]);
+            values.push(&c["ballotBoxFinishTime"]);
+        }
+    }
+    let mut res = vec![];
+    for value in values.iter().filter_map(|v| v.as_str()) {
+        let found = DatasetVersion::from_datetime(value)?;
+        if found != version {
+            res.push(format!(
+                "The datetime {} has the format of the {}, but the dataset is {}",
+                value, found, version
+            ));
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::test::test_dataset_tally_path, file_structure::data_source::local_source};
+    use chrono::NaiveDate;
+
+    fn expected() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 12, 6)
+            .unwrap()
+            .and_hms_opt(7, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_from_datetime() {
+        assert_eq!(
+            DatasetVersion::from_datetime("2023-12-06T07:00:00").unwrap(),
+            DatasetVersion::V1
+        );
+        assert_eq!(
+            DatasetVersion::from_datetime("2023-12-06T07:00:00+01:00").unwrap(),
+            DatasetVersion::V2
+        );
+        assert!(DatasetVersion::from_datetime("2023-12-06").is_err());
+    }
+
+    #[test]
+    fn test_detect() {
+        let source = local_source();
+        assert_eq!(
+            DatasetVersion::detect(&source, &test_dataset_tally_path()).unwrap(),
+            DatasetVersion::V1
+        );
+        assert!(DatasetVersion::detect(&source, Path::new("./toto")).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_datetime("2023-12-06T07:00:00").unwrap(), expected());
+        assert_eq!(
+            parse_datetime("2023-12-06T07:00:00.000").unwrap(),
+            expected()
+        );
+        assert_eq!(
+            parse_datetime("2023-12-06T07:00:00+01:00").unwrap(),
+            expected()
+        );
+        assert!(parse_datetime("2023-12-06 07:00").is_err());
+    }
+
+    #[test]
+    fn test_datetimes_of_other_version() {
+        let payload = r#"{"electionEventContext": {
+            "startTime": "2023-12-06T07:00:00",
+            "finishTime": "2023-12-20T12:00:00+01:00",
+            "verificationCardSetContexts": [{
+                "ballotBoxStartTime": "2023-12-06T07:00:00",
+                "ballotBoxFinishTime": "2023-12-20T12:00:00"
+            }]
+        }}"#;
+        assert_eq!(
+            datetimes_of_other_version(payload, DatasetVersion::V1)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            datetimes_of_other_version(payload, DatasetVersion::V2)
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(datetimes_of_other_version("{", DatasetVersion::V1).is_err());
+    }
+}
@@ -3,17 +3,31 @@
 //! The module is separate in two module: [setup] and [tally]
 //!
 pub mod common_types;
+pub mod dataset_version;
 pub mod setup;
+#[cfg(feature = "native")]
 pub mod setup_or_tally;
+#[cfg(feature = "native")]
 pub mod tally;
+#[cfg(feature = "native")]
 mod xml;
 
+#[cfg(feature = "native")]
+pub(crate) use xml::SchemaValidator;
+#[cfg(feature = "native")]
+pub use xml::{
+    embedded_xsd_text, override_schema, override_schema_from_file, schema_resources, xsd_text,
+    SchemaKind, SchemaResource, SchemaVersion,
+};
+
+#[cfg(feature = "native")]
 use self::{
     setup::{
         control_component_code_shares_payload::ControlComponentCodeSharesPayload,
         control_component_public_keys_payload::ControlComponentPublicKeysPayload,
         election_event_configuration::ElectionEventConfiguration,
         election_event_context_payload::ElectionEventContextPayload,
+        primes_mapping_table_payload::PrimesMappingTablePayload,
         setup_component_public_keys_payload::SetupComponentPublicKeysPayload,
         setup_component_tally_data_payload::SetupComponentTallyDataPayload,
         setup_component_verification_data_payload::SetupComponentVerificationDataPayload,
@@ -28,37 +42,50 @@ use self::{
         VerifierTallyDataType,
     },
 };
+#[cfg(feature = "native")]
 use crate::file_structure::{file::File, FileReadMode, FileType};
 use anyhow::{anyhow, bail};
 use chrono::NaiveDateTime;
+#[cfg(feature = "native")]
 use roxmltree::Document;
 use rug::Integer;
-use rust_ev_crypto_primitives::{ByteArray, Decode, Hexa};
+#[cfg(feature = "native")]
+use rust_ev_crypto_primitives::Hexa;
+use rust_ev_crypto_primitives::{ByteArray, Decode};
 use serde::de::{Deserialize, Deserializer, Error};
+#[cfg(feature = "native")]
 use setup_or_tally::SetupOrTally;
-use std::path::Path;
+#[cfg(feature = "native")]
+use std::io::Read;
 
 /// The type VerifierData implement an option between [VerifierSetupData] and [VerifierTallyData]
+#[cfg(feature = "native")]
 pub type VerifierData = SetupOrTally<VerifierSetupData, VerifierTallyData>;
 
 /// The type VerifierDataType implement an option between [VerifierSetupDataType] and [VerifierTallyDataType]
+#[cfg(feature = "native")]
 pub type VerifierDataType = SetupOrTally<VerifierSetupDataType, VerifierTallyDataType>;
 
+#[cfg(feature = "native")]
 macro_rules! create_verifier_setup_data_type {
     ($p: ident, $s: ident) => {
         VerifierDataType::$p(VerifierSetupDataType::$s)
     };
 }
+#[cfg(feature = "native")]
 pub(crate) use create_verifier_setup_data_type;
 
+#[cfg(feature = "native")]
 macro_rules! create_verifier_tally_data_type {
     ($p: ident, $s: ident) => {
         VerifierDataType::$p(VerifierTallyDataType::$s)
     };
 }
+#[cfg(feature = "native")]
 pub(crate) use create_verifier_tally_data_type;
 
 /// Trait implementing the collection of the specific setup data type from the enum object
+#[cfg(feature = "native")]
 pub trait VerifierSetupDataTrait {
     fn setup_component_public_keys_payload(&self) -> Option<&SetupComponentPublicKeysPayload> {
         None
@@ -83,9 +110,13 @@ pub trait VerifierSetupDataTrait {
     fn election_event_configuration(&self) -> Option<&ElectionEventConfiguration> {
         None
     }
+    fn primes_mapping_table_payload(&self) -> Option<&PrimesMappingTablePayload> {
+        None
+    }
 }
 
 /// Trait implementing the collection of the specific tally data type from the enum object
+#[cfg(feature = "native")]
 pub trait VerifierTallyDataTrait {
     fn e_voting_decrypt(&self) -> Option<&EVotingDecrypt> {
         None
@@ -111,6 +142,52 @@ pub trait VerifierTallyDataTrait {
     }
 }
 
+/// Trait giving a uniform access to the identification of a payload
+///
+/// The functions return `None` if the identification is not relevant for the payload. It
+/// allows to write the consistency verifications on the ids once for all the payload types.
+#[cfg(feature = "native")]
+pub trait PayloadIdentityTrait {
+    /// Election event id of the payload
+    fn election_event_id(&self) -> Option<&String> {
+        None
+    }
+
+    /// Verification card set id or ballot box id of the payload
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        None
+    }
+
+    /// Node id of the control component that produced the payload
+    fn node_id(&self) -> Option<usize> {
+        None
+    }
+
+    /// Chunk id of the payload
+    fn chunk_id(&self) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(feature = "native")]
+impl<T: PayloadIdentityTrait> PayloadIdentityTrait for Box<T> {
+    fn election_event_id(&self) -> Option<&String> {
+        self.as_ref().election_event_id()
+    }
+
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        self.as_ref().vcs_or_bb_id()
+    }
+
+    fn node_id(&self) -> Option<usize> {
+        self.as_ref().node_id()
+    }
+
+    fn chunk_id(&self) -> Option<usize> {
+        self.as_ref().chunk_id()
+    }
+}
+
 /// A trait defining the necessary function to decode to the Verifier Data
 pub trait VerifierDataDecode: Sized {
     /// Decode the data from the file
@@ -122,6 +199,7 @@ pub trait VerifierDataDecode: Sized {
     ///
     /// # Return
     /// The decoded data or [anyhow::Result] if something wrong
+    #[cfg(feature = "native")]
     fn from_file(f: &File, t: &FileType, mode: &FileReadMode) -> anyhow::Result<Self> {
         match mode {
             FileReadMode::Memory => Self::from_file_memory(f, t),
@@ -137,6 +215,7 @@ pub trait VerifierDataDecode: Sized {
     ///
     /// # Return
     /// The decoded data or [anyhow::Result] if something wrong
+    #[cfg(feature = "native")]
     fn from_file_memory(f: &File, t: &FileType) -> anyhow::Result<Self> {
         let s = f.read_data().map_err(|e| {
             anyhow!(e).context(format!("Error reading data in file {}", f.to_str()))
@@ -160,12 +239,16 @@ pub trait VerifierDataDecode: Sized {
     ///
     /// # Return
     /// The decoded data or [anyhow::Result] if something wrong
+    #[cfg(feature = "native")]
     fn from_file_stream(f: &File, t: &FileType) -> anyhow::Result<Self> {
         match t {
             FileType::Json => {
-                bail!(format!("from_file not implemented for JSON Files"))
+                let reader = f
+                    .open_reader()
+                    .map_err(|e| e.context(format!("Cannot open file {}", f.to_str())))?;
+                Self::from_json_reader(reader)
             }
-            FileType::Xml => Self::from_xml_file(&f.get_path()),
+            FileType::Xml => Self::from_xml_file(f),
         }
     }
 
@@ -178,21 +261,34 @@ pub trait VerifierDataDecode: Sized {
         bail!(format!("from_json not implemented now"))
     }
 
+    /// Decode the data from a reader on a json file, without reading the file completely
+    /// in a string
+    ///
+    /// # Return
+    /// The decoded data or [anyhow::Result] if something wrong, e.g. if it is not allowed, or if an error
+    /// occured during the decoding
+    #[cfg(feature = "native")]
+    fn from_json_reader<R: Read>(_: R) -> anyhow::Result<Self> {
+        bail!(format!("from_json_reader not implemented now"))
+    }
+
     /// Decode the data from a xml [Document] (roxmltreee)
     ///
     /// # Return
     /// The decoded data or [anyhow::Result] if something wrong, e.g. if it is not allowed, or if an error
     /// occured during the decoding
+    #[cfg(feature = "native")]
     fn from_roxmltree<'a>(_: &'a Document<'a>) -> anyhow::Result<Self> {
         bail!(format!("from_roxmltree not implemented now"))
     }
 
-    /// Decode the data from a xml xml file
+    /// Decode the data from a xml file, read from the source of the file
     ///
     /// # Return
     /// The decoded data or [anyhow::Result] if something wrong, e.g. if it is not allowed, or if an error
     /// occured during the decoding
-    fn from_xml_file(_: &Path) -> anyhow::Result<Self> {
+    #[cfg(feature = "native")]
+    fn from_xml_file(_: &File) -> anyhow::Result<Self> {
         bail!(format!("from_xml_file not implemented now"))
     }
 }
@@ -205,11 +301,18 @@ macro_rules! implement_trait_verifier_data_json_decode {
                 serde_json::from_str(s)
                     .map_err(|e| anyhow!(e).context(format!("Cannot deserialize json")))
             }
+
+            #[cfg(feature = "native")]
+            fn from_json_reader<R: std::io::Read>(r: R) -> anyhow::Result<Self> {
+                serde_json::from_reader(r)
+                    .map_err(|e| anyhow!(e).context(format!("Cannot deserialize json")))
+            }
         }
     };
 }
 use implement_trait_verifier_data_json_decode;
 
+#[cfg(feature = "native")]
 impl VerifierSetupDataTrait for VerifierData {
     fn setup_component_public_keys_payload(&self) -> Option<&SetupComponentPublicKeysPayload> {
         match self {
@@ -261,8 +364,16 @@ impl VerifierSetupDataTrait for VerifierData {
             VerifierData::Tally(_) => None,
         }
     }
+
+    fn primes_mapping_table_payload(&self) -> Option<&PrimesMappingTablePayload> {
+        match self {
+            VerifierData::Setup(d) => d.primes_mapping_table_payload(),
+            VerifierData::Tally(_) => None,
+        }
+    }
 }
 
+#[cfg(feature = "native")]
 impl VerifierTallyDataTrait for VerifierData {
     fn e_voting_decrypt(&self) -> Option<&EVotingDecrypt> {
         match self {
@@ -308,7 +419,16 @@ impl VerifierTallyDataTrait for VerifierData {
     }
 }
 
+#[cfg(feature = "native")]
 impl VerifierDataType {
+    /// Type of the file (json or xml)
+    pub fn get_file_type(&self) -> FileType {
+        match self {
+            VerifierDataType::Setup(t) => t.get_file_type(),
+            VerifierDataType::Tally(t) => t.get_file_type(),
+        }
+    }
+
     /// Read VerifierDataType from a String as JSON
     pub fn verifier_data_from_file(&self, f: &File) -> anyhow::Result<VerifierData> {
         match self {
@@ -324,6 +444,7 @@ impl VerifierDataType {
     }
 }
 
+#[cfg(feature = "native")]
 fn deserialize_string_hex_to_integer<'de, D>(deserializer: D) -> Result<Integer, D::Error>
 where
     D: Deserializer<'de>,
@@ -350,10 +471,10 @@ where
 {
     let buf = String::deserialize(deserializer)?;
 
-    NaiveDateTime::parse_from_str(&buf, "%Y-%m-%dT%H:%M:%S")
-        .map_err(|e| Error::custom(e.to_string()))
+    dataset_version::parse_datetime(&buf).map_err(|e| Error::custom(e.to_string()))
 }
 
+#[cfg(feature = "native")]
 fn deserialize_seq_string_hex_to_seq_integer<'de, D>(
     deserializer: D,
 ) -> Result<Vec<Integer>, D::Error>
@@ -386,6 +507,7 @@ where
 }
 
 #[allow(dead_code)]
+#[cfg(feature = "native")]
 fn deserialize_seq_string_base64_to_seq_bytearray<'de, D>(
     deserializer: D,
 ) -> Result<Vec<ByteArray>, D::Error>
@@ -449,6 +571,7 @@ where
     deserializer.deserialize_seq(Visitor)
 }
 
+#[cfg(feature = "native")]
 fn deserialize_seq_seq_string_hex_to_seq_seq_integer<'de, D>(
     deserializer: D,
 ) -> Result<Vec<Vec<Integer>>, D::Error>
@@ -484,6 +607,7 @@ where
     deserializer.deserialize_seq(Visitor)
 }
 
+#[cfg(feature = "native")]
 fn deserialize_seq_seq_string_base64_to_seq_seq_integer<'de, D>(
     deserializer: D,
 ) -> Result<Vec<Vec<Integer>>, D::Error>
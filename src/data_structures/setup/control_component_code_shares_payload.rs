@@ -1,9 +1,9 @@
 use super::super::{
     common_types::{EncryptionParametersDef, ExponentiatedEncryptedElement, Proof, Signature},
     deserialize_seq_string_base64_to_seq_integer, implement_trait_verifier_data_json_decode,
-    VerifierDataDecode,
+    PayloadIdentityTrait, VerifierDataDecode,
 };
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use crate::signature::{CertificateAuthority, VerifiySignatureTrait};
 use anyhow::{anyhow, Context};
 use rug::Integer;
 use rust_ev_crypto_primitives::{
@@ -28,6 +28,24 @@ pub struct ControlComponentCodeSharesPayloadInner {
     pub signature: Signature,
 }
 
+impl PayloadIdentityTrait for ControlComponentCodeSharesPayloadInner {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        Some(&self.verification_card_set_id)
+    }
+
+    fn node_id(&self) -> Option<usize> {
+        Some(self.node_id)
+    }
+
+    fn chunk_id(&self) -> Option<usize> {
+        Some(self.chunk_id)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ControlComponentCodeShare {
@@ -100,7 +118,7 @@ impl<'a> VerifiySignatureTrait<'a> for ControlComponentCodeSharesPayloadInner {
         ))
     }
 
-    fn get_signature(&self) -> ByteArray {
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
         self.signature.get_signature()
     }
 }
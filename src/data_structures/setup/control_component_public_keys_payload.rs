@@ -1,9 +1,11 @@
+#[cfg(feature = "native")]
+use super::super::PayloadIdentityTrait;
 use super::super::{
     common_types::{EncryptionParametersDef, ProofUnderline, Signature},
     deserialize_seq_string_base64_to_seq_integer, implement_trait_verifier_data_json_decode,
     VerifierDataDecode,
 };
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use crate::signature::{CertificateAuthority, VerifiySignatureTrait};
 use anyhow::{anyhow, Context};
 use rug::Integer;
 use rust_ev_crypto_primitives::{
@@ -23,6 +25,17 @@ pub struct ControlComponentPublicKeysPayload {
 
 implement_trait_verifier_data_json_decode!(ControlComponentPublicKeysPayload);
 
+#[cfg(feature = "native")]
+impl PayloadIdentityTrait for ControlComponentPublicKeysPayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+
+    fn node_id(&self) -> Option<usize> {
+        Some(self.control_component_public_keys.node_id)
+    }
+}
+
 impl VerifyDomainTrait for ControlComponentPublicKeysPayload {}
 
 impl<'a> From<&'a ControlComponentPublicKeysPayload> for HashableMessage<'a> {
@@ -59,7 +72,7 @@ impl<'a> VerifiySignatureTrait<'a> for ControlComponentPublicKeysPayload {
         ))
     }
 
-    fn get_signature(&self) -> ByteArray {
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
         self.signature.get_signature()
     }
 }
@@ -1,64 +1,201 @@
 use super::super::{
-    xml::{hashable::XMLFileHashable, xml_read_to_end_into_buffer, SchemaKind},
-    VerifierDataDecode,
+    dataset_version::parse_datetime,
+    xml::{
+        hashable::{report_progress, XMLFileHashable},
+        SchemaKind,
+    },
+    PayloadIdentityTrait, VerifierDataDecode,
 };
 use crate::{
     data_structures::common_types::Signature,
-    direct_trust::{CertificateAuthority, VerifiySignatureTrait},
-};
-use anyhow::anyhow;
-use quick_xml::{
-    de::from_str as xml_de_from_str,
-    events::{BytesEnd, BytesStart, Event},
-    Reader,
+    file_structure::file::File,
+    signature::{CertificateAuthority, VerifiySignatureTrait},
 };
+use anyhow::{anyhow, Context};
+use chrono::{NaiveDate, NaiveDateTime};
+use quick_xml::{de::from_str as xml_de_from_str, events::Event, Reader, Writer};
 use rust_ev_crypto_primitives::{
     ByteArray, HashableMessage, RecursiveHashTrait, VerifyDomainTrait,
 };
 use serde::Deserialize;
-use std::path::{Path, PathBuf};
+use std::{cell::RefCell, collections::HashSet};
 
 #[derive(Debug, Clone)]
 pub struct ElectionEventConfiguration {
-    pub path: PathBuf,
+    pub file: File,
     pub header: ConfigHeader,
     pub signature: Signature,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct ConfigHeader {
-    pub file_date: String,
-    pub voter_total: usize,
-    pub partial_delivery: Option<PartialDelivery>,
+impl PayloadIdentityTrait for ElectionEventConfiguration {}
+
+/// Ballot (Vorlage) of a vote in the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ballot {
+    pub identification: String,
+    pub questions: Vec<BallotQuestion>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct PartialDelivery {
-    pub voter_from: usize,
-    pub voter_to: usize,
+/// Question of a ballot, with the identifications of its answers (including the hidden answers)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BallotQuestion {
+    pub identification: String,
+    pub answer_identifications: Vec<String>,
 }
 
-impl VerifyDomainTrait for ElectionEventConfiguration {}
+/// Authorization of the configuration, corresponding to a ballot box
+///
+/// The dates are in local time (the offset of the configuration is dropped), like the times of
+/// the ballot boxes in the election event context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authorization {
+    pub identification: String,
+    pub name: String,
+    /// The ballot box is a test ballot box
+    pub test: bool,
+    /// Opening of the ballot box
+    pub from_date: NaiveDateTime,
+    /// Official closing of the ballot box
+    pub to_date: NaiveDateTime,
+    /// Grace period after the closing, in seconds
+    pub grace_period: usize,
+}
 
-impl VerifierDataDecode for ElectionEventConfiguration {
-    fn from_xml_file(p: &Path) -> anyhow::Result<Self> {
-        let mut reader = Reader::from_file(p).map_err(|e| {
-            anyhow!(e).context(format!(
-                "Error creating xml reader for file {}",
-                p.to_str().unwrap()
-            ))
-        })?;
-        reader.trim_text(true);
+/// Fields of an authorization during the reading of the file
+#[derive(Default)]
+struct AuthorizationFields {
+    identification: Option<String>,
+    name: Option<String>,
+    test: Option<bool>,
+    from_date: Option<NaiveDateTime>,
+    to_date: Option<NaiveDateTime>,
+    grace_period: Option<usize>,
+}
+
+impl AuthorizationFields {
+    fn set(&mut self, tag: &[u8], text: String) -> anyhow::Result<()> {
+        match tag {
+            b"authorizationIdentification" => self.identification = Some(text),
+            b"authorizationName" => self.name = Some(text),
+            b"authorizationTest" => {
+                self.test = Some(
+                    text.parse::<bool>()
+                        .with_context(|| format!("authorizationTest {} is not a boolean", text))?,
+                )
+            }
+            b"authorizationFromDate" => self.from_date = Some(parse_datetime(&text)?),
+            b"authorizationToDate" => self.to_date = Some(parse_datetime(&text)?),
+            b"authorizationGracePeriod" => {
+                self.grace_period = Some(text.parse::<usize>().with_context(|| {
+                    format!("authorizationGracePeriod {} is not a number", text)
+                })?)
+            }
+            _ => (),
+        }
+        Ok(())
+    }
 
-        let header_tag = "header";
-        let signature_tag = "signature";
-        let mut signature_started = false;
+    /// The authorization. The test flag and the grace period are optional in the schema
+    fn finish(self) -> anyhow::Result<Authorization> {
+        let identification = self
+            .identification
+            .ok_or_else(|| anyhow!("authorizationIdentification not found"))?;
+        let missing = |tag: &str| anyhow!("{} not found in authorization {}", tag, identification);
+        Ok(Authorization {
+            name: self.name.ok_or_else(|| missing("authorizationName"))?,
+            test: self.test.unwrap_or(false),
+            from_date: self
+                .from_date
+                .ok_or_else(|| missing("authorizationFromDate"))?,
+            to_date: self.to_date.ok_or_else(|| missing("authorizationToDate"))?,
+            grace_period: self.grace_period.unwrap_or(0),
+            identification,
+        })
+    }
+}
 
-        let mut signature: Option<Signature> = None;
-        let mut config_header: Option<ConfigHeader> = None;
+impl Ballot {
+    /// Find the question with the given identification
+    pub fn find_question(&self, identification: &str) -> Option<&BallotQuestion> {
+        self.questions
+            .iter()
+            .find(|q| q.identification == identification)
+    }
+}
+
+impl ElectionEventConfiguration {
+    /// All the identifications defined in the configuration
+    ///
+    /// The identifications are the values of the tags whose name ends with `Identification`
+    /// (e.g. `questionIdentification`, `answerIdentification`, `candidateIdentification`).
+    /// The actual voting options are built with these identifications.
+    ///
+    /// The file is read in streaming mode.
+    pub fn identifications(&self) -> anyhow::Result<HashSet<String>> {
+        Ok(self
+            .texts_of_tags(|name| name.ends_with(b"Identification"))?
+            .into_iter()
+            .collect())
+    }
+
+    /// The names of the members of the electoral board
+    ///
+    /// The file is read in streaming mode.
+    pub fn electoral_board_members(&self) -> anyhow::Result<Vec<String>> {
+        self.texts_of_tags(|name| name == b"electoralBoardMemberName")
+    }
 
+    /// The identifications of the authorizations
+    ///
+    /// Each verification card set corresponds to an authorization. The alias of the
+    /// verification card set is the identification of the authorization prefixed with `vcs_`.
+    ///
+    /// The file is read in streaming mode.
+    pub fn authorization_identifications(&self) -> anyhow::Result<Vec<String>> {
+        self.texts_of_tags(|name| name == b"authorizationIdentification")
+    }
+
+    /// The date of the contest (election event)
+    ///
+    /// The file is read in streaming mode.
+    pub fn contest_date(&self) -> anyhow::Result<NaiveDate> {
+        let value = self
+            .texts_of_tags(|name| name == b"contestDate")?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("contestDate not found"))?;
+        NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+            .map_err(|e| anyhow!(e).context(format!("contestDate {} is not a valid date", value)))
+    }
+
+    /// The identifications of the elections
+    ///
+    /// The file is read in streaming mode.
+    pub fn election_identifications(&self) -> anyhow::Result<Vec<String>> {
+        self.texts_of_tags(|name| name == b"electionIdentification")
+    }
+
+    /// The ballots of the votes, in the order of the file
+    ///
+    /// A vote can contain several ballots, each of them with its own questions (one for a standard
+    /// ballot, several for a variant ballot).
+    ///
+    /// The file is read in streaming mode.
+    pub fn ballots(&self) -> anyhow::Result<Vec<Ballot>> {
+        let mut reader = self
+            .file
+            .open_reader()
+            .map(Reader::from_reader)
+            .map_err(|e| {
+                e.context(format!(
+                    "Error creating xml reader for file {}",
+                    self.file.to_str()
+                ))
+            })?;
+        reader.trim_text(true);
+        let mut res: Vec<Ballot> = vec![];
+        let mut current: Option<Ballot> = None;
+        let mut tag: Vec<u8> = vec![];
         let mut buf = Vec::new();
         loop {
             match reader.read_event_into(&mut buf) {
@@ -68,60 +205,326 @@ impl VerifierDataDecode for ElectionEventConfiguration {
                 }
                 Ok(Event::Eof) => break,
                 Ok(Event::Start(e)) => {
-                    if e == BytesStart::new(signature_tag) {
-                        signature_started = true;
+                    tag = e.local_name().as_ref().to_vec();
+                    if tag == b"ballot" {
+                        current = Some(Ballot {
+                            identification: String::new(),
+                            questions: vec![],
+                        });
                     }
-                    if e == BytesStart::new(header_tag) {
-                        let header_bytes = xml_read_to_end_into_buffer(
-                            &mut reader,
-                            &BytesStart::new(header_tag),
-                            &mut buf,
-                        )
-                        .map_err(|e| {
-                            anyhow!(e).context("Error reading header bytes".to_string())
-                        })?;
-                        config_header = Some(
-                            xml_de_from_str(&String::from_utf8_lossy(&header_bytes)).map_err(
-                                |e| anyhow!(e).context("Error deserializing header".to_string()),
-                            )?,
-                        );
+                }
+                Ok(Event::Text(e)) => {
+                    if let Some(ballot) = current.as_mut() {
+                        let text = || -> anyhow::Result<String> {
+                            Ok(e.unescape()
+                                .map_err(|e| anyhow!(e).context("Error reading text"))?
+                                .into_owned())
+                        };
+                        match tag.as_slice() {
+                            b"ballotIdentification" => ballot.identification = text()?,
+                            b"questionIdentification" => ballot.questions.push(BallotQuestion {
+                                identification: text()?,
+                                answer_identifications: vec![],
+                            }),
+                            b"answerIdentification" => match ballot.questions.last_mut() {
+                                Some(q) => q.answer_identifications.push(text()?),
+                                None => {
+                                    return Err(anyhow!(format!(
+                                        "Answer found before a question in ballot {}",
+                                        ballot.identification
+                                    )))
+                                }
+                            },
+                            _ => (),
+                        }
                     }
                 }
                 Ok(Event::End(e)) => {
-                    if e == BytesEnd::new(signature_tag) {
-                        signature_started = false;
+                    tag.clear();
+                    if e.local_name().as_ref() == b"ballot" {
+                        if let Some(ballot) = current.take() {
+                            res.push(ballot)
+                        }
                     }
                 }
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(res)
+    }
+
+    /// The authorizations (ballot boxes) with their opening times, in the order of the file
+    ///
+    /// The file is read in streaming mode.
+    pub fn authorizations(&self) -> anyhow::Result<Vec<Authorization>> {
+        let mut reader = self
+            .file
+            .open_reader()
+            .map(Reader::from_reader)
+            .map_err(|e| {
+                e.context(format!(
+                    "Error creating xml reader for file {}",
+                    self.file.to_str()
+                ))
+            })?;
+        reader.trim_text(true);
+        let mut res = vec![];
+        let mut current: Option<AuthorizationFields> = None;
+        let mut tag: Vec<u8> = vec![];
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Err(e) => {
+                    return Err(anyhow!(e)
+                        .context(format!("Error at position {}", reader.buffer_position())))
+                }
+                Ok(Event::Eof) => break,
+                Ok(Event::Start(e)) => {
+                    tag = e.local_name().as_ref().to_vec();
+                    if tag == b"authorization" {
+                        current = Some(AuthorizationFields::default());
+                    }
+                }
+                Ok(Event::Text(e)) => {
+                    if let Some(fields) = current.as_mut() {
+                        let text = e
+                            .unescape()
+                            .map_err(|e| anyhow!(e).context("Error reading text"))?
+                            .into_owned();
+                        fields.set(&tag, text)?;
+                    }
+                }
+                Ok(Event::End(e)) => {
+                    tag.clear();
+                    if e.local_name().as_ref() == b"authorization" {
+                        if let Some(fields) = current.take() {
+                            res.push(fields.finish()?)
+                        }
+                    }
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(res)
+    }
+
+    /// The values of the tags whose local name satisfies the predicate, in the order of the file
+    fn texts_of_tags(&self, predicate: impl Fn(&[u8]) -> bool) -> anyhow::Result<Vec<String>> {
+        let mut reader = self
+            .file
+            .open_reader()
+            .map(Reader::from_reader)
+            .map_err(|e| {
+                e.context(format!(
+                    "Error creating xml reader for file {}",
+                    self.file.to_str()
+                ))
+            })?;
+        reader.trim_text(true);
+        let mut res = vec![];
+        let mut in_tag = false;
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Err(e) => {
+                    return Err(anyhow!(e)
+                        .context(format!("Error at position {}", reader.buffer_position())))
+                }
+                Ok(Event::Eof) => break,
+                Ok(Event::Start(e)) => in_tag = predicate(e.local_name().as_ref()),
                 Ok(Event::Text(e)) => {
-                    if signature_started {
-                        signature = Some(Signature {
-                            signature_contents: e.unescape().unwrap().into_owned(),
-                        })
+                    if in_tag {
+                        res.push(
+                            e.unescape()
+                                .map_err(|e| anyhow!(e).context("Error reading text"))?
+                                .into_owned(),
+                        );
                     }
                 }
-                // There are several other `Event`s we do not consider here
+                Ok(Event::End(_)) => in_tag = false,
                 _ => (),
             }
-            // if we don't keep a borrow elsewhere, we can clear the buffer to keep memory usage low
             buf.clear();
         }
-        if config_header.is_none() {
-            return Err(anyhow!("Header not found"));
+        Ok(res)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHeader {
+    pub file_date: String,
+    pub voter_total: usize,
+    pub partial_delivery: Option<PartialDelivery>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialDelivery {
+    pub voter_from: usize,
+    pub voter_to: usize,
+}
+
+impl VerifyDomainTrait for ElectionEventConfiguration {}
+
+/// Local name of the header tag
+const HEADER_TAG: &[u8] = b"header";
+/// Name of the signature tag, excluded from the digest
+const SIGNATURE_TAG: &str = "signature";
+
+/// Decoder of the configuration from the xml events
+///
+/// The decoder is fed either by a plain reader or by the events of the hashing pass (see
+/// [XMLFileHashable::try_hash_with_tap])
+#[derive(Default)]
+struct ConfigDecoder {
+    header_writer: Option<Writer<Vec<u8>>>,
+    header_depth: usize,
+    header_bytes: Option<Vec<u8>>,
+    in_signature: bool,
+    signature: Option<String>,
+    error: Option<anyhow::Error>,
+}
+
+impl ConfigDecoder {
+    /// Consume the next event. The first error is kept for [Self::finish]
+    fn consume(&mut self, event: &Event) {
+        if self.error.is_none() {
+            if let Err(e) = self.try_consume(event) {
+                self.error = Some(e)
+            }
+        }
+    }
+
+    fn try_consume(&mut self, event: &Event) -> anyhow::Result<()> {
+        match event {
+            Event::Start(e) if e.local_name().as_ref() == HEADER_TAG => {
+                if self.header_writer.is_some() {
+                    self.header_depth += 1
+                } else if self.header_bytes.is_none() {
+                    self.header_writer = Some(Writer::new(Vec::new()));
+                    self.header_depth = 0;
+                }
+            }
+            Event::Start(e) if e.local_name().as_ref() == SIGNATURE_TAG.as_bytes() => {
+                self.in_signature = true
+            }
+            Event::End(e) if e.local_name().as_ref() == SIGNATURE_TAG.as_bytes() => {
+                self.in_signature = false
+            }
+            Event::Text(e) if self.in_signature => {
+                let text = e
+                    .unescape()
+                    .map_err(|e| anyhow!(e).context("Error reading signature"))?;
+                if !text.trim().is_empty() {
+                    self.signature = Some(text.trim().to_string())
+                }
+            }
+            _ => (),
         }
-        if signature.is_none() {
-            return Err(anyhow!("Signature not found"));
+        if let Some(w) = self.header_writer.as_mut() {
+            w.write_event(event)
+                .map_err(|e| anyhow!(e).context("Error reading header bytes"))?;
+            if let Event::End(e) = event {
+                if e.local_name().as_ref() == HEADER_TAG {
+                    match self.header_depth {
+                        0 => {
+                            self.header_bytes = self.header_writer.take().map(|w| w.into_inner());
+                        }
+                        _ => self.header_depth -= 1,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The configuration decoded from the consumed events
+    fn finish(self, f: &File) -> anyhow::Result<ElectionEventConfiguration> {
+        if let Some(e) = self.error {
+            return Err(e);
         }
-        Ok(Self {
-            path: p.to_path_buf(),
-            header: config_header.unwrap(),
-            signature: signature.unwrap(),
+        let header_bytes = self
+            .header_bytes
+            .ok_or_else(|| anyhow!("Header not found"))?;
+        let header = xml_de_from_str(&String::from_utf8_lossy(&header_bytes))
+            .map_err(|e| anyhow!(e).context("Error deserializing header".to_string()))?;
+        let signature = self
+            .signature
+            .ok_or_else(|| anyhow!("Signature not found"))?;
+        Ok(ElectionEventConfiguration {
+            file: f.clone(),
+            header,
+            signature: Signature {
+                signature_contents: signature,
+            },
         })
     }
 }
 
+impl ElectionEventConfiguration {
+    /// Hashable of the file, excluding the signature. The digest is cached
+    fn xml_hashable(f: &File) -> anyhow::Result<XMLFileHashable> {
+        XMLFileHashable::new(&f.get_path(), &SchemaKind::Config, SIGNATURE_TAG)
+            .map(|h| h.with_source(f.source().clone()).with_cache())
+    }
+
+    /// Decode the file with a plain reader, without calculating the digest
+    fn decode(f: &File) -> anyhow::Result<Self> {
+        let mut reader = f
+            .open_reader()
+            .map(Reader::from_reader)
+            .map_err(|e| e.context(format!("Error creating xml reader for file {}", f.to_str())))?;
+        reader.trim_text(true);
+        let mut decoder = ConfigDecoder::default();
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Err(e) => {
+                    return Err(anyhow!(e)
+                        .context(format!("Error at position {}", reader.buffer_position())))
+                }
+                Ok(Event::Eof) => break,
+                Ok(e) => decoder.consume(&e),
+            }
+            // if we don't keep a borrow elsewhere, we can clear the buffer to keep memory usage low
+            buf.clear();
+        }
+        decoder.finish(f)
+    }
+}
+
+impl VerifierDataDecode for ElectionEventConfiguration {
+    /// Decode the configuration
+    ///
+    /// At the first decoding, the file is decoded during the calculation of the digest (one
+    /// pass over the file), and the digest is cached for the verification of the signature. The
+    /// next decodings use a plain reader. If the digest cannot be calculated, the file is
+    /// decoded with a plain reader.
+    fn from_xml_file(f: &File) -> anyhow::Result<Self> {
+        if let Ok(hashable) = Self::xml_hashable(f) {
+            if hashable.cached_digest().is_none() {
+                let decoder = RefCell::new(ConfigDecoder::default());
+                if hashable
+                    .try_hash_with_tap(&|e| decoder.borrow_mut().consume(e))
+                    .is_ok()
+                {
+                    if let Ok(res) = decoder.into_inner().finish(f) {
+                        return Ok(res);
+                    }
+                }
+            }
+        }
+        Self::decode(f)
+    }
+}
+
 impl<'a> VerifiySignatureTrait<'a> for ElectionEventConfiguration {
     fn get_hashable(&'a self) -> anyhow::Result<HashableMessage<'a>> {
-        let hashable = XMLFileHashable::new(&self.path, &SchemaKind::Config, "signature");
+        let hashable =
+            Self::xml_hashable(&self.file)?.with_progress(report_progress("configuration"));
         let hash = hashable.try_hash()?;
         Ok(HashableMessage::Hashed(hash))
     }
@@ -134,7 +537,7 @@ impl<'a> VerifiySignatureTrait<'a> for ElectionEventConfiguration {
         Ok(String::from(CertificateAuthority::Canton))
     }
 
-    fn get_signature(&self) -> ByteArray {
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
         self.signature.get_signature()
     }
 }
@@ -142,14 +545,131 @@ impl<'a> VerifiySignatureTrait<'a> for ElectionEventConfiguration {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::config::test::test_dataset_tally_path;
+    use crate::{
+        config::test::test_dataset_tally_path,
+        data_structures::{setup::VerifierSetupDataType, VerifierDataType},
+    };
+
+    fn config_file() -> File {
+        File::new(
+            &test_dataset_tally_path().join("setup"),
+            &VerifierDataType::Setup(VerifierSetupDataType::ElectionEventConfiguration),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_decode_with_digest() {
+        let file = config_file();
+        let config = ElectionEventConfiguration::from_xml_file(&file).unwrap();
+        assert!(ElectionEventConfiguration::xml_hashable(&file)
+            .unwrap()
+            .cached_digest()
+            .is_some());
+        let plain = ElectionEventConfiguration::decode(&file).unwrap();
+        assert_eq!(
+            config.signature.signature_contents,
+            plain.signature.signature_contents
+        );
+        assert_eq!(
+            config.electoral_board_members().unwrap(),
+            plain.electoral_board_members().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_identifications() {
+        let file = config_file();
+        let config = ElectionEventConfiguration::from_xml_file(&file).unwrap();
+        let ids = config.identifications().unwrap();
+        assert!(ids.contains("806f52e6-9d49-4906-b2a8-7c89dfdf53e2"));
+        assert!(ids.contains("3aa38c9e-6e93-3159-91e1-c3da90681572"));
+        assert!(ids.contains("nrw_test"));
+        assert!(!ids.contains("toto"));
+    }
+
+    #[test]
+    fn test_electoral_board_members() {
+        let file = config_file();
+        let config = ElectionEventConfiguration::from_xml_file(&file).unwrap();
+        assert_eq!(
+            config.electoral_board_members().unwrap(),
+            vec!["EA1".to_string(), "EA2".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_authorization_identifications() {
+        let file = config_file();
+        let config = ElectionEventConfiguration::from_xml_file(&file).unwrap();
+        let ids = config.authorization_identifications().unwrap();
+        assert_eq!(ids.len(), 4);
+        assert_eq!(
+            config.contest_date().unwrap(),
+            NaiveDate::from_ymd_opt(2027, 11, 25).unwrap()
+        );
+        assert!(ids.contains(&"516e2551-ee42-3401-9988-7dfebd0ac0c0".to_string()));
+    }
+
+    #[test]
+    fn test_authorizations() {
+        let file = config_file();
+        let config = ElectionEventConfiguration::from_xml_file(&file).unwrap();
+        let authorizations = config.authorizations().unwrap();
+        assert_eq!(
+            authorizations
+                .iter()
+                .map(|a| a.identification.clone())
+                .collect::<Vec<_>>(),
+            config.authorization_identifications().unwrap()
+        );
+        let a = &authorizations[0];
+        assert_eq!(a.name, "CH_Testurne1");
+        assert!(a.test);
+        assert_eq!(
+            a.from_date,
+            NaiveDateTime::parse_from_str("2023-12-06T07:00:00", "%Y-%m-%dT%H:%M:%S").unwrap()
+        );
+        assert_eq!(
+            a.to_date,
+            NaiveDateTime::parse_from_str("2027-11-25T23:00:00", "%Y-%m-%dT%H:%M:%S").unwrap()
+        );
+        assert_eq!(a.grace_period, 900);
+    }
+
+    #[test]
+    fn test_ballots() {
+        let file = config_file();
+        let config = ElectionEventConfiguration::from_xml_file(&file).unwrap();
+        let ballots = config.ballots().unwrap();
+        assert_eq!(ballots.len(), 2);
+        assert_eq!(
+            ballots[0].identification,
+            "9cbcbd59-94ad-4f48-bc7d-4bc38e5c0c51"
+        );
+        assert_eq!(ballots[0].questions.len(), 1);
+        assert_eq!(ballots[1].questions.len(), 3);
+        let q = ballots[0]
+            .find_question("806f52e6-9d49-4906-b2a8-7c89dfdf53e2")
+            .unwrap();
+        assert_eq!(q.answer_identifications.len(), 3);
+        assert!(ballots[0]
+            .find_question("1129f74b-1e49-4636-9731-84dba618bec6")
+            .is_none());
+        assert!(ballots[1]
+            .questions
+            .iter()
+            .all(|q| q.answer_identifications.len() == 3));
+        assert_eq!(
+            config.election_identifications().unwrap(),
+            vec!["nrw_test".to_string(), "majorz_test".to_string()]
+        );
+    }
 
     #[test]
     fn read_data_set() {
-        let path = test_dataset_tally_path()
-            .join("setup")
-            .join("configuration-anonymized.xml");
-        let config_res = ElectionEventConfiguration::from_xml_file(&path);
+        let file = config_file();
+        let config_res = ElectionEventConfiguration::from_xml_file(&file);
         assert!(config_res.is_ok());
         let config = config_res.unwrap();
         assert_eq!(config.header.voter_total, 43);
@@ -1,16 +1,20 @@
+#[cfg(feature = "native")]
+use super::super::PayloadIdentityTrait;
 use super::super::{
     common_types::{EncryptionParametersDef, Signature},
     deserialize_string_string_to_datetime, implement_trait_verifier_data_json_decode,
     VerifierDataDecode,
 };
 use crate::config::Config as VerifierConfig;
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use crate::signature::{CertificateAuthority, VerifiySignatureTrait};
 use anyhow::anyhow;
 use chrono::NaiveDateTime;
 use rust_ev_crypto_primitives::{
     ByteArray, EncryptionParameters, HashableMessage, VerifyDomainTrait,
 };
 use serde::Deserialize;
+#[cfg(feature = "native")]
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -25,6 +29,13 @@ pub struct ElectionEventContextPayload {
 
 implement_trait_verifier_data_json_decode!(ElectionEventContextPayload);
 
+#[cfg(feature = "native")]
+impl PayloadIdentityTrait for ElectionEventContextPayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_context.election_event_id)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ElectionEventContext {
@@ -39,6 +50,10 @@ pub struct ElectionEventContext {
     pub maximum_number_of_voting_options: usize,
     pub maximum_number_of_selections: usize,
     pub maximum_number_of_write_ins_plus_one: usize,
+    /// Order of the control components (node ids) in the mix net. Optional in the context
+    #[serde(default)]
+    #[cfg_attr(not(feature = "native"), allow(dead_code))]
+    pub mixing_order: Option<Vec<usize>>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -65,6 +80,7 @@ pub struct PrimesMappingTable {
     #[serde(with = "EncryptionParametersDef")]
     pub encryption_group: EncryptionParameters,
     pub p_table: Vec<PTableElement>,
+    #[cfg_attr(not(feature = "native"), allow(dead_code))]
     pub number_of_voting_options: usize,
 }
 
@@ -78,6 +94,20 @@ pub struct PTableElement {
     pub correctness_information: String,
 }
 
+/// Identifications building the actual voting option
+///
+/// The actual voting option has the form `<id>|<id>` or `<id>|<id>|<accumulation>` for the candidates
+/// that can be accumulated.
+#[cfg(feature = "native")]
+pub fn actual_voting_option_identifications(actual_voting_option: &str) -> Vec<&str> {
+    let mut res: Vec<&str> = actual_voting_option.split('|').collect();
+    if res.len() == 3 && res[2].chars().all(|c| c.is_ascii_digit()) {
+        res.pop();
+    }
+    res
+}
+
+#[cfg(feature = "native")]
 impl VerificationCardSetContext {
     pub fn number_of_voters(&self) -> usize {
         self.number_of_voting_cards
@@ -86,8 +116,21 @@ impl VerificationCardSetContext {
     pub fn number_of_voting_options(&self) -> usize {
         self.primes_mapping_table.p_table.len()
     }
+
+    /// Number of voting options per correctness information
+    ///
+    /// The correctness information is the identification of the question (for a ballot of a vote)
+    /// or of the election the voting option belongs to.
+    pub fn number_of_voting_options_per_correctness_information(&self) -> BTreeMap<&str, usize> {
+        let mut res = BTreeMap::new();
+        for e in self.primes_mapping_table.p_table.iter() {
+            *res.entry(e.correctness_information.as_str()).or_insert(0) += 1;
+        }
+        res
+    }
 }
 
+#[cfg(feature = "native")]
 impl ElectionEventContext {
     pub fn find_verification_card_set_context<'a>(
         &'a self,
@@ -97,6 +140,46 @@ impl ElectionEventContext {
             .iter()
             .find(|c| &c.verification_card_set_id == vcs_id)
     }
+
+    /// Expected number of voting cards for the given verification card set
+    ///
+    /// Return `None` if the verification card set is not found
+    pub fn number_of_voting_cards(&self, vcs_id: &String) -> Option<usize> {
+        self.find_verification_card_set_context(vcs_id)
+            .map(|c| c.number_of_voters())
+    }
+
+    /// Expected number of voting options for the given verification card set
+    ///
+    /// Return `None` if the verification card set is not found
+    pub fn number_of_voting_options(&self, vcs_id: &String) -> Option<usize> {
+        self.find_verification_card_set_context(vcs_id)
+            .map(|c| c.number_of_voting_options())
+    }
+
+    /// Order of the control components (node ids) in the mix net
+    ///
+    /// If the context does not define the order, the order of the specification is returned
+    /// (see [VerifierConfig::default_mixing_order])
+    pub fn mixing_order(&self) -> Vec<usize> {
+        match &self.mixing_order {
+            Some(o) => o.clone(),
+            None => VerifierConfig::default_mixing_order(),
+        }
+    }
+
+    /// Expected number of voting cards and of voting options per verification card set id
+    pub fn vcs_expectations(&self) -> HashMap<String, (usize, usize)> {
+        self.verification_card_set_contexts
+            .iter()
+            .map(|c| {
+                (
+                    c.verification_card_set_id.clone(),
+                    (c.number_of_voters(), c.number_of_voting_options()),
+                )
+            })
+            .collect()
+    }
 }
 
 impl VerifyDomainTrait for ElectionEventContextPayload {
@@ -162,7 +245,7 @@ impl<'a> VerifiySignatureTrait<'a> for ElectionEventContextPayload {
         Ok(String::from(CertificateAuthority::SdmConfig))
     }
 
-    fn get_signature(&self) -> ByteArray {
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
         self.signature.get_signature()
     }
 }
@@ -228,6 +311,19 @@ mod test {
     use crate::config::test::test_dataset_tally_path;
     use std::fs;
 
+    #[test]
+    fn test_actual_voting_option_identifications() {
+        assert_eq!(actual_voting_option_identifications("a|b"), vec!["a", "b"]);
+        assert_eq!(
+            actual_voting_option_identifications("a|b|1"),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            actual_voting_option_identifications("a|99"),
+            vec!["a", "99"]
+        );
+    }
+
     #[test]
     fn read_data_set() {
         let path = test_dataset_tally_path()
@@ -240,4 +336,74 @@ mod test {
         }
         assert!(r_eec.is_ok())
     }
+
+    #[test]
+    fn vcs_expectations() {
+        let path = test_dataset_tally_path()
+            .join("setup")
+            .join("electionEventContextPayload.json");
+        let json = fs::read_to_string(path).unwrap();
+        let eec = ElectionEventContextPayload::from_json(&json)
+            .unwrap()
+            .election_event_context;
+        let expectations = eec.vcs_expectations();
+        assert_eq!(expectations.len(), eec.verification_card_set_contexts.len());
+        for c in eec.verification_card_set_contexts.iter() {
+            let id = &c.verification_card_set_id;
+            assert_eq!(
+                eec.number_of_voting_cards(id),
+                Some(c.number_of_voting_cards)
+            );
+            assert_eq!(
+                eec.number_of_voting_options(id),
+                Some(c.primes_mapping_table.p_table.len())
+            );
+            assert_eq!(
+                expectations.get(id),
+                Some(&(
+                    c.number_of_voting_cards,
+                    c.primes_mapping_table.p_table.len()
+                ))
+            );
+        }
+        assert!(eec.number_of_voting_cards(&"toto".to_string()).is_none());
+    }
+
+    #[test]
+    fn voting_options_per_correctness_information() {
+        let path = test_dataset_tally_path()
+            .join("setup")
+            .join("electionEventContextPayload.json");
+        let json = fs::read_to_string(path).unwrap();
+        let eec = ElectionEventContextPayload::from_json(&json)
+            .unwrap()
+            .election_event_context;
+        for c in eec.verification_card_set_contexts.iter() {
+            let options = c.number_of_voting_options_per_correctness_information();
+            assert_eq!(
+                options.values().sum::<usize>(),
+                c.number_of_voting_options()
+            );
+        }
+        let options = eec.verification_card_set_contexts[0]
+            .number_of_voting_options_per_correctness_information();
+        assert_eq!(
+            options.get("806f52e6-9d49-4906-b2a8-7c89dfdf53e2"),
+            Some(&3)
+        );
+    }
+
+    #[test]
+    fn mixing_order() {
+        let path = test_dataset_tally_path()
+            .join("setup")
+            .join("electionEventContextPayload.json");
+        let json = fs::read_to_string(path).unwrap();
+        let mut eec = ElectionEventContextPayload::from_json(&json)
+            .unwrap()
+            .election_event_context;
+        assert_eq!(eec.mixing_order(), vec![1, 2, 3, 4]);
+        eec.mixing_order = Some(vec![2, 1, 4, 3]);
+        assert_eq!(eec.mixing_order(), vec![2, 1, 4, 3]);
+    }
 }
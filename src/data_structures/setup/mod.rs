@@ -1,28 +1,40 @@
 //! Module implementing the data structures of the setup directory
 
+#[cfg(feature = "native")]
 pub mod control_component_code_shares_payload;
 pub mod control_component_public_keys_payload;
+#[cfg(feature = "native")]
 pub mod election_event_configuration;
 pub mod election_event_context_payload;
+#[cfg(feature = "native")]
+pub mod primes_mapping_table_payload;
 pub mod setup_component_public_keys_payload;
+#[cfg(feature = "native")]
 pub mod setup_component_tally_data_payload;
+#[cfg(feature = "native")]
 pub mod setup_component_verification_data_payload;
 
+#[cfg(feature = "native")]
 use self::{
     control_component_code_shares_payload::ControlComponentCodeSharesPayload,
     control_component_public_keys_payload::ControlComponentPublicKeysPayload,
     election_event_configuration::ElectionEventConfiguration,
     election_event_context_payload::ElectionEventContextPayload,
+    primes_mapping_table_payload::PrimesMappingTablePayload,
     setup_component_public_keys_payload::SetupComponentPublicKeysPayload,
     setup_component_tally_data_payload::SetupComponentTallyDataPayload,
     setup_component_verification_data_payload::SetupComponentVerificationDataPayload,
 };
+#[cfg(feature = "native")]
 use super::{VerifierDataDecode, VerifierSetupDataTrait};
-use crate::file_structure::{file::File, FileReadMode, FileType};
+#[cfg(feature = "native")]
+use crate::file_structure::{file::File, FileType};
+#[cfg(feature = "native")]
 use enum_kinds::EnumKind;
 
 /// Types of the setup directory
 /// An enum VerifierSetupDataType is automatically creating
+#[cfg(feature = "native")]
 #[derive(Clone, EnumKind)]
 #[enum_kind(VerifierSetupDataType)]
 pub enum VerifierSetupData {
@@ -33,8 +45,10 @@ pub enum VerifierSetupData {
     ControlComponentCodeSharesPayload(ControlComponentCodeSharesPayload),
     SetupComponentTallyDataPayload(SetupComponentTallyDataPayload),
     ElectionEventConfiguration(ElectionEventConfiguration),
+    PrimesMappingTablePayload(PrimesMappingTablePayload),
 }
 
+#[cfg(feature = "native")]
 impl VerifierSetupDataType {
     /// Get the type of the file for the [VerifierSetupData]
     pub fn get_file_type(&self) -> FileType {
@@ -46,19 +60,7 @@ impl VerifierSetupDataType {
             Self::ControlComponentCodeSharesPayload => FileType::Json,
             Self::SetupComponentTallyDataPayload => FileType::Json,
             Self::ElectionEventConfiguration => FileType::Xml,
-        }
-    }
-
-    /// Get the read mode of the file for the [VerifierSetupData]
-    pub fn get_file_read_mode(&self) -> FileReadMode {
-        match self {
-            Self::ElectionEventContextPayload => FileReadMode::Memory,
-            Self::SetupComponentPublicKeysPayload => FileReadMode::Memory,
-            Self::ControlComponentPublicKeysPayload => FileReadMode::Memory,
-            Self::SetupComponentVerificationDataPayload => FileReadMode::Memory,
-            Self::ControlComponentCodeSharesPayload => FileReadMode::Memory,
-            Self::SetupComponentTallyDataPayload => FileReadMode::Memory,
-            Self::ElectionEventConfiguration => FileReadMode::Streaming,
+            Self::PrimesMappingTablePayload => FileType::Json,
         }
     }
 
@@ -68,26 +70,18 @@ impl VerifierSetupDataType {
     pub fn verifier_data_from_file(&self, f: &File) -> anyhow::Result<VerifierSetupData> {
         match self {
             VerifierSetupDataType::ElectionEventContextPayload => {
-                ElectionEventContextPayload::from_file(
-                    f,
-                    &self.get_file_type(),
-                    &self.get_file_read_mode(),
-                )
-                .map(VerifierSetupData::ElectionEventContextPayload)
+                ElectionEventContextPayload::from_file(f, &self.get_file_type(), &f.read_mode())
+                    .map(VerifierSetupData::ElectionEventContextPayload)
             }
             VerifierSetupDataType::SetupComponentPublicKeysPayload => {
-                SetupComponentPublicKeysPayload::from_file(
-                    f,
-                    &self.get_file_type(),
-                    &self.get_file_read_mode(),
-                )
-                .map(VerifierSetupData::SetupComponentPublicKeysPayload)
+                SetupComponentPublicKeysPayload::from_file(f, &self.get_file_type(), &f.read_mode())
+                    .map(VerifierSetupData::SetupComponentPublicKeysPayload)
             }
             VerifierSetupDataType::ControlComponentPublicKeysPayload => {
                 ControlComponentPublicKeysPayload::from_file(
                     f,
                     &self.get_file_type(),
-                    &self.get_file_read_mode(),
+                    &f.read_mode(),
                 )
                 .map(VerifierSetupData::ControlComponentPublicKeysPayload)
             }
@@ -95,7 +89,7 @@ impl VerifierSetupDataType {
                 SetupComponentVerificationDataPayload::from_file(
                     f,
                     &self.get_file_type(),
-                    &self.get_file_read_mode(),
+                    &f.read_mode(),
                 )
                 .map(VerifierSetupData::SetupComponentVerificationDataPayload)
             }
@@ -103,30 +97,27 @@ impl VerifierSetupDataType {
                 ControlComponentCodeSharesPayload::from_file(
                     f,
                     &self.get_file_type(),
-                    &self.get_file_read_mode(),
+                    &f.read_mode(),
                 )
                 .map(VerifierSetupData::ControlComponentCodeSharesPayload)
             }
             VerifierSetupDataType::SetupComponentTallyDataPayload => {
-                SetupComponentTallyDataPayload::from_file(
-                    f,
-                    &self.get_file_type(),
-                    &self.get_file_read_mode(),
-                )
-                .map(VerifierSetupData::SetupComponentTallyDataPayload)
+                SetupComponentTallyDataPayload::from_file(f, &self.get_file_type(), &f.read_mode())
+                    .map(VerifierSetupData::SetupComponentTallyDataPayload)
             }
             VerifierSetupDataType::ElectionEventConfiguration => {
-                ElectionEventConfiguration::from_file(
-                    f,
-                    &self.get_file_type(),
-                    &self.get_file_read_mode(),
-                )
-                .map(VerifierSetupData::ElectionEventConfiguration)
+                ElectionEventConfiguration::from_file(f, &self.get_file_type(), &f.read_mode())
+                    .map(VerifierSetupData::ElectionEventConfiguration)
+            }
+            VerifierSetupDataType::PrimesMappingTablePayload => {
+                PrimesMappingTablePayload::from_file(f, &self.get_file_type(), &f.read_mode())
+                    .map(VerifierSetupData::PrimesMappingTablePayload)
             }
         }
     }
 }
 
+#[cfg(feature = "native")]
 impl VerifierSetupDataTrait for VerifierSetupData {
     fn setup_component_public_keys_payload(&self) -> Option<&SetupComponentPublicKeysPayload> {
         if let VerifierSetupData::SetupComponentPublicKeysPayload(d) = self {
@@ -178,4 +169,11 @@ impl VerifierSetupDataTrait for VerifierSetupData {
         }
         None
     }
+
+    fn primes_mapping_table_payload(&self) -> Option<&PrimesMappingTablePayload> {
+        if let VerifierSetupData::PrimesMappingTablePayload(d) = self {
+            return Some(d);
+        }
+        None
+    }
 }
@@ -0,0 +1,60 @@
+use super::{
+    super::{
+        common_types::EncryptionParametersDef, implement_trait_verifier_data_json_decode,
+        PayloadIdentityTrait, VerifierDataDecode,
+    },
+    election_event_context_payload::PTableElement,
+};
+use anyhow::anyhow;
+use rust_ev_crypto_primitives::{EncryptionParameters, VerifyDomainTrait};
+use serde::Deserialize;
+
+/// Mapping of the actual voting options to their encoding (the small primes) for the whole
+/// election event
+///
+/// The file is only delivered in the newer datasets. It must correspond to the primes mapping
+/// tables of the verification card sets in the election event context
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PrimesMappingTablePayload {
+    #[serde(with = "EncryptionParametersDef")]
+    pub encryption_group: EncryptionParameters,
+    pub election_event_id: String,
+    pub p_table: Vec<PTableElement>,
+}
+
+implement_trait_verifier_data_json_decode!(PrimesMappingTablePayload);
+
+impl PayloadIdentityTrait for PrimesMappingTablePayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+}
+
+impl VerifyDomainTrait for PrimesMappingTablePayload {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_data_set() {
+        let json = r#"{
+            "encryptionGroup": {"p": "Fw==", "q": "Cw==", "g": "Ag=="},
+            "electionEventId": "0B1E4A4B5F0C4C4E8B3E3C8A3E4D5F6A",
+            "pTable": [
+                {
+                    "actualVotingOption": "a|b",
+                    "encodedVotingOption": 5,
+                    "semanticInformation": "NON_BLANK|yes",
+                    "correctnessInformation": "b"
+                }
+            ]
+        }"#;
+        let r_eec = PrimesMappingTablePayload::from_json(json);
+        assert!(r_eec.is_ok(), "{:?}", r_eec.err());
+        let payload = r_eec.unwrap();
+        assert_eq!(payload.p_table.len(), 1);
+        assert_eq!(payload.p_table[0].encoded_voting_option, 5);
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "native")]
+use super::super::PayloadIdentityTrait;
 use super::{
     super::{
         common_types::{EncryptionParametersDef, ProofUnderline, Signature},
@@ -6,7 +8,7 @@ use super::{
     },
     control_component_public_keys_payload::ControlComponentPublicKeys,
 };
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use crate::signature::{CertificateAuthority, VerifiySignatureTrait};
 use anyhow::anyhow;
 use rug::Integer;
 use rust_ev_crypto_primitives::{
@@ -26,6 +28,13 @@ pub struct SetupComponentPublicKeysPayload {
 
 implement_trait_verifier_data_json_decode!(SetupComponentPublicKeysPayload);
 
+#[cfg(feature = "native")]
+impl PayloadIdentityTrait for SetupComponentPublicKeysPayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+}
+
 impl VerifyDomainTrait for SetupComponentPublicKeysPayload {}
 
 impl<'a> From<&'a SetupComponentPublicKeysPayload> for HashableMessage<'a> {
@@ -55,7 +64,7 @@ impl<'a> VerifiySignatureTrait<'a> for SetupComponentPublicKeysPayload {
         Ok(String::from(CertificateAuthority::SdmConfig))
     }
 
-    fn get_signature(&self) -> ByteArray {
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
         self.signature.get_signature()
     }
 }
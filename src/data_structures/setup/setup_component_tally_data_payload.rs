@@ -1,9 +1,9 @@
 use super::super::{
     common_types::{EncryptionParametersDef, Signature},
     deserialize_seq_seq_string_base64_to_seq_seq_integer,
-    implement_trait_verifier_data_json_decode, VerifierDataDecode,
+    implement_trait_verifier_data_json_decode, PayloadIdentityTrait, VerifierDataDecode,
 };
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use crate::signature::{CertificateAuthority, VerifiySignatureTrait};
 use anyhow::anyhow;
 use rug::Integer;
 use rust_ev_crypto_primitives::{
@@ -27,6 +27,16 @@ pub struct SetupComponentTallyDataPayload {
 
 implement_trait_verifier_data_json_decode!(SetupComponentTallyDataPayload);
 
+impl PayloadIdentityTrait for SetupComponentTallyDataPayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        Some(&self.verification_card_set_id)
+    }
+}
+
 impl VerifyDomainTrait for SetupComponentTallyDataPayload {}
 
 impl<'a> From<&'a SetupComponentTallyDataPayload> for HashableMessage<'a> {
@@ -67,7 +77,7 @@ impl<'a> VerifiySignatureTrait<'a> for SetupComponentTallyDataPayload {
         Ok(String::from(CertificateAuthority::SdmConfig))
     }
 
-    fn get_signature(&self) -> ByteArray {
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
         self.signature.get_signature()
     }
 }
@@ -1,9 +1,9 @@
 use super::super::{
     common_types::{EncryptionParametersDef, ExponentiatedEncryptedElement, Signature},
     deserialize_seq_string_base64_to_seq_integer, implement_trait_verifier_data_json_decode,
-    VerifierDataDecode,
+    PayloadIdentityTrait, VerifierDataDecode,
 };
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use crate::signature::{CertificateAuthority, VerifiySignatureTrait};
 use anyhow::anyhow;
 use rug::Integer;
 use rust_ev_crypto_primitives::{
@@ -26,6 +26,20 @@ pub struct SetupComponentVerificationDataPayload {
 
 implement_trait_verifier_data_json_decode!(SetupComponentVerificationDataPayload);
 
+impl PayloadIdentityTrait for SetupComponentVerificationDataPayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        Some(&self.verification_card_set_id)
+    }
+
+    fn chunk_id(&self) -> Option<usize> {
+        Some(self.chunk_id)
+    }
+}
+
 impl SetupComponentVerificationDataPayload {
     #[allow(dead_code)]
     pub fn find_setup_component_verification_data_inner<'a>(
@@ -89,7 +103,7 @@ impl<'a> VerifiySignatureTrait<'a> for SetupComponentVerificationDataPayload {
         Ok(String::from(CertificateAuthority::SdmConfig))
     }
 
-    fn get_signature(&self) -> ByteArray {
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
         self.signature.get_signature()
     }
 }
@@ -1,7 +1,7 @@
 //! Module implementing [SetupOrTally]
 
 /// Generic Enum that is a type of setup or tally
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SetupOrTally<S, T> {
     Setup(S),
     Tally(T),
@@ -22,27 +22,19 @@ impl<S: Clone, T: Clone> SetupOrTally<S, T> {
         !self.is_setup()
     }
 
-    /// Unwrap setup and give a reference to S
-    ///
-    /// panic if type is tally
-    pub fn unwrap_setup(&self) -> &S {
+    /// Give a reference to S, or `None` if type is tally
+    pub fn setup(&self) -> Option<&S> {
         match self {
-            SetupOrTally::Setup(s) => s,
-            SetupOrTally::Tally(_) => {
-                panic!("called `SetupOrTally::unwrap_setup()` on a `Tally` value")
-            }
+            SetupOrTally::Setup(s) => Some(s),
+            SetupOrTally::Tally(_) => None,
         }
     }
 
-    /// Unwrap tally and give a reference to S
-    ///
-    /// panic if type is seup
-    pub fn unwrap_tally(&self) -> &T {
+    /// Give a reference to T, or `None` if type is setup
+    pub fn tally(&self) -> Option<&T> {
         match self {
-            SetupOrTally::Setup(_) => {
-                panic!("called `SetupOrTally::unwrap_tally()` on a `Setup` value")
-            }
-            SetupOrTally::Tally(t) => t,
+            SetupOrTally::Setup(_) => None,
+            SetupOrTally::Tally(t) => Some(t),
         }
     }
 }
@@ -1,8 +1,8 @@
 use super::super::{
     common_types::{EncryptionParametersDef, ExponentiatedEncryptedElement, Signature},
-    implement_trait_verifier_data_json_decode, VerifierDataDecode,
+    implement_trait_verifier_data_json_decode, PayloadIdentityTrait, VerifierDataDecode,
 };
-use crate::data_structures::common_types::{DecryptionProof, Proof};
+use crate::data_structures::common_types::{PlaintextEqualityProof, Proof};
 use anyhow::anyhow;
 use rust_ev_crypto_primitives::EncryptionParameters;
 use serde::Deserialize;
@@ -20,6 +20,20 @@ pub struct ControlComponentBallotBoxPayload {
 }
 implement_trait_verifier_data_json_decode!(ControlComponentBallotBoxPayload);
 
+impl PayloadIdentityTrait for ControlComponentBallotBoxPayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        Some(&self.ballot_box_id)
+    }
+
+    fn node_id(&self) -> Option<usize> {
+        Some(self.node_id)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfirmedEncryptedVote {
@@ -28,7 +42,7 @@ pub struct ConfirmedEncryptedVote {
     pub exponentiated_encrypted_vote: ExponentiatedEncryptedElement,
     pub encrypted_partial_choice_return_codes: ExponentiatedEncryptedElement,
     pub exponentiation_proof: Proof,
-    pub plaintext_equality_proof: DecryptionProof,
+    pub plaintext_equality_proof: PlaintextEqualityProof,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -1,6 +1,6 @@
 use super::super::{
     common_types::{EncryptionParametersDef, ExponentiatedEncryptedElement, Signature},
-    implement_trait_verifier_data_json_decode, VerifierDataDecode,
+    implement_trait_verifier_data_json_decode, PayloadIdentityTrait, VerifierDataDecode,
 };
 use super::tally_component_shuffle_payload::VerifiableShuffle;
 use crate::data_structures::common_types::DecryptionProof;
@@ -22,6 +22,20 @@ pub struct ControlComponentShufflePayload {
 }
 implement_trait_verifier_data_json_decode!(ControlComponentShufflePayload);
 
+impl PayloadIdentityTrait for ControlComponentShufflePayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        Some(&self.ballot_box_id)
+    }
+
+    fn node_id(&self) -> Option<usize> {
+        Some(self.node_id)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct VerifiableDecryptions {
@@ -1,27 +1,417 @@
-use super::super::{
-    xml::{hashable::XMLFileHashable, SchemaKind},
-    VerifierDataDecode,
+//! Module implementing the decrypted results of the e-voting system (`evoting-decrypt_*.xml`)
+//!
+//! The file contains the decrypted ballots per ballot box, counting circle and domain of
+//! influence. The elections are given in election groups (each ballot of a group contains one
+//! ballot per election). The structure of the schema evoting-decrypt-1-3, where the ballots are
+//! given per election, is read too: each election is then a group containing only this election.
+//! The version of the schema of a file is given by [EVotingDecrypt::schema_version].
+//!
+//! The file is read in streaming mode.
+
+use super::{
+    super::{
+        common_types::Signature,
+        xml::{
+            hashable::{report_progress, XMLFileHashable},
+            SchemaKind, SchemaVersion,
+        },
+        PayloadIdentityTrait, VerifierDataDecode,
+    },
+    last_mut,
+};
+use crate::{
+    file_structure::file::File,
+    signature::{CertificateAuthority, VerifiySignatureTrait},
 };
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use anyhow::{anyhow, bail, Context};
+use quick_xml::{events::Event, Reader};
 use rust_ev_crypto_primitives::{ByteArray, HashableMessage, RecursiveHashTrait};
-use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone)]
 pub struct EVotingDecrypt {
-    pub path: PathBuf,
+    pub file: File,
+    pub contest_identification: String,
+    pub cast_ballots: usize,
+    pub ballot_boxes: Vec<DecryptBallotBox>,
+    pub signature: Signature,
 }
 
-impl VerifierDataDecode for EVotingDecrypt {
-    fn from_xml_file(p: &Path) -> anyhow::Result<Self> {
+#[derive(Debug, Clone, Default)]
+pub struct DecryptBallotBox {
+    pub ballot_box_identification: String,
+    pub counting_circles: Vec<DecryptCountingCircle>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecryptCountingCircle {
+    pub counting_circle_identification: String,
+    pub domains_of_influence: Vec<DecryptDomainOfInfluence>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecryptDomainOfInfluence {
+    pub domain_of_influence_identification: String,
+    pub votes: Vec<DecryptVote>,
+    pub election_groups: Vec<DecryptElectionGroup>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecryptVote {
+    pub vote_identification: String,
+    pub ballots: Vec<DecryptVoteBallot>,
+}
+
+/// Decrypted ballot of a vote
+#[derive(Debug, Clone, Default)]
+pub struct DecryptVoteBallot {
+    pub chosen_answer_identifications: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecryptElectionGroup {
+    pub election_group_identification: String,
+    pub ballots: Vec<DecryptElectionGroupBallot>,
+}
+
+/// Decrypted ballot of an election group, containing one ballot per election
+#[derive(Debug, Clone, Default)]
+pub struct DecryptElectionGroupBallot {
+    pub ballot_elections: Vec<DecryptBallotElection>,
+}
+
+/// Decrypted ballot of an election
+#[derive(Debug, Clone, Default)]
+pub struct DecryptBallotElection {
+    pub election_identification: String,
+    pub chosen_list_identification: Option<String>,
+    pub chosen_candidate_list_identifications: Vec<String>,
+    pub chosen_candidate_identifications: Vec<String>,
+    pub chosen_write_ins_candidate_values: Vec<String>,
+}
+
+impl PayloadIdentityTrait for EVotingDecrypt {}
+
+impl EVotingDecrypt {
+    /// Version of the schema of the decrypt file given in `xml`
+    ///
+    /// The elections are given in election groups from the version 1.3 (evoting-decrypt-1-4),
+    /// per election before (evoting-decrypt-1-3)
+    pub fn schema_version(xml: &str) -> SchemaVersion {
+        let mut reader = Reader::from_str(xml);
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) | Ok(Event::Empty(e))
+                    if e.local_name().as_ref() == b"electionGroup" =>
+                {
+                    return SchemaVersion::V1_3
+                }
+                Ok(Event::Start(e)) | Ok(Event::Empty(e))
+                    if e.local_name().as_ref() == b"election" =>
+                {
+                    return SchemaVersion::V1_2
+                }
+                Ok(Event::Eof) | Err(_) => return SchemaVersion::default(),
+                _ => (),
+            }
+        }
+    }
+
+    /// The ballot box with the given id. `None` if not found
+    pub fn ballot_box(&self, id: &str) -> Option<&DecryptBallotBox> {
+        self.ballot_boxes
+            .iter()
+            .find(|bb| bb.ballot_box_identification == id)
+    }
+
+    /// Ids of the ballot boxes
+    pub fn ballot_box_ids(&self) -> Vec<&str> {
+        self.ballot_boxes
+            .iter()
+            .map(|bb| bb.ballot_box_identification.as_str())
+            .collect()
+    }
+
+    /// Iterator over the domains of influence of all the ballot boxes, with the id of the
+    /// counting circle
+    pub fn domains_of_influence(&self) -> impl Iterator<Item = (&str, &DecryptDomainOfInfluence)> {
+        self.ballot_boxes.iter().flat_map(|bb| {
+            bb.counting_circles.iter().flat_map(|cc| {
+                cc.domains_of_influence
+                    .iter()
+                    .map(|doi| (cc.counting_circle_identification.as_str(), doi))
+            })
+        })
+    }
+
+    /// Number of times each answer is chosen, over all the ballot boxes
+    pub fn chosen_answers_count(&self) -> BTreeMap<String, usize> {
+        let mut res = BTreeMap::new();
+        for a in self
+            .domains_of_influence()
+            .flat_map(|(_, doi)| doi.votes.iter())
+            .flat_map(|v| v.ballots.iter())
+            .flat_map(|b| b.chosen_answer_identifications.iter())
+        {
+            *res.entry(a.clone()).or_default() += 1;
+        }
+        res
+    }
+
+    /// Number of times each candidate is chosen, over all the ballot boxes
+    ///
+    /// The candidates are counted both from the list (`chosenCandidateListIdentification`) and
+    /// from the choice without list (`chosenCandidateIdentification`)
+    pub fn chosen_candidates_count(&self) -> BTreeMap<String, usize> {
+        let mut res = BTreeMap::new();
+        for c in self.ballot_elections().flat_map(|b| {
+            b.chosen_candidate_list_identifications
+                .iter()
+                .chain(b.chosen_candidate_identifications.iter())
+        }) {
+            *res.entry(c.clone()).or_default() += 1;
+        }
+        res
+    }
+
+    /// Iterator over the ballots of all the elections
+    pub fn ballot_elections(&self) -> impl Iterator<Item = &DecryptBallotElection> {
+        self.domains_of_influence()
+            .flat_map(|(_, doi)| doi.election_groups.iter())
+            .flat_map(|g| g.ballots.iter())
+            .flat_map(|b| b.ballot_elections.iter())
+    }
+}
+
+impl DecryptBallotBox {
+    /// Number of decrypted ballots in the ballot box
+    ///
+    /// Each voter casts one ballot per vote and per election group of its domains of influence.
+    /// The number of ballots is then the maximal number of ballots for the same vote or election
+    /// group in a counting circle, summed over the counting circles.
+    pub fn number_of_ballots(&self) -> usize {
+        self.counting_circles
+            .iter()
+            .map(|cc| {
+                cc.domains_of_influence
+                    .iter()
+                    .flat_map(|doi| {
+                        doi.votes
+                            .iter()
+                            .map(|v| v.ballots.len())
+                            .chain(doi.election_groups.iter().map(|g| g.ballots.len()))
+                    })
+                    .max()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+}
+
+/// State of the reading of the file
+#[derive(Default)]
+struct DecryptReader {
+    /// Local names of the open elements
+    stack: Vec<String>,
+    contest_identification: Option<String>,
+    cast_ballots: Option<usize>,
+    ballot_boxes: Vec<DecryptBallotBox>,
+    signature: Option<String>,
+}
+
+impl DecryptReader {
+    fn parent(&self) -> Option<&str> {
+        self.stack.iter().rev().nth(1).map(|s| s.as_str())
+    }
+
+    fn last_doi(&mut self) -> anyhow::Result<&mut DecryptDomainOfInfluence> {
+        let bb = last_mut(&mut self.ballot_boxes, "countingCircle")?;
+        let cc = last_mut(&mut bb.counting_circles, "domainOfInfluence")?;
+        last_mut(&mut cc.domains_of_influence, "vote or election")
+    }
+
+    fn last_vote(&mut self) -> anyhow::Result<&mut DecryptVote> {
+        last_mut(&mut self.last_doi()?.votes, "ballot")
+    }
+
+    fn last_group(&mut self) -> anyhow::Result<&mut DecryptElectionGroup> {
+        last_mut(&mut self.last_doi()?.election_groups, "ballot")
+    }
+
+    fn last_ballot_election(&mut self) -> anyhow::Result<&mut DecryptBallotElection> {
+        let group = self.last_group()?;
+        let ballot = last_mut(&mut group.ballots, "ballotElection")?;
+        last_mut(&mut ballot.ballot_elections, "chosen element")
+    }
+
+    fn start(&mut self, name: &str) -> anyhow::Result<()> {
+        self.stack.push(name.to_string());
+        match name {
+            "ballotsBox" => self.ballot_boxes.push(DecryptBallotBox::default()),
+            "countingCircle" => last_mut(&mut self.ballot_boxes, name)?
+                .counting_circles
+                .push(DecryptCountingCircle::default()),
+            "domainOfInfluence" => {
+                let bb = last_mut(&mut self.ballot_boxes, name)?;
+                last_mut(&mut bb.counting_circles, name)?
+                    .domains_of_influence
+                    .push(DecryptDomainOfInfluence::default())
+            }
+            "vote" => self.last_doi()?.votes.push(DecryptVote::default()),
+            "electionGroup" | "election" => self
+                .last_doi()?
+                .election_groups
+                .push(DecryptElectionGroup::default()),
+            "ballot" => match self.parent().map(|s| s.to_string()).as_deref() {
+                Some("vote") => self.last_vote()?.ballots.push(DecryptVoteBallot::default()),
+                Some("electionGroup") => self
+                    .last_group()?
+                    .ballots
+                    .push(DecryptElectionGroupBallot::default()),
+                Some("election") => {
+                    let group = self.last_group()?;
+                    let election_identification = group.election_group_identification.clone();
+                    group.ballots.push(DecryptElectionGroupBallot {
+                        ballot_elections: vec![DecryptBallotElection {
+                            election_identification,
+                            ..Default::default()
+                        }],
+                    })
+                }
+                _ => bail!("Element ballot found outside a vote or an election"),
+            },
+            "ballotElection" => {
+                let group = self.last_group()?;
+                last_mut(&mut group.ballots, name)?
+                    .ballot_elections
+                    .push(DecryptBallotElection::default())
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, text: String) -> anyhow::Result<()> {
+        let name = match self.stack.last() {
+            Some(n) => n.clone(),
+            None => return Ok(()),
+        };
+        match name.as_str() {
+            "contestIdentification" => self.contest_identification = Some(text),
+            "castBallots" => {
+                self.cast_ballots = Some(
+                    text.parse::<usize>()
+                        .with_context(|| format!("castBallots {} is not a number", text))?,
+                )
+            }
+            "signature" => self.signature = Some(text),
+            "ballotBoxIdentification" => {
+                last_mut(&mut self.ballot_boxes, &name)?.ballot_box_identification = text
+            }
+            "countingCircleIdentification" => {
+                let bb = last_mut(&mut self.ballot_boxes, &name)?;
+                last_mut(&mut bb.counting_circles, &name)?.counting_circle_identification = text
+            }
+            "domainOfInfluenceIdentification" => {
+                self.last_doi()?.domain_of_influence_identification = text
+            }
+            "voteIdentification" => self.last_vote()?.vote_identification = text,
+            "chosenAnswerIdentification" => last_mut(&mut self.last_vote()?.ballots, &name)?
+                .chosen_answer_identifications
+                .push(text),
+            "electionGroupIdentification" => {
+                self.last_group()?.election_group_identification = text
+            }
+            "electionIdentification" => match self.parent().map(|s| s.to_string()).as_deref() {
+                Some("election") => self.last_group()?.election_group_identification = text,
+                _ => self.last_ballot_election()?.election_identification = text,
+            },
+            "chosenListIdentification" => {
+                self.last_ballot_election()?.chosen_list_identification = Some(text)
+            }
+            "chosenCandidateListIdentification" => self
+                .last_ballot_election()?
+                .chosen_candidate_list_identifications
+                .push(text),
+            "chosenCandidateIdentification" => self
+                .last_ballot_election()?
+                .chosen_candidate_identifications
+                .push(text),
+            "chosenWriteInsCandidateValue" => self
+                .last_ballot_election()?
+                .chosen_write_ins_candidate_values
+                .push(text),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) {
+        self.stack.pop();
+    }
+
+    fn finish(self, f: &File) -> anyhow::Result<EVotingDecrypt> {
         Ok(EVotingDecrypt {
-            path: p.to_path_buf(),
+            file: f.clone(),
+            contest_identification: self
+                .contest_identification
+                .ok_or_else(|| anyhow!("contestIdentification not found"))?,
+            cast_ballots: self
+                .cast_ballots
+                .ok_or_else(|| anyhow!("castBallots not found"))?,
+            ballot_boxes: self.ballot_boxes,
+            signature: Signature {
+                signature_contents: self
+                    .signature
+                    .ok_or_else(|| anyhow!("Signature not found"))?,
+            },
         })
     }
 }
 
+impl VerifierDataDecode for EVotingDecrypt {
+    fn from_xml_file(f: &File) -> anyhow::Result<Self> {
+        let mut reader = f
+            .open_reader()
+            .map(Reader::from_reader)
+            .map_err(|e| e.context(format!("Error creating xml reader for file {}", f.to_str())))?;
+        reader.trim_text(true);
+        let mut state = DecryptReader::default();
+        let mut buf = Vec::new();
+        loop {
+            let position = reader.buffer_position();
+            let res = match reader.read_event_into(&mut buf) {
+                Err(e) => Err(anyhow!(e)),
+                Ok(Event::Eof) => break,
+                Ok(Event::Start(e)) => {
+                    state.start(&String::from_utf8_lossy(e.local_name().as_ref()))
+                }
+                Ok(Event::Empty(e)) => state
+                    .start(&String::from_utf8_lossy(e.local_name().as_ref()))
+                    .map(|_| state.end()),
+                Ok(Event::Text(e)) => e
+                    .unescape()
+                    .map_err(|e| anyhow!(e).context("Error reading text"))
+                    .and_then(|t| state.text(t.into_owned())),
+                Ok(Event::End(_)) => {
+                    state.end();
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            res.with_context(|| format!("Error at position {}", position))?;
+            buf.clear();
+        }
+        state.finish(f)
+    }
+}
+
 impl<'a> VerifiySignatureTrait<'a> for EVotingDecrypt {
     fn get_hashable(&'a self) -> anyhow::Result<HashableMessage<'a>> {
-        let hashable = XMLFileHashable::new(&self.path, &SchemaKind::Decrypt, "signature");
+        let hashable =
+            XMLFileHashable::new(&self.file.get_path(), &SchemaKind::Decrypt, "signature")?
+                .with_source(self.file.source().clone())
+                .with_progress(report_progress("evoting-decrypt"));
         let hash = hashable.try_hash()?;
         Ok(HashableMessage::Hashed(hash))
     }
@@ -31,25 +421,132 @@ impl<'a> VerifiySignatureTrait<'a> for EVotingDecrypt {
     }
 
     fn get_certificate_authority(&self) -> anyhow::Result<String> {
-        Ok(String::from(CertificateAuthority::Canton))
+        Ok(String::from(CertificateAuthority::SdmTally))
     }
 
-    fn get_signature(&self) -> ByteArray {
-        todo!()
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
+        self.signature.get_signature()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::config::test::test_dataset_tally_path;
+    use crate::{
+        config::test::test_dataset_tally_path,
+        data_structures::{tally::VerifierTallyDataType, VerifierDataType},
+        file_structure::data_source::local_source,
+    };
+    use rust_ev_crypto_primitives::Decode;
+    use std::fs;
+
+    fn data_type() -> VerifierDataType {
+        VerifierDataType::Tally(VerifierTallyDataType::EVotingDecrypt)
+    }
+
+    fn get_decrypt() -> EVotingDecrypt {
+        let file = File::new(&test_dataset_tally_path().join("tally"), &data_type(), None);
+        EVotingDecrypt::from_xml_file(&file).unwrap()
+    }
 
     #[test]
     fn read_data_set() {
-        let path = test_dataset_tally_path()
-            .join("tally")
-            .join("evoting-decrypt_Post_E2E_DEV.xml");
-        let decrypt = EVotingDecrypt::from_xml_file(&path);
-        assert!(decrypt.is_ok())
+        let decrypt = get_decrypt();
+        assert_eq!(decrypt.contest_identification, "Post_E2E_DEV");
+        assert_eq!(decrypt.cast_ballots, 4);
+        assert_eq!(decrypt.ballot_boxes.len(), 4);
+        assert!(!decrypt.signature.signature_contents.is_empty());
+        let bb = decrypt
+            .ballot_box("516e2551-ee42-3401-9988-7dfebd0ac0c0")
+            .unwrap();
+        assert_eq!(bb.counting_circles.len(), 1);
+        let cc = &bb.counting_circles[0];
+        assert_eq!(cc.counting_circle_identification, "10001");
+        let doi = &cc.domains_of_influence[0];
+        assert_eq!(doi.domain_of_influence_identification, "doid-ch1-mu");
+        assert_eq!(doi.votes[0].vote_identification, "ch_test");
+        assert_eq!(
+            doi.votes[0].ballots[0].chosen_answer_identifications.len(),
+            4
+        );
+        let group = &doi.election_groups[0];
+        assert_eq!(group.election_group_identification, "nrw_test");
+        let election = &group.ballots[0].ballot_elections[0];
+        assert_eq!(election.election_identification, "nrw_test");
+        assert_eq!(election.chosen_list_identification, Some("99".to_string()));
+        assert_eq!(election.chosen_candidate_list_identifications.len(), 6);
+        assert_eq!(
+            EVotingDecrypt::schema_version(&decrypt.file.read_data().unwrap()),
+            SchemaVersion::V1_3
+        );
+    }
+
+    #[test]
+    fn number_of_ballots() {
+        let decrypt = get_decrypt();
+        let total: usize = decrypt
+            .ballot_boxes
+            .iter()
+            .map(|bb| bb.number_of_ballots())
+            .sum();
+        assert_eq!(total, decrypt.cast_ballots);
+        assert_eq!(decrypt.chosen_answers_count().values().sum::<usize>(), 16);
+        assert_eq!(
+            decrypt.chosen_candidates_count().values().sum::<usize>(),
+            25
+        );
+    }
+
+    #[test]
+    fn read_schema_structure() {
+        let name = "rust_verifier_test_evoting_decrypt.xml";
+        let file = File::new_with_name(&local_source(), &std::env::temp_dir(), &data_type(), name);
+        let path = file.get_path();
+        fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<results xmlns="http://www.evoting.ch/xmlns/decrypt/1">
+  <contestIdentification>test</contestIdentification>
+  <castBallots>1</castBallots>
+  <ballotsBox>
+    <ballotBoxIdentification>bb1</ballotBoxIdentification>
+    <countingCircle>
+      <countingCircleIdentification>cc1</countingCircleIdentification>
+      <domainOfInfluence>
+        <domainOfInfluenceIdentification>doi1</domainOfInfluenceIdentification>
+        <election>
+          <electionIdentification>e1</electionIdentification>
+          <ballot>
+            <chosenCandidateIdentification>c1</chosenCandidateIdentification>
+            <chosenWriteInsCandidateValue>toto</chosenWriteInsCandidateValue>
+          </ballot>
+          <ballot/>
+        </election>
+      </domainOfInfluence>
+    </countingCircle>
+  </ballotsBox>
+  <signature>dGVzdA==</signature>
+</results>"#,
+        )
+        .unwrap();
+        let decrypt = EVotingDecrypt::from_xml_file(&file).unwrap();
+        assert_eq!(
+            EVotingDecrypt::schema_version(&fs::read_to_string(&path).unwrap()),
+            SchemaVersion::V1_2
+        );
+        fs::remove_file(&path).unwrap();
+        let group =
+            &decrypt.ballot_boxes[0].counting_circles[0].domains_of_influence[0].election_groups[0];
+        assert_eq!(group.election_group_identification, "e1");
+        assert_eq!(group.ballots.len(), 2);
+        let election = &group.ballots[0].ballot_elections[0];
+        assert_eq!(election.election_identification, "e1");
+        assert_eq!(election.chosen_candidate_identifications, vec!["c1"]);
+        assert_eq!(election.chosen_write_ins_candidate_values, vec!["toto"]);
+        assert_eq!(decrypt.ballot_boxes[0].number_of_ballots(), 2);
+        assert_eq!(
+            decrypt.get_signature().unwrap(),
+            ByteArray::base64_decode("dGVzdA==").unwrap()
+        );
     }
 }
@@ -1,27 +1,644 @@
-use super::super::{
-    xml::{hashable::XMLFileHashable, SchemaKind},
-    VerifierDataDecode,
+//! Module implementing the results of the e-voting (eCH-0110)
+//!
+//! The results are given per counting circle, with the results of the votes (per ballot and
+//! question) and of the election groups (per election, list and candidate). Only the totals are
+//! read, the subtotals per channel or per voter type are ignored.
+//!
+//! The file is read in streaming mode.
+//!
+//! The counts formatted with a locale (e.g. `1'234` or `1 234`) are not valid according to the
+//! XSD, but they occur in the files. They are read with a warning and listed in
+//! [ECH0110::locale_formatted_counts], so that the verifications can run and the anomaly can be
+//! reported.
+
+use super::{
+    super::{
+        common_types::Signature,
+        xml::{
+            hashable::{report_progress, XMLFileHashable},
+            SchemaKind,
+        },
+        PayloadIdentityTrait, VerifierDataDecode,
+    },
+    last_mut,
+};
+use crate::{
+    file_structure::file::File,
+    signature::{CertificateAuthority, VerifiySignatureTrait},
 };
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use anyhow::{anyhow, bail, Context};
+use log::warn;
+use quick_xml::{events::Event, Reader};
 use rust_ev_crypto_primitives::{ByteArray, HashableMessage, RecursiveHashTrait};
-use std::path::{Path, PathBuf};
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// Separators of the thousands accepted in the counts formatted with a locale
+const THOUSANDS_SEPARATORS: [char; 7] = ['\'', '\u{2019}', ',', '.', ' ', '\u{a0}', '\u{202f}'];
 
 #[derive(Debug, Clone)]
 pub struct ECH0110 {
-    pub path: PathBuf,
+    pub file: File,
+    pub contest_identification: String,
+    pub contest_date: String,
+    pub counting_circles: Vec<ResultCountingCircle>,
+    /// Counts formatted with a locale, in the order of the file
+    pub locale_formatted_counts: Vec<LocaleFormattedCount>,
+    pub signature: Signature,
 }
 
-impl VerifierDataDecode for ECH0110 {
-    fn from_xml_file(p: &Path) -> anyhow::Result<Self> {
+/// Count formatted with a locale in the file (e.g. `1'234`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleFormattedCount {
+    /// Name of the element containing the count
+    pub element: String,
+    /// Id of the counting circle containing the count
+    pub counting_circle_id: Option<String>,
+    /// Text of the count in the file
+    pub text: String,
+    /// Value read
+    pub value: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResultCountingCircle {
+    pub counting_circle_id: String,
+    pub counting_circle_name: Option<String>,
+    pub count_of_received_valid_voting_cards: usize,
+    pub count_of_received_invalid_voting_cards: usize,
+    pub votes: Vec<ResultVote>,
+    pub election_groups: Vec<ResultElectionGroup>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResultVote {
+    pub vote_identification: String,
+    pub domain_of_influence_identification: String,
+    pub count_of_voters: usize,
+    pub ballots: Vec<ResultBallot>,
+}
+
+/// Result of a ballot of a vote. A standard ballot contains one question, a variant ballot
+/// contains many questions and the tie breaks
+#[derive(Debug, Clone, Default)]
+pub struct ResultBallot {
+    pub ballot_identification: String,
+    pub ballot_position: usize,
+    pub count_of_received_ballots: usize,
+    pub count_of_accounted_ballots: usize,
+    pub count_of_unaccounted_ballots: usize,
+    pub count_of_unaccounted_blank_ballots: usize,
+    pub count_of_unaccounted_invalid_ballots: usize,
+    pub questions: Vec<ResultQuestion>,
+    pub tie_breaks: Vec<ResultTieBreak>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResultQuestion {
+    pub question_identification: String,
+    pub count_of_answer_yes: usize,
+    pub count_of_answer_no: usize,
+    pub count_of_answer_invalid: usize,
+    pub count_of_answer_empty: usize,
+}
+
+/// Result of a tie break, with the number of valid answers in favour of each question
+#[derive(Debug, Clone, Default)]
+pub struct ResultTieBreak {
+    pub question_identification: String,
+    pub count_of_answer_invalid: usize,
+    pub count_of_answer_empty: usize,
+    pub count_in_favour_of: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResultElectionGroup {
+    pub election_group_identification: String,
+    pub domain_of_influence_identification: String,
+    pub count_of_voters: usize,
+    pub count_of_received_ballots: usize,
+    pub count_of_accounted_ballots: usize,
+    pub count_of_unaccounted_ballots: usize,
+    pub count_of_unaccounted_blank_ballots: usize,
+    pub count_of_unaccounted_invalid_ballots: usize,
+    pub elections: Vec<ResultElection>,
+}
+
+/// Result of an election. The lists are only given for a proportional election
+#[derive(Debug, Clone, Default)]
+pub struct ResultElection {
+    pub election_identification: String,
+    pub type_of_election: usize,
+    pub number_of_mandates: usize,
+    pub lists: Vec<ResultList>,
+    pub candidates: Vec<ResultCandidate>,
+    pub count_of_invalid_votes: Option<usize>,
+    pub count_of_blank_votes: Option<usize>,
+    pub count_of_individual_votes: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResultList {
+    pub list_identification: String,
+    pub list_indenture_number: String,
+    pub count_of_changed_ballots: usize,
+    pub count_of_unchanged_ballots: usize,
+    pub count_of_candidate_votes: usize,
+    pub count_of_additional_votes: usize,
+    pub count_of_party_votes: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResultCandidate {
+    pub candidate_identification: String,
+    pub candidate_reference: String,
+    pub count_of_votes: usize,
+    pub list_results: Vec<ResultCandidateList>,
+}
+
+/// Votes of a candidate from the ballots of a list
+#[derive(Debug, Clone, Default)]
+pub struct ResultCandidateList {
+    pub list_identification: String,
+    pub count_of_votes_from_unchanged_ballots: usize,
+    pub count_of_votes_from_changed_ballots: usize,
+}
+
+impl PayloadIdentityTrait for ECH0110 {}
+
+impl ECH0110 {
+    /// The counting circle with the given id. `None` if not found
+    pub fn counting_circle(&self, id: &str) -> Option<&ResultCountingCircle> {
+        self.counting_circles
+            .iter()
+            .find(|cc| cc.counting_circle_id == id)
+    }
+
+    /// Number of received valid voting cards per counting circle id
+    pub fn received_voting_cards_per_counting_circle(&self) -> BTreeMap<String, usize> {
+        self.counting_circles
+            .iter()
+            .map(|cc| {
+                (
+                    cc.counting_circle_id.clone(),
+                    cc.count_of_received_valid_voting_cards,
+                )
+            })
+            .collect()
+    }
+
+    /// Total number of received valid voting cards over all the counting circles
+    pub fn number_of_received_voting_cards(&self) -> usize {
+        self.counting_circles
+            .iter()
+            .map(|cc| cc.count_of_received_valid_voting_cards)
+            .sum()
+    }
+
+    /// Iterator over the results of all the elections, with the id of the counting circle
+    pub fn elections(&self) -> impl Iterator<Item = (&str, &ResultElection)> {
+        self.counting_circles.iter().flat_map(|cc| {
+            cc.election_groups
+                .iter()
+                .flat_map(|g| g.elections.iter())
+                .map(|e| (cc.counting_circle_id.as_str(), e))
+        })
+    }
+
+    /// Number of votes of each candidate, over all the counting circles
+    pub fn candidate_votes_count(&self) -> BTreeMap<String, usize> {
+        let mut res = BTreeMap::new();
+        for c in self.elections().flat_map(|(_, e)| e.candidates.iter()) {
+            *res.entry(c.candidate_identification.clone()).or_default() += c.count_of_votes;
+        }
+        res
+    }
+}
+
+/// State of the reading of the file
+#[derive(Default)]
+struct ResultReader {
+    /// Local names of the open elements
+    stack: Vec<String>,
+    contest_identification: Option<String>,
+    contest_date: Option<String>,
+    counting_circles: Vec<ResultCountingCircle>,
+    /// Counts formatted with a locale. Collected during the parsing of the counts
+    locale_formatted_counts: RefCell<Vec<LocaleFormattedCount>>,
+    signature: Option<String>,
+}
+
+/// Parse the count formatted with a separator of the thousands (e.g. `1'234` or `1 234`)
+///
+/// The separator must be the same in the whole count and separate groups of three digits.
+/// `None` if the text is not such a count
+fn parse_locale_count(text: &str) -> Option<usize> {
+    let separator = text.chars().find(|c| !c.is_ascii_digit())?;
+    if !THOUSANDS_SEPARATORS.contains(&separator) {
+        return None;
+    }
+    let groups: Vec<&str> = text.split(separator).collect();
+    if !(1..=3).contains(&groups[0].len())
+        || groups[1..].iter().any(|g| g.len() != 3)
+        || groups
+            .iter()
+            .any(|g| !g.chars().all(|c| c.is_ascii_digit()))
+    {
+        return None;
+    }
+    groups.concat().parse().ok()
+}
+
+impl ResultReader {
+    /// Parse the count of the element `name`
+    ///
+    /// The counts formatted with a locale are accepted with a warning and collected
+    fn parse_count(&self, name: &str, text: &str) -> anyhow::Result<usize> {
+        if let Ok(n) = text.parse::<usize>() {
+            return Ok(n);
+        }
+        let value = parse_locale_count(text.trim())
+            .ok_or_else(|| anyhow!("{} {} is not a number", name, text))?;
+        let counting_circle_id = self
+            .counting_circles
+            .last()
+            .map(|cc| cc.counting_circle_id.clone());
+        warn!(
+            "eCH-0110: the count {} of {} (counting circle {}) is formatted with a locale. Read as {}",
+            text,
+            name,
+            counting_circle_id.as_deref().unwrap_or("-"),
+            value
+        );
+        self.locale_formatted_counts
+            .borrow_mut()
+            .push(LocaleFormattedCount {
+                element: name.to_string(),
+                counting_circle_id,
+                text: text.to_string(),
+                value,
+            });
+        Ok(value)
+    }
+
+    fn parent(&self) -> Option<&str> {
+        self.stack.iter().rev().nth(1).map(|s| s.as_str())
+    }
+
+    fn is_in(&self, name: &str) -> bool {
+        self.stack.iter().any(|s| s == name)
+    }
+
+    fn last_cc(&mut self, name: &str) -> anyhow::Result<&mut ResultCountingCircle> {
+        last_mut(&mut self.counting_circles, name)
+    }
+
+    fn last_vote(&mut self, name: &str) -> anyhow::Result<&mut ResultVote> {
+        last_mut(&mut self.last_cc(name)?.votes, name)
+    }
+
+    fn last_ballot(&mut self, name: &str) -> anyhow::Result<&mut ResultBallot> {
+        last_mut(&mut self.last_vote(name)?.ballots, name)
+    }
+
+    fn last_question(&mut self, name: &str) -> anyhow::Result<&mut ResultQuestion> {
+        last_mut(&mut self.last_ballot(name)?.questions, name)
+    }
+
+    fn last_tie_break(&mut self, name: &str) -> anyhow::Result<&mut ResultTieBreak> {
+        last_mut(&mut self.last_ballot(name)?.tie_breaks, name)
+    }
+
+    fn last_group(&mut self, name: &str) -> anyhow::Result<&mut ResultElectionGroup> {
+        last_mut(&mut self.last_cc(name)?.election_groups, name)
+    }
+
+    fn last_election(&mut self, name: &str) -> anyhow::Result<&mut ResultElection> {
+        last_mut(&mut self.last_group(name)?.elections, name)
+    }
+
+    fn last_list(&mut self, name: &str) -> anyhow::Result<&mut ResultList> {
+        last_mut(&mut self.last_election(name)?.lists, name)
+    }
+
+    fn last_candidate(&mut self, name: &str) -> anyhow::Result<&mut ResultCandidate> {
+        last_mut(&mut self.last_election(name)?.candidates, name)
+    }
+
+    fn last_candidate_list(&mut self, name: &str) -> anyhow::Result<&mut ResultCandidateList> {
+        last_mut(&mut self.last_candidate(name)?.list_results, name)
+    }
+
+    fn start(&mut self, name: &str) -> anyhow::Result<()> {
+        self.stack.push(name.to_string());
+        match name {
+            "countingCircleResults" => self.counting_circles.push(ResultCountingCircle::default()),
+            "voteResults" => self.last_cc(name)?.votes.push(ResultVote::default()),
+            "ballotResult" => self.last_vote(name)?.ballots.push(ResultBallot::default()),
+            "standardBallot" | "questionInformation" => self
+                .last_ballot(name)?
+                .questions
+                .push(ResultQuestion::default()),
+            "tieBreak" => self
+                .last_ballot(name)?
+                .tie_breaks
+                .push(ResultTieBreak::default()),
+            "electionGroupResults" => self
+                .last_cc(name)?
+                .election_groups
+                .push(ResultElectionGroup::default()),
+            "electionResults" => self
+                .last_group(name)?
+                .elections
+                .push(ResultElection::default()),
+            "list" => self.last_election(name)?.lists.push(ResultList::default()),
+            "candidate" => self
+                .last_election(name)?
+                .candidates
+                .push(ResultCandidate::default()),
+            "listResults" => self
+                .last_candidate(name)?
+                .list_results
+                .push(ResultCandidateList::default()),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, text: String) -> anyhow::Result<()> {
+        let name = match self.stack.last() {
+            Some(n) => n.clone(),
+            None => return Ok(()),
+        };
+        // The counts of type resultDetailType are given in the child element total
+        let (name, parent) = if name == "total" {
+            match self.parent() {
+                Some(p) => (p.to_string(), self.stack.iter().rev().nth(2).cloned()),
+                None => return Ok(()),
+            }
+        } else {
+            (name, self.parent().map(|s| s.to_string()))
+        };
+        match (name.as_str(), parent.as_deref()) {
+            ("contestIdentification", _) => self.contest_identification = Some(text),
+            ("contestDate", _) => self.contest_date = Some(text),
+            ("signature", _) => self.signature = Some(text),
+            ("countingCircleId", _) => self.last_cc(&name)?.counting_circle_id = text,
+            ("countingCircleName", _) => self.last_cc(&name)?.counting_circle_name = Some(text),
+            ("countOfReceivedValidVotingCardsTotal", _) => {
+                self.last_cc(&name)?.count_of_received_valid_voting_cards =
+                    self.parse_count(&name, &text)?
+            }
+            ("countOfReceivedInvalidVotingCardsTotal", _) => {
+                self.last_cc(&name)?.count_of_received_invalid_voting_cards =
+                    self.parse_count(&name, &text)?
+            }
+            ("voteIdentification", _) => self.last_vote(&name)?.vote_identification = text,
+            ("domainOfInfluenceIdentification", Some("vote")) => {
+                self.last_vote(&name)?.domain_of_influence_identification = text
+            }
+            ("domainOfInfluenceIdentification", Some("electionGroupResults")) => {
+                self.last_group(&name)?.domain_of_influence_identification = text
+            }
+            ("countOfVotersTotal", _) => {
+                let count = self.parse_count(&name, &text)?;
+                if self.is_in("electionGroupResults") {
+                    self.last_group(&name)?.count_of_voters = count
+                } else {
+                    self.last_vote(&name)?.count_of_voters = count
+                }
+            }
+            ("ballotIdentification", _) => self.last_ballot(&name)?.ballot_identification = text,
+            ("ballotPosition", _) => {
+                self.last_ballot(&name)?.ballot_position = self.parse_count(&name, &text)?
+            }
+            (
+                "countOfReceivedBallotsTotal"
+                | "countOfAccountedBallotsTotal"
+                | "countOfAccountedBallots"
+                | "countOfUnaccountedBallotsTotal"
+                | "countOfUnaccountedBallots"
+                | "countOfUnaccountedBlankBallots"
+                | "countOfUnaccountedInvalidBallots",
+                _,
+            ) => self.ballot_count(&name, self.parse_count(&name, &text)?)?,
+            ("questionIdentification", Some("standardBallot" | "questionInformation")) => {
+                self.last_question(&name)?.question_identification = text
+            }
+            ("questionIdentification", Some("tieBreak")) => {
+                self.last_tie_break(&name)?.question_identification = text
+            }
+            ("questionIdentification", Some("countInFavourOf")) => self
+                .last_tie_break(&name)?
+                .count_in_favour_of
+                .push((text, 0)),
+            ("countOfValidAnswers", _) => {
+                last_mut(&mut self.last_tie_break(&name)?.count_in_favour_of, &name)?.1 =
+                    self.parse_count(&name, &text)?
+            }
+            (
+                "countOfAnswerYes"
+                | "countOfAnswerNo"
+                | "countOfAnswerInvalid"
+                | "countOfAnswerEmpty",
+                _,
+            ) => self.answer_count(&name, self.parse_count(&name, &text)?)?,
+            ("electionGroupIdentification", _) => {
+                self.last_group(&name)?.election_group_identification = text
+            }
+            ("electionIdentification", _) => {
+                self.last_election(&name)?.election_identification = text
+            }
+            ("typeOfElection", _) => {
+                self.last_election(&name)?.type_of_election = self.parse_count(&name, &text)?
+            }
+            ("numberOfMandates", _) => {
+                self.last_election(&name)?.number_of_mandates = self.parse_count(&name, &text)?
+            }
+            ("countOfInvalidVotesTotal", _) => {
+                self.last_election(&name)?.count_of_invalid_votes =
+                    Some(self.parse_count(&name, &text)?)
+            }
+            ("countOfBlankVotesTotal", _) => {
+                self.last_election(&name)?.count_of_blank_votes =
+                    Some(self.parse_count(&name, &text)?)
+            }
+            ("countOfIndividualVotesTotal", _) => {
+                self.last_election(&name)?.count_of_individual_votes =
+                    Some(self.parse_count(&name, &text)?)
+            }
+            ("listIdentification", Some("listInformation")) => {
+                self.last_list(&name)?.list_identification = text
+            }
+            ("listIdentification", Some("listResults")) => {
+                self.last_candidate_list(&name)?.list_identification = text
+            }
+            ("listIndentureNumber", _) => self.last_list(&name)?.list_indenture_number = text,
+            (
+                "countOfChangedBallots"
+                | "countOfUnchangedBallots"
+                | "countOfCandidateVotes"
+                | "countOfAdditionalVotes"
+                | "countOfPartyVotes",
+                _,
+            ) => {
+                let count = self.parse_count(&name, &text)?;
+                let list = self.last_list(&name)?;
+                match name.as_str() {
+                    "countOfChangedBallots" => list.count_of_changed_ballots = count,
+                    "countOfUnchangedBallots" => list.count_of_unchanged_ballots = count,
+                    "countOfCandidateVotes" => list.count_of_candidate_votes = count,
+                    "countOfAdditionalVotes" => list.count_of_additional_votes = count,
+                    _ => list.count_of_party_votes = count,
+                }
+            }
+            ("candidateIdentification", _) => {
+                self.last_candidate(&name)?.candidate_identification = text
+            }
+            ("candidateReference", _) => self.last_candidate(&name)?.candidate_reference = text,
+            ("countOfVotesTotal", _) => {
+                self.last_candidate(&name)?.count_of_votes = self.parse_count(&name, &text)?
+            }
+            ("countOfvotesFromUnchangedBallots", _) => {
+                self.last_candidate_list(&name)?
+                    .count_of_votes_from_unchanged_ballots = self.parse_count(&name, &text)?
+            }
+            ("countOfvotesFromChangedBallots", _) => {
+                self.last_candidate_list(&name)?
+                    .count_of_votes_from_changed_ballots = self.parse_count(&name, &text)?
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Set the count of ballots, of the ballot of a vote or of the election group
+    fn ballot_count(&mut self, name: &str, count: usize) -> anyhow::Result<()> {
+        if self.is_in("electionGroupResults") {
+            let group = self.last_group(name)?;
+            match name {
+                "countOfReceivedBallotsTotal" => group.count_of_received_ballots = count,
+                "countOfAccountedBallots" => group.count_of_accounted_ballots = count,
+                "countOfUnaccountedBallots" => group.count_of_unaccounted_ballots = count,
+                "countOfUnaccountedBlankBallots" => {
+                    group.count_of_unaccounted_blank_ballots = count
+                }
+                "countOfUnaccountedInvalidBallots" => {
+                    group.count_of_unaccounted_invalid_ballots = count
+                }
+                _ => bail!(format!(
+                    "Element {} not expected in an election group",
+                    name
+                )),
+            }
+        } else {
+            let ballot = self.last_ballot(name)?;
+            match name {
+                "countOfReceivedBallotsTotal" => ballot.count_of_received_ballots = count,
+                "countOfAccountedBallotsTotal" => ballot.count_of_accounted_ballots = count,
+                "countOfUnaccountedBallotsTotal" => ballot.count_of_unaccounted_ballots = count,
+                "countOfUnaccountedBlankBallots" => {
+                    ballot.count_of_unaccounted_blank_ballots = count
+                }
+                "countOfUnaccountedInvalidBallots" => {
+                    ballot.count_of_unaccounted_invalid_ballots = count
+                }
+                _ => bail!(format!("Element {} not expected in a ballot", name)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the count of answers, of a question or of a tie break
+    fn answer_count(&mut self, name: &str, count: usize) -> anyhow::Result<()> {
+        if self.is_in("tieBreak") {
+            let tie_break = self.last_tie_break(name)?;
+            match name {
+                "countOfAnswerInvalid" => tie_break.count_of_answer_invalid = count,
+                "countOfAnswerEmpty" => tie_break.count_of_answer_empty = count,
+                _ => bail!(format!("Element {} not expected in a tie break", name)),
+            }
+        } else {
+            let question = self.last_question(name)?;
+            match name {
+                "countOfAnswerYes" => question.count_of_answer_yes = count,
+                "countOfAnswerNo" => question.count_of_answer_no = count,
+                "countOfAnswerInvalid" => question.count_of_answer_invalid = count,
+                _ => question.count_of_answer_empty = count,
+            }
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) {
+        self.stack.pop();
+    }
+
+    fn finish(self, f: &File) -> anyhow::Result<ECH0110> {
         Ok(ECH0110 {
-            path: p.to_path_buf(),
+            file: f.clone(),
+            contest_identification: self
+                .contest_identification
+                .ok_or_else(|| anyhow!("contestIdentification not found"))?,
+            contest_date: self
+                .contest_date
+                .ok_or_else(|| anyhow!("contestDate not found"))?,
+            counting_circles: self.counting_circles,
+            locale_formatted_counts: self.locale_formatted_counts.into_inner(),
+            signature: Signature {
+                signature_contents: self
+                    .signature
+                    .ok_or_else(|| anyhow!("Signature not found"))?,
+            },
         })
     }
 }
 
+impl VerifierDataDecode for ECH0110 {
+    fn from_xml_file(f: &File) -> anyhow::Result<Self> {
+        let mut reader = f
+            .open_reader()
+            .map(Reader::from_reader)
+            .map_err(|e| e.context(format!("Error creating xml reader for file {}", f.to_str())))?;
+        reader.trim_text(true);
+        let mut state = ResultReader::default();
+        let mut buf = Vec::new();
+        loop {
+            let position = reader.buffer_position();
+            let res = match reader.read_event_into(&mut buf) {
+                Err(e) => Err(anyhow!(e)),
+                Ok(Event::Eof) => break,
+                Ok(Event::Start(e)) => {
+                    state.start(&String::from_utf8_lossy(e.local_name().as_ref()))
+                }
+                Ok(Event::Empty(e)) => state
+                    .start(&String::from_utf8_lossy(e.local_name().as_ref()))
+                    .map(|_| state.end()),
+                Ok(Event::Text(e)) => e
+                    .unescape()
+                    .map_err(|e| anyhow!(e).context("Error reading text"))
+                    .and_then(|t| state.text(t.into_owned())),
+                Ok(Event::End(_)) => {
+                    state.end();
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            res.with_context(|| format!("Error at position {}", position))?;
+            buf.clear();
+        }
+        state.finish(f)
+    }
+}
+
 impl<'a> VerifiySignatureTrait<'a> for ECH0110 {
     fn get_hashable(&'a self) -> anyhow::Result<HashableMessage<'a>> {
-        let hashable = XMLFileHashable::new(&self.path, &SchemaKind::Ech0110, "eCH-0110:extension");
+        let hashable = XMLFileHashable::new(
+            &self.file.get_path(),
+            &SchemaKind::Ech0110,
+            "eCH-0110:extension",
+        )?
+        .with_source(self.file.source().clone())
+        .with_progress(report_progress("eCH-0110"));
         let hash = hashable.try_hash()?;
         Ok(HashableMessage::Hashed(hash))
     }
@@ -31,25 +648,159 @@ impl<'a> VerifiySignatureTrait<'a> for ECH0110 {
     }
 
     fn get_certificate_authority(&self) -> anyhow::Result<String> {
-        Ok(String::from(CertificateAuthority::Canton))
+        Ok(String::from(CertificateAuthority::SdmTally))
     }
 
-    fn get_signature(&self) -> ByteArray {
-        todo!()
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
+        self.signature.get_signature()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::config::test::test_dataset_tally_path;
+    use crate::{
+        config::test::test_dataset_tally_path,
+        data_structures::{tally::VerifierTallyDataType, VerifierDataType},
+        file_structure::memory_fs::MemoryFileSystem,
+    };
+    use std::{fs, path::Path};
+
+    fn data_type() -> VerifierDataType {
+        VerifierDataType::Tally(VerifierTallyDataType::ECH0110)
+    }
+
+    fn get_ech_0110() -> ECH0110 {
+        let file = File::new(&test_dataset_tally_path().join("tally"), &data_type(), None);
+        ECH0110::from_xml_file(&file).unwrap()
+    }
 
     #[test]
     fn read_data_set() {
-        let path = test_dataset_tally_path()
+        let ech_0110 = get_ech_0110();
+        assert_eq!(ech_0110.contest_identification, "Post_E2E_DEV");
+        assert_eq!(ech_0110.contest_date, "2027-11-25");
+        assert_eq!(ech_0110.counting_circles.len(), 4);
+        assert!(!ech_0110.signature.signature_contents.is_empty());
+        let cc = ech_0110.counting_circle("10001").unwrap();
+        assert_eq!(cc.counting_circle_name, Some("CH_Testurne1".to_string()));
+        assert_eq!(cc.count_of_received_valid_voting_cards, 1);
+        let vote = &cc.votes[0];
+        assert_eq!(vote.vote_identification, "ch_test");
+        assert_eq!(vote.domain_of_influence_identification, "doid-ch1-mu");
+        assert_eq!(vote.count_of_voters, 1);
+        assert_eq!(vote.ballots.len(), 2);
+        let ballot = &vote.ballots[0];
+        assert_eq!(
+            ballot.ballot_identification,
+            "9cbcbd59-94ad-4f48-bc7d-4bc38e5c0c51"
+        );
+        assert_eq!(ballot.ballot_position, 1);
+        assert_eq!(ballot.count_of_received_ballots, 1);
+        assert_eq!(ballot.count_of_unaccounted_blank_ballots, 1);
+        assert_eq!(ballot.questions.len(), 1);
+        assert_eq!(ballot.questions[0].count_of_answer_empty, 1);
+        let group = &cc.election_groups[0];
+        assert_eq!(group.election_group_identification, "nrw_test");
+        assert_eq!(group.domain_of_influence_identification, "doid-ch1-mu");
+        assert_eq!(group.count_of_voters, 1);
+        assert_eq!(group.count_of_accounted_ballots, 1);
+        let election = &group.elections[0];
+        assert_eq!(election.election_identification, "nrw_test");
+        assert_eq!(election.type_of_election, 1);
+        assert_eq!(election.number_of_mandates, 6);
+        assert!(!election.lists.is_empty());
+        assert_eq!(election.lists[0].list_indenture_number, "01a");
+        let candidate = &election.candidates[0];
+        assert_eq!(
+            candidate.candidate_identification,
+            "8cc68a36-df81-4381-aaff-83544ffe6e6c"
+        );
+        assert_eq!(candidate.candidate_reference, "01a.01");
+        assert_eq!(
+            candidate.list_results[0].list_identification,
+            "244ab111-a5d4-4090-bed3-79f850a27fa1"
+        );
+    }
+
+    #[test]
+    fn read_variant_ballot() {
+        let ech_0110 = get_ech_0110();
+        let ballot = ech_0110
+            .counting_circles
+            .iter()
+            .flat_map(|cc| cc.votes.iter())
+            .flat_map(|v| v.ballots.iter())
+            .find(|b| !b.tie_breaks.is_empty())
+            .unwrap();
+        assert!(ballot.questions.len() > 1);
+        let tie_break = &ballot.tie_breaks[0];
+        assert!(!tie_break.question_identification.is_empty());
+        assert!(!tie_break.count_in_favour_of.is_empty());
+        assert!(ballot
+            .questions
+            .iter()
+            .any(|q| q.question_identification == tie_break.count_in_favour_of[0].0));
+    }
+
+    #[test]
+    fn counts() {
+        let ech_0110 = get_ech_0110();
+        assert_eq!(
+            ech_0110.received_voting_cards_per_counting_circle(),
+            BTreeMap::from([
+                ("10001".to_string(), 1),
+                ("10002".to_string(), 1),
+                ("10003".to_string(), 0),
+                ("10004".to_string(), 2)
+            ])
+        );
+        assert_eq!(ech_0110.number_of_received_voting_cards(), 4);
+        assert_eq!(ech_0110.elections().count(), 5);
+        let candidates = ech_0110.candidate_votes_count();
+        assert!(!candidates.is_empty());
+        assert_eq!(candidates.values().sum::<usize>(), 0);
+    }
+
+    #[test]
+    fn test_parse_locale_count() {
+        assert_eq!(parse_locale_count("1'234"), Some(1234));
+        assert_eq!(parse_locale_count("1\u{2019}234\u{2019}567"), Some(1234567));
+        assert_eq!(parse_locale_count("12 345"), Some(12345));
+        assert_eq!(parse_locale_count("1.234"), Some(1234));
+        assert_eq!(parse_locale_count("1'23"), None);
+        assert_eq!(parse_locale_count("1'234.567"), None);
+        assert_eq!(parse_locale_count("1234'567"), None);
+        assert_eq!(parse_locale_count("12a"), None);
+        assert_eq!(parse_locale_count("1234"), None);
+    }
+
+    #[test]
+    fn read_locale_formatted_count() {
+        let source = test_dataset_tally_path()
             .join("tally")
             .join("eCH-0110_Post_E2E_DEV.xml");
-        let ech_0110 = ECH0110::from_xml_file(&path);
-        assert!(ech_0110.is_ok())
+        let content = fs::read_to_string(source).unwrap().replacen(
+            "countOfReceivedValidVotingCardsTotal>1<",
+            "countOfReceivedValidVotingCardsTotal>1'001<",
+            1,
+        );
+        let mfs = MemoryFileSystem::new(Path::new("ech_0110_locale_formatted_count"));
+        mfs.add_file("eCH-0110.xml", &content);
+        let file = File::new_with_name(&mfs.source(), mfs.root(), &data_type(), "eCH-0110.xml");
+        let ech_0110 = ECH0110::from_xml_file(&file).unwrap();
+        let cc = &ech_0110.counting_circles[0];
+        assert_eq!(cc.count_of_received_valid_voting_cards, 1001);
+        assert_eq!(
+            ech_0110.locale_formatted_counts,
+            vec![LocaleFormattedCount {
+                element: "countOfReceivedValidVotingCardsTotal".to_string(),
+                counting_circle_id: Some(cc.counting_circle_id.clone()),
+                text: "1'001".to_string(),
+                value: 1001
+            }]
+        );
+        mfs.add_file("eCH-0110.xml", &content.replacen("1'001<", "1'01<", 1));
+        assert!(ECH0110::from_xml_file(&file).is_err());
     }
 }
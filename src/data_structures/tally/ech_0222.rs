@@ -1,26 +1,436 @@
-use super::super::{
-    xml::{hashable::XMLFileHashable, SchemaKind},
-    VerifierDataDecode,
+//! Module implementing the raw data of the e-voting results (eCH-0222)
+//!
+//! The raw data contain the cast ballots per counting circle. Each cast vote of a voter contains
+//! one raw ballot per ballot of each vote and one raw ballot per election group of its domains of
+//! influence. The raw data do not contain the ballot boxes.
+//!
+//! The file is read in streaming mode.
+
+use super::{
+    super::{
+        common_types::Signature,
+        dataset_version::parse_datetime,
+        xml::{
+            hashable::{report_progress, XMLFileHashable},
+            SchemaKind,
+        },
+        PayloadIdentityTrait, VerifierDataDecode,
+    },
+    last_mut,
+};
+use crate::{
+    file_structure::file::File,
+    signature::{CertificateAuthority, VerifiySignatureTrait},
 };
-use crate::direct_trust::{CertificateAuthority, VerifiySignatureTrait};
+use anyhow::{anyhow, bail, Context};
+use chrono::NaiveDateTime;
+use quick_xml::{events::Event, Reader};
 use rust_ev_crypto_primitives::{ByteArray, HashableMessage, RecursiveHashTrait};
-use std::path::{Path, PathBuf};
-
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone)]
-pub struct ECH0222 {pub path: PathBuf,}
+pub struct ECH0222 {
+    pub file: File,
+    pub contest_identification: String,
+    /// Creation of the raw data, after the decryption of the ballot boxes (optional in the
+    /// schema)
+    pub creation_date_time: Option<NaiveDateTime>,
+    pub counting_circles: Vec<RawDataCountingCircle>,
+    pub signature: Signature,
+}
 
-impl VerifierDataDecode for ECH0222 {
-    fn from_xml_file(p: &Path) -> anyhow::Result<Self> {
+#[derive(Debug, Clone, Default)]
+pub struct RawDataCountingCircle {
+    pub counting_circle_id: String,
+    pub votes: Vec<RawDataVote>,
+    pub election_group_ballots: Vec<RawDataElectionGroupBallot>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RawDataVote {
+    pub vote_identification: String,
+    pub ballots: Vec<RawDataVoteBallot>,
+}
+
+/// Raw ballot of a vote
+#[derive(Debug, Clone, Default)]
+pub struct RawDataVoteBallot {
+    pub ballot_identification: String,
+    pub ballot_casted_number: Option<usize>,
+    pub questions: Vec<RawDataQuestion>,
+}
+
+/// Answer to a question. `casted_vote` and `answer_identification` are `None` if the question
+/// is not answered
+#[derive(Debug, Clone, Default)]
+pub struct RawDataQuestion {
+    pub question_identification: String,
+    pub casted_vote: Option<usize>,
+    pub answer_identification: Option<String>,
+}
+
+/// Raw ballot of an election group, containing the ballots of each election of the group
+#[derive(Debug, Clone, Default)]
+pub struct RawDataElectionGroupBallot {
+    pub election_group_identification: Option<String>,
+    pub elections: Vec<RawDataElection>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RawDataElection {
+    pub election_identification: String,
+    pub ballots: Vec<RawDataElectionBallot>,
+}
+
+/// Raw ballot of an election
+#[derive(Debug, Clone, Default)]
+pub struct RawDataElectionBallot {
+    pub list_identification: Option<String>,
+    pub ballot_positions: Vec<RawDataBallotPosition>,
+    pub is_unchanged_ballot: Option<bool>,
+}
+
+/// Position on the ballot of an election
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawDataBallotPosition {
+    Candidate {
+        candidate_identification: String,
+        candidate_reference_on_position: String,
+    },
+    WriteIn(String),
+    Empty,
+}
+
+impl PayloadIdentityTrait for ECH0222 {}
+
+impl ECH0222 {
+    /// The counting circle with the given id. `None` if not found
+    pub fn counting_circle(&self, id: &str) -> Option<&RawDataCountingCircle> {
+        self.counting_circles
+            .iter()
+            .find(|cc| cc.counting_circle_id == id)
+    }
+
+    /// Number of e-voting cast votes per counting circle id
+    pub fn cast_votes_per_counting_circle(&self) -> BTreeMap<String, usize> {
+        self.counting_circles
+            .iter()
+            .map(|cc| (cc.counting_circle_id.clone(), cc.number_of_cast_votes()))
+            .collect()
+    }
+
+    /// Total number of e-voting cast votes over all the counting circles
+    pub fn number_of_cast_votes(&self) -> usize {
+        self.counting_circles
+            .iter()
+            .map(|cc| cc.number_of_cast_votes())
+            .sum()
+    }
+
+    /// Number of times each answer is chosen, over all the counting circles
+    pub fn chosen_answers_count(&self) -> BTreeMap<String, usize> {
+        let mut res = BTreeMap::new();
+        for a in self
+            .counting_circles
+            .iter()
+            .flat_map(|cc| cc.votes.iter())
+            .flat_map(|v| v.ballots.iter())
+            .flat_map(|b| b.questions.iter())
+            .filter_map(|q| q.answer_identification.as_ref())
+        {
+            *res.entry(a.clone()).or_default() += 1;
+        }
+        res
+    }
+
+    /// Number of times each candidate is chosen, over all the counting circles
+    ///
+    /// The write-ins and the empty positions are not counted
+    pub fn chosen_candidates_count(&self) -> BTreeMap<String, usize> {
+        let mut res = BTreeMap::new();
+        for p in self
+            .election_ballots()
+            .flat_map(|b| b.ballot_positions.iter())
+        {
+            if let RawDataBallotPosition::Candidate {
+                candidate_identification,
+                ..
+            } = p
+            {
+                *res.entry(candidate_identification.clone()).or_default() += 1;
+            }
+        }
+        res
+    }
+
+    /// Iterator over the ballots of all the elections
+    pub fn election_ballots(&self) -> impl Iterator<Item = &RawDataElectionBallot> {
+        self.counting_circles
+            .iter()
+            .flat_map(|cc| cc.election_group_ballots.iter())
+            .flat_map(|g| g.elections.iter())
+            .flat_map(|e| e.ballots.iter())
+    }
+}
+
+impl RawDataCountingCircle {
+    /// Number of e-voting cast votes in the counting circle
+    ///
+    /// The number of cast votes is the maximal number of raw ballots for the same ballot of a vote
+    /// or for the same election group.
+    pub fn number_of_cast_votes(&self) -> usize {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for v in self.votes.iter() {
+            for b in v.ballots.iter() {
+                *counts
+                    .entry(format!(
+                        "vote {} ballot {}",
+                        v.vote_identification, b.ballot_identification
+                    ))
+                    .or_default() += 1;
+            }
+        }
+        for g in self.election_group_ballots.iter() {
+            *counts
+                .entry(format!("election group {}", g.identification()))
+                .or_default() += 1;
+        }
+        counts.values().max().cloned().unwrap_or(0)
+    }
+}
+
+impl RawDataElectionGroupBallot {
+    /// Identification of the election group
+    ///
+    /// If missing (optional in the schema), the identifications of the elections are used
+    pub fn identification(&self) -> String {
+        match &self.election_group_identification {
+            Some(id) => id.clone(),
+            None => self
+                .elections
+                .iter()
+                .map(|e| e.election_identification.as_str())
+                .collect::<Vec<_>>()
+                .join("|"),
+        }
+    }
+}
+
+/// State of the reading of the file
+#[derive(Default)]
+struct RawDataReader {
+    /// Local names of the open elements
+    stack: Vec<String>,
+    contest_identification: Option<String>,
+    creation_date_time: Option<NaiveDateTime>,
+    counting_circles: Vec<RawDataCountingCircle>,
+    signature: Option<String>,
+}
+
+impl RawDataReader {
+    fn parent(&self) -> Option<&str> {
+        self.stack.iter().rev().nth(1).map(|s| s.as_str())
+    }
+
+    fn last_cc(&mut self, name: &str) -> anyhow::Result<&mut RawDataCountingCircle> {
+        last_mut(&mut self.counting_circles, name)
+    }
+
+    fn last_vote(&mut self, name: &str) -> anyhow::Result<&mut RawDataVote> {
+        last_mut(&mut self.last_cc(name)?.votes, name)
+    }
+
+    fn last_vote_ballot(&mut self, name: &str) -> anyhow::Result<&mut RawDataVoteBallot> {
+        last_mut(&mut self.last_vote(name)?.ballots, name)
+    }
+
+    fn last_question(&mut self, name: &str) -> anyhow::Result<&mut RawDataQuestion> {
+        last_mut(&mut self.last_vote_ballot(name)?.questions, name)
+    }
+
+    fn last_group(&mut self, name: &str) -> anyhow::Result<&mut RawDataElectionGroupBallot> {
+        last_mut(&mut self.last_cc(name)?.election_group_ballots, name)
+    }
+
+    fn last_election(&mut self, name: &str) -> anyhow::Result<&mut RawDataElection> {
+        last_mut(&mut self.last_group(name)?.elections, name)
+    }
+
+    fn last_election_ballot(&mut self, name: &str) -> anyhow::Result<&mut RawDataElectionBallot> {
+        last_mut(&mut self.last_election(name)?.ballots, name)
+    }
+
+    fn last_position(&mut self, name: &str) -> anyhow::Result<&mut RawDataBallotPosition> {
+        last_mut(&mut self.last_election_ballot(name)?.ballot_positions, name)
+    }
+
+    fn start(&mut self, name: &str) -> anyhow::Result<()> {
+        self.stack.push(name.to_string());
+        match name {
+            "countingCircleRawData" => self.counting_circles.push(RawDataCountingCircle::default()),
+            "voteRawData" => self.last_cc(name)?.votes.push(RawDataVote::default()),
+            "electionGroupBallotRawData" => self
+                .last_cc(name)?
+                .election_group_ballots
+                .push(RawDataElectionGroupBallot::default()),
+            "electionRawData" => self
+                .last_group(name)?
+                .elections
+                .push(RawDataElection::default()),
+            "ballotRawData" => match self.parent().map(|s| s.to_string()).as_deref() {
+                Some("voteRawData") => self
+                    .last_vote(name)?
+                    .ballots
+                    .push(RawDataVoteBallot::default()),
+                Some("electionRawData") => self
+                    .last_election(name)?
+                    .ballots
+                    .push(RawDataElectionBallot::default()),
+                _ => bail!("Element ballotRawData found outside a vote or an election"),
+            },
+            "questionRawData" => self
+                .last_vote_ballot(name)?
+                .questions
+                .push(RawDataQuestion::default()),
+            "candidate" => self.last_election_ballot(name)?.ballot_positions.push(
+                RawDataBallotPosition::Candidate {
+                    candidate_identification: String::new(),
+                    candidate_reference_on_position: String::new(),
+                },
+            ),
+            "isEmpty" => self
+                .last_election_ballot(name)?
+                .ballot_positions
+                .push(RawDataBallotPosition::Empty),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, text: String) -> anyhow::Result<()> {
+        let name = match self.stack.last() {
+            Some(n) => n.clone(),
+            None => return Ok(()),
+        };
+        match name.as_str() {
+            "contestIdentification" => self.contest_identification = Some(text),
+            "creationDateTime" => self.creation_date_time = Some(parse_datetime(&text)?),
+            "signature" => self.signature = Some(text),
+            "countingCircleId" => self.last_cc(&name)?.counting_circle_id = text,
+            "voteIdentification" => self.last_vote(&name)?.vote_identification = text,
+            "ballotIdentification" => self.last_vote_ballot(&name)?.ballot_identification = text,
+            "ballotCastedNumber" => {
+                self.last_vote_ballot(&name)?.ballot_casted_number = Some(
+                    text.parse::<usize>()
+                        .with_context(|| format!("ballotCastedNumber {} is not a number", text))?,
+                )
+            }
+            "questionIdentification" => self.last_question(&name)?.question_identification = text,
+            "castedVote" => {
+                self.last_question(&name)?.casted_vote = Some(
+                    text.parse::<usize>()
+                        .with_context(|| format!("castedVote {} is not a number", text))?,
+                )
+            }
+            "answerIdentification" => self.last_question(&name)?.answer_identification = Some(text),
+            "electionGroupIdentification" => {
+                self.last_group(&name)?.election_group_identification = Some(text)
+            }
+            "electionIdentification" => self.last_election(&name)?.election_identification = text,
+            "listIdentification" => {
+                self.last_election_ballot(&name)?.list_identification = Some(text)
+            }
+            "isUnchangedBallot" => {
+                self.last_election_ballot(&name)?.is_unchanged_ballot = Some(
+                    text.parse::<bool>()
+                        .with_context(|| format!("isUnchangedBallot {} is not a boolean", text))?,
+                )
+            }
+            "candidateIdentification" | "candidateReferenceOnPosition" => {
+                if let RawDataBallotPosition::Candidate {
+                    candidate_identification,
+                    candidate_reference_on_position,
+                } = self.last_position(&name)?
+                {
+                    match name.as_str() {
+                        "candidateIdentification" => *candidate_identification = text,
+                        _ => *candidate_reference_on_position = text,
+                    }
+                }
+            }
+            "writeIn" => *self.last_position(&name)? = RawDataBallotPosition::WriteIn(text),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) {
+        self.stack.pop();
+    }
+
+    fn finish(self, f: &File) -> anyhow::Result<ECH0222> {
         Ok(ECH0222 {
-            path: p.to_path_buf(),
+            file: f.clone(),
+            contest_identification: self
+                .contest_identification
+                .ok_or_else(|| anyhow!("contestIdentification not found"))?,
+            creation_date_time: self.creation_date_time,
+            counting_circles: self.counting_circles,
+            signature: Signature {
+                signature_contents: self
+                    .signature
+                    .ok_or_else(|| anyhow!("Signature not found"))?,
+            },
         })
     }
 }
 
+impl VerifierDataDecode for ECH0222 {
+    fn from_xml_file(f: &File) -> anyhow::Result<Self> {
+        let mut reader = f
+            .open_reader()
+            .map(Reader::from_reader)
+            .map_err(|e| e.context(format!("Error creating xml reader for file {}", f.to_str())))?;
+        reader.trim_text(true);
+        let mut state = RawDataReader::default();
+        let mut buf = Vec::new();
+        loop {
+            let position = reader.buffer_position();
+            let res = match reader.read_event_into(&mut buf) {
+                Err(e) => Err(anyhow!(e)),
+                Ok(Event::Eof) => break,
+                Ok(Event::Start(e)) => {
+                    state.start(&String::from_utf8_lossy(e.local_name().as_ref()))
+                }
+                Ok(Event::Empty(e)) => state
+                    .start(&String::from_utf8_lossy(e.local_name().as_ref()))
+                    .map(|_| state.end()),
+                Ok(Event::Text(e)) => e
+                    .unescape()
+                    .map_err(|e| anyhow!(e).context("Error reading text"))
+                    .and_then(|t| state.text(t.into_owned())),
+                Ok(Event::End(_)) => {
+                    state.end();
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            res.with_context(|| format!("Error at position {}", position))?;
+            buf.clear();
+        }
+        state.finish(f)
+    }
+}
+
 impl<'a> VerifiySignatureTrait<'a> for ECH0222 {
     fn get_hashable(&'a self) -> anyhow::Result<HashableMessage<'a>> {
-        let hashable = XMLFileHashable::new(&self.path, &SchemaKind::Ech0222, "eCH-0222:extension");
+        let hashable = XMLFileHashable::new(
+            &self.file.get_path(),
+            &SchemaKind::Ech0222,
+            "eCH-0222:extension",
+        )?
+        .with_source(self.file.source().clone())
+        .with_progress(report_progress("eCH-0222"));
         let hash = hashable.try_hash()?;
         Ok(HashableMessage::Hashed(hash))
     }
@@ -30,25 +440,152 @@ impl<'a> VerifiySignatureTrait<'a> for ECH0222 {
     }
 
     fn get_certificate_authority(&self) -> anyhow::Result<String> {
-        Ok(String::from(CertificateAuthority::Canton))
+        Ok(String::from(CertificateAuthority::SdmTally))
     }
 
-    fn get_signature(&self) -> ByteArray {
-        todo!()
+    fn get_signature(&self) -> anyhow::Result<ByteArray> {
+        self.signature.get_signature()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::config::test::test_dataset_tally_path;
+    use crate::{
+        config::test::test_dataset_tally_path,
+        data_structures::{tally::VerifierTallyDataType, VerifierDataType},
+        file_structure::data_source::local_source,
+    };
+    use std::fs;
+
+    fn data_type() -> VerifierDataType {
+        VerifierDataType::Tally(VerifierTallyDataType::ECH0222)
+    }
+
+    fn get_ech_0222() -> ECH0222 {
+        let file = File::new(&test_dataset_tally_path().join("tally"), &data_type(), None);
+        ECH0222::from_xml_file(&file).unwrap()
+    }
 
     #[test]
     fn read_data_set() {
-        let path = test_dataset_tally_path()
-            .join("tally")
-            .join("eCH-0222_Post_E2E_DEV.xml");
-        let ech_0222 = ECH0222::from_xml_file(&path);
-        assert!(ech_0222.is_ok())
+        let ech_0222 = get_ech_0222();
+        assert_eq!(ech_0222.contest_identification, "Post_E2E_DEV");
+        assert_eq!(
+            ech_0222.creation_date_time.unwrap().to_string(),
+            "2024-02-15 21:11:16.224450700"
+        );
+        assert_eq!(ech_0222.counting_circles.len(), 4);
+        assert!(!ech_0222.signature.signature_contents.is_empty());
+        let cc = ech_0222.counting_circle("10002").unwrap();
+        assert_eq!(cc.votes.len(), 1);
+        assert_eq!(cc.votes[0].vote_identification, "ch_test");
+        assert_eq!(cc.votes[0].ballots.len(), 2);
+        let question = &cc.votes[0].ballots[0].questions[0];
+        assert_eq!(
+            question.question_identification,
+            "16f99be5-0ce6-4a81-ae8b-1817ceecaebc"
+        );
+        assert_eq!(question.casted_vote, Some(3));
+        assert_eq!(
+            question.answer_identification,
+            Some("ecdbcc43-396a-348f-a671-919abfff4e12".to_string())
+        );
+        assert_eq!(cc.election_group_ballots.len(), 2);
+        let group = &cc.election_group_ballots[0];
+        assert_eq!(group.identification(), "nrw_test");
+        let ballot = &group.elections[0].ballots[0];
+        assert_eq!(ballot.list_identification, Some("99".to_string()));
+        assert_eq!(ballot.ballot_positions.len(), 6);
+        assert!(ballot
+            .ballot_positions
+            .iter()
+            .all(|p| p == &RawDataBallotPosition::Empty));
+        assert_eq!(ballot.is_unchanged_ballot, Some(true));
+        assert!(ech_0222.counting_circle("10003").unwrap().votes.is_empty());
+    }
+
+    #[test]
+    fn cast_votes() {
+        let ech_0222 = get_ech_0222();
+        let cast_votes = ech_0222.cast_votes_per_counting_circle();
+        assert_eq!(
+            cast_votes,
+            BTreeMap::from([
+                ("10001".to_string(), 1),
+                ("10002".to_string(), 1),
+                ("10003".to_string(), 0),
+                ("10004".to_string(), 2)
+            ])
+        );
+        assert_eq!(ech_0222.number_of_cast_votes(), 4);
+        assert_eq!(ech_0222.chosen_answers_count().values().sum::<usize>(), 16);
+        assert!(ech_0222.chosen_candidates_count().is_empty());
+    }
+
+    #[test]
+    fn read_candidates() {
+        let name = "rust_verifier_test_ech_0222.xml";
+        let file = File::new_with_name(&local_source(), &std::env::temp_dir(), &data_type(), name);
+        let path = file.get_path();
+        fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<eCH-0222:delivery xmlns:eCH-0222="http://www.ech.ch/xmlns/eCH-0222/1">
+  <eCH-0222:rawDataDelivery>
+    <eCH-0222:rawData>
+      <eCH-0222:contestIdentification>test</eCH-0222:contestIdentification>
+      <eCH-0222:countingCircleRawData>
+        <eCH-0222:countingCircleId>cc1</eCH-0222:countingCircleId>
+        <eCH-0222:electionGroupBallotRawData>
+          <eCH-0222:electionRawData>
+            <eCH-0222:electionIdentification>e1</eCH-0222:electionIdentification>
+            <eCH-0222:ballotRawData>
+              <eCH-0222:ballotPosition>
+                <eCH-0222:candidate>
+                  <eCH-0222:candidateIdentification>c1</eCH-0222:candidateIdentification>
+                  <eCH-0222:candidateReferenceOnPosition>01.01</eCH-0222:candidateReferenceOnPosition>
+                </eCH-0222:candidate>
+              </eCH-0222:ballotPosition>
+              <eCH-0222:ballotPosition>
+                <eCH-0222:candidate>
+                  <eCH-0222:writeIn>toto</eCH-0222:writeIn>
+                </eCH-0222:candidate>
+              </eCH-0222:ballotPosition>
+              <eCH-0222:ballotPosition>
+                <eCH-0222:isEmpty>true</eCH-0222:isEmpty>
+              </eCH-0222:ballotPosition>
+            </eCH-0222:ballotRawData>
+          </eCH-0222:electionRawData>
+        </eCH-0222:electionGroupBallotRawData>
+      </eCH-0222:countingCircleRawData>
+    </eCH-0222:rawData>
+    <eCH-0222:extension>
+      <signature>dGVzdA==</signature>
+    </eCH-0222:extension>
+  </eCH-0222:rawDataDelivery>
+</eCH-0222:delivery>"#,
+        )
+        .unwrap();
+        let ech_0222 = ECH0222::from_xml_file(&file).unwrap();
+        fs::remove_file(&path).unwrap();
+        let group = &ech_0222.counting_circles[0].election_group_ballots[0];
+        assert_eq!(group.identification(), "e1");
+        assert_eq!(
+            group.elections[0].ballots[0].ballot_positions,
+            vec![
+                RawDataBallotPosition::Candidate {
+                    candidate_identification: "c1".to_string(),
+                    candidate_reference_on_position: "01.01".to_string()
+                },
+                RawDataBallotPosition::WriteIn("toto".to_string()),
+                RawDataBallotPosition::Empty
+            ]
+        );
+        assert_eq!(
+            ech_0222.chosen_candidates_count(),
+            BTreeMap::from([("c1".to_string(), 1)])
+        );
+        assert_eq!(ech_0222.number_of_cast_votes(), 1);
     }
 }
@@ -1,11 +1,15 @@
 pub mod control_component_ballot_box_payload;
 pub mod control_component_shuffle_payload;
+#[cfg(feature = "native")]
 pub mod e_voting_decrypt;
+#[cfg(feature = "native")]
 pub mod ech_0110;
+#[cfg(feature = "native")]
 pub mod ech_0222;
 pub mod tally_component_shuffle_payload;
 pub mod tally_component_votes_payload;
 
+#[cfg(feature = "native")]
 use self::{
     control_component_ballot_box_payload::ControlComponentBallotBoxPayload,
     control_component_shuffle_payload::ControlComponentShufflePayload,
@@ -13,10 +17,16 @@ use self::{
     tally_component_shuffle_payload::TallyComponentShufflePayload,
     tally_component_votes_payload::TallyComponentVotesPayload,
 };
+#[cfg(feature = "native")]
 use super::{VerifierDataDecode, VerifierTallyDataTrait};
-use crate::file_structure::{file::File, FileReadMode, FileType};
+#[cfg(feature = "native")]
+use crate::file_structure::{file::File, FileType};
+#[cfg(feature = "native")]
+use anyhow::anyhow;
+#[cfg(feature = "native")]
 use enum_kinds::EnumKind;
 
+#[cfg(feature = "native")]
 #[derive(Clone, EnumKind)]
 #[enum_kind(VerifierTallyDataType)]
 pub enum VerifierTallyData {
@@ -29,6 +39,7 @@ pub enum VerifierTallyData {
     ControlComponentShufflePayload(ControlComponentShufflePayload),
 }
 
+#[cfg(feature = "native")]
 impl VerifierTallyDataType {
     pub fn get_file_type(&self) -> FileType {
         match self {
@@ -42,71 +53,48 @@ impl VerifierTallyDataType {
         }
     }
 
-    pub fn get_file_read_mode(&self) -> FileReadMode {
-        match self {
-            Self::EVotingDecrypt => FileReadMode::Memory,
-            Self::ECH0110 => FileReadMode::Memory,
-            Self::ECH0222 => FileReadMode::Memory,
-            Self::TallyComponentVotesPayload => FileReadMode::Memory,
-            Self::TallyComponentShufflePayload => FileReadMode::Memory,
-            Self::ControlComponentBallotBoxPayload => FileReadMode::Memory,
-            Self::ControlComponentShufflePayload => FileReadMode::Memory,
-        }
-    }
-
     /// Read from String as json or xml
     ///
     /// All the types have to implement the trait [VerifierDataDecode]
     pub fn verifier_data_from_file(&self, f: &File) -> anyhow::Result<VerifierTallyData> {
         match self {
             VerifierTallyDataType::EVotingDecrypt => {
-                EVotingDecrypt::from_file(f, &self.get_file_type(), &self.get_file_read_mode())
+                EVotingDecrypt::from_file(f, &self.get_file_type(), &f.read_mode())
                     .map(VerifierTallyData::EVotingDecrypt)
             }
             VerifierTallyDataType::ECH0110 => {
-                ECH0110::from_file(f, &self.get_file_type(), &self.get_file_read_mode())
+                ECH0110::from_file(f, &self.get_file_type(), &f.read_mode())
                     .map(VerifierTallyData::ECH0110)
             }
             VerifierTallyDataType::ECH0222 => {
-                ECH0222::from_file(f, &self.get_file_type(), &self.get_file_read_mode())
+                ECH0222::from_file(f, &self.get_file_type(), &f.read_mode())
                     .map(VerifierTallyData::ECH0222)
             }
             VerifierTallyDataType::TallyComponentVotesPayload => {
-                TallyComponentVotesPayload::from_file(
-                    f,
-                    &self.get_file_type(),
-                    &self.get_file_read_mode(),
-                )
-                .map(VerifierTallyData::TallyComponentVotesPayload)
+                TallyComponentVotesPayload::from_file(f, &self.get_file_type(), &f.read_mode())
+                    .map(VerifierTallyData::TallyComponentVotesPayload)
             }
             VerifierTallyDataType::TallyComponentShufflePayload => {
-                TallyComponentShufflePayload::from_file(
-                    f,
-                    &self.get_file_type(),
-                    &self.get_file_read_mode(),
-                )
-                .map(VerifierTallyData::TallyComponentShufflePayload)
+                TallyComponentShufflePayload::from_file(f, &self.get_file_type(), &f.read_mode())
+                    .map(VerifierTallyData::TallyComponentShufflePayload)
             }
             VerifierTallyDataType::ControlComponentBallotBoxPayload => {
                 ControlComponentBallotBoxPayload::from_file(
                     f,
                     &self.get_file_type(),
-                    &self.get_file_read_mode(),
+                    &f.read_mode(),
                 )
                 .map(VerifierTallyData::ControlComponentBallotBoxPayload)
             }
             VerifierTallyDataType::ControlComponentShufflePayload => {
-                ControlComponentShufflePayload::from_file(
-                    f,
-                    &self.get_file_type(),
-                    &self.get_file_read_mode(),
-                )
-                .map(VerifierTallyData::ControlComponentShufflePayload)
+                ControlComponentShufflePayload::from_file(f, &self.get_file_type(), &f.read_mode())
+                    .map(VerifierTallyData::ControlComponentShufflePayload)
             }
         }
     }
 }
 
+#[cfg(feature = "native")]
 impl VerifierTallyDataTrait for VerifierTallyData {
     fn e_voting_decrypt(&self) -> Option<&EVotingDecrypt> {
         if let VerifierTallyData::EVotingDecrypt(d) = self {
@@ -153,3 +141,13 @@ impl VerifierTallyDataTrait for VerifierTallyData {
         None
     }
 }
+
+/// Helper to take the last element of a list as mutable, with an error if the list is empty
+///
+/// Used by the streaming readers of the xml files, where the elements are added to the last
+/// opened parent
+#[cfg(feature = "native")]
+fn last_mut<'a, T>(list: &'a mut [T], name: &str) -> anyhow::Result<&'a mut T> {
+    list.last_mut()
+        .ok_or_else(|| anyhow!(format!("Element {} found outside its parent", name)))
+}
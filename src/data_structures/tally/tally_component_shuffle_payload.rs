@@ -1,12 +1,12 @@
 use super::super::{
     common_types::{EncryptionParametersDef, ExponentiatedEncryptedElement, Signature},
     deserialize_seq_string_base64_to_seq_integer, deserialize_string_base64_to_integer,
-    implement_trait_verifier_data_json_decode, VerifierDataDecode,
+    implement_trait_verifier_data_json_decode, PayloadIdentityTrait, VerifierDataDecode,
 };
 use crate::data_structures::common_types::DecryptionProof;
 use anyhow::anyhow;
 use rug::Integer;
-use rust_ev_crypto_primitives::EncryptionParameters;
+use rust_ev_crypto_primitives::{ByteArray, Decode, EncryptionParameters};
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -22,6 +22,16 @@ pub struct TallyComponentShufflePayload {
 }
 implement_trait_verifier_data_json_decode!(TallyComponentShufflePayload);
 
+impl PayloadIdentityTrait for TallyComponentShufflePayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        Some(&self.ballot_box_id)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct VerifiableShuffle {
@@ -104,6 +114,20 @@ pub struct DecryptedVote {
     pub message: Vec<String>,
 }
 
+impl DecryptedVote {
+    /// The elements of the message (encoded in base64) as integers
+    pub fn message_as_integers(&self) -> anyhow::Result<Vec<Integer>> {
+        self.message
+            .iter()
+            .map(|m| {
+                ByteArray::base64_decode(m)
+                    .map(|b| b.into_mp_integer())
+                    .map_err(|e| anyhow!(format!("Cannot decode the message {}: {}", m, e)))
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -116,6 +140,16 @@ mod test {
         let json = fs::read_to_string(path).unwrap();
         let r_eec = TallyComponentShufflePayload::from_json(&json);
         println!("{:?}", r_eec.as_ref().err());
-        assert!(r_eec.is_ok())
+        assert!(r_eec.is_ok());
+        let votes = r_eec
+            .unwrap()
+            .verifiable_plaintext_decryption
+            .decrypted_votes;
+        let m = votes[1].message_as_integers().unwrap();
+        assert_eq!(m, vec![Integer::from(1), Integer::from(1)]);
+        let wrong = DecryptedVote {
+            message: vec!["AQ==".to_string(), "#".to_string()],
+        };
+        assert!(wrong.message_as_integers().is_err());
     }
 }
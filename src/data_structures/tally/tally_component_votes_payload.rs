@@ -1,6 +1,6 @@
 use super::super::{
     common_types::{EncryptionParametersDef, Signature},
-    implement_trait_verifier_data_json_decode, VerifierDataDecode,
+    implement_trait_verifier_data_json_decode, PayloadIdentityTrait, VerifierDataDecode,
 };
 use anyhow::anyhow;
 use rust_ev_crypto_primitives::EncryptionParameters;
@@ -22,6 +22,16 @@ pub struct TallyComponentVotesPayload {
 
 implement_trait_verifier_data_json_decode!(TallyComponentVotesPayload);
 
+impl PayloadIdentityTrait for TallyComponentVotesPayload {
+    fn election_event_id(&self) -> Option<&String> {
+        Some(&self.election_event_id)
+    }
+
+    fn vcs_or_bb_id(&self) -> Option<&String> {
+        Some(&self.ballot_box_id)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
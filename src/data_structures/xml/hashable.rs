@@ -3,7 +3,13 @@ use super::{
     schema::{Schema, SchemaKind},
     schema_tree::{ComplexTypeChildKind, ElementNode},
 };
-use anyhow::{anyhow, Context};
+use crate::{
+    application_runner::forward_hash_progress,
+    file_structure::data_source::{local_source, DataSource, FileReader},
+};
+use anyhow::{anyhow, bail, Context};
+use lazy_static::lazy_static;
+use log::debug;
 use quick_xml::{
     events::Event,
     name::{Namespace, QName, ResolveResult::*},
@@ -11,27 +17,76 @@ use quick_xml::{
 };
 use rust_ev_crypto_primitives::{ByteArray, Decode, HashableMessage, RecursiveHashTrait};
 use std::{
+    cell::Cell,
     collections::HashMap,
-    fs::File,
-    io::BufReader,
+    fs,
     path::{Path, PathBuf},
     str,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::SystemTime,
 };
 
+/// Callback reporting the progress of the hashing
+///
+/// The arguments are the number of bytes processed and the total number of bytes of the file
+pub type HashProgressFn = dyn Fn(u64, u64) + Send + Sync;
+
+/// Callback receiving the xml events read during the hashing
+///
+/// See [XMLFileHashable::try_hash_with_tap]
+pub type EventTap<'t> = dyn Fn(&Event) + 't;
+
+/// Maximal number of times the progress is reported during the hashing of a file
+const PROGRESS_STEPS: u64 = 100;
+
+/// Size and modification time of a file, to detect a modification after the hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Outcome of the hashing of a file (digest or error message), with the stamp of the file
+type CachedDigest = (FileStamp, Result<ByteArray, String>);
+
+lazy_static! {
+    /// Digests of the files, per path and excluded tag
+    static ref DIGEST_CACHE: Mutex<HashMap<(PathBuf, String), CachedDigest>> =
+        Mutex::new(HashMap::new());
+}
+
 /// An struct to hash the xml file according to the specification of Swiss Post
 /// TODO: the options (xs:choice) are missing
 pub struct XMLFileHashable {
+    source: Arc<dyn DataSource>,
     file: PathBuf,
     schema: &'static Schema<'static>,
     exclusion: String,
+    progress: Option<Box<HashProgressFn>>,
+    with_cache: bool,
 }
 
 /// An struct to hash a node in an xml file according to the specification of Swiss Post
 struct NodeHashable<'a> {
-    reader: &'a mut NsReader<BufReader<File>>,
+    reader: &'a mut NsReader<FileReader>,
     tag_name: &'a str,
     schema_node: &'a ElementNode,
     exclusion: String,
+    progress: Option<&'a ProgressReporter<'a>>,
+    tap: Option<&'a EventTap<'a>>,
+}
+
+/// Report the progress to the callback, at most [PROGRESS_STEPS] times
+///
+/// The callback is called synchronously by the reader. A slow callback slows down the hashing,
+/// so that the reader never runs ahead of the consumer of the progress.
+struct ProgressReporter<'a> {
+    callback: &'a HashProgressFn,
+    total: u64,
+    next: Cell<u64>,
 }
 
 impl XMLFileHashable {
@@ -39,57 +94,245 @@ impl XMLFileHashable {
     ///
     /// `exclusion` contains the name of the tag to be excluded. The tag should be exactly
     /// the same than in xml file (with or without namespaces)
-    pub fn new(xml: &Path, schema_kind: &SchemaKind, exclusion: &str) -> Self {
-        Self::new_with_schema(xml, schema_kind.schema(), exclusion)
+    ///
+    /// Error if the schema cannot be loaded (e.g. a schema overriding the embedded xsd is not
+    /// valid)
+    pub fn new(xml: &Path, schema_kind: &SchemaKind, exclusion: &str) -> anyhow::Result<Self> {
+        Ok(Self::new_with_schema(
+            xml,
+            schema_kind.try_schema()?,
+            exclusion,
+        ))
     }
 
     pub fn new_with_schema(xml: &Path, schema: &'static Schema<'static>, exclusion: &str) -> Self {
         Self {
+            source: local_source(),
             file: xml.to_path_buf(),
             schema,
             exclusion: exclusion.to_string(),
+            progress: None,
+            with_cache: false,
         }
     }
-}
 
-impl RecursiveHashTrait for XMLFileHashable {
-    type Error = anyhow::Error;
+    /// Read the file from the given source (per default on the disk)
+    pub fn with_source(mut self, source: Arc<dyn DataSource>) -> Self {
+        self.source = source;
+        self
+    }
 
-    fn try_hash(&self) -> Result<ByteArray, Self::Error> {
-        let mut reader = NsReader::from_file(&self.file).map_err(|e| {
-            anyhow!(e).context(format!(
-                "Error creating xml reader for file {}",
-                self.file.to_str().unwrap()
-            ))
-        })?;
+    /// Set a callback reporting the progress of the hashing
+    ///
+    /// The callback is called with the number of bytes processed and the total size of the
+    /// file. It is useful for huge files, whose hashing can take several minutes.
+    pub fn with_progress(mut self, callback: impl Fn(u64, u64) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Use the digest cached by a previous pass over the file (see [Self::cached_digest])
+    ///
+    /// Used for the big files, that are read once to be decoded and hashed at the same time (see
+    /// [Self::try_hash_with_tap]). The authenticity verification takes then the cached digest.
+    pub fn with_cache(mut self) -> Self {
+        self.with_cache = true;
+        self
+    }
+
+    /// Key of the digest in the cache
+    fn cache_key(&self) -> (PathBuf, String) {
+        (self.file.clone(), self.exclusion.clone())
+    }
+
+    /// Digest calculated by a previous pass over the file, if the file is unchanged since
+    ///
+    /// The outcome is an error if the previous calculation failed. `None` if the digest was never
+    /// calculated or if the file changed.
+    pub fn cached_digest(&self) -> Option<anyhow::Result<ByteArray>> {
+        let stamp = FileStamp::of(&self.file)?;
+        match DIGEST_CACHE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&self.cache_key())
+        {
+            Some((s, res)) if *s == stamp => Some(res.clone().map_err(|e| anyhow!(e))),
+            _ => None,
+        }
+    }
+
+    /// Keep the outcome of the hashing in the cache
+    fn store_digest(&self, stamp: Option<FileStamp>, res: &anyhow::Result<ByteArray>) {
+        if let Some(s) = stamp {
+            DIGEST_CACHE
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(
+                    self.cache_key(),
+                    (
+                        s,
+                        res.as_ref()
+                            .map(|b| b.clone())
+                            .map_err(|e| format!("{:#}", e)),
+                    ),
+                );
+        }
+    }
+
+    /// Calculate the digest, giving all the events read to `tap`
+    ///
+    /// The caller can decode the file from the events, so that the file is read only once for
+    /// the decoding and the hashing. The outcome is cached (see [Self::cached_digest]).
+    pub fn try_hash_with_tap(&self, tap: &EventTap) -> anyhow::Result<ByteArray> {
+        let stamp = FileStamp::of(&self.file);
+        let res = self.hash_file(Some(tap));
+        self.store_digest(stamp, &res);
+        res
+    }
+
+    /// Read the file and calculate the digest
+    fn hash_file(&self, tap: Option<&EventTap>) -> anyhow::Result<ByteArray> {
+        let mut reader = self
+            .source
+            .open_reader(&self.file)
+            .map(NsReader::from_reader)
+            .map_err(|e| {
+                e.context(format!(
+                    "Error creating xml reader for file {}",
+                    self.file.display()
+                ))
+            })?;
+        let total = self.source.file_len(&self.file).unwrap_or(0);
+        let reporter = self
+            .progress
+            .as_deref()
+            .map(|f| ProgressReporter::new(f, total));
         let mut buf = Vec::new();
         let schema_node = ElementNode::try_from(self.schema)?;
         let _ns = self.schema.target_namespace_name().as_bytes();
         loop {
-            //match reader.read_resolved_event_into(/*&mut buf*/).unwrap() {
-            match reader.read_resolved_event_into(&mut buf) {
-                Ok((Bound(Namespace(_ns)), Event::Start(e))) => {
+            let (ns, event) = reader
+                .read_resolved_event_into(&mut buf)
+                .map_err(|e| anyhow!(e).context("Error reader in try_hash"))?;
+            if let Some(t) = tap {
+                t(&event)
+            }
+            match (ns, event) {
+                (Bound(Namespace(_ns)), Event::Start(e)) => {
                     let tag_local_name = e.local_name();
-                    let tag_name = str::from_utf8(tag_local_name.as_ref()).unwrap();
+                    let tag_name = str::from_utf8(tag_local_name.as_ref())
+                        .context("The name of the tag is not valid UTF-8")?;
                     if tag_name == schema_node.name() {
-                        return NodeHashable::new(
+                        let res = NodeHashable::new(
                             &schema_node,
                             tag_name,
                             &mut reader,
                             &self.exclusion,
+                            reporter.as_ref(),
+                            tap,
                         )
                         .try_hash();
+                        if res.is_ok() {
+                            if let Some(r) = reporter.as_ref() {
+                                r.finish()
+                            }
+                        }
+                        return res;
                     }
                 }
-                Ok((_, Event::Eof)) => panic!("tag {} not found", schema_node.name()),
-                Ok(_) => (),
-                Err(e) => return Err(anyhow!(e).context("Error reader in try_hash")),
+                (_, Event::Eof) => bail!("tag {} not found", schema_node.name()),
+                _ => (),
             }
             buf.clear();
         }
     }
 }
 
+impl FileStamp {
+    /// Stamp of the file. `None` if the file is not on the disk
+    fn of(path: &Path) -> Option<Self> {
+        fs::metadata(path).ok().map(|m| Self {
+            len: m.len(),
+            modified: m.modified().ok(),
+        })
+    }
+}
+
+/// Callback reporting the progress of the hashing of the file `name`
+///
+/// Each step is forwarded to the event streams of the runner (see
+/// [crate::application_runner::forward_hash_progress]). The progress is also logged every 10
+/// percent
+pub fn report_progress(name: &str) -> impl Fn(u64, u64) + Send + Sync + 'static {
+    let name = name.to_string();
+    let last_logged = AtomicU64::new(0);
+    move |processed, total| {
+        if total == 0 {
+            return;
+        }
+        forward_hash_progress(&name, processed, total);
+        let percent = processed * 100 / total;
+        let decile = percent / 10;
+        if decile > last_logged.load(Ordering::Relaxed) {
+            last_logged.store(decile, Ordering::Relaxed);
+            debug!(
+                "Hashing of {}: {}% ({} of {} bytes)",
+                name, percent, processed, total
+            );
+        }
+    }
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn new(callback: &'a HashProgressFn, total: u64) -> Self {
+        Self {
+            callback,
+            total,
+            next: Cell::new(0),
+        }
+    }
+
+    /// Size of a step between two reports
+    fn step(&self) -> u64 {
+        (self.total / PROGRESS_STEPS).max(1)
+    }
+
+    /// Report the position, if the next step is reached
+    fn report(&self, position: u64) {
+        let position = position.min(self.total);
+        if position >= self.next.get() {
+            (self.callback)(position, self.total);
+            self.next.set(position + self.step());
+        }
+    }
+
+    /// Report the end of the hashing
+    fn finish(&self) {
+        (self.callback)(self.total, self.total)
+    }
+}
+
+impl RecursiveHashTrait for XMLFileHashable {
+    type Error = anyhow::Error;
+
+    /// Calculate the digest
+    ///
+    /// If the cache is used (see [XMLFileHashable::with_cache]), the digest of a previous pass
+    /// is taken and the outcome is cached.
+    fn try_hash(&self) -> Result<ByteArray, Self::Error> {
+        if !self.with_cache {
+            return self.hash_file(None);
+        }
+        if let Some(res) = self.cached_digest() {
+            return res;
+        }
+        let stamp = FileStamp::of(&self.file);
+        let res = self.hash_file(None);
+        self.store_digest(stamp, &res);
+        res
+    }
+}
+
 impl<'a> NodeHashable<'a> {
     /// Create a new [NodeHashable]
     ///
@@ -98,23 +341,45 @@ impl<'a> NodeHashable<'a> {
     fn new(
         schema_node: &'a ElementNode,
         tag_name: &'a str,
-        reader: &'a mut NsReader<BufReader<File>>,
+        reader: &'a mut NsReader<FileReader>,
         exclusion: &str,
+        progress: Option<&'a ProgressReporter<'a>>,
+        tap: Option<&'a EventTap<'a>>,
     ) -> Self {
         Self {
             reader,
             tag_name,
             schema_node,
             exclusion: exclusion.to_string(),
+            progress,
+            tap,
+        }
+    }
+
+    /// Read the next event, giving it to the tap
+    fn read_event<'b>(&mut self, buf: &'b mut Vec<u8>) -> Result<Event<'b>, quick_xml::Error> {
+        let res = self.reader.read_event_into(buf);
+        if let (Ok(e), Some(tap)) = (&res, self.tap) {
+            tap(e)
+        }
+        res
+    }
+
+    /// Report the current position of the reader to the progress reporter
+    fn report_progress(&self) {
+        if let Some(r) = self.progress {
+            r.report(self.reader.buffer_position() as u64)
         }
     }
 
     /// Hash a native type
     fn hash_native_type(&mut self, native_type: &str) -> anyhow::Result<ByteArray> {
         let mut buf = Vec::new();
-        match self.reader.read_event_into(&mut buf) {
+        match self.read_event(&mut buf) {
             Ok(Event::Text(b)) => Ok(NativeTypeConverter::new(
-                b.unescape().unwrap().into_owned().as_str(),
+                b.unescape()
+                    .map_err(|e| anyhow!(e).context("Error unescaping the text"))?
+                    .as_ref(),
                 native_type,
             )?
             .to_hashable()?
@@ -148,6 +413,8 @@ impl<'a> NodeHashable<'a> {
             schema_node.name(),
             self.reader,
             &self.exclusion,
+            self.progress,
+            self.tap,
         )
         .try_hash()
     }
@@ -158,8 +425,7 @@ impl<'a> NodeHashable<'a> {
         element_node: &ElementNode,
         hashed_children: &HashMap<String, Vec<ByteArray>>,
     ) {
-        if hashed_children.contains_key(element_node.name()) {
-            let values = hashed_children.get(element_node.name()).unwrap();
+        if let Some(values) = hashed_children.get(element_node.name()) {
             if values.len() == 1 {
                 hashables.push(HashableMessage::Hashed(values[0].clone()));
             } else {
@@ -171,8 +437,7 @@ impl<'a> NodeHashable<'a> {
             }
         } else {
             let exclusion_local_name = QName(self.exclusion.as_bytes()).local_name();
-            let exclusion_name = str::from_utf8(exclusion_local_name.as_ref()).unwrap();
-            if exclusion_name != element_node.name() {
+            if exclusion_local_name.as_ref() != element_node.name().as_bytes() {
                 hashables.push(HashableMessage::Hashed(
                     hashable_no_value(element_node.name()).hash(),
                 ));
@@ -197,26 +462,23 @@ impl<'a> NodeHashable<'a> {
         element_nodes: &[ComplexTypeChildKind],
         hashed_children: &HashMap<String, Vec<ByteArray>>,
     ) {
-        match element_nodes
-            .iter()
-            .find(|e| e.is_element() && hashed_children.contains_key(e.unwrap_element().name()))
-        {
-            Some(e) => {
-                self.push_hashed_from_element_node(hashables, e.unwrap_element(), hashed_children)
-            }
+        let element = element_nodes.iter().find_map(|e| match e {
+            ComplexTypeChildKind::Element(n) if hashed_children.contains_key(n.name()) => Some(n),
+            _ => None,
+        });
+        match element {
+            Some(n) => self.push_hashed_from_element_node(hashables, n, hashed_children),
             None => {
-                match element_nodes.iter().find(|e| {
-                    e.is_sequence()
-                        && e.unwrap_sequence()
-                            .iter()
-                            .any(|e| hashed_children.contains_key(e.name()))
-                }) {
-                    Some(e) => self.push_hashed_from_sequence(
-                        hashables,
-                        e.unwrap_sequence(),
-                        hashed_children,
-                    ),
-                    None => (),
+                let sequence = element_nodes.iter().find_map(|e| match e {
+                    ComplexTypeChildKind::Sequence(seq, _)
+                        if seq.iter().any(|n| hashed_children.contains_key(n.name())) =>
+                    {
+                        Some(seq)
+                    }
+                    _ => None,
+                });
+                if let Some(seq) = sequence {
+                    self.push_hashed_from_sequence(hashables, seq, hashed_children)
                 }
             }
         }
@@ -239,10 +501,10 @@ impl<'a> NodeHashable<'a> {
                 ComplexTypeChildKind::Element(e) => {
                     self.push_hashed_from_element_node(&mut hashables, e, hashed_children)
                 }
-                ComplexTypeChildKind::Sequence(seq) => {
+                ComplexTypeChildKind::Sequence(seq, _) => {
                     self.push_hashed_from_sequence(&mut hashables, seq, hashed_children)
                 }
-                ComplexTypeChildKind::Choice(choices) => {
+                ComplexTypeChildKind::Choice(choices, _) => {
                     self.push_hashed_from_choices(&mut hashables, choices, hashed_children)
                 }
             }
@@ -256,7 +518,7 @@ impl<'a> NodeHashable<'a> {
         let mut hm: HashMap<String, Vec<ByteArray>> = HashMap::new();
         let mut is_in_exclusion = false;
         loop {
-            match self.reader.read_event_into(&mut buf) {
+            match self.read_event(&mut buf) {
                 Ok(Event::Start(e)) => {
                     if !is_in_exclusion {
                         let tag_local_name = e.local_name();
@@ -265,10 +527,7 @@ impl<'a> NodeHashable<'a> {
                             is_in_exclusion = true;
                         } else {
                             let hash = self.get_hash_from_child(tag_name)?;
-                            if !hm.contains_key(tag_name) {
-                                hm.insert(tag_name.to_string(), vec![]);
-                            }
-                            hm.get_mut(tag_name).unwrap().push(hash);
+                            hm.entry(tag_name.to_string()).or_default().push(hash);
                         }
                     }
                 }
@@ -285,6 +544,7 @@ impl<'a> NodeHashable<'a> {
                     return Err(anyhow!(e).context("Error in hash_complex_type getting the type"));
                 }
             }
+            self.report_progress();
             buf.clear();
         }
         self.hash_hashed_children(&hm)
@@ -389,6 +649,73 @@ mod test {
         assert_eq!(xml_hashable.try_hash().unwrap(), expected)
     }
 
+    #[test]
+    fn test_1_schema_1_with_progress() {
+        use std::sync::{Arc, Mutex};
+        let xml = test_xml_path().join("test_1_schema_1.xml");
+        let total = fs::metadata(&xml).unwrap().len();
+        let reported = Arc::new(Mutex::new(vec![]));
+        let reported_cloned = reported.clone();
+        let xml_hashable = XMLFileHashable::new_with_schema(&xml, get_schema_test_1(), "")
+            .with_progress(move |p, t| reported_cloned.lock().unwrap().push((p, t)));
+        let expected = HashableMessage::from(vec![
+            HashableMessage::from("test"),
+            HashableMessage::from("true"),
+            HashableMessage::from(10usize),
+        ])
+        .hash();
+        assert_eq!(xml_hashable.try_hash().unwrap(), expected);
+        let reported = reported.lock().unwrap();
+        assert!(reported.len() > 1);
+        assert!(reported.iter().all(|(_, t)| *t == total));
+        assert!(reported.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert_eq!(reported.last().unwrap(), &(total, total));
+    }
+
+    #[test]
+    fn test_1_schema_1_with_tap() {
+        use std::cell::RefCell;
+        let xml = test_xml_path().join("test_1_schema_1.xml");
+        let xml_hashable =
+            XMLFileHashable::new_with_schema(&xml, get_schema_test_1(), "").with_cache();
+        let texts = RefCell::new(vec![]);
+        let hash = xml_hashable
+            .try_hash_with_tap(&|e| {
+                if let Event::Text(t) = e {
+                    let t = t.unescape().unwrap().trim().to_string();
+                    if !t.is_empty() {
+                        texts.borrow_mut().push(t)
+                    }
+                }
+            })
+            .unwrap();
+        assert_eq!(texts.into_inner(), vec!["test", "0", "10"]);
+        assert_eq!(xml_hashable.cached_digest().unwrap().unwrap(), hash);
+        assert_eq!(xml_hashable.try_hash().unwrap(), hash);
+        assert!(
+            XMLFileHashable::new_with_schema(&xml, get_schema_test_1(), "valueInt")
+                .cached_digest()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_progress_reporter() {
+        use std::sync::Mutex;
+        let reported = Mutex::new(vec![]);
+        let callback = |p: u64, _: u64| reported.lock().unwrap().push(p);
+        let reporter = ProgressReporter::new(&callback, 1000);
+        for p in 0..1200 {
+            reporter.report(p)
+        }
+        reporter.finish();
+        let reported = reported.into_inner().unwrap();
+        assert_eq!(reported.len(), 102);
+        assert_eq!(reported[1], 10);
+        assert_eq!(reported[100], 1000);
+        assert_eq!(reported[101], 1000);
+    }
+
     #[test]
     fn test_1_schema_1_with_exclusion() {
         let xml = test_xml_path().join("test_1_schema_1.xml");
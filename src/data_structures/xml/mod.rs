@@ -1,7 +1,10 @@
 //! Module to manage the schemas used for the verifier
+#[cfg(feature = "native")]
 pub mod hashable;
 mod schema;
+mod schema_resources;
 mod schema_tree;
+mod validator;
 
 use anyhow::anyhow;
 use quick_xml::{
@@ -12,7 +15,12 @@ use quick_xml::{
 use rust_ev_crypto_primitives::HashableMessage;
 use std::io::BufRead;
 
-pub use schema::SchemaKind;
+pub use schema::{SchemaKind, SchemaVersion};
+pub use schema_resources::{
+    embedded_xsd_text, override_schema, override_schema_from_file, schema_resources, xsd_text,
+    SchemaResource,
+};
+pub use validator::SchemaValidator;
 
 // reads from a start tag all the way to the corresponding end tag,
 // returns the bytes of the whole tag
@@ -33,7 +41,7 @@ pub fn xml_read_to_end_into_buffer<R: BufRead>(
         junk_buf.clear();
         let event = reader
             .read_event_into(junk_buf)
-            .map_err(|e| anyhow!(e).context("format!(Error reading event"))?;
+            .map_err(|e| anyhow!(e).context("Error reading event"))?;
         w.write_event(&event).map_err(|e| {
             anyhow!(e).context(format!("Error writing event {:?} in writer", event))
         })?;
@@ -47,7 +55,10 @@ pub fn xml_read_to_end_into_buffer<R: BufRead>(
                 depth -= 1;
             }
             Event::Eof => {
-                panic!("oh no")
+                return Err(anyhow!(
+                    "End of file reached before the end of the tag {:?}",
+                    String::from_utf8_lossy(tag_name.as_ref())
+                ))
             }
             _ => {}
         }
@@ -1,14 +1,16 @@
 //! Module to read the static schema and to prodive some functionalities in the
 //! structure
 //!
-//! Use the object [OnceLock] to create the structure only once from the static string. Action is thread safe
+//! The schemas are parsed lazily and cached in a registry keyed by the kind of schema and the
+//! version of the dataset ([SchemaVersion]). Then datasets of different versions can be
+//! validated in the same process. Action is thread safe.
 
-use crate::resources;
+use super::schema_resources::xsd_text;
 use anyhow::{anyhow, Context, Result};
 use core::fmt;
 use roxmltree::{Document, Node as RoNode};
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock, PoisonError};
 
 const NS_ECH_0006: &str = "http://www.ech.ch/xmlns/eCH-0006/2";
 const NS_ECH_0007: &str = "http://www.ech.ch/xmlns/eCH-0007/6";
@@ -19,21 +21,17 @@ const NS_ECH_0058: &str = "http://www.ech.ch/xmlns/eCH-0058/5";
 const NS_ECH_0155: &str = "http://www.ech.ch/xmlns/eCH-0155/4";
 const NS_ECH_0222: &str = "http://www.ech.ch/xmlns/eCH-0222/1";
 
-static SCHEMA_CELL_ECH_0006: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_0007: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_0008: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_0010: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_0044: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_0058: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_0110: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_0155: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_0222: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_DECRYPT: OnceLock<Schema> = OnceLock::new();
-static SCHEMA_CELL_ECH_CONFIG: OnceLock<Schema> = OnceLock::new();
+/// Registry of the parsed schemas
+///
+/// The schemas are never dropped (they are leaked to get a static lifetime), what is
+/// acceptable since the number of kinds and versions is small.
+type SchemaRegistry = Mutex<HashMap<(SchemaKind, SchemaVersion), &'static Schema<'static>>>;
+
+static SCHEMA_REGISTRY: OnceLock<SchemaRegistry> = OnceLock::new();
 
 const XML_SCHEMA_URI: &str = "http://www.w3.org/2001/XMLSchema";
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 /// Enumarate for the kind of schemas.
 pub enum SchemaKind {
@@ -50,11 +48,44 @@ pub enum SchemaKind {
     Config,
 }
 
+/// Version of the dataset, defining the set of XSD to use
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum SchemaVersion {
+    /// Version 1.2 of the E-Voting system
+    #[default]
+    V1_2,
+    /// Version 1.3 of the E-Voting system. The elections of the decrypt file are given in
+    /// election groups (evoting-decrypt-1-4)
+    V1_3,
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaVersion::V1_2 => write!(f, "1.2"),
+            SchemaVersion::V1_3 => write!(f, "1.3"),
+        }
+    }
+}
+
+impl TryFrom<&str> for SchemaVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.split('.').take(2).collect::<Vec<_>>().as_slice() {
+            ["1", "2"] => Ok(Self::V1_2),
+            ["1", "3"] => Ok(Self::V1_3),
+            _ => Err(anyhow!("No schema for the dataset version {}", value)),
+        }
+    }
+}
+
 /// Schema containing the structure of the schema
 #[allow(dead_code)]
 pub struct Schema<'a> {
     document: Document<'a>,
     schema_kind: Option<SchemaKind>,
+    version: SchemaVersion,
     target_namespace_name: String,
     target_namespace_uri: String,
     xml_schema_name: String,
@@ -76,77 +107,99 @@ impl<'a> fmt::Debug for Schema<'a> {
 }
 
 impl SchemaKind {
-    /// Get the schema structure
+    /// All the kinds of schemas
+    pub const ALL: [SchemaKind; 11] = [
+        SchemaKind::Ech0006,
+        SchemaKind::Ech0007,
+        SchemaKind::Ech0008,
+        SchemaKind::Ech0010,
+        SchemaKind::Ech0044,
+        SchemaKind::Ech0058,
+        SchemaKind::Ech0110,
+        SchemaKind::Ech0155,
+        SchemaKind::Ech0222,
+        SchemaKind::Decrypt,
+        SchemaKind::Config,
+    ];
+
+    /// The xsd of the schema for the given version
+    ///
+    /// The embedded xsd, or the xsd overriding it (see [super::schema_resources::override_schema])
+    fn xsd(&self, version: SchemaVersion) -> &'static str {
+        xsd_text(*self, version)
+    }
+
+    /// The schema for the given version is parsed and cached
+    pub(super) fn is_loaded(&self, version: SchemaVersion) -> bool {
+        SCHEMA_REGISTRY.get().is_some_and(|r| {
+            r.lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .contains_key(&(*self, version))
+        })
+    }
+
+    /// Get the schema structure for the default version
     ///
     /// Error if a nerror occurs
     pub fn try_schema(&self) -> anyhow::Result<&'static Schema<'static>> {
-        match self {
-            SchemaKind::Ech0006 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0006)?;
-                Ok(SCHEMA_CELL_ECH_0006.get_or_init(|| xsd))
-            }
-            SchemaKind::Ech0007 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0007)?;
-                Ok(SCHEMA_CELL_ECH_0007.get_or_init(|| xsd))
-            }
-            SchemaKind::Ech0008 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0008)?;
-                Ok(SCHEMA_CELL_ECH_0008.get_or_init(|| xsd))
-            }
-            SchemaKind::Ech0010 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0010)?;
-                Ok(SCHEMA_CELL_ECH_0010.get_or_init(|| xsd))
-            }
-            SchemaKind::Ech0044 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0044)?;
-                Ok(SCHEMA_CELL_ECH_0044.get_or_init(|| xsd))
-            }
-            SchemaKind::Ech0058 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0058)?;
-                Ok(SCHEMA_CELL_ECH_0058.get_or_init(|| xsd))
-            }
-            SchemaKind::Ech0110 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0110)?;
-                Ok(SCHEMA_CELL_ECH_0110.get_or_init(|| xsd))
-            }
-            SchemaKind::Ech0155 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0155)?;
-                Ok(SCHEMA_CELL_ECH_0155.get_or_init(|| xsd))
-            }
-            SchemaKind::Ech0222 => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_ECH_0222)?;
-                Ok(SCHEMA_CELL_ECH_0222.get_or_init(|| xsd))
-            }
-            SchemaKind::Decrypt => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_DECRYPT)?;
-                Ok(SCHEMA_CELL_ECH_DECRYPT.get_or_init(|| xsd))
-            }
-            SchemaKind::Config => {
-                let xsd = Schema::try_new(Some(*self), resources::XSD_CONFIG)?;
-                Ok(SCHEMA_CELL_ECH_CONFIG.get_or_init(|| xsd))
-            }
+        self.try_schema_with_version(SchemaVersion::default())
+    }
+
+    /// Get the schema structure for the given version
+    ///
+    /// The schema is parsed at the first call and cached in the registry.
+    ///
+    /// Error if a nerror occurs
+    pub fn try_schema_with_version(
+        &self,
+        version: SchemaVersion,
+    ) -> anyhow::Result<&'static Schema<'static>> {
+        let registry = SCHEMA_REGISTRY.get_or_init(|| Mutex::new(HashMap::new()));
+        if let Some(s) = registry
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(*self, version))
+        {
+            return Ok(*s);
         }
+        // The lock is released during the parsing, since the imported schemas are
+        // collected from the registry
+        let xsd = Schema::try_new_with_version(Some(*self), self.xsd(version), version)?;
+        let mut registry = registry.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(*registry
+            .entry((*self, version))
+            .or_insert_with(|| Box::leak(Box::new(xsd))))
     }
 
     /// Get the schema structure
     ///
-    /// Panic if a nerror occurs
+    /// Panic if a nerror occurs. Only for the tests: use [SchemaKind::try_schema]
+    #[cfg(test)]
     pub fn schema(&self) -> &'static Schema<'static> {
         self.try_schema().unwrap()
     }
 
     pub fn get_schema_from_namespace(ns: &str) -> Result<&'static Schema<'static>> {
-        match ns {
-            NS_ECH_0006 => Ok(SchemaKind::Ech0006.schema()),
-            NS_ECH_0007 => Ok(SchemaKind::Ech0007.schema()),
-            NS_ECH_0008 => Ok(SchemaKind::Ech0008.schema()),
-            NS_ECH_0010 => Ok(SchemaKind::Ech0010.schema()),
-            NS_ECH_0044 => Ok(SchemaKind::Ech0044.schema()),
-            NS_ECH_0058 => Ok(SchemaKind::Ech0058.schema()),
-            NS_ECH_0155 => Ok(SchemaKind::Ech0155.schema()),
-            NS_ECH_0222 => Ok(SchemaKind::Ech0222.schema()),
-            _ => Err(anyhow!("No schema for namespace {} found", ns)),
-        }
+        Self::get_schema_from_namespace_with_version(ns, SchemaVersion::default())
+    }
+
+    /// Get the schema for the namespace and the given version
+    pub fn get_schema_from_namespace_with_version(
+        ns: &str,
+        version: SchemaVersion,
+    ) -> Result<&'static Schema<'static>> {
+        let kind = match ns {
+            NS_ECH_0006 => SchemaKind::Ech0006,
+            NS_ECH_0007 => SchemaKind::Ech0007,
+            NS_ECH_0008 => SchemaKind::Ech0008,
+            NS_ECH_0010 => SchemaKind::Ech0010,
+            NS_ECH_0044 => SchemaKind::Ech0044,
+            NS_ECH_0058 => SchemaKind::Ech0058,
+            NS_ECH_0155 => SchemaKind::Ech0155,
+            NS_ECH_0222 => SchemaKind::Ech0222,
+            _ => return Err(anyhow!("No schema for namespace {} found", ns)),
+        };
+        kind.try_schema_with_version(version)
     }
 }
 
@@ -157,6 +210,17 @@ impl<'a> Schema<'a> {
     /// - It is not possible to create it
     /// - Targetnamespace is missing
     pub fn try_new(schema_kind: Option<SchemaKind>, xsd_str: &'static str) -> Result<Self> {
+        Self::try_new_with_version(schema_kind, xsd_str, SchemaVersion::default())
+    }
+
+    /// Try to create a new schema of kind [schema_kind] with the static str [xsd_str]
+    ///
+    /// The imported schemas are taken with the same version
+    pub fn try_new_with_version(
+        schema_kind: Option<SchemaKind>,
+        xsd_str: &'static str,
+        version: SchemaVersion,
+    ) -> Result<Self> {
         let doc = Document::parse(xsd_str).with_context(|| "Failed to read the schema")?;
         let root = doc.root_element();
         let target_ns_uri = root
@@ -165,8 +229,11 @@ impl<'a> Schema<'a> {
             .map(|a| a.value().to_string())
             .ok_or(anyhow!("targetNamespace is missing"))?;
         let mut hm = HashMap::new();
+        // The default namespace (without name) is not referenced with a prefix
         for ns in root.namespaces() {
-            hm.insert(ns.name().unwrap().to_string(), ns.uri().to_string());
+            if let Some(name) = ns.name() {
+                hm.insert(name.to_string(), ns.uri().to_string());
+            }
         }
         let target_ns_name = hm
             .iter()
@@ -189,6 +256,7 @@ impl<'a> Schema<'a> {
             xml_schema_name: schema_ns_name.clone(),
             namespaces: hm,
             schema_kind,
+            version,
             sub_schemas: HashMap::new(),
         };
         let sub_schmeas = res.collect_import()?;
@@ -214,7 +282,8 @@ impl<'a> Schema<'a> {
             let ns = ns_res?;
             res.insert(
                 ns.clone(),
-                SchemaKind::get_schema_from_namespace(ns.as_str()).context("Collection import")?,
+                SchemaKind::get_schema_from_namespace_with_version(ns.as_str(), self.version)
+                    .context("Collection import")?,
             );
         }
         Ok(res)
@@ -222,12 +291,18 @@ impl<'a> Schema<'a> {
 
     /// Try to create a new schema of kind [schema_kind] with the static str [xsd_str]
     ///
-    /// Panic if it is not possible to create it
-    #[allow(dead_code)]
+    /// Panic if it is not possible to create it. Only for the tests: use [Schema::try_new]
+    #[cfg(test)]
     pub fn new(schema_kind: Option<SchemaKind>, xsd_str: &'static str) -> Self {
         Self::try_new(schema_kind, xsd_str).unwrap()
     }
 
+    /// Version of the schema
+    #[allow(dead_code)]
+    pub fn version(&self) -> SchemaVersion {
+        self.version
+    }
+
     /// Root element of the schema
     pub fn root_element(&'a self) -> RoNode<'a, 'a> {
         self.document().root_element()
@@ -300,7 +375,10 @@ impl<'a> Schema<'a> {
             .iter()
             .filter(|(n, _)| n != &self.target_namespace_name() && n != &&self.xml_schema_name)
         {
-            res.insert(n.to_string(), self.sub_schema(u).unwrap().root_element());
+            // The namespaces without imported schema are ignored
+            if let Ok(s) = self.sub_schema(u) {
+                res.insert(n.to_string(), s.root_element());
+            }
         }
         res
     }
@@ -375,6 +453,32 @@ mod test {
         assert!(xsd_res.is_ok());
     }
 
+    #[test]
+    fn test_registry() {
+        let xsd = SchemaKind::Ech0222.schema();
+        assert!(std::ptr::eq(
+            xsd,
+            SchemaKind::Ech0222
+                .try_schema_with_version(SchemaVersion::V1_2)
+                .unwrap()
+        ));
+        assert!(std::ptr::eq(
+            xsd.sub_schema(NS_ECH_0058).unwrap(),
+            SchemaKind::Ech0058.schema()
+        ));
+        assert_eq!(xsd.version(), SchemaVersion::V1_2);
+    }
+
+    #[test]
+    fn test_schema_version() {
+        assert_eq!(
+            SchemaVersion::try_from("1.2.3").unwrap(),
+            SchemaVersion::V1_2
+        );
+        assert_eq!(SchemaVersion::try_from("1.3").unwrap(), SchemaVersion::V1_3);
+        assert!(SchemaVersion::try_from("0.9").is_err());
+    }
+
     #[test]
     fn test_target_namespace_name() {
         let xsd = SchemaKind::Config.schema();
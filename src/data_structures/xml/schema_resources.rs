@@ -0,0 +1,235 @@
+//! Module giving access to the XSD used by the verifier
+//!
+//! The XSD are embedded in the verifier. For forward compatibility, the XSD of a kind of schema
+//! can be overridden with a file on the disk (see [override_schema]) before the schema is used the
+//! first time. A [SchemaResource] documents which XSD (name, version and hash) the verifier
+//! validates against, e.g. in the report of the run.
+
+use super::schema::{SchemaKind, SchemaVersion};
+use crate::resources;
+use anyhow::{anyhow, Context};
+use data_encoding::HEXLOWER;
+use roxmltree::Document;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock, PoisonError},
+};
+
+/// XSD loaded from the disk, overriding the embedded XSD
+///
+/// The texts are leaked to get a static lifetime, like the parsed schemas
+type SchemaOverrides = Mutex<HashMap<(SchemaKind, SchemaVersion), (PathBuf, &'static str)>>;
+
+static SCHEMA_OVERRIDES: OnceLock<SchemaOverrides> = OnceLock::new();
+
+fn overrides() -> &'static SchemaOverrides {
+    SCHEMA_OVERRIDES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Description of the XSD used for a kind of schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaResource {
+    /// Name of the schema (e.g. `eCH-0110`)
+    pub name: String,
+    /// Version of the schema (e.g. `4.0`)
+    pub version: String,
+    /// Version of the datasets validated with the schema
+    pub dataset_version: String,
+    /// SHA-256 hash of the XSD (hexadecimal, lower case)
+    pub sha256: String,
+    /// File from which the XSD is loaded. `None` if the embedded XSD is used
+    pub overridden_by: Option<PathBuf>,
+}
+
+/// Name of the embedded XSD, containing the version of the schema (e.g. `eCH-0110-4-0`), and its
+/// text
+///
+/// The versions differ only by the XSD of the decrypt file
+fn embedded_xsd(kind: SchemaKind, version: SchemaVersion) -> (&'static str, &'static str) {
+    match kind {
+        SchemaKind::Ech0006 => ("eCH-0006-2-0", resources::XSD_ECH_0006),
+        SchemaKind::Ech0007 => ("eCH-0007-6-0", resources::XSD_ECH_0007),
+        SchemaKind::Ech0008 => ("eCH-0008-3-0", resources::XSD_ECH_0008),
+        SchemaKind::Ech0010 => ("eCH-0010-6-0", resources::XSD_ECH_0010),
+        SchemaKind::Ech0044 => ("eCH-0044-4-1", resources::XSD_ECH_0044),
+        SchemaKind::Ech0058 => ("eCH-0058-5-0", resources::XSD_ECH_0058),
+        SchemaKind::Ech0110 => ("eCH-0110-4-0", resources::XSD_ECH_0110),
+        SchemaKind::Ech0155 => ("eCH-0155-4-0", resources::XSD_ECH_0155),
+        SchemaKind::Ech0222 => ("eCH-0222-1-0", resources::XSD_ECH_0222),
+        SchemaKind::Decrypt => match version {
+            SchemaVersion::V1_2 => ("evoting-decrypt-1-3", resources::XSD_DECRYPT),
+            SchemaVersion::V1_3 => ("evoting-decrypt-1-4", resources::XSD_DECRYPT_1_4),
+        },
+        SchemaKind::Config => ("evoting-config-6-0", resources::XSD_CONFIG),
+    }
+}
+
+/// Text of the XSD embedded in the verifier
+pub fn embedded_xsd_text(kind: SchemaKind, version: SchemaVersion) -> &'static str {
+    embedded_xsd(kind, version).1
+}
+
+/// Text of the XSD used by the verifier: the XSD overriding the embedded XSD, if any, else the
+/// embedded XSD
+pub fn xsd_text(kind: SchemaKind, version: SchemaVersion) -> &'static str {
+    match overrides()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&(kind, version))
+    {
+        Some((_, xsd)) => xsd,
+        None => embedded_xsd_text(kind, version),
+    }
+}
+
+/// Target namespace of the XSD
+fn target_namespace(xsd: &str) -> anyhow::Result<String> {
+    let doc = Document::parse(xsd).context("Failed to read the schema")?;
+    doc.root_element()
+        .attribute("targetNamespace")
+        .map(|ns| ns.to_string())
+        .ok_or_else(|| anyhow!("targetNamespace is missing"))
+}
+
+/// Version of the schema in the attribute `version` of the XSD. `None` if it is not defined
+fn version_attribute(xsd: &str) -> Option<String> {
+    Document::parse(xsd)
+        .ok()?
+        .root_element()
+        .attribute("version")
+        .map(|v| v.to_string())
+}
+
+/// Override the embedded XSD of the kind of schema with the XSD in the file
+///
+/// The XSD must have the same target namespace as the embedded XSD, since the imported schemas
+/// are found with their namespace.
+///
+/// # Error
+/// If the file cannot be read, if the XSD is not valid or if the schema is already used (the
+/// parsed schemas are cached and cannot be replaced)
+pub fn override_schema(
+    kind: SchemaKind,
+    version: SchemaVersion,
+    path: &Path,
+) -> anyhow::Result<()> {
+    if kind.is_loaded(version) {
+        return Err(anyhow!(
+            "The schema {:?} is already used and cannot be overridden",
+            kind
+        ));
+    }
+    let xsd = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read the schema in {:?}", path))?;
+    let ns = target_namespace(&xsd).with_context(|| format!("Schema in {:?} not valid", path))?;
+    let expected = target_namespace(embedded_xsd_text(kind, version))?;
+    if ns != expected {
+        return Err(anyhow!(
+            "The target namespace {} of the schema in {:?} is not the namespace {} of {:?}",
+            ns,
+            path,
+            expected,
+            kind
+        ));
+    }
+    overrides()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(
+            (kind, version),
+            (path.to_path_buf(), Box::leak(xsd.into_boxed_str())),
+        );
+    Ok(())
+}
+
+/// Override the embedded XSD with the XSD in the file, the kind of schema being detected with
+/// the target namespace
+///
+/// Return the kind of the overridden schema. See [override_schema] for the errors
+pub fn override_schema_from_file(
+    version: SchemaVersion,
+    path: &Path,
+) -> anyhow::Result<SchemaKind> {
+    let xsd = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read the schema in {:?}", path))?;
+    let ns = target_namespace(&xsd).with_context(|| format!("Schema in {:?} not valid", path))?;
+    let kind = SchemaKind::ALL
+        .into_iter()
+        .find(|k| {
+            target_namespace(embedded_xsd_text(*k, version)).is_ok_and(|expected| expected == ns)
+        })
+        .ok_or_else(|| anyhow!("No schema with the namespace {} of {:?}", ns, path))?;
+    override_schema(kind, version, path)?;
+    Ok(kind)
+}
+
+impl SchemaResource {
+    /// Description of the XSD used for the kind of schema and the version of the datasets
+    ///
+    /// The version of an overriding XSD is taken from its attribute `version`, if defined
+    pub fn new(kind: SchemaKind, version: SchemaVersion) -> Self {
+        let (file_name, _) = embedded_xsd(kind, version);
+        let overridden_by = overrides()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(kind, version))
+            .map(|(p, _)| p.clone());
+        let xsd = xsd_text(kind, version);
+        // The file name ends with the major and minor versions, e.g. eCH-0110-4-0
+        let mut parts = file_name.rsplitn(3, '-');
+        let minor = parts.next().unwrap_or_default();
+        let major = parts.next().unwrap_or_default();
+        let name = parts.next().unwrap_or_default();
+        let embedded_version = format!("{}.{}", major, minor);
+        Self {
+            name: name.to_string(),
+            version: match overridden_by {
+                Some(_) => version_attribute(xsd).unwrap_or(embedded_version),
+                None => embedded_version,
+            },
+            dataset_version: version.to_string(),
+            sha256: HEXLOWER.encode(&Sha256::digest(xsd.as_bytes())),
+            overridden_by,
+        }
+    }
+}
+
+/// Description of all the XSD used for the version of the datasets
+pub fn schema_resources(version: SchemaVersion) -> Vec<SchemaResource> {
+    SchemaKind::ALL
+        .into_iter()
+        .map(|k| SchemaResource::new(k, version))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_schema_resources() {
+        let res = schema_resources(SchemaVersion::V1_2);
+        assert_eq!(res.len(), SchemaKind::ALL.len());
+        let ech_0110 = SchemaResource::new(SchemaKind::Ech0110, SchemaVersion::V1_2);
+        assert_eq!(ech_0110.name, "eCH-0110");
+        assert_eq!(ech_0110.version, "4.0");
+        assert_eq!(ech_0110.dataset_version, "1.2");
+        assert_eq!(ech_0110.sha256.len(), 64);
+        assert!(ech_0110.overridden_by.is_none());
+        let config = SchemaResource::new(SchemaKind::Config, SchemaVersion::V1_2);
+        assert_eq!(config.name, "evoting-config");
+        assert_eq!(config.version, "6.0");
+        let decrypt = SchemaResource::new(SchemaKind::Decrypt, SchemaVersion::V1_3);
+        assert_eq!(decrypt.name, "evoting-decrypt");
+        assert_eq!(decrypt.version, "1.4");
+        assert_eq!(decrypt.dataset_version, "1.3");
+        assert_eq!(
+            xsd_text(SchemaKind::Ech0222, SchemaVersion::V1_2),
+            resources::XSD_ECH_0222
+        );
+    }
+}
@@ -1,7 +1,7 @@
 //! Module to define an iterator over the definition of the types in the schema, as a tree structure.
 
 use super::schema::Schema;
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use core::fmt;
 use quick_xml::name::QName;
 use roxmltree::{Document as RoDocument, Node as RoNode};
@@ -13,13 +13,23 @@ use std::str;
 pub struct ElementNode {
     schema: &'static Schema<'static>,
     name: String,
+    occurs: Occurs,
     node_kind: ElementNodeKind,
 }
 
+/// Number of occurrences allowed for a node (attributes `minOccurs` and `maxOccurs`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurs {
+    pub min: usize,
+    /// `usize::MAX` if unbounded
+    pub max: usize,
+}
+
 impl fmt::Debug for ElementNode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ElementNode")
             .field("name", &self.name)
+            .field("occurs", &self.occurs)
             .field("node_kind", &self.node_kind)
             .finish()
     }
@@ -42,8 +52,8 @@ pub enum ElementNodeKind {
 pub enum ComplexTypeChildKind {
     Element(ElementNode),
     /// Node sequnece. It stores the [RoNode] of the location of the information
-    Sequence(Vec<ElementNode>),
-    Choice(Vec<ComplexTypeChildKind>),
+    Sequence(Vec<ElementNode>, Occurs),
+    Choice(Vec<ComplexTypeChildKind>, Occurs),
 }
 
 impl ElementNodeKind {
@@ -78,28 +88,6 @@ impl ElementNodeKind {
         Err(anyhow!("The node is not a complex type"))
     }
 
-    /// Unwrap the [ElementNode] to the list of the children under the complex type
-    ///
-    /// Panic if the node is not complex
-    pub fn unwrap_complex_type(&self) -> &Vec<ComplexTypeChildKind> {
-        let res = self.try_unwrap_complex_type();
-        if let Err(e) = res {
-            panic!("{}", e);
-        }
-        res.unwrap()
-    }
-
-    /// Unwrap the [ElementNode] to the native type
-    ///
-    /// Panic if the node is not native
-    pub fn unwrap_native(&self) -> &str {
-        let res = self.try_unwrap_native();
-        if let Err(e) = res {
-            panic!("{}", e);
-        }
-        res.unwrap()
-    }
-
     pub fn try_find_child_with_tag_name(
         &self,
         tag_name: &str,
@@ -112,17 +100,17 @@ impl ElementNodeKind {
                         return Ok(Some(e));
                     }
                 }
-                ComplexTypeChildKind::Sequence(seq) => {
+                ComplexTypeChildKind::Sequence(seq, _) => {
                     if let Some(e) = seq.iter().find(|e| e.has_name(tag_name)) {
                         return Ok(Some(e));
                     }
                 }
-                ComplexTypeChildKind::Choice(choices) => {
-                    if let Some(e) = choices
-                        .iter()
-                        .find(|e| e.is_element() && e.unwrap_element().has_name(tag_name))
-                    {
-                        return Ok(Some(e.unwrap_element()));
+                ComplexTypeChildKind::Choice(choices, _) => {
+                    if let Some(e) = choices.iter().find_map(|e| match e {
+                        ComplexTypeChildKind::Element(e) if e.has_name(tag_name) => Some(e),
+                        _ => None,
+                    }) {
+                        return Ok(Some(e));
                     }
                 }
             }
@@ -141,6 +129,9 @@ impl ElementNodeKind {
         let mut res_schema = schema;
         //println!("Schema node type {:?}", node.schema_node_type());
         if let Some(q_name) = node.schema_node_type() {
+            let local_name = q_name.local_name();
+            let local_name = str::from_utf8(local_name.as_ref())
+                .context("The name of the type is not valid UTF-8")?;
             // The type name is qualified with prefix
             if let Some(prefix) = q_name.prefix() {
                 //println!("Prefix {:?}", prefix);
@@ -149,37 +140,23 @@ impl ElementNodeKind {
                 //println!("Schema: {}", schema.target_namespace_name());
                 //print!("Subschema: {:?}", schema.sub_schema_nodes_with_name());
                 //}
-                match prefix.as_ref() {
+                let ns_name = str::from_utf8(prefix.as_ref())
+                    .context("The prefix of the type is not valid UTF-8")?;
+                match ns_name {
                     // The prefix is for xmlschema
-                    s if s == schema.xmlschema_namespace_name().as_bytes() => {
-                        return Ok(ElementNodeKind::Native(
-                            str::from_utf8(q_name.local_name().as_ref())
-                                .unwrap()
-                                .to_string(),
-                        ));
+                    s if s == schema.xmlschema_namespace_name() => {
+                        return Ok(ElementNodeKind::Native(local_name.to_string()));
                     }
                     // The prefix is for target namespace (e.g. in the current schema)
-                    s if s == schema.target_namespace_name().as_bytes() => {
-                        let n = schema.document().find_node_with_name(
-                            str::from_utf8(q_name.local_name().as_ref()).unwrap(),
-                        );
-                        if n.is_some() {
-                            res_node = Some(n.unwrap());
-                        }
+                    s if s == schema.target_namespace_name() => {
+                        res_node = schema.document().find_node_with_name(local_name);
                     }
                     // The prefix is another namespace in the import
-                    s if schema
-                        .sub_schema_nodes_with_name()
-                        .contains_key(str::from_utf8(s).unwrap()) =>
-                    {
-                        let ns_name = str::from_utf8(s).unwrap();
+                    s if schema.sub_schema_nodes_with_name().contains_key(s) => {
                         let sub_schema = schema.sub_schema_name(ns_name)?;
                         let doc = sub_schema.document();
-                        let n = doc.find_node_with_name(
-                            str::from_utf8(q_name.local_name().as_ref()).unwrap(),
-                        );
-                        if n.is_some() {
-                            res_node = Some(n.unwrap());
+                        if let Some(n) = doc.find_node_with_name(local_name) {
+                            res_node = Some(n);
                             res_schema = sub_schema;
                         }
                     }
@@ -202,7 +179,9 @@ impl ElementNodeKind {
                 if n.is_schema_complex_type() {
                     let mut res = vec![];
                     // First children is the sequence containing the children
-                    let seq_node = n.first_element_child().unwrap();
+                    let seq_node = n.first_element_child().ok_or_else(|| {
+                        anyhow!("The complex type {:?} has no child", n.attr_name())
+                    })?;
                     for c in seq_node.children().filter(|e| e.is_child_of_complex_type()) {
                         res.push(ComplexTypeChildKind::try_from_roxml_node(&c, res_schema)?)
                     }
@@ -224,16 +203,19 @@ impl ElementNodeKind {
 
 impl ComplexTypeChildKind {
     /// Is the child an element
+    #[allow(dead_code)]
     pub fn is_element(&self) -> bool {
         self.try_unwrap_element().is_ok()
     }
 
     /// Is the child a sequence
+    #[allow(dead_code)]
     pub fn is_sequence(&self) -> bool {
         self.try_unwrap_sequence().is_ok()
     }
 
     /// Is the child a choice
+    #[allow(dead_code)]
     pub fn is_choice(&self) -> bool {
         self.try_unwrap_choice().is_ok()
     }
@@ -252,7 +234,7 @@ impl ComplexTypeChildKind {
     ///
     /// Return error if the child is not an sequence
     fn try_unwrap_sequence(&self) -> anyhow::Result<&Vec<ElementNode>> {
-        if let Self::Sequence(n) = self {
+        if let Self::Sequence(n, _) = self {
             return Ok(n);
         }
         Err(anyhow!("The node is not a sequence"))
@@ -262,45 +244,12 @@ impl ComplexTypeChildKind {
     ///
     /// Return error if the child is not an choice
     fn try_unwrap_choice(&self) -> anyhow::Result<&Vec<ComplexTypeChildKind>> {
-        if let Self::Choice(n) = self {
+        if let Self::Choice(n, _) = self {
             return Ok(n);
         }
         Err(anyhow!("The node is not a choice"))
     }
 
-    /// Unwrap the [ComplexTypeChildKind] to an [ElementNode]
-    ///
-    /// Panic if the child is not an element
-    pub fn unwrap_element(&self) -> &ElementNode {
-        let res = self.try_unwrap_element();
-        if let Err(e) = res {
-            panic!("{}", e);
-        }
-        res.unwrap()
-    }
-
-    /// Unwrap the [ComplexTypeChildKind] to a list of [ElementNode]
-    ///
-    /// Panic if the child is not an sequence
-    pub fn unwrap_sequence(&self) -> &Vec<ElementNode> {
-        let res = self.try_unwrap_sequence();
-        if let Err(e) = res {
-            panic!("{}", e);
-        }
-        res.unwrap()
-    }
-
-    /// Unwrap the [ComplexTypeChildKind] to a list of [ElementNode]
-    ///
-    /// Panic if the child is not an choice
-    fn unwrap_choice(&self) -> &Vec<ComplexTypeChildKind> {
-        let res = self.try_unwrap_choice();
-        if let Err(e) = res {
-            panic!("{}", e);
-        }
-        res.unwrap()
-    }
-
     /// Transform a [RoNode] to a [ComplexTypeChildKind]
     ///
     /// The entry should be the child under the sequence under the complex type
@@ -321,7 +270,7 @@ impl ComplexTypeChildKind {
             for c in node.children().filter(|e| e.is_child_of_complex_type()) {
                 res.push(ComplexTypeChildKind::try_from_roxml_node(&c, schema)?);
             }
-            return Ok(Self::Choice(res));
+            return Ok(Self::Choice(res, Occurs::try_from_roxml_node(node)?));
         }
 
         // Manage the case if it is a sequence
@@ -330,7 +279,7 @@ impl ComplexTypeChildKind {
             for c in node.children().filter(|e| e.is_child_of_complex_type()) {
                 res.push(ElementNode::try_from_roxml_node(&c, schema)?);
             }
-            return Ok(Self::Sequence(res));
+            return Ok(Self::Sequence(res, Occurs::try_from_roxml_node(node)?));
         }
         anyhow::bail!(
             "The node should be an element, a sequence or a choice, not {:?}",
@@ -363,6 +312,11 @@ impl ElementNode {
         self.name.as_str()
     }
 
+    /// Number of occurrences allowed for the node
+    pub fn occurs(&self) -> Occurs {
+        self.occurs
+    }
+
     /// Check if the node has the name given
     pub fn has_name(&self, name: &str) -> bool {
         self.name() == name
@@ -382,11 +336,27 @@ impl ElementNode {
         Ok(Self {
             schema,
             name: name.to_string(),
+            occurs: Occurs::try_from_roxml_node(node)?,
             node_kind: ElementNodeKind::try_from_roxml_node(node, schema)?,
         })
     }
 }
 
+impl Occurs {
+    /// Occurrences given by the attributes of the node
+    fn try_from_roxml_node(node: &RoNode<'_, '_>) -> anyhow::Result<Self> {
+        Ok(Self {
+            min: node.min_occurs()?,
+            max: node.max_occurs()?,
+        })
+    }
+
+    /// The node is optional
+    pub fn is_optional(&self) -> bool {
+        self.min == 0
+    }
+}
+
 /// Trait to extend the functionalities of [RoDocument]
 trait AdditionalMethodsRoxmlDocument<'a>: Sized {
     /// find a node in the document under the root with a given name
@@ -448,16 +418,20 @@ trait AdditionalMethodsRoxmlNode<'a>: Sized {
     }
 
     /// Get the value `minOccurs` of the node. Default is 1
-    fn min_occurs(&'a self) -> usize {
-        self.find_attribute("minOccurs")
-            .map_or(1, |e| e.parse::<usize>().unwrap())
+    fn min_occurs(&'a self) -> anyhow::Result<usize> {
+        self.find_attribute("minOccurs").map_or(Ok(1), |e| {
+            e.parse::<usize>()
+                .with_context(|| format!("minOccurs {} is not a number", e))
+        })
     }
 
     /// Get the value `maxOccurs` of the node. Default is 1. `"unbounded"` is set to `usize:MAX`
-    fn max_occurs(&'a self) -> usize {
-        self.find_attribute("maxOccurs").map_or(1, |e| match e {
-            "unbounded" => usize::MAX,
-            s => s.parse::<usize>().unwrap(),
+    fn max_occurs(&'a self) -> anyhow::Result<usize> {
+        self.find_attribute("maxOccurs").map_or(Ok(1), |e| match e {
+            "unbounded" => Ok(usize::MAX),
+            s => s
+                .parse::<usize>()
+                .with_context(|| format!("maxOccurs {} is not a number", s)),
         })
     }
 
@@ -561,20 +535,20 @@ impl<'a> AdditionalMethodsRoxmlNode<'a> for RoNode<'a, 'a> {
                 .ok_or(anyhow!("The atribute base is missing for restriction."))?
                 .as_bytes(),
         );
+        let local_name = base.local_name();
+        let local_name = str::from_utf8(local_name.as_ref())
+            .context("The name of the base type is not valid UTF-8")?;
         if let Some(prefix) = base.prefix() {
-            match prefix.as_ref() {
+            let ns_name = str::from_utf8(prefix.as_ref())
+                .context("The prefix of the base type is not valid UTF-8")?;
+            match ns_name {
                 // The prefix of base is for xmlschema
-                s if s == schema.xmlschema_namespace_name().as_bytes() => {
-                    return Ok(str::from_utf8(base.local_name().as_ref())
-                        .unwrap()
-                        .to_string());
+                s if s == schema.xmlschema_namespace_name() => {
+                    return Ok(local_name.to_string());
                 }
                 // The prefix is for target namespace (e.g. in the current schema)
-                s if s == schema.target_namespace_name().as_bytes() => {
-                    match schema
-                        .document()
-                        .find_node_with_name(str::from_utf8(base.local_name().as_ref()).unwrap())
-                    {
+                s if s == schema.target_namespace_name() => {
+                    match schema.document().find_node_with_name(local_name) {
                         Some(n) => return n.native_type_from_simple_type(schema),
                         None => {
                             return Err(anyhow!(
@@ -586,16 +560,10 @@ impl<'a> AdditionalMethodsRoxmlNode<'a> for RoNode<'a, 'a> {
                     }
                 }
                 // The prefix is another namespace in the import
-                s if schema
-                    .sub_schema_nodes_with_name()
-                    .contains_key(str::from_utf8(s).unwrap()) =>
-                {
-                    let ns_name = str::from_utf8(s).unwrap();
+                s if schema.sub_schema_nodes_with_name().contains_key(s) => {
                     let sub_schema = schema.sub_schema_name(ns_name)?;
                     let doc = sub_schema.document();
-                    match doc
-                        .find_node_with_name(str::from_utf8(base.local_name().as_ref()).unwrap())
-                    {
+                    match doc.find_node_with_name(local_name) {
                         Some(n) => return n.native_type_from_simple_type(sub_schema),
                         None => {
                             return Err(anyhow!(
@@ -700,11 +668,25 @@ mod tests {
         let choice = cs2[2].try_unwrap_choice().unwrap();
         assert_eq!(choice.len(), 2);
         assert!(choice[0].is_element());
-        assert!(choice[0].unwrap_element().node_kind.is_native());
-        assert_eq!(choice[0].unwrap_element().name, "choiceString1");
+        assert!(choice[0]
+            .try_unwrap_element()
+            .unwrap()
+            .node_kind
+            .is_native());
+        assert_eq!(
+            choice[0].try_unwrap_element().unwrap().name,
+            "choiceString1"
+        );
         assert!(choice[0].is_element());
-        assert!(choice[0].unwrap_element().node_kind.is_native());
-        assert_eq!(choice[0].unwrap_element().name, "choiceString2");
+        assert!(choice[0]
+            .try_unwrap_element()
+            .unwrap()
+            .node_kind
+            .is_native());
+        assert_eq!(
+            choice[0].try_unwrap_element().unwrap().name,
+            "choiceString2"
+        );
         assert!(cs2[3].is_element());
         assert_eq!(cs2[3].try_unwrap_element().unwrap().name, "ctToto");
     }
@@ -943,13 +925,13 @@ mod test_additional_method_node {
             .children()
             .find(|e| e.find_attribute("name") == Some("contestDescriptionInformationType"))
             .unwrap();
-        assert_eq!(n1.max_occurs(), 1);
+        assert_eq!(n1.max_occurs().unwrap(), 1);
         let n2 = n1
             .first_element_child()
             .unwrap()
             .first_element_child()
             .unwrap();
-        assert_eq!(n2.max_occurs(), usize::MAX);
+        assert_eq!(n2.max_occurs().unwrap(), usize::MAX);
     }
 
     #[test]
@@ -960,13 +942,13 @@ mod test_additional_method_node {
             .children()
             .find(|e| e.find_attribute("name") == Some("contestDescriptionInformationType"))
             .unwrap();
-        assert_eq!(n1.min_occurs(), 1);
+        assert_eq!(n1.min_occurs().unwrap(), 1);
         let n2 = n1
             .first_element_child()
             .unwrap()
             .first_element_child()
             .unwrap();
-        assert_eq!(n2.min_occurs(), 4);
+        assert_eq!(n2.min_occurs().unwrap(), 4);
     }
 
     #[test]
@@ -0,0 +1,446 @@
+//! Module implementing the validation of the xml documents against their schema
+//!
+//! The document is checked against the tree of the schema (see [ElementNode]):
+//! - the structure of the elements (sequences and choices)
+//! - the number of occurrences of the elements and of the groups (`minOccurs` and `maxOccurs`)
+//! - the native types of the values (e.g. integer, boolean, date)
+//!
+//! The namespaces of the elements and the attributes are not checked. For the simple types, only
+//! the native type behind the restriction is checked (the facets like patterns, enumerations or
+//! lengths are not verified).
+
+use super::{
+    schema::{Schema, SchemaVersion},
+    schema_tree::{ComplexTypeChildKind, ElementNode, ElementNodeKind, Occurs},
+    SchemaKind,
+};
+use crate::data_structures::dataset_version::DatasetVersion;
+use anyhow::{anyhow, bail, Context};
+use chrono::{NaiveDate, NaiveTime};
+use roxmltree::{Document, Node as RoNode};
+use std::fmt::Display;
+
+/// Validator of the xml documents against a schema
+#[derive(Debug)]
+pub struct SchemaValidator {
+    root: ElementNode,
+}
+
+/// Violation of the schema found in a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Path of the element (e.g. `/delivery/header[1]/messageId[1]`)
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl SchemaValidator {
+    /// New validator for the schema of the given kind
+    ///
+    /// # Error
+    /// If the schema or its tree cannot be built
+    pub fn new(schema_kind: &SchemaKind) -> anyhow::Result<Self> {
+        Self::new_with_schema(schema_kind.try_schema()?)
+    }
+
+    /// New validator for the schema of the given kind and version of the dataset
+    ///
+    /// # Error
+    /// If the schema or its tree cannot be built
+    pub fn new_with_version(
+        schema_kind: &SchemaKind,
+        version: SchemaVersion,
+    ) -> anyhow::Result<Self> {
+        Self::new_with_schema(schema_kind.try_schema_with_version(version)?)
+    }
+
+    /// New validator for the given schema
+    ///
+    /// # Error
+    /// If the tree of the schema cannot be built
+    pub fn new_with_schema(schema: &'static Schema<'static>) -> anyhow::Result<Self> {
+        Ok(Self {
+            root: ElementNode::try_from(schema).context("Cannot build the tree of the schema")?,
+        })
+    }
+
+    /// Validate the xml document
+    ///
+    /// Return the list of the violations of the schema (empty if the document is valid)
+    ///
+    /// # Error
+    /// If the document is not a well-formed xml
+    pub fn validate_str(&self, xml: &str) -> anyhow::Result<Vec<SchemaViolation>> {
+        let doc = Document::parse(xml)
+            .map_err(|e| anyhow!(e).context("The document is not a well-formed xml"))?;
+        let root = doc.root_element();
+        let path = format!("/{}", root.tag_name().name());
+        let mut res = vec![];
+        if !self.root.has_name(root.tag_name().name()) {
+            res.push(violation(
+                &path,
+                format!("The root element must be {}", self.root.name()),
+            ));
+            return Ok(res);
+        }
+        validate_element(&root, &self.root, &path, &mut res);
+        Ok(res)
+    }
+}
+
+fn violation(path: &str, message: String) -> SchemaViolation {
+    SchemaViolation {
+        path: path.to_string(),
+        message,
+    }
+}
+
+/// Validate the element `node` against the node of the schema `schema_node`
+fn validate_element(
+    node: &RoNode,
+    schema_node: &ElementNode,
+    path: &str,
+    res: &mut Vec<SchemaViolation>,
+) {
+    match schema_node.node_kind() {
+        ElementNodeKind::Native(native_type) => {
+            if let Some(c) = node.children().find(|c| c.is_element()) {
+                res.push(violation(
+                    path,
+                    format!(
+                        "Unexpected element {} in a value of type {}",
+                        c.tag_name().name(),
+                        native_type
+                    ),
+                ));
+                return;
+            }
+            let value: String = node
+                .children()
+                .filter(|c| c.is_text())
+                .filter_map(|c| c.text())
+                .collect();
+            if let Err(e) = check_native_value(native_type, &value) {
+                res.push(violation(path, e.to_string()))
+            }
+        }
+        ElementNodeKind::ComplexType(particles) => {
+            let mut matcher = ContentMatcher {
+                children: node.children().filter(|c| c.is_element()).collect(),
+                pos: 0,
+                path,
+            };
+            for p in particles.iter() {
+                matcher.match_particle(p, res);
+            }
+            for i in matcher.pos..matcher.children.len() {
+                res.push(violation(
+                    &matcher.child_path(i),
+                    format!(
+                        "Unexpected element {}",
+                        matcher.children[i].tag_name().name()
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+/// Matcher of the children of an element against the content of a complex type
+///
+/// The children are consumed greedily, what is sufficient since the schemas must fulfill the
+/// unique particle attribution.
+struct ContentMatcher<'a, 'input> {
+    children: Vec<RoNode<'a, 'input>>,
+    pos: usize,
+    path: &'a str,
+}
+
+impl<'a, 'input> ContentMatcher<'a, 'input> {
+    /// Path of the child at position `i`, with the index under the children of the same name
+    fn child_path(&self, i: usize) -> String {
+        let name = self.children[i].tag_name().name();
+        let index = self.children[..i]
+            .iter()
+            .filter(|c| c.tag_name().name() == name)
+            .count();
+        format!("{}/{}[{}]", self.path, name, index + 1)
+    }
+
+    fn current_name(&self) -> Option<&str> {
+        self.children.get(self.pos).map(|c| c.tag_name().name())
+    }
+
+    /// The current child can be the start of the particle
+    fn starts(&self, particle: &ComplexTypeChildKind) -> bool {
+        match particle {
+            ComplexTypeChildKind::Element(e) => self.current_name() == Some(e.name()),
+            ComplexTypeChildKind::Sequence(elements, _) => elements
+                .iter()
+                .any(|e| self.current_name() == Some(e.name())),
+            ComplexTypeChildKind::Choice(alternatives, _) => {
+                alternatives.iter().any(|a| self.starts(a))
+            }
+        }
+    }
+
+    fn match_particle(&mut self, particle: &ComplexTypeChildKind, res: &mut Vec<SchemaViolation>) {
+        match particle {
+            ComplexTypeChildKind::Element(e) => self.match_element(e, res),
+            ComplexTypeChildKind::Sequence(elements, occurs) => {
+                let mut count = 0;
+                while count < occurs.max {
+                    if count >= occurs.min && !self.starts(particle) {
+                        break;
+                    }
+                    let before = self.pos;
+                    for e in elements.iter() {
+                        self.match_element(e, res);
+                    }
+                    count += 1;
+                    if self.pos == before {
+                        break;
+                    }
+                }
+            }
+            ComplexTypeChildKind::Choice(alternatives, occurs) => {
+                self.match_choice(particle, alternatives, occurs, res)
+            }
+        }
+    }
+
+    fn match_choice(
+        &mut self,
+        particle: &ComplexTypeChildKind,
+        alternatives: &[ComplexTypeChildKind],
+        occurs: &Occurs,
+        res: &mut Vec<SchemaViolation>,
+    ) {
+        let mut count = 0;
+        while count < occurs.max {
+            match alternatives.iter().find(|a| self.starts(a)) {
+                Some(a) => {
+                    let before = self.pos;
+                    self.match_particle(a, res);
+                    count += 1;
+                    if self.pos == before {
+                        break;
+                    }
+                }
+                None => {
+                    if count < occurs.min && !is_emptiable(particle) {
+                        let mut names = vec![];
+                        element_names(particle, &mut names);
+                        res.push(violation(
+                            self.path,
+                            format!("Expected one of the elements {}", names.join(", ")),
+                        ))
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    fn match_element(&mut self, element: &ElementNode, res: &mut Vec<SchemaViolation>) {
+        let occurs = element.occurs();
+        let mut count = 0;
+        while count < occurs.max && self.current_name() == Some(element.name()) {
+            validate_element(
+                &self.children[self.pos],
+                element,
+                &self.child_path(self.pos),
+                res,
+            );
+            self.pos += 1;
+            count += 1;
+        }
+        if count < occurs.min {
+            res.push(violation(
+                self.path,
+                format!(
+                    "Expected at least {} element(s) {}, found {}",
+                    occurs.min,
+                    element.name(),
+                    count
+                ),
+            ))
+        }
+    }
+}
+
+/// The particle can be empty
+fn is_emptiable(particle: &ComplexTypeChildKind) -> bool {
+    match particle {
+        ComplexTypeChildKind::Element(e) => e.occurs().is_optional(),
+        ComplexTypeChildKind::Sequence(elements, occurs) => {
+            occurs.is_optional() || elements.iter().all(|e| e.occurs().is_optional())
+        }
+        ComplexTypeChildKind::Choice(alternatives, occurs) => {
+            occurs.is_optional() || alternatives.iter().any(is_emptiable)
+        }
+    }
+}
+
+/// Collect the names of the elements of the particle
+fn element_names<'a>(particle: &'a ComplexTypeChildKind, names: &mut Vec<&'a str>) {
+    match particle {
+        ComplexTypeChildKind::Element(e) => names.push(e.name()),
+        ComplexTypeChildKind::Sequence(elements, _) => {
+            names.extend(elements.iter().map(|e| e.name()))
+        }
+        ComplexTypeChildKind::Choice(alternatives, _) => {
+            for a in alternatives.iter() {
+                element_names(a, names)
+            }
+        }
+    }
+}
+
+/// Remove the timezone at the end of a date or a time (`Z` or `+hh:mm`)
+fn strip_timezone(value: &str) -> &str {
+    if let Some(v) = value.strip_suffix('Z') {
+        return v;
+    }
+    let len = value.len();
+    if len > 6 && value.is_char_boundary(len - 6) {
+        let (v, tz) = value.split_at(len - 6);
+        if (tz.starts_with('+') || tz.starts_with('-')) && tz.as_bytes()[3] == b':' {
+            return v;
+        }
+    }
+    value
+}
+
+fn is_decimal(value: &str) -> bool {
+    let unsigned = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    !(int.is_empty() && frac.is_empty())
+        && int.chars().all(|c| c.is_ascii_digit())
+        && frac.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_base64(value: &str) -> bool {
+    let v: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    v.len() % 4 == 0
+        && v.trim_end_matches('=').len() + 2 >= v.len()
+        && v.trim_end_matches('=')
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+}
+
+/// Check that the value is valid for the native type of xml schema
+///
+/// The types that are not listed (e.g. string, token) accept all the values
+fn check_native_value(native_type: &str, value: &str) -> anyhow::Result<()> {
+    let v = value.trim();
+    let ok = match native_type {
+        "boolean" => matches!(v, "true" | "false" | "1" | "0"),
+        "integer" => v.parse::<i128>().is_ok(),
+        "long" => v.parse::<i64>().is_ok(),
+        "int" => v.parse::<i32>().is_ok(),
+        "short" => v.parse::<i16>().is_ok(),
+        "byte" => v.parse::<i8>().is_ok(),
+        "nonNegativeInteger" => v.parse::<u128>().is_ok(),
+        "positiveInteger" => v.parse::<u128>().is_ok_and(|i| i > 0),
+        "nonPositiveInteger" => v.parse::<i128>().is_ok_and(|i| i <= 0),
+        "negativeInteger" => v.parse::<i128>().is_ok_and(|i| i < 0),
+        "unsignedLong" => v.parse::<u64>().is_ok(),
+        "unsignedInt" => v.parse::<u32>().is_ok(),
+        "unsignedShort" => v.parse::<u16>().is_ok(),
+        "unsignedByte" => v.parse::<u8>().is_ok(),
+        "decimal" => is_decimal(v),
+        "double" | "float" => matches!(v, "INF" | "-INF" | "NaN") || v.parse::<f64>().is_ok(),
+        "date" => NaiveDate::parse_from_str(strip_timezone(v), "%Y-%m-%d").is_ok(),
+        "time" => NaiveTime::parse_from_str(strip_timezone(v), "%H:%M:%S%.f").is_ok(),
+        "dateTime" => DatasetVersion::from_datetime(v).is_ok(),
+        "gYear" => v.len() >= 4 && v.parse::<i32>().is_ok(),
+        "base64Binary" => is_base64(v),
+        "hexBinary" => v.len() % 2 == 0 && v.chars().all(|c| c.is_ascii_hexdigit()),
+        _ => true,
+    };
+    if !ok {
+        bail!("The value \"{}\" is not a valid {}", value, native_type)
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::schema::test_schemas::{get_schema_test_1, get_schema_test_2},
+        *,
+    };
+
+    const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+    fn xml_2(content: &str) -> String {
+        format!(
+            r#"{}<test:tests xmlns:test="http://www.evoting.ch/xmlns/test2/1">{}</test:tests>"#,
+            XML_HEADER, content
+        )
+    }
+
+    #[test]
+    fn test_valid() {
+        let validator = SchemaValidator::new_with_schema(get_schema_test_1()).unwrap();
+        let xml = format!(
+            r#"{}<test:tests xmlns:test="http://www.evoting.ch/xmlns/test1/1"><test:valueString>test</test:valueString><test:valueInt>10</test:valueInt></test:tests>"#,
+            XML_HEADER
+        );
+        assert!(validator.validate_str(&xml).unwrap().is_empty());
+        let validator = SchemaValidator::new_with_schema(get_schema_test_2()).unwrap();
+        let xml = xml_2(
+            "<test:valueString>a</test:valueString><test:valueBoolean>false</test:valueBoolean><test:complexType><test:ctString>b</test:ctString><test:csToto>c</test:csToto></test:complexType><test:valueList>1</test:valueList><test:valueList>2</test:valueList><test:valueInt>3</test:valueInt>",
+        );
+        assert!(validator.validate_str(&xml).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_violations() {
+        let validator = SchemaValidator::new_with_schema(get_schema_test_2()).unwrap();
+        let xml = xml_2(
+            "<test:valueString>a</test:valueString><test:valueBoolean>yes</test:valueBoolean><test:complexType><test:ctString>b</test:ctString></test:complexType><test:valueList>-1</test:valueList><test:valueInt>3</test:valueInt><test:toto/>",
+        );
+        let violations = validator.validate_str(&xml).unwrap();
+        assert_eq!(violations.len(), 4);
+        assert_eq!(violations[0].path, "/tests/valueBoolean[1]");
+        assert_eq!(violations[1].path, "/tests/complexType[1]");
+        assert!(violations[1].message.contains("csToto"));
+        assert_eq!(violations[2].path, "/tests/valueList[1]");
+        assert_eq!(violations[3].path, "/tests/toto[1]");
+        assert_eq!(violations[3].message, "Unexpected element toto");
+    }
+
+    #[test]
+    fn test_wrong_root() {
+        let validator = SchemaValidator::new_with_schema(get_schema_test_1()).unwrap();
+        let violations = validator
+            .validate_str(&format!("{}<toto/>", XML_HEADER))
+            .unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "/toto");
+        assert!(validator.validate_str("<toto>").is_err());
+    }
+
+    #[test]
+    fn test_check_native_value() {
+        assert!(check_native_value("nonNegativeInteger", " 12 ").is_ok());
+        assert!(check_native_value("nonNegativeInteger", "-1").is_err());
+        assert!(check_native_value("decimal", "-1.50").is_ok());
+        assert!(check_native_value("decimal", "1e5").is_err());
+        assert!(check_native_value("date", "2023-12-06").is_ok());
+        assert!(check_native_value("date", "2023-12-06+01:00").is_ok());
+        assert!(check_native_value("date", "06.12.2023").is_err());
+        assert!(check_native_value("dateTime", "2023-12-06T07:00:00").is_ok());
+        assert!(check_native_value("base64Binary", "dGVzdA==").is_ok());
+        assert!(check_native_value("base64Binary", "dGVzdA=").is_err());
+        assert!(check_native_value("string", "anything").is_ok());
+    }
+}
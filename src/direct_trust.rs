@@ -1,102 +1,267 @@
-use anyhow::Context;
-use rust_ev_crypto_primitives::{verify_signature, ByteArray, HashableMessage, Keystore};
-
-/// List of valide Certificate authorities
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CertificateAuthority {
-    Canton,
-    SdmConfig,
-    SdmTally,
-    VotingServer,
-    ControlComponent1,
-    ControlComponent2,
-    ControlComponent3,
-    ControlComponent4,
+pub use crate::signature::{CertificateAuthority, VerifiySignatureTrait};
+
+use crate::config::Config;
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+use x509_parser::{certificate::X509Certificate, pem::parse_x509_pem};
+
+/// Problem found by the pre-validation of a direct trust certificate
+///
+/// The certificates are checked before verifying the signatures, so that an unsuitable
+/// certificate is reported as such and not as an error of the signature verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertificateProblem {
+    /// The certificate cannot be read or parsed
+    Unreadable(String),
+    /// The key usage does not allow digital signatures
+    KeyUsage(String),
+    /// The extended key usage does not allow code signing
+    ExtendedKeyUsage(String),
+    /// The common name of the subject does not follow the naming scheme
+    CommonName(String),
+    /// The certificate is not valid at the time of the verification
+    Validity(String),
 }
 
-impl CertificateAuthority {
-    pub fn get_ca_cc(node: &usize) -> Option<Self> {
-        match node {
-            1 => Some(Self::ControlComponent1),
-            2 => Some(Self::ControlComponent2),
-            3 => Some(Self::ControlComponent3),
-            4 => Some(Self::ControlComponent4),
-            _ => None,
+impl CertificateProblem {
+    /// A blocking problem makes the certificate unusable for the verification of signatures
+    ///
+    /// The naming scheme of the common name is not blocking, since the test certificates don't
+    /// follow it. The validity window is not blocking either, since a signature created during
+    /// the validity of the certificate can be verified afterwards. The validity is reported by
+    /// the verifications of the direct trust certificates
+    pub fn is_blocking(&self) -> bool {
+        !matches!(self, Self::CommonName(_) | Self::Validity(_))
+    }
+}
+
+impl Display for CertificateProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unreadable(s) => write!(f, "Certificate cannot be read: {}", s),
+            Self::KeyUsage(s) => write!(f, "Wrong key usage: {}", s),
+            Self::ExtendedKeyUsage(s) => write!(f, "Wrong extended key usage: {}", s),
+            Self::CommonName(s) => write!(f, "Wrong common name: {}", s),
+            Self::Validity(s) => write!(f, "Certificate not valid: {}", s),
         }
     }
 }
 
-impl From<CertificateAuthority> for String {
-    fn from(value: CertificateAuthority) -> Self {
-        match value {
-            CertificateAuthority::Canton => "canton".to_string(),
-            CertificateAuthority::SdmConfig => "sdm_config".to_string(),
-            CertificateAuthority::SdmTally => "sdm_tally".to_string(),
-            CertificateAuthority::VotingServer => "voting_server".to_string(),
-            CertificateAuthority::ControlComponent1 => "control_component_1".to_string(),
-            CertificateAuthority::ControlComponent2 => "control_component_2".to_string(),
-            CertificateAuthority::ControlComponent3 => "control_component_3".to_string(),
-            CertificateAuthority::ControlComponent4 => "control_component_4".to_string(),
+/// Normalize a name for the comparison of the common name with the certificate authority
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Check the parsed certificate for the certificate authority
+fn check_x509_certificate(
+    ca: CertificateAuthority,
+    cert: &X509Certificate,
+) -> Vec<CertificateProblem> {
+    let mut res = vec![];
+    let validity = cert.validity();
+    if !validity.is_valid() {
+        res.push(CertificateProblem::Validity(format!(
+            "the validity is from {} to {}",
+            validity.not_before, validity.not_after
+        )))
+    }
+    match cert.key_usage() {
+        Ok(Some(ku)) => {
+            if !ku.value.digital_signature() {
+                res.push(CertificateProblem::KeyUsage(
+                    "digital signature not allowed".to_string(),
+                ))
+            }
         }
+        Ok(None) => (),
+        Err(e) => res.push(CertificateProblem::KeyUsage(format!(
+            "key usage cannot be read: {}",
+            e
+        ))),
     }
+    match cert.extended_key_usage() {
+        Ok(Some(eku)) => {
+            if !eku.value.any && !eku.value.code_signing {
+                res.push(CertificateProblem::ExtendedKeyUsage(
+                    "code signing not allowed".to_string(),
+                ))
+            }
+        }
+        Ok(None) => (),
+        Err(e) => res.push(CertificateProblem::ExtendedKeyUsage(format!(
+            "extended key usage cannot be read: {}",
+            e
+        ))),
+    }
+    let expected_cn = String::from(ca);
+    let cns: Vec<&str> = cert
+        .subject()
+        .iter_common_name()
+        .filter_map(|cn| cn.as_str().ok())
+        .collect();
+    if !cns
+        .iter()
+        .any(|cn| normalize_name(cn).contains(&normalize_name(&expected_cn)))
+    {
+        res.push(CertificateProblem::CommonName(format!(
+            "the common name {:?} does not contain {}",
+            cns, expected_cn
+        )))
+    }
+    res
 }
 
-/// Trait that must be implemented for each object implementing a signature to be verified
+/// Check the certificate (in pem format) for the certificate authority
 ///
-/// The following function are to be implemented for the object to make it running:
-/// - [VerifiySignatureTrait::get_hashable] Get the [HashableMessage] for the object
-/// - [VerifiySignatureTrait::get_context_data] Get the context data as [HashableMessage] for the object, according to the specifications
-/// - [VerifiySignatureTrait::get_certificate_authority] Certificate Authority of the certificate to fin the certificate in the keystore
-/// - [VerifiySignatureTrait::get_signature] Get the signature of the object
-pub trait VerifiySignatureTrait<'a>
-where
-    Self: 'a,
-{
-    /// Get the hashable from the object
-    fn get_hashable(&'a self) -> anyhow::Result<HashableMessage<'a>>;
-
-    /// Get the context data of the object according to the specifications
-    fn get_context_data(&'a self) -> Vec<HashableMessage<'a>>;
-
-    /// Get the Certificate Authority to the specifications
-    fn get_certificate_authority(&self) -> anyhow::Result<String>;
-
-    /// Get the signature of the object
-    fn get_signature(&self) -> ByteArray;
-
-    /// Get the context data of the object according to the context data
-    fn get_context_hashable(&'a self) -> HashableMessage {
-        if self.get_context_data().len() == 1 {
-            return self.get_context_data()[0].clone();
-        }
-        HashableMessage::from(self.get_context_data())
+/// Return the list of problems. The list is empty if the certificate is suitable for the
+/// verification of signatures of the certificate authority.
+pub fn check_certificate(ca: CertificateAuthority, pem: &[u8]) -> Vec<CertificateProblem> {
+    let pem = match parse_x509_pem(pem) {
+        Ok((_, pem)) => pem,
+        Err(e) => return vec![CertificateProblem::Unreadable(e.to_string())],
+    };
+    match pem.parse_x509() {
+        Ok(cert) => check_x509_certificate(ca, &cert),
+        Err(e) => vec![CertificateProblem::Unreadable(e.to_string())],
     }
+}
 
-    /// Verfiy the signature according to the specifications of Verifier
-    fn verifiy_signature(&'a self, keystore: &Keystore) -> anyhow::Result<bool> {
-        let ca = &self
-            .get_certificate_authority()
-            .context("Error getting ca")?;
-        let hashable_message = self
-            .get_hashable()
-            .context("Error getting the hashable message")?;
-        verify_signature(
-            keystore,
-            ca.as_str(),
-            &hashable_message,
-            &self.get_context_hashable(),
-            &self.get_signature(),
-        )
-        .context("Error verifying the signature")
+/// Check the certificate file for the certificate authority (see [check_certificate])
+pub fn check_certificate_file(ca: CertificateAuthority, path: &Path) -> Vec<CertificateProblem> {
+    match fs::read(path) {
+        Ok(pem) => check_certificate(ca, &pem),
+        Err(e) => vec![CertificateProblem::Unreadable(format!(
+            "{}: {}",
+            path.display(),
+            e
+        ))],
     }
 }
 
+/// Check the certificates of all the certificate authorities in the direct trust directory
+///
+/// Used at the start of the application to report the problems before running the verifications
+pub fn check_direct_trust_certificates(
+    config: &Config,
+) -> Vec<(CertificateAuthority, CertificateProblem)> {
+    CertificateAuthority::all()
+        .into_iter()
+        .flat_map(|ca| {
+            check_certificate_file(ca, &config.direct_trust_certificate_path(&String::from(ca)))
+                .into_iter()
+                .map(move |p| (ca, p))
+        })
+        .collect()
+}
+
+/// Information on the direct trust certificate of a certificate authority, for the reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateInfo {
+    pub authority: CertificateAuthority,
+    /// Path of the certificate file
+    pub path: PathBuf,
+    /// Subject of the certificate. `None` if the certificate cannot be read
+    pub subject: Option<String>,
+    /// Serial number (hexadecimal). `None` if the certificate cannot be read
+    pub serial: Option<String>,
+    /// Start of the validity. `None` if the certificate cannot be read
+    pub not_before: Option<String>,
+    /// End of the validity. `None` if the certificate cannot be read
+    pub not_after: Option<String>,
+    /// Problems found by the pre-validation (see [check_certificate])
+    pub problems: Vec<CertificateProblem>,
+}
+
+impl CertificateInfo {
+    /// Read the information of the certificate file for the certificate authority
+    ///
+    /// If the certificate cannot be read, the problem is given in [CertificateInfo::problems]
+    pub fn new(ca: CertificateAuthority, path: &Path) -> Self {
+        let mut res = Self {
+            authority: ca,
+            path: path.to_path_buf(),
+            subject: None,
+            serial: None,
+            not_before: None,
+            not_after: None,
+            problems: check_certificate_file(ca, path),
+        };
+        if let Ok(pem) = fs::read(path) {
+            if let Ok((_, pem)) = parse_x509_pem(&pem) {
+                if let Ok(cert) = pem.parse_x509() {
+                    res.subject = Some(cert.subject().to_string());
+                    res.serial = Some(cert.raw_serial_as_string());
+                    res.not_before = Some(cert.validity().not_before.to_string());
+                    res.not_after = Some(cert.validity().not_after.to_string());
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Information on the certificates of all the certificate authorities in the direct trust
+/// directory
+pub fn direct_trust_certificate_infos(config: &Config) -> Vec<CertificateInfo> {
+    CertificateAuthority::all()
+        .into_iter()
+        .map(|ca| {
+            CertificateInfo::new(ca, &config.direct_trust_certificate_path(&String::from(ca)))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
-    use crate::config::test::CONFIG_TEST;
+    use crate::config::test::{test_datasets_path, CONFIG_TEST};
 
     use super::*;
 
+    #[test]
+    fn test_check_certificate() {
+        let path = test_datasets_path().join("direct-trust");
+        for ca in CertificateAuthority::all() {
+            let problems =
+                check_certificate_file(ca, &path.join(format!("{}.cer", String::from(ca))));
+            // The test certificates don't follow the naming scheme
+            assert!(problems.iter().all(|p| !p.is_blocking()));
+            assert!(!problems
+                .iter()
+                .any(|p| matches!(p, CertificateProblem::Validity(_))));
+        }
+        let problems = check_certificate(CertificateAuthority::Canton, b"toto");
+        assert_eq!(problems.len(), 1);
+        assert!(matches!(problems[0], CertificateProblem::Unreadable(_)));
+        assert!(matches!(
+            check_certificate_file(CertificateAuthority::Canton, &path.join("toto.cer"))[0],
+            CertificateProblem::Unreadable(_)
+        ));
+    }
+
+    #[test]
+    fn test_certificate_info() {
+        let path = test_datasets_path().join("direct-trust");
+        for ca in CertificateAuthority::all() {
+            let info = CertificateInfo::new(ca, &path.join(format!("{}.cer", String::from(ca))));
+            assert!(info.subject.is_some(), "{:?}", ca);
+            assert!(info.serial.is_some());
+            assert!(info.not_after.is_some());
+            assert!(info.problems.iter().all(|p| !p.is_blocking()));
+        }
+        let info = CertificateInfo::new(CertificateAuthority::Canton, &path.join("toto.cer"));
+        assert!(info.subject.is_none());
+        assert!(matches!(
+            info.problems[0],
+            CertificateProblem::Unreadable(_)
+        ));
+    }
+
     #[test]
     fn test_create() {
         let dt = CONFIG_TEST.keystore().unwrap();
@@ -0,0 +1,474 @@
+//! C-compatible interface (FFI) of the verifier, to embed the verifier in applications that are
+//! not written in Rust (e.g. a GUI)
+//!
+//! The module is available with the feature `ffi`. The library must be built as C library, e.g.
+//! with `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+//!
+//! # Usage
+//! 1. Create a handle with [verifier_new], with the root directory of the verifier (containing
+//!    the directory of the direct trust certificates)
+//! 2. Enumerate the verifications with [verifier_list_verifications]
+//! 3. Run the verifications on a dataset with [verifier_run], returning the report of the run
+//!    (see [crate::application_runner::RunReport])
+//! 4. Free the handle with [verifier_free]
+//!
+//! The lists and the reports are returned as json strings.
+//!
+//! # Errors
+//! Each function returns a [VerifierErrorCode]. If the code is not [VerifierErrorCode::Ok], the
+//! message of the error can be fetched with [verifier_last_error]. The panics are caught and
+//! returned as [VerifierErrorCode::Panic].
+//!
+//! # Memory ownership
+//! - The handle is owned by the caller and must be freed with [verifier_free]
+//! - The strings returned by the library (out parameters) are owned by the caller and must be
+//!   freed with [verifier_string_free]. They are not freed with the handle
+//! - The strings given to the library are borrowed for the duration of the call only
+//! - The configuration of a handle is kept until the end of the process, since the verifications
+//!   refer to it. An application should create only one handle
+
+use crate::{
+    application_runner::{
+        check_verification_dir, no_action_after_fn, no_action_before_fn, start_check, RunParallel,
+        Runner,
+    },
+    config::Config as VerifierConfig,
+    file_structure::data_source::{local_source, LocalDataSource},
+    verification::{
+        meta_data::MetaDataQuery, suite::query_verification_metadata, VerificationPeriod,
+    },
+};
+use anyhow::anyhow;
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    ptr,
+};
+
+/// Code returned by the functions of the FFI
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifierErrorCode {
+    Ok = 0,
+    /// A mandatory pointer is null
+    NullPointer = 1,
+    /// A string is not valid UTF-8 or contains a null character
+    InvalidString = 2,
+    /// An argument is not valid (e.g. unknown period, exclusion not a json array)
+    InvalidArgument = 3,
+    /// The verifier cannot start (e.g. the keystore cannot be read)
+    StartCheck = 4,
+    /// The dataset is not valid for the period
+    InvalidDataset = 5,
+    /// The run of the verifications failed
+    RunFailed = 6,
+    /// The result cannot be serialized to json
+    Serialization = 7,
+    /// The library panicked
+    Panic = 8,
+}
+
+/// Handle of the verifier, created by [verifier_new]
+pub struct VerifierHandle {
+    config: &'static VerifierConfig,
+    last_error: Option<String>,
+}
+
+/// Error of a function of the FFI, recorded in the handle
+struct FfiError {
+    code: VerifierErrorCode,
+    message: String,
+}
+
+impl FfiError {
+    fn new(code: VerifierErrorCode, e: impl std::fmt::Display) -> Self {
+        Self {
+            code,
+            message: format!("{:#}", e),
+        }
+    }
+}
+
+/// Read the string, `None` if the pointer is null
+///
+/// # Safety
+/// `s` must be null or a valid C string
+unsafe fn str_from_ptr<'a>(s: *const c_char) -> Result<Option<&'a str>, FfiError> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map(Some)
+        .map_err(|e| FfiError::new(VerifierErrorCode::InvalidString, e))
+}
+
+/// Read the mandatory string `name`
+///
+/// # Safety
+/// `s` must be null or a valid C string
+unsafe fn mandatory_str<'a>(s: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    str_from_ptr(s)?.ok_or_else(|| {
+        FfiError::new(
+            VerifierErrorCode::NullPointer,
+            format!("The argument {} is null", name),
+        )
+    })
+}
+
+/// Give the ownership of the string to the caller in `out`
+///
+/// # Safety
+/// `out` must be a valid pointer
+unsafe fn string_to_out(value: String, out: *mut *mut c_char) -> Result<(), FfiError> {
+    let s = CString::new(value).map_err(|e| FfiError::new(VerifierErrorCode::InvalidString, e))?;
+    *out = s.into_raw();
+    Ok(())
+}
+
+/// Run `f` with the handle, catching the panics and recording the error in the handle
+///
+/// # Safety
+/// `handle` must be null or a handle created by [verifier_new]
+unsafe fn with_handle(
+    handle: *mut VerifierHandle,
+    f: impl FnOnce(&VerifierHandle) -> Result<(), FfiError>,
+) -> VerifierErrorCode {
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return VerifierErrorCode::NullPointer,
+    };
+    let res = match catch_unwind(AssertUnwindSafe(|| f(handle))) {
+        Ok(r) => r,
+        Err(_) => Err(FfiError::new(
+            VerifierErrorCode::Panic,
+            "The verifier panicked",
+        )),
+    };
+    match res {
+        Ok(()) => {
+            handle.last_error = None;
+            VerifierErrorCode::Ok
+        }
+        Err(e) => {
+            handle.last_error = Some(e.message);
+            e.code
+        }
+    }
+}
+
+/// Create a handle of the verifier, with the root directory of the verifier in `root_dir`
+///
+/// The handle is written in `out_handle`. If the verifier cannot start (see
+/// [crate::application_runner::start_check]), the handle is created anyway and
+/// [VerifierErrorCode::StartCheck] is returned, so that the error can be fetched with
+/// [verifier_last_error].
+///
+/// # Safety
+/// `root_dir` must be a valid C string and `out_handle` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn verifier_new(
+    root_dir: *const c_char,
+    out_handle: *mut *mut VerifierHandle,
+) -> VerifierErrorCode {
+    if out_handle.is_null() {
+        return VerifierErrorCode::NullPointer;
+    }
+    *out_handle = ptr::null_mut();
+    let root_dir = match mandatory_str(root_dir, "root_dir") {
+        Ok(s) => s,
+        Err(e) => return e.code,
+    };
+    let res = catch_unwind(|| {
+        let root_dir: &'static str = Box::leak(root_dir.to_string().into_boxed_str());
+        let config: &'static VerifierConfig = Box::leak(Box::new(VerifierConfig::new(root_dir)));
+        let check = start_check(config);
+        (config, check)
+    });
+    let (config, check) = match res {
+        Ok(r) => r,
+        Err(_) => return VerifierErrorCode::Panic,
+    };
+    let (code, last_error) = match check {
+        Ok(()) => (VerifierErrorCode::Ok, None),
+        Err(e) => (VerifierErrorCode::StartCheck, Some(format!("{:#}", e))),
+    };
+    *out_handle = Box::into_raw(Box::new(VerifierHandle { config, last_error }));
+    code
+}
+
+/// Free the handle
+///
+/// # Safety
+/// `handle` must be null or a handle created by [verifier_new], not already freed
+#[no_mangle]
+pub unsafe extern "C" fn verifier_free(handle: *mut VerifierHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Free a string returned by the library
+///
+/// # Safety
+/// `s` must be null or a string returned by the library, not already freed
+#[no_mangle]
+pub unsafe extern "C" fn verifier_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Message of the last error of the handle, written in `out_message`
+///
+/// `out_message` is set to null if the last call was successful
+///
+/// # Safety
+/// `handle` must be a handle created by [verifier_new] and `out_message` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn verifier_last_error(
+    handle: *const VerifierHandle,
+    out_message: *mut *mut c_char,
+) -> VerifierErrorCode {
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return VerifierErrorCode::NullPointer,
+    };
+    if out_message.is_null() {
+        return VerifierErrorCode::NullPointer;
+    }
+    *out_message = ptr::null_mut();
+    match &handle.last_error {
+        Some(m) => match string_to_out(m.clone(), out_message) {
+            Ok(()) => VerifierErrorCode::Ok,
+            Err(e) => e.code,
+        },
+        None => VerifierErrorCode::Ok,
+    }
+}
+
+/// List of the verifications of both periods, written as json array in `out_json`
+///
+/// Each entry contains the metadata of the verification and its status (see
+/// [crate::verification::meta_data::MetaDataEntry])
+///
+/// # Safety
+/// `handle` must be a handle created by [verifier_new] and `out_json` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn verifier_list_verifications(
+    handle: *mut VerifierHandle,
+    out_json: *mut *mut c_char,
+) -> VerifierErrorCode {
+    with_handle(handle, |h| {
+        if out_json.is_null() {
+            return Err(FfiError::new(
+                VerifierErrorCode::NullPointer,
+                "The argument out_json is null",
+            ));
+        }
+        let entries = query_verification_metadata(&MetaDataQuery::new(), &[], h.config)
+            .map_err(|e| FfiError::new(VerifierErrorCode::StartCheck, e))?;
+        let json = serde_json::to_string(&entries)
+            .map_err(|e| FfiError::new(VerifierErrorCode::Serialization, e))?;
+        string_to_out(json, out_json)
+    })
+}
+
+/// Run the verifications on the dataset in `dataset_dir` and write the report of the run as
+/// json in `out_report_json`
+///
+/// - `period` is `setup` or `tally`. If null, the period is detected from the dataset
+/// - `exclusion_json` is a json array with the ids of the verifications to exclude. It can be
+///   null
+///
+/// The call is blocking until the end of the run. The failures of the verifications are given in
+/// the report and are not errors of the function.
+///
+/// # Safety
+/// `handle` must be a handle created by [verifier_new], `dataset_dir` a valid C string,
+/// `period` and `exclusion_json` null or valid C strings, and `out_report_json` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn verifier_run(
+    handle: *mut VerifierHandle,
+    dataset_dir: *const c_char,
+    period: *const c_char,
+    exclusion_json: *const c_char,
+    out_report_json: *mut *mut c_char,
+) -> VerifierErrorCode {
+    with_handle(handle, |h| {
+        if out_report_json.is_null() {
+            return Err(FfiError::new(
+                VerifierErrorCode::NullPointer,
+                "The argument out_report_json is null",
+            ));
+        }
+        let dir = Path::new(mandatory_str(dataset_dir, "dataset_dir")?);
+        let period = match str_from_ptr(period)? {
+            Some(p) => Some(
+                VerificationPeriod::try_from(p)
+                    .map_err(|e| FfiError::new(VerifierErrorCode::InvalidArgument, e))?,
+            ),
+            None => None,
+        };
+        let exclusion: Vec<String> = match str_from_ptr(exclusion_json)? {
+            Some(s) => serde_json::from_str(s).map_err(|e| {
+                FfiError::new(
+                    VerifierErrorCode::InvalidArgument,
+                    anyhow!(e).context("The exclusion must be a json array of ids"),
+                )
+            })?,
+            None => vec![],
+        };
+        if let Some(p) = &period {
+            check_verification_dir(p, &LocalDataSource, dir)
+                .map_err(|e| FfiError::new(VerifierErrorCode::InvalidDataset, e))?;
+        }
+        let metadata = h
+            .config
+            .verification_metadata()
+            .map_err(|e| FfiError::new(VerifierErrorCode::StartCheck, e))?;
+        let mut runner = match &period {
+            Some(p) => Runner::new(
+                dir,
+                p,
+                metadata,
+                &exclusion,
+                RunParallel::default(),
+                h.config,
+                no_action_before_fn,
+                no_action_after_fn,
+            ),
+            None => Runner::new_with_detected_period(
+                dir,
+                local_source(),
+                metadata,
+                &exclusion,
+                RunParallel::default(),
+                h.config,
+                no_action_before_fn,
+                no_action_after_fn,
+            ),
+        }
+        .map_err(|e| FfiError::new(VerifierErrorCode::InvalidDataset, e))?;
+        if let Some(e) = runner.run_all(metadata) {
+            return Err(FfiError::new(VerifierErrorCode::RunFailed, e));
+        }
+        let json = runner
+            .report()
+            .to_json()
+            .map_err(|e| FfiError::new(VerifierErrorCode::Serialization, e))?;
+        string_to_out(json, out_report_json)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::test::{test_dataset_setup_path, CONFIG_TEST};
+
+    fn new_handle() -> *mut VerifierHandle {
+        let root_dir = CString::new(".").unwrap();
+        let mut handle = ptr::null_mut();
+        assert_eq!(
+            unsafe { verifier_new(root_dir.as_ptr(), &mut handle) },
+            VerifierErrorCode::Ok
+        );
+        assert!(!handle.is_null());
+        handle
+    }
+
+    /// Take the string returned by the library
+    fn take_string(s: *mut c_char) -> String {
+        assert!(!s.is_null());
+        let res = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+        unsafe { verifier_string_free(s) };
+        res
+    }
+
+    #[test]
+    fn test_list_verifications() {
+        let handle = new_handle();
+        let mut json = ptr::null_mut();
+        assert_eq!(
+            unsafe { verifier_list_verifications(handle, &mut json) },
+            VerifierErrorCode::Ok
+        );
+        let entries: serde_json::Value = serde_json::from_str(&take_string(json)).unwrap();
+        assert_eq!(
+            entries.as_array().unwrap().len(),
+            CONFIG_TEST.verification_metadata().unwrap().len()
+        );
+        assert_eq!(entries[0]["id"], "01.01");
+        unsafe { verifier_free(handle) };
+    }
+
+    #[test]
+    fn test_run() {
+        let handle = new_handle();
+        let dir = CString::new(test_dataset_setup_path().to_str().unwrap()).unwrap();
+        let period = CString::new("setup").unwrap();
+        // Only the verification 01.01 is run
+        let exclusion: Vec<String> = CONFIG_TEST
+            .verification_metadata()
+            .unwrap()
+            .id_list_for_period(&VerificationPeriod::Setup)
+            .into_iter()
+            .filter(|id| id != "01.01")
+            .collect();
+        let exclusion = CString::new(serde_json::to_string(&exclusion).unwrap()).unwrap();
+        let mut json = ptr::null_mut();
+        assert_eq!(
+            unsafe {
+                verifier_run(
+                    handle,
+                    dir.as_ptr(),
+                    period.as_ptr(),
+                    exclusion.as_ptr(),
+                    &mut json,
+                )
+            },
+            VerifierErrorCode::Ok
+        );
+        let report: serde_json::Value = serde_json::from_str(&take_string(json)).unwrap();
+        assert_eq!(report["period"], "setup");
+        assert_eq!(report["verifications"][0]["id"], "01.01");
+        unsafe { verifier_free(handle) };
+    }
+
+    #[test]
+    fn test_errors() {
+        let handle = new_handle();
+        let dir = CString::new(test_dataset_setup_path().to_str().unwrap()).unwrap();
+        let period = CString::new("toto").unwrap();
+        let mut json = ptr::null_mut();
+        assert_eq!(
+            unsafe {
+                verifier_run(
+                    handle,
+                    dir.as_ptr(),
+                    period.as_ptr(),
+                    ptr::null(),
+                    &mut json,
+                )
+            },
+            VerifierErrorCode::InvalidArgument
+        );
+        assert!(json.is_null());
+        let mut message = ptr::null_mut();
+        assert_eq!(
+            unsafe { verifier_last_error(handle, &mut message) },
+            VerifierErrorCode::Ok
+        );
+        assert!(!take_string(message).is_empty());
+        assert_eq!(
+            unsafe { verifier_run(handle, ptr::null(), ptr::null(), ptr::null(), &mut json,) },
+            VerifierErrorCode::NullPointer
+        );
+        assert_eq!(
+            unsafe { verifier_list_verifications(ptr::null_mut(), &mut json) },
+            VerifierErrorCode::NullPointer
+        );
+        unsafe { verifier_free(handle) };
+    }
+}
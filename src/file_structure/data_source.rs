@@ -0,0 +1,185 @@
+//! Module implementing the sources of the files of the dataset
+//!
+//! [super::file::File], [super::file_group::FileGroup] and the directories access the files only
+//! through a [DataSource]. The source is given to the directories at their creation (e.g.
+//! [super::setup_directory::SetupDirectory::new_with_source]) and passed down to their files,
+//! file groups and subdirectories. Per default, the files are read on the disk
+//! ([LocalDataSource]). Other sources are e.g. the entries of a ZIP archive or a dataset whose
+//! layout is normalized (see [super::normalization]).
+//!
+//! The source belongs to the directory, so that datasets of different sources can be verified
+//! in the same process, and the directories and the verifications work unchanged with every
+//! source.
+use anyhow::anyhow;
+use std::{
+    collections::BTreeSet,
+    fmt::Debug,
+    fs,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Reader on a file of a [DataSource] (see [DataSource::open_reader])
+pub type FileReader = Box<dyn BufRead + Send>;
+
+/// Trait for the sources of the files
+///
+/// The paths are the complete paths, as given by the directories using the source
+pub trait DataSource: Send + Sync + Debug {
+    /// Test if the file exists
+    fn file_exists(&self, path: &Path) -> bool;
+
+    /// Test if the directory exists
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Size of the file in bytes
+    fn file_len(&self, path: &Path) -> anyhow::Result<u64>;
+
+    /// Open a buffered reader on the file
+    fn open_reader(&self, path: &Path) -> anyhow::Result<FileReader>;
+
+    /// Paths of the entries (files and subdirectories) of the directory, sorted
+    fn read_dir(&self, path: &Path) -> Vec<PathBuf>;
+
+    /// Paths of the files matching the glob pattern
+    fn glob(&self, pattern: &str) -> Vec<PathBuf>;
+
+    /// Read the content of the file
+    fn read_to_string(&self, path: &Path) -> anyhow::Result<String> {
+        let mut res = String::new();
+        self.open_reader(path)?
+            .read_to_string(&mut res)
+            .map_err(|e| anyhow!(e))?;
+        Ok(res)
+    }
+
+    /// Read at most `len` bytes of the file, starting at `start`
+    ///
+    /// The bytes that are not valid utf-8 (e.g. a character cut at the limits of the range)
+    /// are replaced
+    fn read_range(&self, path: &Path, start: u64, len: usize) -> anyhow::Result<String> {
+        let mut reader = self.open_reader(path)?;
+        std::io::copy(&mut reader.by_ref().take(start), &mut std::io::sink())
+            .map_err(|e| anyhow!(e))?;
+        let mut buf = vec![];
+        reader
+            .take(len as u64)
+            .read_to_end(&mut buf)
+            .map_err(|e| anyhow!(e))?;
+        Ok(String::from_utf8_lossy(&buf).to_string())
+    }
+}
+
+/// Source reading the files on the disk
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalDataSource;
+
+impl DataSource for LocalDataSource {
+    fn file_exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn file_len(&self, path: &Path) -> anyhow::Result<u64> {
+        fs::metadata(path).map(|m| m.len()).map_err(|e| anyhow!(e))
+    }
+
+    fn open_reader(&self, path: &Path) -> anyhow::Result<FileReader> {
+        let f = fs::File::open(path).map_err(|e| anyhow!(e))?;
+        Ok(Box::new(BufReader::new(f)))
+    }
+
+    fn read_dir(&self, path: &Path) -> Vec<PathBuf> {
+        match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn glob(&self, pattern: &str) -> Vec<PathBuf> {
+        match glob::glob(pattern) {
+            Ok(paths) => paths.filter_map(|p| p.ok()).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> anyhow::Result<String> {
+        fs::read_to_string(path).map_err(|e| anyhow!(e))
+    }
+
+    fn read_range(&self, path: &Path, start: u64, len: usize) -> anyhow::Result<String> {
+        let mut f = fs::File::open(path).map_err(|e| anyhow!(e))?;
+        f.seek(SeekFrom::Start(start)).map_err(|e| anyhow!(e))?;
+        let mut buf = vec![];
+        f.take(len as u64)
+            .read_to_end(&mut buf)
+            .map_err(|e| anyhow!(e))?;
+        Ok(String::from_utf8_lossy(&buf).to_string())
+    }
+}
+
+/// The source reading the files on the disk, default source of the directories
+pub fn local_source() -> Arc<dyn DataSource> {
+    Arc::new(LocalDataSource)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{test::test_dataset_setup_path, Config};
+
+    #[test]
+    fn test_local() {
+        let setup = test_dataset_setup_path().join(Config::setup_dir_name());
+        let path = setup.join("electionEventContextPayload.json");
+        let source = local_source();
+        assert!(source.file_exists(&path));
+        assert!(source.is_dir(&setup));
+        assert!(!source.is_dir(&path));
+        let len = source.file_len(&path).unwrap();
+        assert_eq!(source.read_to_string(&path).unwrap().len() as u64, len);
+        assert_eq!(source.read_range(&path, 0, 1).unwrap(), "{");
+        assert_eq!(
+            LocalDataSource.read_range(&path, 1, 10).unwrap(),
+            DataSource::read_range(&DefaultReadRangeSource, &path, 1, 10).unwrap()
+        );
+        assert!(source.read_dir(&setup).contains(&path));
+        assert_eq!(
+            source.glob(setup.join("electionEventContext*.json").to_str().unwrap()),
+            vec![path]
+        );
+    }
+
+    /// Source on the disk using the default implementation of `read_range`
+    struct DefaultReadRangeSource;
+
+    impl DataSource for DefaultReadRangeSource {
+        fn file_exists(&self, path: &Path) -> bool {
+            LocalDataSource.file_exists(path)
+        }
+        fn is_dir(&self, path: &Path) -> bool {
+            LocalDataSource.is_dir(path)
+        }
+        fn file_len(&self, path: &Path) -> anyhow::Result<u64> {
+            LocalDataSource.file_len(path)
+        }
+        fn open_reader(&self, path: &Path) -> anyhow::Result<FileReader> {
+            LocalDataSource.open_reader(path)
+        }
+        fn read_dir(&self, path: &Path) -> Vec<PathBuf> {
+            LocalDataSource.read_dir(path)
+        }
+        fn glob(&self, pattern: &str) -> Vec<PathBuf> {
+            LocalDataSource.glob(pattern)
+        }
+    }
+}
@@ -1,74 +1,199 @@
-use super::GetFileNameTrait;
+use super::{
+    data_source::{local_source, DataSource, FileReader},
+    file_access::record_file_access,
+    FileReadMode, FileType, GetFileNameTrait,
+};
 use crate::data_structures::{VerifierData, VerifierDataType};
 use anyhow::anyhow;
-use glob::glob;
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
 
-#[derive(Clone)]
+/// Number of bytes read at the beginning and at the end of a file to check its content
+const CONTENT_CHECK_LENGTH: usize = 4096;
+
+#[derive(Clone, Debug)]
 pub struct File {
+    source: Arc<dyn DataSource>,
     path: PathBuf,
     data_type: VerifierDataType,
 }
 
+/// Problem of the content of a file, detected before decoding it (see [File::check_content])
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FileContentError {
+    /// The file is empty or contains only whitespaces
+    #[error("[FILE_EMPTY] File \"{path}\" is empty")]
+    Empty { path: String },
+    /// The end of the file is missing (unbalanced json or xml)
+    #[error("[FILE_TRUNCATED] File \"{path}\" is truncated: {reason}")]
+    Truncated { path: String, reason: String },
+}
+
+impl FileContentError {
+    pub const EMPTY_CODE: &'static str = "FILE_EMPTY";
+    pub const TRUNCATED_CODE: &'static str = "FILE_TRUNCATED";
+
+    /// Code of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            FileContentError::Empty { .. } => Self::EMPTY_CODE,
+            FileContentError::Truncated { .. } => Self::TRUNCATED_CODE,
+        }
+    }
+}
+
 macro_rules! create_file {
-    ($l: expr, $p: ident, $s: expr) => {
-        File::new(&$l, &VerifierDataType::$p($s), None)
+    ($src: expr, $l: expr, $p: ident, $s: expr) => {
+        File::new_with_source(&$src, &$l, &VerifierDataType::$p($s), None)
     };
-    ($l: expr, $p: ident, $s: expr, $n: expr) => {
-        File::new(&$l, &VerifierDataType::$p($s), Some($n))
+    ($src: expr, $l: expr, $p: ident, $s: expr, $n: expr) => {
+        File::new_with_source(&$src, &$l, &VerifierDataType::$p($s), Some($n))
     };
 }
 pub(crate) use create_file;
 
 impl File {
+    /// New file on the disk
     pub fn new(location: &Path, data_type: &VerifierDataType, file_nb: Option<usize>) -> Self {
+        Self::new_with_source(&local_source(), location, data_type, file_nb)
+    }
+
+    /// New file read from the given source
+    pub fn new_with_source(
+        source: &Arc<dyn DataSource>,
+        location: &Path,
+        data_type: &VerifierDataType,
+        file_nb: Option<usize>,
+    ) -> Self {
         let name = data_type.get_file_name(file_nb);
         let mut path = location.join(&name);
         if name.contains('*') {
-            let r_entries = glob(path.as_os_str().to_str().unwrap());
-            if let Ok(entries) = r_entries {
-                let p = entries.last();
-                if let Some(p_f) = p {
-                    path = location.join(p_f.unwrap().file_name().unwrap());
-                };
+            if let Some(f_name) = source
+                .glob(&path.to_string_lossy())
+                .last()
+                .and_then(|p_f| p_f.file_name().map(|n| n.to_os_string()))
+            {
+                path = location.join(f_name);
             }
         }
         File {
+            source: source.clone(),
             path,
             data_type: data_type.clone(),
         }
     }
 
+    /// New file with an explicit name in the location (e.g. a nonstandard name in a file group)
+    pub fn new_with_name(
+        source: &Arc<dyn DataSource>,
+        location: &Path,
+        data_type: &VerifierDataType,
+        name: &str,
+    ) -> Self {
+        File {
+            source: source.clone(),
+            path: location.join(name),
+            data_type: data_type.clone(),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn get_location(&self) -> PathBuf {
-        self.path.parent().unwrap().to_path_buf()
+        self.path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default()
     }
 
     pub fn exists(&self) -> bool {
-        self.path.exists()
+        self.source.file_exists(&self.path)
+    }
+
+    /// Source from which the file is read
+    pub fn source(&self) -> &Arc<dyn DataSource> {
+        &self.source
+    }
+
+    /// Size of the file in bytes
+    pub fn size(&self) -> anyhow::Result<u64> {
+        self.source.file_len(&self.path)
+    }
+
+    /// Open a buffered reader on the file
+    pub fn open_reader(&self) -> anyhow::Result<FileReader> {
+        self.source.open_reader(&self.path)
     }
 
     pub fn get_path(&self) -> PathBuf {
         self.path.to_path_buf()
     }
 
-    pub fn to_str(&self) -> &str {
-        self.path.to_str().unwrap()
+    /// Path of the file as string (the non UTF-8 characters are replaced)
+    pub fn to_str(&self) -> Cow<'_, str> {
+        self.path.to_string_lossy()
     }
 
     pub fn read_data(&self) -> anyhow::Result<String> {
-        fs::read_to_string(self.get_path())
-            .map_err(|e| anyhow!(e).context(format!("Cannot read file \"{}\"", self.to_str())))
+        record_file_access(&self.path);
+        self.source
+            .read_to_string(&self.path)
+            .map_err(|e| e.context(format!("Cannot read file \"{}\"", self.to_str())))
+    }
+
+    /// Mode to read the file, according to its type and its size (see [FileReadMode::from_file_size])
+    pub fn read_mode(&self) -> FileReadMode {
+        FileReadMode::from_file_size(&self.data_type.get_file_type(), self.size().unwrap_or(0))
+    }
+
+    /// Check that the file is neither empty nor obviously truncated, without reading it
+    /// completely
+    ///
+    /// Only the beginning and the end of the file are read. A json file starting with `{` (resp.
+    /// `[`) must end with `}` (resp. `]`). A xml file must end with the closing tag of its root
+    /// element. The file must exist.
+    pub fn check_content(&self) -> anyhow::Result<()> {
+        let len = self
+            .size()
+            .map_err(|e| e.context(format!("Cannot read file \"{}\"", self.to_str())))?;
+        let head = self
+            .source
+            .read_range(&self.path, 0, CONTENT_CHECK_LENGTH)?;
+        if len == 0 || (len as usize <= CONTENT_CHECK_LENGTH && head.trim().is_empty()) {
+            return Err(anyhow!(FileContentError::Empty {
+                path: self.to_str().to_string()
+            }));
+        }
+        let tail = self.source.read_range(
+            &self.path,
+            len.saturating_sub(CONTENT_CHECK_LENGTH as u64),
+            CONTENT_CHECK_LENGTH,
+        )?;
+        let reason = match self.data_type.get_file_type() {
+            FileType::Json => truncated_json_reason(&head, &tail),
+            FileType::Xml => truncated_xml_reason(&head, &tail),
+        };
+        match reason {
+            Some(reason) => Err(anyhow!(FileContentError::Truncated {
+                path: self.to_str().to_string(),
+                reason
+            })),
+            None => Ok(()),
+        }
     }
 
     pub fn get_data(&self) -> anyhow::Result<VerifierData> {
+        record_file_access(&self.path);
         if !self.exists() {
             return Err(anyhow!(format!(
                 "File \"{}\" does not exists",
                 self.to_str()
             )));
         }
+        self.check_content()?;
         self.data_type.verifier_data_from_file(self).map_err(|e| {
             anyhow!(e).context(format!(
                 "Content of the file \"{}\" is not valid",
@@ -78,13 +203,72 @@ impl File {
     }
 }
 
+/// Reason why the json is truncated, if the closing character of the first object or array is
+/// missing at the end
+fn truncated_json_reason(head: &str, tail: &str) -> Option<String> {
+    let expected = match head
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .chars()
+        .next()
+    {
+        Some('{') => '}',
+        Some('[') => ']',
+        _ => return None,
+    };
+    match tail.trim_end().ends_with(expected) {
+        true => None,
+        false => Some(format!("the json does not end with '{}'", expected)),
+    }
+}
+
+/// Name of the root element of the xml, skipping the declaration, the comments and the
+/// document type
+fn xml_root_name(head: &str) -> Option<&str> {
+    let mut rest = head.trim_start_matches('\u{feff}');
+    loop {
+        rest = rest.trim_start();
+        if let Some(r) = rest.strip_prefix("<?") {
+            rest = &r[r.find("?>")? + 2..];
+        } else if let Some(r) = rest.strip_prefix("<!--") {
+            rest = &r[r.find("-->")? + 3..];
+        } else if let Some(r) = rest.strip_prefix("<!") {
+            rest = &r[r.find('>')? + 1..];
+        } else {
+            let r = rest.strip_prefix('<')?;
+            let end = r.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+            return Some(&r[..end]);
+        }
+    }
+}
+
+/// Reason why the xml is truncated, if the root element is not closed at the end
+fn truncated_xml_reason(head: &str, tail: &str) -> Option<String> {
+    let root = xml_root_name(head)?;
+    let tail = tail.trim_end();
+    let closed = tail
+        .strip_suffix('>')
+        .and_then(|t| t.rfind("</").map(|pos| t[pos + 2..].trim() == root))
+        .unwrap_or(false);
+    match closed {
+        true => None,
+        false => Some(format!("the root element <{}> is not closed", root)),
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::*;
-    use crate::config::test::{test_dataset_setup_path, test_dataset_tally_path};
+    use super::{super::memory_fs::MemoryFileSystem, *};
+    use crate::config::{
+        test::{test_dataset_setup_path, test_dataset_tally_path},
+        Config,
+    };
     use crate::data_structures::{
-        setup::VerifierSetupDataType, tally::VerifierTallyDataType, VerifierDataType,
-        VerifierSetupDataTrait,
+        setup::{
+            election_event_context_payload::ElectionEventContextPayload, VerifierSetupDataType,
+        },
+        tally::VerifierTallyDataType,
+        VerifierDataDecode, VerifierDataType, VerifierSetupDataTrait,
     };
     use std::path::PathBuf;
 
@@ -116,6 +300,7 @@ mod test {
     fn test_file_macro() {
         let location = get_location();
         let f = create_file!(
+            local_source(),
             &location,
             Setup,
             VerifierSetupDataType::ElectionEventContextPayload
@@ -203,6 +388,7 @@ mod test {
     fn test_file_with_nb_macro() {
         let location = get_location();
         let f = create_file!(
+            local_source(),
             &location,
             Setup,
             VerifierSetupDataType::ControlComponentPublicKeysPayload,
@@ -217,4 +403,75 @@ mod test {
         let data = f.get_data().unwrap();
         assert!(data.is_setup());
     }
+
+    #[test]
+    fn test_check_content() {
+        let mfs = MemoryFileSystem::new(Path::new("file_test_check_content"));
+        let json_type = VerifierDataType::Setup(VerifierSetupDataType::ElectionEventContextPayload);
+        let xml_type = VerifierDataType::Tally(VerifierTallyDataType::ECH0222);
+        let code = |f: &File| {
+            f.check_content()
+                .unwrap_err()
+                .downcast_ref::<FileContentError>()
+                .unwrap()
+                .code()
+        };
+        mfs.add_file("ok/electionEventContextPayload.json", "{\"a\": [1, 2]}\n")
+            .add_file(
+                "ok/eCH-0222_1.xml",
+                "<?xml version=\"1.0\"?>\n<!-- c -->\n<ns:delivery a=\"1\"><b/></ns:delivery>\n",
+            )
+            .add_file("empty/electionEventContextPayload.json", " \n")
+            .add_file("empty/eCH-0222_1.xml", "")
+            .add_file(
+                "truncated/electionEventContextPayload.json",
+                "{\"a\": [1, 2",
+            )
+            .add_file(
+                "truncated/eCH-0222_1.xml",
+                "<?xml version=\"1.0\"?><ns:delivery><b/></ns:deliv",
+            );
+        let root = mfs.root();
+        let source = mfs.source();
+        let file =
+            |d: &str, t: &VerifierDataType| File::new_with_source(&source, &root.join(d), t, None);
+        assert!(file("ok", &json_type).check_content().is_ok());
+        assert!(file("ok", &xml_type).check_content().is_ok());
+        for t in [&json_type, &xml_type] {
+            assert_eq!(code(&file("empty", t)), FileContentError::EMPTY_CODE);
+            assert_eq!(
+                code(&file("truncated", t)),
+                FileContentError::TRUNCATED_CODE
+            );
+        }
+        let e = file("truncated", &json_type).get_data().unwrap_err();
+        assert!(e.downcast_ref::<FileContentError>().is_some());
+        assert!(e.to_string().contains("does not end with '}'"));
+    }
+
+    #[test]
+    fn test_read_mode() {
+        let json_type = VerifierDataType::Setup(VerifierSetupDataType::ElectionEventContextPayload);
+        let f = File::new(&get_location(), &json_type, None);
+        assert_eq!(f.read_mode(), FileReadMode::Memory);
+        let xml_type = VerifierDataType::Tally(VerifierTallyDataType::ECH0222);
+        let f = File::new(&test_dataset_tally_path().join("tally"), &xml_type, None);
+        assert_eq!(f.read_mode(), FileReadMode::Streaming);
+        let threshold = Config::file_streaming_threshold();
+        assert_eq!(
+            FileReadMode::from_file_size(&FileType::Json, threshold),
+            FileReadMode::Memory
+        );
+        assert_eq!(
+            FileReadMode::from_file_size(&FileType::Json, threshold + 1),
+            FileReadMode::Streaming
+        );
+        let f = File::new(&get_location(), &json_type, None);
+        assert!(ElectionEventContextPayload::from_file(
+            &f,
+            &FileType::Json,
+            &FileReadMode::Streaming
+        )
+        .is_ok());
+    }
 }
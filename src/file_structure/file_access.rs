@@ -0,0 +1,91 @@
+//! Module recording the accesses of the verifications to the data files
+//!
+//! The verification running in the current thread is set with [with_recording]. Each file read
+//! through [super::file::File] is then registered for this verification. The accesses can be
+//! collected with [take_file_accesses], in order to report which verifications touched a file.
+//!
+//! The reads done in other threads than the thread of the verification (e.g. in a parallel
+//! iterator of the verification) are not attributed to the verification.
+
+use lazy_static::lazy_static;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+/// Ids of the verifications having accessed each file
+pub type FileAccesses = BTreeMap<PathBuf, BTreeSet<String>>;
+
+lazy_static! {
+    static ref FILE_ACCESSES: Mutex<FileAccesses> = Mutex::new(BTreeMap::new());
+}
+
+thread_local! {
+    static CURRENT_VERIFICATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Run `f` recording the accesses to the files for the verification `id`
+///
+/// The previous verification of the thread is restored at the end, so that the calls can be
+/// nested
+pub fn with_recording<R>(id: &str, f: impl FnOnce() -> R) -> R {
+    let previous = CURRENT_VERIFICATION.with(|c| c.replace(Some(id.to_string())));
+    let res = f();
+    CURRENT_VERIFICATION.with(|c| *c.borrow_mut() = previous);
+    res
+}
+
+/// Register the access to the file for the verification running in the current thread
+///
+/// Nothing is registered if no verification is running in the thread
+pub(super) fn record_file_access(path: &Path) {
+    if let Some(id) = CURRENT_VERIFICATION.with(|c| c.borrow().clone()) {
+        FILE_ACCESSES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(path.to_path_buf())
+            .or_default()
+            .insert(id);
+    }
+}
+
+/// Take the accesses to the files located under `root` registered since the last call
+pub fn take_file_accesses(root: &Path) -> FileAccesses {
+    let mut accesses = FILE_ACCESSES.lock().unwrap_or_else(PoisonError::into_inner);
+    let (res, others) = std::mem::take(&mut *accesses)
+        .into_iter()
+        .partition(|(p, _)| p.starts_with(root));
+    *accesses = others;
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_recording() {
+        let root = Path::new("file_access_test");
+        record_file_access(&root.join("a.json"));
+        with_recording("01.01", || {
+            record_file_access(&root.join("a.json"));
+            with_recording("02.01", || record_file_access(&root.join("b.json")));
+            record_file_access(&root.join("b.json"));
+        });
+        with_recording("03.01", || record_file_access(&root.join("a.json")));
+        record_file_access(&root.join("c.json"));
+        let accesses = take_file_accesses(root);
+        assert_eq!(accesses.len(), 2);
+        assert_eq!(
+            accesses[&root.join("a.json")],
+            BTreeSet::from(["01.01".to_string(), "03.01".to_string()])
+        );
+        assert_eq!(
+            accesses[&root.join("b.json")],
+            BTreeSet::from(["01.01".to_string(), "02.01".to_string()])
+        );
+        assert!(take_file_accesses(root).is_empty());
+    }
+}
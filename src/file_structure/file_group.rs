@@ -1,15 +1,58 @@
 //! Trait implementing group of files with the same structure (in particular for the files from the control components)
-use super::{file::File, GetFileNameTrait};
+//!
+//! The list of the files of a group is taken at the start of each iteration (snapshot). If the
+//! content of the location changed in between (files added or removed, files modified), the
+//! modification is registered and can be collected with [take_concurrent_modifications], in
+//! order to report that the dataset was modified during the verification.
+use super::{
+    data_source::{local_source, DataSource},
+    file::File,
+    GetFileNameTrait,
+};
 use crate::data_structures::VerifierDataType;
+use lazy_static::lazy_static;
+use log::warn;
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fs,
     marker::PhantomData,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::SystemTime,
 };
 
+lazy_static! {
+    static ref CONCURRENT_MODIFICATIONS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+}
+
+/// Take the descriptions of the concurrent modifications detected since the last call
+pub fn take_concurrent_modifications() -> Vec<String> {
+    let mut modifications = CONCURRENT_MODIFICATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let res = modifications.iter().cloned().collect();
+    modifications.clear();
+    res
+}
+
+/// Register a concurrent modification
+fn register_concurrent_modification(description: String) {
+    CONCURRENT_MODIFICATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(description);
+}
+
 /// Trait for the possibility to mock the iteration over filegroup
 pub trait FileGroupIterTrait<T>: Iterator<Item = (usize, T)> {
-    fn current_elt(&self) -> Option<T>;
+    /// Get the element with the given number (e.g. the chunk number or the node id)
+    ///
+    /// The element is read directly, without iterating from the start. The position of the
+    /// iteration is not changed. Return `None` if the number is not in the group.
+    fn get(&self, number: usize) -> Option<T>;
+    fn current_elt(&self) -> Option<T> {
+        self.current_index().and_then(|i| self.get(*i))
+    }
     fn current_pos(&self) -> &usize;
     fn current_index(&self) -> Option<&usize>;
     fn is_over(&self) -> bool {
@@ -20,19 +63,37 @@ pub trait FileGroupIterTrait<T>: Iterator<Item = (usize, T)> {
 /// File Group
 #[derive(Clone)]
 pub struct FileGroup {
+    /// source of the files
+    source: Arc<dyn DataSource>,
     /// location of the file group
     location: PathBuf,
     /// data_type. With the data_type it is possible to find the files in the location
     data_type: VerifierDataType,
     /// The numbers for which the files are defined
     indexes: Vec<usize>,
+    /// The names of the files that do not follow the standard name (e.g. number with leading zeros)
+    nonstandard_names: BTreeMap<usize, String>,
+}
+
+/// State of the files of a group, used to detect the modifications of the files during the run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileGroupState {
+    /// The numbers for which the files are defined
+    numbers: Vec<usize>,
+    /// Latest modification time of the files (`None` if not available, e.g. files in memory)
+    latest_modification: Option<SystemTime>,
 }
 
 /// Iterator for the files in a file group
+///
+/// The iterator works on a snapshot of the list of files taken when the iterator is created.
+/// At the end of the iteration, the state of the files is compared with the snapshot.
 #[derive(Clone)]
 pub struct FileGroupIter<T> {
     pub file_group: FileGroup,
     pos: usize,
+    snapshot: FileGroupState,
+    checked: bool,
     not_used: PhantomData<T>,
 }
 
@@ -44,20 +105,23 @@ where
     type Item = (usize, T);
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.is_over() {
-            true => None,
-            false => {
-                let res = (*self.current_index().unwrap(), self.current_elt().unwrap());
-                self.pos += 1;
-                Some(res)
+        while !self.is_over() {
+            let res = self
+                .current_index()
+                .and_then(|i| self.get(*i).map(|elt| (*i, elt)));
+            self.pos += 1;
+            if res.is_some() {
+                return res;
             }
         }
+        self.check_concurrent_modifications();
+        None
     }
 }
 
 impl FileGroupIterTrait<File> for FileGroupIter<File> {
-    fn current_elt(&self) -> Option<File> {
-        self.current_file()
+    fn get(&self, number: usize) -> Option<File> {
+        self.file_with_number(number)
     }
 
     fn current_pos(&self) -> &usize {
@@ -71,14 +135,57 @@ impl FileGroupIterTrait<File> for FileGroupIter<File> {
 
 impl<T> FileGroupIter<T> {
     /// Create a new [FileGroupIter<T>]
+    ///
+    /// The list of the files is taken from the location (snapshot). A modification since the
+    /// creation of the file group is registered.
     pub fn new(file_group: &FileGroup) -> Self {
+        let mut file_group = file_group.clone();
+        let snapshot = file_group.state();
+        if snapshot.numbers != file_group.indexes {
+            register_concurrent_modification(format!(
+                "The files {} in {:?} changed since the start of the verification: numbers {:?} instead of {:?}",
+                file_group.get_file_name(),
+                file_group.location,
+                snapshot.numbers,
+                file_group.indexes
+            ));
+            file_group.set_numbers();
+        }
         FileGroupIter {
-            file_group: file_group.clone(),
+            file_group,
             pos: 0,
+            snapshot,
+            checked: false,
             not_used: PhantomData,
         }
     }
 
+    /// Compare the state of the files with the snapshot and register the modification
+    ///
+    /// The comparison is done once, at the end of the iteration
+    fn check_concurrent_modifications(&mut self) {
+        if self.checked {
+            return;
+        }
+        self.checked = true;
+        let state = self.file_group.state();
+        if state.numbers.len() != self.snapshot.numbers.len() {
+            register_concurrent_modification(format!(
+                "The number of files {} in {:?} changed during the iteration: {} instead of {}",
+                self.file_group.get_file_name(),
+                self.file_group.location,
+                state.numbers.len(),
+                self.snapshot.numbers.len()
+            ));
+        } else if state != self.snapshot {
+            register_concurrent_modification(format!(
+                "The files {} in {:?} were modified during the iteration",
+                self.file_group.get_file_name(),
+                self.file_group.location
+            ));
+        }
+    }
+
     /// Get the current  of the ireation
     pub fn current_pos_impl(&self) -> &usize {
         &self.pos
@@ -94,13 +201,16 @@ impl<T> FileGroupIter<T> {
 
     /// Get the current file
     pub fn current_file(&self) -> Option<File> {
-        self.current_index_impl().map(|i| {
-            File::new(
-                &self.file_group.location,
-                &self.file_group.data_type,
-                Some(*i),
-            )
-        })
+        self.current_index_impl()
+            .map(|i| self.file_group.get_file_with_number(*i))
+    }
+
+    /// Get the file with the given number, if the number is in the snapshot
+    pub fn file_with_number(&self, number: usize) -> Option<File> {
+        match self.file_group.get_numbers().contains(&number) {
+            true => Some(self.file_group.get_file_with_number(number)),
+            false => None,
+        }
     }
 }
 
@@ -127,11 +237,14 @@ macro_rules! impl_iterator_over_data_payload {
         type $pread = anyhow::Result<Box<$p>>;
         type $preaditer = FileGroupIter<$pread>;
         impl FileGroupIterTrait<$pread> for $preaditer {
-            fn current_elt(&self) -> Option<$pread> {
-                match self.current_file() {
-                    Some(f) => Some(f.get_data().map(|d| Box::new(d.$fct().unwrap().clone()))),
-                    None => None,
-                }
+            fn get(&self, number: usize) -> Option<$pread> {
+                self.file_with_number(number).map(|f| {
+                    f.get_data().and_then(|d| {
+                        d.$fct().cloned().map(Box::new).ok_or_else(|| {
+                            anyhow::anyhow!("The data is not a {}", stringify!($fct))
+                        })
+                    })
+                })
             }
             fn current_pos(&self) -> &usize {
                 self.current_pos_impl()
@@ -156,32 +269,115 @@ pub(super) use add_type_for_file_group_iter_trait;
 
 impl FileGroup {
     /// New [FileGroup]
+    ///
+    /// The files are read on the disk
     pub fn new(location: &Path, data_type: VerifierDataType) -> Self {
+        Self::new_with_source(&local_source(), location, data_type)
+    }
+
+    /// New [FileGroup] whose files are read from the given source
+    ///
+    /// A warning is logged if some file names are not standard (see [FileGroup::nonstandard_names])
+    pub fn new_with_source(
+        source: &Arc<dyn DataSource>,
+        location: &Path,
+        data_type: VerifierDataType,
+    ) -> Self {
         let mut res = Self {
+            source: source.clone(),
             location: location.to_path_buf(),
             data_type,
             indexes: vec![],
+            nonstandard_names: BTreeMap::new(),
         };
-        res.set_numbers();
+        for n in res.set_numbers() {
+            warn!("File {} in {:?} ignored", n, res.location);
+        }
+        if !res.nonstandard_names.is_empty() {
+            warn!(
+                "Nonstandard file names in {:?}: {}",
+                res.location,
+                res.nonstandard_names().join(", ")
+            );
+        }
         res
     }
 
-    fn set_numbers(&mut self) {
-        if self.location_exists() {
-            for e in fs::read_dir(&self.location).unwrap() {
-                let name = e.unwrap().file_name().to_str().unwrap().to_string();
-                let matching = self.data_type.get_raw_file_name();
-                let matching_splitted: Vec<&str> = matching.split("{}").collect();
-                let tmp = name
-                    .replace(matching_splitted[0], "")
-                    .replace(matching_splitted[1], "")
-                    .parse::<usize>();
-                if let Ok(i) = tmp {
-                    self.indexes.push(i)
-                }
+    /// Set the numbers and the nonstandard names, returning the ignored names
+    fn set_numbers(&mut self) -> Vec<String> {
+        let (names, ignored_names) = self.read_file_names();
+        self.indexes = names.keys().cloned().collect();
+        self.nonstandard_names = names
+            .into_iter()
+            .filter(|(i, n)| n != &self.data_type.get_file_name(Some(*i)))
+            .collect();
+        ignored_names
+    }
+
+    /// Parse the number of the file name, if the name matches the file group
+    ///
+    /// The number can have leading zeros (e.g. `controlComponentCodeSharesPayload.01.json`)
+    fn parse_number(&self, name: &str) -> Option<usize> {
+        self.data_type.parse_file_name(name).flatten()
+    }
+
+    /// Read the names of the files from the location, with their number
+    ///
+    /// If many files have the same number (e.g. `1` and `01`), the standard name is preferred,
+    /// else the first name in alphabetical order. The other names are ignored and returned as
+    /// second element of the tuple.
+    fn read_file_names(&self) -> (BTreeMap<usize, String>, Vec<String>) {
+        let mut res: BTreeMap<usize, String> = BTreeMap::new();
+        let mut ignored_names = vec![];
+        if !self.location_exists() {
+            return (res, ignored_names);
+        }
+        for e in self.source.read_dir(&self.location) {
+            let name = match e.file_name() {
+                Some(n) => n.to_string_lossy().to_string(),
+                None => continue,
+            };
+            if let Some(i) = self.parse_number(&name) {
+                let standard = self.data_type.get_file_name(Some(i));
+                let keep_existing = match res.get(&i) {
+                    Some(existing) => {
+                        existing == &standard || (name != standard && existing < &name)
+                    }
+                    None => {
+                        res.insert(i, name);
+                        continue;
+                    }
+                };
+                let (kept, ignored) = match keep_existing {
+                    true => (res[&i].clone(), name),
+                    false => (name.clone(), res.insert(i, name).unwrap_or_default()),
+                };
+                ignored_names.push(format!("{} (same number as {})", ignored, kept));
             }
-            self.indexes.sort()
         }
+        (res, ignored_names)
+    }
+
+    /// Current state of the files in the location
+    pub fn state(&self) -> FileGroupState {
+        let (names, _) = self.read_file_names();
+        let latest_modification = names
+            .values()
+            .filter_map(|n| {
+                fs::metadata(self.location.join(n))
+                    .and_then(|m| m.modified())
+                    .ok()
+            })
+            .max();
+        FileGroupState {
+            numbers: names.keys().cloned().collect(),
+            latest_modification,
+        }
+    }
+
+    /// Names of the files that do not follow the standard name (e.g. number with leading zeros)
+    pub fn nonstandard_names(&self) -> Vec<String> {
+        self.nonstandard_names.values().cloned().collect()
     }
 
     /// Get the location
@@ -203,7 +399,7 @@ impl FileGroup {
 
     /// Test if the location exist
     pub fn location_exists(&self) -> bool {
-        self.location.is_dir()
+        self.source.is_dir(&self.location)
     }
 
     /// Test if the file group has elements, i.e. it exists files
@@ -223,8 +419,15 @@ impl FileGroup {
     }
 
     /// Get the file with the given number
+    ///
+    /// If the name of the file is not standard, the file with the nonstandard name is returned
     pub fn get_file_with_number(&self, number: usize) -> File {
-        File::new(&self.location, &self.data_type, Some(number))
+        match self.nonstandard_names.get(&number) {
+            Some(name) => File::new_with_name(&self.source, &self.location, &self.data_type, name),
+            None => {
+                File::new_with_source(&self.source, &self.location, &self.data_type, Some(number))
+            }
+        }
     }
 
     /// Iterate over the files
@@ -235,7 +438,7 @@ impl FileGroup {
 
 #[cfg(test)]
 mod test {
-    use super::*;
+    use super::{super::memory_fs::MemoryFileSystem, *};
     use crate::config::test::test_dataset_setup_path;
     use crate::data_structures::setup::VerifierSetupDataType;
     use std::path::PathBuf;
@@ -275,6 +478,93 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_iter_get() {
+        let location = get_location();
+        let fg = FileGroup::new(
+            &location,
+            VerifierDataType::Setup(VerifierSetupDataType::ControlComponentPublicKeysPayload),
+        );
+        let mut iter = fg.iter();
+        iter.next();
+        assert_eq!(
+            iter.get(3).unwrap().get_path(),
+            location.join("controlComponentPublicKeysPayload.3.json")
+        );
+        assert!(iter.get(5).is_none());
+        assert_eq!(iter.current_index(), Some(&2));
+    }
+
+    #[test]
+    fn test_leading_zeros() {
+        let mfs = MemoryFileSystem::new(Path::new("file_group_test_leading_zeros"));
+        mfs.add_file("controlComponentCodeSharesPayload.01.json", "{}")
+            .add_file("controlComponentCodeSharesPayload.2.json", "{}")
+            .add_file("controlComponentCodeSharesPayload.002.json", "{}")
+            .add_file("controlComponentCodeSharesPayload.10.json", "{}")
+            .add_file("controlComponentCodeSharesPayload.+3.json", "{}")
+            .add_file("controlComponentCodeSharesPayload..json", "{}");
+        let fg = FileGroup::new_with_source(
+            &mfs.source(),
+            mfs.root(),
+            VerifierDataType::Setup(VerifierSetupDataType::ControlComponentCodeSharesPayload),
+        );
+        assert_eq!(fg.get_numbers(), &[1, 2, 10]);
+        assert_eq!(
+            fg.nonstandard_names(),
+            vec!["controlComponentCodeSharesPayload.01.json".to_string()]
+        );
+        assert_eq!(
+            fg.get_file_with_number(1).get_path(),
+            mfs.root().join("controlComponentCodeSharesPayload.01.json")
+        );
+        assert_eq!(
+            fg.get_file_with_number(2).get_path(),
+            mfs.root().join("controlComponentCodeSharesPayload.2.json")
+        );
+        assert!(fg.iter().all(|(_, f)| f.exists()));
+    }
+
+    #[test]
+    fn test_concurrent_modifications() {
+        let location = std::env::temp_dir().join("rust_verifier_test_file_group_concurrent");
+        let _ = fs::remove_dir_all(&location);
+        fs::create_dir_all(&location).unwrap();
+        for i in 1..=2 {
+            fs::write(
+                location.join(format!("controlComponentPublicKeysPayload.{}.json", i)),
+                "{}",
+            )
+            .unwrap();
+        }
+        let fg = FileGroup::new(
+            &location,
+            VerifierDataType::Setup(VerifierSetupDataType::ControlComponentPublicKeysPayload),
+        );
+        let is_registered = |modifications: &[String]| {
+            modifications
+                .iter()
+                .any(|m| m.contains("rust_verifier_test_file_group_concurrent"))
+        };
+        // No modification
+        assert_eq!(fg.iter().count(), 2);
+        assert!(!is_registered(&take_concurrent_modifications()));
+        // File added during the iteration
+        let mut it = fg.iter();
+        it.next();
+        fs::write(
+            location.join("controlComponentPublicKeysPayload.3.json"),
+            "{}",
+        )
+        .unwrap();
+        assert_eq!(it.count(), 1);
+        assert!(is_registered(&take_concurrent_modifications()));
+        // File added since the creation of the group: the snapshot contains the new file
+        assert_eq!(fg.iter().count(), 3);
+        assert!(is_registered(&take_concurrent_modifications()));
+        fs::remove_dir_all(&location).unwrap();
+    }
+
     #[test]
     fn test_file_group_not_exist() {
         let location = get_location().join("toto");
@@ -325,19 +615,16 @@ pub mod mock {
             type $pread = anyhow::Result<Box<$p>>;
             type $mockpreaditer = MockFileGroupIter<$pread, $preaditer>;
             impl FileGroupIterTrait<$pread> for $mockpreaditer {
-                fn current_elt(&self) -> Option<$pread> {
-                    match self.current_index() {
-                        Some(i) => match self.mocked_data().get(i) {
-                            Some(data) => match data {
-                                Ok(d) => Some(Ok(d.clone().to_owned())),
-                                Err(e) => Some(Err(anyhow!(format!("{}", e)))),
-                            },
-                            None => match self.orig().current_elt().unwrap() {
-                                Ok(d) => Some(Ok((d.clone().to_owned()))),
-                                Err(e) => Some(Err(anyhow!(e))),
-                            },
+                fn get(&self, number: usize) -> Option<$pread> {
+                    match self.mocked_data().get(&number) {
+                        Some(data) => match data {
+                            Ok(d) => Some(Ok(d.clone().to_owned())),
+                            Err(e) => Some(Err(anyhow!(format!("{}", e)))),
+                        },
+                        None => match self.orig().get(number)? {
+                            Ok(d) => Some(Ok((d.clone().to_owned()))),
+                            Err(e) => Some(Err(anyhow!(e))),
                         },
-                        None => None,
                     }
                 }
 
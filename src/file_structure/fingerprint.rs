@@ -0,0 +1,358 @@
+//! Module implementing the fingerprints of the files of a delivery
+//!
+//! The fingerprints are recorded after the verification of a delivery. When a corrected
+//! re-delivery of the setup arrives, the fingerprints of the new delivery are compared with
+//! the recorded ones, in order to report which parts changed and to verify only the changed
+//! VCS directories (partial delivery). In the same way, the ballot boxes arriving incrementally
+//! during the election are detected for the tally (see
+//! [crate::application_runner::IncrementalTallyState]).
+use super::{data_source::DataSource, file::File};
+use crate::config::Config;
+use anyhow::{anyhow, Context};
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Component, Path},
+};
+
+/// Fingerprints (SHA-256) of the files of a delivery
+///
+/// The keys are the paths of the files relative to the root of the dataset, with `/` as separator
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprints(BTreeMap<String, String>);
+
+/// Difference between the fingerprints of two deliveries
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FingerprintsDiff {
+    /// Files present only in the new delivery
+    pub added: Vec<String>,
+    /// Files present only in the recorded delivery
+    pub removed: Vec<String>,
+    /// Files whose content changed
+    pub changed: Vec<String>,
+}
+
+/// Fingerprint of the file in its data source
+pub(crate) fn file_fingerprint(source: &dyn DataSource, path: &Path) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut reader = source
+        .open_reader(path)
+        .with_context(|| format!("Cannot open file {:?} for the fingerprint", path))?;
+    io::copy(&mut reader, &mut hasher)
+        .with_context(|| format!("Cannot read file {:?} for the fingerprint", path))?;
+    Ok(HEXLOWER.encode(&hasher.finalize()))
+}
+
+/// Path relative to the root with `/` as separator
+pub(super) fn relative_path(root: &Path, path: &Path) -> anyhow::Result<String> {
+    let rel = path
+        .strip_prefix(root)
+        .map_err(|e| anyhow!(e).context(format!("{:?} is not in {:?}", path, root)))?;
+    Ok(rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+impl Fingerprints {
+    /// Calculate the fingerprints of the given files
+    ///
+    /// `root` is the root of the dataset (containing the directories setup and tally). The
+    /// files that do not exist are ignored.
+    pub fn compute(root: &Path, files: &[File]) -> anyhow::Result<Self> {
+        let mut res = BTreeMap::new();
+        for f in files.iter().filter(|f| f.exists()) {
+            let path = f.get_path();
+            res.insert(
+                relative_path(root, &path)?,
+                file_fingerprint(f.source().as_ref(), &path)?,
+            );
+        }
+        Ok(Self(res))
+    }
+
+    /// Load the fingerprints from a json file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read the fingerprints in {:?}", path))?;
+        serde_json::from_str(&s)
+            .map_err(|e| anyhow!(e).context(format!("Fingerprints in {:?} not valid", path)))
+    }
+
+    /// Save the fingerprints to a json file
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(p) = path.parent() {
+            fs::create_dir_all(p).with_context(|| format!("Cannot create directory {:?}", p))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Cannot write the fingerprints to {:?}", path))
+    }
+
+    /// Number of files
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Fingerprint of the file with the given relative path
+    pub fn get(&self, relative_path: &str) -> Option<&String> {
+        self.0.get(relative_path)
+    }
+
+    /// Difference between these fingerprints and the recorded fingerprints
+    pub fn diff(&self, recorded: &Self) -> FingerprintsDiff {
+        FingerprintsDiff {
+            added: self
+                .0
+                .keys()
+                .filter(|k| !recorded.0.contains_key(*k))
+                .cloned()
+                .collect(),
+            removed: recorded
+                .0
+                .keys()
+                .filter(|k| !self.0.contains_key(*k))
+                .cloned()
+                .collect(),
+            changed: self
+                .0
+                .iter()
+                .filter(|(k, v)| recorded.0.get(*k).is_some_and(|r| r != *v))
+                .map(|(k, _)| k.clone())
+                .collect(),
+        }
+    }
+}
+
+impl FromIterator<(String, String)> for Fingerprints {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        Self(BTreeMap::from_iter(iter))
+    }
+}
+
+impl FingerprintsDiff {
+    /// All the files that are added, removed or changed
+    pub fn all_files(&self) -> impl Iterator<Item = &String> {
+        self.added
+            .iter()
+            .chain(self.removed.iter())
+            .chain(self.changed.iter())
+    }
+
+    /// No difference
+    pub fn is_empty(&self) -> bool {
+        self.all_files().next().is_none()
+    }
+
+    /// Name of the VCS directories containing at least one difference
+    pub fn changed_vcs_directories(&self) -> Vec<String> {
+        self.all_files()
+            .filter_map(|f| vcs_name(f))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Files outside of the VCS directories that are different
+    ///
+    /// If not empty, the context of the election event changed and all the VCS directories
+    /// are concerned.
+    pub fn changed_context_files(&self) -> Vec<String> {
+        self.all_files()
+            .filter(|f| vcs_name(f).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// Name of the ballot box directories containing at least one difference
+    pub fn changed_bb_directories(&self) -> Vec<String> {
+        self.all_files()
+            .filter_map(|f| bb_name(f))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Files outside of the ballot box directories that are different
+    ///
+    /// If not empty, the setup or the results of the tally changed and all the ballot boxes
+    /// are concerned.
+    pub fn changed_files_outside_ballot_boxes(&self) -> Vec<String> {
+        self.all_files()
+            .filter(|f| bb_name(f).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// Description of the changes for the report
+    pub fn to_strings(&self) -> Vec<String> {
+        self.added
+            .iter()
+            .map(|f| format!("{}: added", f))
+            .chain(self.removed.iter().map(|f| format!("{}: removed", f)))
+            .chain(self.changed.iter().map(|f| format!("{}: changed", f)))
+            .collect()
+    }
+}
+
+/// Name of the VCS directory containing the file with the relative path, if any
+fn vcs_name(relative_path: &str) -> Option<String> {
+    subdirectory_name(
+        relative_path,
+        Config::setup_dir_name(),
+        Config::vcs_dir_name(),
+    )
+}
+
+/// Name of the ballot box directory containing the file with the relative path, if any
+fn bb_name(relative_path: &str) -> Option<String> {
+    subdirectory_name(
+        relative_path,
+        Config::tally_dir_name(),
+        Config::bb_dir_name(),
+    )
+}
+
+/// Name of the subdirectory of `period_dir/parent_dir` containing the file with the relative
+/// path, if any
+fn subdirectory_name(relative_path: &str, period_dir: &str, parent_dir: &str) -> Option<String> {
+    let mut components = Path::new(relative_path).components();
+    let period = Component::Normal(period_dir.as_ref());
+    let parent = Component::Normal(parent_dir.as_ref());
+    if components.next() != Some(period) || components.next() != Some(parent) {
+        return None;
+    }
+    let name = components.next()?;
+    // The file must be within the directory
+    components.next()?;
+    Some(name.as_os_str().to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::memory_fs::MemoryFileSystem, *};
+    use crate::{
+        config::test::test_dataset_setup_path,
+        data_structures::{setup::VerifierSetupDataType, VerifierDataType},
+        file_structure::VerificationDirectory,
+        verification::VerificationPeriod,
+    };
+
+    fn vcs_file(vcs: &str, file: &str) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            Config::setup_dir_name(),
+            Config::vcs_dir_name(),
+            vcs,
+            file
+        )
+    }
+
+    #[test]
+    fn test_compute() {
+        let path = test_dataset_setup_path();
+        let dir = VerificationDirectory::new(&VerificationPeriod::Setup, &path);
+        let fp = Fingerprints::compute(&path, &dir.files()).unwrap();
+        assert_eq!(fp.len(), dir.files().iter().filter(|f| f.exists()).count());
+        assert!(fp
+            .get(&format!(
+                "{}/electionEventContextPayload.json",
+                Config::setup_dir_name()
+            ))
+            .is_some());
+        assert!(fp.diff(&fp).is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let mfs = MemoryFileSystem::new(Path::new("fingerprint_test_diff"));
+        let root = mfs.root().to_path_buf();
+        let files = |names: &[String]| -> Vec<File> {
+            names
+                .iter()
+                .map(|n| {
+                    File::new_with_source(
+                        &mfs.source(),
+                        root.join(n).parent().unwrap(),
+                        &VerifierDataType::Setup(
+                            VerifierSetupDataType::SetupComponentTallyDataPayload,
+                        ),
+                        None,
+                    )
+                })
+                .collect()
+        };
+        let names = vec![
+            vcs_file("vcs_1", "setupComponentTallyDataPayload.json"),
+            vcs_file("vcs_2", "setupComponentTallyDataPayload.json"),
+            vcs_file("vcs_3", "setupComponentTallyDataPayload.json"),
+        ];
+        for n in names.iter() {
+            mfs.add_file(n, "{}");
+        }
+        let recorded = Fingerprints::compute(&root, &files(&names)).unwrap();
+        assert_eq!(recorded.len(), 3);
+        mfs.add_file(&names[1], "{\"a\": 1}");
+        mfs.remove_file(&names[2]);
+        let current = Fingerprints::compute(&root, &files(&names)).unwrap();
+        let diff = current.diff(&recorded);
+        assert_eq!(diff.changed, vec![names[1].clone()]);
+        assert_eq!(diff.removed, vec![names[2].clone()]);
+        assert!(diff.added.is_empty());
+        assert_eq!(
+            diff.changed_vcs_directories(),
+            vec!["vcs_2".to_string(), "vcs_3".to_string()]
+        );
+        assert!(diff.changed_context_files().is_empty());
+        assert_eq!(diff.to_strings().len(), 2);
+    }
+
+    #[test]
+    fn test_bb_name() {
+        let bb_file = format!(
+            "{}/{}/bb_1/a.json",
+            Config::tally_dir_name(),
+            Config::bb_dir_name()
+        );
+        assert_eq!(bb_name(&bb_file), Some("bb_1".to_string()));
+        assert_eq!(vcs_name(&bb_file), None);
+        assert_eq!(bb_name(&vcs_file("toto", "a.json")), None);
+        let diff = FingerprintsDiff {
+            added: vec![bb_file],
+            removed: vec![],
+            changed: vec![vcs_file("toto", "a.json")],
+        };
+        assert_eq!(diff.changed_bb_directories(), vec!["bb_1".to_string()]);
+        assert_eq!(
+            diff.changed_files_outside_ballot_boxes(),
+            vec![vcs_file("toto", "a.json")]
+        );
+    }
+
+    #[test]
+    fn test_vcs_name() {
+        assert_eq!(
+            vcs_name(&vcs_file("toto", "a.json")),
+            Some("toto".to_string())
+        );
+        assert_eq!(
+            vcs_name(&format!("{}/a.json", Config::setup_dir_name())),
+            None
+        );
+        assert_eq!(
+            vcs_name(&format!(
+                "{}/{}/toto",
+                Config::setup_dir_name(),
+                Config::vcs_dir_name()
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Module implementing the manifest of a verification directory
+//!
+//! The manifest lists all the files of the verification directory with their size and their
+//! SHA-256 hash. It allows the auditors to prove which version of the dataset has been verified
+//! (the [Manifest::signing_input] can be signed) and to detect the files of the setup that changed
+//! between the verification of the setup and the verification of the tally.
+use super::{
+    data_source::DataSource,
+    fingerprint::{file_fingerprint, relative_path, Fingerprints, FingerprintsDiff},
+    parse_file_name,
+};
+use crate::{config::Config, verification::VerificationPeriod};
+use anyhow::{anyhow, Context};
+use chrono::Local;
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Entry of the manifest for one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path relative to the root of the verification directory, with `/` as separator
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// SHA-256 hash (hexadecimal, lower case)
+    pub sha256: String,
+}
+
+/// Manifest of the files of a verification directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Version of the verifier creating the manifest
+    pub verifier_version: String,
+    /// Period of the verification (setup or tally)
+    pub period: String,
+    /// Date and time of the creation (local time)
+    pub created: String,
+    /// Entries sorted by path
+    pub files: Vec<ManifestEntry>,
+    /// SHA-256 hash of the [Manifest::signing_input]
+    pub digest: String,
+}
+
+/// All the files in the directory and its subdirectories, read from the source
+fn collect_files(source: &dyn DataSource, dir: &Path, res: &mut Vec<PathBuf>) {
+    for p in source.read_dir(dir) {
+        if source.is_dir(&p) {
+            collect_files(source, &p, res)
+        } else {
+            res.push(p)
+        }
+    }
+}
+
+impl Manifest {
+    /// Calculate the manifest of all the files in the verification directory `root`, read from
+    /// the source
+    pub fn compute(
+        source: &dyn DataSource,
+        root: &Path,
+        period: &VerificationPeriod,
+    ) -> anyhow::Result<Self> {
+        let mut paths = vec![];
+        collect_files(source, root, &mut paths);
+        let mut files = paths
+            .iter()
+            .map(|p| {
+                Ok(ManifestEntry {
+                    path: relative_path(root, p)?,
+                    size: source
+                        .file_len(p)
+                        .with_context(|| format!("Cannot read the size of {:?}", p))?,
+                    sha256: file_fingerprint(source, p)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut res = Self {
+            verifier_version: env!("CARGO_PKG_VERSION").to_string(),
+            period: period.to_string(),
+            created: Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+            files,
+            digest: String::new(),
+        };
+        res.digest = res.calculate_digest();
+        Ok(res)
+    }
+
+    /// Canonical representation of the entries, to be signed
+    ///
+    /// One line per file `<sha256>  <path>`, sorted by path (same format as `sha256sum`)
+    pub fn signing_input(&self) -> String {
+        self.files
+            .iter()
+            .map(|e| format!("{}  {}\n", e.sha256, e.path))
+            .collect()
+    }
+
+    fn calculate_digest(&self) -> String {
+        HEXLOWER.encode(&Sha256::digest(self.signing_input().as_bytes()))
+    }
+
+    /// Check that the digest corresponds to the entries (the manifest is not altered)
+    pub fn is_digest_valid(&self) -> bool {
+        self.digest == self.calculate_digest()
+    }
+
+    /// Number of files
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The hashes of the entries as [Fingerprints]
+    pub fn fingerprints(&self) -> Fingerprints {
+        Fingerprints::from_iter(
+            self.files
+                .iter()
+                .map(|e| (e.path.clone(), e.sha256.clone())),
+        )
+    }
+
+    /// Difference between the files of this manifest and the files of the recorded manifest
+    pub fn diff(&self, recorded: &Self) -> FingerprintsDiff {
+        self.fingerprints().diff(&recorded.fingerprints())
+    }
+
+    /// Difference limited to the files of the setup directory
+    ///
+    /// Used to detect the files of the setup that changed between the verification of the setup
+    /// and the verification of the tally
+    pub fn setup_diff(&self, recorded: &Self) -> FingerprintsDiff {
+        let prefix = format!("{}/", Config::setup_dir_name());
+        let setup_only = |m: &Self| {
+            Fingerprints::from_iter(
+                m.files
+                    .iter()
+                    .filter(|e| e.path.starts_with(&prefix))
+                    .map(|e| (e.path.clone(), e.sha256.clone())),
+            )
+        };
+        setup_only(self).diff(&setup_only(recorded))
+    }
+
+    /// Files of the directories setup and tally whose name is not the name of a known data type
+    ///
+    /// The files are ignored by the verifications. The files outside these directories (e.g. the
+    /// archives of the dataset) are not considered
+    pub fn unknown_files(&self) -> Vec<String> {
+        let prefixes = [
+            format!("{}/", Config::setup_dir_name()),
+            format!("{}/", Config::tally_dir_name()),
+        ];
+        self.files
+            .iter()
+            .filter(|e| prefixes.iter().any(|p| e.path.starts_with(p)))
+            .filter(|e| {
+                let name = e.path.rsplit('/').next().unwrap_or_default();
+                parse_file_name(name).is_none()
+            })
+            .map(|e| e.path.clone())
+            .collect()
+    }
+
+    /// Load the manifest from a json file
+    ///
+    /// Return an error if the digest does not correspond to the entries
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read the manifest in {:?}", path))?;
+        let res: Self = serde_json::from_str(&s)
+            .map_err(|e| anyhow!(e).context(format!("Manifest in {:?} not valid", path)))?;
+        if !res.is_digest_valid() {
+            return Err(anyhow!(
+                "The digest of the manifest in {:?} does not correspond to the files",
+                path
+            ));
+        }
+        Ok(res)
+    }
+
+    /// Save the manifest to a json file
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(p) = path.parent() {
+            fs::create_dir_all(p).with_context(|| format!("Cannot create directory {:?}", p))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Cannot write the manifest to {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::{data_source::LocalDataSource, memory_fs::MemoryFileSystem},
+        *,
+    };
+    use crate::config::test::test_dataset_tally_path;
+
+    #[test]
+    fn test_compute() {
+        let mfs = MemoryFileSystem::new(Path::new("manifest_test_compute"));
+        let setup_file = format!("{}/a.json", Config::setup_dir_name());
+        mfs.add_file(&setup_file, "{}")
+            .add_file(&format!("{}/b/c.xml", Config::tally_dir_name()), "<a/>");
+        let m = Manifest::compute(
+            mfs.source().as_ref(),
+            mfs.root(),
+            &VerificationPeriod::Tally,
+        )
+        .unwrap();
+        assert_eq!(m.len(), 2);
+        assert_eq!(m.files[0].path, setup_file);
+        assert_eq!(m.files[0].size, 2);
+        assert_eq!(
+            m.files[0].sha256,
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+        assert!(m
+            .signing_input()
+            .starts_with(&format!("{}  {}\n", m.files[0].sha256, setup_file)));
+        assert!(m.is_digest_valid());
+        let mut altered = m.clone();
+        altered.files[1].sha256 = m.files[0].sha256.clone();
+        assert!(!altered.is_digest_valid());
+    }
+
+    #[test]
+    fn test_setup_diff() {
+        let mfs = MemoryFileSystem::new(Path::new("manifest_test_setup_diff"));
+        let setup_file = format!("{}/a.json", Config::setup_dir_name());
+        mfs.add_file(&setup_file, "{}");
+        let recorded = Manifest::compute(
+            mfs.source().as_ref(),
+            mfs.root(),
+            &VerificationPeriod::Setup,
+        )
+        .unwrap();
+        mfs.add_file(&format!("{}/b.json", Config::tally_dir_name()), "{}");
+        let current = Manifest::compute(
+            mfs.source().as_ref(),
+            mfs.root(),
+            &VerificationPeriod::Tally,
+        )
+        .unwrap();
+        assert_eq!(current.diff(&recorded).added.len(), 1);
+        assert!(current.setup_diff(&recorded).is_empty());
+        mfs.add_file(&setup_file, "[]");
+        let current = Manifest::compute(
+            mfs.source().as_ref(),
+            mfs.root(),
+            &VerificationPeriod::Tally,
+        )
+        .unwrap();
+        assert_eq!(current.setup_diff(&recorded).changed, vec![setup_file]);
+    }
+
+    #[test]
+    fn test_unknown_files() {
+        let mfs = MemoryFileSystem::new(Path::new("manifest_test_unknown_files"));
+        let unknown = format!("{}/b/notes.txt", Config::tally_dir_name());
+        mfs.add_file(
+            &format!(
+                "{}/electionEventContextPayload.json",
+                Config::setup_dir_name()
+            ),
+            "{}",
+        )
+        .add_file(
+            &format!(
+                "{}/b/controlComponentBallotBoxPayload_01.json",
+                Config::tally_dir_name()
+            ),
+            "{}",
+        )
+        .add_file(&unknown, "")
+        .add_file("dataset.zip", "");
+        let m = Manifest::compute(
+            mfs.source().as_ref(),
+            mfs.root(),
+            &VerificationPeriod::Tally,
+        )
+        .unwrap();
+        assert_eq!(m.unknown_files(), vec![unknown]);
+    }
+
+    #[test]
+    fn test_dataset() {
+        let m = Manifest::compute(
+            &LocalDataSource,
+            &test_dataset_tally_path(),
+            &VerificationPeriod::Tally,
+        )
+        .unwrap();
+        assert!(!m.is_empty());
+        assert!(m.files.iter().any(|e| e
+            .path
+            .starts_with(&format!("{}/", Config::tally_dir_name()))));
+        assert!(m.files.windows(2).all(|w| w[0].path < w[1].path));
+        assert!(m.unknown_files().is_empty());
+    }
+}
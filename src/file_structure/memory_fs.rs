@@ -0,0 +1,256 @@
+//! Module implementing an in-memory file system
+//!
+//! The files registered in a [MemoryFileSystem] are seen by [super::file::File],
+//! [super::file_group::FileGroup] and the directories created with its source
+//! ([MemoryFileSystem::source]) as if they were on the disk. It allows to construct small
+//! synthetic datasets programmatically in the unit tests, without touching the disk.
+//!
+//! The module is only available for the tests (feature `test-tools`). The datasets delivered as
+//! ZIP archive are read with their own source (see [super::zip_archive]).
+use super::data_source::{DataSource, FileReader};
+use anyhow::anyhow;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug},
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
+};
+
+/// Files in memory, with their complete path
+#[derive(Default)]
+struct MemoryDataSource {
+    files: RwLock<BTreeMap<PathBuf, String>>,
+}
+
+impl Debug for MemoryDataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryDataSource")
+            .field(
+                "files",
+                &self
+                    .files
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .len(),
+            )
+            .finish()
+    }
+}
+
+/// Handle to a set of files in memory located under a root path
+///
+/// The files are only visible through the source of the handle, so that many memory file
+/// systems can use the same root.
+pub struct MemoryFileSystem {
+    root: PathBuf,
+    source: Arc<MemoryDataSource>,
+}
+
+impl MemoryFileSystem {
+    /// New empty memory file system under root
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            source: Arc::new(MemoryDataSource::default()),
+        }
+    }
+
+    /// Root of the memory file system
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Source to give to the directories, in order to read the files in memory
+    pub fn source(&self) -> Arc<dyn DataSource> {
+        self.source.clone()
+    }
+
+    /// Add or replace a file with the given path relative to the root
+    pub fn add_file(&self, relative_path: &str, content: &str) -> &Self {
+        self.source
+            .files
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(self.root.join(relative_path), content.to_string());
+        self
+    }
+
+    /// Remove the file with the given path relative to the root
+    pub fn remove_file(&self, relative_path: &str) -> &Self {
+        self.source
+            .files
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.root.join(relative_path));
+        self
+    }
+
+    /// Paths of all the files in the memory file system
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.source
+            .files
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Remove all the files
+    pub fn clear(&self) {
+        self.source
+            .files
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+impl MemoryDataSource {
+    fn content(&self, path: &Path) -> anyhow::Result<String> {
+        self.files
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(path)
+            .cloned()
+            .ok_or_else(|| anyhow!("File {:?} not found in memory", path))
+    }
+}
+
+impl DataSource for MemoryDataSource {
+    fn file_exists(&self, path: &Path) -> bool {
+        self.files
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(path)
+    }
+
+    /// A directory exists in memory if it contains at least one file
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .any(|p| p != path && p.starts_with(path))
+    }
+
+    fn file_len(&self, path: &Path) -> anyhow::Result<u64> {
+        self.content(path).map(|s| s.len() as u64)
+    }
+
+    fn open_reader(&self, path: &Path) -> anyhow::Result<FileReader> {
+        Ok(Box::new(Cursor::new(self.content(path)?.into_bytes())))
+    }
+
+    fn read_dir(&self, path: &Path) -> Vec<PathBuf> {
+        self.files
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .filter_map(|p| p.strip_prefix(path).ok())
+            .filter_map(|p| p.components().next())
+            .map(|c| path.join(c))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    fn glob(&self, pattern: &str) -> Vec<PathBuf> {
+        match glob::Pattern::new(pattern) {
+            Ok(p) => self
+                .files
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .keys()
+                .filter(|k| p.matches_path(k))
+                .cloned()
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> anyhow::Result<String> {
+        self.content(path)
+    }
+
+    fn read_range(&self, path: &Path, start: u64, len: usize) -> anyhow::Result<String> {
+        let s = self.content(path)?;
+        let bytes = s.as_bytes();
+        let start = (start as usize).min(bytes.len());
+        let end = start.saturating_add(len).min(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[start..end]).to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_memory_fs() {
+        let mfs = MemoryFileSystem::new(Path::new("memory_fs_test"));
+        mfs.add_file("a/b/toto.json", "{}")
+            .add_file("a/c.1.json", "1")
+            .add_file("a/c.2.json", "2");
+        let root = mfs.root();
+        let source = mfs.source();
+        assert!(source.file_exists(&root.join("a/b/toto.json")));
+        assert!(!source.file_exists(&root.join("a/b/titi.json")));
+        assert!(source.is_dir(&root.join("a")));
+        assert!(source.is_dir(&root.join("a/b")));
+        assert!(!source.is_dir(&root.join("a/c.1.json")));
+        assert_eq!(
+            source.read_to_string(&root.join("a/c.2.json")).unwrap(),
+            "2"
+        );
+        assert!(source.read_to_string(&root.join("a/c.3.json")).is_err());
+        assert_eq!(source.file_len(&root.join("a/b/toto.json")).unwrap(), 2);
+        assert_eq!(
+            source
+                .read_range(&root.join("a/b/toto.json"), 1, 10)
+                .unwrap(),
+            "}"
+        );
+        assert_eq!(
+            source
+                .read_range(&root.join("a/b/toto.json"), 5, 10)
+                .unwrap(),
+            ""
+        );
+        let mut s = String::new();
+        source
+            .open_reader(&root.join("a/b/toto.json"))
+            .unwrap()
+            .read_to_string(&mut s)
+            .unwrap();
+        assert_eq!(s, "{}");
+        assert!(source.open_reader(&root.join("a/b/titi.json")).is_err());
+        assert_eq!(
+            source.read_dir(&root.join("a")),
+            vec![
+                root.join("a/b"),
+                root.join("a/c.1.json"),
+                root.join("a/c.2.json")
+            ]
+        );
+        assert_eq!(
+            source.glob(root.join("a/c.*.json").to_str().unwrap()),
+            vec![root.join("a/c.1.json"), root.join("a/c.2.json")]
+        );
+        mfs.remove_file("a/c.1.json");
+        assert!(!source.file_exists(&root.join("a/c.1.json")));
+        assert_eq!(mfs.files().len(), 2);
+    }
+
+    #[test]
+    fn test_same_root() {
+        let root = Path::new("memory_fs_test_same_root");
+        let mfs1 = MemoryFileSystem::new(root);
+        let mfs2 = MemoryFileSystem::new(root);
+        mfs1.add_file("toto.json", "{}");
+        assert!(mfs1.source().file_exists(&root.join("toto.json")));
+        assert!(!mfs2.source().file_exists(&root.join("toto.json")));
+    }
+}
@@ -1,30 +1,69 @@
 //! Module implementing the structure of files and directories
 //! to collect data for the verifications
 //!
+pub mod data_source;
 pub mod file;
+pub mod file_access;
 pub mod file_group;
+pub mod fingerprint;
+pub mod manifest;
+#[cfg(any(test, feature = "test-tools"))]
+pub mod memory_fs;
+pub mod normalization;
 pub mod setup_directory;
+pub mod statistics;
+#[cfg(any(test, feature = "test-tools"))]
+pub mod synthetic;
 pub mod tally_directory;
+pub mod zip_archive;
 
 use crate::{
+    config::Config,
     data_structures::{
         setup::VerifierSetupDataType, tally::VerifierTallyDataType, VerifierDataType,
     },
     verification::VerificationPeriod,
 };
+use data_source::{local_source, DataSource};
+use file::File;
+use fingerprint::Fingerprints;
+use log::warn;
+use rayon::prelude::*;
 use setup_directory::SetupDirectory;
-use std::path::Path;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tally_directory::TallyDirectory;
+use unicode_normalization::UnicodeNormalization;
 
 use self::{setup_directory::SetupDirectoryTrait, tally_directory::TallyDirectoryTrait};
 
 #[derive(Clone)]
 /// Type represending a VerificationDirectory (subdirectory setup or tally)
 pub struct VerificationDirectory {
+    location: PathBuf,
     setup: SetupDirectory,
     tally: Option<TallyDirectory>,
 }
 
+/// Status of the decoding of a file (see [VerificationDirectory::decode_all])
+#[derive(Debug)]
+pub struct FileDecodeStatus {
+    /// Path of the file
+    pub path: PathBuf,
+    /// The error if the file cannot be decoded (or does not exist)
+    pub error: Option<anyhow::Error>,
+}
+
+impl FileDecodeStatus {
+    /// The file could be decoded
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
 /// Enum to define the type of the file (Json or Xml)
 pub enum FileType {
     Json,
@@ -32,11 +71,116 @@ pub enum FileType {
 }
 
 /// Enum representing the mode to read a fie (Memory or streaming).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileReadMode {
     Memory,
     Streaming,
 }
 
+impl FileReadMode {
+    /// Mode to read a file of the given type and size
+    ///
+    /// The json files are read in memory, unless they are bigger than
+    /// [Config::file_streaming_threshold]. The xml files are always streamed, since the xml
+    /// structures are only decoded from the file.
+    pub fn from_file_size(file_type: &FileType, size: u64) -> Self {
+        match file_type {
+            FileType::Json if size <= Config::file_streaming_threshold() => Self::Memory,
+            FileType::Json => Self::Streaming,
+            FileType::Xml => Self::Streaming,
+        }
+    }
+}
+
+/// Create the structure of each subdirectory of `path` using `new_fn`
+///
+/// The creation of the structures reads the entries of all the subdirectories, what can be slow on
+/// network storage with hundreds of directories. Then the subdirectories are scanned concurrently within a
+/// bounded thread pool (see [Config::directory_scan_threads]). The order of the entries is kept.
+pub(crate) fn scan_subdirectories<T: Send>(
+    source: &dyn DataSource,
+    path: &Path,
+    new_fn: impl Fn(&Path) -> T + Send + Sync,
+) -> Vec<T> {
+    if !source.is_dir(path) {
+        return vec![];
+    }
+    let entries = source.read_dir(path);
+    let scan = || {
+        entries
+            .par_iter()
+            .filter(|e| source.is_dir(e))
+            .map(|e| new_fn(e))
+            .collect()
+    };
+    match rayon::ThreadPoolBuilder::new()
+        .num_threads(Config::directory_scan_threads())
+        .build()
+    {
+        Ok(pool) => pool.install(scan),
+        Err(e) => {
+            warn!(
+                "Cannot create the thread pool to scan {:?}: {}. Run in the global pool",
+                path, e
+            );
+            scan()
+        }
+    }
+}
+
+/// Normalize the name of a directory for the comparison on a case insensitive file system
+///
+/// The name is normalized in the Unicode form NFC and converted in lower case
+pub fn normalize_directory_name(name: &str) -> String {
+    name.nfc().collect::<String>().to_lowercase()
+}
+
+/// Result of the check of the names of the subdirectories (VCS or ballot boxes) against the
+/// referenced ids
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectoryNamesCheck {
+    /// Groups of names that are identical after normalization (see [normalize_directory_name])
+    pub duplicates: Vec<Vec<String>>,
+    /// Names that do not match a referenced id
+    pub unknown: Vec<String>,
+    /// Referenced ids without directory
+    pub missing: Vec<String>,
+}
+
+impl DirectoryNamesCheck {
+    /// Check the names of the directories against the referenced ids
+    ///
+    /// The comparison with the ids is exact, since the reading of the files uses the names
+    /// of the directories as ids.
+    pub fn new(names: &[String], ids: &[String]) -> Self {
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for n in names.iter() {
+            groups
+                .entry(normalize_directory_name(n))
+                .or_default()
+                .push(n.clone());
+        }
+        let names_set: BTreeSet<&String> = names.iter().collect();
+        let ids_set: BTreeSet<&String> = ids.iter().collect();
+        Self {
+            duplicates: groups.into_values().filter(|g| g.len() > 1).collect(),
+            unknown: names_set
+                .difference(&ids_set)
+                .map(|s| s.to_string())
+                .collect(),
+            missing: ids_set
+                .difference(&names_set)
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+
+    /// No duplicate, no unknown and no missing directory
+    pub fn is_ok(&self) -> bool {
+        self.duplicates.is_empty() && self.unknown.is_empty() && self.missing.is_empty()
+    }
+}
+
 /// Trait defining functions to get the filename
 pub trait GetFileNameTrait {
     /// Get the file name as it is defiened
@@ -70,6 +214,85 @@ pub trait GetFileNameTrait {
             None => s,
         }
     }
+
+    /// Reverse of [GetFileNameTrait::get_file_name]: test if the name is a name of the type
+    ///
+    /// # return
+    /// - `None` if the name does not match
+    /// - `Some(Some(n))` if the raw name contains `{}`, `n` being the injected number. The number
+    ///   can have leading zeros (e.g. `new_01`)
+    /// - `Some(None)` else. The raw name can contain the wildcard `*` (e.g. `eCH-0110_*.xml`)
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_verifier::file_structure::GetFileNameTrait;
+    /// struct Test;
+    /// impl GetFileNameTrait for Test {
+    ///     fn get_raw_file_name(&self) -> String {
+    ///         String::from("new_{}")
+    ///     }
+    /// };
+    /// let t = Test {};
+    /// assert_eq!(t.parse_file_name("new_2"), Some(Some(2)));
+    /// assert_eq!(t.parse_file_name("new_02"), Some(Some(2)));
+    /// assert_eq!(t.parse_file_name("new_"), None);
+    /// assert_eq!(t.parse_file_name("old_2"), None);
+    /// ```
+    fn parse_file_name(&self, name: &str) -> Option<Option<usize>> {
+        let raw = self.get_raw_file_name();
+        match raw.split_once("{}") {
+            Some((prefix, suffix)) => {
+                let nb = name.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                if nb.is_empty() || !nb.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                nb.parse::<usize>().ok().map(Some)
+            }
+            None if raw.contains('*') => {
+                glob::Pattern::new(&raw).ok()?.matches(name).then_some(None)
+            }
+            None => (raw == name).then_some(None),
+        }
+    }
+}
+
+/// All the data types of the setup
+const SETUP_DATA_TYPES: [VerifierSetupDataType; 8] = [
+    VerifierSetupDataType::ElectionEventContextPayload,
+    VerifierSetupDataType::SetupComponentPublicKeysPayload,
+    VerifierSetupDataType::ControlComponentPublicKeysPayload,
+    VerifierSetupDataType::SetupComponentVerificationDataPayload,
+    VerifierSetupDataType::ControlComponentCodeSharesPayload,
+    VerifierSetupDataType::SetupComponentTallyDataPayload,
+    VerifierSetupDataType::ElectionEventConfiguration,
+    VerifierSetupDataType::PrimesMappingTablePayload,
+];
+
+/// All the data types of the tally
+const TALLY_DATA_TYPES: [VerifierTallyDataType; 7] = [
+    VerifierTallyDataType::ECH0110,
+    VerifierTallyDataType::EVotingDecrypt,
+    VerifierTallyDataType::ECH0222,
+    VerifierTallyDataType::TallyComponentVotesPayload,
+    VerifierTallyDataType::TallyComponentShufflePayload,
+    VerifierTallyDataType::ControlComponentBallotBoxPayload,
+    VerifierTallyDataType::ControlComponentShufflePayload,
+];
+
+/// All the data types of the setup and of the tally
+fn all_data_types() -> impl Iterator<Item = VerifierDataType> {
+    SETUP_DATA_TYPES
+        .into_iter()
+        .map(VerifierDataType::Setup)
+        .chain(TALLY_DATA_TYPES.into_iter().map(VerifierDataType::Tally))
+}
+
+/// Parse a file name back into its data type and its number (if the data type is a file group)
+///
+/// The function is the reverse of [GetFileNameTrait::get_file_name] over all the data types.
+/// Return `None` if the name is not the name of a known data type
+pub fn parse_file_name(name: &str) -> Option<(VerifierDataType, Option<usize>)> {
+    all_data_types().find_map(|t| t.parse_file_name(name).map(|nb| (t, nb)))
 }
 
 /// Trait to set the necessary functions for the struct [VerificationDirectory] that
@@ -94,32 +317,48 @@ pub trait VerificationDirectoryTrait {
     type SetupDirType: SetupDirectoryTrait;
     type TallyDirType: TallyDirectoryTrait;
 
-    /// Unwrap setup and give a reference to S
+    /// Give a reference to the setup directory
     ///
-    /// panic if type is tally
+    /// The setup directory exists for both periods
     fn unwrap_setup(&self) -> &Self::SetupDirType;
 
-    /// Unwrap tally and give a reference to S
+    /// Give a reference to the tally directory
     ///
-    /// panic if type is seup
-    fn unwrap_tally(&self) -> &Self::TallyDirType;
+    /// `None` if the period is setup
+    fn tally(&self) -> Option<&Self::TallyDirType>;
 }
 
 impl VerificationDirectory {
-    /// Create a new VerificationDirectory
+    /// Create a new VerificationDirectory on the disk
     pub fn new(period: &VerificationPeriod, location: &Path) -> Self {
+        Self::new_with_source(period, location, local_source())
+    }
+
+    /// Create a new VerificationDirectory whose files are read from the given source
+    pub fn new_with_source(
+        period: &VerificationPeriod,
+        location: &Path,
+        source: Arc<dyn DataSource>,
+    ) -> Self {
         match period {
             VerificationPeriod::Setup => VerificationDirectory {
-                setup: SetupDirectory::new(location),
+                location: location.to_path_buf(),
+                setup: SetupDirectory::new_with_source(location, source),
                 tally: None,
             },
             VerificationPeriod::Tally => VerificationDirectory {
-                setup: SetupDirectory::new(location),
-                tally: Some(TallyDirectory::new(location)),
+                location: location.to_path_buf(),
+                setup: SetupDirectory::new_with_source(location, source.clone()),
+                tally: Some(TallyDirectory::new_with_source(location, source)),
             },
         }
     }
 
+    /// Source from which the files are read
+    pub fn source(&self) -> &Arc<dyn DataSource> {
+        self.setup.source()
+    }
+
     /// Is setup
     #[allow(dead_code)]
     pub fn is_setup(&self) -> bool {
@@ -127,31 +366,76 @@ impl VerificationDirectory {
     }
 
     /// Is tally
-    #[allow(dead_code)]
     pub fn is_tally(&self) -> bool {
         !self.is_setup()
     }
+
+    /// Period of the directory
+    pub fn period(&self) -> VerificationPeriod {
+        match self.is_setup() {
+            true => VerificationPeriod::Setup,
+            false => VerificationPeriod::Tally,
+        }
+    }
+
+    /// All the files expected in the directory, according to the period
+    pub fn files(&self) -> Vec<File> {
+        let mut res = self.setup.files();
+        if let Some(t) = &self.tally {
+            res.append(&mut t.files());
+        }
+        res
+    }
+
+    /// Fingerprints of all the files of the directory
+    pub fn fingerprints(&self) -> anyhow::Result<Fingerprints> {
+        Fingerprints::compute(&self.location, &self.files())
+    }
+
+    /// Keep only the VCS directories with the given names
+    ///
+    /// Used to verify a partial delivery, where only some VCS directories changed
+    pub fn restrict_vcs_directories(&mut self, names: &[String]) {
+        self.setup.restrict_vcs_directories(names)
+    }
+
+    /// Keep only the ballot box directories with the given names. Nothing happens for the setup
+    ///
+    /// Used to verify only the ballot boxes arrived since the last run of the tally
+    pub fn restrict_bb_directories(&mut self, names: &[String]) {
+        if let Some(t) = self.tally.as_mut() {
+            t.restrict_bb_directories(names)
+        }
+    }
+
+    /// Decode all the files of the directory without verifying them
+    ///
+    /// The files are read according to their read mode (streaming where applicable).
+    /// The function gives a fast structural check of the dataset before running the verifications.
+    ///
+    /// # Return
+    /// The status of decoding for each file
+    pub fn decode_all(&self) -> Vec<FileDecodeStatus> {
+        self.files()
+            .par_iter()
+            .map(|f| FileDecodeStatus {
+                path: f.get_path(),
+                error: f.get_data().err(),
+            })
+            .collect()
+    }
 }
 
 impl VerificationDirectoryTrait for VerificationDirectory {
     type SetupDirType = SetupDirectory;
     type TallyDirType = TallyDirectory;
 
-    /// Unwrap setup and give a reference to S
-    ///
-    /// panic if type is tally
     fn unwrap_setup(&self) -> &SetupDirectory {
         &self.setup
     }
 
-    /// Unwrap tally and give a reference to S
-    ///
-    /// panic if type is seup
-    fn unwrap_tally(&self) -> &TallyDirectory {
-        match &self.tally {
-            Some(t) => t,
-            None => panic!("called `unwrap_tally()` on a `Setup` value"),
-        }
+    fn tally(&self) -> Option<&TallyDirectory> {
+        self.tally.as_ref()
     }
 }
 
@@ -167,6 +451,7 @@ impl GetFileNameTrait for VerifierSetupDataType {
             Self::ControlComponentCodeSharesPayload => "controlComponentCodeSharesPayload.{}.json",
             Self::SetupComponentTallyDataPayload => "setupComponentTallyDataPayload.json",
             Self::ElectionEventConfiguration => "configuration-anonymized.xml",
+            Self::PrimesMappingTablePayload => "primesMappingTablePayload.json",
         };
         s.to_string()
     }
@@ -198,8 +483,91 @@ impl GetFileNameTrait for VerifierDataType {
 
 #[cfg(test)]
 mod test {
-    use super::*;
-    use crate::config::test::{test_dataset_setup_path, test_dataset_tally_path};
+    use super::{data_source::LocalDataSource, *};
+    use crate::config::test::{
+        get_test_verifier_setup_dir, get_test_verifier_tally_dir, test_dataset_setup_path,
+        test_dataset_tally_path,
+    };
+
+    #[test]
+    fn test_scan_subdirectories() {
+        let path = test_dataset_setup_path()
+            .join(Config::setup_dir_name())
+            .join(Config::vcs_dir_name());
+        let source = local_source();
+        let expected: Vec<PathBuf> = source
+            .read_dir(&path)
+            .into_iter()
+            .filter(|p| p.is_dir())
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(
+            scan_subdirectories(source.as_ref(), &path, |p| p.to_path_buf()),
+            expected
+        );
+        assert!(
+            scan_subdirectories(source.as_ref(), &path.join("toto"), |p| p.to_path_buf())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_normalize_directory_name() {
+        assert_eq!(normalize_directory_name("ABCdef"), "abcdef");
+        // Decomposed and composed forms of "é"
+        assert_eq!(
+            normalize_directory_name("Re\u{301}sultat"),
+            normalize_directory_name("R\u{e9}sultat")
+        );
+    }
+
+    #[test]
+    fn test_directory_names_check() {
+        let ids = vec!["ABC".to_string(), "def".to_string(), "ghi".to_string()];
+        let check = DirectoryNamesCheck::new(&ids, &ids);
+        assert!(check.is_ok());
+        let check = DirectoryNamesCheck::new(
+            &["ABC".to_string(), "abc".to_string(), "def".to_string()],
+            &ids,
+        );
+        assert!(!check.is_ok());
+        assert_eq!(
+            check.duplicates,
+            vec![vec!["ABC".to_string(), "abc".to_string()]]
+        );
+        assert_eq!(check.unknown, vec!["abc".to_string()]);
+        assert_eq!(check.missing, vec!["ghi".to_string()]);
+    }
+
+    #[test]
+    fn test_decode_all_setup() {
+        let status = get_test_verifier_setup_dir().decode_all();
+        assert!(!status.is_empty());
+        for s in status.iter() {
+            assert!(s.is_ok(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_decode_all_tally() {
+        let dir = get_test_verifier_tally_dir();
+        let status = dir.decode_all();
+        assert_eq!(status.len(), dir.files().len());
+        for s in status.iter() {
+            assert!(s.is_ok(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_decode_all_missing() {
+        let dir = VerificationDirectory::new(
+            &VerificationPeriod::Setup,
+            &test_dataset_setup_path().join("toto"),
+        );
+        let status = dir.decode_all();
+        assert_eq!(status.len(), 3);
+        assert!(status.iter().all(|s| !s.is_ok()));
+    }
 
     #[test]
     fn test_setup_files_exist() {
@@ -286,6 +654,110 @@ mod test {
             )
             .exists());
     }
+
+    /// Numbers used in the property tests: small numbers, powers of 10 and their neighbours,
+    /// and the extremes
+    fn test_numbers() -> Vec<usize> {
+        let mut res: Vec<usize> = (0..100).collect();
+        let mut p = 10usize;
+        while let Some(next) = p.checked_mul(10) {
+            res.extend([p - 1, p, p + 1]);
+            p = next;
+        }
+        res.extend([usize::MAX - 1, usize::MAX]);
+        res
+    }
+
+    #[test]
+    fn test_parse_file_name_roundtrip() {
+        for t in all_data_types() {
+            let raw = t.get_raw_file_name();
+            if raw.contains("{}") {
+                for n in test_numbers() {
+                    let name = t.get_file_name(Some(n));
+                    assert_eq!(
+                        parse_file_name(&name),
+                        Some((t.clone(), Some(n))),
+                        "{}",
+                        name
+                    );
+                    let zeros = raw.replace("{}", &format!("00{}", n));
+                    assert_eq!(
+                        parse_file_name(&zeros),
+                        Some((t.clone(), Some(n))),
+                        "{}",
+                        zeros
+                    );
+                }
+            } else {
+                let name = raw.replace('*', "Post_E2E_DEV");
+                assert_eq!(parse_file_name(&name), Some((t.clone(), None)), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_file_name_unique() {
+        // Each name is matched by exactly one data type
+        for t in all_data_types() {
+            let name = t.get_file_name(Some(3)).replace('*', "x");
+            assert_eq!(
+                all_data_types()
+                    .filter(|t2| t2.parse_file_name(&name).is_some())
+                    .count(),
+                1,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_file_name_not_matching() {
+        for t in all_data_types() {
+            let raw = t.get_raw_file_name();
+            let mut names = vec![
+                format!("x{}", raw),
+                format!("{}x", raw),
+                raw.to_uppercase(),
+                raw.replace(".json", ".xml").replace(".xml", ".txt"),
+            ];
+            if raw.contains("{}") {
+                names.extend(
+                    ["", "+1", "-1", "1a", " 1", "1.5", "99999999999999999999999"]
+                        .iter()
+                        .map(|nb| raw.replace("{}", nb)),
+                );
+            }
+            for name in names {
+                assert!(parse_file_name(&name).is_none(), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_file_name_dataset() {
+        let mut paths = vec![];
+        for root in [test_dataset_setup_path(), test_dataset_tally_path()] {
+            for d in [Config::setup_dir_name(), Config::tally_dir_name()] {
+                collect_dataset_files(&root.join(d), &mut paths);
+            }
+        }
+        assert!(!paths.is_empty());
+        for p in paths {
+            let name = p.file_name().unwrap().to_str().unwrap();
+            assert!(parse_file_name(name).is_some(), "{}", name);
+        }
+    }
+
+    fn collect_dataset_files(dir: &Path, res: &mut Vec<PathBuf>) {
+        for p in LocalDataSource.read_dir(dir) {
+            match LocalDataSource.is_dir(&p) {
+                true => collect_dataset_files(&p, res),
+                false => res.push(p),
+            }
+        }
+    }
 }
 
 #[cfg(any(test, doc))]
@@ -308,7 +780,7 @@ pub mod mock {
     //!        .unwrap_setup_mut()
     //!        .mock_election_event_context_payload(&Ok(&eec));
     //!    // Test the verification that should generate failures
-    //!    fn_verification(&mock_dir, &mut result);
+    //!    let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
     //! ```
     use super::{
         setup_directory::mock::MockSetupDirectory, tally_directory::mock::MockTallyDirectory, *,
@@ -327,11 +799,8 @@ pub mod mock {
             &self.setup
         }
 
-        fn unwrap_tally(&self) -> &MockTallyDirectory {
-            match &self.tally {
-                Some(t) => t,
-                None => panic!("called `unwrap_tally()` on a `Setup` value"),
-            }
+        fn tally(&self) -> Option<&MockTallyDirectory> {
+            self.tally.as_ref()
         }
     }
 
@@ -359,7 +828,7 @@ pub mod mock {
         pub fn unwrap_tally_mut(&mut self) -> &mut MockTallyDirectory {
             match &mut self.tally {
                 Some(t) => t,
-                None => panic!("called `unwrap_tally()` on a `Setup` value"),
+                None => panic!("called `unwrap_tally_mut()` on a `Setup` value"),
             }
         }
     }
@@ -0,0 +1,512 @@
+//! Module implementing the normalization of the layout of a dataset
+//!
+//! The deliveries do not always have the canonical layout: the directories setup and tally can be
+//! within one or more top directories (e.g. the name of the delivery), and the names of the
+//! directories and of the files can have another casing (e.g.
+//! `Setup/ElectionEventContextPayload.JSON`). The verifications would then fail with "file does
+//! not exist".
+//!
+//! [NormalizedDataset::open] detects these variants and creates a [MappedDataSource]
+//! ([NormalizedDataset::source]) presenting the files under a virtual root in the canonical layout.
+//! The files are neither copied nor renamed. The files of setup and tally that are not files of
+//! a known data type are reported in the [LayoutReport], instead of being silently ignored.
+//!
+//! The names of the directories of the verification card sets and of the ballot boxes (ids) are
+//! not changed.
+use super::{
+    all_data_types,
+    data_source::{DataSource, FileReader},
+    fingerprint::relative_path,
+    GetFileNameTrait,
+};
+use crate::config::Config;
+use log::{info, warn};
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Maximal number of top directories around the directories setup and tally
+const MAX_NESTING_DEPTH: usize = 3;
+
+/// Name of the virtual root of the normalized dataset, under the input directory
+const NORMALIZED_DIR_NAME: &str = ".normalized";
+
+/// Report of the normalization of the layout
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayoutReport {
+    /// Directory containing the directories setup and tally, relative to the input, with `/`
+    /// as separator. Empty if it is the input, `None` if no such directory has been found
+    pub dataset_root: Option<String>,
+    /// Files whose path has been normalized: path relative to the dataset root and
+    /// canonical path
+    pub renamed: Vec<(String, String)>,
+    /// Files of setup and tally whose name is not the name of a known data type (path
+    /// relative to the dataset root). They are ignored by the verifications
+    pub unrecognized_files: Vec<String>,
+    /// Files having the same canonical path as another file (path relative to the dataset
+    /// root). They are ignored, the file with the canonical path having the priority
+    pub duplicates: Vec<String>,
+}
+
+/// Source presenting the files of the dataset in the canonical layout
+///
+/// The keys are the complete paths under the virtual root. The files are read from their actual
+/// path in the source of the input (on the disk or in another source)
+#[derive(Debug)]
+struct MappedDataSource {
+    inner: Arc<dyn DataSource>,
+    files: BTreeMap<PathBuf, PathBuf>,
+}
+
+/// Dataset whose layout has been normalized
+///
+/// The mapping is computed at the opening: the files added later in the input are not visible.
+pub struct NormalizedDataset {
+    input: PathBuf,
+    root: PathBuf,
+    source: Arc<dyn DataSource>,
+    report: LayoutReport,
+}
+
+impl LayoutReport {
+    /// The layout is the canonical layout (nothing to map)
+    pub fn is_canonical(&self) -> bool {
+        self.dataset_root.as_ref().map_or(true, |r| r.is_empty())
+            && self.renamed.is_empty()
+            && self.duplicates.is_empty()
+    }
+
+    /// Log the report: the variants detected as info and the ignored files as warnings
+    pub fn log(&self) {
+        match &self.dataset_root {
+            None => warn!(
+                "No directory {} or {} found in the dataset",
+                Config::setup_dir_name(),
+                Config::tally_dir_name()
+            ),
+            Some(r) if !r.is_empty() => info!("Dataset found in the subdirectory {}", r),
+            Some(_) => (),
+        }
+        for (actual, canonical) in self.renamed.iter() {
+            info!("File {} read as {}", actual, canonical)
+        }
+        for f in self.unrecognized_files.iter() {
+            warn!("Unrecognized file in the dataset (ignored): {}", f)
+        }
+        for f in self.duplicates.iter() {
+            warn!(
+                "Duplicate file in the dataset after normalization (ignored): {}",
+                f
+            )
+        }
+    }
+}
+
+impl NormalizedDataset {
+    /// Detect the layout of the dataset in `input`, read from `source`, and map the files if
+    /// the layout is not canonical
+    ///
+    /// `input` can be a directory on the disk or the root of another source (e.g. a
+    /// [super::zip_archive::ZipDataset])
+    pub fn open(input: &Path, source: Arc<dyn DataSource>) -> Self {
+        let mut report = LayoutReport::default();
+        let dataset_root = match find_dataset_root(source.as_ref(), input, 0) {
+            Some(r) => r,
+            None => {
+                return Self {
+                    input: input.to_path_buf(),
+                    root: input.to_path_buf(),
+                    source,
+                    report,
+                }
+            }
+        };
+        report.dataset_root = Some(relative_path(input, &dataset_root).unwrap_or_default());
+        let root = input.join(NORMALIZED_DIR_NAME);
+        let mut files = BTreeMap::new();
+        map_directory(
+            source.as_ref(),
+            &dataset_root,
+            &dataset_root,
+            "",
+            &mut files,
+            &mut report,
+        );
+        let files = files
+            .into_iter()
+            .map(|(canonical, actual)| {
+                let actual_rel = relative_path(&dataset_root, &actual).unwrap_or_default();
+                if actual_rel != canonical {
+                    report.renamed.push((actual_rel, canonical.clone()));
+                }
+                (root.join(canonical), actual)
+            })
+            .collect();
+        let (root, source): (PathBuf, Arc<dyn DataSource>) = match report.is_canonical() {
+            true => (dataset_root, source),
+            false => (
+                root,
+                Arc::new(MappedDataSource {
+                    inner: source,
+                    files,
+                }),
+            ),
+        };
+        Self {
+            input: input.to_path_buf(),
+            root,
+            source,
+            report,
+        }
+    }
+
+    /// Root of the dataset in the canonical layout (containing the directories setup and tally)
+    ///
+    /// The input itself if the layout is already canonical
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Source from which the files of the dataset are read in the canonical layout
+    ///
+    /// The source of the input itself if the layout is already canonical
+    pub fn source(&self) -> Arc<dyn DataSource> {
+        self.source.clone()
+    }
+
+    /// Path of the input
+    pub fn input_path(&self) -> &Path {
+        &self.input
+    }
+
+    /// Report of the normalization
+    pub fn report(&self) -> &LayoutReport {
+        &self.report
+    }
+}
+
+/// Name of the file or of the directory
+fn entry_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Canonical name of the directory setup or tally, case insensitive
+fn canonical_period_dir_name(name: &str) -> Option<&'static str> {
+    [Config::setup_dir_name(), Config::tally_dir_name()]
+        .into_iter()
+        .find(|n| n.eq_ignore_ascii_case(name))
+}
+
+/// The directory containing the directories setup or tally, searched in the subdirectories
+/// up to [MAX_NESTING_DEPTH]
+fn find_dataset_root(source: &dyn DataSource, dir: &Path, depth: usize) -> Option<PathBuf> {
+    let subdirs = source
+        .read_dir(dir)
+        .into_iter()
+        .filter(|p| source.is_dir(p))
+        .collect::<Vec<_>>();
+    if subdirs
+        .iter()
+        .any(|p| canonical_period_dir_name(&entry_name(p)).is_some())
+    {
+        return Some(dir.to_path_buf());
+    }
+    if depth == MAX_NESTING_DEPTH {
+        return None;
+    }
+    subdirs
+        .iter()
+        .find_map(|p| find_dataset_root(source, p, depth + 1))
+}
+
+/// Canonical name of the subdirectory `name` of the directory with the canonical path `parent`
+///
+/// Only the fixed names of the layout are normalized
+fn canonical_directory_name(parent: &str, name: &str) -> String {
+    let fixed = match parent {
+        "" => canonical_period_dir_name(name),
+        p if p == Config::setup_dir_name() => Some(Config::vcs_dir_name()),
+        p if p == Config::tally_dir_name() => Some(Config::bb_dir_name()),
+        _ => None,
+    };
+    match fixed {
+        Some(n) if n.eq_ignore_ascii_case(name) => n.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Strip the prefix, ignoring the ASCII case
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let (head, tail) = (s.get(..prefix.len())?, s.get(prefix.len()..)?);
+    head.eq_ignore_ascii_case(prefix).then_some(tail)
+}
+
+/// Strip the suffix, ignoring the ASCII case
+fn strip_suffix_ignore_case<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    let start = s.len().checked_sub(suffix.len())?;
+    let (head, tail) = (s.get(..start)?, s.get(start..)?);
+    tail.eq_ignore_ascii_case(suffix).then_some(head)
+}
+
+/// Canonical name of `name` for the raw file name `raw` (see
+/// [GetFileNameTrait::get_raw_file_name]), ignoring the ASCII case
+///
+/// The number and the part matching the wildcard are kept
+fn canonical_name_for_raw(raw: &str, name: &str) -> Option<String> {
+    match raw.split_once("{}").or_else(|| raw.split_once('*')) {
+        Some((prefix, suffix)) => {
+            let middle = strip_suffix_ignore_case(strip_prefix_ignore_case(name, prefix)?, suffix)?;
+            let valid = match raw.contains("{}") {
+                true => !middle.is_empty() && middle.chars().all(|c| c.is_ascii_digit()),
+                false => true,
+            };
+            valid.then(|| format!("{}{}{}", prefix, middle, suffix))
+        }
+        None => raw.eq_ignore_ascii_case(name).then(|| raw.to_string()),
+    }
+}
+
+/// Canonical name of the file, if it is the name of a known data type ignoring the case
+pub fn canonical_file_name(name: &str) -> Option<String> {
+    all_data_types().find_map(|t| canonical_name_for_raw(&t.get_raw_file_name(), name))
+}
+
+/// Map the files of the directory `dir` having the canonical path `canonical` (relative to the
+/// dataset root, with `/` as separator)
+///
+/// The files outside setup and tally keep their name
+fn map_directory(
+    source: &dyn DataSource,
+    dataset_root: &Path,
+    dir: &Path,
+    canonical: &str,
+    files: &mut BTreeMap<String, PathBuf>,
+    report: &mut LayoutReport,
+) {
+    let in_period = canonical
+        .split('/')
+        .next()
+        .is_some_and(|c| canonical_period_dir_name(c).is_some());
+    let join = |name: &str| match canonical.is_empty() {
+        true => name.to_string(),
+        false => format!("{}/{}", canonical, name),
+    };
+    for p in source.read_dir(dir) {
+        let name = entry_name(&p);
+        if source.is_dir(&p) {
+            if canonical.is_empty() && name == NORMALIZED_DIR_NAME {
+                continue;
+            }
+            map_directory(
+                source,
+                dataset_root,
+                &p,
+                &join(&canonical_directory_name(canonical, &name)),
+                files,
+                report,
+            );
+            continue;
+        }
+        let canonical_name = match in_period {
+            true => canonical_file_name(&name).unwrap_or_else(|| {
+                report.unrecognized_files.push(join(&name));
+                name.clone()
+            }),
+            false => name.clone(),
+        };
+        let key = join(&canonical_name);
+        match files.entry(key.clone()) {
+            Entry::Vacant(e) => {
+                e.insert(p);
+            }
+            Entry::Occupied(mut e) => {
+                // The file with the canonical path has the priority
+                let duplicate = match p.ends_with(&key) {
+                    true => e.insert(p),
+                    false => p,
+                };
+                report
+                    .duplicates
+                    .push(relative_path(dataset_root, &duplicate).unwrap_or_default());
+            }
+        }
+    }
+}
+
+impl MappedDataSource {
+    /// Actual path of the file
+    fn actual(&self, path: &Path) -> anyhow::Result<&PathBuf> {
+        self.files
+            .get(path)
+            .ok_or_else(|| anyhow::anyhow!("File {:?} not found in the dataset", path))
+    }
+}
+
+impl DataSource for MappedDataSource {
+    fn file_exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files.keys().any(|p| p != path && p.starts_with(path))
+    }
+
+    fn file_len(&self, path: &Path) -> anyhow::Result<u64> {
+        self.inner.file_len(self.actual(path)?)
+    }
+
+    fn open_reader(&self, path: &Path) -> anyhow::Result<FileReader> {
+        self.inner.open_reader(self.actual(path)?)
+    }
+
+    fn read_dir(&self, path: &Path) -> Vec<PathBuf> {
+        self.files
+            .keys()
+            .filter_map(|p| p.strip_prefix(path).ok())
+            .filter_map(|p| p.components().next())
+            .map(|c| path.join(c))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    fn glob(&self, pattern: &str) -> Vec<PathBuf> {
+        match glob::Pattern::new(pattern) {
+            Ok(p) => self
+                .files
+                .keys()
+                .filter(|k| p.matches_path(k))
+                .cloned()
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> anyhow::Result<String> {
+        self.inner.read_to_string(self.actual(path)?)
+    }
+
+    fn read_range(&self, path: &Path, start: u64, len: usize) -> anyhow::Result<String> {
+        self.inner.read_range(self.actual(path)?, start, len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::{data_source::local_source, memory_fs::MemoryFileSystem},
+        *,
+    };
+    use crate::{
+        config::test::test_dataset_tally_path,
+        file_structure::{
+            setup_directory::SetupDirectoryTrait, VerificationDirectory, VerificationDirectoryTrait,
+        },
+        verification::VerificationPeriod,
+    };
+
+    #[test]
+    fn test_canonical_file_name() {
+        assert_eq!(
+            canonical_file_name("ElectionEventContextPayload.JSON").unwrap(),
+            "electionEventContextPayload.json"
+        );
+        assert_eq!(
+            canonical_file_name("CONTROLCOMPONENTSHUFFLEPAYLOAD_01.json").unwrap(),
+            "controlComponentShufflePayload_01.json"
+        );
+        assert_eq!(
+            canonical_file_name("ech-0110_Post_E2E.XML").unwrap(),
+            "eCH-0110_Post_E2E.xml"
+        );
+        assert!(canonical_file_name("controlComponentShufflePayload_a.json").is_none());
+        assert!(canonical_file_name("notes.txt").is_none());
+    }
+
+    #[test]
+    fn test_canonical_directory_name() {
+        assert_eq!(canonical_directory_name("", "Setup"), "setup");
+        assert_eq!(canonical_directory_name("", "data"), "data");
+        assert_eq!(
+            canonical_directory_name("tally", "Ballot_Boxes"),
+            "ballot_boxes"
+        );
+        assert_eq!(
+            canonical_directory_name("tally/ballot_boxes", "AB12"),
+            "AB12"
+        );
+    }
+
+    #[test]
+    fn test_canonical_layout() {
+        let dataset = NormalizedDataset::open(&test_dataset_tally_path(), local_source());
+        assert!(dataset.report().is_canonical());
+        assert!(dataset.report().unrecognized_files.is_empty());
+        assert_eq!(dataset.root(), test_dataset_tally_path());
+    }
+
+    #[test]
+    fn test_nested_layout() {
+        let mfs = MemoryFileSystem::new(Path::new("normalization_test_nested_layout"));
+        let content = std::fs::read_to_string(
+            test_dataset_tally_path()
+                .join(Config::setup_dir_name())
+                .join("electionEventContextPayload.json"),
+        )
+        .unwrap();
+        mfs.add_file(
+            "delivery/Data/SETUP/ElectionEventContextPayload.json",
+            &content,
+        )
+        .add_file(
+            "delivery/Data/Setup/verification_card_sets/ab12/controlComponentCodeSharesPayload.1.json",
+            "{}",
+        )
+        .add_file("delivery/Data/setup/readme.txt", "")
+        .add_file("delivery/Data/Setup/electionEventContextPayload.json", "{}")
+        .add_file("delivery/hashes.txt", "");
+        let dataset = NormalizedDataset::open(mfs.root(), mfs.source());
+        let report = dataset.report();
+        assert_eq!(report.dataset_root.as_deref(), Some("delivery/Data"));
+        assert!(!report.is_canonical());
+        assert_eq!(
+            report.unrecognized_files,
+            vec!["setup/readme.txt".to_string()]
+        );
+        assert_eq!(
+            report.duplicates,
+            vec!["Setup/electionEventContextPayload.json".to_string()]
+        );
+        assert_eq!(report.renamed.len(), 2);
+        let setup = dataset.root().join(Config::setup_dir_name());
+        let source = dataset.source();
+        assert!(source.is_dir(&setup));
+        assert!(source.file_exists(
+            &setup
+                .join(Config::vcs_dir_name())
+                .join("ab12")
+                .join("controlComponentCodeSharesPayload.1.json")
+        ));
+        let dir = VerificationDirectory::new_with_source(
+            &VerificationPeriod::Setup,
+            dataset.root(),
+            source,
+        );
+        assert!(dir.unwrap_setup().election_event_context_payload().is_ok());
+        // The source of the input is not changed
+        assert!(!mfs.source().is_dir(&setup));
+    }
+
+    #[test]
+    fn test_no_dataset() {
+        let mfs = MemoryFileSystem::new(Path::new("normalization_test_no_dataset"));
+        mfs.add_file("a/b/c/d/setup/electionEventContextPayload.json", "{}");
+        let dataset = NormalizedDataset::open(mfs.root(), mfs.source());
+        assert!(dataset.report().dataset_root.is_none());
+        assert_eq!(dataset.root(), mfs.root());
+    }
+}
@@ -1,11 +1,13 @@
 //! Module to implement the setup directory
 
 use super::{
+    data_source::{local_source, DataSource},
     file::{create_file, File},
     file_group::{
         add_type_for_file_group_iter_trait, impl_iterator_over_data_payload, FileGroup,
         FileGroupIter, FileGroupIterTrait,
     },
+    scan_subdirectories,
 };
 use crate::{
     config::Config,
@@ -16,6 +18,7 @@ use crate::{
             control_component_public_keys_payload::ControlComponentPublicKeysPayload,
             election_event_configuration::ElectionEventConfiguration,
             election_event_context_payload::ElectionEventContextPayload,
+            primes_mapping_table_payload::PrimesMappingTablePayload,
             setup_component_public_keys_payload::SetupComponentPublicKeysPayload,
             setup_component_tally_data_payload::SetupComponentTallyDataPayload,
             setup_component_verification_data_payload::SetupComponentVerificationDataPayload,
@@ -24,18 +27,22 @@ use crate::{
         VerifierDataType, VerifierSetupDataTrait,
     },
 };
+use anyhow::anyhow;
 use std::{
-    fs,
+    collections::BTreeSet,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 /// The setup directoy, containing the files, file groues and subdirectories
 #[derive(Clone)]
 pub struct SetupDirectory {
+    source: Arc<dyn DataSource>,
     location: PathBuf,
     setup_component_public_keys_payload_file: File,
     election_event_context_payload_file: File,
     election_event_configuration_file: File,
+    primes_mapping_table_payload_file: File,
     control_component_public_keys_payload_group: FileGroup,
     vcs_directories: Vec<VCSDirectory>,
 }
@@ -64,6 +71,8 @@ pub trait SetupDirectoryTrait {
     fn setup_component_public_keys_payload_file(&self) -> &File;
     fn election_event_context_payload_file(&self) -> &File;
     fn election_event_configuration_file(&self) -> &File;
+    /// File of the primes mapping table. The file is optional (only in the newer datasets)
+    fn primes_mapping_table_payload_file(&self) -> &File;
     fn control_component_public_keys_payload_group(&self) -> &FileGroup;
     fn vcs_directories(&self) -> &Vec<Self::VCSDirType>;
     fn setup_component_public_keys_payload(
@@ -72,10 +81,20 @@ pub trait SetupDirectoryTrait {
 
     fn election_event_context_payload(&self) -> anyhow::Result<Box<ElectionEventContextPayload>>;
     fn election_event_configuration(&self) -> anyhow::Result<Box<ElectionEventConfiguration>>;
+    fn primes_mapping_table_payload(&self) -> anyhow::Result<Box<PrimesMappingTablePayload>>;
 
     fn control_component_public_keys_payload_iter(
         &self,
     ) -> Self::ControlComponentPublicKeysPayloadAsResultIterType;
+
+    /// The set of node ids found in the file groups of the control components
+    fn node_ids(&self) -> BTreeSet<usize> {
+        self.control_component_public_keys_payload_group()
+            .get_numbers()
+            .iter()
+            .cloned()
+            .collect()
+    }
 }
 
 /// Trait to set the necessary functions for the struct [VCSDirectory] that
@@ -131,50 +150,95 @@ impl_iterator_over_data_payload!(
 );
 
 impl SetupDirectory {
-    /// New [SetupDirectory]
-    #[allow(clippy::redundant_clone)]
+    /// New [SetupDirectory] on the disk
     pub fn new(data_location: &Path) -> Self {
+        Self::new_with_source(data_location, local_source())
+    }
+
+    /// New [SetupDirectory] whose files are read from the given source
+    #[allow(clippy::redundant_clone)]
+    pub fn new_with_source(data_location: &Path, source: Arc<dyn DataSource>) -> Self {
         let location = data_location.join(Config::setup_dir_name());
         let mut res = Self {
+            source: source.clone(),
             location: location.to_path_buf(),
             setup_component_public_keys_payload_file: create_file!(
+                source,
                 location,
                 Setup,
                 VerifierSetupDataType::SetupComponentPublicKeysPayload
             ),
             election_event_context_payload_file: create_file!(
+                source,
                 location,
                 Setup,
                 VerifierSetupDataType::ElectionEventContextPayload
             ),
             election_event_configuration_file: create_file!(
+                source,
                 location,
                 Setup,
                 VerifierSetupDataType::ElectionEventConfiguration
             ),
-            control_component_public_keys_payload_group: FileGroup::new(
+            primes_mapping_table_payload_file: create_file!(
+                source,
+                location,
+                Setup,
+                VerifierSetupDataType::PrimesMappingTablePayload
+            ),
+            control_component_public_keys_payload_group: FileGroup::new_with_source(
+                &source,
                 &location,
                 create_verifier_setup_data_type!(Setup, ControlComponentPublicKeysPayload),
             ),
             vcs_directories: vec![],
         };
-        let vcs_path = location.join(Config::vcs_dir_name());
-        if vcs_path.is_dir() {
-            for re in fs::read_dir(&vcs_path).unwrap() {
-                let e = re.unwrap().path();
-                if e.is_dir() {
-                    res.vcs_directories.push(VCSDirectory::new(&e))
-                }
-            }
-        }
+        res.vcs_directories = scan_subdirectories(
+            source.as_ref(),
+            &location.join(Config::vcs_dir_name()),
+            |p| VCSDirectory::new_with_source(p, &source),
+        );
         res
     }
 
+    /// Source from which the files are read
+    pub fn source(&self) -> &Arc<dyn DataSource> {
+        &self.source
+    }
+
     /// Get location
     #[allow(dead_code)]
     pub fn get_location(&self) -> &Path {
         self.location.as_path()
     }
+
+    /// All the files of the directory, including the files of the file groups
+    /// and of the subdirectories
+    pub fn files(&self) -> Vec<File> {
+        let mut res = vec![
+            self.setup_component_public_keys_payload_file.clone(),
+            self.election_event_context_payload_file.clone(),
+            self.election_event_configuration_file.clone(),
+        ];
+        if self.primes_mapping_table_payload_file.exists() {
+            res.push(self.primes_mapping_table_payload_file.clone());
+        }
+        res.extend(
+            self.control_component_public_keys_payload_group
+                .iter()
+                .map(|(_, f)| f),
+        );
+        for d in self.vcs_directories.iter() {
+            res.append(&mut d.files());
+        }
+        res
+    }
+
+    /// Keep only the VCS directories with the given names
+    pub fn restrict_vcs_directories(&mut self, names: &[String]) {
+        self.vcs_directories
+            .retain(|d| names.contains(&d.get_name()))
+    }
 }
 
 impl SetupDirectoryTrait for SetupDirectory {
@@ -191,6 +255,9 @@ impl SetupDirectoryTrait for SetupDirectory {
     fn election_event_configuration_file(&self) -> &File {
         &self.election_event_configuration_file
     }
+    fn primes_mapping_table_payload_file(&self) -> &File {
+        &self.primes_mapping_table_payload_file
+    }
     fn control_component_public_keys_payload_group(&self) -> &FileGroup {
         &self.control_component_public_keys_payload_group
     }
@@ -204,21 +271,48 @@ impl SetupDirectoryTrait for SetupDirectory {
         self.setup_component_public_keys_payload_file
             .get_data()
             .map_err(|e| e.context("in setup_component_public_keys_payload"))
-            .map(|d| Box::new(d.setup_component_public_keys_payload().unwrap().clone()))
+            .and_then(|d| {
+                d.setup_component_public_keys_payload()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a setup_component_public_keys_payload"))
+            })
     }
 
     fn election_event_context_payload(&self) -> anyhow::Result<Box<ElectionEventContextPayload>> {
         self.election_event_context_payload_file
             .get_data()
             .map_err(|e| e.context("in election_event_context_payload"))
-            .map(|d| Box::new(d.election_event_context_payload().unwrap().clone()))
+            .and_then(|d| {
+                d.election_event_context_payload()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a election_event_context_payload"))
+            })
     }
 
     fn election_event_configuration(&self) -> anyhow::Result<Box<ElectionEventConfiguration>> {
         self.election_event_configuration_file
             .get_data()
             .map_err(|e| e.context("in election_event_configuration"))
-            .map(|d| Box::new(d.election_event_configuration().unwrap().clone()))
+            .and_then(|d| {
+                d.election_event_configuration()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a election_event_configuration"))
+            })
+    }
+
+    fn primes_mapping_table_payload(&self) -> anyhow::Result<Box<PrimesMappingTablePayload>> {
+        self.primes_mapping_table_payload_file
+            .get_data()
+            .map_err(|e| e.context("in primes_mapping_table_payload"))
+            .and_then(|d| {
+                d.primes_mapping_table_payload()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a primes_mapping_table_payload"))
+            })
     }
 
     fn control_component_public_keys_payload_iter(
@@ -229,20 +323,29 @@ impl SetupDirectoryTrait for SetupDirectory {
 }
 
 impl VCSDirectory {
-    /// New [VCSDirectory]
+    /// New [VCSDirectory] on the disk
+    #[allow(dead_code)]
     pub fn new(location: &Path) -> Self {
+        Self::new_with_source(location, &local_source())
+    }
+
+    /// New [VCSDirectory] whose files are read from the given source
+    pub fn new_with_source(location: &Path, source: &Arc<dyn DataSource>) -> Self {
         Self {
             location: location.to_path_buf(),
             setup_component_tally_data_payload_file: create_file!(
+                source,
                 location,
                 Setup,
                 VerifierSetupDataType::SetupComponentTallyDataPayload
             ),
-            setup_component_verification_data_payload_group: FileGroup::new(
+            setup_component_verification_data_payload_group: FileGroup::new_with_source(
+                source,
                 location,
                 create_verifier_setup_data_type!(Setup, SetupComponentVerificationDataPayload),
             ),
-            control_component_code_shares_payload_group: FileGroup::new(
+            control_component_code_shares_payload_group: FileGroup::new_with_source(
+                source,
                 location,
                 create_verifier_setup_data_type!(Setup, ControlComponentCodeSharesPayload),
             ),
@@ -254,6 +357,22 @@ impl VCSDirectory {
     pub fn get_location(&self) -> &Path {
         self.location.as_path()
     }
+
+    /// All the files of the directory, including the files of the file groups
+    pub fn files(&self) -> Vec<File> {
+        let mut res = vec![self.setup_component_tally_data_payload_file.clone()];
+        res.extend(
+            self.setup_component_verification_data_payload_group
+                .iter()
+                .map(|(_, f)| f),
+        );
+        res.extend(
+            self.control_component_code_shares_payload_group
+                .iter()
+                .map(|(_, f)| f),
+        );
+        res
+    }
 }
 
 impl VCSDirectoryTrait for VCSDirectory {
@@ -277,7 +396,12 @@ impl VCSDirectoryTrait for VCSDirectory {
         self.setup_component_tally_data_payload_file
             .get_data()
             .map_err(|e| e.context("in setup_component_tally_data_payload"))
-            .map(|d| Box::new(d.setup_component_tally_data_payload().unwrap().clone()))
+            .and_then(|d| {
+                d.setup_component_tally_data_payload()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a setup_component_tally_data_payload"))
+            })
     }
 
     fn setup_component_verification_data_payload_iter(
@@ -294,16 +418,14 @@ impl VCSDirectoryTrait for VCSDirectory {
     fn get_name(&self) -> String {
         self.location
             .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::*;
+    use super::{super::memory_fs, *};
     use crate::config::test::{
         test_dataset_tally_path as get_location, test_verification_card_set_path,
     };
@@ -321,6 +443,7 @@ mod test {
             assert!(p.is_ok());
             assert_eq!(p.unwrap().control_component_public_keys.node_id, i)
         }
+        assert_eq!(dir.node_ids(), BTreeSet::from([1, 2, 3, 4]));
         let expected = [
             "1B3775CB351C64AC33B754BA3A02AED2",
             "6F00E7676CF3D20E19346C7CBDF62A0A",
@@ -349,6 +472,48 @@ mod test {
             assert!(p.is_ok());
             assert_eq!(p.unwrap().chunk_id, i)
         }
+        let iter = dir.setup_component_verification_data_payload_iter();
+        for i in iter.file_group.get_numbers().iter().rev() {
+            assert_eq!(iter.get(*i).unwrap().unwrap().chunk_id, *i)
+        }
+        assert!(iter.get(10000).is_none());
+    }
+
+    #[test]
+    fn test_setup_dir_in_memory() {
+        let mfs = memory_fs::MemoryFileSystem::new(Path::new("memory_setup_dir"));
+        mfs.add_file("setup/electionEventContextPayload.json", "{}")
+            .add_file("setup/controlComponentPublicKeysPayload.1.json", "{}")
+            .add_file("setup/controlComponentPublicKeysPayload.3.json", "{}")
+            .add_file(
+                "setup/verification_card_sets/vcs_1/setupComponentTallyDataPayload.json",
+                "{}",
+            )
+            .add_file(
+                "setup/verification_card_sets/vcs_2/setupComponentVerificationDataPayload.2.json",
+                "{}",
+            );
+        let dir = SetupDirectory::new_with_source(mfs.root(), mfs.source());
+        assert!(dir.election_event_context_payload_file().exists());
+        assert!(!dir.setup_component_public_keys_payload_file().exists());
+        assert!(dir.election_event_context_payload().is_err());
+        assert_eq!(
+            dir.control_component_public_keys_payload_group()
+                .get_numbers(),
+            &vec![1, 3]
+        );
+        let mut names: Vec<String> = dir.vcs_directories().iter().map(|d| d.get_name()).collect();
+        names.sort();
+        assert_eq!(names, vec!["vcs_1", "vcs_2"]);
+        let vcs_1 = dir
+            .vcs_directories()
+            .iter()
+            .find(|d| d.get_name() == "vcs_1")
+            .unwrap();
+        assert!(vcs_1.setup_component_tally_data_payload_file().exists());
+        assert!(!vcs_1
+            .setup_component_verification_data_payload_group()
+            .has_elements());
     }
 }
 
@@ -406,6 +571,7 @@ pub mod mock {
         mocked_setup_component_public_keys_payload_file: Option<File>,
         mocked_election_event_context_payload_file: Option<File>,
         mocked_election_event_configuration_file: Option<File>,
+        mocked_primes_mapping_table_payload_file: Option<File>,
         mocked_control_component_public_keys_payload_group: Option<FileGroup>,
         mocked_setup_component_public_keys_payload:
             Option<anyhow::Result<Box<SetupComponentPublicKeysPayload>>>,
@@ -413,6 +579,7 @@ pub mod mock {
             Option<anyhow::Result<Box<ElectionEventContextPayload>>>,
         mocked_election_event_configuration:
             Option<anyhow::Result<Box<ElectionEventConfiguration>>>,
+        mocked_primes_mapping_table_payload: Option<anyhow::Result<Box<PrimesMappingTablePayload>>>,
         mocked_control_component_public_keys_payloads:
             HashMap<usize, ControlComponentPublicKeysPayloadAsResult>,
         vcs_directories: Vec<MockVCSDirectory>,
@@ -494,6 +661,11 @@ pub mod mock {
             mocked_election_event_configuration_file,
             File
         );
+        wrap_file_group_getter!(
+            primes_mapping_table_payload_file,
+            mocked_primes_mapping_table_payload_file,
+            File
+        );
         wrap_file_group_getter!(
             control_component_public_keys_payload_group,
             mocked_control_component_public_keys_payload_group,
@@ -519,6 +691,11 @@ pub mod mock {
             mocked_election_event_configuration,
             ElectionEventConfiguration
         );
+        wrap_payload_getter!(
+            primes_mapping_table_payload,
+            mocked_primes_mapping_table_payload,
+            PrimesMappingTablePayload
+        );
 
         wrap_payload_iter!(
             control_component_public_keys_payload_iter,
@@ -589,10 +766,12 @@ pub mod mock {
                 mocked_setup_component_public_keys_payload_file: None,
                 mocked_election_event_context_payload_file: None,
                 mocked_election_event_configuration_file: None,
+                mocked_primes_mapping_table_payload_file: None,
                 mocked_control_component_public_keys_payload_group: None,
                 mocked_setup_component_public_keys_payload: None,
                 mocked_election_event_context_payload: None,
                 mocked_election_event_configuration: None,
+                mocked_primes_mapping_table_payload: None,
                 mocked_control_component_public_keys_payloads: HashMap::new(),
                 vcs_directories: vcs_dirs,
             }
@@ -612,6 +791,9 @@ pub mod mock {
         pub fn mock_election_event_configuration_file(&mut self, data: &File) {
             self.mocked_election_event_configuration_file = Some(data.clone());
         }
+        pub fn mock_primes_mapping_table_payload_file(&mut self, data: &File) {
+            self.mocked_primes_mapping_table_payload_file = Some(data.clone());
+        }
         pub fn mock_control_component_public_keys_payload_group(&mut self, data: &FileGroup) {
             self.mocked_control_component_public_keys_payload_group = Some(data.clone());
         }
@@ -631,6 +813,11 @@ pub mod mock {
             mocked_election_event_configuration,
             ElectionEventConfiguration
         );
+        mock_payload!(
+            mock_primes_mapping_table_payload,
+            mocked_primes_mapping_table_payload,
+            PrimesMappingTablePayload
+        );
 
         mock_payload_iter!(
             mock_control_component_public_keys_payloads,
@@ -0,0 +1,233 @@
+//! Module implementing the statistics of the verification card sets
+//!
+//! The auditors cross-check the statistics against the delivery notes of the canton: number of
+//! voting cards, number of chunks and size of the files per verification card set (VCS), and for
+//! the tally the files and the number of votes of the ballot box of the VCS.
+//!
+//! The statistics are computed from the directory structure. Only the election event context is
+//! decoded. The numbers of verification cards and of votes are read from the json without
+//! decoding the payloads (and without verifying them).
+
+use super::{
+    file::File,
+    setup_directory::{SetupDirectoryTrait, VCSDirectory, VCSDirectoryTrait},
+    tally_directory::{BBDirectory, BBDirectoryTrait, TallyDirectoryTrait},
+    VerificationDirectory, VerificationDirectoryTrait,
+};
+use crate::data_structures::setup::election_event_context_payload::VerificationCardSetContext;
+use log::warn;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Statistics of a verification card set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VCSStatistics {
+    /// Name of the directory of the VCS (id of the VCS)
+    pub name: String,
+    /// Id of the ballot box referenced by the VCS in the election event context. `None` if the VCS
+    /// is not in the context
+    pub ballot_box_id: Option<String>,
+    /// Number of voting cards of the VCS in the election event context
+    pub number_of_voting_cards: Option<usize>,
+    /// Number of verification cards in the setup component tally data. `None` if the file
+    /// cannot be read
+    pub verification_cards: Option<usize>,
+    /// Number of chunks of the setup component verification data
+    pub chunks: usize,
+    /// Number of files in the directory of the VCS
+    pub files: usize,
+    /// Total size in bytes of the files in the directory of the VCS
+    pub bytes: u64,
+    /// Statistics of the ballot box of the VCS (only for the tally)
+    pub ballot_box: Option<BallotBoxStatistics>,
+}
+
+/// Statistics of the ballot box of a verification card set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BallotBoxStatistics {
+    /// The directory of the ballot box exists
+    pub found: bool,
+    /// Number of files in the directory of the ballot box
+    pub files: usize,
+    /// Total size in bytes of the files in the directory of the ballot box
+    pub bytes: u64,
+    /// Number of votes in the tally component votes payload. `None` if the file cannot be read
+    pub votes: Option<usize>,
+}
+
+/// Fields of the setup component tally data needed for the statistics
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationCardIds {
+    verification_card_ids: Vec<serde::de::IgnoredAny>,
+}
+
+/// Fields of the tally component votes payload needed for the statistics
+#[derive(Deserialize)]
+struct Votes {
+    votes: Vec<serde::de::IgnoredAny>,
+}
+
+/// Number of existing files and their total size in bytes
+fn files_statistics(files: &[File]) -> (usize, u64) {
+    files
+        .iter()
+        .filter(|f| f.exists())
+        .fold((0, 0), |(nb, bytes), f| {
+            (nb + 1, bytes + f.size().unwrap_or(0))
+        })
+}
+
+/// Number of elements read with `count` from the json of the file. `None` if the file cannot be
+/// read
+fn count_in_file<T: for<'de> Deserialize<'de>>(
+    file: &File,
+    count: impl Fn(T) -> usize,
+) -> Option<usize> {
+    if !file.exists() {
+        return None;
+    }
+    match file
+        .read_data()
+        .and_then(|s| serde_json::from_str::<T>(&s).map_err(anyhow::Error::from))
+    {
+        Ok(v) => Some(count(v)),
+        Err(e) => {
+            warn!("Statistics: cannot read {:?}: {:#}", file.get_path(), e);
+            None
+        }
+    }
+}
+
+fn ballot_box_statistics(bb_dir: Option<&BBDirectory>) -> BallotBoxStatistics {
+    match bb_dir {
+        Some(d) => {
+            let (files, bytes) = files_statistics(&d.files());
+            BallotBoxStatistics {
+                found: true,
+                files,
+                bytes,
+                votes: count_in_file(d.tally_component_votes_payload_file(), |v: Votes| {
+                    v.votes.len()
+                }),
+            }
+        }
+        None => BallotBoxStatistics {
+            found: false,
+            files: 0,
+            bytes: 0,
+            votes: None,
+        },
+    }
+}
+
+fn statistics_of_vcs(
+    vcs_dir: &VCSDirectory,
+    context: Option<&VerificationCardSetContext>,
+    bb_dirs: Option<&[BBDirectory]>,
+) -> VCSStatistics {
+    let (files, bytes) = files_statistics(&vcs_dir.files());
+    let ballot_box_id = context.map(|c| c.ballot_box_id.clone());
+    let ballot_box = bb_dirs.map(|dirs| {
+        ballot_box_statistics(
+            ballot_box_id
+                .as_ref()
+                .and_then(|id| dirs.iter().find(|d| d.get_name().eq_ignore_ascii_case(id))),
+        )
+    });
+    VCSStatistics {
+        name: vcs_dir.get_name(),
+        ballot_box_id,
+        number_of_voting_cards: context.map(|c| c.number_of_voting_cards),
+        verification_cards: count_in_file(
+            vcs_dir.setup_component_tally_data_payload_file(),
+            |v: VerificationCardIds| v.verification_card_ids.len(),
+        ),
+        chunks: vcs_dir
+            .setup_component_verification_data_payload_group()
+            .get_numbers()
+            .len(),
+        files,
+        bytes,
+        ballot_box,
+    }
+}
+
+/// Statistics of all the verification card sets of the directory, in the order of the
+/// directories
+///
+/// The statistics of the ballot boxes are only computed for the tally. If the election event
+/// context cannot be read, the VCS are not related to their ballot box.
+pub fn vcs_statistics(dir: &VerificationDirectory) -> Vec<VCSStatistics> {
+    let setup_dir = dir.unwrap_setup();
+    let context = match setup_dir.election_event_context_payload() {
+        Ok(c) => Some(c),
+        Err(e) => {
+            warn!(
+                "Statistics: cannot read the election event context: {:#}",
+                e
+            );
+            None
+        }
+    };
+    let vcs_contexts: Vec<&VerificationCardSetContext> = context
+        .as_ref()
+        .map(|c| {
+            c.election_event_context
+                .verification_card_set_contexts
+                .iter()
+                .collect()
+        })
+        .unwrap_or_default();
+    let bb_dirs = dir.tally().map(|t| t.bb_directories().as_slice());
+    setup_dir
+        .vcs_directories()
+        .par_iter()
+        .map(|d| {
+            let name = d.get_name();
+            let vcs_context = vcs_contexts
+                .iter()
+                .find(|c| c.verification_card_set_id.eq_ignore_ascii_case(&name))
+                .copied();
+            statistics_of_vcs(d, vcs_context, bb_dirs)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::test::{test_dataset_setup_path, test_dataset_tally_path},
+        verification::VerificationPeriod,
+    };
+
+    #[test]
+    fn test_setup() {
+        let dir =
+            VerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let stats = vcs_statistics(&dir);
+        assert_eq!(stats.len(), dir.unwrap_setup().vcs_directories().len());
+        for s in stats.iter() {
+            assert!(s.ballot_box_id.is_some());
+            assert_eq!(s.verification_cards, s.number_of_voting_cards);
+            assert!(s.chunks > 0);
+            assert_eq!(s.files, 2 * s.chunks + 1);
+            assert!(s.bytes > 0);
+            assert!(s.ballot_box.is_none());
+        }
+    }
+
+    #[test]
+    fn test_tally() {
+        let dir =
+            VerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        for s in vcs_statistics(&dir).iter() {
+            let bb = s.ballot_box.as_ref().unwrap();
+            if bb.found {
+                assert!(bb.files > 0);
+                assert!(bb.votes.is_some());
+            }
+        }
+    }
+}
@@ -0,0 +1,416 @@
+//! Module implementing a builder of synthetic datasets for the tests
+//!
+//! The builder writes minimal setup and tally directories in a temporary directory, so that a
+//! verification can be tested with the exact data of the case, without shipping big fixtures
+//! and without mocking each payload. The payloads are valid json, with an encryption group of
+//! small numbers and placeholders for the signatures. The verifications of the signatures and
+//! of the proofs fail on these payloads.
+//!
+//! The module is available in the tests of the crate and with the feature `test-tools`.
+//!
+//! Example of usage:
+//! ```ignore
+//!    let dataset = SyntheticDatasetBuilder::new("wrong_alias", &VerificationPeriod::Tally)
+//!        .with_verification_card_set("vcs_1", "bb_1", &["vc_1", "vc_2"])
+//!        .with_field(
+//!            &SyntheticLocation::Setup,
+//!            "electionEventContextPayload.json",
+//!            "/electionEventContext/electionEventAlias",
+//!            json!("new alias"),
+//!        )
+//!        .build()?;
+//!    let result = fn_verification(&dataset.verification_directory(), &context);
+//! ```
+
+use super::{GetFileNameTrait, VerificationDirectory};
+use crate::{
+    data_structures::{setup::VerifierSetupDataType, VerifierDataType},
+    verification::VerificationPeriod,
+};
+use anyhow::{anyhow, Context};
+use data_encoding::BASE64;
+use rug::{integer::Order, Integer};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    env::temp_dir,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Default election event id of the synthetic datasets
+pub const SYNTHETIC_ELECTION_EVENT_ID: &str = "0B1E4A4B5F0C4C4E8B3E3C8A3E4D5F6A";
+
+/// Start time of the election event and of the ballot boxes
+const START_TIME: &str = "2024-01-01T08:00:00";
+
+/// Finish time of the election event and of the ballot boxes
+const FINISH_TIME: &str = "2024-01-31T12:00:00";
+
+/// Location of a file in a synthetic dataset
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SyntheticLocation {
+    /// Directory `setup`
+    Setup,
+    /// Directory `tally`
+    Tally,
+    /// Directory of the verification card set with the given id in the setup
+    VerificationCardSet(String),
+    /// Directory of the ballot box with the given id in the tally
+    BallotBox(String),
+}
+
+impl SyntheticLocation {
+    /// Path of the location, relative to the root of the dataset
+    fn relative_path(&self) -> PathBuf {
+        match self {
+            Self::Setup => PathBuf::from("setup"),
+            Self::Tally => PathBuf::from("tally"),
+            Self::VerificationCardSet(id) => {
+                Path::new("setup").join("verification_card_sets").join(id)
+            }
+            Self::BallotBox(id) => Path::new("tally").join("ballot_boxes").join(id),
+        }
+    }
+}
+
+/// Modification of a field in a payload, applied when the dataset is built
+#[derive(Debug, Clone)]
+struct FieldChange {
+    location: SyntheticLocation,
+    file_name: String,
+    pointer: String,
+    value: Value,
+}
+
+/// Builder of a [SyntheticDataset]
+///
+/// The builder creates the election event context payload. The verification card sets are
+/// added with [SyntheticDatasetBuilder::with_verification_card_set]. The other payloads are
+/// given as json with [SyntheticDatasetBuilder::with_payload]
+#[derive(Debug, Clone)]
+pub struct SyntheticDatasetBuilder {
+    name: String,
+    period: VerificationPeriod,
+    files: BTreeMap<(SyntheticLocation, String), Value>,
+    directories: Vec<SyntheticLocation>,
+    changes: Vec<FieldChange>,
+}
+
+/// Synthetic dataset written in a temporary directory
+///
+/// The directory is removed when the dataset is dropped
+#[derive(Debug)]
+pub struct SyntheticDataset {
+    path: PathBuf,
+    period: VerificationPeriod,
+}
+
+/// Encode the integer in base64, like in the payloads
+pub fn integer_to_base64(value: &Integer) -> String {
+    BASE64.encode(&value.to_digits::<u8>(Order::Msf))
+}
+
+/// Encryption group of the synthetic payloads
+///
+/// The group is the group of the quadratic residues modulo the safe prime 23
+pub fn synthetic_encryption_group() -> Value {
+    json!({
+        "p": integer_to_base64(&Integer::from(23)),
+        "q": integer_to_base64(&Integer::from(11)),
+        "g": integer_to_base64(&Integer::from(2)),
+    })
+}
+
+/// Placeholder of the signature of the synthetic payloads
+pub fn synthetic_signature() -> Value {
+    json!({ "signatureContents": BASE64.encode(&[0u8]) })
+}
+
+fn file_name(data_type: VerifierSetupDataType) -> String {
+    VerifierDataType::Setup(data_type).get_file_name(None)
+}
+
+impl SyntheticDatasetBuilder {
+    /// New builder for a dataset of the period
+    ///
+    /// The name is used for the temporary directory, so that the tests running in parallel
+    /// must use different names
+    pub fn new(name: &str, period: &VerificationPeriod) -> Self {
+        let mut files = BTreeMap::new();
+        files.insert(
+            (
+                SyntheticLocation::Setup,
+                file_name(VerifierSetupDataType::ElectionEventContextPayload),
+            ),
+            json!({
+                "encryptionGroup": synthetic_encryption_group(),
+                "seed": "SYNTHETIC",
+                "smallPrimes": [3, 5, 7],
+                "electionEventContext": {
+                    "electionEventId": SYNTHETIC_ELECTION_EVENT_ID,
+                    "electionEventAlias": name,
+                    "electionEventDescription": name,
+                    "verificationCardSetContexts": [],
+                    "startTime": START_TIME,
+                    "finishTime": FINISH_TIME,
+                    "maximumNumberOfVotingOptions": 0,
+                    "maximumNumberOfSelections": 0,
+                    "maximumNumberOfWriteInsPlusOne": 1,
+                },
+                "signature": synthetic_signature(),
+            }),
+        );
+        let mut directories = vec![SyntheticLocation::Setup];
+        if period.is_tally() {
+            directories.push(SyntheticLocation::Tally);
+        }
+        Self {
+            name: name.to_string(),
+            period: *period,
+            files,
+            directories,
+            changes: vec![],
+        }
+    }
+
+    /// Add a verification card set with its ballot box and its verification cards
+    ///
+    /// The context of the verification card set is added to the election event context and
+    /// the setup component tally data payload is created. For the tally, the (empty) directory
+    /// of the ballot box is created
+    pub fn with_verification_card_set(
+        mut self,
+        vcs_id: &str,
+        bb_id: &str,
+        verification_card_ids: &[&str],
+    ) -> Self {
+        let eec_key = (
+            SyntheticLocation::Setup,
+            file_name(VerifierSetupDataType::ElectionEventContextPayload),
+        );
+        if let Some(Value::Array(contexts)) = self
+            .files
+            .get_mut(&eec_key)
+            .and_then(|eec| eec.pointer_mut("/electionEventContext/verificationCardSetContexts"))
+        {
+            contexts.push(json!({
+                "verificationCardSetId": vcs_id,
+                "verificationCardSetAlias": vcs_id,
+                "verificationCardSetDescription": vcs_id,
+                "ballotBoxId": bb_id,
+                "ballotBoxStartTime": START_TIME,
+                "ballotBoxFinishTime": FINISH_TIME,
+                "testBallotBox": false,
+                "numberOfVotingCards": verification_card_ids.len(),
+                "gracePeriod": 900,
+                "primesMappingTable": {
+                    "encryptionGroup": synthetic_encryption_group(),
+                    "pTable": [],
+                    "numberOfVotingOptions": 0,
+                },
+            }))
+        }
+        let location = SyntheticLocation::VerificationCardSet(vcs_id.to_string());
+        let keys: Vec<Value> = (0..verification_card_ids.len())
+            .map(|i| json!([integer_to_base64(&Integer::from(2 * i + 4))]))
+            .collect();
+        self.files.insert(
+            (
+                location.clone(),
+                file_name(VerifierSetupDataType::SetupComponentTallyDataPayload),
+            ),
+            json!({
+                "electionEventId": SYNTHETIC_ELECTION_EVENT_ID,
+                "verificationCardSetId": vcs_id,
+                "ballotBoxDefaultTitle": bb_id,
+                "encryptionGroup": synthetic_encryption_group(),
+                "verificationCardIds": verification_card_ids,
+                "verificationCardPublicKeys": keys,
+                "signature": synthetic_signature(),
+            }),
+        );
+        self.directories.push(location);
+        if self.period.is_tally() {
+            self.directories
+                .push(SyntheticLocation::BallotBox(bb_id.to_string()));
+        }
+        self
+    }
+
+    /// Add the payload with the given file name (e.g. `controlComponentBallotBoxPayload_1.json`)
+    /// at the location. A payload already existing is replaced
+    pub fn with_payload(
+        mut self,
+        location: &SyntheticLocation,
+        file_name: &str,
+        value: Value,
+    ) -> Self {
+        self.directories.push(location.clone());
+        self.files
+            .insert((location.clone(), file_name.to_string()), value);
+        self
+    }
+
+    /// Set the field of the payload to the value
+    ///
+    /// The field is given as json pointer (e.g. `/electionEventContext/electionEventAlias`).
+    /// The change is applied by [SyntheticDatasetBuilder::build], after all the payloads are
+    /// added. The build fails if the payload or the field does not exist
+    pub fn with_field(
+        mut self,
+        location: &SyntheticLocation,
+        file_name: &str,
+        pointer: &str,
+        value: Value,
+    ) -> Self {
+        self.changes.push(FieldChange {
+            location: location.clone(),
+            file_name: file_name.to_string(),
+            pointer: pointer.to_string(),
+            value,
+        });
+        self
+    }
+
+    /// Path of the dataset in the temporary directory
+    pub fn path(&self) -> PathBuf {
+        temp_dir().join(format!(
+            "rust_verifier_synthetic_{}_{}",
+            self.name,
+            std::process::id()
+        ))
+    }
+
+    /// Write the dataset in the temporary directory
+    ///
+    /// A dataset with the same path is removed before
+    pub fn build(mut self) -> anyhow::Result<SyntheticDataset> {
+        for change in self.changes.iter() {
+            let payload = self
+                .files
+                .get_mut(&(change.location.clone(), change.file_name.clone()))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "The payload {} does not exist in {:?}",
+                        change.file_name,
+                        change.location
+                    )
+                })?;
+            let field = payload.pointer_mut(&change.pointer).ok_or_else(|| {
+                anyhow!(
+                    "The field {} does not exist in the payload {}",
+                    change.pointer,
+                    change.file_name
+                )
+            })?;
+            *field = change.value.clone();
+        }
+        let path = self.path();
+        if path.exists() {
+            fs::remove_dir_all(&path)
+                .with_context(|| format!("Cannot remove the dataset {}", path.display()))?;
+        }
+        for location in self.directories.iter() {
+            let dir = path.join(location.relative_path());
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("Cannot create the directory {}", dir.display()))?;
+        }
+        for ((location, file_name), value) in self.files.iter() {
+            let file = path.join(location.relative_path()).join(file_name);
+            fs::write(&file, serde_json::to_string_pretty(value)?)
+                .with_context(|| format!("Cannot write the file {}", file.display()))?;
+        }
+        Ok(SyntheticDataset {
+            path,
+            period: self.period,
+        })
+    }
+}
+
+impl SyntheticDataset {
+    /// Path of the dataset (containing the directories `setup` and `tally`)
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The verification directory of the dataset
+    pub fn verification_directory(&self) -> VerificationDirectory {
+        VerificationDirectory::new(&self.period, &self.path)
+    }
+}
+
+impl Drop for SyntheticDataset {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::{
+            setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+            tally_directory::TallyDirectoryTrait,
+            VerificationDirectoryTrait,
+        },
+        *,
+    };
+
+    #[test]
+    fn test_build() {
+        let dataset = SyntheticDatasetBuilder::new("test_build", &VerificationPeriod::Tally)
+            .with_verification_card_set("vcs_1", "bb_1", &["vc_1", "vc_2"])
+            .with_verification_card_set("vcs_2", "bb_2", &[])
+            .with_field(
+                &SyntheticLocation::Setup,
+                "electionEventContextPayload.json",
+                "/electionEventContext/electionEventAlias",
+                json!("changed"),
+            )
+            .build()
+            .unwrap();
+        let dir = dataset.verification_directory();
+        let eec = dir.unwrap_setup().election_event_context_payload().unwrap();
+        assert_eq!(eec.election_event_context.election_event_alias, "changed");
+        assert_eq!(
+            eec.election_event_context
+                .verification_card_set_contexts
+                .len(),
+            2
+        );
+        assert_eq!(eec.encryption_group.p(), &Integer::from(23));
+        let vcs_dirs = dir.unwrap_setup().vcs_directories();
+        assert_eq!(vcs_dirs.len(), 2);
+        let vcs_1 = vcs_dirs.iter().find(|d| d.get_name() == "vcs_1").unwrap();
+        let tally_data = vcs_1.setup_component_tally_data_payload().unwrap();
+        assert_eq!(tally_data.verification_card_ids, vec!["vc_1", "vc_2"]);
+        assert_eq!(tally_data.verification_card_public_keys.len(), 2);
+        assert_eq!(dir.tally().unwrap().bb_directories().len(), 2);
+        let path = dataset.path().to_path_buf();
+        drop(dataset);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let res = SyntheticDatasetBuilder::new("test_unknown_field", &VerificationPeriod::Setup)
+            .with_field(
+                &SyntheticLocation::Setup,
+                "electionEventContextPayload.json",
+                "/toto",
+                json!(1),
+            )
+            .build();
+        assert!(res.is_err());
+        let res = SyntheticDatasetBuilder::new("test_unknown_field", &VerificationPeriod::Setup)
+            .with_field(
+                &SyntheticLocation::Tally,
+                "electionEventContextPayload.json",
+                "/seed",
+                json!(1),
+            )
+            .build();
+        assert!(res.is_err());
+    }
+}
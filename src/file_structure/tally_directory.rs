@@ -1,9 +1,11 @@
 use super::{
+    data_source::{local_source, DataSource},
     file::{create_file, File},
     file_group::{
         add_type_for_file_group_iter_trait, impl_iterator_over_data_payload, FileGroup,
         FileGroupIter, FileGroupIterTrait,
     },
+    scan_subdirectories,
 };
 use crate::{
     config::Config,
@@ -12,19 +14,23 @@ use crate::{
         tally::{
             control_component_ballot_box_payload::ControlComponentBallotBoxPayload,
             control_component_shuffle_payload::ControlComponentShufflePayload,
+            e_voting_decrypt::EVotingDecrypt, ech_0110::ECH0110, ech_0222::ECH0222,
             tally_component_shuffle_payload::TallyComponentShufflePayload,
             tally_component_votes_payload::TallyComponentVotesPayload, VerifierTallyDataType,
         },
         VerifierDataType, VerifierTallyDataTrait,
     },
 };
+use anyhow::anyhow;
 use std::{
-    fs,
+    collections::BTreeSet,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 #[derive(Clone)]
 pub struct TallyDirectory {
+    source: Arc<dyn DataSource>,
     location: PathBuf,
     e_voting_decrypt_file: File,
     ech_0110_file: File,
@@ -53,6 +59,18 @@ pub trait TallyDirectoryTrait {
     fn ech_0110_file(&self) -> &File;
     fn ech_0222_file(&self) -> &File;
     fn bb_directories(&self) -> &Vec<Self::BBDirType>;
+    fn e_voting_decrypt(&self) -> anyhow::Result<Box<EVotingDecrypt>>;
+    fn ech_0110(&self) -> anyhow::Result<Box<ECH0110>>;
+    fn ech_0222(&self) -> anyhow::Result<Box<ECH0222>>;
+
+    /// The set of node ids found in the file groups of the control components
+    /// over all the ballot boxes
+    fn node_ids(&self) -> BTreeSet<usize> {
+        self.bb_directories()
+            .iter()
+            .flat_map(|d| d.node_ids())
+            .collect()
+    }
 }
 
 /// Trait to set the necessary functions for the struct [BBDirectory] that
@@ -83,6 +101,20 @@ pub trait BBDirectoryTrait {
     ) -> Self::ControlComponentShufflePayloadloadAsResultIterType;
 
     fn get_name(&self) -> String;
+
+    /// The set of node ids found in the file groups of the control components
+    fn node_ids(&self) -> BTreeSet<usize> {
+        self.control_component_ballot_box_payload_group()
+            .get_numbers()
+            .iter()
+            .chain(
+                self.control_component_shuffle_payload_group()
+                    .get_numbers()
+                    .iter(),
+            )
+            .cloned()
+            .collect()
+    }
 }
 
 impl_iterator_over_data_payload!(
@@ -114,6 +146,39 @@ impl TallyDirectoryTrait for TallyDirectory {
     fn bb_directories(&self) -> &Vec<BBDirectory> {
         &self.bb_directories
     }
+    fn e_voting_decrypt(&self) -> anyhow::Result<Box<EVotingDecrypt>> {
+        self.e_voting_decrypt_file
+            .get_data()
+            .map_err(|e| e.context("in e_voting_decrypt"))
+            .and_then(|d| {
+                d.e_voting_decrypt()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a e_voting_decrypt"))
+            })
+    }
+    fn ech_0110(&self) -> anyhow::Result<Box<ECH0110>> {
+        self.ech_0110_file
+            .get_data()
+            .map_err(|e| e.context("in ech_0110"))
+            .and_then(|d| {
+                d.ech_0110()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a ech_0110"))
+            })
+    }
+    fn ech_0222(&self) -> anyhow::Result<Box<ECH0222>> {
+        self.ech_0222_file
+            .get_data()
+            .map_err(|e| e.context("in ech_0222"))
+            .and_then(|d| {
+                d.ech_0222()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a ech_0222"))
+            })
+    }
 }
 
 impl BBDirectoryTrait for BBDirectory {
@@ -137,13 +202,23 @@ impl BBDirectoryTrait for BBDirectory {
         self.tally_component_votes_payload_file
             .get_data()
             .map_err(|e| e.context("in tally_component_votes_payload"))
-            .map(|d| Box::new(d.tally_component_votes_payload().unwrap().clone()))
+            .and_then(|d| {
+                d.tally_component_votes_payload()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a tally_component_votes_payload"))
+            })
     }
     fn tally_component_shuffle_payload(&self) -> anyhow::Result<Box<TallyComponentShufflePayload>> {
         self.tally_component_shuffle_payload_file
             .get_data()
             .map_err(|e| e.context("in tally_component_shuffle_payload"))
-            .map(|d| Box::new(d.tally_component_shuffle_payload().unwrap().clone()))
+            .and_then(|d| {
+                d.tally_component_shuffle_payload()
+                    .cloned()
+                    .map(Box::new)
+                    .ok_or_else(|| anyhow!("The data is not a tally_component_shuffle_payload"))
+            })
     }
 
     fn control_component_ballot_box_payload_iter(
@@ -161,65 +236,101 @@ impl BBDirectoryTrait for BBDirectory {
     fn get_name(&self) -> String {
         self.location
             .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
     }
 }
 
 impl TallyDirectory {
-    #[allow(clippy::redundant_clone)]
+    /// New [TallyDirectory] on the disk
     pub fn new(data_location: &Path) -> TallyDirectory {
+        Self::new_with_source(data_location, local_source())
+    }
+
+    /// New [TallyDirectory] whose files are read from the given source
+    #[allow(clippy::redundant_clone)]
+    pub fn new_with_source(data_location: &Path, source: Arc<dyn DataSource>) -> TallyDirectory {
         let location = data_location.join(Config::tally_dir_name());
         let mut res = TallyDirectory {
+            source: source.clone(),
             location: location.to_path_buf(),
             e_voting_decrypt_file: create_file!(
+                source,
                 location,
                 Tally,
                 VerifierTallyDataType::EVotingDecrypt
             ),
-            ech_0110_file: create_file!(location, Tally, VerifierTallyDataType::ECH0110),
-            ech_0222_file: create_file!(location, Tally, VerifierTallyDataType::ECH0222),
+            ech_0110_file: create_file!(source, location, Tally, VerifierTallyDataType::ECH0110),
+            ech_0222_file: create_file!(source, location, Tally, VerifierTallyDataType::ECH0222),
             bb_directories: vec![],
         };
-        let bb_path = location.join(Config::bb_dir_name());
-        if bb_path.is_dir() {
-            for re in fs::read_dir(&bb_path).unwrap() {
-                let e = re.unwrap().path();
-                if e.is_dir() {
-                    res.bb_directories.push(BBDirectory::new(&e))
-                }
-            }
-        }
+        res.bb_directories = scan_subdirectories(
+            source.as_ref(),
+            &location.join(Config::bb_dir_name()),
+            |p| BBDirectory::new_with_source(p, &source),
+        );
         res
     }
 
+    /// Source from which the files are read
+    pub fn source(&self) -> &Arc<dyn DataSource> {
+        &self.source
+    }
+
     #[allow(dead_code)]
     pub fn get_location(&self) -> &Path {
         self.location.as_path()
     }
+
+    /// All the files of the directory, including the files of the subdirectories
+    pub fn files(&self) -> Vec<File> {
+        let mut res = vec![
+            self.e_voting_decrypt_file.clone(),
+            self.ech_0110_file.clone(),
+            self.ech_0222_file.clone(),
+        ];
+        for d in self.bb_directories.iter() {
+            res.append(&mut d.files());
+        }
+        res
+    }
+
+    /// Keep only the ballot box directories with the given names
+    pub fn restrict_bb_directories(&mut self, names: &[String]) {
+        self.bb_directories
+            .retain(|d| names.contains(&d.get_name()))
+    }
 }
 
 impl BBDirectory {
+    #[allow(dead_code)]
     pub fn new(location: &Path) -> Self {
+        Self::new_with_source(location, &local_source())
+    }
+
+    /// New [BBDirectory] whose files are read from the given source
+    pub fn new_with_source(location: &Path, source: &Arc<dyn DataSource>) -> Self {
         Self {
             location: location.to_path_buf(),
             tally_component_votes_payload_file: create_file!(
+                source,
                 location,
                 Tally,
                 VerifierTallyDataType::TallyComponentVotesPayload
             ),
             tally_component_shuffle_payload_file: create_file!(
+                source,
                 location,
                 Tally,
                 VerifierTallyDataType::TallyComponentShufflePayload
             ),
-            control_component_ballot_box_payload_group: FileGroup::new(
+            control_component_ballot_box_payload_group: FileGroup::new_with_source(
+                source,
                 location,
                 create_verifier_tally_data_type!(Tally, ControlComponentBallotBoxPayload),
             ),
-            control_component_shuffle_payload_group: FileGroup::new(
+            control_component_shuffle_payload_group: FileGroup::new_with_source(
+                source,
                 location,
                 create_verifier_tally_data_type!(Tally, ControlComponentShufflePayload),
             ),
@@ -230,6 +341,25 @@ impl BBDirectory {
     pub fn get_location(&self) -> &Path {
         self.location.as_path()
     }
+
+    /// All the files of the directory, including the files of the file groups
+    pub fn files(&self) -> Vec<File> {
+        let mut res = vec![
+            self.tally_component_votes_payload_file.clone(),
+            self.tally_component_shuffle_payload_file.clone(),
+        ];
+        res.extend(
+            self.control_component_ballot_box_payload_group
+                .iter()
+                .map(|(_, f)| f),
+        );
+        res.extend(
+            self.control_component_shuffle_payload_group
+                .iter()
+                .map(|(_, f)| f),
+        );
+        res
+    }
 }
 
 #[cfg(any(test, doc))]
@@ -242,9 +372,9 @@ pub mod mock {
     use super::super::file_group::mock::MockFileGroupIter;
     use super::{super::mock::wrap_file_group_getter, *};
     use crate::file_structure::file_group::mock::{
-        impl_iterator_over_data_payload_mock, wrap_payload_iter,
+        impl_iterator_over_data_payload_mock, mock_payload_iter, wrap_payload_iter,
     };
-    use crate::file_structure::mock::wrap_payload_getter;
+    use crate::file_structure::mock::{mock_payload, wrap_payload_getter};
     use anyhow::anyhow;
     use std::collections::HashMap;
 
@@ -273,6 +403,9 @@ pub mod mock {
         mocked_e_voting_decrypt_file: Option<File>,
         mocked_ech_0110_file: Option<File>,
         mocked_ech_0222_file: Option<File>,
+        mocked_e_voting_decrypt: Option<anyhow::Result<Box<EVotingDecrypt>>>,
+        mocked_ech_0110: Option<anyhow::Result<Box<ECH0110>>>,
+        mocked_ech_0222: Option<anyhow::Result<Box<ECH0222>>>,
         bb_directories: Vec<MockBBDirectory>,
     }
 
@@ -355,6 +488,10 @@ pub mod mock {
         fn bb_directories(&self) -> &Vec<MockBBDirectory> {
             &self.bb_directories
         }
+
+        wrap_payload_getter!(e_voting_decrypt, mocked_e_voting_decrypt, EVotingDecrypt);
+        wrap_payload_getter!(ech_0110, mocked_ech_0110, ECH0110);
+        wrap_payload_getter!(ech_0222, mocked_ech_0222, ECH0222);
     }
 
     impl MockBBDirectory {
@@ -387,6 +524,27 @@ pub mod mock {
         pub fn mock_get_name(&mut self, data: &str) {
             self.mocked_get_name = Some(data.to_string())
         }
+        mock_payload!(
+            mock_tally_component_shuffle_payload,
+            mocked_tally_component_shuffle_payload,
+            TallyComponentShufflePayload
+        );
+        mock_payload!(
+            mock_tally_component_votes_payload,
+            mocked_tally_component_votes_payload,
+            TallyComponentVotesPayload
+        );
+
+        mock_payload_iter!(
+            mock_control_component_ballot_box_payloads,
+            mocked_control_component_ballot_box_payloads,
+            ControlComponentBallotBoxPayload
+        );
+        mock_payload_iter!(
+            mock_control_component_shuffle_payloads,
+            mocked_control_component_shuffle_payloads,
+            ControlComponentShufflePayload
+        );
     }
 
     impl MockTallyDirectory {
@@ -402,6 +560,9 @@ pub mod mock {
                 mocked_e_voting_decrypt_file: None,
                 mocked_ech_0110_file: None,
                 mocked_ech_0222_file: None,
+                mocked_e_voting_decrypt: None,
+                mocked_ech_0110: None,
+                mocked_ech_0222: None,
                 bb_directories: bb_dirs,
             }
         }
@@ -418,5 +579,12 @@ pub mod mock {
         pub fn mock_ech_0222_file(&mut self, data: &File) {
             self.mocked_ech_0222_file = Some(data.clone());
         }
+        mock_payload!(
+            mock_e_voting_decrypt,
+            mocked_e_voting_decrypt,
+            EVotingDecrypt
+        );
+        mock_payload!(mock_ech_0110, mocked_ech_0110, ECH0110);
+        mock_payload!(mock_ech_0222, mocked_ech_0222, ECH0222);
     }
 }
@@ -0,0 +1,490 @@
+//! Module implementing the verification directory for a dataset delivered as ZIP archive
+//!
+//! The archive is not extracted, neither on the disk nor in memory. The entries are indexed at
+//! the opening and read from the archive when the verifications read them (see
+//! [ZipDataset::source]). The [VerificationDirectory] created on the root of the archive with this
+//! source reads them like files on the disk, so that all the verifications work without change.
+//!
+//! The entries are streamed: each reader decompresses its entry on its own view of the archive,
+//! so that several files can be read in parallel. The archive file is locked only for each read
+//! of compressed data, and the memory used does not depend on the sizes given in the archive.
+//!
+//! The entries that are not valid UTF-8 (e.g. documents delivered with the dataset) do not prevent
+//! the opening. An error is reported only when such an entry is read as a file of the dataset.
+//!
+//! The archive can contain the directories setup and tally directly, or within one top directory.
+use super::{
+    data_source::{DataSource, FileReader},
+    VerificationDirectory,
+};
+use crate::{config::Config, verification::VerificationPeriod};
+use anyhow::{anyhow, Context};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug},
+    fs,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{sync_channel, Receiver},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+};
+use zip::{read::ZipFile, ZipArchive};
+
+/// Size of the chunks of decompressed data sent to a [ZipEntryReader]
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of chunks that can be decompressed in advance for a [ZipEntryReader]
+const CHUNKS_IN_ADVANCE: usize = 4;
+
+/// Maximum capacity reserved in advance to read an entry. The sizes given in the archive are not
+/// trusted to allocate the memory
+const MAX_PREALLOCATION: u64 = 16 * 1024 * 1024;
+
+/// Entry of the archive
+#[derive(Debug, Clone, Copy)]
+struct ZipEntry {
+    index: usize,
+    size: u64,
+}
+
+/// Archive file shared by the clones of the [ZipArchive]
+///
+/// Each clone has its own position. The file is locked only during a read, and not during the
+/// decompression of the data
+#[derive(Debug, Clone)]
+struct SharedFile {
+    file: Arc<Mutex<fs::File>>,
+    pos: u64,
+}
+
+/// Reader on an entry of the archive
+///
+/// The entry is decompressed in a thread on a clone of the archive and sent by chunks through a
+/// bounded channel. The thread stops if the reader is dropped
+struct ZipEntryReader {
+    receiver: Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+/// Source reading the files from the ZIP archive
+///
+/// The keys are the complete paths under the path of the archive. The content of an entry is
+/// decompressed each time the file is read
+struct ZipDataSource {
+    archive: ZipArchive<SharedFile>,
+    password: Option<Vec<u8>>,
+    entries: BTreeMap<PathBuf, ZipEntry>,
+}
+
+/// Dataset delivered as ZIP archive
+///
+/// The files are read through the source of the dataset ([ZipDataset::source]).
+pub struct ZipDataset {
+    archive_path: PathBuf,
+    root: PathBuf,
+    source: Arc<ZipDataSource>,
+}
+
+/// Test if the path is a ZIP archive (according to the extension)
+pub fn is_zip_archive(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("zip"))
+}
+
+/// Entry `index` of the archive, decrypted with `password` if given
+fn zip_entry<'a>(
+    zip: &'a mut ZipArchive<SharedFile>,
+    index: usize,
+    password: Option<&[u8]>,
+) -> anyhow::Result<ZipFile<'a>> {
+    match password {
+        Some(pw) => zip
+            .by_index_decrypt(index, pw)
+            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e).context("Wrong password for the archive")),
+        None => zip.by_index(index).map_err(|e| anyhow!(e)),
+    }
+}
+
+impl ZipDataset {
+    /// Open the archive and index its entries
+    ///
+    /// `password` is necessary if the files of the archive are encrypted. It is checked at the
+    /// opening
+    pub fn open(archive: &Path, password: Option<&[u8]>) -> anyhow::Result<Self> {
+        let file = fs::File::open(archive)
+            .with_context(|| format!("Cannot open the archive {:?}", archive))?;
+        let mut zip = ZipArchive::new(SharedFile::new(file))
+            .map_err(|e| anyhow!(e).context(format!("{:?} is not a valid ZIP archive", archive)))?;
+        // The path of the archive is used as root, since it cannot be a directory on the disk
+        let mut entries = BTreeMap::new();
+        for i in 0..zip.len() {
+            let entry = zip_entry(&mut zip, i, password)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry
+                .enclosed_name()
+                .ok_or_else(|| anyhow!("Invalid path {} in the archive", entry.name()))?
+                .to_path_buf();
+            entries.insert(
+                archive.join(name),
+                ZipEntry {
+                    index: i,
+                    size: entry.size(),
+                },
+            );
+        }
+        let source = Arc::new(ZipDataSource {
+            archive: zip,
+            password: password.map(|p| p.to_vec()),
+            entries,
+        });
+        let root = dataset_root(source.as_ref(), archive);
+        Ok(Self {
+            archive_path: archive.to_path_buf(),
+            root,
+            source,
+        })
+    }
+
+    /// Root of the dataset (containing the directories setup and tally)
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the archive
+    pub fn archive_path(&self) -> &Path {
+        &self.archive_path
+    }
+
+    /// Source from which the files of the dataset are read
+    pub fn source(&self) -> Arc<dyn DataSource> {
+        self.source.clone()
+    }
+
+    /// Number of files in the archive
+    pub fn len(&self) -> usize {
+        self.source.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.source.entries.is_empty()
+    }
+
+    /// The [VerificationDirectory] of the dataset for the period
+    pub fn verification_directory(&self, period: &VerificationPeriod) -> VerificationDirectory {
+        VerificationDirectory::new_with_source(period, &self.root, self.source())
+    }
+}
+
+/// The root of the dataset in the archive: the root of the archive, or its top directory if
+/// it is the only entry of the archive
+fn dataset_root(source: &dyn DataSource, archive_root: &Path) -> PathBuf {
+    if source.is_dir(&archive_root.join(Config::setup_dir_name())) {
+        return archive_root.to_path_buf();
+    }
+    match source.read_dir(archive_root).as_slice() {
+        [top] if source.is_dir(top) => top.clone(),
+        _ => archive_root.to_path_buf(),
+    }
+}
+
+impl SharedFile {
+    fn new(file: fs::File) -> Self {
+        Self {
+            file: Arc::new(Mutex::new(file)),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for SharedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        file.seek(SeekFrom::Start(self.pos))?;
+        let n = file.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SharedFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = match pos {
+            SeekFrom::Start(p) => p,
+            _ => {
+                let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+                file.seek(SeekFrom::Start(self.pos))?;
+                file.seek(pos)?
+            }
+        };
+        Ok(self.pos)
+    }
+}
+
+impl ZipEntryReader {
+    /// Start the decompression of the entry `index` of `zip`
+    ///
+    /// The errors opening the entry (e.g. wrong password) are returned at the first read
+    fn new(mut zip: ZipArchive<SharedFile>, index: usize, password: Option<Vec<u8>>) -> Self {
+        let (sender, receiver) = sync_channel(CHUNKS_IN_ADVANCE);
+        thread::spawn(move || {
+            let mut file = match zip_entry(&mut zip, index, password.as_deref()) {
+                Ok(f) => f,
+                Err(e) => {
+                    let _ = sender.send(Err(io::Error::other(e)));
+                    return;
+                }
+            };
+            loop {
+                let mut chunk = vec![0; CHUNK_SIZE];
+                let res = file.read(&mut chunk).map(|n| {
+                    chunk.truncate(n);
+                    chunk
+                });
+                let end = !matches!(&res, Ok(c) if !c.is_empty());
+                // The reader has been dropped if the send fails
+                if sender.send(res).is_err() || end {
+                    return;
+                }
+            }
+        });
+        Self {
+            receiver,
+            chunk: vec![],
+            pos: 0,
+            finished: false,
+        }
+    }
+}
+
+impl Read for ZipEntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.chunk.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.chunk = self.receiver.recv().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "The decompression of the entry stopped unexpectedly",
+                )
+            })??;
+            self.pos = 0;
+            if self.chunk.is_empty() {
+                self.finished = true;
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl ZipDataSource {
+    /// Entry of the archive at `path`
+    fn entry(&self, path: &Path) -> anyhow::Result<&ZipEntry> {
+        self.entries
+            .get(path)
+            .ok_or_else(|| anyhow!("File {:?} not found in the archive", path))
+    }
+}
+
+impl Debug for ZipDataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZipDataSource")
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+impl DataSource for ZipDataSource {
+    fn file_exists(&self, path: &Path) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// A directory exists in the archive if it contains at least one file
+    fn is_dir(&self, path: &Path) -> bool {
+        self.entries
+            .keys()
+            .any(|p| p != path && p.starts_with(path))
+    }
+
+    fn file_len(&self, path: &Path) -> anyhow::Result<u64> {
+        self.entry(path).map(|e| e.size)
+    }
+
+    fn open_reader(&self, path: &Path) -> anyhow::Result<FileReader> {
+        let entry = self.entry(path)?;
+        Ok(Box::new(BufReader::new(ZipEntryReader::new(
+            self.archive.clone(),
+            entry.index,
+            self.password.clone(),
+        ))))
+    }
+
+    fn read_dir(&self, path: &Path) -> Vec<PathBuf> {
+        self.entries
+            .keys()
+            .filter_map(|p| p.strip_prefix(path).ok())
+            .filter_map(|p| p.components().next())
+            .map(|c| path.join(c))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    fn glob(&self, pattern: &str) -> Vec<PathBuf> {
+        match glob::Pattern::new(pattern) {
+            Ok(p) => self
+                .entries
+                .keys()
+                .filter(|k| p.matches_path(k))
+                .cloned()
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> anyhow::Result<String> {
+        let size = self.entry(path)?.size;
+        let mut content = Vec::with_capacity(size.min(MAX_PREALLOCATION) as usize);
+        self.open_reader(path)?
+            .read_to_end(&mut content)
+            .with_context(|| format!("Cannot extract {:?} from the archive", path))?;
+        String::from_utf8(content).map_err(|e| {
+            anyhow!(e).context(format!("{:?} in the archive is not valid UTF-8", path))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::test::test_dataset_setup_path,
+        file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    };
+    use std::io::Write;
+    use zip::{write::FileOptions, ZipWriter};
+
+    fn write_archive(path: &Path, prefix: &str) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        let name = "electionEventContextPayload.json";
+        zip.start_file(
+            format!("{}{}/{}", prefix, Config::setup_dir_name(), name),
+            FileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(
+            fs::read_to_string(
+                test_dataset_setup_path()
+                    .join(Config::setup_dir_name())
+                    .join(name),
+            )
+            .unwrap()
+            .as_bytes(),
+        )
+        .unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_open() {
+        for (i, prefix) in ["", "dataset/"].iter().enumerate() {
+            let path = std::env::temp_dir().join(format!("rust_verifier_test_zip_{}.zip", i));
+            write_archive(&path, prefix);
+            assert!(is_zip_archive(&path));
+            let dataset = ZipDataset::open(&path, None).unwrap();
+            assert_eq!(dataset.len(), 1);
+            assert_eq!(dataset.root(), path.join(prefix.trim_end_matches('/')));
+            let dir = dataset.verification_directory(&VerificationPeriod::Setup);
+            assert!(dir.unwrap_setup().election_event_context_payload().is_ok());
+            drop(dataset);
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_not_utf8_entry() {
+        let path = std::env::temp_dir().join("rust_verifier_test_zip_not_utf8.zip");
+        write_archive(&path, "");
+        let mut zip = ZipWriter::new_append(
+            fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&path)
+                .unwrap(),
+        )
+        .unwrap();
+        zip.start_file("documents/report.pdf", FileOptions::default())
+            .unwrap();
+        zip.write_all(&[0x25, 0x50, 0xff, 0xfe]).unwrap();
+        zip.finish().unwrap();
+        let dataset = ZipDataset::open(&path, None).unwrap();
+        assert_eq!(dataset.len(), 2);
+        let source = dataset.source();
+        let pdf = path.join("documents/report.pdf");
+        assert_eq!(source.file_len(&pdf).unwrap(), 4);
+        assert!(source.read_to_string(&pdf).is_err());
+        let dir = dataset.verification_directory(&VerificationPeriod::Setup);
+        assert!(dir.unwrap_setup().election_event_context_payload().is_ok());
+        drop(dataset);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_readers() {
+        let path = std::env::temp_dir().join("rust_verifier_test_zip_parallel.zip");
+        write_archive(&path, "");
+        let dataset = ZipDataset::open(&path, None).unwrap();
+        let source = dataset.source();
+        let file = path
+            .join(Config::setup_dir_name())
+            .join("electionEventContextPayload.json");
+        let expected = source.read_to_string(&file).unwrap();
+        let mut first = source.open_reader(&file).unwrap();
+        let mut second = source.open_reader(&file).unwrap();
+        // A reader dropped before the end does not block the other readers
+        drop(source.open_reader(&file).unwrap());
+        let (mut res_first, mut res_second) = (vec![], vec![]);
+        loop {
+            let mut buf = [0; 100];
+            let n_first = first.read(&mut buf).unwrap();
+            res_first.extend_from_slice(&buf[..n_first]);
+            let n_second = second.read(&mut buf).unwrap();
+            res_second.extend_from_slice(&buf[..n_second]);
+            if n_first == 0 && n_second == 0 {
+                break;
+            }
+        }
+        assert_eq!(String::from_utf8(res_first).unwrap(), expected);
+        assert_eq!(String::from_utf8(res_second).unwrap(), expected);
+        assert_eq!(
+            source.read_range(&file, 10, 20).unwrap(),
+            expected[10..30].to_string()
+        );
+        drop((first, second, source));
+        drop(dataset);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_not_zip() {
+        let path = test_dataset_setup_path()
+            .join(Config::setup_dir_name())
+            .join("electionEventContextPayload.json");
+        assert!(!is_zip_archive(&path));
+        assert!(ZipDataset::open(&path, None).is_err());
+    }
+}
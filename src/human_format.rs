@@ -0,0 +1,257 @@
+//! Module implementing the human-readable formatting of durations, sizes, numbers and dates
+//!
+//! The types wrap the raw values. They are displayed in a human-readable form (e.g. `1h 12m`,
+//! `3.4 GiB`) for the console and the reports, and serialized with the raw value and the
+//! formatted value for the json reports.
+//!
+//! The numbers and the dates of the human-readable reports are formatted according to a
+//! [ReportLocale]. The json reports contain the raw values.
+
+use anyhow::bail;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::{fmt::Display, time::Duration};
+
+const SIZE_UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Duration formatted in a human-readable form
+///
+/// - Less than a second: `350ms`
+/// - Less than a minute: `12.3s`
+/// - Less than an hour: `4m 05s`
+/// - Else: `1h 12m`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HumanDuration(pub Duration);
+
+/// Size in bytes formatted in a human-readable form with binary units (e.g. `3.4 GiB`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HumanSize(pub u64);
+
+/// Locale of the numbers and the dates in the human-readable reports
+///
+/// - `en` (default): `1,234,567` and `2024-02-15`
+/// - `de-CH`, `fr-CH`, `it-CH`: `1'234'567` and `15.02.2024`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportLocale {
+    #[default]
+    En,
+    DeCh,
+    FrCh,
+    ItCh,
+}
+
+impl ReportLocale {
+    fn thousands_separator(&self) -> char {
+        match self {
+            Self::En => ',',
+            Self::DeCh | Self::FrCh | Self::ItCh => '\'',
+        }
+    }
+
+    fn date_format(&self) -> &'static str {
+        match self {
+            Self::En => "%Y-%m-%d",
+            Self::DeCh | Self::FrCh | Self::ItCh => "%d.%m.%Y",
+        }
+    }
+
+    /// Format the number with the thousands separator of the locale
+    pub fn format_number(&self, n: u64) -> String {
+        let digits = n.to_string();
+        let mut res = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                res.push(self.thousands_separator());
+            }
+            res.push(c);
+        }
+        res
+    }
+
+    /// Format the date according to the locale
+    pub fn format_date(&self, date: &NaiveDate) -> String {
+        date.format(self.date_format()).to_string()
+    }
+
+    /// Format the date and the time (`HH:MM:SS`) according to the locale
+    pub fn format_datetime(&self, datetime: &NaiveDateTime) -> String {
+        format!(
+            "{} {}",
+            self.format_date(&datetime.date()),
+            datetime.format("%H:%M:%S")
+        )
+    }
+}
+
+impl TryFrom<&str> for ReportLocale {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().replace('_', "-").as_str() {
+            "en" => Ok(Self::En),
+            "de-ch" => Ok(Self::DeCh),
+            "fr-ch" => Ok(Self::FrCh),
+            "it-ch" => Ok(Self::ItCh),
+            _ => bail!(format!(
+                "Cannot read locale from value '{}' (expected en, de-CH, fr-CH or it-CH)",
+                value
+            )),
+        }
+    }
+}
+
+impl Display for ReportLocale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::En => "en",
+            Self::DeCh => "de-CH",
+            Self::FrCh => "fr-CH",
+            Self::ItCh => "it-CH",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Serialize for ReportLocale {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl From<Duration> for HumanDuration {
+    fn from(value: Duration) -> Self {
+        Self(value)
+    }
+}
+
+impl From<u64> for HumanSize {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        if secs == 0 {
+            return write!(f, "{}ms", self.0.as_millis());
+        }
+        if secs < 60 {
+            return write!(f, "{:.1}s", self.0.as_secs_f64());
+        }
+        if secs < 3600 {
+            return write!(f, "{}m {:02}s", secs / 60, secs % 60);
+        }
+        write!(f, "{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+impl Display for HumanSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{:.1} {}", value, SIZE_UNITS[unit])
+    }
+}
+
+impl Serialize for HumanDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("HumanDuration", 2)?;
+        s.serialize_field("seconds", &self.0.as_secs_f64())?;
+        s.serialize_field("formatted", &self.to_string())?;
+        s.end()
+    }
+}
+
+impl Serialize for HumanSize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("HumanSize", 2)?;
+        s.serialize_field("bytes", &self.0)?;
+        s.serialize_field("formatted", &self.to_string())?;
+        s.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(
+            HumanDuration(Duration::from_millis(350)).to_string(),
+            "350ms"
+        );
+        assert_eq!(
+            HumanDuration(Duration::from_millis(12_340)).to_string(),
+            "12.3s"
+        );
+        assert_eq!(
+            HumanDuration(Duration::from_secs(245)).to_string(),
+            "4m 05s"
+        );
+        assert_eq!(
+            HumanDuration(Duration::from_secs(4320 + 59)).to_string(),
+            "1h 12m"
+        );
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!(HumanSize(512).to_string(), "512 B");
+        assert_eq!(HumanSize(1536).to_string(), "1.5 KiB");
+        assert_eq!(HumanSize(3_650_722_201).to_string(), "3.4 GiB");
+    }
+
+    #[test]
+    fn test_locale() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 15).unwrap();
+        let datetime = date.and_hms_opt(21, 11, 5).unwrap();
+        assert_eq!(ReportLocale::default().format_number(1234567), "1,234,567");
+        assert_eq!(ReportLocale::default().format_date(&date), "2024-02-15");
+        assert_eq!(ReportLocale::DeCh.format_number(1234567), "1'234'567");
+        assert_eq!(ReportLocale::FrCh.format_number(123456), "123'456");
+        assert_eq!(ReportLocale::ItCh.format_number(999), "999");
+        assert_eq!(ReportLocale::DeCh.format_number(0), "0");
+        assert_eq!(ReportLocale::DeCh.format_date(&date), "15.02.2024");
+        assert_eq!(
+            ReportLocale::FrCh.format_datetime(&datetime),
+            "15.02.2024 21:11:05"
+        );
+        assert_eq!(ReportLocale::try_from("de-CH").unwrap(), ReportLocale::DeCh);
+        assert_eq!(ReportLocale::try_from("fr_ch").unwrap(), ReportLocale::FrCh);
+        assert_eq!(
+            ReportLocale::try_from(ReportLocale::ItCh.to_string().as_str()).unwrap(),
+            ReportLocale::ItCh
+        );
+        assert!(ReportLocale::try_from("de").is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(
+            serde_json::to_string(&HumanSize(1536)).unwrap(),
+            "{\"bytes\":1536,\"formatted\":\"1.5 KiB\"}"
+        );
+        assert_eq!(
+            serde_json::to_string(&HumanDuration(Duration::from_millis(1500))).unwrap(),
+            "{\"seconds\":1.5,\"formatted\":\"1.5s\"}"
+        );
+    }
+}
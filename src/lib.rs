@@ -1,11 +1,54 @@
 //! Library for all the functionalities of the E-Voting Verifier
+//!
+//! The library code does not panic on a malformed dataset: the problems are returned as errors
+//! and surfaced as errors of the verifications. With the feature `no-panic`, clippy enforces it
+//! (the tests are not concerned).
+//!
+//! Without the feature `native` (default), only the core without file system and keystore is
+//! compiled: the data structures of the spot-checked payloads, the cryptographic helpers, the
+//! proofs and the spot checks (see [spot_check]). The file structure, the verifications, the
+//! runner and the direct trust are native only, as well as the data structures and the helpers
+//! used only by these layers.
+//!
+//! The core does not build for the wasm32 targets: the big integers of the crypto primitives and
+//! of the payloads are GMP integers (crate `rug`), and GMP cannot be built for these targets. The
+//! core has no file system, no keystore and no thread pool, but a browser tool needs a port of
+//! the big integers first.
+
+#![cfg_attr(
+    all(feature = "no-panic", not(test)),
+    deny(
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::todo,
+        clippy::unimplemented
+    )
+)]
 
 mod resources;
 mod consts;
+#[cfg(feature = "native")]
 pub mod application_runner;
 pub mod config;
 mod data_structures;
+#[cfg(feature = "native")]
 pub mod file_structure;
+#[cfg(feature = "native")]
 pub mod verification;
+pub mod spot_check;
+pub mod human_format;
+pub mod memory_stats;
+pub mod proofs;
+pub mod preliminary;
+#[cfg(feature = "native")]
+pub mod prelude;
+#[cfg(all(feature = "native", any(test, feature = "ffi")))]
+pub mod ffi;
+#[cfg(feature = "native")]
 mod direct_trust;
+mod signature;
+mod crypto_helpers;
+#[cfg(feature = "native")]
+mod batch_exponentiation;
 
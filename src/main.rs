@@ -4,46 +4,159 @@
 //! ```shell
 //! rust_verifier_console --help
 //! ```
+//!
+//! The console application uses the public interface of the library crate.
 
-
-mod resources;
-mod consts;
-mod application_runner;
-mod config;
-mod data_structures;
-mod file_structure;
-mod verification;
-mod direct_trust;
-
-use anyhow::bail;
-use application_runner::{
-    check_verification_dir, init_logger, no_action_after_fn, no_action_before_fn, start_check,
-    RunParallel, Runner,
-};
-use config::Config as VerifierConfig;
+use anyhow::{bail, Context};
 use lazy_static::lazy_static;
-use log::{error, info, LevelFilter};
-use std::path::PathBuf;
+use log::{error, info, warn, LevelFilter};
+use rust_verifier::{
+    application_runner::{
+        check_verification_dir, direct_trust_certificate_infos, init_logger,
+        no_action_after_fn, no_action_before_fn, override_schema_from_file,
+        record_fingerprints, start_check, vcs_selection_for_partial_delivery,
+        DateTimeParsingMode, HtmlReport, IncrementalTallyState, PartialReportHandle,
+        RunParallel, Runner, SchemaVersion,
+    },
+    config::{Config as VerifierConfig, StrictnessProfile},
+    file_structure::{
+        data_source::{local_source, DataSource},
+        manifest::Manifest,
+        normalization::NormalizedDataset,
+        zip_archive::{is_zip_archive, ZipDataset},
+    },
+    human_format::ReportLocale,
+    verification::{
+        suite::{ExecutionPolicy, VerificationSelection},
+        VerificationPeriod,
+    },
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
 use structopt::StructOpt;
-use verification::{meta_data::VerificationMetaDataList, VerificationPeriod};
 
 lazy_static! {
     static ref CONFIG: VerifierConfig = VerifierConfig::new(".");
 }
 
+/// Count the allocations to measure the heap used per verification
+#[cfg(feature = "memory-stats")]
+#[global_allocator]
+static GLOBAL: rust_verifier::memory_stats::CountingAllocator =
+    rust_verifier::memory_stats::CountingAllocator;
+
 /// Specification of the sub commands (tally or setup)
 #[derive(Debug, PartialEq, StructOpt)]
 #[structopt()]
 struct VerifierSubCommand {
     #[structopt(short, long, parse(from_os_str))]
     /// Directory where the data are stored
-    /// The directory must contains the subdirectory setup and tally
+    /// The directory must contains the subdirectory setup and tally.
+    /// Can be a ZIP archive of the directory (extension zip)
     dir: PathBuf,
 
+    #[structopt(long, parse(from_os_str))]
+    /// File containing the password of the ZIP archive, if the archive is encrypted
+    zip_password_file: Option<PathBuf>,
+
     #[structopt(long)]
     /// Exclusion of verifications.
-    /// Use the id of the verification. Many separated by blanks. E.g. --exclude 02.02 05.05
+    /// Use the id of the verification. Many separated by blanks. E.g. --exclude 02.02 X.05.01
     exclude: Vec<String>,
+
+    #[structopt(long)]
+    /// Run only the selected verifications. Use the id of the verification or the category
+    /// (authenticity, consistency, completness, integrity or evidence). Many separated by blanks.
+    /// E.g. --only authenticity 05.01
+    only: Vec<String>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File with the fingerprints recorded for a previous delivery of the setup.
+    /// Only the VCS directories that changed since are verified (partial delivery)
+    previous_fingerprints: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File where the fingerprints of the delivery are recorded after the verifications
+    record_fingerprints: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File with the state of the incremental verification of the tally. Only the ballot boxes
+    /// added or changed since the last run are verified, and the report is cumulative.
+    /// The state is updated after the run
+    incremental_tally: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File where the report of the verifications is written (json format if the extension
+    /// is json, csv format if the extension is csv, text format else)
+    json_report: Option<PathBuf>,
+
+    #[structopt(long)]
+    /// Interval in seconds to write the partial report (current results) during the run to the
+    /// file of the report. Requires --json-report
+    partial_report_interval: Option<u64>,
+
+    #[structopt(long)]
+    /// Locale of the numbers and the dates in the text and csv reports (en, de-CH, fr-CH or
+    /// it-CH). Default: en
+    locale: Option<String>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File where the report of the verifications is written as standalone HTML (summary,
+    /// details per verification, fingerprint of the dataset and direct trust certificates)
+    html_report: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File where the verifications per data file are written (json format if the extension
+    /// is json, text format else)
+    file_report: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File where the result of each verification is persisted as soon as it is finished
+    checkpoint: Option<PathBuf>,
+
+    #[structopt(long)]
+    /// Resume the run from the checkpoint: the verifications already completed are not run again
+    /// and their results are taken over. Requires --checkpoint
+    resume: bool,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File where the manifest (size and SHA-256 hash of all the files of the dataset) is
+    /// written before the verifications
+    manifest: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// Manifest written by the verification of the setup. The files of the setup that changed
+    /// since are reported
+    setup_manifest: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// XSD overriding the embedded XSD with the same target namespace (e.g. a newer minor
+    /// version of an eCH standard). Many separated by blanks
+    xsd: Vec<PathBuf>,
+
+    #[structopt(long)]
+    /// Stop the run after the first verification with errors (e.g. a file cannot be read).
+    /// The failures do not stop the run
+    fail_fast: bool,
+
+    #[structopt(long, parse(from_os_str))]
+    /// File (json) containing the strictness profile, i.e. the tolerances of the plausibility
+    /// checks. Per default, the checks have no tolerance. The relaxations are listed in the
+    /// report
+    strictness_profile: Option<PathBuf>,
+
+    #[structopt(long)]
+    /// Reject the datetimes that are not in the format of the version of the dataset.
+    /// Per default, they are accepted with a warning
+    strict_datetime: bool,
 }
 
 /// Enum with the possible subcommands
@@ -59,6 +172,11 @@ enum SubCommands {
     /// Tally Verification
     /// Verify the tally configuration
     Tally(VerifierSubCommand),
+
+    #[structopt()]
+    /// Verification with detection of the period
+    /// Detect the period (setup or tally) from the dataset and verify it
+    Auto(VerifierSubCommand),
 }
 
 /// Main command
@@ -71,20 +189,44 @@ struct VerifiyCommand {
     sub: SubCommands,
 }
 
-impl From<&SubCommands> for VerificationPeriod {
-    fn from(value: &SubCommands) -> Self {
-        match value {
-            SubCommands::Setup(_) => VerificationPeriod::Setup,
-            SubCommands::Tally(_) => VerificationPeriod::Tally,
-        }
-    }
-}
-
 impl SubCommands {
     fn verifier_sub_command(&self) -> &VerifierSubCommand {
         match self {
             SubCommands::Setup(c) => c,
             SubCommands::Tally(c) => c,
+            SubCommands::Auto(c) => c,
+        }
+    }
+
+    /// The period given by the sub command. `None` if the period has to be detected
+    fn period(&self) -> Option<VerificationPeriod> {
+        match self {
+            SubCommands::Setup(_) => Some(VerificationPeriod::Setup),
+            SubCommands::Tally(_) => Some(VerificationPeriod::Tally),
+            SubCommands::Auto(_) => None,
+        }
+    }
+}
+
+/// Write the partial report to `path` at each interval, until the sender of `stop` is dropped
+fn write_partial_reports(
+    handle: PartialReportHandle,
+    path: PathBuf,
+    locale: ReportLocale,
+    interval: Duration,
+    stop: Receiver<()>,
+) {
+    while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(interval) {
+        if let Some(report) = handle.report() {
+            match report.with_locale(locale).save(&path) {
+                Ok(_) => info!(
+                    "Partial report written to {:?} ({} of {} verifications run)",
+                    path,
+                    report.summary.total - report.summary.not_run,
+                    report.summary.total
+                ),
+                Err(e) => warn!("{:#}", e),
+            }
         }
     }
 }
@@ -92,21 +234,200 @@ impl SubCommands {
 /// Execute the runner for a given period
 /// 
 /// # Argument
-/// * `period`: The Verification Period
+/// * `period`: The Verification Period. If `None`, the period is detected from the dataset
 /// * `cmd`: The [VerifierSubCommand] containung the necessary information to run the test
-fn execute_runner(period: &VerificationPeriod, cmd: &VerifierSubCommand) {
-    let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
-    let mut runner = Runner::new(
-        &cmd.dir,
-        period,
-        &metadata,
-        &cmd.exclude,
-        RunParallel,
-        &CONFIG,
-        no_action_before_fn,
-        no_action_after_fn,
-    );
-    runner.run_all(&metadata);
+/// * `dir`: The directory of the dataset (the directory in memory if `cmd.dir` is a ZIP archive)
+/// * `source`: The source from which the files of the dataset are read
+fn execute_runner(
+    period: Option<&VerificationPeriod>,
+    cmd: &VerifierSubCommand,
+    dir: &Path,
+    source: Arc<dyn DataSource>,
+) -> anyhow::Result<()> {
+    let metadata = CONFIG.verification_metadata()?;
+    let locale = match &cmd.locale {
+        Some(l) => ReportLocale::try_from(l.as_str())?,
+        None => ReportLocale::default(),
+    };
+    if cmd.strict_datetime {
+        CONFIG.set_datetime_parsing_mode(DateTimeParsingMode::Strict)?;
+    }
+    for p in cmd.xsd.iter() {
+        let kind = override_schema_from_file(SchemaVersion::default(), p)?;
+        warn!("Schema {:?} overridden by {:?}", kind, p);
+    }
+    if let Some(p) = &cmd.strictness_profile {
+        CONFIG.set_strictness(StrictnessProfile::load(p)?)?;
+        info!("Strictness profile {:?} used", p);
+    }
+    let mut runner = match period {
+        Some(p) => Runner::new_with_source(
+            dir,
+            source.clone(),
+            p,
+            metadata,
+            &cmd.exclude,
+            RunParallel::default(),
+            &CONFIG,
+            no_action_before_fn,
+            no_action_after_fn,
+        )?,
+        None => Runner::new_with_detected_period(
+            dir,
+            source.clone(),
+            metadata,
+            &cmd.exclude,
+            RunParallel::default(),
+            &CONFIG,
+            no_action_before_fn,
+            no_action_after_fn,
+        )?,
+    };
+    if !cmd.only.is_empty() {
+        runner.select(&VerificationSelection::from_values(&cmd.only))?;
+    }
+    let period = *runner.period();
+    let verification_dir = runner.verification_directory();
+    info!("Start Verifier for {}", period);
+    if cmd.fail_fast {
+        runner.set_execution_policy(ExecutionPolicy::FailFast);
+    }
+    runner.set_journal_file(&CONFIG.journal_file_path())?;
+    match (&cmd.checkpoint, cmd.resume) {
+        (Some(p), resume) => {
+            let restored = runner.set_checkpoint_file(p, resume)?;
+            info!("Checkpoint {:?} used ({} verifications restored)", p, restored);
+        }
+        (None, true) => bail!("A run can only be resumed with a checkpoint (--checkpoint)"),
+        (None, false) => (),
+    }
+    match (&cmd.previous_fingerprints, period) {
+        (Some(p), VerificationPeriod::Setup) => {
+            if let Some(names) = vcs_selection_for_partial_delivery(&verification_dir, p)? {
+                runner.set_vcs_selection(names);
+            }
+        }
+        (Some(_), VerificationPeriod::Tally) => {
+            bail!("A partial delivery can only be verified for the setup")
+        }
+        (None, _) => (),
+    }
+    let incremental = match (&cmd.incremental_tally, period) {
+        (Some(p), VerificationPeriod::Tally) => {
+            let state = IncrementalTallyState::load(p)?;
+            let selection = state.ballot_box_selection(&verification_dir)?;
+            if let Some(names) = &selection {
+                runner.set_bb_selection(names.clone());
+            }
+            Some((p, state, selection))
+        }
+        (Some(_), VerificationPeriod::Setup) => {
+            bail!("The incremental verification is only possible for the tally")
+        }
+        (None, _) => None,
+    };
+    // The manifest gives the fingerprint of the dataset in the html report
+    let manifest = match cmd.manifest.is_some()
+        || cmd.setup_manifest.is_some()
+        || cmd.html_report.is_some()
+    {
+        true => Some(Manifest::compute(source.as_ref(), dir, &period)?),
+        false => None,
+    };
+    if let Some(manifest) = &manifest {
+        for f in manifest.unknown_files() {
+            warn!("Unknown file in the dataset (ignored): {}", f);
+        }
+        if let Some(p) = &cmd.manifest {
+            manifest.save(p)?;
+            info!(
+                "Manifest of {} files written to {:?} (digest {})",
+                manifest.len(),
+                p,
+                manifest.digest
+            );
+        }
+        if let Some(p) = &cmd.setup_manifest {
+            let diff = manifest.setup_diff(&Manifest::load(p)?);
+            if diff.is_empty() {
+                info!("The files of the setup did not change since {:?}", p);
+            }
+            for s in diff.to_strings() {
+                warn!("Setup changed since {:?}: {}", p, s);
+            }
+        }
+    }
+    let partial_reports = match (cmd.partial_report_interval, &cmd.json_report) {
+        (Some(secs), Some(p)) => {
+            let (stop, stopped) = mpsc::channel();
+            let (handle, path) = (runner.partial_report_handle(), p.clone());
+            let interval = Duration::from_secs(secs.max(1));
+            let writer = thread::spawn(move || {
+                write_partial_reports(handle, path, locale, interval, stopped)
+            });
+            Some((stop, writer))
+        }
+        (Some(_), None) => bail!("The partial report is written to the file of --json-report"),
+        (None, _) => None,
+    };
+    runner.run_all(metadata);
+    if let Some((stop, writer)) = partial_reports {
+        drop(stop);
+        let _ = writer.join();
+    }
+    // For the incremental tally, the report is cumulative
+    let report = match cmd.json_report.is_some()
+        || cmd.html_report.is_some()
+        || incremental.is_some()
+    {
+        true => {
+            let report = runner.report().with_locale(locale);
+            Some(match &incremental {
+                Some((_, state, selection)) => {
+                    state.cumulative_report(report, selection.as_deref(), &verification_dir)
+                }
+                None => report,
+            })
+        }
+        false => None,
+    };
+    if let (Some(p), Some(report)) = (&cmd.json_report, &report) {
+        report.save(p)?;
+        info!("Report written to {:?}", p);
+    }
+    if let (Some(p), Some(report)) = (&cmd.html_report, &report) {
+        let mut html = HtmlReport::new(report)
+            .with_certificates(direct_trust_certificate_infos(&CONFIG));
+        if let Some(m) = &manifest {
+            html = html.with_fingerprint(&m.digest);
+        }
+        html.save(p)?;
+        info!("HTML report written to {:?}", p);
+    }
+    if let Some(p) = &cmd.file_report {
+        runner.file_access_report().save(p)?;
+        info!("File report written to {:?}", p);
+    }
+    if let Some(p) = &cmd.record_fingerprints {
+        record_fingerprints(&verification_dir, p)?;
+    }
+    if let (Some((p, mut state, _)), Some(report)) = (incremental, &report) {
+        match state.update(&verification_dir, report, CONFIG.verification_metadata()?)? {
+            true => {
+                state.save(p)?;
+                info!(
+                    "State of the incremental tally written to {:?} ({} ballot boxes verified)",
+                    p,
+                    state.ballot_boxes.len()
+                );
+            }
+            false => warn!(
+                "The state of the incremental tally {:?} is not updated, since the run has errors or is not finished",
+                p
+            ),
+        }
+    }
+    Ok(())
 }
 
 /// Execute the verifier
@@ -120,20 +441,52 @@ fn execute_verifier() -> anyhow::Result<()> {
         bail!("Application cannot start: {}", e);
     };
     let command = VerifiyCommand::from_args();
-    let period = VerificationPeriod::from(&command.sub);
+    let period = command.sub.period();
     let sub_command = command.sub.verifier_sub_command();
-    info!("Start Verifier for {}", period);
-    if let Err(e) = check_verification_dir(&period, &sub_command.dir) {
-        bail!("Application cannot start: {}", e);
-    } else {
-        execute_runner(&period, sub_command);
+    let zip_dataset = match is_zip_archive(&sub_command.dir) {
+        true => {
+            let password = match &sub_command.zip_password_file {
+                Some(p) => Some(
+                    fs::read_to_string(p)
+                        .with_context(|| format!("Cannot read the password in {:?}", p))?,
+                ),
+                None => None,
+            };
+            let dataset = ZipDataset::open(
+                &sub_command.dir,
+                password.as_ref().map(|p| p.trim_end().as_bytes()),
+            )?;
+            info!(
+                "{} files found in the archive {:?}",
+                dataset.len(),
+                sub_command.dir
+            );
+            Some(dataset)
+        }
+        false => None,
+    };
+    let (input, source) = match &zip_dataset {
+        Some(d) => (d.root().to_path_buf(), d.source()),
+        None => (sub_command.dir.clone(), local_source()),
+    };
+    // The nested layouts and the names with another casing are mapped to the canonical layout
+    let normalized = NormalizedDataset::open(&input, source);
+    normalized.report().log();
+    let dir = normalized.root().to_path_buf();
+    if let Some(p) = &period {
+        if let Err(e) = check_verification_dir(p, normalized.source().as_ref(), &dir) {
+            bail!("Application cannot start: {}", e);
+        }
     }
+    execute_runner(period.as_ref(), sub_command, &dir, normalized.source())?;
     info!("Verifier finished");
     Ok(())
 }
 
 fn main() {
-    init_logger(&CONFIG, LevelFilter::Debug, true);
+    if let Err(e) = init_logger(&CONFIG, LevelFilter::Debug, true) {
+        eprintln!("{:#}", e)
+    }
     if let Err(e) = execute_verifier() {
         error!("{}", e)
     }
@@ -0,0 +1,282 @@
+//! Module implementing the instrumentation of the memory used by the verifications
+//!
+//! The heap is measured with the [CountingAllocator], a wrapper of the system allocator counting
+//! the allocations. The console application registers it as global allocator with the feature
+//! `memory-stats`. The embedders can register it in their application:
+//! ```ignore
+//! #[global_allocator]
+//! static GLOBAL: CountingAllocator = CountingAllocator;
+//! ```
+//! Without the allocator, only the resident set size (RSS) of the process is measured (on Linux,
+//! read from `/proc/self/status`).
+//!
+//! The memory used during a verification is measured with a [MemoryProbe]. The verifications
+//! running in parallel share the heap: their measures include the allocations of the other
+//! verifications running at the same time. The verifications that need streaming-mode
+//! improvements are identified reliably with a sequential run.
+
+use crate::human_format::HumanSize;
+use serde::Serialize;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Display,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+/// Maximal number of probes measuring the peak of the heap at the same time
+const MAX_PROBES: usize = 64;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const PEAK_INIT: AtomicU64 = AtomicU64::new(0);
+
+/// The counting allocator is registered (at least one allocation counted)
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Bytes of the heap in use
+static IN_USE: AtomicU64 = AtomicU64::new(0);
+/// Number of allocations since the start of the process
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+/// Bytes allocated since the start of the process
+static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+/// Bit mask of the slots of the probes in use
+static PROBE_SLOTS: AtomicU64 = AtomicU64::new(0);
+/// Peak of the heap in use per slot of probe
+static PROBE_PEAKS: [AtomicU64; MAX_PROBES] = [PEAK_INIT; MAX_PROBES];
+
+/// Allocator counting the allocations, wrapping the system allocator
+pub struct CountingAllocator;
+
+/// Count the allocation of `size` bytes and update the peaks of the probes
+fn record_allocation(size: u64) {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        ACTIVE.store(true, Ordering::Relaxed);
+    }
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED.fetch_add(size, Ordering::Relaxed);
+    let in_use = IN_USE.fetch_add(size, Ordering::Relaxed) + size;
+    let mut slots = PROBE_SLOTS.load(Ordering::Relaxed);
+    while slots != 0 {
+        PROBE_PEAKS[slots.trailing_zeros() as usize].fetch_max(in_use, Ordering::Relaxed);
+        slots &= slots - 1;
+    }
+}
+
+fn record_deallocation(size: u64) {
+    IN_USE.fetch_sub(size, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_allocation(layout.size() as u64);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_allocation(layout.size() as u64);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record_deallocation(layout.size() as u64);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            match new_size >= layout.size() {
+                true => record_allocation((new_size - layout.size()) as u64),
+                false => record_deallocation((layout.size() - new_size) as u64),
+            }
+        }
+        new_ptr
+    }
+}
+
+/// The [CountingAllocator] is registered as global allocator
+pub fn is_heap_counted() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Memory used during a measure (e.g. a verification)
+///
+/// The values of the heap are `None` if the [CountingAllocator] is not registered. The values of
+/// the RSS are `None` if they cannot be read (not on Linux).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryStats {
+    /// Peak of the heap in use during the measure, above the heap in use at its start
+    pub peak_heap: Option<HumanSize>,
+    /// Bytes allocated during the measure (sum of all the allocations)
+    pub allocated: Option<HumanSize>,
+    /// Number of allocations during the measure (including the reallocations growing a block)
+    pub allocations: Option<u64>,
+    /// Resident set size of the process at the end of the measure
+    pub rss: Option<HumanSize>,
+    /// Peak resident set size of the process since its start, at the end of the measure
+    pub peak_rss: Option<HumanSize>,
+}
+
+/// Probe measuring the memory used from its start until [MemoryProbe::stop]
+///
+/// At most 64 probes measure the peak of the heap at the same time. The other probes measure
+/// only the allocations.
+pub struct MemoryProbe {
+    slot: Option<usize>,
+    in_use: u64,
+    allocations: u64,
+    allocated: u64,
+}
+
+/// Take a free slot for the peak of the heap. `None` if all slots are taken
+fn acquire_slot() -> Option<usize> {
+    let mut slots = PROBE_SLOTS.load(Ordering::Relaxed);
+    loop {
+        let slot = slots.trailing_ones() as usize;
+        if slot >= MAX_PROBES {
+            return None;
+        }
+        match PROBE_SLOTS.compare_exchange_weak(
+            slots,
+            slots | (1 << slot),
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => {
+                PROBE_PEAKS[slot].store(IN_USE.load(Ordering::Relaxed), Ordering::Relaxed);
+                return Some(slot);
+            }
+            Err(current) => slots = current,
+        }
+    }
+}
+
+fn release_slot(slot: usize) {
+    PROBE_SLOTS.fetch_and(!(1 << slot), Ordering::AcqRel);
+}
+
+/// Read the value in kB of the field of `/proc/self/status` (e.g. `VmRSS`) in bytes
+#[cfg(target_os = "linux")]
+fn proc_status_bytes(status: &str, field: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix(field)?.strip_prefix(':'))
+        .and_then(|v| v.trim().strip_suffix("kB"))
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// RSS and peak RSS of the process
+#[cfg(target_os = "linux")]
+fn process_rss() -> (Option<u64>, Option<u64>) {
+    match std::fs::read_to_string("/proc/self/status") {
+        Ok(s) => (
+            proc_status_bytes(&s, "VmRSS"),
+            proc_status_bytes(&s, "VmHWM"),
+        ),
+        Err(_) => (None, None),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_rss() -> (Option<u64>, Option<u64>) {
+    (None, None)
+}
+
+impl MemoryProbe {
+    /// Start a measure
+    pub fn start() -> Self {
+        let slot = match is_heap_counted() {
+            true => acquire_slot(),
+            false => None,
+        };
+        Self {
+            slot,
+            in_use: IN_USE.load(Ordering::Relaxed),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            allocated: ALLOCATED.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Stop the measure and return the memory used since the start
+    pub fn stop(mut self) -> MemoryStats {
+        let counted = is_heap_counted();
+        let peak_heap = self.slot.take().map(|slot| {
+            let peak = PROBE_PEAKS[slot].load(Ordering::Relaxed);
+            release_slot(slot);
+            HumanSize(peak.saturating_sub(self.in_use))
+        });
+        let (rss, peak_rss) = process_rss();
+        MemoryStats {
+            peak_heap,
+            allocated: counted.then(|| {
+                HumanSize(
+                    ALLOCATED
+                        .load(Ordering::Relaxed)
+                        .saturating_sub(self.allocated),
+                )
+            }),
+            allocations: counted.then(|| {
+                ALLOCATIONS
+                    .load(Ordering::Relaxed)
+                    .saturating_sub(self.allocations)
+            }),
+            rss: rss.map(HumanSize),
+            peak_rss: peak_rss.map(HumanSize),
+        }
+    }
+}
+
+impl Drop for MemoryProbe {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            release_slot(slot)
+        }
+    }
+}
+
+impl Display for MemoryStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(s) = &self.peak_heap {
+            parts.push(format!("peak heap {}", s));
+        }
+        if let (Some(s), Some(n)) = (&self.allocated, &self.allocations) {
+            parts.push(format!("allocated {} ({} allocations)", s, n));
+        }
+        if let Some(s) = &self.rss {
+            parts.push(format!("RSS {}", s));
+        }
+        if let Some(s) = &self.peak_rss {
+            parts.push(format!("peak RSS {}", s));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_slots() {
+        let probes: Vec<MemoryProbe> = (0..MAX_PROBES + 1).map(|_| MemoryProbe::start()).collect();
+        assert!(probes.last().unwrap().slot.is_none());
+        drop(probes);
+        let probe = MemoryProbe::start();
+        assert!(probe.slot.is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_proc_status_bytes() {
+        let status = "Name:\ttoto\nVmHWM:\t  2048 kB\nVmRSS:\t  1024 kB\n";
+        assert_eq!(proc_status_bytes(status, "VmRSS"), Some(1024 * 1024));
+        assert_eq!(proc_status_bytes(status, "VmHWM"), Some(2048 * 1024));
+        assert_eq!(proc_status_bytes(status, "VmSwap"), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Module exposing the re-derivation of the encryption group from the seed of the election event
+//!
+//! The encryption group (p, q, g) is derived from the seed (algorithm GetEncryptionParameters)
+//! and the small primes are the first small prime group members of the group (algorithm
+//! GetSmallPrimeGroupMembers). The functions recompute them and compare them with the values of
+//! a payload (e.g. the election event context), without reading the directories. The
+//! verifications 05.01 and 05.02 use the same functions.
+
+use crate::config::Config;
+use anyhow::{anyhow, Context};
+use rug::Integer;
+use rust_ev_crypto_primitives::EncryptionParameters;
+use std::fmt::Display;
+
+/// Comparison of a value of the payload with the recomputed value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueComparison<T> {
+    /// Value of the payload
+    pub payload: T,
+    /// Value recomputed
+    pub calculated: T,
+}
+
+/// Comparison of the encryption group of the payload with the group derived from the seed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptionGroupComparison {
+    pub p: ValueComparison<Integer>,
+    pub q: ValueComparison<Integer>,
+    pub g: ValueComparison<Integer>,
+}
+
+/// Comparison of the small primes of the payload with the recomputed small primes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmallPrimesComparison {
+    /// Number of small primes of the payload and number of recomputed small primes
+    pub len: ValueComparison<usize>,
+    /// Position (starting at 0) and values of the first different small prime. `None` if the
+    /// common part is equal
+    pub first_difference: Option<(usize, ValueComparison<usize>)>,
+}
+
+/// Result of the re-derivation from the seed, compared with the values of the payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptionParametersComparison {
+    pub seed: String,
+    pub encryption_group: EncryptionGroupComparison,
+    /// Small primes recomputed from the group derived from the seed
+    pub small_primes: SmallPrimesComparison,
+}
+
+impl<T: PartialEq> ValueComparison<T> {
+    pub fn new(payload: T, calculated: T) -> Self {
+        Self {
+            payload,
+            calculated,
+        }
+    }
+
+    /// The value of the payload is the recomputed value
+    pub fn is_equal(&self) -> bool {
+        self.payload == self.calculated
+    }
+}
+
+impl EncryptionGroupComparison {
+    /// Compare the group of the payload with the recomputed group
+    pub fn new(payload: &EncryptionParameters, calculated: &EncryptionParameters) -> Self {
+        Self {
+            p: ValueComparison::new(payload.p().clone(), calculated.p().clone()),
+            q: ValueComparison::new(payload.q().clone(), calculated.q().clone()),
+            g: ValueComparison::new(payload.g().clone(), calculated.g().clone()),
+        }
+    }
+
+    /// Names and comparisons of the elements of the group (p, q and g)
+    pub fn elements(&self) -> [(&'static str, &ValueComparison<Integer>); 3] {
+        [("p", &self.p), ("q", &self.q), ("g", &self.g)]
+    }
+
+    /// Names of the elements of the group that are different
+    pub fn different_elements(&self) -> Vec<&'static str> {
+        self.elements()
+            .into_iter()
+            .filter(|(_, c)| !c.is_equal())
+            .map(|(n, _)| n)
+            .collect()
+    }
+
+    /// The group of the payload is the recomputed group
+    pub fn is_equal(&self) -> bool {
+        self.different_elements().is_empty()
+    }
+}
+
+impl SmallPrimesComparison {
+    /// Compare the small primes of the payload with the recomputed small primes
+    pub fn new(payload: &[usize], calculated: &[usize]) -> Self {
+        Self {
+            len: ValueComparison::new(payload.len(), calculated.len()),
+            first_difference: payload
+                .iter()
+                .zip(calculated.iter())
+                .position(|(p, c)| p != c)
+                .map(|i| (i, ValueComparison::new(payload[i], calculated[i]))),
+        }
+    }
+
+    /// The small primes of the payload are the recomputed small primes
+    pub fn is_equal(&self) -> bool {
+        self.len.is_equal() && self.first_difference.is_none()
+    }
+}
+
+impl EncryptionParametersComparison {
+    /// The values of the payload are the values derived from the seed
+    pub fn is_equal(&self) -> bool {
+        self.encryption_group.is_equal() && self.small_primes.is_equal()
+    }
+}
+
+/// Derive the encryption group from the seed (algorithm GetEncryptionParameters)
+pub fn derive_encryption_group(seed: &str) -> anyhow::Result<EncryptionParameters> {
+    EncryptionParameters::get_encryption_parameters(&seed.to_string())
+        .map_err(|e| anyhow!(e))
+        .with_context(|| format!("Error calculating encryption parameters from seed {}", seed))
+}
+
+/// Compute the small prime group members of the group (algorithm GetSmallPrimeGroupMembers)
+///
+/// The number of small primes is the maximum number of voting options
+pub fn derive_small_primes(eg: &EncryptionParameters) -> anyhow::Result<Vec<usize>> {
+    eg.get_small_prime_group_members(Config::maximum_number_of_voting_options())
+        .map_err(|e| anyhow!(e))
+        .context("Error getting small prime group members")
+}
+
+/// Re-derive the encryption group and the small primes from the seed and compare them with the
+/// group `eg` and the small primes `small_primes` of the payload
+///
+/// An error is returned only if the values cannot be computed. The differences are given in the
+/// comparison (see [EncryptionParametersComparison::is_equal])
+pub fn rederive_encryption_parameters(
+    seed: &str,
+    eg: &EncryptionParameters,
+    small_primes: &[usize],
+) -> anyhow::Result<EncryptionParametersComparison> {
+    let calculated_eg = derive_encryption_group(seed)?;
+    let calculated_primes = derive_small_primes(&calculated_eg)?;
+    Ok(EncryptionParametersComparison {
+        seed: seed.to_string(),
+        encryption_group: EncryptionGroupComparison::new(eg, &calculated_eg),
+        small_primes: SmallPrimesComparison::new(small_primes, &calculated_primes),
+    })
+}
+
+impl<T: Display> Display for ValueComparison<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "payload: {} / calculated: {}",
+            self.payload, self.calculated
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::test::get_test_verifier_setup_dir as get_verifier_dir,
+        file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    };
+
+    #[test]
+    fn test_rederive_encryption_parameters() {
+        let eec = get_verifier_dir()
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        let comparison =
+            rederive_encryption_parameters(&eec.seed, &eec.encryption_group, &eec.small_primes)
+                .unwrap();
+        assert!(comparison.is_equal());
+        let comparison = rederive_encryption_parameters(
+            &eec.seed,
+            &eec.encryption_group,
+            &eec.small_primes[1..],
+        )
+        .unwrap();
+        assert!(comparison.encryption_group.is_equal());
+        assert!(!comparison.small_primes.is_equal());
+        assert!(!comparison.small_primes.len.is_equal());
+        assert_eq!(comparison.small_primes.first_difference.unwrap().0, 0);
+    }
+
+    #[test]
+    fn test_encryption_group_comparison() {
+        let eec = get_verifier_dir()
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        let mut eg = eec.encryption_group.clone();
+        eg.set_q(&Integer::from(5usize));
+        let comparison = EncryptionGroupComparison::new(&eg, &eec.encryption_group);
+        assert!(!comparison.is_equal());
+        assert_eq!(comparison.different_elements(), vec!["q"]);
+        assert_eq!(
+            comparison.q.to_string(),
+            format!("payload: 5 / calculated: {}", eec.encryption_group.q())
+        );
+    }
+
+    #[test]
+    fn test_small_primes_comparison() {
+        assert!(SmallPrimesComparison::new(&[5, 7, 11], &[5, 7, 11]).is_equal());
+        let c = SmallPrimesComparison::new(&[5, 7, 13], &[5, 7, 11]);
+        assert!(c.len.is_equal());
+        assert_eq!(c.first_difference, Some((2, ValueComparison::new(13, 11))));
+        let c = SmallPrimesComparison::new(&[5, 7], &[5, 7, 11]);
+        assert!(!c.is_equal());
+        assert!(c.first_difference.is_none());
+    }
+}
@@ -0,0 +1,52 @@
+//! Module re-exporting the types commonly needed by the applications embedding the verifier
+//!
+//! ```ignore
+//! use rust_verifier::prelude::*;
+//! ```
+//!
+//! The applications should use the prelude instead of the paths of the modules, that can be
+//! reorganized.
+
+pub use crate::{
+    application_runner::{
+        no_action_after_fn, no_action_before_fn, CategoryParallelism, ReportPostProcessor,
+        RunParallel, RunReport, Runner, RunnerEvent, RunnerEventKind,
+    },
+    config::Config,
+    file_structure::{
+        setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectory, VerificationDirectoryTrait,
+    },
+    verification::{
+        manual::ManualCheck,
+        meta_data::{VerificationMetaData, VerificationMetaDataList},
+        query::{ResultQuery, ResultStatus},
+        result::{
+            Entity, EntityKind, FailedItem, VerificationEvent, VerificationResult,
+            VerificationResultTrait,
+        },
+        suite::{ExecutionPolicy, VerificationSuite},
+        verifications::{RunObserver, Verification},
+        VerificationCategory, VerificationContext, VerificationPeriod, VerificationStatus,
+    },
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::test::{test_dataset_setup_path, CONFIG_TEST};
+
+    #[test]
+    fn test_prelude() {
+        let metadata =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let suite =
+            VerificationSuite::new(&VerificationPeriod::Setup, &metadata, &[], &CONFIG_TEST)
+                .unwrap();
+        assert!(!suite.list.0.is_empty());
+        let dir =
+            VerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        assert!(!dir.unwrap_setup().vcs_directories().is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+//! Module exposing the verification of the individual zero-knowledge proofs of the protocol
+//!
+//! The functions can be used to verify a proof extracted from a dataset without reading the
+//! directories. The auxiliary information `i_aux` is constructed exactly as in the verifications,
+//! using [SchnorrProofContext], [DecryptionProofContext], [exponentiation_pcc_i_aux] and
+//! [create_vote_i_aux]. The functions taking `i_aux` explicitly verify a proof with any auxiliary
+//! information.
+//!
+//! The inputs are [rug::Integer] and the types of the payloads, that can be deserialized from
+//! the json payloads of the dataset.
+
+pub use crate::crypto_helpers::{
+    create_vote_i_aux, exponentiation_pcc_bases_and_images, exponentiation_pcc_i_aux,
+    verify_decryption, verify_plaintext_equality, DecryptionProofContext,
+    PlaintextEqualityStatement, SchnorrProofContext,
+};
+pub use crate::data_structures::common_types::{
+    DecryptionProof, ExponentiatedEncryptedElement, PlaintextEqualityProof, Proof,
+};
+use anyhow::anyhow;
+use rug::Integer;
+use rust_ev_crypto_primitives::{verify_exponentiation, verify_schnorr, EncryptionParameters};
+
+/// Verify the Schnorr proof of knowledge of the secret key of the public key `pk`
+/// (algorithm VerifySchnorrProof) with the auxiliary information `i_aux`
+pub fn verify_schnorr_proof(
+    eg: &EncryptionParameters,
+    proof: &Proof,
+    pk: &Integer,
+    i_aux: &[String],
+) -> anyhow::Result<bool> {
+    verify_schnorr(eg, proof.as_tuple(), pk, &i_aux.to_vec()).map_err(|e| anyhow!(e))
+}
+
+/// Verify the Schnorr proof of a key generated in the election event `ee_id`
+///
+/// The auxiliary information is given by the context of the key
+pub fn verify_key_schnorr_proof(
+    eg: &EncryptionParameters,
+    proof: &Proof,
+    pk: &Integer,
+    ee_id: &str,
+    context: &SchnorrProofContext,
+) -> anyhow::Result<bool> {
+    verify_schnorr_proof(eg, proof, pk, &context.i_aux(ee_id))
+}
+
+/// Verify the exponentiation proof that the `ys` are the `gs` exponentiated with the same
+/// secret exponent (algorithm VerifyExponentiation) with the auxiliary information `i_aux`
+pub fn verify_exponentiation_proof(
+    eg: &EncryptionParameters,
+    gs: &[Integer],
+    ys: &[Integer],
+    proof: &Proof,
+    i_aux: &[String],
+) -> anyhow::Result<bool> {
+    verify_exponentiation(
+        eg,
+        &gs.to_vec(),
+        &ys.to_vec(),
+        proof.as_tuple(),
+        &i_aux.to_vec(),
+    )
+    .map_err(|e| anyhow!(e))
+}
+
+/// Verify the exponentiation proof of the encrypted partial choice return codes of the
+/// verification card `vc_id`, calculated by the control component `node_id`
+///
+/// `k_j_id` is the voter choice return code generation public key of the verification card
+#[allow(clippy::too_many_arguments)]
+pub fn verify_pcc_exponentiation_proof(
+    eg: &EncryptionParameters,
+    encrypted_pcc: &ExponentiatedEncryptedElement,
+    k_j_id: &Integer,
+    exponentiated_pcc: &ExponentiatedEncryptedElement,
+    proof: &Proof,
+    ee_id: &str,
+    vc_id: &str,
+    node_id: usize,
+) -> anyhow::Result<bool> {
+    let (gs, ys) =
+        exponentiation_pcc_bases_and_images(eg, encrypted_pcc, k_j_id, exponentiated_pcc);
+    verify_exponentiation_proof(
+        eg,
+        &gs,
+        &ys,
+        proof,
+        &exponentiation_pcc_i_aux(ee_id, vc_id, node_id),
+    )
+}
+
+/// Verify the decryption proof of a ciphertext of the ballot box `bb_id` in the election
+/// event `ee_id`
+///
+/// The auxiliary information is given by the context of the decryption. See [verify_decryption]
+/// for the other inputs
+#[allow(clippy::too_many_arguments)]
+pub fn verify_mixing_decryption_proof(
+    eg: &EncryptionParameters,
+    ciphertext: &ExponentiatedEncryptedElement,
+    pk: &[Integer],
+    m: &[Integer],
+    proof: &DecryptionProof,
+    ee_id: &str,
+    bb_id: &str,
+    context: &DecryptionProofContext,
+) -> anyhow::Result<bool> {
+    verify_decryption(eg, ciphertext, pk, m, proof, &context.i_aux(ee_id, bb_id))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::test::{test_dataset_setup_path, test_verification_card_set_path},
+        data_structures::{
+            setup::{
+                control_component_code_shares_payload::ControlComponentCodeSharesPayload,
+                control_component_public_keys_payload::ControlComponentPublicKeysPayload,
+                setup_component_verification_data_payload::SetupComponentVerificationDataPayload,
+            },
+            VerifierDataDecode,
+        },
+    };
+    use std::fs;
+
+    #[test]
+    fn test_i_aux() {
+        assert_eq!(
+            SchnorrProofContext::ChoiceReturnCodes { node_id: 2 }.i_aux("ee"),
+            vec!["ee", "GenKeysCCR", "2"]
+        );
+        assert_eq!(
+            SchnorrProofContext::ElectionKey { node_id: 2 }.i_aux("ee"),
+            vec!["ee", "SetupTallyCCM", "2"]
+        );
+        assert_eq!(
+            SchnorrProofContext::ElectoralBoard.i_aux("ee"),
+            vec!["ee", "SetupTallyEB"]
+        );
+        assert_eq!(
+            DecryptionProofContext::OnlineControlComponent { node_id: 3 }.i_aux("ee", "bb"),
+            vec!["ee", "bb", "MixDecOnline", "3"]
+        );
+        assert_eq!(
+            DecryptionProofContext::TallyControlComponent.i_aux("ee", "bb"),
+            vec!["ee", "bb", "MixDecOffline"]
+        );
+        assert_eq!(
+            exponentiation_pcc_i_aux("ee", "vc", 4),
+            vec!["ee", "vc", "GenEncLongCodeShares", "4"]
+        );
+    }
+
+    #[test]
+    fn test_verify_key_schnorr_proof() {
+        let payload = ControlComponentPublicKeysPayload::from_json(
+            &fs::read_to_string(
+                test_dataset_setup_path()
+                    .join("setup")
+                    .join("controlComponentPublicKeysPayload.2.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let eg = &payload.encryption_group;
+        let ee_id = &payload.election_event_id;
+        let keys = &payload.control_component_public_keys;
+        let context = SchnorrProofContext::ChoiceReturnCodes {
+            node_id: keys.node_id,
+        };
+        let proof = Proof::from(&keys.ccrj_schnorr_proofs[0]);
+        let pk = &keys.ccrj_choice_return_codes_encryption_public_key[0];
+        assert!(verify_key_schnorr_proof(eg, &proof, pk, ee_id, &context).unwrap());
+        assert!(!verify_key_schnorr_proof(
+            eg,
+            &proof,
+            pk,
+            ee_id,
+            &SchnorrProofContext::ElectionKey {
+                node_id: keys.node_id
+            }
+        )
+        .unwrap());
+        assert!(!verify_key_schnorr_proof(
+            eg,
+            &proof,
+            &keys.ccrj_choice_return_codes_encryption_public_key[1],
+            ee_id,
+            &context
+        )
+        .unwrap());
+        let proof = Proof::from(&keys.ccmj_schnorr_proofs[0]);
+        assert!(verify_key_schnorr_proof(
+            eg,
+            &proof,
+            &keys.ccmj_election_public_key[0],
+            ee_id,
+            &SchnorrProofContext::ElectionKey {
+                node_id: keys.node_id
+            }
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_verify_pcc_exponentiation_proof() {
+        let cc_payload = ControlComponentCodeSharesPayload::from_json(
+            &fs::read_to_string(
+                test_verification_card_set_path().join("controlComponentCodeSharesPayload.0.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let setup_payload = SetupComponentVerificationDataPayload::from_json(
+            &fs::read_to_string(
+                test_verification_card_set_path()
+                    .join("setupComponentVerificationDataPayload.0.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let cc = &cc_payload[0];
+        let share = &cc.control_component_code_shares[0];
+        let setup_verif = setup_payload
+            .setup_component_verification_data
+            .iter()
+            .find(|d| d.verification_card_id == share.verification_card_id)
+            .unwrap();
+        let verify = |node_id: usize| {
+            verify_pcc_exponentiation_proof(
+                &cc.encryption_group,
+                &setup_verif.encrypted_hashed_squared_partial_choice_return_codes,
+                &share.voter_choice_return_code_generation_public_key[0],
+                &share.exponentiated_encrypted_partial_choice_return_codes,
+                &share.encrypted_partial_choice_return_code_exponentiation_proof,
+                &cc.election_event_id,
+                &share.verification_card_id,
+                node_id,
+            )
+            .unwrap()
+        };
+        assert!(verify(cc.node_id));
+        assert!(!verify(cc.node_id + 1));
+    }
+}
@@ -10,6 +10,7 @@ pub static XSD_ECH_0155: &str = include_str!("../resources/schemas/eCH-0155-4-0.
 pub static XSD_ECH_0222: &str = include_str!("../resources/schemas/eCH-0222-1-0.xsd");
 pub static XSD_CONFIG: &str = include_str!("../resources/schemas/evoting-config-6-0.xsd");
 pub static XSD_DECRYPT: &str = include_str!("../resources/schemas/evoting-decrypt-1-3.xsd");
+pub static XSD_DECRYPT_1_4: &str = include_str!("../resources/schemas/evoting-decrypt-1-4.xsd");
 
 #[cfg(test)]
 pub(crate) mod test_resources {
@@ -0,0 +1,156 @@
+//! Module defining the signed objects and the authorities signing them
+//!
+//! The hash of a signed object and its context data do not need the keystore, so that they are
+//! also available without the feature `native` (e.g. for the [crate::spot_check]). The
+//! verification of the signature against the keystore and the checks of the direct trust
+//! certificates are native only (see module `direct_trust`).
+
+use anyhow::anyhow;
+#[cfg(feature = "native")]
+use anyhow::Context;
+#[cfg(feature = "native")]
+use rust_ev_crypto_primitives::{verify_signature, Keystore};
+use rust_ev_crypto_primitives::{ByteArray, HashableMessage};
+
+/// List of valide Certificate authorities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateAuthority {
+    Canton,
+    SdmConfig,
+    SdmTally,
+    VotingServer,
+    ControlComponent1,
+    ControlComponent2,
+    ControlComponent3,
+    ControlComponent4,
+}
+
+impl CertificateAuthority {
+    /// All the certificate authorities
+    pub fn all() -> [Self; 8] {
+        [
+            Self::Canton,
+            Self::SdmConfig,
+            Self::SdmTally,
+            Self::VotingServer,
+            Self::ControlComponent1,
+            Self::ControlComponent2,
+            Self::ControlComponent3,
+            Self::ControlComponent4,
+        ]
+    }
+
+    pub fn get_ca_cc(node: &usize) -> Option<Self> {
+        match node {
+            1 => Some(Self::ControlComponent1),
+            2 => Some(Self::ControlComponent2),
+            3 => Some(Self::ControlComponent3),
+            4 => Some(Self::ControlComponent4),
+            _ => None,
+        }
+    }
+}
+
+impl From<CertificateAuthority> for String {
+    fn from(value: CertificateAuthority) -> Self {
+        match value {
+            CertificateAuthority::Canton => "canton".to_string(),
+            CertificateAuthority::SdmConfig => "sdm_config".to_string(),
+            CertificateAuthority::SdmTally => "sdm_tally".to_string(),
+            CertificateAuthority::VotingServer => "voting_server".to_string(),
+            CertificateAuthority::ControlComponent1 => "control_component_1".to_string(),
+            CertificateAuthority::ControlComponent2 => "control_component_2".to_string(),
+            CertificateAuthority::ControlComponent3 => "control_component_3".to_string(),
+            CertificateAuthority::ControlComponent4 => "control_component_4".to_string(),
+        }
+    }
+}
+
+impl TryFrom<&str> for CertificateAuthority {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::all()
+            .into_iter()
+            .find(|ca| String::from(*ca) == value)
+            .ok_or_else(|| anyhow!(format!("Unknown certificate authority {}", value)))
+    }
+}
+
+/// Trait that must be implemented for each object implementing a signature to be verified
+///
+/// The following function are to be implemented for the object to make it running:
+/// - [VerifiySignatureTrait::get_hashable] Get the [HashableMessage] for the object
+/// - [VerifiySignatureTrait::get_context_data] Get the context data as [HashableMessage] for the object, according to the specifications
+/// - [VerifiySignatureTrait::get_certificate_authority] Certificate Authority of the certificate to fin the certificate in the keystore
+/// - [VerifiySignatureTrait::get_signature] Get the signature of the object
+///
+/// Without the feature `native`, only the hashable is used (spot checks). The other functions
+/// are implemented by all the signed objects for the verification of the signature (native only)
+#[cfg_attr(not(feature = "native"), allow(dead_code))]
+pub trait VerifiySignatureTrait<'a>
+where
+    Self: 'a,
+{
+    /// Get the hashable from the object
+    fn get_hashable(&'a self) -> anyhow::Result<HashableMessage<'a>>;
+
+    /// Get the context data of the object according to the specifications
+    fn get_context_data(&'a self) -> Vec<HashableMessage<'a>>;
+
+    /// Get the Certificate Authority to the specifications
+    fn get_certificate_authority(&self) -> anyhow::Result<String>;
+
+    /// Get the signature of the object
+    ///
+    /// Error if the signature in the file cannot be decoded
+    fn get_signature(&self) -> anyhow::Result<ByteArray>;
+
+    /// Get the context data of the object according to the context data
+    fn get_context_hashable(&'a self) -> HashableMessage {
+        if self.get_context_data().len() == 1 {
+            return self.get_context_data()[0].clone();
+        }
+        HashableMessage::from(self.get_context_data())
+    }
+
+    /// Verfiy the signature according to the specifications of Verifier
+    ///
+    /// Only available with the feature `native`, since the keystore is read from the file system
+    #[cfg(feature = "native")]
+    fn verifiy_signature(&'a self, keystore: &Keystore) -> anyhow::Result<bool> {
+        let ca = &self
+            .get_certificate_authority()
+            .context("Error getting ca")?;
+        let hashable_message = self
+            .get_hashable()
+            .context("Error getting the hashable message")?;
+        let signature = self
+            .get_signature()
+            .context("Error getting the signature")?;
+        verify_signature(
+            keystore,
+            ca.as_str(),
+            &hashable_message,
+            &self.get_context_hashable(),
+            &signature,
+        )
+        .context("Error verifying the signature")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ca_try_from() {
+        for ca in CertificateAuthority::all() {
+            assert_eq!(
+                CertificateAuthority::try_from(String::from(ca).as_str()).unwrap(),
+                ca
+            );
+        }
+        assert!(CertificateAuthority::try_from("toto").is_err());
+    }
+}
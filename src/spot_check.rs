@@ -0,0 +1,304 @@
+//! Module implementing the spot checks of single payloads
+//!
+//! A spot check verifies one payload, given as json string, without any access to the file
+//! system or to the keystore. It is the core used by a browser tool, letting a citizen verify
+//! a single payload file:
+//! - decode the payload
+//! - calculate the hash of the payload (recursive hash of the specification)
+//! - verify the proofs that can be verified with the payload alone
+//!
+//! The signature of the payload is not verified, since it requires the keystore. The
+//! verification of the signatures and of the consistency between the payloads stays in the
+//! verifications of the native application (see [crate::verification]).
+//!
+//! The module is compiled without the feature `native`. It does not build for the wasm32
+//! targets yet, since the crypto primitives depend on GMP (crate `rug`), see the crate
+//! documentation.
+
+use crate::{
+    crypto_helpers::{SchnorrProofContext, Seed},
+    data_structures::{
+        common_types::Proof,
+        setup::{
+            control_component_public_keys_payload::{
+                ControlComponentPublicKeys, ControlComponentPublicKeysPayload,
+            },
+            election_event_context_payload::ElectionEventContextPayload,
+            setup_component_public_keys_payload::SetupComponentPublicKeysPayload,
+        },
+        VerifierDataDecode,
+    },
+    signature::VerifiySignatureTrait,
+};
+use anyhow::bail;
+use rug::Integer;
+use rust_ev_crypto_primitives::{verify_schnorr, ByteArray, EncryptionParameters};
+use std::{fmt::Display, iter::zip};
+
+/// Types of the payloads supported by the spot checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpotCheckPayloadType {
+    ElectionEventContext,
+    SetupComponentPublicKeys,
+    ControlComponentPublicKeys,
+}
+
+/// Report of a spot check
+///
+/// As for the verifications, the errors are problems preventing the check (e.g. the payload
+/// cannot be decoded) and the failures are negative results of the check.
+#[derive(Debug, Clone)]
+pub struct SpotCheckReport {
+    pub payload_type: SpotCheckPayloadType,
+    /// Hash of the payload. `None` if the payload cannot be decoded
+    pub hash: Option<ByteArray>,
+    pub errors: Vec<String>,
+    pub failures: Vec<String>,
+}
+
+impl SpotCheckPayloadType {
+    /// Find the type of the payload from the name of the file (e.g. `controlComponentPublicKeysPayload.1.json`)
+    pub fn from_file_name(name: &str) -> anyhow::Result<Self> {
+        if name.starts_with("electionEventContextPayload") {
+            return Ok(Self::ElectionEventContext);
+        }
+        if name.starts_with("setupComponentPublicKeysPayload") {
+            return Ok(Self::SetupComponentPublicKeys);
+        }
+        if name.starts_with("controlComponentPublicKeysPayload") {
+            return Ok(Self::ControlComponentPublicKeys);
+        }
+        bail!(format!(
+            "The file {} is not supported by the spot checks",
+            name
+        ))
+    }
+}
+
+impl Display for SpotCheckPayloadType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::ElectionEventContext => "electionEventContextPayload",
+            Self::SetupComponentPublicKeys => "setupComponentPublicKeysPayload",
+            Self::ControlComponentPublicKeys => "controlComponentPublicKeysPayload",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl SpotCheckReport {
+    fn new(payload_type: SpotCheckPayloadType) -> Self {
+        Self {
+            payload_type,
+            hash: None,
+            errors: vec![],
+            failures: vec![],
+        }
+    }
+
+    /// The check is ok (no error and no failure)
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty() && self.failures.is_empty()
+    }
+
+    /// Calculate the hash of the payload, adding an error if not possible
+    fn set_hash<'a, T: VerifiySignatureTrait<'a>>(&mut self, payload: &'a T) {
+        match payload.get_hashable() {
+            Ok(h) => self.hash = Some(h.hash()),
+            Err(e) => self
+                .errors
+                .push(format!("Cannot calculate the hash of the payload: {}", e)),
+        }
+    }
+}
+
+/// Decode the payload, adding an error to the report if not possible
+fn decode<T: VerifierDataDecode>(json: &str, report: &mut SpotCheckReport) -> Option<T> {
+    match T::from_json(&json.to_string()) {
+        Ok(p) => Some(p),
+        Err(e) => {
+            report.errors.push(format!(
+                "Cannot decode the payload {}: {}",
+                report.payload_type, e
+            ));
+            None
+        }
+    }
+}
+
+/// Run the spot check of the payload given as json string
+pub fn spot_check(payload_type: SpotCheckPayloadType, json: &str) -> SpotCheckReport {
+    let mut report = SpotCheckReport::new(payload_type);
+    match payload_type {
+        SpotCheckPayloadType::ElectionEventContext => {
+            if let Some(p) = decode::<ElectionEventContextPayload>(json, &mut report) {
+                report.set_hash(&p);
+                if let Err(e) = Seed::try_from(p.seed.as_str()) {
+                    report.failures.push(format!("{}", e));
+                }
+            }
+        }
+        SpotCheckPayloadType::SetupComponentPublicKeys => {
+            if let Some(p) = decode::<SetupComponentPublicKeysPayload>(json, &mut report) {
+                report.set_hash(&p);
+                for cc_pk in p
+                    .setup_component_public_keys
+                    .combined_control_component_public_keys
+                    .iter()
+                {
+                    check_control_component_public_keys(
+                        &p.encryption_group,
+                        &p.election_event_id,
+                        cc_pk,
+                        &mut report,
+                    );
+                }
+                check_schnorr_proofs(
+                    &p.encryption_group,
+                    &p.setup_component_public_keys.electoral_board_public_key,
+                    &p.setup_component_public_keys
+                        .electoral_board_schnorr_proofs
+                        .iter()
+                        .map(Proof::from)
+                        .collect::<Vec<_>>(),
+                    &SchnorrProofContext::ElectoralBoard.i_aux(&p.election_event_id),
+                    "Electoral board",
+                    &mut report,
+                );
+            }
+        }
+        SpotCheckPayloadType::ControlComponentPublicKeys => {
+            if let Some(p) = decode::<ControlComponentPublicKeysPayload>(json, &mut report) {
+                report.set_hash(&p);
+                check_control_component_public_keys(
+                    &p.encryption_group,
+                    &p.election_event_id,
+                    &p.control_component_public_keys,
+                    &mut report,
+                );
+            }
+        }
+    }
+    report
+}
+
+/// Check the Schnorr proofs of the CCR_j and CCM_j keys of a control component
+fn check_control_component_public_keys(
+    eg: &EncryptionParameters,
+    ee_id: &str,
+    cc_pk: &ControlComponentPublicKeys,
+    report: &mut SpotCheckReport,
+) {
+    let j = cc_pk.node_id;
+    check_schnorr_proofs(
+        eg,
+        &cc_pk.ccrj_choice_return_codes_encryption_public_key,
+        &cc_pk
+            .ccrj_schnorr_proofs
+            .iter()
+            .map(Proof::from)
+            .collect::<Vec<_>>(),
+        &SchnorrProofContext::ChoiceReturnCodes { node_id: j }.i_aux(ee_id),
+        &format!("CCR_j for node {}", j),
+        report,
+    );
+    check_schnorr_proofs(
+        eg,
+        &cc_pk.ccmj_election_public_key,
+        &cc_pk
+            .ccmj_schnorr_proofs
+            .iter()
+            .map(Proof::from)
+            .collect::<Vec<_>>(),
+        &SchnorrProofContext::ElectionKey { node_id: j }.i_aux(ee_id),
+        &format!("CCM_j for node {}", j),
+        report,
+    );
+}
+
+/// Check the Schnorr proofs of the public keys
+///
+/// The proofs are verified sequentially, since no thread is available in a browser
+fn check_schnorr_proofs(
+    eg: &EncryptionParameters,
+    pks: &[Integer],
+    pis: &[Proof],
+    i_aux: &[String],
+    proof_name: &str,
+    report: &mut SpotCheckReport,
+) {
+    if pks.len() != pis.len() {
+        report.errors.push(format!(
+            "The length of pks and pis is not the same for {}",
+            proof_name
+        ));
+        return;
+    }
+    let i_aux = i_aux.to_vec();
+    for (i, (pk, pi)) in zip(pks, pis).enumerate() {
+        match verify_schnorr(eg, pi.as_tuple(), pk, &i_aux) {
+            Ok(true) => (),
+            Ok(false) => report.failures.push(format!(
+                "Schnorr proof of {} not ok at pos {}",
+                proof_name, i
+            )),
+            Err(e) => report.errors.push(format!(
+                "Error verifying the Schnorr proof of {} at pos {}: {}",
+                proof_name, i, e
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::test::test_dataset_setup_path;
+    use std::fs;
+
+    fn read_payload(name: &str) -> String {
+        fs::read_to_string(test_dataset_setup_path().join("setup").join(name)).unwrap()
+    }
+
+    #[test]
+    fn test_from_file_name() {
+        assert_eq!(
+            SpotCheckPayloadType::from_file_name("controlComponentPublicKeysPayload.1.json")
+                .unwrap(),
+            SpotCheckPayloadType::ControlComponentPublicKeys
+        );
+        assert!(SpotCheckPayloadType::from_file_name("toto.json").is_err());
+    }
+
+    #[test]
+    fn test_ok() {
+        for name in [
+            "electionEventContextPayload.json",
+            "setupComponentPublicKeysPayload.json",
+            "controlComponentPublicKeysPayload.1.json",
+        ] {
+            let report = spot_check(
+                SpotCheckPayloadType::from_file_name(name).unwrap(),
+                &read_payload(name),
+            );
+            assert!(report.is_ok(), "{}: {:?}", name, report);
+            assert!(report.hash.is_some());
+        }
+    }
+
+    #[test]
+    fn test_hash_stable() {
+        let json = read_payload("setupComponentPublicKeysPayload.json");
+        let h1 = spot_check(SpotCheckPayloadType::SetupComponentPublicKeys, &json).hash;
+        let h2 = spot_check(SpotCheckPayloadType::SetupComponentPublicKeys, &json).hash;
+        assert_eq!(h1, h2);
+    }
+
+    #[test]
+    fn test_not_decodable() {
+        let report = spot_check(SpotCheckPayloadType::ElectionEventContext, "{}");
+        assert!(!report.is_ok());
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.hash.is_none());
+    }
+}
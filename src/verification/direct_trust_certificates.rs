@@ -0,0 +1,125 @@
+//! Module implementing the verification of the direct trust certificates
+//!
+//! At the start, the application only loads the keystore (see [crate::direct_trust]). The
+//! verification checks each certificate of the expected certificate authorities: the certificate
+//! is present in the keystore, it is valid at the time of the verification and its key usage
+//! allows the verification of signatures. The failures are reported per certificate.
+
+use super::result::{
+    create_verification_error, create_verification_failure, FailureCode, VerificationEvent,
+    VerificationResult,
+};
+use crate::{
+    config::Config,
+    direct_trust::{check_certificate_file, CertificateAuthority, CertificateProblem},
+};
+use anyhow::anyhow;
+use log::{debug, warn};
+use rust_ev_crypto_primitives::Keystore;
+use std::path::PathBuf;
+
+/// Verify the certificates of all the certificate authorities of the keystore
+///
+/// `path` gives the path of the certificate file of the certificate authority
+fn verify_certificates(
+    keystore: &Keystore,
+    path: impl Fn(CertificateAuthority) -> PathBuf,
+    result: &mut VerificationResult,
+) {
+    for ca in CertificateAuthority::all() {
+        let name = String::from(ca);
+        debug!("Verify the direct trust certificate of {}", name);
+        let file_name = format!("{}.cer", name);
+        if keystore.certificate(&name).is_err() {
+            result.push(
+                create_verification_failure!(format!(
+                    "The certificate of {} is missing in the direct trust keystore",
+                    name
+                ))
+                .with_code(FailureCode::MissingFile)
+                .with_file(&file_name),
+            );
+            result.push_failed_item("certificate", &name, "missing");
+            continue;
+        }
+        for problem in check_certificate_file(ca, &path(ca)) {
+            // The test certificates don't follow the naming scheme of the common name
+            if matches!(problem, CertificateProblem::CommonName(_)) {
+                warn!("Certificate of {}: {}", name, problem);
+                continue;
+            }
+            let (code, reason) = match problem {
+                CertificateProblem::Unreadable(_) => (FailureCode::ReadError, "unreadable"),
+                CertificateProblem::Validity(_) => (FailureCode::SignatureInvalid, "not valid"),
+                _ => (FailureCode::SignatureInvalid, "wrong key usage"),
+            };
+            result.push(
+                create_verification_failure!(format!("Certificate of {}: {}", name, problem))
+                    .with_code(code)
+                    .with_file(&file_name),
+            );
+            result.push_failed_item("certificate", &name, reason);
+        }
+    }
+}
+
+/// Verify the direct trust certificates of the configuration
+pub(super) fn verify_direct_trust_certificates(config: &Config, result: &mut VerificationResult) {
+    let keystore = match config.keystore() {
+        Ok(ks) => ks,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot read the direct trust keystore",
+                e
+            ));
+            return;
+        }
+    };
+    verify_certificates(
+        &keystore,
+        |ca| config.direct_trust_certificate_path(&String::from(ca)),
+        result,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::result::VerificationResultTrait, *};
+    use crate::config::test::{test_datasets_path, CONFIG_TEST};
+    use rust_ev_crypto_primitives::CertificateExtension;
+    use std::fs;
+
+    #[test]
+    fn test_ok() {
+        let mut result = VerificationResult::new();
+        verify_direct_trust_certificates(&CONFIG_TEST, &mut result);
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_missing_certificate() {
+        // The keystore is read on the disk
+        let location = std::env::temp_dir().join("rust_verifier_test_direct_trust_missing");
+        let _ = fs::remove_dir_all(&location);
+        fs::create_dir_all(&location).unwrap();
+        let source = test_datasets_path().join("direct-trust");
+        for ca in CertificateAuthority::all()
+            .into_iter()
+            .filter(|ca| *ca != CertificateAuthority::ControlComponent4)
+        {
+            let file_name = format!("{}.cer", String::from(ca));
+            fs::copy(source.join(&file_name), location.join(&file_name)).unwrap();
+        }
+        let keystore = Keystore::from_directory(&location, &CertificateExtension::Cer).unwrap();
+        let mut result = VerificationResult::new();
+        verify_certificates(
+            &keystore,
+            |ca| location.join(format!("{}.cer", String::from(ca))),
+            &mut result,
+        );
+        fs::remove_dir_all(&location).unwrap();
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failures()[0].code(), FailureCode::MissingFile);
+        assert_eq!(result.failed_items()[0].id, "control_component_4");
+    }
+}
@@ -0,0 +1,219 @@
+//! Module implementing the detection of duplicated chunks in a file group
+//!
+//! Two distinct files of a file group (e.g. two chunks of the setup verification data) must never
+//! be byte-identical, nor share the same signature. Else, the files were probably copied during
+//! the generation of the dataset. The files are compared with the digests of the manifest (see
+//! [crate::file_structure::manifest]) and with the signatures read from the json, without
+//! decoding the payloads.
+
+use super::result::{
+    create_verification_error, create_verification_failure, FailureCode, VerificationEvent,
+    VerificationResult,
+};
+use crate::{
+    data_structures::common_types::Signature,
+    file_structure::{file_group::FileGroup, fingerprint::file_fingerprint},
+};
+use anyhow::anyhow;
+use log::debug;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+/// Digest and signatures of one file of the group
+struct ChunkFingerprint {
+    number: usize,
+    digest: anyhow::Result<String>,
+    signatures: anyhow::Result<Vec<String>>,
+}
+
+/// Numbers of the files sharing the same value, for the values shared by more than one file
+fn shared_values<'a>(values: impl Iterator<Item = (usize, &'a String)>) -> Vec<Vec<usize>> {
+    let mut map: BTreeMap<&String, Vec<usize>> = BTreeMap::new();
+    for (nb, v) in values {
+        let numbers = map.entry(v).or_default();
+        if !numbers.contains(&nb) {
+            numbers.push(nb);
+        }
+    }
+    map.into_values().filter(|l| l.len() > 1).collect()
+}
+
+fn numbers_to_string(numbers: &[usize]) -> String {
+    numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Verify that no two distinct files of the group are byte-identical or share a signature
+///
+/// `name` is the name of the group in the messages (e.g. `vcs_1/setupComponentVerificationData`)
+pub(super) fn verify_no_duplicate_chunks(
+    group: &FileGroup,
+    name: &str,
+    result: &mut VerificationResult,
+) {
+    let files: Vec<_> = group.iter().collect();
+    debug!(
+        "Search duplicated chunks in {} files of {}",
+        files.len(),
+        name
+    );
+    let fingerprints: Vec<ChunkFingerprint> = files
+        .par_iter()
+        .map(|(nb, f)| ChunkFingerprint {
+            number: *nb,
+            digest: file_fingerprint(f.source().as_ref(), &f.get_path()),
+            signatures: f
+                .read_data()
+                .and_then(|s| Signature::signatures_of_json(&s)),
+        })
+        .collect();
+    let mut digests = vec![];
+    let mut signatures = vec![];
+    for fp in fingerprints.iter() {
+        match &fp.digest {
+            Ok(d) => digests.push((fp.number, d)),
+            Err(e) => result.push(create_verification_error!(
+                format!(
+                    "Cannot calculate the digest of the file {} of {}",
+                    fp.number, name
+                ),
+                anyhow!(e.to_string())
+            )),
+        }
+        match &fp.signatures {
+            Ok(l) => signatures.extend(l.iter().map(|s| (fp.number, s))),
+            Err(e) => result.push(create_verification_error!(
+                format!(
+                    "Cannot read the signatures of the file {} of {}",
+                    fp.number, name
+                ),
+                anyhow!(e.to_string())
+            )),
+        }
+    }
+    let identical = shared_values(digests.into_iter());
+    for numbers in identical.iter() {
+        result.push(
+            create_verification_failure!(format!(
+                "The files {} of {} are identical",
+                numbers_to_string(numbers),
+                name
+            ))
+            .with_code(FailureCode::FileContent)
+            .with_file(name),
+        );
+        for n in numbers.iter() {
+            result.push_failed_item("file", &format!("{}/{}", name, n), "identical file");
+        }
+    }
+    // The identical files share their signatures: only the other files are reported
+    for numbers in shared_values(signatures.into_iter())
+        .iter()
+        .filter(|l| !identical.contains(l))
+    {
+        result.push(
+            create_verification_failure!(format!(
+                "The files {} of {} have the same signature",
+                numbers_to_string(numbers),
+                name
+            ))
+            .with_code(FailureCode::SignatureInvalid)
+            .with_file(name),
+        );
+        for n in numbers.iter() {
+            result.push_failed_item("file", &format!("{}/{}", name, n), "same signature");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::result::VerificationResultTrait, *};
+    use crate::{
+        data_structures::{setup::VerifierSetupDataType, VerifierDataType},
+        file_structure::memory_fs::MemoryFileSystem,
+    };
+    use std::path::Path;
+
+    fn group(name: &str, files: &[&str]) -> (MemoryFileSystem, FileGroup) {
+        let mfs = MemoryFileSystem::new(Path::new(name));
+        for (i, content) in files.iter().enumerate() {
+            mfs.add_file(
+                &format!("controlComponentPublicKeysPayload.{}.json", i + 1),
+                content,
+            );
+        }
+        let group = FileGroup::new_with_source(
+            &mfs.source(),
+            mfs.root(),
+            VerifierDataType::Setup(VerifierSetupDataType::ControlComponentPublicKeysPayload),
+        );
+        (mfs, group)
+    }
+
+    #[test]
+    fn test_signatures_of_json() {
+        assert_eq!(
+            Signature::signatures_of_json(r#"{"a": 1, "signature": {"signatureContents": "AB"}}"#)
+                .unwrap(),
+            vec!["AB"]
+        );
+        assert_eq!(
+            Signature::signatures_of_json(
+                r#" [{"signature": {"signatureContents": "AB"}}, {"signature": {"signatureContents": "CD"}}]"#
+            )
+            .unwrap(),
+            vec!["AB", "CD"]
+        );
+        assert!(Signature::signatures_of_json(r#"{"a": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_no_duplicate() {
+        let (_mfs, group) = group(
+            "duplicate_chunks_no_duplicate",
+            &[
+                r#"{"a": 1, "signature": {"signatureContents": "AB"}}"#,
+                r#"{"a": 2, "signature": {"signatureContents": "CD"}}"#,
+            ],
+        );
+        let mut result = VerificationResult::new();
+        verify_no_duplicate_chunks(&group, "group", &mut result);
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_identical_files() {
+        let (_mfs, group) = group(
+            "duplicate_chunks_identical",
+            &[
+                r#"{"a": 1, "signature": {"signatureContents": "AB"}}"#,
+                r#"{"a": 1, "signature": {"signatureContents": "AB"}}"#,
+                r#"{"a": 2, "signature": {"signatureContents": "CD"}}"#,
+            ],
+        );
+        let mut result = VerificationResult::new();
+        verify_no_duplicate_chunks(&group, "group", &mut result);
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failures()[0].code(), FailureCode::FileContent);
+        assert_eq!(result.failed_items().len(), 2);
+    }
+
+    #[test]
+    fn test_same_signature() {
+        let (_mfs, group) = group(
+            "duplicate_chunks_same_signature",
+            &[
+                r#"{"a": 1, "signature": {"signatureContents": "AB"}}"#,
+                r#"{"a": 2, "signature": {"signatureContents": "AB"}}"#,
+            ],
+        );
+        let mut result = VerificationResult::new();
+        verify_no_duplicate_chunks(&group, "group", &mut result);
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failures()[0].code(), FailureCode::SignatureInvalid);
+    }
+}
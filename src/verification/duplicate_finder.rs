@@ -0,0 +1,285 @@
+//! Module implementing a memory-bounded search of duplicated ids
+//!
+//! The global uniqueness of the ids (e.g. verification card ids over all the verification card
+//! sets) cannot be verified with a set in memory for tens of millions of ids. The ids are
+//! collected in a buffer of bounded size. When the buffer is full, it is sorted and written to
+//! a temporary file (sorted run). At the end, the sorted runs are merged and the duplicates are
+//! found as consecutive equal ids.
+//!
+//! The sorted runs are written in a private temporary directory, created at the first run. At
+//! most [MAX_FAN_IN] runs are open at the same time: if there are more runs, they are first
+//! merged by groups into longer runs.
+
+use anyhow::{bail, Context};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    fs,
+    io::{BufRead, BufReader, BufWriter, Lines, Write},
+    path::PathBuf,
+};
+use tempfile::TempDir;
+
+/// Maximal number of sorted runs merged at the same time
+const MAX_FAN_IN: usize = 64;
+
+/// Memory-bounded search of duplicated ids
+///
+/// At most `max_in_memory` ids are kept in memory. The temporary directory and its files are
+/// deleted when the structure is dropped.
+pub struct DuplicateFinder {
+    max_in_memory: usize,
+    buffer: Vec<String>,
+    dir: Option<TempDir>,
+    runs: VecDeque<PathBuf>,
+    run_counter: usize,
+    len: usize,
+}
+
+impl DuplicateFinder {
+    /// New finder keeping at most `max_in_memory` ids in memory (at least 1)
+    pub fn new(max_in_memory: usize) -> Self {
+        Self {
+            max_in_memory: max_in_memory.max(1),
+            buffer: vec![],
+            dir: None,
+            runs: VecDeque::new(),
+            run_counter: 0,
+            len: 0,
+        }
+    }
+
+    /// Number of ids pushed
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of sorted runs written to the disk
+    pub fn number_of_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Add an id
+    ///
+    /// The id cannot contain a new line, since the sorted runs are written line by line
+    pub fn push(&mut self, id: &str) -> anyhow::Result<()> {
+        if id.contains('\n') {
+            bail!(format!("The id {:?} contains a new line", id))
+        }
+        self.buffer.push(id.to_string());
+        self.len += 1;
+        if self.buffer.len() >= self.max_in_memory {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Create a new file for a sorted run in the temporary directory
+    ///
+    /// The file is created only if it does not exist yet
+    fn new_run(&mut self) -> anyhow::Result<(PathBuf, BufWriter<fs::File>)> {
+        let dir = match self.dir.take() {
+            Some(d) => d,
+            None => tempfile::Builder::new()
+                .prefix("rust_verifier_dedup_")
+                .tempdir()
+                .context("Cannot create the temporary directory")?,
+        };
+        let path = dir.path().join(format!("run_{}.txt", self.run_counter));
+        self.dir = Some(dir);
+        self.run_counter += 1;
+        let f = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("Cannot create the temporary file {:?}", path))?;
+        Ok((path, BufWriter::new(f)))
+    }
+
+    /// Write the buffer as sorted run to a temporary file
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.buffer.sort_unstable();
+        let (path, mut writer) = self.new_run()?;
+        self.runs.push_back(path.clone());
+        for id in self.buffer.drain(..) {
+            writeln!(writer, "{}", id)
+                .with_context(|| format!("Cannot write to the temporary file {:?}", path))?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Cannot write to the temporary file {:?}", path))
+    }
+
+    /// Merge the first [MAX_FAN_IN] runs into a new run, at the end of the runs
+    fn merge_first_runs(&mut self) -> anyhow::Result<()> {
+        let merged: Vec<PathBuf> = self.runs.drain(..MAX_FAN_IN.min(self.runs.len())).collect();
+        let (path, mut writer) = self.new_run()?;
+        let mut readers = open_runs(&merged)?;
+        for id in MergedRuns::new(&mut readers)? {
+            writeln!(writer, "{}", id?)
+                .with_context(|| format!("Cannot write to the temporary file {:?}", path))?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Cannot write to the temporary file {:?}", path))?;
+        self.runs.push_back(path);
+        drop(readers);
+        for p in merged.iter() {
+            let _ = fs::remove_file(p);
+        }
+        Ok(())
+    }
+
+    /// The duplicated ids, sorted. Each duplicated id is returned once
+    pub fn duplicates(mut self) -> anyhow::Result<Vec<String>> {
+        if self.runs.is_empty() {
+            self.buffer.sort_unstable();
+            return duplicates_in_sorted(self.buffer.drain(..).map(Ok));
+        }
+        self.flush()?;
+        while self.runs.len() > MAX_FAN_IN {
+            self.merge_first_runs()?;
+        }
+        let runs: Vec<PathBuf> = self.runs.iter().cloned().collect();
+        let mut readers = open_runs(&runs)?;
+        duplicates_in_sorted(MergedRuns::new(&mut readers)?)
+    }
+}
+
+/// Open the sorted runs to read them line by line
+fn open_runs(paths: &[PathBuf]) -> anyhow::Result<Vec<Lines<BufReader<fs::File>>>> {
+    paths
+        .iter()
+        .map(|p| {
+            fs::File::open(p)
+                .map(|f| BufReader::new(f).lines())
+                .with_context(|| format!("Cannot open the temporary file {:?}", p))
+        })
+        .collect()
+}
+
+/// Iterator merging the sorted runs in sorted order
+struct MergedRuns<'a> {
+    readers: &'a mut [Lines<BufReader<fs::File>>],
+    heap: BinaryHeap<Reverse<(String, usize)>>,
+}
+
+impl<'a> MergedRuns<'a> {
+    fn new(readers: &'a mut [Lines<BufReader<fs::File>>]) -> anyhow::Result<Self> {
+        let mut heap = BinaryHeap::new();
+        for (i, r) in readers.iter_mut().enumerate() {
+            if let Some(l) = r.next() {
+                heap.push(Reverse((l.context("Cannot read the temporary file")?, i)));
+            }
+        }
+        Ok(Self { readers, heap })
+    }
+}
+
+impl<'a> Iterator for MergedRuns<'a> {
+    type Item = anyhow::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((id, i)) = self.heap.pop()?;
+        match self.readers[i].next() {
+            Some(Ok(l)) => self.heap.push(Reverse((l, i))),
+            Some(Err(e)) => return Some(Err(e).context("Cannot read the temporary file")),
+            None => (),
+        }
+        Some(Ok(id))
+    }
+}
+
+/// The duplicates in an iterator of sorted ids
+fn duplicates_in_sorted(
+    ids: impl Iterator<Item = anyhow::Result<String>>,
+) -> anyhow::Result<Vec<String>> {
+    let mut res: Vec<String> = vec![];
+    let mut previous: Option<String> = None;
+    for id in ids {
+        let id = id?;
+        if previous.as_ref() == Some(&id) {
+            if res.last() != Some(&id) {
+                res.push(id.clone());
+            }
+        } else {
+            previous = Some(id);
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ids() -> Vec<String> {
+        let mut res: Vec<String> = (0..100).map(|i| format!("{:032x}", i * 7)).collect();
+        res.push(format!("{:032x}", 14));
+        res.push(format!("{:032x}", 700));
+        res.push(format!("{:032x}", 14));
+        res.push(format!("{:032x}", 693));
+        res
+    }
+
+    #[test]
+    fn test_in_memory() {
+        let mut finder = DuplicateFinder::new(1000);
+        for id in ids().iter() {
+            finder.push(id).unwrap();
+        }
+        assert_eq!(finder.number_of_runs(), 0);
+        assert_eq!(finder.len(), 104);
+        assert_eq!(
+            finder.duplicates().unwrap(),
+            vec![format!("{:032x}", 14), format!("{:032x}", 693)]
+        );
+    }
+
+    #[test]
+    fn test_with_runs() {
+        let mut finder = DuplicateFinder::new(10);
+        for id in ids().iter() {
+            finder.push(id).unwrap();
+        }
+        assert_eq!(finder.number_of_runs(), 10);
+        let dir = finder.dir.as_ref().unwrap().path().to_path_buf();
+        assert!(finder.runs.iter().all(|p| p.starts_with(&dir)));
+        assert_eq!(
+            finder.duplicates().unwrap(),
+            vec![format!("{:032x}", 14), format!("{:032x}", 693)]
+        );
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_more_runs_than_fan_in() {
+        let mut finder = DuplicateFinder::new(1);
+        for id in ids().iter() {
+            finder.push(id).unwrap();
+        }
+        assert_eq!(finder.number_of_runs(), 104);
+        assert!(finder.number_of_runs() > MAX_FAN_IN);
+        assert_eq!(
+            finder.duplicates().unwrap(),
+            vec![format!("{:032x}", 14), format!("{:032x}", 693)]
+        );
+    }
+
+    #[test]
+    fn test_no_duplicate() {
+        let mut finder = DuplicateFinder::new(3);
+        for id in ["c", "a", "b", "e", "d"] {
+            finder.push(id).unwrap();
+        }
+        assert!(finder.duplicates().unwrap().is_empty());
+        assert!(DuplicateFinder::new(3).push("a\nb").is_err());
+    }
+}
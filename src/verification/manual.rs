@@ -0,0 +1,291 @@
+//! Module extracting the data of the manual verifications
+//!
+//! Some verifications cannot be automated: the auditors must compare the data of the dataset
+//! with information outside of the dataset (e.g. the dates of the election event or the number
+//! of voters published by the canton). For these verifications, the relevant data is extracted
+//! and rendered in a human-readable form (see [ManualCheck]), to be inserted in the report.
+
+use super::VerificationPeriod;
+use crate::file_structure::{
+    setup_directory::SetupDirectoryTrait,
+    tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+    VerificationDirectoryTrait,
+};
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Format of the datetimes in the extracted values
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Data of a manual verification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManualCheck {
+    /// Id of the manual verification (e.g. `M.01`)
+    pub id: String,
+    pub name: String,
+    /// What the auditor has to verify with the values
+    pub description: String,
+    /// Extracted values, as pairs (label, value)
+    pub values: Vec<(String, String)>,
+    /// Errors reading the data. The values are incomplete in case of errors
+    pub errors: Vec<String>,
+}
+
+impl ManualCheck {
+    fn new(id: &str, name: &str, description: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            values: vec![],
+            errors: vec![],
+        }
+    }
+
+    fn push_value(&mut self, label: &str, value: impl ToString) {
+        self.values.push((label.to_string(), value.to_string()))
+    }
+
+    fn push_error(&mut self, context: &str, e: &anyhow::Error) {
+        self.errors.push(format!("{}: {:#}", context, e))
+    }
+
+    /// The manual check as text, with one line per value
+    pub fn to_text(&self) -> String {
+        let mut res = format!("{} {}: {}\n", self.id, self.name, self.description);
+        for (label, value) in self.values.iter() {
+            let _ = writeln!(res, "  {}: {}", label, value);
+        }
+        for e in self.errors.iter() {
+            let _ = writeln!(res, "  error: {}", e);
+        }
+        res
+    }
+}
+
+/// Extract the data of the manual verifications of the period from the directory
+///
+/// The manual verifications of the setup are extracted for both periods, since the dataset of
+/// the tally contains the setup.
+pub fn extract_manual_checks<D: VerificationDirectoryTrait>(
+    dir: &D,
+    period: &VerificationPeriod,
+) -> Vec<ManualCheck> {
+    let mut res = extract_setup_manual_checks(dir.unwrap_setup());
+    if period.is_tally() {
+        if let Some(tally_dir) = dir.tally() {
+            res.append(&mut extract_tally_manual_checks(
+                dir.unwrap_setup(),
+                tally_dir,
+            ));
+        }
+    }
+    res
+}
+
+fn extract_setup_manual_checks<S: SetupDirectoryTrait>(setup_dir: &S) -> Vec<ManualCheck> {
+    let mut dates = ManualCheck::new(
+        "M.01",
+        "ElectionEventDates",
+        "The election event and its dates correspond to the election event planned by the canton",
+    );
+    let mut vcs = ManualCheck::new(
+        "M.02",
+        "VerificationCardSets",
+        "The number of voting cards, the test ballot boxes and the grace periods correspond to the configuration of the canton",
+    );
+    let mut parameters = ManualCheck::new(
+        "M.03",
+        "ElectionParameters",
+        "The parameters of the election event correspond to the ballots of the canton",
+    );
+    match setup_dir.election_event_context_payload() {
+        Ok(p) => {
+            let context = &p.election_event_context;
+            dates.push_value("Election event id", &context.election_event_id);
+            dates.push_value("Alias", &context.election_event_alias);
+            dates.push_value("Description", &context.election_event_description);
+            dates.push_value("Start", context.start_time.format(DATETIME_FORMAT));
+            dates.push_value("Finish", context.finish_time.format(DATETIME_FORMAT));
+            for c in context.verification_card_set_contexts.iter() {
+                vcs.push_value(
+                    &format!(
+                        "{} ({})",
+                        c.verification_card_set_alias, c.verification_card_set_id
+                    ),
+                    format!(
+                        "{} voting cards, ballot box {}{}, {} - {}, grace period {}s",
+                        c.number_of_voting_cards,
+                        c.ballot_box_id,
+                        if c.test_ballot_box { " (test)" } else { "" },
+                        c.ballot_box_start_time.format(DATETIME_FORMAT),
+                        c.ballot_box_finish_time.format(DATETIME_FORMAT),
+                        c.grace_period
+                    ),
+                );
+            }
+            vcs.push_value(
+                "Total voting cards (without test)",
+                context
+                    .verification_card_set_contexts
+                    .iter()
+                    .filter(|c| !c.test_ballot_box)
+                    .map(|c| c.number_of_voting_cards)
+                    .sum::<usize>(),
+            );
+            parameters.push_value("Seed", &p.seed);
+            parameters.push_value("Bit length of p", p.encryption_group.p().significant_bits());
+            parameters.push_value(
+                "Maximum number of voting options",
+                context.maximum_number_of_voting_options,
+            );
+            parameters.push_value(
+                "Maximum number of selections",
+                context.maximum_number_of_selections,
+            );
+            parameters.push_value(
+                "Maximum number of write-ins",
+                context.maximum_number_of_write_ins_plus_one - 1,
+            );
+        }
+        Err(e) => {
+            for c in [&mut dates, &mut vcs, &mut parameters] {
+                c.push_error("election_event_context_payload cannot be read", &e)
+            }
+        }
+    }
+    vec![dates, vcs, parameters]
+}
+
+fn extract_tally_manual_checks<S: SetupDirectoryTrait, T: TallyDirectoryTrait>(
+    setup_dir: &S,
+    tally_dir: &T,
+) -> Vec<ManualCheck> {
+    let mut bbs = ManualCheck::new(
+        "M.11",
+        "BallotBoxes",
+        "The decrypted ballot boxes and their number of votes correspond to the ballot boxes of the election event",
+    );
+    let mut results = ManualCheck::new(
+        "M.12",
+        "Results",
+        "The results correspond to the results published by the canton",
+    );
+    let test_bb_ids: Vec<String> = match setup_dir.election_event_context_payload() {
+        Ok(p) => p
+            .election_event_context
+            .verification_card_set_contexts
+            .iter()
+            .filter(|c| c.test_ballot_box)
+            .map(|c| c.ballot_box_id.clone())
+            .collect(),
+        Err(e) => {
+            bbs.push_error("election_event_context_payload cannot be read", &e);
+            vec![]
+        }
+    };
+    for bb_dir in tally_dir.bb_directories().iter() {
+        match bb_dir.tally_component_votes_payload() {
+            Ok(p) => bbs.push_value(
+                &p.ballot_box_id,
+                format!(
+                    "{} votes{}",
+                    p.votes.len(),
+                    if test_bb_ids.contains(&p.ballot_box_id) {
+                        " (test)"
+                    } else {
+                        ""
+                    }
+                ),
+            ),
+            Err(e) => bbs.push_error(
+                &format!(
+                    "tally_component_votes_payload of {} cannot be read",
+                    bb_dir.get_name()
+                ),
+                &e,
+            ),
+        }
+    }
+    match tally_dir.ech_0110() {
+        Ok(ech_0110) => {
+            results.push_value("Contest", &ech_0110.contest_identification);
+            results.push_value("Contest date", &ech_0110.contest_date);
+            results.push_value("Counting circles", ech_0110.counting_circles.len());
+            results.push_value(
+                "Received voting cards (eCH-0110)",
+                ech_0110.number_of_received_voting_cards(),
+            );
+            // Read with tolerance, but to be checked against the results published by the canton
+            for c in ech_0110.locale_formatted_counts.iter() {
+                results.push_value(
+                    "Count formatted with a locale (eCH-0110)",
+                    format!(
+                        "{} = {} read as {} (counting circle {})",
+                        c.element,
+                        c.text,
+                        c.value,
+                        c.counting_circle_id.as_deref().unwrap_or("-")
+                    ),
+                );
+            }
+        }
+        Err(e) => results.push_error("ech_0110 cannot be read", &e),
+    }
+    match tally_dir.ech_0222() {
+        Ok(ech_0222) => {
+            results.push_value("Cast votes (eCH-0222)", ech_0222.number_of_cast_votes())
+        }
+        Err(e) => results.push_error("ech_0222 cannot be read", &e),
+    }
+    vec![bbs, results]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::test::{
+        get_test_verifier_setup_dir, get_test_verifier_tally_dir, test_dataset_setup_path,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_setup() {
+        let checks =
+            extract_manual_checks(&get_test_verifier_setup_dir(), &VerificationPeriod::Setup);
+        assert_eq!(
+            checks.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+            vec!["M.01", "M.02", "M.03"]
+        );
+        assert!(checks.iter().all(|c| c.errors.is_empty()));
+        assert_eq!(checks[0].values[0].0, "Election event id");
+        assert!(checks[0].to_text().starts_with("M.01 ElectionEventDates: "));
+    }
+
+    #[test]
+    fn test_tally() {
+        let dir = get_test_verifier_tally_dir();
+        let checks = extract_manual_checks(&dir, &VerificationPeriod::Tally);
+        assert_eq!(checks.len(), 5);
+        assert!(checks.iter().all(|c| c.errors.is_empty()));
+        assert_eq!(
+            checks[3].values.len(),
+            dir.tally().unwrap().bb_directories().len()
+        );
+    }
+
+    #[test]
+    fn test_error() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Err(anyhow!("test")));
+        let checks = extract_manual_checks(&mock_dir, &VerificationPeriod::Setup);
+        assert!(checks
+            .iter()
+            .all(|c| c.values.is_empty() && c.errors.len() == 1));
+        assert!(checks[0].to_text().contains("  error: "));
+    }
+}
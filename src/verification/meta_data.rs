@@ -1,18 +1,70 @@
 //! Module to implement the metadata of the tests
 //!
 //! The metadata list is loaded from the file in resources.
+//!
+//! The verifications of the specification have the id of the specification (e.g. `03.01`). The
+//! additional verifications, not part of the specification, have the prefix
+//! [ADDITIONAL_ID_PREFIX] (e.g. `X.10.01`), so that their ids never clash with the ids of a future
+//! version of the specification.
+//!
+//! The front-ends can query the list with a [MetaDataQuery] to present the verifications that
+//! can be selected for a run. The query returns [MetaDataEntry], containing the metadata and the
+//! status of the verification (implemented, excluded).
+//!
+//! Each verification has a [VerificationImpact]: a failure of a blocking verification prevents
+//! the certification of the result, a failure of an informational verification must only be
+//! analysed. The impact is given in the file with the key `impact` (default `blocking`).
+//!
+//! Each verification has a [VerificationScope]: the verifications of scope `ballot_box` verify
+//! each ballot box independently and can be restricted to a selection of ballot boxes (see the
+//! incremental verification of the tally). The scope is given in the file with the key `scope`
+//! (default `dataset`).
 
 use super::{VerificationCategory, VerificationPeriod};
 use anyhow::anyhow;
 use serde::{
     de::{Deserialize as Deserialize2, Deserializer, Error},
-    Deserialize,
+    Deserialize, Serialize,
 };
 
+/// Prefix of the ids of the verifications that are not part of the specification
+pub const ADDITIONAL_ID_PREFIX: &str = "X.";
+
 /// List of Verification Metadata
 #[derive(Deserialize, Debug, Clone)]
 pub struct VerificationMetaDataList(pub Vec<VerificationMetaData>);
 
+/// Impact of a failure of the verification on the overall outcome of a run
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationImpact {
+    /// A failure prevents the certification of the result
+    #[default]
+    Blocking,
+    /// A failure must be analysed, but does not prevent the certification of the result
+    Informational,
+}
+
+impl std::fmt::Display for VerificationImpact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Blocking => write!(f, "blocking"),
+            Self::Informational => write!(f, "informational"),
+        }
+    }
+}
+
+/// Scope of a verification, i.e. the part of the dataset verified as a whole
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationScope {
+    /// The verification compares or aggregates data of the whole dataset
+    #[default]
+    Dataset,
+    /// Each ballot box is verified independently of the other ballot boxes
+    BallotBox,
+}
+
 /// Metadata of a verification
 #[derive(Deserialize, Debug, Clone)]
 pub struct VerificationMetaData {
@@ -23,13 +75,15 @@ pub struct VerificationMetaData {
     name: String,
 
     /// Algorithm in the specifications
-    #[allow(dead_code)]
     algorithm: String,
 
     /// Description of the verification
-    #[allow(dead_code)]
     description: String,
 
+    /// Guidance to interpret a failure of the verification
+    #[serde(default)]
+    failure_guidance: String,
+
     /// Period (Set or Tally) of the verification
     #[serde(deserialize_with = "deserialize_string_to_period")]
     period: VerificationPeriod,
@@ -37,6 +91,113 @@ pub struct VerificationMetaData {
     /// Category of the verification
     #[serde(deserialize_with = "deserialize_string_to_category")]
     category: VerificationCategory,
+
+    /// Impact of a failure on the overall outcome
+    #[serde(default)]
+    impact: VerificationImpact,
+
+    /// Scope of the verification
+    #[serde(default)]
+    scope: VerificationScope,
+}
+
+/// Query on the metadata of the verifications
+///
+/// Each criterion restricts the selection. A query without criterion selects all the
+/// verifications.
+#[derive(Debug, Clone, Default)]
+pub struct MetaDataQuery {
+    periods: Vec<VerificationPeriod>,
+    categories: Vec<VerificationCategory>,
+    impacts: Vec<VerificationImpact>,
+    implemented: Option<bool>,
+    excluded: Option<bool>,
+}
+
+/// Entry of the list of verifications, as presented by a front-end
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MetaDataEntry {
+    pub id: String,
+    pub name: String,
+    pub algorithm: String,
+    pub description: String,
+    pub period: VerificationPeriod,
+    pub category: VerificationCategory,
+    pub impact: VerificationImpact,
+    /// The verification is implemented
+    pub implemented: bool,
+    /// The verification is in the list of exclusions
+    pub excluded: bool,
+}
+
+impl MetaDataQuery {
+    /// New query without criterion
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select the verifications of the given period (many periods can be added)
+    pub fn with_period(mut self, period: VerificationPeriod) -> Self {
+        self.periods.push(period);
+        self
+    }
+
+    /// Select the verifications of the given category (many categories can be added)
+    pub fn with_category(mut self, category: VerificationCategory) -> Self {
+        self.categories.push(category);
+        self
+    }
+
+    /// Select the verifications with the given impact (many impacts can be added)
+    pub fn with_impact(mut self, impact: VerificationImpact) -> Self {
+        self.impacts.push(impact);
+        self
+    }
+
+    /// Select the implemented (`true`) or the not implemented (`false`) verifications
+    pub fn with_implemented(mut self, implemented: bool) -> Self {
+        self.implemented = Some(implemented);
+        self
+    }
+
+    /// Select the excluded (`true`) or the not excluded (`false`) verifications
+    pub fn with_excluded(mut self, excluded: bool) -> Self {
+        self.excluded = Some(excluded);
+        self
+    }
+
+    /// The entry is selected by the query
+    pub fn matches(&self, entry: &MetaDataEntry) -> bool {
+        (self.periods.is_empty() || self.periods.contains(&entry.period))
+            && (self.categories.is_empty() || self.categories.contains(&entry.category))
+            && (self.impacts.is_empty() || self.impacts.contains(&entry.impact))
+            && self.implemented.map_or(true, |b| b == entry.implemented)
+            && self.excluded.map_or(true, |b| b == entry.excluded)
+    }
+}
+
+impl MetaDataEntry {
+    /// Entry for the metadata
+    ///
+    /// `implemented` contains the ids of the implemented verifications and `exclusion` the ids of
+    /// the excluded verifications
+    pub fn new(
+        meta_data: &VerificationMetaData,
+        implemented: &[String],
+        exclusion: &[String],
+    ) -> Self {
+        Self {
+            id: meta_data.id.clone(),
+            name: meta_data.name.clone(),
+            algorithm: meta_data.algorithm.clone(),
+            description: meta_data.description.clone(),
+            period: meta_data.period,
+            category: meta_data.category,
+            impact: meta_data.impact,
+            implemented: implemented.contains(&meta_data.id),
+            excluded: exclusion.contains(&meta_data.id),
+        }
+    }
 }
 
 impl VerificationMetaDataList {
@@ -64,7 +225,6 @@ impl VerificationMetaDataList {
         self.0.iter().map(|e| e.id.clone()).collect::<Vec<String>>()
     }
 
-    #[allow(dead_code)]
     pub fn id_list_for_period(&self, period: &VerificationPeriod) -> Vec<String> {
         self.0
             .iter()
@@ -88,6 +248,25 @@ impl VerificationMetaDataList {
     pub fn iter(&self) -> std::slice::Iter<VerificationMetaData> {
         self.0.iter()
     }
+
+    /// Query the metadata (see [MetaDataQuery])
+    ///
+    /// `implemented` contains the ids of the implemented verifications and `exclusion` the ids of
+    /// the excluded verifications. The entries are sorted by id
+    pub fn query(
+        &self,
+        query: &MetaDataQuery,
+        implemented: &[String],
+        exclusion: &[String],
+    ) -> Vec<MetaDataEntry> {
+        let mut res: Vec<MetaDataEntry> = self
+            .iter()
+            .map(|m| MetaDataEntry::new(m, implemented, exclusion))
+            .filter(|e| query.matches(e))
+            .collect();
+        res.sort_by(|a, b| a.id.cmp(&b.id));
+        res
+    }
 }
 
 impl VerificationMetaData {
@@ -99,16 +278,45 @@ impl VerificationMetaData {
         &self.name
     }
 
+    /// The verification is not part of the specification (see [ADDITIONAL_ID_PREFIX])
+    pub fn is_additional(&self) -> bool {
+        self.id.starts_with(ADDITIONAL_ID_PREFIX)
+    }
+
     #[allow(dead_code)]
     pub fn algorithm(&self) -> &String {
         &self.algorithm
     }
 
-    #[allow(dead_code)]
+    /// Short description of what the verification verifies
     pub fn description(&self) -> &String {
         &self.description
     }
 
+    /// Guidance to interpret a failure of the verification
+    pub fn failure_guidance(&self) -> &String {
+        &self.failure_guidance
+    }
+
+    /// Documentation of the verification, to be shown next to the result of the verification
+    ///
+    /// The documentation contains the description and, if the verification is not ok, the
+    /// guidance to interpret the failures. Then the auditors do not need to consult the
+    /// specification for each failure.
+    pub fn documentation(&self, is_ok: bool) -> String {
+        let mut res = format!("{} ({}): {}", self.name, self.id, self.description);
+        if !self.algorithm.is_empty() {
+            res = format!("{} [{}]", res, self.algorithm);
+        }
+        if !is_ok && !self.failure_guidance.is_empty() {
+            res = format!(
+                "{}\nHow to interpret the failures: {}",
+                res, self.failure_guidance
+            );
+        }
+        res
+    }
+
     pub fn period(&self) -> &VerificationPeriod {
         &self.period
     }
@@ -117,6 +325,16 @@ impl VerificationMetaData {
         &self.category
     }
 
+    /// Impact of a failure of the verification on the overall outcome
+    pub fn impact(&self) -> VerificationImpact {
+        self.impact
+    }
+
+    /// Scope of the verification
+    pub fn scope(&self) -> VerificationScope {
+        self.scope
+    }
+
     pub fn from_id(id: &str, data: &str) -> Option<Self> {
         match VerificationMetaDataList::load(data) {
             Ok(l) => l.get(id).cloned(),
@@ -156,4 +374,141 @@ mod test {
         assert!(!metadata.is_empty());
         assert!(metadata.meta_data_from_id("01.01").is_some())
     }
+
+    #[test]
+    fn test_ids() {
+        let metadata =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        // The ids are `NN.NN`, with the prefix for the additional verifications
+        let is_number = |s: &str| s.len() == 2 && s.chars().all(|c| c.is_ascii_digit());
+        for m in metadata.iter() {
+            let id = m.id().trim_start_matches(ADDITIONAL_ID_PREFIX);
+            let parts: Vec<&str> = id.split('.').collect();
+            assert!(
+                parts.len() == 2 && parts.iter().all(|p| is_number(p)),
+                "wrong id {}",
+                m.id()
+            );
+        }
+        assert!(!metadata.get("01.01").unwrap().is_additional());
+        assert!(metadata.get("X.10.01").unwrap().is_additional());
+    }
+
+    #[test]
+    fn test_documentation() {
+        let metadata =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        for m in metadata.iter() {
+            assert!(!m.description().is_empty(), "description of {}", m.id());
+            assert!(
+                !m.failure_guidance().is_empty(),
+                "failure guidance of {}",
+                m.id()
+            );
+        }
+        let m = metadata.get("01.01").unwrap();
+        assert!(m
+            .documentation(true)
+            .starts_with("VerifySetupCompleteness (01.01)"));
+        assert!(!m.documentation(true).contains(m.failure_guidance()));
+        assert!(m.documentation(false).contains(m.failure_guidance()));
+    }
+
+    #[test]
+    fn test_query() {
+        let metadata =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let implemented = vec![
+            "01.01".to_string(),
+            "X.01.01".to_string(),
+            "06.01".to_string(),
+        ];
+        let exclusion = vec!["X.01.01".to_string()];
+        let all = metadata.query(&MetaDataQuery::new(), &implemented, &exclusion);
+        assert_eq!(all.len(), metadata.len());
+        assert!(all.windows(2).all(|w| w[0].id < w[1].id));
+        let setup = metadata.query(
+            &MetaDataQuery::new().with_period(VerificationPeriod::Setup),
+            &implemented,
+            &exclusion,
+        );
+        assert!(!setup.is_empty());
+        assert!(setup.iter().all(|e| e.period == VerificationPeriod::Setup));
+        let res = metadata.query(
+            &MetaDataQuery::new()
+                .with_period(VerificationPeriod::Setup)
+                .with_implemented(true),
+            &implemented,
+            &exclusion,
+        );
+        assert_eq!(
+            res.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["01.01", "X.01.01"]
+        );
+        assert!(!res[0].excluded);
+        assert!(res[1].excluded);
+        let res = metadata.query(
+            &MetaDataQuery::new()
+                .with_implemented(true)
+                .with_excluded(false),
+            &implemented,
+            &exclusion,
+        );
+        assert_eq!(
+            res.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["01.01", "06.01"]
+        );
+        let res = metadata.query(
+            &MetaDataQuery::new()
+                .with_category(VerificationCategory::Authenticity)
+                .with_implemented(false),
+            &implemented,
+            &exclusion,
+        );
+        assert!(!res.is_empty());
+        assert!(res
+            .iter()
+            .all(|e| e.category == VerificationCategory::Authenticity && !e.implemented));
+    }
+
+    #[test]
+    fn test_impact() {
+        let metadata =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        assert_eq!(
+            metadata.get("01.01").unwrap().impact(),
+            VerificationImpact::Blocking
+        );
+        assert_eq!(
+            metadata.get("X.04.02").unwrap().impact(),
+            VerificationImpact::Informational
+        );
+        let res = metadata.query(
+            &MetaDataQuery::new().with_impact(VerificationImpact::Informational),
+            &[],
+            &[],
+        );
+        assert!(!res.is_empty());
+        assert!(res
+            .iter()
+            .all(|e| e.impact == VerificationImpact::Informational));
+    }
+
+    #[test]
+    fn test_scope() {
+        let metadata =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        assert_eq!(
+            metadata.get("X.10.02").unwrap().scope(),
+            VerificationScope::BallotBox
+        );
+        assert_eq!(
+            metadata.get("X.08.01").unwrap().scope(),
+            VerificationScope::Dataset
+        );
+        assert_eq!(
+            metadata.get("06.01").unwrap().scope(),
+            VerificationScope::Dataset
+        );
+    }
 }
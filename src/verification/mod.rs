@@ -1,6 +1,11 @@
 //! Module implementing all the verifications
 
+mod direct_trust_certificates;
+mod duplicate_chunks;
+mod duplicate_finder;
+pub mod manual;
 pub mod meta_data;
+pub mod query;
 pub mod result;
 mod setup;
 pub mod suite;
@@ -8,15 +13,33 @@ mod tally;
 pub mod verifications;
 
 use self::result::{
-    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
+    create_verification_error, create_verification_failure, FailureCode, VerificationEvent,
+    VerificationResult,
 };
-use crate::{config::Config, direct_trust::VerifiySignatureTrait};
-use anyhow::{anyhow, bail, Result};
-use log::debug;
+use crate::{
+    application_runner::is_directory_tally,
+    config::Config,
+    data_structures::{
+        dataset_version::DatasetVersion, PayloadIdentityTrait, SchemaKind, SchemaValidator,
+        SchemaVersion,
+    },
+    direct_trust::{CertificateAuthority, VerifiySignatureTrait},
+    file_structure::{
+        data_source::DataSource,
+        file::{File, FileContentError},
+        setup_directory::{SetupDirectory, SetupDirectoryTrait},
+        tally_directory::{BBDirectoryTrait, TallyDirectory, TallyDirectoryTrait},
+    },
+};
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, warn};
+use rayon::prelude::*;
 use rust_ev_crypto_primitives::{HashableMessage, Keystore};
-use std::fmt::Display;
+use serde::Serialize;
+use std::{fmt::Display, path::Path, sync::Arc};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum VerificationCategory {
     Authenticity,
     Consistency,
@@ -32,7 +55,8 @@ pub enum VerificationStatus {
     Finished,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum VerificationPeriod {
     Setup,
     Tally,
@@ -47,42 +71,374 @@ impl VerificationPeriod {
     pub fn is_tally(&self) -> bool {
         self == &VerificationPeriod::Tally
     }
+
+    /// Detect the period from the dataset located in `path`, read from the source
+    ///
+    /// The period is tally if the directory tally exists and contains ballot boxes. The ballot boxes
+    /// must be defined in the election event context of the setup.
+    ///
+    /// # Error
+    /// If the directory is not a valid dataset, if the election event context cannot be read or if
+    /// a ballot box of the tally is not defined in the setup
+    pub fn detect(source: &Arc<dyn DataSource>, path: &Path) -> Result<Self> {
+        if !is_directory_tally(source.as_ref(), path)? {
+            return Ok(VerificationPeriod::Setup);
+        }
+        let ee_context = SetupDirectory::new_with_source(path, source.clone())
+            .election_event_context_payload()
+            .context("Cannot detect the period")?;
+        let bb_ids: Vec<&String> = ee_context
+            .election_event_context
+            .verification_card_set_contexts
+            .iter()
+            .map(|c| &c.ballot_box_id)
+            .collect();
+        let tally_dir = TallyDirectory::new_with_source(path, source.clone());
+        if tally_dir.bb_directories().is_empty() {
+            warn!(
+                "The tally directory in {:?} contains no ballot box. The period setup is detected",
+                path
+            );
+            return Ok(VerificationPeriod::Setup);
+        }
+        if let Some(bb) = tally_dir
+            .bb_directories()
+            .iter()
+            .find(|d| !bb_ids.contains(&&d.get_name()))
+        {
+            bail!(format!(
+                "The ballot box {} of the tally is not defined in the election event context of the setup",
+                bb.get_name()
+            ))
+        }
+        Ok(VerificationPeriod::Tally)
+    }
+}
+
+/// Context given to the verification functions
+///
+/// The verification functions have the following form:
+/// ```ignore
+/// fn fn_verification<D: VerificationDirectoryTrait>(
+///    dir: &D,
+///    context: &VerificationContext,
+/// ) -> VerificationResult {
+///     ...
+/// }
+/// ```
+///
+/// The context contains the configuration and the version of the dataset, if it is known
+#[derive(Clone, Copy)]
+pub struct VerificationContext {
+    config: &'static Config,
+    dataset_version: Option<DatasetVersion>,
+}
+
+impl VerificationContext {
+    /// New context, without version of the dataset
+    pub fn new(config: &'static Config) -> Self {
+        Self {
+            config,
+            dataset_version: None,
+        }
+    }
+
+    /// Context with the version of the dataset
+    pub fn with_dataset_version(mut self, version: Option<DatasetVersion>) -> Self {
+        self.dataset_version = version;
+        self
+    }
+
+    /// The configuration of the verifier
+    pub fn config(&self) -> &'static Config {
+        self.config
+    }
+
+    /// The version of the dataset (see [DatasetVersion::detect]), `None` if it is not known
+    pub fn dataset_version(&self) -> Option<DatasetVersion> {
+        self.dataset_version
+    }
 }
 
 /// Verify the signatue for a given object implementing [VerifiySignatureTrait]
 fn verify_signature_for_object<'a, T>(
     obj: &'a T,
-    result: &mut VerificationResult,
     config: &'static Config,
     name: &str,
-) where
+) -> VerificationResult
+where
     T: VerifiySignatureTrait<'a>,
 {
+    let mut result = VerificationResult::new();
     let ks = match config.keystore() {
         Ok(ks) => ks,
         Err(e) => {
             result.push(create_verification_error!(
-                "Cannot read keystore in election_event_configuration",
+                format!("Cannot read keystore to verify the signature of {}", name),
                 e
             ));
-            return;
+            return result;
         }
     };
+    // Pre-validation of the certificate, to report an unsuitable certificate as such
+    if let Ok(ca) = obj
+        .get_certificate_authority()
+        .and_then(|ca| CertificateAuthority::try_from(ca.as_str()))
+    {
+        let problems: Vec<String> = config
+            .certificate_problems(ca)
+            .iter()
+            .filter(|p| p.is_blocking())
+            .map(|p| p.to_string())
+            .collect();
+        if !problems.is_empty() {
+            result.push(
+                create_verification_failure!(format!(
+                    "The certificate {} cannot be used to verify the signature of {}: {}",
+                    String::from(ca),
+                    name,
+                    problems.join("; ")
+                ))
+                .with_code(FailureCode::SignatureInvalid)
+                .with_file(name),
+            );
+            return result;
+        }
+    }
     match obj.verifiy_signature(&ks) {
         Ok(t) => {
             if !t {
-                result.push(create_verification_failure!(format!(
-                    "Wrong signature for {}",
-                    name
-                )))
+                result.push(
+                    create_verification_failure!(format!("Wrong signature for {}", name))
+                        .with_code(FailureCode::SignatureInvalid)
+                        .with_file(name),
+                )
+            }
+        }
+        Err(e) => {
+            result.push(
+                create_verification_error!(format!("Error testing signature of {}", name), e)
+                    .with_code(FailureCode::SignatureInvalid)
+                    .with_file(name),
+            );
+        }
+    }
+    result
+}
+
+/// Number of payloads read before their signatures are verified in parallel
+///
+/// The payloads of a batch are kept in memory. The batch is large enough to keep the worker
+/// threads busy, and small enough for the large payloads (e.g. the code shares)
+const SIGNATURE_BATCH_SIZE: usize = 32;
+
+/// Verify the signatures of the payloads of a file group (or of any sequence of payloads)
+///
+/// The items of the iterator are the name of the payload (used in the messages) and the
+/// payload or the error reading it. The payloads are read sequentially by batches of
+/// [SIGNATURE_BATCH_SIZE] and the signatures of a batch are verified in parallel on the thread
+/// pool of rayon. The results are aggregated in the order of the iterator.
+fn verify_signatures_for_payloads<T>(
+    payloads: impl Iterator<Item = (String, anyhow::Result<Box<T>>)>,
+    config: &'static Config,
+) -> VerificationResult
+where
+    T: for<'a> VerifiySignatureTrait<'a> + Send + Sync,
+{
+    let mut result = VerificationResult::new();
+    let mut payloads = payloads.peekable();
+    while payloads.peek().is_some() {
+        let batch: Vec<(String, anyhow::Result<Box<T>>)> =
+            payloads.by_ref().take(SIGNATURE_BATCH_SIZE).collect();
+        debug!(
+            "Verify the signatures of a batch of {} payloads",
+            batch.len()
+        );
+        let results: Vec<VerificationResult> = batch
+            .into_par_iter()
+            .map(|(name, p)| match p {
+                Ok(p) => verify_signature_for_object(p.as_ref(), config, &name),
+                Err(e) => {
+                    let mut r = VerificationResult::new();
+                    r.push(
+                        create_verification_error!(format!("{} cannot be read", name), e)
+                            .with_code(FailureCode::ReadError)
+                            .with_file(&name),
+                    );
+                    r
+                }
+            })
+            .collect();
+        for mut r in results {
+            result.append(&mut r);
+        }
+    }
+    result
+}
+
+/// Verify that the election event id of each payload is equal to the expected one
+///
+/// The items of the iterator are the name of the payload (used in the messages) and the
+/// payload or the error reading it. The payloads without election event id are ignored.
+fn verify_election_event_ids<P: PayloadIdentityTrait>(
+    payloads: impl Iterator<Item = (String, anyhow::Result<P>)>,
+    expected: &String,
+    result: &mut VerificationResult,
+) {
+    for (name, p) in payloads {
+        match p {
+            Ok(p) => {
+                if let Some(id) = p.election_event_id() {
+                    if id != expected {
+                        result.push(
+                            create_verification_failure!(format!(
+                                "Election Event ID not equal in {}",
+                                name
+                            ))
+                            .with_code(FailureCode::Inconsistency)
+                            .with_file(&name),
+                        );
+                    }
+                }
             }
+            Err(e) => result.push(
+                create_verification_error!(format!("{} has wrong format", name), e)
+                    .with_code(FailureCode::ReadError)
+                    .with_file(&name),
+            ),
         }
+    }
+}
+
+/// Verify that the verification card set id or ballot box id of each payload is equal to
+/// the expected one
+///
+/// See [verify_election_event_ids] for the items of the iterator.
+fn verify_vcs_or_bb_ids<P: PayloadIdentityTrait>(
+    payloads: impl Iterator<Item = (String, anyhow::Result<P>)>,
+    expected: &String,
+    result: &mut VerificationResult,
+) {
+    for (name, p) in payloads {
+        match p {
+            Ok(p) => {
+                if let Some(id) = p.vcs_or_bb_id() {
+                    if id != expected {
+                        result.push(
+                            create_verification_failure!(format!(
+                                "The id {} is not equal to the expected id {} in {}",
+                                id, expected, name
+                            ))
+                            .with_code(FailureCode::Inconsistency)
+                            .with_file(&name),
+                        );
+                    }
+                }
+            }
+            Err(e) => result.push(
+                create_verification_error!(format!("{} has wrong format", name), e)
+                    .with_code(FailureCode::ReadError)
+                    .with_file(&name),
+            ),
+        }
+    }
+}
+
+/// Failure for a missing file, with the name of the file (or of the payload) in the context
+fn missing_file_failure(name: &str) -> VerificationEvent {
+    create_verification_failure!(format!("{} does not exist", name))
+        .with_code(FailureCode::MissingFile)
+        .with_file(name)
+}
+
+/// Verify that the file is neither empty nor truncated (see [File::check_content])
+///
+/// A missing file is ignored, since it is reported as missing. An empty or truncated file is a
+/// failure, with a failed item of kind `file` giving the code of the problem
+fn verify_file_content(file: &File, result: &mut VerificationResult) {
+    if !file.exists() {
+        return;
+    }
+    if let Err(e) = file.check_content() {
+        match e.downcast_ref::<FileContentError>() {
+            Some(content_error) => {
+                result.push_failed_item("file", &file.to_str(), content_error.code());
+                result.push(
+                    VerificationEvent::new_failure(e)
+                        .with_code(FailureCode::FileContent)
+                        .with_file(file.to_str()),
+                );
+            }
+            None => result.push(create_verification_error!(
+                format!("Cannot check the content of {}", file.to_str()),
+                e
+            )),
+        }
+    }
+}
+
+/// Validate the xml file against the schema of the given kind
+///
+/// Each violation of the schema is a failure, with a failed item giving the path of the element
+fn validate_xml_file(
+    file: &File,
+    schema_kind: &SchemaKind,
+    name: &str,
+    result: &mut VerificationResult,
+) {
+    match file.read_data() {
+        Ok(xml) => validate_xml_str(&xml, schema_kind, SchemaVersion::default(), name, result),
+        Err(e) => result.push(
+            create_verification_error!(format!("Cannot validate {}", name), e)
+                .with_code(FailureCode::ReadError)
+                .with_file(name),
+        ),
+    }
+}
+
+/// Validate the xml document against the schema of the given kind and version of the dataset
+///
+/// See [validate_xml_file]
+fn validate_xml_str(
+    xml: &str,
+    schema_kind: &SchemaKind,
+    version: SchemaVersion,
+    name: &str,
+    result: &mut VerificationResult,
+) {
+    let validator = match SchemaValidator::new_with_version(schema_kind, version) {
+        Ok(v) => v,
         Err(e) => {
             result.push(create_verification_error!(
-                format!("Error testing signature of {}", name),
+                format!("Cannot create the validator of the schema for {}", name),
                 e
             ));
+            return;
         }
+    };
+    let violations = match validator.validate_str(xml) {
+        Ok(v) => v,
+        Err(e) => {
+            result.push(
+                create_verification_error!(format!("Cannot validate {}", name), e)
+                    .with_code(FailureCode::ReadError)
+                    .with_file(name),
+            );
+            return;
+        }
+    };
+    debug!(
+        "{} violation(s) of the schema in {}",
+        violations.len(),
+        name
+    );
+    for v in violations.iter() {
+        result.push(
+            create_verification_failure!(format!("{} does not match the schema: {}", name, v))
+                .with_code(FailureCode::SchemaViolation)
+                .with_file(name),
+        );
+        result.push_failed_item(name, &v.path, &v.message);
     }
 }
 
@@ -149,3 +505,47 @@ impl Display for VerificationCategory {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{result::VerificationResultTrait, *};
+    use crate::config::test::{test_dataset_setup_path, test_dataset_tally_path};
+    use crate::data_structures::{setup::VerifierSetupDataType, VerifierDataType};
+    use crate::file_structure::{data_source::local_source, memory_fs::MemoryFileSystem};
+
+    #[test]
+    fn test_verify_file_content() {
+        let mfs = MemoryFileSystem::new(Path::new("verification_test_verify_file_content"));
+        mfs.add_file("ok/electionEventContextPayload.json", "{}")
+            .add_file("truncated/electionEventContextPayload.json", "{\"a\":");
+        let data_type = VerifierDataType::Setup(VerifierSetupDataType::ElectionEventContextPayload);
+        let mut result = VerificationResult::new();
+        let file =
+            |d: &str| File::new_with_source(&mfs.source(), &mfs.root().join(d), &data_type, None);
+        for d in ["ok", "missing"] {
+            verify_file_content(&file(d), &mut result);
+        }
+        assert!(result.is_ok().unwrap());
+        verify_file_content(&file("truncated"), &mut result);
+        assert!(result.has_failures().unwrap());
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(
+            result.failed_items()[0].reason,
+            FileContentError::TRUNCATED_CODE
+        );
+    }
+
+    #[test]
+    fn test_detect_period() {
+        let source = local_source();
+        assert_eq!(
+            VerificationPeriod::detect(&source, &test_dataset_setup_path()).unwrap(),
+            VerificationPeriod::Setup
+        );
+        assert_eq!(
+            VerificationPeriod::detect(&source, &test_dataset_tally_path()).unwrap(),
+            VerificationPeriod::Tally
+        );
+        assert!(VerificationPeriod::detect(&source, Path::new("./toto")).is_err());
+    }
+}
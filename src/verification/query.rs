@@ -0,0 +1,300 @@
+//! Module implementing the queries on the results of the verifications
+//!
+//! The queries allow the front-ends (e.g. GUI) to browse the results without copying them in
+//! their own model. A [ResultQuery] is created with the builder functions and applied on a
+//! [VerificationList] (or a suite). It returns lightweight views ([ResultView]) referencing the
+//! verifications.
+
+use super::{
+    result::{FailedItem, VerificationResultTrait},
+    suite::VerificationList,
+    verifications::Verification,
+    VerificationCategory,
+};
+use crate::file_structure::VerificationDirectory;
+use serde::Serialize;
+
+/// Status of the result of a verification
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum ResultStatus {
+    /// The verification is not run (or is running)
+    NotRun,
+    /// The verification is ok
+    Ok,
+    /// The verification has errors (and possibly failures)
+    Errors,
+    /// The verification has failures, but no error
+    Failures,
+}
+
+impl ResultStatus {
+    /// Status of the result
+    pub fn of<T: VerificationResultTrait>(result: &T) -> Self {
+        match (result.is_ok(), result.has_errors()) {
+            (None, _) => Self::NotRun,
+            (Some(true), _) => Self::Ok,
+            (Some(false), Some(true)) => Self::Errors,
+            (Some(false), _) => Self::Failures,
+        }
+    }
+}
+
+/// Query on the results of the verifications
+///
+/// Each criterion restricts the selection. A query without criterion selects all the
+/// verifications.
+#[derive(Debug, Clone, Default)]
+pub struct ResultQuery {
+    statuses: Vec<ResultStatus>,
+    categories: Vec<VerificationCategory>,
+    text: Option<String>,
+    item_id: Option<String>,
+}
+
+/// View on the result of a verification selected by a [ResultQuery]
+pub struct ResultView<'v, 'a> {
+    verification: &'v Verification<'a, VerificationDirectory>,
+    status: ResultStatus,
+    messages: Vec<String>,
+    failed_items: Vec<&'v FailedItem>,
+}
+
+impl ResultQuery {
+    /// New query without criterion
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select the verifications with the given status (many statuses can be added)
+    pub fn with_status(mut self, status: ResultStatus) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    /// Select the verifications of the given category (many categories can be added)
+    pub fn with_category(mut self, category: VerificationCategory) -> Self {
+        self.categories.push(category);
+        self
+    }
+
+    /// Select the verifications whose messages (errors and failures) contain the text
+    ///
+    /// The search is case insensitive. Only the matching messages are in the views
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_lowercase());
+        self
+    }
+
+    /// Select the verifications concerning the item (e.g. a ballot box or a VCS)
+    ///
+    /// A verification concerns the item if a failed item has the id, or if a message
+    /// contains the id. Only the matching failed items and messages are in the views
+    pub fn with_item_id(mut self, id: &str) -> Self {
+        self.item_id = Some(id.to_string());
+        self
+    }
+
+    /// Apply the query on the verifications
+    pub fn apply<'v, 'a: 'v>(
+        &self,
+        verifications: impl Iterator<Item = &'v Verification<'a, VerificationDirectory>>,
+    ) -> Vec<ResultView<'v, 'a>> {
+        verifications.filter_map(|v| self.view(v)).collect()
+    }
+
+    /// View of the verification if it is selected by the query
+    fn view<'v, 'a: 'v>(
+        &self,
+        verification: &'v Verification<'a, VerificationDirectory>,
+    ) -> Option<ResultView<'v, 'a>> {
+        let status = ResultStatus::of(verification);
+        if !self.statuses.is_empty() && !self.statuses.contains(&status) {
+            return None;
+        }
+        if !self.categories.is_empty()
+            && !self
+                .categories
+                .contains(verification.meta_data().category())
+        {
+            return None;
+        }
+        let mut messages: Vec<String> = verification
+            .errors_to_string()
+            .into_iter()
+            .chain(verification.failures_to_string())
+            .collect();
+        let mut failed_items: Vec<&FailedItem> = verification.failed_items().iter().collect();
+        if let Some(t) = &self.text {
+            messages.retain(|m| m.to_lowercase().contains(t));
+            if messages.is_empty() {
+                return None;
+            }
+        }
+        if let Some(id) = &self.item_id {
+            messages.retain(|m| m.contains(id));
+            failed_items.retain(|i| &i.id == id);
+            if messages.is_empty() && failed_items.is_empty() {
+                return None;
+            }
+        }
+        Some(ResultView {
+            verification,
+            status,
+            messages,
+            failed_items,
+        })
+    }
+}
+
+impl<'v, 'a> ResultView<'v, 'a> {
+    /// The verification
+    pub fn verification(&self) -> &'v Verification<'a, VerificationDirectory> {
+        self.verification
+    }
+
+    /// Id of the verification
+    pub fn id(&self) -> &'v str {
+        self.verification.id()
+    }
+
+    /// Name of the verification
+    pub fn name(&self) -> &'a str {
+        self.verification.meta_data().name()
+    }
+
+    /// Category of the verification
+    pub fn category(&self) -> VerificationCategory {
+        *self.verification.meta_data().category()
+    }
+
+    /// Status of the verification
+    pub fn status(&self) -> ResultStatus {
+        self.status
+    }
+
+    /// Messages (errors and failures) selected by the query
+    pub fn messages(&self) -> &[String] {
+        &self.messages
+    }
+
+    /// Failed items selected by the query
+    pub fn failed_items(&self) -> &[&'v FailedItem] {
+        &self.failed_items
+    }
+}
+
+impl<'a> VerificationList<'a> {
+    /// Query the results of the verifications
+    pub fn query<'v>(&'v self, query: &ResultQuery) -> Vec<ResultView<'v, 'a>> {
+        query.apply(self.0.iter())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::{
+            meta_data::VerificationMetaDataList,
+            result::{
+                create_verification_error, create_verification_failure, VerificationEvent,
+                VerificationResult,
+            },
+            VerificationContext, VerificationPeriod,
+        },
+        *,
+    };
+    use crate::config::test::{test_dataset_setup_path, CONFIG_TEST};
+    use anyhow::anyhow;
+    use log::debug;
+
+    fn ok(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+        VerificationResult::new()
+    }
+
+    fn failures(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+        let mut result = VerificationResult::new();
+        result.push(create_verification_failure!(
+            "Wrong number of votes in bb_1"
+        ));
+        result.push_failed_item("ballot_box", "bb_1", "wrong number");
+        result.push(create_verification_failure!(
+            "Wrong number of votes in bb_2"
+        ));
+        result.push_failed_item("ballot_box", "bb_2", "wrong number");
+        result
+    }
+
+    fn errors(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+        let mut result = VerificationResult::new();
+        result.push(create_verification_error!(
+            "Cannot read the payload of vcs_1"
+        ));
+        result
+    }
+
+    #[test]
+    fn test_query() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut list = VerificationList(vec![
+            Verification::new(
+                "01.01",
+                "VerifySetupCompleteness",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+            Verification::new(
+                "X.01.01",
+                "VerifyVerificationDataCoverCards",
+                failures,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+            Verification::new(
+                "03.01",
+                "VerifyEncryptionGroupConsistency",
+                errors,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap(),
+        ]);
+        assert_eq!(list.query(&ResultQuery::new()).len(), 3);
+        assert!(list
+            .query(&ResultQuery::new())
+            .iter()
+            .all(|v| v.status() == ResultStatus::NotRun));
+        let dir =
+            VerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        for v in list.0.iter_mut() {
+            v.run(&dir);
+        }
+        let res = list.query(&ResultQuery::new().with_status(ResultStatus::Failures));
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].id(), "X.01.01");
+        assert_eq!(res[0].messages().len(), 2);
+        let res = list.query(
+            &ResultQuery::new()
+                .with_status(ResultStatus::Failures)
+                .with_status(ResultStatus::Errors),
+        );
+        assert_eq!(res.len(), 2);
+        let res = list.query(&ResultQuery::new().with_category(VerificationCategory::Consistency));
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].status(), ResultStatus::Errors);
+        let res = list.query(&ResultQuery::new().with_text("CANNOT READ"));
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].id(), "03.01");
+        let res = list.query(&ResultQuery::new().with_item_id("bb_2"));
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].messages().len(), 1);
+        assert_eq!(res[0].failed_items().len(), 1);
+        assert_eq!(res[0].failed_items()[0].id, "bb_2");
+        assert!(list
+            .query(&ResultQuery::new().with_item_id("bb_3"))
+            .is_empty());
+    }
+}
@@ -1,15 +1,128 @@
 //! Module implementing the errors of the verifications
 //!
 //use crate::error::VerifierError;
-use thiserror::Error;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display};
 
 /// Enum representing one event (an error or a failure) during the tests
-#[derive(Error, Debug)]
+///
+/// The event can be annotated with the entities it concerns (see
+/// [VerificationEvent::with_entity]), so that the events can be grouped per entity across the
+/// verifications. The entities are not part of the message.
+///
+/// For the automated triage, the event has a [FailureCode] and an [EventContext] (see
+/// [VerificationEvent::with_code] and [VerificationEvent::with_file]).
+#[derive(Debug)]
 pub enum VerificationEvent {
-    #[error(transparent)]
-    Error { source: anyhow::Error },
-    #[error(transparent)]
-    Failure { source: anyhow::Error },
+    Error {
+        source: anyhow::Error,
+        entities: Vec<Entity>,
+        code: FailureCode,
+        context: EventContext,
+    },
+    Failure {
+        source: anyhow::Error,
+        entities: Vec<Entity>,
+        code: FailureCode,
+        context: EventContext,
+    },
+}
+
+/// Code classifying the cause of an event, for the automated triage of the results
+///
+/// The events created without code are [FailureCode::Unclassified]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCode {
+    #[default]
+    Unclassified,
+    /// An expected file is missing
+    MissingFile,
+    /// A file is empty or truncated
+    FileContent,
+    /// A file cannot be read or decoded
+    ReadError,
+    /// A XML file does not match its schema
+    SchemaViolation,
+    /// The signature of a file is not valid or cannot be verified
+    SignatureInvalid,
+    /// A zero-knowledge proof is not valid
+    ProofInvalid,
+    /// A value is not in its domain (e.g. not a member of the group)
+    DomainCheck,
+    /// Data of different files or of different nodes are not consistent
+    Inconsistency,
+    /// The verification is not applicable for the dataset
+    NotApplicable,
+}
+
+/// Context of an event: where in the dataset the event occurred
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EventContext {
+    /// Name of the file (or of the payload)
+    pub file: Option<String>,
+    /// Id of the verification card set
+    pub vcs_id: Option<String>,
+    /// Number of the chunk
+    pub chunk: Option<usize>,
+}
+
+/// Kind of the entities of the election event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    VerificationCardSet,
+    BallotBox,
+    /// Control component, identified by its node id
+    ControlComponent,
+    VerificationCard,
+}
+
+/// Entity (verification card set, ballot box, etc.) concerned by an event
+///
+/// The entities are sorted per kind, then per id
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub id: String,
+}
+
+/// Item (voting card, chunk, ballot box, etc.) for which the verification failed
+///
+/// The failed items are collected in addition to the failures, in order to export them
+/// for further investigations (see [VerificationResultTrait::failed_items_to_csv])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedItem {
+    /// Kind of the item (e.g. `verification_card`, `chunk`, `ballot_box`)
+    pub kind: String,
+    /// Identifier of the item
+    pub id: String,
+    /// Reason of the failure
+    pub reason: String,
+}
+
+/// Result of the verification for one item (chunk, verification card set, ballot box, etc.)
+///
+/// For the verifications over many items, the sub-results give the number of items verified
+/// successfully (see [VerificationResult::append_sub_result])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubResult {
+    /// Kind of the item (e.g. `chunk`, `ballot_box`)
+    pub kind: String,
+    /// Identifier of the item
+    pub id: String,
+    /// Number of errors for the item
+    pub errors: usize,
+    /// Number of failures for the item
+    pub failures: usize,
+}
+
+/// Number of items of a kind verified successfully
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubResultsSummary {
+    pub kind: String,
+    pub total: usize,
+    pub ok: usize,
 }
 
 /// Struct representing a result of the verification
@@ -18,6 +131,8 @@ pub enum VerificationEvent {
 pub struct VerificationResult {
     errors: Vec<VerificationEvent>,
     failures: Vec<VerificationEvent>,
+    failed_items: Vec<FailedItem>,
+    sub_results: Vec<SubResult>,
 }
 
 /// Trait defining functions to access the verficiation result
@@ -52,6 +167,295 @@ pub trait VerificationResultTrait {
 
     /// Mutable reference to the failures
     fn failures_mut(&mut self) -> &mut Vec<VerificationEvent>;
+
+    /// All the failed items
+    fn failed_items(&self) -> &Vec<FailedItem>;
+
+    /// All the sub-results
+    fn sub_results(&self) -> &Vec<SubResult>;
+
+    /// The errors and the failures with the given code
+    fn events_with_code(&self, code: FailureCode) -> Vec<&VerificationEvent> {
+        self.errors()
+            .iter()
+            .chain(self.failures().iter())
+            .filter(|e| e.code() == code)
+            .collect()
+    }
+
+    /// Number of errors and failures per code. The codes without event are not in the map
+    fn count_by_code(&self) -> BTreeMap<FailureCode, usize> {
+        let mut res = BTreeMap::new();
+        for e in self.errors().iter().chain(self.failures().iter()) {
+            *res.entry(e.code()).or_default() += 1;
+        }
+        res
+    }
+
+    /// Summary of the sub-results per kind, in the order of the first sub-result of each kind
+    fn sub_results_summary(&self) -> Vec<SubResultsSummary> {
+        let mut res: Vec<SubResultsSummary> = vec![];
+        for sub in self.sub_results() {
+            let pos = match res.iter().position(|s| s.kind == sub.kind) {
+                Some(pos) => pos,
+                None => {
+                    res.push(SubResultsSummary {
+                        kind: sub.kind.clone(),
+                        total: 0,
+                        ok: 0,
+                    });
+                    res.len() - 1
+                }
+            };
+            res[pos].total += 1;
+            if sub.is_ok() {
+                res[pos].ok += 1;
+            }
+        }
+        res
+    }
+
+    /// The failed items as csv (with header), separated by `,`
+    fn failed_items_to_csv(&self) -> String {
+        let mut res = String::from("kind,id,reason\n");
+        for item in self.failed_items() {
+            res.push_str(&format!(
+                "{},{},{}\n",
+                csv_field(&item.kind),
+                csv_field(&item.id),
+                csv_field(&item.reason)
+            ));
+        }
+        res
+    }
+}
+
+/// Escape a field for csv, if necessary
+pub(crate) fn csv_field(value: &str) -> String {
+    match value.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+impl Display for EntityKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::VerificationCardSet => "verification card set",
+            Self::BallotBox => "ballot box",
+            Self::ControlComponent => "control component",
+            Self::VerificationCard => "verification card",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.kind, self.id)
+    }
+}
+
+impl Entity {
+    pub fn new(kind: EntityKind, id: impl ToString) -> Self {
+        Self {
+            kind,
+            id: id.to_string(),
+        }
+    }
+}
+
+impl FailureCode {
+    /// All the codes
+    pub const ALL: [FailureCode; 10] = [
+        Self::Unclassified,
+        Self::MissingFile,
+        Self::FileContent,
+        Self::ReadError,
+        Self::SchemaViolation,
+        Self::SignatureInvalid,
+        Self::ProofInvalid,
+        Self::DomainCheck,
+        Self::Inconsistency,
+        Self::NotApplicable,
+    ];
+
+    /// The code as string (e.g. `signature_invalid`), like in the serialization
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unclassified => "unclassified",
+            Self::MissingFile => "missing_file",
+            Self::FileContent => "file_content",
+            Self::ReadError => "read_error",
+            Self::SchemaViolation => "schema_violation",
+            Self::SignatureInvalid => "signature_invalid",
+            Self::ProofInvalid => "proof_invalid",
+            Self::DomainCheck => "domain_check",
+            Self::Inconsistency => "inconsistency",
+            Self::NotApplicable => "not_applicable",
+        }
+    }
+}
+
+impl Display for FailureCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for FailureCode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.as_str() == value)
+            .ok_or_else(|| anyhow::anyhow!("Unknown failure code '{}'", value))
+    }
+}
+
+impl VerificationEvent {
+    /// New error without code and context
+    pub fn new_error(source: anyhow::Error) -> Self {
+        Self::Error {
+            source,
+            entities: vec![],
+            code: FailureCode::default(),
+            context: EventContext::default(),
+        }
+    }
+
+    /// New failure without code and context
+    pub fn new_failure(source: anyhow::Error) -> Self {
+        Self::Failure {
+            source,
+            entities: vec![],
+            code: FailureCode::default(),
+            context: EventContext::default(),
+        }
+    }
+
+    /// Annotate the event with the entity of the given kind and id
+    ///
+    /// The first verification card set is also set as `vcs_id` of the context
+    pub fn with_entity(mut self, kind: EntityKind, id: impl ToString) -> Self {
+        self.add_entity(Entity::new(kind, id));
+        self
+    }
+
+    fn add_entity(&mut self, entity: Entity) {
+        match self {
+            Self::Error {
+                entities, context, ..
+            }
+            | Self::Failure {
+                entities, context, ..
+            } => {
+                if entity.kind == EntityKind::VerificationCardSet && context.vcs_id.is_none() {
+                    context.vcs_id = Some(entity.id.clone());
+                }
+                if !entities.contains(&entity) {
+                    entities.push(entity)
+                }
+            }
+        }
+    }
+
+    /// Set the code of the event
+    pub fn with_code(mut self, code: FailureCode) -> Self {
+        match &mut self {
+            Self::Error { code: c, .. } | Self::Failure { code: c, .. } => *c = code,
+        }
+        self
+    }
+
+    /// Set the file of the context of the event
+    pub fn with_file(mut self, file: impl ToString) -> Self {
+        self.context_mut().file = Some(file.to_string());
+        self
+    }
+
+    /// Set the chunk of the context of the event
+    pub fn with_chunk(mut self, chunk: usize) -> Self {
+        self.context_mut().chunk = Some(chunk);
+        self
+    }
+
+    /// The code of the event
+    pub fn code(&self) -> FailureCode {
+        match self {
+            Self::Error { code, .. } | Self::Failure { code, .. } => *code,
+        }
+    }
+
+    /// The context of the event
+    pub fn context(&self) -> &EventContext {
+        match self {
+            Self::Error { context, .. } | Self::Failure { context, .. } => context,
+        }
+    }
+
+    fn context_mut(&mut self) -> &mut EventContext {
+        match self {
+            Self::Error { context, .. } | Self::Failure { context, .. } => context,
+        }
+    }
+
+    /// The entities concerned by the event
+    pub fn entities(&self) -> &[Entity] {
+        match self {
+            Self::Error { entities, .. } | Self::Failure { entities, .. } => entities,
+        }
+    }
+
+    /// The embedded error
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            Self::Error { source, .. } | Self::Failure { source, .. } => source,
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Self::Failure { .. })
+    }
+}
+
+impl Display for VerificationEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.inner(), f)
+    }
+}
+
+impl std::error::Error for VerificationEvent {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        std::error::Error::source(&**self.inner())
+    }
+}
+
+/// Group the messages of the events per entity. The events without entity are ignored
+///
+/// The messages of an entity are in the order of the events
+pub fn group_by_entity(events: &[VerificationEvent]) -> BTreeMap<Entity, Vec<String>> {
+    let mut res: BTreeMap<Entity, Vec<String>> = BTreeMap::new();
+    for e in events.iter() {
+        for entity in e.entities() {
+            res.entry(entity.clone()).or_default().push(e.to_string());
+        }
+    }
+    res
+}
+
+impl SubResult {
+    /// The item has neither errors nor failures
+    pub fn is_ok(&self) -> bool {
+        self.errors == 0 && self.failures == 0
+    }
+}
+
+impl Display for SubResultsSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}/{} ok", self.kind, self.ok, self.total)
+    }
 }
 
 impl VerificationResult {
@@ -60,14 +464,27 @@ impl VerificationResult {
         VerificationResult {
             errors: vec![],
             failures: vec![],
+            failed_items: vec![],
+            sub_results: vec![],
         }
     }
 
+    /// Push a new failed item to the VerificationResult
+    ///
+    /// The failure itself must be pushed separately with [Self::push]
+    pub fn push_failed_item(&mut self, kind: &str, id: &str, reason: &str) {
+        self.failed_items.push(FailedItem {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            reason: reason.to_string(),
+        })
+    }
+
     /// Push a new error or failure to the VerificationResult
     pub fn push(&mut self, e: VerificationEvent) {
         match &e {
-            VerificationEvent::Error { source: _ } => self.errors.push(e),
-            VerificationEvent::Failure { source: _ } => self.failures.push(e),
+            VerificationEvent::Error { .. } => self.errors.push(e),
+            VerificationEvent::Failure { .. } => self.failures.push(e),
         }
     }
 
@@ -75,12 +492,36 @@ impl VerificationResult {
     pub fn append(&mut self, other: &mut Self) {
         self.errors.append(other.errors_mut());
         self.failures.append(other.failures_mut());
+        self.failed_items.append(&mut other.failed_items);
+        self.sub_results.append(&mut other.sub_results);
+    }
+
+    /// Append the results of other, being the result for the item `id` of the given kind, to
+    /// self, emptying the vectors of other
+    ///
+    /// The number of errors and failures of other are recorded as sub-result
+    pub fn append_sub_result(&mut self, kind: &str, id: &str, other: &mut Self) {
+        self.sub_results.push(SubResult {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            errors: other.errors.len(),
+            failures: other.failures.len(),
+        });
+        self.append(other);
+    }
+
+    /// Annotate all the errors and failures with the entity of the given kind and id (see
+    /// [VerificationEvent::with_entity])
+    pub fn annotate(&mut self, kind: EntityKind, id: &str) {
+        for e in self.errors.iter_mut().chain(self.failures.iter_mut()) {
+            e.add_entity(Entity::new(kind, id))
+        }
     }
 
     /// Append anyhow errors to self as errors
     #[allow(dead_code)]
     pub fn append_errors(&mut self, errors: &[anyhow::Error]) {
-        let events: Vec<VerificationEvent> = errors.iter().map(|e| VerificationEvent::Error { source: anyhow::anyhow!(e.to_string()) }).collect();
+        let events: Vec<VerificationEvent> = errors.iter().map(|e| VerificationEvent::new_error(anyhow::anyhow!(e.to_string()))).collect();
         for e in events {
             self.push(e)
         }
@@ -88,7 +529,7 @@ impl VerificationResult {
 
     /// Append anyhow errors to self as failures
     pub fn append_failures(&mut self, failures: &[anyhow::Error]) {
-        let events: Vec<VerificationEvent> = failures.iter().map(|e| VerificationEvent::Error { source: anyhow::anyhow!(e.to_string()) }).collect();
+        let events: Vec<VerificationEvent> = failures.iter().map(|e| VerificationEvent::new_error(anyhow::anyhow!(e.to_string()))).collect();
         for e in events {
             self.push(e)
         }
@@ -103,7 +544,7 @@ impl Default for VerificationResult {
 
 impl VerificationResultTrait for VerificationResult {
     fn is_ok(&self) -> Option<bool> {
-        Some(!self.has_errors().unwrap() && !self.has_failures().unwrap())
+        Some(self.errors.is_empty() && self.failures.is_empty())
     }
 
     fn has_errors(&self) -> Option<bool> {
@@ -137,6 +578,14 @@ impl VerificationResultTrait for VerificationResult {
     fn failures_to_string(&self) -> Vec<String> {
         self.failures().iter().map(|e| e.to_string()).collect()
     }
+
+    fn failed_items(&self) -> &Vec<FailedItem> {
+        &self.failed_items
+    }
+
+    fn sub_results(&self) -> &Vec<SubResult> {
+        &self.sub_results
+    }
 }
 
 /// Macro to create a verification error (with or without embedded error)
@@ -144,12 +593,12 @@ macro_rules! create_verification_error {
     ($m: expr) => {{
         let e = anyhow!($m);
         debug!("{}", format!("Error: {}", e));
-        VerificationEvent::Error { source: e }
+        VerificationEvent::new_error(e)
     }};
     ($m: expr, $e: expr) => {{
         let e = anyhow!($e).context($m);
         debug!("{}", format!("Error: {}", e));
-        VerificationEvent::Error { source: e }
+        VerificationEvent::new_error(e)
     }};
 }
 pub(crate) use create_verification_error;
@@ -159,16 +608,148 @@ macro_rules! create_verification_failure {
     ($m: expr) => {{
         let e = anyhow!($m);
         debug!("{}", format!("Failure: {}", e));
-        VerificationEvent::Failure { source: e }
+        VerificationEvent::new_failure(e)
     }};
     ($m: expr, $e: expr) => {{
         let e = anyhow!($e).context($m);
         debug!("{}", format!("Failure: {}", e));
-        VerificationEvent::Failure { source: e }
+        VerificationEvent::new_failure(e)
     }};
 }
 pub(crate) use create_verification_failure;
 
+#[cfg(test)]
+mod test {
+    use super::*;
+    use anyhow::anyhow;
+    use log::debug;
+
+    #[test]
+    fn test_failed_items() {
+        let mut result = VerificationResult::new();
+        result.push(create_verification_failure!("failure"));
+        result.push_failed_item("chunk", "1", "wrong proof");
+        let mut other = VerificationResult::new();
+        other.push_failed_item("verification_card", "abc", "wrong \"proof\", node 1");
+        result.append(&mut other);
+        assert_eq!(result.failed_items().len(), 2);
+        assert!(other.failed_items().is_empty());
+        assert_eq!(
+            result.failed_items_to_csv(),
+            "kind,id,reason\nchunk,1,wrong proof\nverification_card,abc,\"wrong \"\"proof\"\", node 1\"\n"
+        );
+    }
+
+    #[test]
+    fn test_entities() {
+        let events = vec![
+            create_verification_failure!("failure bb_2")
+                .with_entity(EntityKind::BallotBox, "bb_2")
+                .with_entity(EntityKind::ControlComponent, 1),
+            create_verification_failure!("failure bb_1")
+                .with_entity(EntityKind::BallotBox, "bb_1")
+                .with_entity(EntityKind::BallotBox, "bb_1"),
+            create_verification_failure!("failure without entity"),
+            create_verification_error!("error bb_2").with_entity(EntityKind::BallotBox, "bb_2"),
+        ];
+        assert_eq!(events[1].entities().len(), 1);
+        assert!(events[0].is_failure());
+        assert_eq!(events[0].to_string(), "failure bb_2");
+        let groups = group_by_entity(&events);
+        assert_eq!(
+            groups.keys().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec!["ballot box bb_1", "ballot box bb_2", "control component 1"]
+        );
+        assert_eq!(
+            groups[&Entity::new(EntityKind::BallotBox, "bb_2")],
+            vec!["failure bb_2", "error bb_2"]
+        );
+    }
+
+    #[test]
+    fn test_codes() {
+        let mut result = VerificationResult::new();
+        result.push(
+            create_verification_failure!("wrong signature")
+                .with_code(FailureCode::SignatureInvalid)
+                .with_file("setup_component_public_keys_payload"),
+        );
+        result.push(
+            create_verification_failure!("wrong proof")
+                .with_code(FailureCode::ProofInvalid)
+                .with_chunk(3)
+                .with_entity(EntityKind::VerificationCardSet, "vcs_1"),
+        );
+        result.push(create_verification_error!("error"));
+        let mut other = VerificationResult::new();
+        other.push(
+            create_verification_failure!("other proof").with_code(FailureCode::ProofInvalid),
+        );
+        other.annotate(EntityKind::VerificationCardSet, "vcs_2");
+        result.append(&mut other);
+        let proofs = result.events_with_code(FailureCode::ProofInvalid);
+        assert_eq!(proofs.len(), 2);
+        assert_eq!(
+            proofs[0].context(),
+            &EventContext {
+                file: None,
+                vcs_id: Some("vcs_1".to_string()),
+                chunk: Some(3)
+            }
+        );
+        assert_eq!(proofs[1].context().vcs_id, Some("vcs_2".to_string()));
+        assert_eq!(
+            result.failures()[0].context().file,
+            Some("setup_component_public_keys_payload".to_string())
+        );
+        assert_eq!(result.errors()[0].code(), FailureCode::Unclassified);
+        assert_eq!(
+            result.count_by_code().into_iter().collect::<Vec<_>>(),
+            vec![
+                (FailureCode::Unclassified, 1),
+                (FailureCode::SignatureInvalid, 1),
+                (FailureCode::ProofInvalid, 2)
+            ]
+        );
+        for c in FailureCode::ALL {
+            assert_eq!(FailureCode::try_from(c.as_str()).unwrap(), c);
+            assert_eq!(serde_json::to_string(&c).unwrap(), format!("\"{}\"", c));
+        }
+        assert!(FailureCode::try_from("toto").is_err());
+    }
+
+    #[test]
+    fn test_sub_results() {
+        let mut result = VerificationResult::new();
+        for i in 0..3 {
+            let mut chunk = VerificationResult::new();
+            if i == 1 {
+                chunk.push(create_verification_failure!("failure"));
+            }
+            result.append_sub_result("chunk", &i.to_string(), &mut chunk);
+        }
+        let mut bb = VerificationResult::new();
+        bb.push(create_verification_error!("error"));
+        result.append_sub_result("ballot_box", "bb_1", &mut bb);
+        assert!(bb.errors().is_empty());
+        result.annotate(EntityKind::VerificationCardSet, "vcs_1");
+        assert!(result
+            .errors()
+            .iter()
+            .chain(result.failures().iter())
+            .all(|e| e.entities() == [Entity::new(EntityKind::VerificationCardSet, "vcs_1")]));
+        assert_eq!(result.errors().len(), 1);
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.sub_results().len(), 4);
+        assert!(!result.sub_results()[1].is_ok());
+        let summary = result.sub_results_summary();
+        assert_eq!(
+            summary.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            vec!["chunk: 2/3 ok", "ballot_box: 0/1 ok"]
+        );
+    }
+}
+
 /*
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum VerificationErrorType {
@@ -1,8 +1,11 @@
 use super::super::{
+    direct_trust_certificates::verify_direct_trust_certificates,
     result::{create_verification_error, VerificationEvent, VerificationResult},
-    suite::VerificationList,
+    suite::{SuiteBuildError, VerificationList},
     verifications::Verification,
     verify_signature_for_object,
+    verify_signatures_for_payloads,
+    VerificationContext,
 };
 use crate::{
     config::Config,
@@ -18,56 +21,58 @@ use log::debug;
 pub fn get_verifications<'a>(
     metadata_list: &'a VerificationMetaDataList,
     config: &'static Config,
-) -> VerificationList<'a> {
-    VerificationList(vec![
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
         Verification::new(
             "02.01",
             "VerifySignatureCantonConfig",
             fn_0201_verify_signature_canton_config,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "02.02",
-            "verifySignatureSetupComponentPublicKeys",
+            "VerifySignatureSetupComponentPublicKeys",
             fn_0202_verify_signature_setup_component_public_keys,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "02.03",
-            "verifySignatureControlComponentPublicKeys",
+            "VerifySignatureControlComponentPublicKeys",
             fn_0203_verify_signature_control_component_public_keys,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "02.04",
-            "verifySignatureSetupComponentTallyData",
+            "VerifySignatureSetupComponentTallyData",
             fn_0204_verify_signature_setup_component_tally_data,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "02.05",
-            "verifySignatureElectionEventContext",
+            "VerifySignatureElectionEventContext",
             fn_0205_verify_signature_election_event_context,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
+        Verification::new(
+            "X.02.01",
+            "VerifyDirectTrustCertificates",
+            fn_x0201_verify_direct_trust_certificates,
+            metadata_list,
+            config,
+        ),
     ])
 }
 
 fn fn_0201_verify_signature_canton_config<D: VerificationDirectoryTrait>(
     dir: &D,
-    config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let ee_config = match setup_dir.election_event_configuration() {
         Ok(p) => p,
@@ -76,22 +81,21 @@ fn fn_0201_verify_signature_canton_config<D: VerificationDirectoryTrait>(
                 format!("{} cannot be read", "election_event_configuration"),
                 e
             ));
-            return;
+            return result;
         }
     };
     verify_signature_for_object(
         ee_config.as_ref(),
-        result,
-        config,
+        context.config(),
         "election_event_configuration",
     )
 }
 
 fn fn_0202_verify_signature_setup_component_public_keys<D: VerificationDirectoryTrait>(
     dir: &D,
-    config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let eg = match setup_dir.setup_component_public_keys_payload() {
         Ok(p) => p,
@@ -100,68 +104,49 @@ fn fn_0202_verify_signature_setup_component_public_keys<D: VerificationDirectory
                 format!("{} cannot be read", "setup_component_public_keys_payload"),
                 e
             ));
-            return;
+            return result;
         }
     };
     verify_signature_for_object(
         eg.as_ref(),
-        result,
-        config,
+        context.config(),
         "setup_component_public_keys_payload",
     )
 }
 
 fn fn_0203_verify_signature_control_component_public_keys<D: VerificationDirectoryTrait>(
     dir: &D,
-    config: &'static Config,
-    result: &mut VerificationResult,
-) {
-    let setup_dir = dir.unwrap_setup();
-    for (i, cc) in setup_dir.control_component_public_keys_payload_iter() {
-        debug!("Verification 2.03 for cc {}", i);
-        match cc {
-            Ok(cc) => verify_signature_for_object(
-                cc.as_ref(),
-                result,
-                config,
-                &format!("control_component_public_keys_payload_{}", i),
-            ),
-            Err(e) => result.push(create_verification_error!(
-                format!("control_component_public_keys_payload_{} cannot be read", i),
-                e
-            )),
-        }
-    }
+    context: &VerificationContext,
+) -> VerificationResult {
+    verify_signatures_for_payloads(
+        dir.unwrap_setup()
+            .control_component_public_keys_payload_iter()
+            .map(|(i, p)| (format!("control_component_public_keys_payload_{}", i), p)),
+        context.config(),
+    )
 }
 
 fn fn_0204_verify_signature_setup_component_tally_data<D: VerificationDirectoryTrait>(
     dir: &D,
-    config: &'static Config,
-    result: &mut VerificationResult,
-) {
-    let setup_dir = dir.unwrap_setup();
-    for d in setup_dir.vcs_directories() {
-        debug!("Verification 2.04 for vcs_dir {}", d.get_name());
-        match d.setup_component_tally_data_payload() {
-            Ok(p) => verify_signature_for_object(
-                p.as_ref(),
-                result,
-                config,
-                &format!("{}/setup_component_tally_data_payload.json", d.get_name(),),
-            ),
-            Err(e) => result.push(create_verification_error!(
-                format!("{}/setup_component_tally_data_payload.json", d.get_name(),),
-                e
-            )),
-        }
-    }
+    context: &VerificationContext,
+) -> VerificationResult {
+    verify_signatures_for_payloads(
+        dir.unwrap_setup().vcs_directories().iter().map(|d| {
+            debug!("Verification 2.04 for vcs_dir {}", d.get_name());
+            (
+                format!("{}/setup_component_tally_data_payload.json", d.get_name()),
+                d.setup_component_tally_data_payload(),
+            )
+        }),
+        context.config(),
+    )
 }
 
 fn fn_0205_verify_signature_election_event_context<D: VerificationDirectoryTrait>(
     dir: &D,
-    config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let rp = match setup_dir.election_event_context_payload() {
         Ok(p) => p,
@@ -170,15 +155,19 @@ fn fn_0205_verify_signature_election_event_context<D: VerificationDirectoryTrait
                 format!("{} cannot be read", "election_event_context_payload"),
                 e
             ));
-            return;
+            return result;
         }
     };
-    verify_signature_for_object(
-        rp.as_ref(),
-        result,
-        config,
-        "election_event_context_payload",
-    )
+    verify_signature_for_object(rp.as_ref(), context.config(), "election_event_context_payload")
+}
+
+fn fn_x0201_verify_direct_trust_certificates<D: VerificationDirectoryTrait>(
+    _dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    verify_direct_trust_certificates(context.config(), &mut result);
+    result
 }
 
 /*
@@ -245,15 +234,23 @@ fn fn_verification_0207<D: VerificationDirectoryTrait>(
 
 #[cfg(test)]
 mod test {
-    use super::{super::super::result::VerificationResultTrait, *};
-    use crate::config::test::{get_test_verifier_setup_dir as get_verifier_dir, CONFIG_TEST};
+    use super::{
+        super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_setup_dir as get_verifier_dir, test_dataset_setup_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
 
     #[test]
     #[ignore = "error with XML"]
     fn test_0201() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0201_verify_signature_canton_config(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_0201_verify_signature_canton_config(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
         if !result.is_ok().unwrap() {
             for e in result.errors() {
                 println!("{:?}", e);
@@ -265,36 +262,164 @@ mod test {
         assert!(result.is_ok().unwrap());
     }
 
+    #[test]
+    fn test_0201_signature_not_base64() {
+        // The positive case fails on the test dataset (see test_0201). The error is caused by the
+        // decoding of the changed signature, which the positive case does not report
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let mut config = mock_dir
+            .unwrap_setup()
+            .election_event_configuration()
+            .unwrap();
+        config.signature.signature_contents = "not base64!".to_string();
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_configuration(&Ok(&config));
+        let result = fn_0201_verify_signature_canton_config(
+            &mock_dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_errors().unwrap());
+        assert!(format!("{:#}", result.errors()[0]).contains("not valid base64"));
+    }
+
+    #[test]
+    fn test_0201_configuration_not_readable() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_configuration(&Err(anyhow!("test")));
+        let result = fn_0201_verify_signature_canton_config(
+            &mock_dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_errors().unwrap());
+        assert!(result.errors_to_string()[0].contains("election_event_configuration"));
+    }
+
     #[test]
     fn test_0202() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0202_verify_signature_setup_component_public_keys(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_0202_verify_signature_setup_component_public_keys(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
         println!("{:?}", result);
         assert!(result.is_ok().unwrap());
     }
 
+    #[test]
+    fn test_0202_wrong_signature() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let mut payload = mock_dir
+            .unwrap_setup()
+            .setup_component_public_keys_payload()
+            .unwrap();
+        payload.signature = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap()
+            .signature;
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_setup_component_public_keys_payload(&Ok(&payload));
+        let result = fn_0202_verify_signature_setup_component_public_keys(
+            &mock_dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_failures().unwrap());
+    }
+
+    #[test]
+    fn test_0202_signature_not_base64() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let mut payload = mock_dir
+            .unwrap_setup()
+            .setup_component_public_keys_payload()
+            .unwrap();
+        payload.signature.signature_contents = "not base64!".to_string();
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_setup_component_public_keys_payload(&Ok(&payload));
+        let result = fn_0202_verify_signature_setup_component_public_keys(
+            &mock_dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_errors().unwrap());
+    }
+
     #[test]
     fn test_0203() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0203_verify_signature_control_component_public_keys(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_0203_verify_signature_control_component_public_keys(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
         assert!(result.is_ok().unwrap());
     }
 
+    #[test]
+    fn test_0203_wrong_signature_and_error() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let mut payloads = mock_dir
+            .unwrap_setup()
+            .control_component_public_keys_payload_iter()
+            .map(|(i, p)| (i, p.unwrap()));
+        let (i_1, p_1) = payloads.next().unwrap();
+        let (i_2, mut p_2) = payloads.next().unwrap();
+        p_2.signature = p_1.signature.clone();
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_control_component_public_keys_payloads(i_2, &Ok(&p_2));
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_control_component_public_keys_payloads(i_1, &Err(anyhow!("test")));
+        let result = fn_0203_verify_signature_control_component_public_keys(
+            &mock_dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert_eq!(result.errors().len(), 1);
+        assert!(result.errors()[0]
+            .to_string()
+            .contains(&format!("control_component_public_keys_payload_{}", i_1)));
+        assert_eq!(result.failures().len(), 1);
+        assert!(result.failures()[0]
+            .to_string()
+            .contains(&format!("control_component_public_keys_payload_{}", i_2)));
+    }
+
     #[test]
     fn test_0204() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0204_verify_signature_setup_component_tally_data(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_0204_verify_signature_setup_component_tally_data(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
         assert!(result.is_ok().unwrap());
     }
 
     #[test]
     fn test_0205() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0205_verify_signature_election_event_context(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_0205_verify_signature_election_event_context(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_x0201() {
+        let dir = get_verifier_dir();
+        let result = fn_x0201_verify_direct_trust_certificates(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
         assert!(result.is_ok().unwrap());
     }
 }
@@ -1,108 +1,505 @@
 use super::super::{
-    result::{create_verification_failure, VerificationEvent, VerificationResult},
-    suite::VerificationList,
+    duplicate_finder::DuplicateFinder,
+    missing_file_failure,
+    result::{
+        create_verification_error, create_verification_failure, EntityKind, FailureCode,
+        VerificationEvent, VerificationResult,
+    },
+    suite::{SuiteBuildError, VerificationList},
     verifications::Verification,
+    verify_file_content, VerificationContext,
 };
 use crate::{
     config::Config,
+    data_structures::dataset_version::{
+        datetimes_of_other_version, DatasetVersion, DateTimeParsingMode,
+    },
     file_structure::{
+        file::File,
         setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
-        VerificationDirectoryTrait,
+        DirectoryNamesCheck, VerificationDirectoryTrait,
     },
     verification::meta_data::VerificationMetaDataList,
 };
 use anyhow::anyhow;
-use log::debug;
+use log::{debug, warn};
+use std::collections::BTreeSet;
 
 pub fn get_verifications<'a>(
     metadata_list: &'a VerificationMetaDataList,
     config: &'static Config,
-) -> VerificationList<'a> {
-    VerificationList(vec![Verification::new(
-        "01.01",
-        "VerifySetupCompleteness",
-        fn_0101_verify_setup_completeness,
-        metadata_list,
-        config,
-    )
-    .unwrap()])
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
+        Verification::new(
+            "01.01",
+            "VerifySetupCompleteness",
+            fn_0101_verify_setup_completeness,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.01.01",
+            "VerifyVerificationDataCoverCards",
+            fn_x0101_verify_verification_data_cover_cards,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.01.02",
+            "VerifyVCSDirectoryNames",
+            fn_x0102_verify_vcs_directory_names,
+            metadata_list,
+            config,
+        ),
+    ])
 }
 
 fn validate_vcs_dir<B: VCSDirectoryTrait>(dir: &B, result: &mut VerificationResult) {
+    let mut missing = vec![];
     if !dir.setup_component_tally_data_payload_file().exists() {
-        result.push(create_verification_failure!(
-            "setup_component_tally_data_payload does not exist"
-        ))
+        missing.push("setup_component_tally_data_payload");
     }
     if !dir
         .setup_component_verification_data_payload_group()
         .has_elements()
     {
-        result.push(create_verification_failure!(
-            "setup_component_verification_data_payload does not exist"
-        ))
+        missing.push("setup_component_verification_data_payload");
     }
     if !dir
         .control_component_code_shares_payload_group()
         .has_elements()
     {
-        result.push(create_verification_failure!(
-            "control_component_code_shares_payload does not exist"
-        ))
+        missing.push("control_component_code_shares_payload");
+    }
+    for m in missing.iter() {
+        result.push(missing_file_failure(m))
+    }
+    verify_file_content(dir.setup_component_tally_data_payload_file(), result);
+    for group in [
+        dir.setup_component_verification_data_payload_group(),
+        dir.control_component_code_shares_payload_group(),
+    ] {
+        for (_, f) in group.iter() {
+            verify_file_content(&f, result);
+        }
+    }
+    if !missing.is_empty() {
+        result.push_failed_item(
+            "verification_card_set",
+            &dir.get_name(),
+            &format!("missing {}", missing.join(", ")),
+        )
     }
 }
 
-fn fn_0101_verify_setup_completeness<D: VerificationDirectoryTrait>(
-    dir: &D,
-    _config: &'static Config,
+/// Verify that the datetimes of the election event context have the format of the version of
+/// the dataset
+///
+/// A datetime in the format of another version is a failure in the strict mode and a warning in
+/// the lenient mode
+fn verify_datetime_format(
+    file: &File,
+    version: DatasetVersion,
+    mode: DateTimeParsingMode,
     result: &mut VerificationResult,
 ) {
+    if !file.exists() {
+        return;
+    }
+    let messages = match file
+        .read_data()
+        .and_then(|d| datetimes_of_other_version(&d, version))
+    {
+        Ok(m) => m,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot verify the format of the datetimes of the election event context",
+                e
+            ));
+            return;
+        }
+    };
+    for m in messages {
+        match mode {
+            DateTimeParsingMode::Strict => result.push(
+                create_verification_failure!(m)
+                    .with_code(FailureCode::FileContent)
+                    .with_file(file.to_str()),
+            ),
+            DateTimeParsingMode::Lenient => warn!("{}", m),
+        }
+    }
+}
+
+fn fn_0101_verify_setup_completeness<D: VerificationDirectoryTrait>(
+    dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     if !setup_dir.election_event_context_payload_file().exists() {
-        result.push(create_verification_failure!(
-            "election_event_context_payload does not exist"
-        ))
+        result.push(missing_file_failure("election_event_context_payload"))
     }
     if !setup_dir
         .setup_component_public_keys_payload_file()
         .exists()
     {
-        result.push(create_verification_failure!(
-            "setup_component_public_keys_payload_file does not exist"
-        ))
+        result.push(missing_file_failure("setup_component_public_keys_payload"))
     }
     if !setup_dir.election_event_configuration_file().exists() {
-        result.push(create_verification_failure!(
-            "setup_component_public_keys_payload_file does not exist"
-        ))
+        result.push(missing_file_failure("election_event_configuration"))
+    }
+    for f in [
+        setup_dir.election_event_context_payload_file(),
+        setup_dir.setup_component_public_keys_payload_file(),
+        setup_dir.election_event_configuration_file(),
+    ] {
+        verify_file_content(f, &mut result);
+    }
+    if let Some(version) = context.dataset_version() {
+        verify_datetime_format(
+            setup_dir.election_event_context_payload_file(),
+            version,
+            context.config().datetime_parsing_mode(),
+            &mut result,
+        );
     }
-    if setup_dir
+    for (_, f) in setup_dir
         .control_component_public_keys_payload_group()
-        .get_numbers()
-        != &vec![1, 2, 3, 4]
+        .iter()
     {
+        verify_file_content(&f, &mut result);
+    }
+    let node_ids = setup_dir.node_ids();
+    if node_ids != BTreeSet::from([1, 2, 3, 4]) {
         result.push(create_verification_failure!(format!(
             "control_component_public_keys_payload_group missing. only these parts are present: {:?}",
-            setup_dir
-                .control_component_public_keys_payload_group()
-                .get_numbers()
+            node_ids
         )))
     }
     for d in setup_dir.vcs_directories().iter() {
-        validate_vcs_dir(d, result);
+        validate_vcs_dir(d, &mut result);
+    }
+    result
+}
+
+fn validate_vcs_cards<B: VCSDirectoryTrait>(
+    dir: &B,
+    expected_number_of_cards: Option<usize>,
+    all_ids: &mut DuplicateFinder,
+    result: &mut VerificationResult,
+) {
+    let vcs_id = dir.get_name();
+    let expected_ids: BTreeSet<String> = match dir.setup_component_tally_data_payload() {
+        Ok(p) => p.verification_card_ids.iter().cloned().collect(),
+        Err(e) => {
+            result.push(create_verification_error!(
+                format!("Cannot extract setup_component_tally_data_payload for {}", vcs_id),
+                e
+            ));
+            return;
+        }
+    };
+    for id in expected_ids.iter() {
+        if let Err(e) = all_ids.push(id) {
+            result.push(create_verification_error!(
+                format!("Cannot register the verification card {}", id),
+                e
+            ));
+            return;
+        }
+    }
+    match expected_number_of_cards {
+        Some(n) if n != expected_ids.len() => result.push(create_verification_failure!(format!(
+            "The number of verification cards {} in {} is not the expected number {}",
+            expected_ids.len(),
+            vcs_id,
+            n
+        ))),
+        Some(_) => (),
+        None => result.push(create_verification_failure!(format!(
+            "The verification card set context for {} is missing",
+            vcs_id
+        ))),
+    }
+    let mut covered_ids = BTreeSet::new();
+    for (i, p) in dir.setup_component_verification_data_payload_iter() {
+        match p {
+            Ok(p) => {
+                for d in p.setup_component_verification_data.iter() {
+                    if !covered_ids.insert(d.verification_card_id.clone()) {
+                        result.push(create_verification_failure!(format!(
+                            "The verification card {} in {} is present in more than one chunk (chunk {})",
+                            d.verification_card_id, vcs_id, i
+                        )))
+                    }
+                }
+            }
+            Err(e) => {
+                result.push(create_verification_error!(
+                    format!(
+                        "Cannot extract setup_component_verification_data_payload for chunk {} in {}",
+                        i, vcs_id
+                    ),
+                    e
+                ));
+                return;
+            }
+        }
+    }
+    let missing: Vec<&String> = expected_ids.difference(&covered_ids).collect();
+    let extras: Vec<&String> = covered_ids.difference(&expected_ids).collect();
+    if !missing.is_empty() {
+        result.push(create_verification_failure!(format!(
+            "{} verification cards of {} are missing in the verification data: {:?}",
+            missing.len(),
+            vcs_id,
+            missing
+        )))
+    }
+    if !extras.is_empty() {
+        result.push(create_verification_failure!(format!(
+            "{} verification cards in the verification data of {} are not expected: {:?}",
+            extras.len(),
+            vcs_id,
+            extras
+        )))
+    }
+    if !missing.is_empty() || !extras.is_empty() {
+        result.push_failed_item(
+            "verification_card_set",
+            &vcs_id,
+            &format!("{} missing, {} not expected", missing.len(), extras.len()),
+        )
+    }
+}
+
+fn fn_x0101_verify_verification_data_cover_cards<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    let eec = match setup_dir.election_event_context_payload() {
+        Ok(o) => o.election_event_context,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let mut all_ids = DuplicateFinder::new(Config::deduplication_max_ids_in_memory());
+    for d in setup_dir.vcs_directories().iter() {
+        validate_vcs_cards(
+            d,
+            eec.number_of_voting_cards(&d.get_name()),
+            &mut all_ids,
+            &mut result,
+        );
+    }
+    debug!(
+        "Search of the duplicated verification cards in {} ids",
+        all_ids.len()
+    );
+    match all_ids.duplicates() {
+        Ok(duplicates) => push_duplicated_cards_failures(&duplicates, &mut result),
+        Err(e) => result.push(create_verification_error!(
+            "Cannot search the duplicated verification cards",
+            e
+        )),
+    }
+    result
+}
+
+/// Push a failure for the verification cards present in more than one verification card set
+fn push_duplicated_cards_failures(duplicates: &[String], result: &mut VerificationResult) {
+    for id in duplicates.iter() {
+        result.push(
+            create_verification_failure!(format!(
+                "The verification card {} is in more than one verification card set",
+                id
+            ))
+            .with_entity(EntityKind::VerificationCard, id),
+        );
+        result.push_failed_item("verification_card", id, "duplicated");
     }
 }
 
+/// Push the failures of the check of the directory names to the result
+///
+/// `kind` is the kind of the directories (`verification_card_set` or `ballot_box`), used for
+/// the failed items
+pub(in crate::verification) fn push_directory_names_failures(
+    check: &DirectoryNamesCheck,
+    kind: &str,
+    result: &mut VerificationResult,
+) {
+    for d in check.duplicates.iter() {
+        result.push(create_verification_failure!(format!(
+            "The {} directories {:?} have the same name on a case insensitive file system",
+            kind, d
+        )));
+        for n in d.iter() {
+            result.push_failed_item(kind, n, "duplicated directory");
+        }
+    }
+    for n in check.unknown.iter() {
+        result.push(create_verification_failure!(format!(
+            "The {} directory {} does not match an id of the election event context",
+            kind, n
+        )));
+        result.push_failed_item(kind, n, "directory not referenced");
+    }
+    for n in check.missing.iter() {
+        result.push(create_verification_failure!(format!(
+            "The {} directory for the id {} of the election event context is missing",
+            kind, n
+        )));
+        result.push_failed_item(kind, n, "directory missing");
+    }
+}
+
+fn fn_x0102_verify_vcs_directory_names<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    let eec = match setup_dir.election_event_context_payload() {
+        Ok(o) => o.election_event_context,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let names: Vec<String> = setup_dir
+        .vcs_directories()
+        .iter()
+        .map(|d| d.get_name())
+        .collect();
+    let ids: Vec<String> = eec
+        .verification_card_set_contexts
+        .iter()
+        .map(|c| c.verification_card_set_id.clone())
+        .collect();
+    push_directory_names_failures(
+        &DirectoryNamesCheck::new(&names, &ids),
+        "verification_card_set",
+        &mut result,
+    );
+    result
+}
+
 #[cfg(test)]
 mod test {
-    use super::{super::super::result::VerificationResultTrait, *};
-    use crate::config::test::{get_test_verifier_setup_dir as get_verifier_dir, CONFIG_TEST};
+    use super::{
+        super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_setup_dir as get_verifier_dir, test_dataset_setup_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
 
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
+        let result = fn_0101_verify_setup_completeness(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_datetime_format() {
+        let dir = get_verifier_dir();
+        let context = VerificationContext::new(&CONFIG_TEST);
+        let result = fn_0101_verify_setup_completeness(
+            &dir,
+            &context.with_dataset_version(Some(DatasetVersion::V1)),
+        );
+        assert!(result.is_ok().unwrap());
+        // Accepted with a warning in the lenient mode
+        let result = fn_0101_verify_setup_completeness(
+            &dir,
+            &context.with_dataset_version(Some(DatasetVersion::V2)),
+        );
+        assert!(result.is_ok().unwrap());
+        let config: &'static Config = Box::leak(Box::new(Config::new(".")));
+        config
+            .set_datetime_parsing_mode(DateTimeParsingMode::Strict)
+            .unwrap();
+        let result = fn_0101_verify_setup_completeness(
+            &dir,
+            &VerificationContext::new(config).with_dataset_version(Some(DatasetVersion::V2)),
+        );
+        assert!(!result.has_errors().unwrap());
+        assert!(result.has_failures().unwrap());
+    }
+
+    #[test]
+    fn test_x0102_ok() {
+        let dir = get_verifier_dir();
+        let result =
+            fn_x0102_verify_vcs_directory_names(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_push_directory_names_failures() {
+        let ids = vec!["abc".to_string(), "def".to_string()];
+        let mut result = VerificationResult::new();
+        push_directory_names_failures(
+            &DirectoryNamesCheck::new(&["abc".to_string(), "ABC".to_string()], &ids),
+            "ballot_box",
+            &mut result,
+        );
+        assert_eq!(result.failures().len(), 3);
+        assert_eq!(result.failed_items().len(), 4);
+    }
+
+    #[test]
+    fn test_x0101_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_x0101_verify_verification_data_cover_cards(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_push_duplicated_cards_failures() {
         let mut result = VerificationResult::new();
-        fn_0101_verify_setup_completeness(&dir, &CONFIG_TEST, &mut result);
+        push_duplicated_cards_failures(&[], &mut result);
         assert!(result.is_ok().unwrap());
+        push_duplicated_cards_failures(&["abc".to_string(), "def".to_string()], &mut result);
+        assert_eq!(result.failures().len(), 2);
+        assert_eq!(result.failed_items()[1].id, "def");
+    }
+
+    #[test]
+    fn test_x0101_extra_card() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let vcs = &mock_dir.unwrap_setup().vcs_directories()[0];
+        let mut tally_data = vcs.setup_component_tally_data_payload().unwrap();
+        let vcs_name = vcs.get_name();
+        tally_data.verification_card_ids.pop();
+        mock_dir.unwrap_setup_mut().vcs_directories_mut()[0]
+            .mock_setup_component_tally_data_payload(&Ok(&tally_data));
+        let result = fn_x0101_verify_verification_data_cover_cards(
+            &mock_dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failed_items().len(), 1);
+        assert_eq!(result.failed_items()[0].id, vcs_name);
     }
 }
@@ -7,107 +7,154 @@ mod v0306_choice_return_codes_public_key_consistency;
 mod v0307_election_pk_consistency;
 mod v0308_primes_mapping_table_consistency;
 mod v0309_election_event_id_consistency;
+mod v0310_verification_card_set_ids_consistency;
+mod v0311_file_name_verification_card_set_ids_consistency;
+mod v0312_verification_card_ids_consistency;
 mod v0313_total_voters_consistency;
+mod v0314_node_ids_consistency;
 mod v0315_chunk_consistency;
+mod x0301_electoral_board_consistency;
+mod x0302_ballots_consistency;
+mod x0303_primes_mapping_table_file_consistency;
 
 use crate::config::Config;
 
 use super::super::{
-    meta_data::VerificationMetaDataList, suite::VerificationList, verifications::Verification,
+    meta_data::VerificationMetaDataList,
+    suite::{SuiteBuildError, VerificationList},
+    verifications::Verification,
 };
 
 pub fn get_verifications<'a>(
     metadata_list: &'a VerificationMetaDataList,
     config: &'static Config,
-) -> VerificationList<'a> {
-    VerificationList(vec![
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
         Verification::new(
             "03.01",
             "VerifyEncryptionGroupConsistency",
             v0301_encryption_group_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "03.02",
             "VerifySetupFileNamesConsistency",
             v0302_setup_file_names_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "03.03",
-            "VerifyCCRChoiceReturnCodesPublicKeyConsistency",
+            "VerifyCCrChoiceReturnCodesPublicKeyConsistency",
             v0303_ccr_choice_return_codes_pk_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "03.04",
-            "VerifyCCMElectionPublicKeyConsistency",
+            "VerifyCCmElectionPublicKeyConsistency",
             v0304_ccm_election_pk_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "03.05",
-            "VerifyCCMAndCCRSchnorrProofsConsistency",
+            "VerifyCcmAndCcrSchnorrProofsConsistency",
             v0305_ccm_and_ccr_schnorr_proofs_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "03.06",
             "VerifyChoiceReturnCodesPublicKeyConsistency",
             v0306_choice_return_codes_public_key_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "03.07",
             "VerifyElectionPublicKeyConsistency",
             v0307_election_pk_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "03.08",
             "VerifyPrimesMappingTableConsistency",
             v0308_primes_mapping_table_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
         Verification::new(
             "03.09",
             "VerifyElectionEventIdConsistency",
             v0309_election_event_id_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
+        Verification::new(
+            "03.10",
+            "VerifyVerificationCardSetIdsConsistency",
+            v0310_verification_card_set_ids_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "03.11",
+            "VerifyFileNameVerificationCardSetIdsConsistency",
+            v0311_file_name_verification_card_set_ids_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "03.12",
+            "VerifyVerificationCardIdsConsistency",
+            v0312_verification_card_ids_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
         Verification::new(
             "03.13",
             "VerifyTotalVotersConsistency",
             v0313_total_voters_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
+        Verification::new(
+            "03.14",
+            "VerifyNodeIdsConsistency",
+            v0314_node_ids_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
         Verification::new(
             "03.15",
             "VerifyChunkConsistency",
             v0315_chunk_consistency::fn_verification,
             metadata_list,
             config,
-        )
-        .unwrap(),
+        ),
+        Verification::new(
+            "X.03.01",
+            "VerifyElectoralBoardConsistency",
+            x0301_electoral_board_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.03.02",
+            "VerifyBallotsConsistency",
+            x0302_ballots_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.03.03",
+            "VerifyPrimesMappingTableFileConsistency",
+            x0303_primes_mapping_table_file_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
     ])
 }
@@ -2,11 +2,11 @@ use super::super::super::result::{
     create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
     file_structure::{
         setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
         VerificationDirectoryTrait,
     },
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
@@ -111,9 +111,9 @@ fn verify_encryption_group_for_vcs_dir<V: VCSDirectoryTrait>(
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let eg = match setup_dir.election_event_context_payload() {
         Ok(p) => p.encryption_group,
@@ -122,7 +122,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "election_event_context_payload cannot be read",
                 e
             ));
-            return;
+            return result;
         }
     };
     match setup_dir.setup_component_public_keys_payload() {
@@ -130,7 +130,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
             &p.encryption_group,
             &eg,
             "setup_component_public_keys_payload",
-            result,
+            &mut result,
         ),
         Err(e) => result.push(create_verification_error!(
             "election_event_context_payload has wrong format",
@@ -150,13 +150,14 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 &cc.encryption_group,
                 &eg,
                 &format!("control_component_public_keys_payload.{}", i),
-                result,
+                &mut result,
             ),
         }
     }
     for vcs in setup_dir.vcs_directories().iter() {
-        verify_encryption_group_for_vcs_dir(vcs, &eg, result);
+        verify_encryption_group_for_vcs_dir(vcs, &eg, &mut result);
     }
+    result
 }
 
 #[cfg(test)]
@@ -181,8 +182,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 
@@ -223,9 +223,8 @@ mod test {
 
     #[test]
     fn test_wrong_election_event_context() {
-        let mut result = VerificationResult::new();
         let mut mock_dir = get_mock_verifier_dir();
-        fn_verification(&mock_dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
         let mut eec = mock_dir
             .unwrap_setup()
@@ -235,13 +234,12 @@ mod test {
         mock_dir
             .unwrap_setup_mut()
             .mock_election_event_context_payload(&Ok(&eec));
-        fn_verification(&mock_dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.has_failures().unwrap());
     }
 
     #[test]
     fn test_wrong_control_component_public_keys() {
-        let mut result = VerificationResult::new();
         let mut mock_dir = get_mock_verifier_dir();
         let mut cc_pk = mock_dir
             .unwrap_setup()
@@ -255,7 +253,7 @@ mod test {
         mock_dir
             .unwrap_setup_mut()
             .mock_control_component_public_keys_payloads(2, &Ok(&cc_pk));
-        fn_verification(&mock_dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.has_failures().unwrap());
     }
 }
@@ -1,38 +1,36 @@
 use super::super::super::result::{
-    create_verification_failure, VerificationEvent, VerificationResult,
+    create_verification_failure, FailureCode, VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
     file_structure::{
         file::File, setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait,
     },
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
+use std::collections::BTreeSet;
 
 fn test_file_exists(file: &File, result: &mut VerificationResult) {
     if !file.exists() {
-        result.push(create_verification_failure!(format!(
-            "File {} does not exist",
-            file.to_str()
-        )))
+        result.push(
+            create_verification_failure!(format!("File {} does not exist", file.to_str()))
+                .with_code(FailureCode::MissingFile)
+                .with_file(file.to_str()),
+        )
     }
 }
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
-    test_file_exists(setup_dir.election_event_context_payload_file(), result);
-    test_file_exists(setup_dir.setup_component_public_keys_payload_file(), result);
-    let mut cc_group_numbers = setup_dir
-        .control_component_public_keys_payload_group()
-        .get_numbers()
-        .clone();
-    cc_group_numbers.sort();
-    if cc_group_numbers != vec![1, 2, 3, 4] {
+    test_file_exists(setup_dir.election_event_context_payload_file(), &mut result);
+    test_file_exists(setup_dir.setup_component_public_keys_payload_file(), &mut result);
+    let cc_group_numbers = setup_dir.node_ids();
+    if cc_group_numbers != BTreeSet::from([1, 2, 3, 4]) {
         result.push(create_verification_failure!(format!(
             "controlComponentPublicKeysPayload must have file from 1 to 4. But actually: {:?}",
             cc_group_numbers
@@ -42,8 +40,9 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
         .control_component_public_keys_payload_group()
         .iter()
     {
-        test_file_exists(&f, result);
+        test_file_exists(&f, &mut result);
     }
+    result
 }
 
 #[cfg(test)]
@@ -54,8 +53,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
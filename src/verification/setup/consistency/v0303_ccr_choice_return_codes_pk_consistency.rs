@@ -2,12 +2,12 @@ use super::super::super::result::{
     create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
     data_structures::{
         setup::control_component_public_keys_payload::ControlComponentPublicKeys,
         VerifierSetupDataTrait,
     },
     file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
@@ -21,10 +21,12 @@ fn validate_cc_ccr_enc_pk<S: SetupDirectoryTrait>(
     let f = setup_dir
         .control_component_public_keys_payload_group()
         .get_file_with_number(node_id);
-    let cc_pk = match f
-        .get_data()
-        .map(|d| Box::new(d.control_component_public_keys_payload().unwrap().clone()))
-    {
+    let cc_pk = match f.get_data().and_then(|d| {
+        d.control_component_public_keys_payload()
+            .cloned()
+            .map(Box::new)
+            .ok_or_else(|| anyhow!("The data is not a control_component_public_keys_payload"))
+    }) {
         Ok(d) => d.control_component_public_keys,
         Err(e) => {
             result.push(create_verification_error!(
@@ -47,9 +49,9 @@ fn validate_cc_ccr_enc_pk<S: SetupDirectoryTrait>(
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let sc_pk = match setup_dir.setup_component_public_keys_payload() {
         Ok(o) => o,
@@ -58,15 +60,16 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "Cannot extract setup_component_public_keys_payload",
                 e
             ));
-            return;
+            return result;
         }
     };
     for node in sc_pk
         .setup_component_public_keys
         .combined_control_component_public_keys
     {
-        validate_cc_ccr_enc_pk(setup_dir, &node, node.node_id, result)
+        validate_cc_ccr_enc_pk(setup_dir, &node, node.node_id, &mut result)
     }
+    result
 }
 
 #[cfg(test)]
@@ -77,8 +80,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
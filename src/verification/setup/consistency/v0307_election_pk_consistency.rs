@@ -2,8 +2,8 @@ use super::super::super::result::{
     create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
     file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
@@ -12,9 +12,9 @@ use rust_ev_crypto_primitives::{Constants, Operations};
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let eg = match setup_dir.election_event_context_payload() {
         Ok(o) => o.encryption_group,
@@ -23,7 +23,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "Cannot extract election_event_context_payload",
                 e
             ));
-            return;
+            return result;
         }
     };
     let sc_pk = match setup_dir.setup_component_public_keys_payload() {
@@ -33,7 +33,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "Cannot extract setup_component_public_keys_payload",
                 e
             ));
-            return;
+            return result;
         }
     };
     let combined_cc_pk = sc_pk
@@ -57,6 +57,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
             )));
         }
     }
+    result
 }
 
 #[cfg(test)]
@@ -67,8 +68,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
@@ -2,8 +2,8 @@ use super::super::super::result::{
     create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
     file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
@@ -11,9 +11,9 @@ use std::collections::HashMap;
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let ee_c_paylod = match setup_dir.election_event_context_payload() {
         Ok(o) => o,
@@ -22,7 +22,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "Cannot extract election_event_context_payload",
                 e
             ));
-            return;
+            return result;
         }
     };
 
@@ -50,6 +50,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
             };
         }
     }
+    result
 }
 
 #[cfg(test)]
@@ -60,8 +61,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
@@ -1,106 +1,82 @@
-use super::super::super::result::{
-    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
+use super::super::super::{
+    result::{create_verification_error, VerificationEvent, VerificationResult},
+    verify_election_event_ids,
 };
 use crate::{
-    config::Config,
     file_structure::{
         setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
         VerificationDirectoryTrait,
     },
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
-
-fn test_election_event_id(
-    ee_id: &String,
-    expected: &String,
-    name: &str,
-    result: &mut VerificationResult,
-) {
-    if ee_id != expected {
-        result.push(create_verification_failure!(format!(
-            "Election Event ID not equal in {}",
-            name
-        )));
-    }
-}
+use std::iter::once;
 
 fn test_ee_id_for_vcs_dir<V: VCSDirectoryTrait>(
     dir: &V,
     expected: &String,
     result: &mut VerificationResult,
 ) {
-    match dir.setup_component_tally_data_payload() {
-        Ok(p) => test_election_event_id(
-            &p.election_event_id,
-            expected,
-            &format!("{}/setup_component_tally_data_payload", dir.get_name()),
-            result,
-        ),
-        Err(e) => result.push(create_verification_error!(
-            format!(
-                "{}/setup_component_tally_data_payload has wrong format",
-                dir.get_name()
-            ),
-            e
+    verify_election_event_ids(
+        once((
+            format!("{}/setup_component_tally_data_payload", dir.get_name()),
+            dir.setup_component_tally_data_payload(),
         )),
-    }
-    for (i, f) in dir.control_component_code_shares_payload_iter() {
-        match f {
-            Err(e) => result.push(create_verification_error!(
-                format!(
-                    "{}/control_component_code_shares_payload_.{} has wrong format",
-                    dir.get_name(),
-                    i
-                ),
-                e
-            )),
-            Ok(cc) => {
-                for p in cc.iter() {
-                    test_election_event_id(
-                        &p.election_event_id,
-                        expected,
-                        &format!(
-                            "{}/control_component_code_shares_payload.{}_chunk{}",
-                            dir.get_name(),
-                            i,
-                            p.chunk_id
-                        ),
-                        result,
-                    )
-                }
-            }
-        }
-    }
-    for (i, f) in dir.setup_component_verification_data_payload_iter() {
-        match f {
-            Err(e) => result.push(create_verification_error!(
-                format!(
-                    "{}/setup_component_verification_data_payload.{} has wrong format",
-                    dir.get_name(),
-                    i
-                ),
-                e
-            )),
-            Ok(s) => test_election_event_id(
-                &s.election_event_id,
-                expected,
-                &format!(
-                    "{}/setup_component_verification_data_payload.{}",
-                    i,
-                    dir.get_name()
-                ),
-                result,
-            ),
-        }
-    }
+        expected,
+        result,
+    );
+    verify_election_event_ids(
+        dir.control_component_code_shares_payload_iter()
+            .flat_map(|(i, f)| match f {
+                Ok(cc) => (*cc)
+                    .into_iter()
+                    .map(|p| {
+                        (
+                            format!(
+                                "{}/control_component_code_shares_payload.{}_chunk{}",
+                                dir.get_name(),
+                                i,
+                                p.chunk_id
+                            ),
+                            Ok(p),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+                Err(e) => vec![(
+                    format!(
+                        "{}/control_component_code_shares_payload_.{}",
+                        dir.get_name(),
+                        i
+                    ),
+                    Err(e),
+                )],
+            }),
+        expected,
+        result,
+    );
+    verify_election_event_ids(
+        dir.setup_component_verification_data_payload_iter()
+            .map(|(i, f)| {
+                (
+                    format!(
+                        "{}/setup_component_verification_data_payload.{}",
+                        dir.get_name(),
+                        i
+                    ),
+                    f,
+                )
+            }),
+        expected,
+        result,
+    );
 }
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let ee_id = match setup_dir.election_event_context_payload() {
         Ok(o) => o.election_event_context.election_event_id,
@@ -109,41 +85,28 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "Cannot extract election_event_context_payload",
                 e
             ));
-            return;
+            return result;
         }
     };
-    match setup_dir.setup_component_public_keys_payload() {
-        Ok(p) => test_election_event_id(
-            &p.election_event_id,
-            &ee_id,
-            "setup_component_public_keys_payload",
-            result,
-        ),
-        Err(e) => result.push(create_verification_error!(
-            "election_event_context_payload has wrong format",
-            e
+    verify_election_event_ids(
+        once((
+            "setup_component_public_keys_payload".to_string(),
+            setup_dir.setup_component_public_keys_payload(),
         )),
-    }
-    for (i, f) in setup_dir.control_component_public_keys_payload_iter() {
-        match f {
-            Err(e) => result.push(create_verification_error!(
-                format!(
-                    "control_component_public_keys_payload.{} has wrong format",
-                    i
-                ),
-                e
-            )),
-            Ok(cc) => test_election_event_id(
-                &cc.election_event_id,
-                &ee_id,
-                &format!("control_component_public_keys_payload.{}", i),
-                result,
-            ),
-        }
-    }
+        &ee_id,
+        &mut result,
+    );
+    verify_election_event_ids(
+        setup_dir
+            .control_component_public_keys_payload_iter()
+            .map(|(i, f)| (format!("control_component_public_keys_payload.{}", i), f)),
+        &ee_id,
+        &mut result,
+    );
     for vcs in setup_dir.vcs_directories().iter() {
-        test_ee_id_for_vcs_dir(vcs, &ee_id, result);
+        test_ee_id_for_vcs_dir(vcs, &ee_id, &mut result);
     }
+    result
 }
 
 #[cfg(test)]
@@ -154,8 +117,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
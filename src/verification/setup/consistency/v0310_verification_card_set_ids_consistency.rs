@@ -0,0 +1,188 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
+};
+use crate::{
+    file_structure::{
+        setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use std::collections::BTreeSet;
+
+const SOURCE_DIRECTORIES: &str = "the directory verification_card_sets";
+const SOURCE_CONTEXT: &str = "electionEventContextPayload";
+const SOURCE_CONFIGURATION: &str = "configuration-anonymized.xml";
+
+/// Prefix of the alias of a verification card set, followed by the authorization identification
+const ALIAS_PREFIX: &str = "vcs_";
+
+/// Compare the ids of two sources
+///
+/// The ids of `expected` missing in `found` and the extra ids of `found` are reported separately,
+/// with the name of the sources.
+fn compare_ids(
+    kind: &str,
+    expected: (&str, &BTreeSet<String>),
+    found: (&str, &BTreeSet<String>),
+    result: &mut VerificationResult,
+) {
+    for id in expected.1.difference(found.1) {
+        result.push(create_verification_failure!(format!(
+            "The {} {} of {} is missing in {}",
+            kind, id, expected.0, found.0
+        )));
+        result.push_failed_item(kind, id, &format!("missing in {}", found.0));
+    }
+    for id in found.1.difference(expected.1) {
+        result.push(create_verification_failure!(format!(
+            "The {} {} of {} is not in {}",
+            kind, id, found.0, expected.0
+        )));
+        result.push_failed_item(kind, id, &format!("extra in {}", found.0));
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    let vcs_contexts = match setup_dir.election_event_context_payload() {
+        Ok(o) => o.election_event_context.verification_card_set_contexts,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let authorizations: BTreeSet<String> = match setup_dir
+        .election_event_configuration()
+        .and_then(|c| c.authorization_identifications())
+    {
+        Ok(ids) => ids.into_iter().collect(),
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract the authorizations from election_event_configuration",
+                e
+            ));
+            return result;
+        }
+    };
+    let dir_ids: BTreeSet<String> = setup_dir
+        .vcs_directories()
+        .iter()
+        .map(|d| d.get_name())
+        .collect();
+    let context_ids: BTreeSet<String> = vcs_contexts
+        .iter()
+        .map(|c| c.verification_card_set_id.clone())
+        .collect();
+    if context_ids.len() != vcs_contexts.len() {
+        result.push(create_verification_failure!(format!(
+            "The verification card set ids are not unique in {}",
+            SOURCE_CONTEXT
+        )));
+    }
+    debug!(
+        "{} verification card sets in {}, {} in {}, {} authorizations in {}",
+        dir_ids.len(),
+        SOURCE_DIRECTORIES,
+        context_ids.len(),
+        SOURCE_CONTEXT,
+        authorizations.len(),
+        SOURCE_CONFIGURATION
+    );
+    compare_ids(
+        "verification_card_set",
+        (SOURCE_CONTEXT, &context_ids),
+        (SOURCE_DIRECTORIES, &dir_ids),
+        &mut result,
+    );
+    let context_authorizations: BTreeSet<String> = vcs_contexts
+        .iter()
+        .map(|c| {
+            c.verification_card_set_alias
+                .strip_prefix(ALIAS_PREFIX)
+                .unwrap_or(&c.verification_card_set_alias)
+                .to_string()
+        })
+        .collect();
+    compare_ids(
+        "authorization",
+        (SOURCE_CONFIGURATION, &authorizations),
+        (SOURCE_CONTEXT, &context_authorizations),
+        &mut result,
+    );
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_setup_dir as get_verifier_dir, test_dataset_setup_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    fn get_mock_verifier_dir() -> MockVerificationDirectory {
+        MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path())
+    }
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_missing_vcs_in_context() {
+        let mut mock_dir = get_mock_verifier_dir();
+        let mut eec = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        let removed = eec
+            .election_event_context
+            .verification_card_set_contexts
+            .remove(0);
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Ok(&eec));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        // Extra on disk and missing authorization
+        assert_eq!(result.failures().len(), 2);
+        assert!(result
+            .failed_items()
+            .iter()
+            .any(|i| i.id == removed.verification_card_set_id));
+    }
+
+    #[test]
+    fn test_wrong_alias() {
+        let mut mock_dir = get_mock_verifier_dir();
+        let mut eec = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        eec.election_event_context.verification_card_set_contexts[1].verification_card_set_alias =
+            "vcs_toto".to_string();
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Ok(&eec));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        // Missing authorization and extra authorization
+        assert_eq!(result.failures().len(), 2);
+        assert!(result.failed_items().iter().any(|i| i.id == "toto"));
+    }
+}
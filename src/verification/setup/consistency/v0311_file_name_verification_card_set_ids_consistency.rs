@@ -0,0 +1,84 @@
+use super::super::super::{result::VerificationResult, verify_vcs_or_bb_ids};
+use crate::{
+    file_structure::{
+        setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use std::iter::once;
+
+/// The ids of the payloads in the directory of the verification card set must be the name
+/// of the directory
+fn test_vcs_ids<V: VCSDirectoryTrait>(dir: &V, result: &mut VerificationResult) {
+    let name = dir.get_name();
+    verify_vcs_or_bb_ids(
+        once((
+            format!("{}/setup_component_tally_data_payload", name),
+            dir.setup_component_tally_data_payload(),
+        )),
+        &name,
+        result,
+    );
+    verify_vcs_or_bb_ids(
+        dir.setup_component_verification_data_payload_iter()
+            .map(|(i, f)| {
+                (
+                    format!("{}/setup_component_verification_data_payload.{}", name, i),
+                    f,
+                )
+            }),
+        &name,
+        result,
+    );
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    for vcs in dir.unwrap_setup().vcs_directories().iter() {
+        test_vcs_ids(vcs, &mut result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{get_test_verifier_setup_dir as get_verifier_dir, CONFIG_TEST};
+    use crate::file_structure::synthetic::{SyntheticDatasetBuilder, SyntheticLocation};
+    use serde_json::json;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_vcs_id() {
+        let dataset =
+            SyntheticDatasetBuilder::new("v0311_wrong_vcs_id", &VerificationPeriod::Setup)
+                .with_verification_card_set("vcs_1", "bb_1", &["vc_1"])
+                .with_field(
+                    &SyntheticLocation::VerificationCardSet("vcs_1".to_string()),
+                    "setupComponentTallyDataPayload.json",
+                    "/verificationCardSetId",
+                    json!("toto"),
+                )
+                .build()
+                .unwrap();
+        let result = fn_verification(
+            &dataset.verification_directory(),
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+}
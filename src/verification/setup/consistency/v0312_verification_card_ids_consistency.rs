@@ -0,0 +1,236 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, VerificationEvent,
+    VerificationResult,
+};
+use crate::{
+    data_structures::setup::setup_component_verification_data_payload::SetupComponentVerificationDataInner,
+    file_structure::{
+        setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+
+/// Verify the verification card ids and the public keys of the tally data of the VCS directory
+///
+/// The tally data must contain one public key per verification card id, and the verification
+/// cards must be in the order of the chunks of the setup component verification data, with the
+/// same public keys. The verifications using the position of the verification cards rely on it.
+fn verify_vcs_dir<V: VCSDirectoryTrait>(vcs: &V, result: &mut VerificationResult) {
+    let name = vcs.get_name();
+    let tally_data = match vcs.setup_component_tally_data_payload() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                format!(
+                    "Cannot extract setup_component_tally_data_payload in {}",
+                    name
+                ),
+                e
+            ));
+            return;
+        }
+    };
+    if tally_data.verification_card_ids.len() != tally_data.verification_card_public_keys.len() {
+        result.push(create_verification_failure!(format!(
+            "The number of verification card ids ({}) is not the same as the number of verification card public keys ({}) in setup_component_tally_data_payload of {}",
+            tally_data.verification_card_ids.len(),
+            tally_data.verification_card_public_keys.len(),
+            name
+        )));
+        result.push_failed_item("verification_card_set", &name, "ids and keys not paired");
+        return;
+    }
+    let mut chunks: Vec<(usize, Vec<SetupComponentVerificationDataInner>)> = vec![];
+    for (i, elt) in vcs.setup_component_verification_data_payload_iter() {
+        match elt {
+            Ok(p) => chunks.push((i, p.setup_component_verification_data)),
+            Err(e) => {
+                result.push(create_verification_error!(
+                    format!(
+                        "Cannot extract setup_component_verification_data_payload for chunk {} in {}",
+                        i, name
+                    ),
+                    e
+                ));
+                return;
+            }
+        }
+    }
+    chunks.sort_by_key(|(i, _)| *i);
+    let verification_data: Vec<&SetupComponentVerificationDataInner> =
+        chunks.iter().flat_map(|(_, d)| d.iter()).collect();
+    if verification_data.len() != tally_data.verification_card_ids.len() {
+        result.push(create_verification_failure!(format!(
+            "The number of verification cards in setup_component_tally_data_payload ({}) is not the same as in the chunks of setup_component_verification_data_payload ({}) in {}",
+            tally_data.verification_card_ids.len(),
+            verification_data.len(),
+            name
+        )));
+        result.push_failed_item("verification_card_set", &name, "number of cards");
+        return;
+    }
+    let position = tally_data
+        .verification_card_ids
+        .iter()
+        .zip(tally_data.verification_card_public_keys.iter())
+        .zip(verification_data.iter())
+        .position(|((id, pk), d)| {
+            id != &d.verification_card_id || pk != &d.verification_card_public_key
+        });
+    if let Some(pos) = position {
+        result.push(
+            create_verification_failure!(format!(
+                "The verification card {} at position {} in setup_component_tally_data_payload does not correspond to the verification card {} in the chunks of setup_component_verification_data_payload in {} (different order or public key)",
+                tally_data.verification_card_ids[pos],
+                pos,
+                verification_data[pos].verification_card_id,
+                name
+            ))
+            .with_entity(EntityKind::VerificationCard, &tally_data.verification_card_ids[pos]),
+        );
+        result.push_failed_item(
+            "verification_card",
+            &tally_data.verification_card_ids[pos],
+            "order or public key",
+        );
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    for vcs in setup_dir.vcs_directories().iter() {
+        let mut vcs_result = VerificationResult::new();
+        verify_vcs_dir(vcs, &mut vcs_result);
+        vcs_result.annotate(EntityKind::VerificationCardSet, &vcs.get_name());
+        result.append(&mut vcs_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{get_test_verifier_setup_dir as get_verifier_dir, CONFIG_TEST};
+    use crate::file_structure::synthetic::{
+        integer_to_base64, synthetic_encryption_group, synthetic_signature,
+        SyntheticDatasetBuilder, SyntheticLocation, SYNTHETIC_ELECTION_EVENT_ID,
+    };
+    use rug::Integer;
+    use serde_json::{json, Value};
+
+    const TALLY_DATA: &str = "setupComponentTallyDataPayload.json";
+
+    fn vcs_location() -> SyntheticLocation {
+        SyntheticLocation::VerificationCardSet("vcs_1".to_string())
+    }
+
+    /// Public key of the verification card, like in the tally data of the synthetic dataset
+    fn public_key(i: usize) -> Value {
+        json!([integer_to_base64(&Integer::from(2 * i + 4))])
+    }
+
+    /// Dataset with one verification card set of two verification cards and the setup component
+    /// verification data of the cards in one chunk
+    fn get_synthetic_builder(name: &str) -> SyntheticDatasetBuilder {
+        let element = json!({ "gamma": integer_to_base64(&Integer::from(3)), "phis": [] });
+        let verification_data: Vec<Value> = ["vc_1", "vc_2"]
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                json!({
+                    "verificationCardId": id,
+                    "encryptedHashedSquaredConfirmationKey": element,
+                    "encryptedHashedSquaredPartialChoiceReturnCodes": element,
+                    "verificationCardPublicKey": public_key(i),
+                })
+            })
+            .collect();
+        SyntheticDatasetBuilder::new(name, &VerificationPeriod::Setup)
+            .with_verification_card_set("vcs_1", "bb_1", &["vc_1", "vc_2"])
+            .with_payload(
+                &vcs_location(),
+                "setupComponentVerificationDataPayload.0.json",
+                json!({
+                    "electionEventId": SYNTHETIC_ELECTION_EVENT_ID,
+                    "verificationCardSetId": "vcs_1",
+                    "partialChoiceReturnCodesAllowList": [],
+                    "chunkId": 0,
+                    "encryptionGroup": synthetic_encryption_group(),
+                    "setupComponentVerificationData": verification_data,
+                    "signature": synthetic_signature(),
+                }),
+            )
+    }
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+        let dataset = get_synthetic_builder("v0312_ok").build().unwrap();
+        let result = fn_verification(
+            &dataset.verification_directory(),
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_swapped_keys() {
+        let dataset = get_synthetic_builder("v0312_swapped_keys")
+            .with_field(
+                &vcs_location(),
+                TALLY_DATA,
+                "/verificationCardPublicKeys",
+                json!([public_key(1), public_key(0)]),
+            )
+            .build()
+            .unwrap();
+        let result = fn_verification(
+            &dataset.verification_directory(),
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failed_items()[0].id, "vc_1");
+        assert_eq!(
+            result.failures()[0]
+                .entities()
+                .iter()
+                .map(|e| e.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                EntityKind::VerificationCard,
+                EntityKind::VerificationCardSet
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_key() {
+        let dataset = get_synthetic_builder("v0312_missing_key")
+            .with_field(
+                &vcs_location(),
+                TALLY_DATA,
+                "/verificationCardPublicKeys",
+                json!([public_key(0)]),
+            )
+            .build()
+            .unwrap();
+        let result = fn_verification(
+            &dataset.verification_directory(),
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+}
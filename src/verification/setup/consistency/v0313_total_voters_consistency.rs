@@ -2,17 +2,39 @@ use super::super::super::result::{
     create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
     file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
 
-pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
-    dir: &D,
-    _config: &'static Config,
+/// Verify that the total of voters is the sum of the voting cards, with the tolerance of the
+/// strictness profile
+fn verify_total_voters(
+    total_voters: usize,
+    sum_voting_cards: usize,
+    tolerance: usize,
     result: &mut VerificationResult,
 ) {
+    let diff = total_voters.abs_diff(sum_voting_cards);
+    if diff > tolerance {
+        result.push(create_verification_failure!(format!(
+            "The sum of voting cards {} is not the same as total voters {} (tolerance {})",
+            sum_voting_cards, total_voters, tolerance
+        )))
+    } else if diff > 0 {
+        debug!(
+            "The difference {} between the sum of voting cards and the total voters is accepted (tolerance {})",
+            diff, tolerance
+        )
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let vcs_contexts = match setup_dir.election_event_context_payload() {
         Ok(o) => o.election_event_context.verification_card_set_contexts,
@@ -21,7 +43,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "Cannot extract election_event_context_payload",
                 e
             ));
-            return;
+            return result;
         }
     };
     let total_voter = match setup_dir.election_event_configuration() {
@@ -31,20 +53,19 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "Cannot extract election_event_context_payload",
                 e
             ));
-            return;
+            return result;
         }
     };
-    if total_voter
-        != vcs_contexts
+    verify_total_voters(
+        total_voter,
+        vcs_contexts
             .iter()
             .map(|e| e.number_of_voting_cards)
-            .sum::<usize>()
-    {
-        result.push(create_verification_failure!(format!(
-            "The sum of voting cards is not the same as total voters {}",
-            total_voter
-        )))
-    }
+            .sum::<usize>(),
+        context.config().strictness().total_voters_tolerance,
+        &mut result,
+    );
+    result
 }
 
 #[cfg(test)]
@@ -55,8 +76,17 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_tolerance() {
         let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        verify_total_voters(10, 8, 2, &mut result);
+        verify_total_voters(10, 12, 2, &mut result);
         assert!(result.is_ok().unwrap());
+        verify_total_voters(10, 7, 2, &mut result);
+        assert_eq!(result.failures().len(), 1);
     }
 }
@@ -0,0 +1,238 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, FailureCode,
+    VerificationEvent, VerificationResult,
+};
+use crate::{
+    file_structure::{
+        setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+
+/// Verify that the node ids `found` are exactly the expected node ids, each one once
+///
+/// Return a failure message describing the difference else
+fn verify_node_id_set(found: &[usize], expected: &[usize]) -> Option<String> {
+    let mut sorted = found.to_vec();
+    sorted.sort();
+    match sorted == expected {
+        true => None,
+        false => Some(format!(
+            "the node ids are {:?}, but {:?} expected",
+            found, expected
+        )),
+    }
+}
+
+/// Verify the node ids of the control component code shares of the chunks of the VCS
+fn verify_node_ids_of_vcs<V: VCSDirectoryTrait>(
+    vcs: &V,
+    expected: &[usize],
+    result: &mut VerificationResult,
+) {
+    let name = vcs.get_name();
+    for (i, p) in vcs.control_component_code_shares_payload_iter() {
+        match p {
+            Ok(p) => {
+                let found: Vec<usize> = p.iter().map(|e| e.node_id).collect();
+                if let Some(msg) = verify_node_id_set(&found, expected) {
+                    result.push(
+                        create_verification_failure!(format!(
+                            "control_component_code_shares_payload of chunk {} in {}: {}",
+                            i, name, msg
+                        ))
+                        .with_code(FailureCode::Inconsistency)
+                        .with_entity(EntityKind::VerificationCardSet, &name)
+                        .with_chunk(i),
+                    );
+                }
+            }
+            Err(e) => result.push(create_verification_error!(
+                format!(
+                    "Cannot extract control_component_code_shares_payload for chunk {} in {}",
+                    i, name
+                ),
+                e
+            )),
+        }
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    // The node ids of the file names are verified in 03.02
+    let expected: Vec<usize> = setup_dir.node_ids().into_iter().collect();
+    debug!("Node ids of the control components: {:?}", expected);
+    for (i, p) in setup_dir.control_component_public_keys_payload_iter() {
+        match p {
+            Ok(p) => {
+                if p.control_component_public_keys.node_id != i {
+                    result.push(
+                        create_verification_failure!(format!(
+                            "The node id {} of control_component_public_keys_payload.{} is not the node id of the file name",
+                            p.control_component_public_keys.node_id, i
+                        ))
+                        .with_code(FailureCode::Inconsistency),
+                    );
+                }
+            }
+            Err(e) => result.push(create_verification_error!(
+                format!("Cannot extract control_component_public_keys_payload.{}", i),
+                e
+            )),
+        }
+    }
+    match setup_dir.setup_component_public_keys_payload() {
+        Ok(p) => {
+            let found: Vec<usize> = p
+                .setup_component_public_keys
+                .combined_control_component_public_keys
+                .iter()
+                .map(|k| k.node_id)
+                .collect();
+            // The keys are combined in the order of the node ids
+            if found != expected {
+                result.push(
+                    create_verification_failure!(format!(
+                        "The node ids of the combined control component public keys in setup_component_public_keys_payload are {:?}, but {:?} expected",
+                        found, expected
+                    ))
+                    .with_code(FailureCode::Inconsistency),
+                );
+            }
+        }
+        Err(e) => result.push(create_verification_error!(
+            "Cannot extract setup_component_public_keys_payload",
+            e
+        )),
+    }
+    match setup_dir.election_event_context_payload() {
+        Ok(p) => {
+            if let Some(msg) =
+                verify_node_id_set(&p.election_event_context.mixing_order(), &expected)
+            {
+                result.push(
+                    create_verification_failure!(format!(
+                        "Mixing order of election_event_context_payload: {}",
+                        msg
+                    ))
+                    .with_code(FailureCode::Inconsistency),
+                );
+            }
+        }
+        Err(e) => result.push(create_verification_error!(
+            "Cannot extract election_event_context_payload",
+            e
+        )),
+    }
+    for vcs in setup_dir.vcs_directories().iter() {
+        verify_node_ids_of_vcs(vcs, &expected, &mut result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_setup_dir as get_verifier_dir, test_dataset_setup_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    fn get_mock_verifier_dir() -> MockVerificationDirectory {
+        MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path())
+    }
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_verify_node_id_set() {
+        assert!(verify_node_id_set(&[2, 1, 4, 3], &[1, 2, 3, 4]).is_none());
+        assert!(verify_node_id_set(&[1, 2, 3], &[1, 2, 3, 4]).is_some());
+        assert!(verify_node_id_set(&[1, 2, 2, 4], &[1, 2, 3, 4]).is_some());
+        assert!(verify_node_id_set(&[1, 2, 3, 4, 4], &[1, 2, 3, 4]).is_some());
+    }
+
+    #[test]
+    fn test_wrong_node_id_cc_public_keys() {
+        let mut mock_dir = get_mock_verifier_dir();
+        let mut payload = mock_dir
+            .unwrap_setup()
+            .control_component_public_keys_payload_iter()
+            .find(|(i, _)| *i == 2)
+            .unwrap()
+            .1
+            .unwrap();
+        payload.control_component_public_keys.node_id = 3;
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_control_component_public_keys_payloads(2, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+
+    #[test]
+    fn test_wrong_order_combined_keys() {
+        let mut mock_dir = get_mock_verifier_dir();
+        let mut payload = mock_dir
+            .unwrap_setup()
+            .setup_component_public_keys_payload()
+            .unwrap();
+        payload
+            .setup_component_public_keys
+            .combined_control_component_public_keys
+            .swap(0, 1);
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_setup_component_public_keys_payload(&Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_mixing_order() {
+        let mut mock_dir = get_mock_verifier_dir();
+        let mut eec = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        eec.election_event_context.mixing_order = Some(vec![1, 2, 3, 5]);
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Ok(&eec));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+    }
+
+    #[test]
+    fn test_duplicate_node_id_code_shares() {
+        let mut mock_dir = get_mock_verifier_dir();
+        let (chunk, payload) = mock_dir.unwrap_setup().vcs_directories()[0]
+            .control_component_code_shares_payload_iter()
+            .next()
+            .unwrap();
+        let mut payload = payload.unwrap();
+        payload[1].node_id = payload[0].node_id;
+        mock_dir.unwrap_setup_mut().vcs_directories_mut()[0]
+            .mock_control_component_code_shares_payloads(chunk, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+}
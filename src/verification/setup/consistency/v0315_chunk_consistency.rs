@@ -2,12 +2,12 @@ use super::super::super::result::{
     create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
     file_structure::{
         file_group::FileGroup,
         setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
         VerificationDirectoryTrait,
     },
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
@@ -30,19 +30,19 @@ fn verify_uninterrupted_monotonic_sequence(
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     for vcs in setup_dir.vcs_directories() {
         verify_uninterrupted_monotonic_sequence(
             vcs.setup_component_verification_data_payload_group(),
-            result,
+            &mut result,
             &vcs.get_name(),
         );
         verify_uninterrupted_monotonic_sequence(
             vcs.control_component_code_shares_payload_group(),
-            result,
+            &mut result,
             &vcs.get_name(),
         );
         for (i, elt) in vcs.setup_component_verification_data_payload_iter() {
@@ -90,6 +90,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
             }
         }
     }
+    result
 }
 
 #[cfg(test)]
@@ -100,8 +101,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
@@ -0,0 +1,95 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
+};
+use crate::{
+    file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+
+/// Verify that the number of public key components and of Schnorr proofs of the electoral
+/// board are equal to the number of members of the electoral board
+fn verify_electoral_board_size(
+    number_of_members: usize,
+    number_of_pk_components: usize,
+    number_of_schnorr_proofs: usize,
+    result: &mut VerificationResult,
+) {
+    if number_of_pk_components != number_of_members {
+        result.push(create_verification_failure!(format!(
+            "The electoral board public key has {} components, but the electoral board has {} members",
+            number_of_pk_components, number_of_members
+        )))
+    }
+    if number_of_schnorr_proofs != number_of_members {
+        result.push(create_verification_failure!(format!(
+            "There are {} electoral board Schnorr proofs, but the electoral board has {} members",
+            number_of_schnorr_proofs, number_of_members
+        )))
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    let members = match setup_dir
+        .election_event_configuration()
+        .and_then(|c| c.electoral_board_members())
+    {
+        Ok(m) => m,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract the electoral board members from election_event_configuration",
+                e
+            ));
+            return result;
+        }
+    };
+    let sc_pk = match setup_dir.setup_component_public_keys_payload() {
+        Ok(o) => o.setup_component_public_keys,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract setup_component_public_keys_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    debug!("Electoral board members: {:?}", members);
+    verify_electoral_board_size(
+        members.len(),
+        sc_pk.electoral_board_public_key.len(),
+        sc_pk.electoral_board_schnorr_proofs.len(),
+        &mut result,
+    );
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::super::super::result::VerificationResultTrait, *};
+    use crate::config::test::{get_test_verifier_setup_dir as get_verifier_dir, CONFIG_TEST};
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_size() {
+        let mut result = VerificationResult::new();
+        verify_electoral_board_size(2, 2, 2, &mut result);
+        assert!(result.is_ok().unwrap());
+        verify_electoral_board_size(3, 2, 2, &mut result);
+        assert_eq!(result.failures().len(), 2);
+        let mut result = VerificationResult::new();
+        verify_electoral_board_size(2, 2, 1, &mut result);
+        assert_eq!(result.failures().len(), 1);
+    }
+}
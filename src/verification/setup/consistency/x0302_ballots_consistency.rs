@@ -0,0 +1,183 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
+};
+use crate::{
+    data_structures::setup::{
+        election_event_configuration::Ballot,
+        election_event_context_payload::VerificationCardSetContext,
+    },
+    file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use std::collections::HashSet;
+
+/// Verify that the voting options of the verification card set reference valid ballots
+///
+/// Each correctness information of the voting options must be an election or a question of a
+/// ballot of the configuration. Each referenced ballot must be complete: all its questions are
+/// present with the number of answers of the configuration.
+fn verify_vcs_ballots(
+    vcs_context: &VerificationCardSetContext,
+    ballots: &[Ballot],
+    election_ids: &[String],
+    result: &mut VerificationResult,
+) {
+    let vcs_id = &vcs_context.verification_card_set_id;
+    let options = vcs_context.number_of_voting_options_per_correctness_information();
+    let mut referenced_ballots = HashSet::new();
+    for id in options.keys() {
+        if election_ids.iter().any(|e| e == id) {
+            continue;
+        }
+        match ballots.iter().position(|b| b.find_question(id).is_some()) {
+            Some(i) => {
+                referenced_ballots.insert(i);
+            }
+            None => {
+                result.push(create_verification_failure!(format!(
+                    "The verification card set {} references the question {} that is not in a ballot of the configuration",
+                    vcs_id, id
+                )));
+                result.push_failed_item("verification_card_set", vcs_id, "unknown ballot");
+            }
+        }
+    }
+    let mut referenced_ballots: Vec<usize> = referenced_ballots.into_iter().collect();
+    referenced_ballots.sort();
+    for ballot in referenced_ballots.iter().map(|i| &ballots[*i]) {
+        for q in ballot.questions.iter() {
+            match options.get(q.identification.as_str()) {
+                None => {
+                    result.push(create_verification_failure!(format!(
+                        "The question {} of the ballot {} is missing in the verification card set {}",
+                        q.identification, ballot.identification, vcs_id
+                    )));
+                    result.push_failed_item("verification_card_set", vcs_id, "incomplete ballot");
+                }
+                Some(n) if *n != q.answer_identifications.len() => {
+                    result.push(create_verification_failure!(format!(
+                        "The verification card set {} has {} voting options for the question {} of the ballot {}, but the question has {} answers",
+                        vcs_id,
+                        n,
+                        q.identification,
+                        ballot.identification,
+                        q.answer_identifications.len()
+                    )));
+                    result.push_failed_item(
+                        "verification_card_set",
+                        vcs_id,
+                        "wrong number of options",
+                    );
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    let config = match setup_dir.election_event_configuration() {
+        Ok(c) => c,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_configuration",
+                e
+            ));
+            return result;
+        }
+    };
+    let ballots = match config.ballots() {
+        Ok(b) => b,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract the ballots from election_event_configuration",
+                e
+            ));
+            return result;
+        }
+    };
+    let election_ids = match config.election_identifications() {
+        Ok(ids) => ids,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract the elections from election_event_configuration",
+                e
+            ));
+            return result;
+        }
+    };
+    let ee_context = match setup_dir.election_event_context_payload() {
+        Ok(o) => o.election_event_context,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    debug!(
+        "{} ballots and {} elections in the configuration",
+        ballots.len(),
+        election_ids.len()
+    );
+    for vcs_context in ee_context.verification_card_set_contexts.iter() {
+        verify_vcs_ballots(vcs_context, &ballots, &election_ids, &mut result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::super::super::result::VerificationResultTrait, *};
+    use crate::config::test::{get_test_verifier_setup_dir as get_verifier_dir, CONFIG_TEST};
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_ballots() {
+        let dir = get_verifier_dir();
+        let setup_dir = dir.unwrap_setup();
+        let config = setup_dir.election_event_configuration().unwrap();
+        let mut ballots = config.ballots().unwrap();
+        let election_ids = config.election_identifications().unwrap();
+        let ee_context = setup_dir
+            .election_event_context_payload()
+            .unwrap()
+            .election_event_context;
+        let vcs_context = ee_context
+            .verification_card_set_contexts
+            .iter()
+            .find(|c| {
+                c.number_of_voting_options_per_correctness_information()
+                    .contains_key(ballots[1].questions[0].identification.as_str())
+            })
+            .unwrap();
+        let mut result = VerificationResult::new();
+        verify_vcs_ballots(vcs_context, &ballots, &election_ids, &mut result);
+        assert!(result.is_ok().unwrap());
+        ballots[1].questions[0].answer_identifications.pop();
+        let mut question = ballots[0].questions[0].clone();
+        question.identification = "toto".to_string();
+        ballots[1].questions.push(question);
+        let mut result = VerificationResult::new();
+        verify_vcs_ballots(vcs_context, &ballots, &election_ids, &mut result);
+        assert_eq!(result.failures().len(), 2);
+        let mut result = VerificationResult::new();
+        verify_vcs_ballots(vcs_context, &ballots[1..], &election_ids, &mut result);
+        assert!(result.has_failures().unwrap());
+        assert!(!result.failed_items().is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, FailureCode, VerificationEvent,
+    VerificationResult,
+};
+use crate::{
+    data_structures::setup::{
+        election_event_context_payload::{
+            actual_voting_option_identifications, ElectionEventContextPayload,
+        },
+        primes_mapping_table_payload::PrimesMappingTablePayload,
+    },
+    file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use std::collections::{BTreeMap, HashSet};
+
+const FILE_NAME: &str = "primesMappingTablePayload";
+
+/// Encoding of the actual voting options recomputed from the primes mapping tables of the
+/// verification card sets in the election event context
+///
+/// Each actual voting option is encoded with the same prime in all the verification card sets
+/// (see VerifC of 05.03). If it is not the case, the first prime is taken
+fn recomputed_encoding(ee_context: &ElectionEventContextPayload) -> BTreeMap<&String, usize> {
+    let mut res = BTreeMap::new();
+    for e in ee_context
+        .election_event_context
+        .verification_card_set_contexts
+        .iter()
+        .flat_map(|vcsc| vcsc.primes_mapping_table.p_table.iter())
+    {
+        res.entry(&e.actual_voting_option)
+            .or_insert(e.encoded_voting_option);
+    }
+    res
+}
+
+/// Verify the header of the file (election event id and encryption group)
+fn verify_header(
+    payload: &PrimesMappingTablePayload,
+    ee_context: &ElectionEventContextPayload,
+    result: &mut VerificationResult,
+) {
+    if payload.election_event_id != ee_context.election_event_context.election_event_id {
+        result.push(
+            create_verification_failure!(format!(
+                "The election event id {} of the primes mapping table is not the election event id {} of the context",
+                payload.election_event_id, ee_context.election_event_context.election_event_id
+            ))
+            .with_code(FailureCode::Inconsistency)
+            .with_file(FILE_NAME),
+        );
+    }
+    let (eg, expected) = (&payload.encryption_group, &ee_context.encryption_group);
+    if eg.p() != expected.p() || eg.q() != expected.q() || eg.g() != expected.g() {
+        result.push(
+            create_verification_failure!(
+                "The encryption group of the primes mapping table is not the encryption group of the context"
+            )
+            .with_code(FailureCode::Inconsistency)
+            .with_file(FILE_NAME),
+        );
+    }
+}
+
+/// Verify that the encoding of the file is the recomputed encoding
+///
+/// The primes must be small primes of the election event context, the identifications of the
+/// actual voting options must be defined in the configuration, and each actual voting option
+/// must be encoded like in the election event context. All the actual voting options of the
+/// context must be in the file.
+fn verify_encoding(
+    payload: &PrimesMappingTablePayload,
+    expected: &BTreeMap<&String, usize>,
+    small_primes: &[usize],
+    identifications: &HashSet<String>,
+    result: &mut VerificationResult,
+) {
+    let small_primes: HashSet<&usize> = small_primes.iter().collect();
+    let mut found = HashSet::new();
+    for e in payload.p_table.iter() {
+        let option = &e.actual_voting_option;
+        if !found.insert(option) {
+            result.push(
+                create_verification_failure!(format!(
+                    "The actual voting option {} is mapped more than once",
+                    option
+                ))
+                .with_code(FailureCode::Inconsistency)
+                .with_file(FILE_NAME),
+            );
+            result.push_failed_item("voting_option", option, "mapped more than once");
+        }
+        if !small_primes.contains(&e.encoded_voting_option) {
+            result.push(
+                create_verification_failure!(format!(
+                    "The encoding {} of the actual voting option {} is not a small prime",
+                    e.encoded_voting_option, option
+                ))
+                .with_code(FailureCode::DomainCheck)
+                .with_file(FILE_NAME),
+            );
+            result.push_failed_item("voting_option", option, "encoding not a small prime");
+        }
+        if let Some(id) = actual_voting_option_identifications(option)
+            .into_iter()
+            .find(|id| !identifications.contains(*id))
+        {
+            result.push(
+                create_verification_failure!(format!(
+                    "The identification {} of the actual voting option {} is not defined in the configuration",
+                    id, option
+                ))
+                .with_code(FailureCode::Inconsistency)
+                .with_file(FILE_NAME),
+            );
+            result.push_failed_item("voting_option", option, "not in the configuration");
+        }
+        match expected.get(option) {
+            Some(p) if *p == e.encoded_voting_option => (),
+            Some(p) => {
+                result.push(
+                    create_verification_failure!(format!(
+                        "The actual voting option {} is encoded with {} instead of {}",
+                        option, e.encoded_voting_option, p
+                    ))
+                    .with_code(FailureCode::Inconsistency)
+                    .with_file(FILE_NAME),
+                );
+                result.push_failed_item("voting_option", option, "wrong encoding");
+            }
+            None => {
+                result.push(
+                    create_verification_failure!(format!(
+                        "The actual voting option {} is not in the election event context",
+                        option
+                    ))
+                    .with_code(FailureCode::Inconsistency)
+                    .with_file(FILE_NAME),
+                );
+                result.push_failed_item("voting_option", option, "not in the context");
+            }
+        }
+    }
+    for option in expected.keys().filter(|o| !found.contains(**o)) {
+        result.push(
+            create_verification_failure!(format!(
+                "The actual voting option {} of the election event context is missing",
+                option
+            ))
+            .with_code(FailureCode::Inconsistency)
+            .with_file(FILE_NAME),
+        );
+        result.push_failed_item("voting_option", option, "missing");
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    if !setup_dir.primes_mapping_table_payload_file().exists() {
+        debug!("No primes mapping table in the dataset: nothing to verify");
+        return result;
+    }
+    let payload = match setup_dir.primes_mapping_table_payload() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract primes_mapping_table_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let ee_context = match setup_dir.election_event_context_payload() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let identifications = match setup_dir
+        .election_event_configuration()
+        .and_then(|c| c.identifications())
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_configuration",
+                e
+            ));
+            return result;
+        }
+    };
+    verify_header(&payload, &ee_context, &mut result);
+    verify_encoding(
+        &payload,
+        &recomputed_encoding(&ee_context),
+        &ee_context.small_primes,
+        &identifications,
+        &mut result,
+    );
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_setup_dir as get_verifier_dir, test_dataset_setup_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    /// Payload built from the election event context of the dataset
+    fn payload_from_context(ee_context: &ElectionEventContextPayload) -> PrimesMappingTablePayload {
+        let mut p_table = vec![];
+        let mut found = HashSet::new();
+        for e in ee_context
+            .election_event_context
+            .verification_card_set_contexts
+            .iter()
+            .flat_map(|vcsc| vcsc.primes_mapping_table.p_table.iter())
+        {
+            if found.insert(e.actual_voting_option.clone()) {
+                p_table.push(e.clone());
+            }
+        }
+        PrimesMappingTablePayload {
+            encryption_group: ee_context.encryption_group.clone(),
+            election_event_id: ee_context.election_event_context.election_event_id.clone(),
+            p_table,
+        }
+    }
+
+    fn mock_dir_with_payload(
+        change: impl Fn(&mut PrimesMappingTablePayload),
+    ) -> MockVerificationDirectory {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let ee_context = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        let mut payload = payload_from_context(&ee_context);
+        change(&mut payload);
+        // The file of the election event context exists and stands for the file of the table
+        let file = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload_file()
+            .clone();
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_primes_mapping_table_payload_file(&file);
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_primes_mapping_table_payload(&Ok(&payload));
+        mock_dir
+    }
+
+    #[test]
+    fn test_no_file() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_ok() {
+        let mock_dir = mock_dir_with_payload(|_| ());
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_encoding() {
+        let mock_dir = mock_dir_with_payload(|p| {
+            let other = p.p_table[1].encoded_voting_option;
+            p.p_table[0].encoded_voting_option = other;
+        });
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert!(result
+            .failed_items()
+            .iter()
+            .any(|f| f.reason == "wrong encoding"));
+    }
+
+    #[test]
+    fn test_missing_option() {
+        let mock_dir = mock_dir_with_payload(|p| {
+            p.p_table.pop();
+        });
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failed_items()[0].reason, "missing");
+    }
+
+    #[test]
+    fn test_not_a_small_prime() {
+        let mock_dir = mock_dir_with_payload(|p| {
+            p.p_table[0].encoded_voting_option = 4;
+        });
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result
+            .failures()
+            .iter()
+            .any(|f| f.code() == FailureCode::DomainCheck));
+    }
+
+    #[test]
+    fn test_wrong_election_event_id() {
+        let mock_dir = mock_dir_with_payload(|p| {
+            p.election_event_id = "toto".to_string();
+        });
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert_eq!(result.failures().len(), 1);
+    }
+}
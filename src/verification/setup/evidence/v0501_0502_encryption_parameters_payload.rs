@@ -2,18 +2,21 @@ use super::super::super::result::{
     create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
     file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    preliminary::{
+        derive_encryption_group, derive_small_primes, EncryptionGroupComparison,
+        SmallPrimesComparison,
+    },
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
-use rust_ev_crypto_primitives::EncryptionParameters;
 
 pub(super) fn fn_0501_verify_encryption_parameters<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let eg: Box<
         crate::data_structures::setup::election_event_context_payload::ElectionEventContextPayload,
@@ -24,10 +27,10 @@ pub(super) fn fn_0501_verify_encryption_parameters<D: VerificationDirectoryTrait
                 "election_event_context_payload cannot be read",
                 e
             ));
-            return;
+            return result;
         }
     };
-    let eg_test = match EncryptionParameters::get_encryption_parameters(&eg.seed) {
+    let eg_test = match derive_encryption_group(&eg.seed) {
         Ok(eg) => eg,
         Err(e) => {
             result.push(create_verification_error!(
@@ -37,37 +40,26 @@ pub(super) fn fn_0501_verify_encryption_parameters<D: VerificationDirectoryTrait
                 ),
                 e
             ));
-            return;
+            return result;
         }
     };
-    if eg_test.p() != eg.encryption_group.p() {
-        result.push(create_verification_failure!(format!(
-            "payload p and calculated p are equal: payload: {} / calculated: {}",
-            eg.encryption_group.p(),
-            eg_test.p()
-        )));
-    }
-    if eg_test.q() != eg.encryption_group.q() {
-        result.push(create_verification_failure!(format!(
-            "payload q and calculated q are equal: payload: {} / calculated: {}",
-            eg.encryption_group.q(),
-            eg_test.q()
-        )));
-    }
-    if eg_test.g() != eg.encryption_group.g() {
-        result.push(create_verification_failure!(format!(
-            "payload g and calculated g are equal: payload: {} / calculated: {}",
-            eg.encryption_group.g(),
-            eg_test.g()
-        )))
+    let comparison = EncryptionGroupComparison::new(&eg.encryption_group, &eg_test);
+    for (name, c) in comparison.elements() {
+        if !c.is_equal() {
+            result.push(create_verification_failure!(format!(
+                "payload {} and calculated {} are not equal: {}",
+                name, name, c
+            )));
+        }
     }
+    result
 }
 
 pub(super) fn fn_0502_verify_small_prime_group_members<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let eg = match setup_dir.election_event_context_payload() {
         Ok(eg) => eg,
@@ -76,44 +68,38 @@ pub(super) fn fn_0502_verify_small_prime_group_members<D: VerificationDirectoryT
                 "election_event_context_payload cannot be read",
                 e
             ));
-            return;
+            return result;
         }
     };
-    let primes = match eg
-        .encryption_group
-        .get_small_prime_group_members(Config::maximum_number_of_voting_options())
-    {
+    let primes = match derive_small_primes(&eg.encryption_group) {
         Ok(p) => p,
         Err(e) => {
             result.push(create_verification_error!(
                 "Error getting small prime group members",
                 e
             ));
-            return;
+            return result;
         }
     };
-    if eg.small_primes.len() != primes.len() {
+    let comparison = SmallPrimesComparison::new(&eg.small_primes, &primes);
+    if !comparison.len.is_equal() {
         result.push(create_verification_failure!(format!(
             "length of primes not the same: calculated: {} / expected {}",
-            primes.len(),
-            eg.small_primes.len()
+            comparison.len.calculated, comparison.len.payload
         )))
-    } else if eg.small_primes != primes {
-        let mut i = 0usize;
-        while eg.small_primes[i] == primes[i] {
-            i += 1;
-        }
+    } else if let Some((i, c)) = comparison.first_difference {
         result.push(
             create_verification_failure!(
                 format!(
                     "Small prime group members are not the same. First error at position {}: calculated {} / expected {}",
                     i + 1,
-                    primes[i],
-                    eg.small_primes[i]
+                    c.calculated,
+                    c.payload
                 )
             )
         )
     }
+    result
 }
 
 #[cfg(test)]
@@ -124,16 +110,16 @@ mod test {
     #[test]
     fn test_0501_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0501_verify_encryption_parameters(&dir, &CONFIG_TEST, &mut result);
+        let result =
+            fn_0501_verify_encryption_parameters(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 
     #[test]
     fn test_0502_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0502_verify_small_prime_group_members(&dir, &CONFIG_TEST, &mut result);
+        let result =
+            fn_0502_verify_small_prime_group_members(&dir, &VerificationContext::new(&CONFIG_TEST));
         if !result.is_ok().unwrap() {
             for e in result.errors() {
                 println!("{:?}", e);
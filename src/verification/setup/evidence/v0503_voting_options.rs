@@ -3,18 +3,181 @@ use super::super::super::result::{
 };
 use crate::{
     config::Config,
-    file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    data_structures::setup::election_event_context_payload::{
+        actual_voting_option_identifications, ElectionEventContextPayload,
+    },
+    file_structure::{
+        setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
 use rug::Integer;
 use rust_ev_crypto_primitives::Constants;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
-pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
-    dir: &D,
-    _config: &'static Config,
+/// VerifC and VerifD: Verify that the encoding of the actual voting options is consistent
+///
+/// Each actual voting option must be encoded with the same prime in all the verification card sets,
+/// each prime must encode only one actual voting option, and the primes must be representable,
+/// i.e. be one of the first `n_sup` small primes.
+fn verify_encoding_consistency(
+    ee_context: &ElectionEventContextPayload,
     result: &mut VerificationResult,
 ) {
+    let representable: HashSet<usize> = ee_context
+        .small_primes
+        .iter()
+        .take(Config::maximum_number_of_voting_options())
+        .copied()
+        .collect();
+    let mut encodings: BTreeMap<&String, BTreeSet<usize>> = BTreeMap::new();
+    let mut options: BTreeMap<usize, BTreeSet<&String>> = BTreeMap::new();
+    for vcsc in ee_context
+        .election_event_context
+        .verification_card_set_contexts
+        .iter()
+    {
+        let p_table = &vcsc.primes_mapping_table.p_table;
+        if p_table.len() > Config::maximum_number_of_voting_options() {
+            result.push(create_verification_failure!(format!(
+                "VerifD: The number of voting options {} of the verification card set {} exceeds the maximum {}",
+                p_table.len(),
+                vcsc.verification_card_set_id,
+                Config::maximum_number_of_voting_options()
+            )));
+            result.push_failed_item(
+                "verification_card_set",
+                &vcsc.verification_card_set_id,
+                "too many voting options",
+            );
+        }
+        for e in p_table.iter() {
+            encodings
+                .entry(&e.actual_voting_option)
+                .or_default()
+                .insert(e.encoded_voting_option);
+            options
+                .entry(e.encoded_voting_option)
+                .or_default()
+                .insert(&e.actual_voting_option);
+        }
+    }
+    for (option, primes) in encodings.iter() {
+        if primes.len() > 1 {
+            result.push(create_verification_failure!(format!(
+                "VerifC: The actual voting option {} is encoded with different primes {:?}",
+                option, primes
+            )));
+            result.push_failed_item("voting_option", option, "encoded with different primes");
+        }
+        for p in primes.iter().filter(|p| !representable.contains(p)) {
+            result.push(create_verification_failure!(format!(
+                "VerifD: The encoding {} of the actual voting option {} is not one of the first {} small primes",
+                p,
+                option,
+                Config::maximum_number_of_voting_options()
+            )));
+            result.push_failed_item("voting_option", option, "encoding not representable");
+        }
+    }
+    for (prime, opts) in options.iter().filter(|(_, o)| o.len() > 1) {
+        result.push(create_verification_failure!(format!(
+            "VerifC: The prime {} encodes different actual voting options {:?}",
+            prime, opts
+        )));
+        for o in opts.iter() {
+            result.push_failed_item(
+                "voting_option",
+                o,
+                &format!("prime {} shared with other options", prime),
+            );
+        }
+    }
+}
+
+/// VerifE: Verify that the actual voting options are built with identifications of the configuration
+fn verify_options_in_configuration(
+    ee_context: &ElectionEventContextPayload,
+    identifications: &HashSet<String>,
+    result: &mut VerificationResult,
+) {
+    let mut checked = HashSet::new();
+    for e in ee_context
+        .election_event_context
+        .verification_card_set_contexts
+        .iter()
+        .flat_map(|vcsc| vcsc.primes_mapping_table.p_table.iter())
+    {
+        if !checked.insert(&e.actual_voting_option) {
+            continue;
+        }
+        if let Some(id) = actual_voting_option_identifications(&e.actual_voting_option)
+            .into_iter()
+            .find(|id| !identifications.contains(*id))
+        {
+            result.push(create_verification_failure!(format!(
+                "VerifE: The identification {} of the actual voting option {} is not defined in the configuration",
+                id, e.actual_voting_option
+            )));
+            result.push_failed_item(
+                "voting_option",
+                &e.actual_voting_option,
+                "not in the configuration",
+            );
+        }
+    }
+}
+
+/// VerifF: Verify that the number of partial choice return codes in the setup verification data
+/// of the VCS directory corresponds to the number of voting options
+fn verify_options_in_verification_data<V: VCSDirectoryTrait>(
+    vcs_dir: &V,
+    number_of_voting_options: usize,
+    result: &mut VerificationResult,
+) {
+    for (i, p) in vcs_dir.setup_component_verification_data_payload_iter() {
+        match p {
+            Ok(p) => {
+                for d in p.setup_component_verification_data.iter().filter(|d| {
+                    d.encrypted_hashed_squared_partial_choice_return_codes
+                        .phis
+                        .len()
+                        != number_of_voting_options
+                }) {
+                    result.push(create_verification_failure!(format!(
+                        "VerifF: The number of partial choice return codes {} for the verification card {} in chunk {} is not the number of voting options {}",
+                        d.encrypted_hashed_squared_partial_choice_return_codes.phis.len(),
+                        d.verification_card_id,
+                        i,
+                        number_of_voting_options
+                    )));
+                    result.push_failed_item(
+                        "verification_card",
+                        &d.verification_card_id,
+                        "wrong number of partial choice return codes",
+                    );
+                }
+            }
+            Err(e) => result.push(create_verification_error!(
+                format!(
+                    "Error getting setup_component_verification_data_payload {} in {}",
+                    i,
+                    vcs_dir.get_name()
+                ),
+                e
+            )),
+        }
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let ee_context = match setup_dir.election_event_context_payload() {
         Ok(eg) => eg,
@@ -23,7 +186,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "election_event_context_payload cannot be read",
                 e
             ));
-            return;
+            return result;
         }
     };
     let mut p_tilde = vec![];
@@ -64,6 +227,30 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
             "VerifB: The product of the phi last primes (the largest possible encoded vote) must be smaller than p"
         ))
     }
+    verify_encoding_consistency(&ee_context, &mut result);
+    match setup_dir
+        .election_event_configuration()
+        .and_then(|c| c.identifications())
+    {
+        Ok(ids) => verify_options_in_configuration(&ee_context, &ids, &mut result),
+        Err(e) => result.push(create_verification_error!(
+            "election_event_configuration cannot be read",
+            e
+        )),
+    }
+    for vcs_dir in setup_dir.vcs_directories().iter() {
+        match ee_context
+            .election_event_context
+            .number_of_voting_options(&vcs_dir.get_name())
+        {
+            Some(n) => verify_options_in_verification_data(vcs_dir, n, &mut result),
+            None => result.push(create_verification_failure!(format!(
+                "No verification card set context found for {}",
+                vcs_dir.get_name()
+            ))),
+        }
+    }
+    result
 }
 
 #[cfg(test)]
@@ -74,8 +261,81 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_encoding_not_consistent() {
+        let dir = get_verifier_dir();
+        let mut ee_context = dir.unwrap_setup().election_event_context_payload().unwrap();
+        let vcs_contexts = &ee_context
+            .election_event_context
+            .verification_card_set_contexts;
+        let option = vcs_contexts[0].primes_mapping_table.p_table[0].clone();
+        let other_prime = vcs_contexts[0].primes_mapping_table.p_table[1].encoded_voting_option;
+        let mut result = VerificationResult::new();
+        verify_encoding_consistency(&ee_context, &mut result);
+        assert!(result.is_ok().unwrap());
+        let vcs_contexts = &mut ee_context
+            .election_event_context
+            .verification_card_set_contexts;
+        let e = vcs_contexts[1]
+            .primes_mapping_table
+            .p_table
+            .iter_mut()
+            .find(|e| e.actual_voting_option == option.actual_voting_option);
+        assert!(e.is_some());
+        e.unwrap().encoded_voting_option = other_prime;
+        let mut result = VerificationResult::new();
+        verify_encoding_consistency(&ee_context, &mut result);
+        assert!(result.has_failures().unwrap());
+        assert!(result
+            .failed_items()
+            .iter()
+            .any(|f| f.id == option.actual_voting_option));
+    }
+
+    #[test]
+    fn test_options_not_in_configuration() {
+        let dir = get_verifier_dir();
+        let mut ee_context = dir.unwrap_setup().election_event_context_payload().unwrap();
+        let ids = dir
+            .unwrap_setup()
+            .election_event_configuration()
+            .unwrap()
+            .identifications()
+            .unwrap();
         let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        verify_options_in_configuration(&ee_context, &ids, &mut result);
         assert!(result.is_ok().unwrap());
+        ee_context
+            .election_event_context
+            .verification_card_set_contexts[0]
+            .primes_mapping_table
+            .p_table[0]
+            .actual_voting_option = "toto|titi".to_string();
+        let mut result = VerificationResult::new();
+        verify_options_in_configuration(&ee_context, &ids, &mut result);
+        assert_eq!(result.failures().len(), 1);
+    }
+
+    #[test]
+    fn test_options_in_verification_data() {
+        let dir = get_verifier_dir();
+        let vcs_dir = &dir.unwrap_setup().vcs_directories()[0];
+        let n = dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap()
+            .election_event_context
+            .number_of_voting_options(&vcs_dir.get_name())
+            .unwrap();
+        let mut result = VerificationResult::new();
+        verify_options_in_verification_data(vcs_dir, n, &mut result);
+        assert!(result.is_ok().unwrap());
+        let mut result = VerificationResult::new();
+        verify_options_in_verification_data(vcs_dir, n + 1, &mut result);
+        assert!(result.has_failures().unwrap());
     }
 }
@@ -1,23 +1,26 @@
 use super::super::super::result::{
-    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
+    create_verification_error, create_verification_failure, EntityKind, FailureCode,
+    VerificationEvent, VerificationResult,
 };
 use crate::{
-    config::Config,
+    batch_exponentiation::BatchExponentiation,
+    crypto_helpers::{verify_schnorr_with, SchnorrProofContext},
     data_structures::common_types::Proof,
     file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
 use rayon::prelude::*;
 use rug::Integer;
-use rust_ev_crypto_primitives::{verify_schnorr, EncryptionParameters};
+use rust_ev_crypto_primitives::EncryptionParameters;
 use std::iter::zip;
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
     let ee_context = match setup_dir.election_event_context_payload() {
         Ok(eg) => eg,
@@ -26,7 +29,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "election_event_context_payload cannot be read",
                 e
             ));
-            return;
+            return result;
         }
     };
     let setup_ppk = match setup_dir.setup_component_public_keys_payload() {
@@ -36,7 +39,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "setup_component_public_keys_payload cannot be read",
                 e
             ));
-            return;
+            return result;
         }
     };
 
@@ -48,11 +51,8 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
         let j = combined_cc_pk.node_id;
 
         // CCRj Schnorr Proofs
-        let i_aux_ccr_j = vec![
-            ee_context.election_event_context.election_event_id.clone(),
-            "GenKeysCCR".to_string(),
-            j.to_string(),
-        ];
+        let i_aux_ccr_j = SchnorrProofContext::ChoiceReturnCodes { node_id: j }
+            .i_aux(&ee_context.election_event_context.election_event_id);
         let proofs: Vec<Proof> = combined_cc_pk
             .ccrj_schnorr_proofs
             .iter()
@@ -71,11 +71,8 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
         result.append(&mut res);
 
         // CCMj Schnorr Proofs
-        let i_aux_ccm_j = vec![
-            ee_context.election_event_context.election_event_id.clone(),
-            "SetupTallyCCM".to_string(),
-            j.to_string(),
-        ];
+        let i_aux_ccm_j = SchnorrProofContext::ElectionKey { node_id: j }
+            .i_aux(&ee_context.election_event_context.election_event_id);
         let proofs: Vec<Proof> = combined_cc_pk
             .ccmj_schnorr_proofs
             .iter()
@@ -95,10 +92,8 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     }
 
     // EB proofs
-    let i_aux_eb = vec![
-        ee_context.election_event_context.election_event_id.clone(),
-        "SetupTallyEB".to_string(),
-    ];
+    let i_aux_eb = SchnorrProofContext::ElectoralBoard
+        .i_aux(&ee_context.election_event_context.election_event_id);
     let proofs: Vec<Proof> = setup_ppk
         .setup_component_public_keys
         .electoral_board_schnorr_proofs
@@ -117,6 +112,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
         &None,
     );
     result.append(&mut res);
+    result
 }
 
 fn run_verify_schnorr_proofs(
@@ -134,11 +130,12 @@ fn run_verify_schnorr_proofs(
             "The length of pks and pis is not the same for {proof_name}"
         )));
     } else {
+        let exp = BatchExponentiation::new(eg);
         let failures: Vec<Option<VerificationEvent>> = zip(pks, pis)
             .enumerate()
             .par_bridge()
             .map(|(i, (pk, pi))| {
-                run_verify_schnorr_proof(eg, pi, pk, i_aux, test_name, proof_name, i, node)
+                run_verify_schnorr_proof(&exp, eg, pi, pk, i_aux, test_name, proof_name, i, node)
             })
             .collect();
         failures.into_iter().for_each(|o| {
@@ -152,6 +149,7 @@ fn run_verify_schnorr_proofs(
 
 #[allow(clippy::too_many_arguments)]
 fn run_verify_schnorr_proof(
+    exp: &BatchExponentiation,
     eg: &EncryptionParameters,
     schnorr: &Proof,
     y: &Integer,
@@ -165,11 +163,9 @@ fn run_verify_schnorr_proof(
         "Verification {} at pos {} for cc {:?}",
         test_name, pos, node
     );
-    match verify_schnorr(eg, schnorr.as_tuple(), y, i_aux) {
+    match verify_schnorr_with(exp, eg, schnorr.as_tuple(), y, i_aux) {
         Err(e) => {
-            return Some(VerificationEvent::Failure {
-                source: anyhow::anyhow!(e),
-            })
+            return Some(VerificationEvent::new_failure(e).with_code(FailureCode::ProofInvalid))
         }
         Ok(b) => {
             if !b {
@@ -177,10 +173,15 @@ fn run_verify_schnorr_proof(
                     "{}: Verifiy {} Schnorr proofs not ok at pos {}",
                     test_name, proof_name, pos
                 );
-                if node.is_some() {
-                    text = format!("{} for node {}", text, node.unwrap());
+                if let Some(j) = node {
+                    text = format!("{} for node {}", text, j);
                 }
-                return Some(create_verification_failure!(text));
+                let failure =
+                    create_verification_failure!(text).with_code(FailureCode::ProofInvalid);
+                return Some(match node {
+                    Some(j) => failure.with_entity(EntityKind::ControlComponent, j),
+                    None => failure,
+                });
             }
         }
     }
@@ -195,8 +196,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
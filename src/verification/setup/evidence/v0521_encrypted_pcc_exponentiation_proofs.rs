@@ -1,9 +1,12 @@
 use super::super::super::result::{
-    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
-    VerificationResultTrait,
+    create_verification_error, create_verification_failure, EntityKind, FailureCode,
+    VerificationEvent, VerificationResult, VerificationResultTrait,
 };
 use crate::{
-    config::Config,
+    batch_exponentiation::BatchExponentiation,
+    crypto_helpers::{
+        exponentiation_pcc_bases_and_images, exponentiation_pcc_i_aux, verify_exponentiation_with,
+    },
     data_structures::{
         setup::{
             control_component_code_shares_payload::ControlComponentCodeShare,
@@ -15,16 +18,19 @@ use crate::{
         setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
         VerificationDirectoryTrait,
     },
+    verification::VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
 use rayon::prelude::*;
-use rust_ev_crypto_primitives::{verify_exponentiation, EncryptionParameters};
+use rust_ev_crypto_primitives::EncryptionParameters;
 use std::iter::zip;
 
 /// Context data according to the specifications
 struct Context<'a> {
     eg: &'a EncryptionParameters,
+    /// Exponentiations in the group of `eg`
+    exp: &'a BatchExponentiation,
     node_id: &'a usize,
     ee_id: &'a String,
     vcs_id: &'a String,
@@ -35,9 +41,9 @@ struct Context<'a> {
 
 pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
     let setup_dir = dir.unwrap_setup();
 
     // Read ee context for the context of the algorithm
@@ -48,7 +54,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 "election_event_context_payload cannot be read",
                 e
             ));
-            return;
+            return result;
         }
     };
 
@@ -71,6 +77,8 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                 vcs_dir.get_name(),
                 chunk_id
             );
+            let chunk_name = format!("{}/{}", vcs_dir.get_name(), chunk_id);
+            let mut chunk_result = VerificationResult::new();
             match setup_verification_data_payload_result {
                 Ok(setup_verification_data_payload) => {
                     let vcs_id = &setup_verification_data_payload.verification_card_set_id;
@@ -78,10 +86,11 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                     let vcs_context = match ee_context.find_verification_card_set_context(vcs_id) {
                         Some(c) => c,
                         None => {
-                            result.push(create_verification_error!(format!(
+                            chunk_result.push(create_verification_error!(format!(
                                 "vcs id {} not found in election_event_context_payload",
                                 vcs_id
                             )));
+                            result.append_sub_result("chunk", &chunk_name, &mut chunk_result);
                             break;
                         }
                     };
@@ -91,11 +100,10 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                     if &vcs_id_for_sum_vcs != vcs_id {
                         // vcs_id changed
                         if !vcs_id_for_sum_vcs.is_empty()
-                            && current_sum_vcs
-                                != ee_context
-                                    .find_verification_card_set_context(&vcs_id_for_sum_vcs)
-                                    .unwrap()
-                                    .number_of_voters()
+                            && ee_context
+                                .find_verification_card_set_context(&vcs_id_for_sum_vcs)
+                                .map(|c| c.number_of_voters())
+                                != Some(current_sum_vcs)
                         {
                             result.push(create_verification_failure!(
                                 format!("Number of vcs {} for vcs id {} not the same as in the chunks of setup_verification_data",
@@ -113,9 +121,19 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                         .control_component_code_shares_payload_group()
                         .get_file_with_number(chunk_id)
                         .get_data()
-                    {
-                        Ok(s) => {
-                            let cc_shares = s.control_component_code_shares_payload().unwrap();
+                        .and_then(|d| {
+                            d.control_component_code_shares_payload()
+                                .cloned()
+                                .ok_or_else(|| {
+                                    anyhow!(
+                                        "The data is not a control_component_code_shares_payload"
+                                    )
+                                })
+                        }) {
+                        Ok(cc_shares) => {
+                            let exp = BatchExponentiation::new(
+                                &setup_verification_data_payload.encryption_group,
+                            );
                             // For each CC (1 to 4)
                             let mut res_cc: Vec<VerificationResult> = (1usize..=4usize)
                                 .par_bridge()
@@ -127,6 +145,7 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                                             let context = Context {
                                                 eg: &setup_verification_data_payload
                                                     .encryption_group,
+                                                exp: &exp,
                                                 node_id: &j,
                                                 ee_id: &setup_verification_data_payload
                                                     .election_event_id,
@@ -155,24 +174,26 @@ pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
                                 })
                                 .collect();
                             for r in res_cc.iter_mut() {
-                                result.append(r);
+                                chunk_result.append(r);
                             }
                         }
-                        Err(e) => result.push(create_verification_error!(
+                        Err(e) => chunk_result.push(create_verification_error!(
                             format!("{} cannot be read", cc_share_chunk_name),
                             e
                         )),
                     }
                 }
                 Err(e) => {
-                    result.push(create_verification_error!(
+                    chunk_result.push(create_verification_error!(
                         format!("{} cannot be read", setup_verif_data_chunk_name),
                         e
                     ));
                 }
             }
+            result.append_sub_result("chunk", &chunk_name, &mut chunk_result);
         }
     }
+    result
 }
 
 /// Supporting algorithm
@@ -209,7 +230,17 @@ fn verify_encrypted_pccexponentiation_proofs(
                 )
             })
             .collect();
-        for fs in failures.iter_mut() {
+        for (vc_id, fs) in zip(verification_card_ids.iter(), failures.iter_mut()) {
+            if let Some(f) = fs.first() {
+                result.push_failed_item(
+                    "verification_card",
+                    vc_id,
+                    &format!(
+                        "{} (vcs {}, chunk {}, node {})",
+                        f, context.vcs_id, context.chunk_id, context.node_id
+                    ),
+                );
+            }
             result.failures_mut().append(fs);
         }
     }
@@ -248,52 +279,42 @@ fn verify_encrypted_pccexponentiation_proofs_for_one_vc(
         ],
         &format!("for chunk {} and voting card {}", context.chunk_id, vc_id),
     ) {
-        let mut gs = setup_verif
-            .encrypted_hashed_squared_partial_choice_return_codes
-            .phis
-            .clone();
-        gs.insert(
-            0,
-            setup_verif
-                .encrypted_hashed_squared_partial_choice_return_codes
-                .gamma
-                .clone(),
-        );
-        gs.insert(0, context.eg.g().clone());
-        let mut ys = cc_code_share
-            .exponentiated_encrypted_partial_choice_return_codes
-            .phis
-            .clone();
-        ys.insert(
-            0,
-            cc_code_share
-                .exponentiated_encrypted_partial_choice_return_codes
-                .gamma
-                .clone(),
-        );
-        ys.insert(
-            0,
-            cc_code_share.voter_choice_return_code_generation_public_key[0].clone(),
+        let (gs, ys) = exponentiation_pcc_bases_and_images(
+            context.eg,
+            &setup_verif.encrypted_hashed_squared_partial_choice_return_codes,
+            &cc_code_share.voter_choice_return_code_generation_public_key[0],
+            &cc_code_share.exponentiated_encrypted_partial_choice_return_codes,
         );
-        let i_aux = vec![
-            context.ee_id.clone(),
-            vc_id.clone(),
-            "GenEncLongCodeShares".to_string(),
-            context.node_id.to_string(),
-        ];
+        let i_aux = exponentiation_pcc_i_aux(context.ee_id, vc_id, *context.node_id);
         let pi_exp_pcc_j = cc_code_share
             .encrypted_partial_choice_return_code_exponentiation_proof
             .clone();
-        match verify_exponentiation(context.eg, &gs, &ys, pi_exp_pcc_j.as_tuple(), &i_aux) {
-            Err(e) => failures.push(VerificationEvent::Failure {
-                source: anyhow::anyhow!(e),
-            }),
+        match verify_exponentiation_with(
+            context.exp,
+            context.eg,
+            &gs,
+            &ys,
+            pi_exp_pcc_j.as_tuple(),
+            &i_aux,
+        ) {
+            Err(e) => failures.push(
+                VerificationEvent::new_failure(e)
+                    .with_code(FailureCode::ProofInvalid)
+                    .with_chunk(*context.chunk_id),
+            ),
             Ok(b) => {
                 if !b {
-                    failures.push(create_verification_failure!(format!(
-                        "Failure verifying proofs for voting card id {} in chunk {} for node {}",
-                        vc_id, context.chunk_id, context.node_id
-                    )))
+                    failures.push(
+                        create_verification_failure!(format!(
+                            "Failure verifying proofs for voting card id {} in chunk {} for node {}",
+                            vc_id, context.chunk_id, context.node_id
+                        ))
+                        .with_code(FailureCode::ProofInvalid)
+                        .with_chunk(*context.chunk_id)
+                        .with_entity(EntityKind::VerificationCardSet, context.vcs_id)
+                        .with_entity(EntityKind::VerificationCard, vc_id)
+                        .with_entity(EntityKind::ControlComponent, context.node_id),
+                    )
                 }
             }
         }
@@ -332,8 +353,7 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_verification(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
@@ -0,0 +1,198 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
+};
+use crate::{
+    crypto_helpers::Seed,
+    file_structure::{setup_directory::SetupDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use chrono::NaiveDate;
+use log::{debug, warn};
+
+/// The seed is derived from the election event: the canton is `canton` and the date is at most
+/// `tolerance_days` before or after `contest_date`
+///
+/// The event (type and index) is not given in the configuration and is not verified
+fn is_seed_bound(seed: &Seed, canton: &str, contest_date: &NaiveDate, tolerance_days: u32) -> bool {
+    seed.canton == canton
+        && (seed.date - *contest_date).num_days().abs() <= i64::from(tolerance_days)
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    let ee_context_payload = match setup_dir.election_event_context_payload() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "election_event_context_payload cannot be read",
+                e
+            ));
+            return result;
+        }
+    };
+    let contest_date = match setup_dir
+        .election_event_configuration()
+        .and_then(|c| c.contest_date())
+    {
+        Ok(d) => d,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "The contest date of the configuration cannot be read",
+                e
+            ));
+            return result;
+        }
+    };
+    let seed = match Seed::try_from(ee_context_payload.seed.as_str()) {
+        Ok(s) => s,
+        Err(e) => {
+            result.push(create_verification_failure!(
+                "The seed does not follow the derivation rule",
+                e
+            ));
+            return result;
+        }
+    };
+    let strictness = context.config().strictness();
+    let canton = match strictness.seed_canton.is_empty() {
+        true => {
+            warn!(
+                "No canton in the strictness profile: the canton {} of the seed is not verified",
+                seed.canton
+            );
+            seed.canton.as_str()
+        }
+        false => strictness.seed_canton.as_str(),
+    };
+    let tolerance_days = strictness.seed_date_tolerance_days;
+    if !is_seed_bound(&seed, canton, &contest_date, tolerance_days) {
+        result.push(create_verification_failure!(format!(
+            "The seed {} of the election event {} is not derived from the election event: {} expected (canton {}, contest date {} of the configuration, tolerance {} days)",
+            ee_context_payload.seed,
+            ee_context_payload.election_event_context.election_event_id,
+            Seed::derive(canton, &contest_date, &seed.event),
+            canton,
+            contest_date,
+            tolerance_days
+        )))
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::super::super::result::VerificationResultTrait, *};
+    use crate::config::test::{test_dataset_setup_path, CONFIG_TEST};
+    use crate::{
+        config::{Config, StrictnessProfile},
+        file_structure::mock::MockVerificationDirectory,
+        verification::VerificationPeriod,
+    };
+    use chrono::Days;
+
+    /// Contest date of the configuration of the test dataset
+    fn contest_date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2027, 11, 25).unwrap()
+    }
+
+    /// Mock directory with the given seed
+    fn get_mock_verifier_dir(seed: &str) -> MockVerificationDirectory {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let mut eec = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        eec.seed = seed.to_string();
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Ok(&eec));
+        mock_dir
+    }
+
+    fn config_with_profile(profile: StrictnessProfile) -> &'static Config {
+        let config: &'static Config = Box::leak(Box::new(Config::new(".")));
+        config.set_strictness(profile).unwrap();
+        config
+    }
+
+    #[test]
+    fn test_is_seed_bound() {
+        let seed = Seed::try_from("NE_20271124_TT04").unwrap();
+        assert!(!is_seed_bound(&seed, "NE", &contest_date(), 0));
+        assert!(is_seed_bound(&seed, "NE", &contest_date(), 1));
+        assert!(!is_seed_bound(&seed, "BE", &contest_date(), 1));
+        assert!(is_seed_bound(&seed, "NE", &contest_date(), u32::MAX));
+        let seed = Seed::try_from("NE_20271126_TT04").unwrap();
+        assert!(is_seed_bound(&seed, "NE", &contest_date(), 1));
+    }
+
+    #[test]
+    fn test_ok() {
+        let dir = get_mock_verifier_dir("NE_20271125_TT04");
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_seed_of_dataset_not_bound() {
+        // The contest date of the anonymized test dataset was moved, without changing the seed
+        let dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Setup, &test_dataset_setup_path());
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+
+    #[test]
+    fn test_wrong_seed() {
+        let dir = get_mock_verifier_dir("NE_2023_TT04");
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+
+    #[test]
+    fn test_seed_of_other_election_event() {
+        let dir = get_mock_verifier_dir("NE_20261125_TT04");
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+
+    #[test]
+    fn test_seed_canton() {
+        let dir = get_mock_verifier_dir("NE_20271125_TT04");
+        let config = config_with_profile(StrictnessProfile {
+            seed_canton: "BE".to_string(),
+            ..Default::default()
+        });
+        let result = fn_verification(&dir, &VerificationContext::new(config));
+        assert_eq!(result.failures().len(), 1);
+        let config = config_with_profile(StrictnessProfile {
+            seed_canton: "NE".to_string(),
+            ..Default::default()
+        });
+        let result = fn_verification(&dir, &VerificationContext::new(config));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_seed_date_tolerance() {
+        let date = contest_date() - Days::new(1);
+        let dir = get_mock_verifier_dir(&format!("NE_{}_TT04", date.format("%Y%m%d")));
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert_eq!(result.failures().len(), 1);
+        let config = config_with_profile(StrictnessProfile {
+            seed_date_tolerance_days: 1,
+            ..Default::default()
+        });
+        let result = fn_verification(&dir, &VerificationContext::new(config));
+        assert!(result.is_ok().unwrap());
+    }
+}
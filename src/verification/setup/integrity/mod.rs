@@ -1,10 +1,14 @@
 use super::super::{
-    result::{create_verification_failure, VerificationEvent, VerificationResult},
-    suite::VerificationList,
+    duplicate_chunks::verify_no_duplicate_chunks,
+    result::{create_verification_failure, FailureCode, VerificationEvent, VerificationResult},
+    suite::{SuiteBuildError, VerificationList},
+    validate_xml_file,
     verifications::Verification,
+    VerificationContext,
 };
 use crate::{
     config::Config,
+    data_structures::SchemaKind,
     file_structure::{
         setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
         VerificationDirectoryTrait,
@@ -13,168 +17,249 @@ use crate::{
 };
 use anyhow::anyhow;
 use log::debug;
+use rayon::prelude::*;
 use rust_ev_crypto_primitives::VerifyDomainTrait;
 
 pub fn get_verifications<'a>(
     metadata_list: &'a VerificationMetaDataList,
     config: &'static Config,
-) -> VerificationList<'a> {
-    VerificationList(vec![Verification::new(
-        "04.01",
-        "VerifySetupIntegrity",
-        fn_0401_verify_setup_integrity,
-        metadata_list,
-        config,
-    )
-    .unwrap()])
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
+        Verification::new(
+            "04.01",
+            "VerifySetupIntegrity",
+            fn_0401_verify_setup_integrity,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.04.01",
+            "VerifySetupXmlSchemas",
+            fn_x0401_verify_setup_xml_schemas,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.04.02",
+            "VerifySetupNoDuplicateChunks",
+            fn_x0402_verify_setup_no_duplicate_chunks,
+            metadata_list,
+            config,
+        ),
+    ])
 }
 
-fn validate_vcs_dir<V: VCSDirectoryTrait>(dir: &V, result: &mut VerificationResult) {
-    match dir.setup_component_tally_data_payload() {
-        Ok(d) => {
-            for e in d.verifiy_domain() {
-                result.push(create_verification_failure!(
-                    format!(
-                        "Error verifying domain for {}/setup_component_tally_data_payload",
-                        dir.get_name()
-                    ),
-                    e
-                ))
-            }
-        }
-        Err(e) => result.push(create_verification_failure!(
-            format!(
-                "{}/setup_component_tally_data_payload has wrong format",
-                dir.get_name()
-            ),
-            e
-        )),
+/// Maximal number of domain errors of a file reported as failures
+///
+/// The other domain errors of the file are only counted, so that the report remains readable
+/// for a file with systematic errors
+const MAX_DOMAIN_FAILURES_PER_FILE: usize = 5;
+
+/// Failures of the verification of the domain of the payload in the file `name`
+fn domain_failures<T: VerifyDomainTrait>(payload: &T, name: &str) -> Vec<VerificationEvent> {
+    payload
+        .verifiy_domain()
+        .into_iter()
+        .map(|e| {
+            create_verification_failure!(format!("Error verifying domain for {}", name), e)
+                .with_code(FailureCode::DomainCheck)
+                .with_file(name)
+        })
+        .collect()
+}
+
+/// Failure if the file `name` cannot be read
+fn read_failure(name: &str, e: anyhow::Error) -> VerificationEvent {
+    create_verification_failure!(format!("{} has wrong format", name), e)
+        .with_code(FailureCode::ReadError)
+        .with_file(name)
+}
+
+/// Push the failures of the file `name` to the result
+///
+/// Only the first [MAX_DOMAIN_FAILURES_PER_FILE] failures are pushed. A last failure gives the
+/// number of the failures not reported.
+fn push_file_failures(
+    name: &str,
+    failures: Vec<VerificationEvent>,
+    result: &mut VerificationResult,
+) {
+    if failures.is_empty() {
+        return;
     }
-    for (i, f) in dir.control_component_code_shares_payload_iter() {
-        match f {
-            Ok(d) => {
-                for (j, p) in d.iter().enumerate() {
-                    for e in p.verifiy_domain() {
-                        result.push(create_verification_failure!(
-                            format!(
-                                "Error verifying domain for {}/control_component_public_keys_payload.{} at entry {}",
-                                dir.get_name(),
-                                i, j
-                            ),
-                            e
-                        ))
-                    }
-                }
-            }
-            Err(e) => result.push(create_verification_failure!(
-                format!(
-                    "{}/control_component_code_shares_payload.{} has wrong format",
-                    dir.get_name(),
-                    i
-                ),
-                e
-            )),
-        }
+    let nb = failures.len();
+    result.push_failed_item("file", name, &format!("{} domain error(s)", nb));
+    for f in failures.into_iter().take(MAX_DOMAIN_FAILURES_PER_FILE) {
+        result.push(f);
     }
-    for (i, f) in dir.setup_component_verification_data_payload_iter() {
-        match f {
-            Ok(d) => {
-                for e in d.verifiy_domain() {
-                    result.push(create_verification_failure!(
-                        format!(
-                            "Error verifying domain for {}/setup_component_verification_data_payload.{}",
-                            dir.get_name(),
-                            i
-                        ),
-                        e
-                    ))
-                }
-            }
-            Err(e) => result.push(create_verification_failure!(
-                format!(
-                    "{}/setup_component_verification_data_payload.{} has wrong format",
-                    dir.get_name(),
-                    i
-                ),
-                e
-            )),
-        }
+    if nb > MAX_DOMAIN_FAILURES_PER_FILE {
+        result.push(
+            create_verification_failure!(format!(
+                "{} further domain errors for {} are not reported ({} in total)",
+                nb - MAX_DOMAIN_FAILURES_PER_FILE,
+                name,
+                nb
+            ))
+            .with_code(FailureCode::DomainCheck)
+            .with_file(name),
+        );
     }
 }
 
-fn fn_0401_verify_setup_integrity<D: VerificationDirectoryTrait>(
-    dir: &D,
-    _config: &'static Config,
+/// Verify the domain of the payloads of a file group
+///
+/// The files are read in batches of the size of the thread pool and the files of a batch are
+/// verified in parallel, so that only one batch is in memory. `name` gives the name of the file
+/// with the given number and `failures` the failures of the payload.
+fn verify_file_group_domain<T, I, N, F>(
+    iter: I,
+    name: N,
+    failures: F,
     result: &mut VerificationResult,
-) {
-    let setup_dir = dir.unwrap_setup();
-    match setup_dir.election_event_context_payload() {
-        Ok(d) => {
-            for e in d.verifiy_domain() {
-                result.push(create_verification_failure!(
-                    "Error verifying domain for election_event_context_payload",
-                    e
-                ))
-            }
+) where
+    T: Send,
+    I: Iterator<Item = (usize, anyhow::Result<T>)>,
+    N: Fn(usize) -> String,
+    F: Fn(&T, &str) -> Vec<VerificationEvent> + Sync,
+{
+    let batch_size = rayon::current_num_threads();
+    let mut iter = iter.map(|(i, p)| (name(i), p));
+    loop {
+        let batch: Vec<(String, anyhow::Result<T>)> = iter.by_ref().take(batch_size).collect();
+        if batch.is_empty() {
+            break;
         }
-        Err(e) => result.push(create_verification_failure!(
-            "election_event_context_payload has wrong format",
-            e
-        )),
-    }
-    match setup_dir.setup_component_public_keys_payload() {
-        Ok(d) => {
-            for e in d.verifiy_domain() {
-                result.push(create_verification_failure!(
-                    "Error verifying domain for setup_component_public_keys_payload",
-                    e
-                ))
-            }
+        let file_failures: Vec<(String, Vec<VerificationEvent>)> = batch
+            .into_par_iter()
+            .map(|(n, p)| {
+                let fs = match p {
+                    Ok(d) => failures(&d, &n),
+                    Err(e) => vec![read_failure(&n, e)],
+                };
+                (n, fs)
+            })
+            .collect();
+        for (n, fs) in file_failures {
+            push_file_failures(&n, fs, result);
         }
-        Err(e) => result.push(create_verification_failure!(
-            "setup_component_public_keys_payload has wrong format",
-            e
-        )),
     }
-    match setup_dir.election_event_configuration() {
-        Ok(d) => {
-            for e in d.verifiy_domain() {
-                result.push(create_verification_failure!(
-                    "Error verifying domain for election_event_configuration",
-                    e
-                ))
-            }
-        }
-        Err(e) => result.push(create_verification_failure!(
-            "election_event_configuration has wrong format",
-            e
-        )),
+}
+
+/// Verify the domain of a single file
+fn verify_file_domain<T: VerifyDomainTrait>(
+    payload: anyhow::Result<Box<T>>,
+    name: &str,
+    result: &mut VerificationResult,
+) {
+    match payload {
+        Ok(d) => push_file_failures(name, domain_failures(d.as_ref(), name), result),
+        Err(e) => push_file_failures(name, vec![read_failure(name, e)], result),
     }
-    for (i, f) in setup_dir.control_component_public_keys_payload_iter() {
-        match f {
-            Ok(d) => {
-                for e in d.verifiy_domain() {
-                    result.push(create_verification_failure!(
-                        format!(
-                            "Error verifying domain for control_component_public_keys_payload.{}",
-                            i
-                        ),
-                        e
-                    ))
-                }
-            }
-            Err(e) => result.push(create_verification_failure!(
-                format!(
-                    "control_component_public_keys_payload.{} has wrong format",
-                    i
-                ),
-                e
-            )),
-        }
+}
+
+fn validate_vcs_dir<V: VCSDirectoryTrait>(dir: &V, result: &mut VerificationResult) {
+    let vcs_name = dir.get_name();
+    debug!("Verify the domain of the payloads of {}", vcs_name);
+    verify_file_domain(
+        dir.setup_component_tally_data_payload(),
+        &format!("{}/setup_component_tally_data_payload", vcs_name),
+        result,
+    );
+    verify_file_group_domain(
+        dir.control_component_code_shares_payload_iter(),
+        |i| format!("{}/control_component_code_shares_payload.{}", vcs_name, i),
+        |d, name| {
+            d.iter()
+                .enumerate()
+                .flat_map(|(j, p)| domain_failures(p, &format!("{} at entry {}", name, j)))
+                .collect()
+        },
+        result,
+    );
+    verify_file_group_domain(
+        dir.setup_component_verification_data_payload_iter(),
+        |i| {
+            format!(
+                "{}/setup_component_verification_data_payload.{}",
+                vcs_name, i
+            )
+        },
+        |d, name| domain_failures(d.as_ref(), name),
+        result,
+    );
+}
+
+fn fn_0401_verify_setup_integrity<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    verify_file_domain(
+        setup_dir.election_event_context_payload(),
+        "election_event_context_payload",
+        &mut result,
+    );
+    verify_file_domain(
+        setup_dir.setup_component_public_keys_payload(),
+        "setup_component_public_keys_payload",
+        &mut result,
+    );
+    verify_file_domain(
+        setup_dir.election_event_configuration(),
+        "election_event_configuration",
+        &mut result,
+    );
+    verify_file_group_domain(
+        setup_dir.control_component_public_keys_payload_iter(),
+        |i| format!("control_component_public_keys_payload.{}", i),
+        |d, name| domain_failures(d.as_ref(), name),
+        &mut result,
+    );
+    for d in setup_dir.vcs_directories().iter() {
+        validate_vcs_dir(d, &mut result);
     }
+    result
+}
+
+fn fn_x0401_verify_setup_xml_schemas<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    validate_xml_file(
+        dir.unwrap_setup().election_event_configuration_file(),
+        &SchemaKind::Config,
+        "configuration-anonymized.xml",
+        &mut result,
+    );
+    result
+}
+
+fn fn_x0402_verify_setup_no_duplicate_chunks<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    verify_no_duplicate_chunks(
+        setup_dir.control_component_public_keys_payload_group(),
+        "control_component_public_keys_payload",
+        &mut result,
+    );
     for d in setup_dir.vcs_directories().iter() {
-        validate_vcs_dir(d, result);
+        verify_no_duplicate_chunks(
+            d.setup_component_verification_data_payload_group(),
+            &format!("{}/setup_component_verification_data_payload", d.get_name()),
+            &mut result,
+        );
+        verify_no_duplicate_chunks(
+            d.control_component_code_shares_payload_group(),
+            &format!("{}/control_component_code_shares_payload", d.get_name()),
+            &mut result,
+        );
     }
+    result
 }
 
 #[cfg(test)]
@@ -185,9 +270,53 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0401_verify_setup_integrity(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_0401_verify_setup_integrity(&dir, &VerificationContext::new(&CONFIG_TEST));
         println!("{:?}", result);
         assert!(result.is_ok().unwrap());
     }
+
+    #[test]
+    fn test_push_file_failures() {
+        let mut result = VerificationResult::new();
+        push_file_failures("file_ok", vec![], &mut result);
+        assert!(result.failures().is_empty());
+        let failures = (0..MAX_DOMAIN_FAILURES_PER_FILE + 3)
+            .map(|i| create_verification_failure!(format!("domain error {}", i)))
+            .collect();
+        push_file_failures("file_1", failures, &mut result);
+        push_file_failures(
+            "file_2",
+            vec![create_verification_failure!("domain error")],
+            &mut result,
+        );
+        assert_eq!(result.failures().len(), MAX_DOMAIN_FAILURES_PER_FILE + 2);
+        assert!(result.failures()[MAX_DOMAIN_FAILURES_PER_FILE]
+            .to_string()
+            .starts_with("3 further domain errors for file_1"));
+        assert_eq!(result.failed_items().len(), 2);
+        assert_eq!(result.failed_items()[0].id, "file_1");
+        assert_eq!(
+            result.failed_items()[0].reason,
+            format!("{} domain error(s)", MAX_DOMAIN_FAILURES_PER_FILE + 3)
+        );
+    }
+
+    #[test]
+    fn test_x0401_ok() {
+        let dir = get_verifier_dir();
+        let result =
+            fn_x0401_verify_setup_xml_schemas(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.errors().is_empty(), "{:?}", result.errors());
+        assert!(result.failures().is_empty(), "{:?}", result.failures());
+    }
+
+    #[test]
+    fn test_x0402_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_x0402_verify_setup_no_duplicate_chunks(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
 }
@@ -1,28 +1,26 @@
 //! Module implementing the verifications for setup
 mod authenticity;
-mod completness;
+pub(super) mod completness;
 mod consistency;
 mod evidence;
 mod integrity;
 
-use super::{meta_data::VerificationMetaDataList, suite::VerificationList};
+use super::{
+    meta_data::VerificationMetaDataList,
+    suite::{SuiteBuildError, VerificationList},
+};
 use crate::config::Config;
 
 /// Collect the verifications of the submodules
 pub fn get_verifications<'a>(
     metadata_list: &'a VerificationMetaDataList,
     config: &'static Config,
-) -> VerificationList<'a> {
-    let mut res = VerificationList(vec![]);
-    res.0
-        .append(&mut authenticity::get_verifications(metadata_list, config).0);
-    res.0
-        .append(&mut completness::get_verifications(metadata_list, config).0);
-    res.0
-        .append(&mut consistency::get_verifications(metadata_list, config).0);
-    res.0
-        .append(&mut evidence::get_verifications(metadata_list, config).0);
-    res.0
-        .append(&mut integrity::get_verifications(metadata_list, config).0);
-    res
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::concat(vec![
+        authenticity::get_verifications(metadata_list, config),
+        completness::get_verifications(metadata_list, config),
+        consistency::get_verifications(metadata_list, config),
+        evidence::get_verifications(metadata_list, config),
+        integrity::get_verifications(metadata_list, config),
+    ])
 }
@@ -1,57 +1,496 @@
 //! Module implementing the suite of verifications
 
 use super::{
-    meta_data::VerificationMetaDataList, setup::get_verifications as get_verifications_setup,
-    tally::get_verifications as get_verifications_tally, verifications::Verification,
+    meta_data::{
+        MetaDataEntry, MetaDataQuery, VerificationImpact, VerificationMetaDataList,
+        VerificationScope,
+    },
+    query::{ResultQuery, ResultStatus, ResultView},
+    result::{VerificationResult, VerificationResultTrait},
+    setup::get_verifications as get_verifications_setup,
+    tally::get_verifications as get_verifications_tally,
+    verifications::Verification,
     VerificationCategory, VerificationPeriod,
 };
-use crate::{config::Config, file_structure::VerificationDirectory};
+use crate::{
+    config::Config,
+    data_structures::dataset_version::DatasetVersion,
+    file_structure::{
+        data_source::{local_source, DataSource},
+        VerificationDirectory,
+    },
+    human_format::HumanDuration,
+};
+use anyhow::anyhow;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::{fmt::Display, path::Path, sync::Arc};
+
+/// Number of the slowest verifications kept in [VerificationSuiteResult]
+const NB_SLOWEST_VERIFICATIONS: usize = 5;
+
+/// Directories to verify in a run of the suite
+///
+/// The ballot box selection applies only to the verifications of scope
+/// [VerificationScope::BallotBox]. The other verifications aggregate or compare data over all
+/// the ballot boxes (e.g. the total of the cast votes, the completeness) and always verify all
+/// the ballot boxes.
+pub struct SuiteDirectories {
+    all: VerificationDirectory,
+    selected: Option<VerificationDirectory>,
+}
+
+impl SuiteDirectories {
+    /// The directory to verify for the verifications of the given scope
+    pub fn get(&self, scope: VerificationScope) -> &VerificationDirectory {
+        match (scope, &self.selected) {
+            (VerificationScope::BallotBox, Some(d)) => d,
+            _ => &self.all,
+        }
+    }
+}
+
+/// Get the list of the verifications that are implemented for the period
+pub fn get_implemented_verifications_id(
+    period: VerificationPeriod,
+    config: &'static Config,
+) -> anyhow::Result<Vec<String>> {
+    let metadata = config.verification_metadata()?;
+    Ok(VerificationSuite::new(&period, metadata, &[], config)?.collect_id())
+}
 
 /// Get the list of the verifications that are not implemented yet
 #[allow(dead_code)]
 pub fn get_not_implemented_verifications_id(
     period: VerificationPeriod,
     config: &'static Config,
-) -> Vec<String> {
-    let metadata =
-        VerificationMetaDataList::load_period(config.get_verification_list_str(), &period).unwrap();
-    let all_id = metadata.id_list();
-    let verifs_id = VerificationSuite::new(&period, &metadata, &[], config).collect_id();
+) -> anyhow::Result<Vec<String>> {
+    let metadata = config.verification_metadata()?;
+    let all_id = metadata.id_list_for_period(&period);
+    let verifs_id = get_implemented_verifications_id(period, config)?;
     let mut diff: Vec<String> = all_id
         .iter()
         .filter(|&x| !verifs_id.contains(x))
         .cloned()
         .collect();
     diff.sort();
-    diff
+    Ok(diff)
+}
+
+/// Query the metadata of the verifications of both periods (see [MetaDataQuery])
+///
+/// The entries give the status of the verifications, so that a front-end can present the list of
+/// the verifications that can be selected for a run
+pub fn query_verification_metadata(
+    query: &MetaDataQuery,
+    exclusion: &[String],
+    config: &'static Config,
+) -> anyhow::Result<Vec<MetaDataEntry>> {
+    let mut implemented = get_implemented_verifications_id(VerificationPeriod::Setup, config)?;
+    implemented.append(&mut get_implemented_verifications_id(
+        VerificationPeriod::Tally,
+        config,
+    )?);
+    Ok(config
+        .verification_metadata()?
+        .query(query, &implemented, exclusion))
 }
 
 /// Enum for the suite of verifications
 pub struct VerificationSuite<'a> {
     period: VerificationPeriod,
     pub list: Box<VerificationList<'a>>,
+    metadata_list: &'a VerificationMetaDataList,
     exclusion: Vec<String>,
+    not_implemented: Vec<String>,
+    vcs_selection: Option<Vec<String>>,
+    bb_selection: Option<Vec<String>>,
+    execution_policy: ExecutionPolicy,
+    data_source: Arc<dyn DataSource>,
+    dataset_version: Option<DatasetVersion>,
+}
+
+/// Duration of a verification, for the slowest verifications of a [VerificationSuiteResult]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerificationDuration {
+    pub id: String,
+    pub duration: HumanDuration,
+}
+
+/// Summary statistics of the results of a suite of verifications
+///
+/// The statistics are computed from the suite (see [VerificationSuite::suite_result]) or from the
+/// verifications of a report, adding the verifications with [VerificationSuiteResult::push]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VerificationSuiteResult {
+    /// Number of verifications ok
+    pub passed: usize,
+    /// Number of verifications with failures, but without error
+    pub failed: usize,
+    /// Number of verifications with errors
+    pub errored: usize,
+    /// Number of verifications not run (or running)
+    pub not_run: usize,
+    /// Number of verifications excluded from the run
+    pub excluded: usize,
+    /// Number of verifications of the period that are not implemented
+    pub not_implemented: usize,
+    /// Sum of the durations of the verifications run. It is longer than the duration of the run
+    /// if the verifications run in parallel
+    pub total_duration: HumanDuration,
+    /// Slowest verifications (at most 5), sorted by decreasing duration
+    pub slowest: Vec<VerificationDuration>,
+    /// Number of failures per category of verification
+    pub failures_by_category: BTreeMap<String, usize>,
+}
+
+impl VerificationSuiteResult {
+    /// Add the result of a verification
+    ///
+    /// `duration` is `None` if the verification is not run
+    pub fn push(
+        &mut self,
+        id: &str,
+        category: &str,
+        status: ResultStatus,
+        duration: Option<HumanDuration>,
+        nb_failures: usize,
+    ) {
+        match status {
+            ResultStatus::Ok => self.passed += 1,
+            ResultStatus::Failures => self.failed += 1,
+            ResultStatus::Errors => self.errored += 1,
+            ResultStatus::NotRun => self.not_run += 1,
+        }
+        *self
+            .failures_by_category
+            .entry(category.to_string())
+            .or_default() += nb_failures;
+        if let Some(d) = duration {
+            self.total_duration = HumanDuration(self.total_duration.0 + d.0);
+            let pos = self
+                .slowest
+                .iter()
+                .position(|s| s.duration < d)
+                .unwrap_or(self.slowest.len());
+            self.slowest.insert(
+                pos,
+                VerificationDuration {
+                    id: id.to_string(),
+                    duration: d,
+                },
+            );
+            self.slowest.truncate(NB_SLOWEST_VERIFICATIONS);
+        }
+    }
+
+    /// Number of verifications of the suite (without the excluded and the not implemented
+    /// verifications)
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.errored + self.not_run
+    }
+}
+
+/// Selection of the verifications to run (inclusion), by id or by category
+///
+/// A verification is selected if its id or its category is in the selection. It is used to run
+/// targeted subsets of the suite, e.g. all the authenticity verifications (see
+/// [VerificationSuite::select]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationSelection {
+    ids: Vec<String>,
+    categories: Vec<VerificationCategory>,
+}
+
+impl VerificationSelection {
+    /// New empty selection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selection from the given values. A value is a category (e.g. `authenticity`) or the id of a
+    /// verification
+    pub fn from_values(values: &[String]) -> Self {
+        values.iter().fold(Self::new(), |s, v| {
+            match VerificationCategory::try_from(v) {
+                Ok(c) => s.with_category(c),
+                Err(_) => s.with_id(v),
+            }
+        })
+    }
+
+    /// Select the verification with the id
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.ids.push(id.to_string());
+        self
+    }
+
+    /// Select the verifications of the category
+    pub fn with_category(mut self, category: VerificationCategory) -> Self {
+        self.categories.push(category);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.categories.is_empty()
+    }
+
+    /// The verification is selected
+    pub fn contains(&self, verification: &Verification<'_, VerificationDirectory>) -> bool {
+        self.ids.contains(verification.id())
+            || self
+                .categories
+                .contains(verification.meta_data().category())
+    }
+
+    /// Check the ids of the selection against the ids of the implemented verifications of the
+    /// period
+    ///
+    /// # Error
+    /// All the problems are collected: if an id is unknown, if the verification is not of the
+    /// period or if it is not implemented
+    fn check(
+        &self,
+        period: &VerificationPeriod,
+        metadata_list: &VerificationMetaDataList,
+        implemented: &[String],
+    ) -> Result<(), SuiteBuildError> {
+        let mut error = SuiteBuildError::default();
+        for id in self.ids.iter() {
+            match metadata_list.meta_data_from_id(id) {
+                None => error.push(format!(
+                    "unknown verification id {} (expected an id like 01.01 or X.10.01, or a category)",
+                    id
+                )),
+                Some(m) if m.period() != period => error.push(format!(
+                    "verification {} is a verification of the {}, not of the {}",
+                    id,
+                    m.period(),
+                    period
+                )),
+                Some(_) if !implemented.contains(id) => {
+                    error.push(format!("verification {} is not implemented", id))
+                }
+                Some(_) => (),
+            }
+        }
+        match error.is_empty() {
+            true => Ok(()),
+            false => Err(error),
+        }
+    }
+}
+
+/// Policy defining if the run continues after a verification with errors
+///
+/// Only the errors (the verification cannot be completed, e.g. a file cannot be read) stop the
+/// run with [ExecutionPolicy::FailFast]. The failures (the verification is completed with a
+/// negative result) never stop the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionPolicy {
+    /// All the verifications are run (default)
+    #[default]
+    Continue,
+    /// The verifications not started yet are not run after the first verification with
+    /// errors. The verifications running at that time are completed
+    FailFast,
+}
+
+impl ExecutionPolicy {
+    /// The run must stop after the verification with the given result
+    pub fn must_stop(&self, result: &VerificationResult) -> bool {
+        match self {
+            ExecutionPolicy::Continue => false,
+            ExecutionPolicy::FailFast => result.has_errors().unwrap_or(false),
+        }
+    }
 }
 
 /// List of verifications
 pub struct VerificationList<'a>(pub Vec<Verification<'a, VerificationDirectory>>);
 
+/// Problems found during the construction of the suite of verifications
+///
+/// All the problems are collected, so that a mismatch between the implemented verifications
+/// and the metadata resource can be reported at once.
+#[derive(Debug, Default)]
+pub struct SuiteBuildError {
+    problems: Vec<String>,
+}
+
+impl SuiteBuildError {
+    /// List of the problems
+    pub fn problems(&self) -> &[String] {
+        &self.problems
+    }
+
+    /// Add a problem
+    pub fn push(&mut self, problem: String) {
+        self.problems.push(problem)
+    }
+
+    /// Add the problems of other
+    pub fn append(&mut self, other: SuiteBuildError) {
+        self.problems.extend(other.problems)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Display for SuiteBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Inconsistent list of verifications ({} problem(s)): {}",
+            self.problems.len(),
+            self.problems.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for SuiteBuildError {}
+
+impl<'a> VerificationList<'a> {
+    /// Create the list from the results of the creation of the verifications
+    ///
+    /// # Error
+    /// All the errors of the creation are collected in the [SuiteBuildError]
+    pub fn from_results(
+        verifications: Vec<anyhow::Result<Verification<'a, VerificationDirectory>>>,
+    ) -> Result<Self, SuiteBuildError> {
+        let mut res = vec![];
+        let mut error = SuiteBuildError::default();
+        for v in verifications {
+            match v {
+                Ok(v) => res.push(v),
+                Err(e) => error.push(format!("{:#}", e)),
+            }
+        }
+        match error.is_empty() {
+            true => Ok(Self(res)),
+            false => Err(error),
+        }
+    }
+
+    /// Concatenate the lists
+    ///
+    /// # Error
+    /// The problems of all the lists are collected in the [SuiteBuildError]
+    pub fn concat(lists: Vec<Result<Self, SuiteBuildError>>) -> Result<Self, SuiteBuildError> {
+        let mut res = vec![];
+        let mut error = SuiteBuildError::default();
+        for l in lists {
+            match l {
+                Ok(mut l) => res.append(&mut l.0),
+                Err(e) => error.append(e),
+            }
+        }
+        match error.is_empty() {
+            true => Ok(Self(res)),
+            false => Err(error),
+        }
+    }
+
+    /// Check the consistency of the list of verifications for the period
+    ///
+    /// # Error
+    /// All the problems are collected:
+    /// - If an id is registered more than once. Only one of them would run after a merge.
+    /// - If the metadata of a verification is not the one of its id
+    /// - If the verification is not applicable for the period (metadata of another period)
+    pub fn check(&self, period: &VerificationPeriod) -> Result<(), SuiteBuildError> {
+        let mut ids = HashSet::new();
+        let mut error = SuiteBuildError::default();
+        for v in self.0.iter() {
+            if !ids.insert(v.id()) {
+                error.push(format!("verification id {} is registered twice", v.id()))
+            }
+            if v.meta_data().id() != v.id() {
+                error.push(format!(
+                    "metadata {} of verification {} doesn't match",
+                    v.meta_data().id(),
+                    v.id()
+                ))
+            }
+            if !v.is_applicable(period) {
+                error.push(format!(
+                    "verification {} of the {} is not applicable for the {}",
+                    v.id(),
+                    v.meta_data().period(),
+                    period
+                ))
+            }
+        }
+        match error.is_empty() {
+            true => Ok(()),
+            false => Err(error),
+        }
+    }
+}
+
 impl<'a> VerificationSuite<'a> {
     /// Create a new suite
     ///
     /// The function collects all the implemented tests and remove the excluded
     /// verifications. The ids in exclusion that does not exist are ignored
+    ///
+    /// # Error
+    /// If a verification cannot be created (e.g. its metadata is missing) or if the list of
+    /// verifications is not consistent (see [VerificationList::check]). All the problems are
+    /// reported in the [SuiteBuildError]
     pub fn new(
         period: &VerificationPeriod,
         metadata_list: &'a VerificationMetaDataList,
         exclusion: &[String],
         config: &'static Config,
-    ) -> VerificationSuite<'a> {
+    ) -> Result<VerificationSuite<'a>, SuiteBuildError> {
         let mut all_verifs = match period {
             VerificationPeriod::Setup => get_verifications_setup(metadata_list, config),
 
             VerificationPeriod::Tally => get_verifications_tally(metadata_list, config),
-        };
+        }?;
+        all_verifs.check(period)?;
+        let implemented: Vec<String> = all_verifs.0.iter().map(|v| v.id().clone()).collect();
+        let mut res = Self::from_list(period, all_verifs, metadata_list, exclusion);
+        res.not_implemented = metadata_list
+            .id_list_for_period(period)
+            .into_iter()
+            .filter(|id| !implemented.contains(id))
+            .collect();
+        res.not_implemented.sort();
+        Ok(res)
+    }
+
+    /// Create a new suite with the selected verifications only (see [VerificationSelection])
+    ///
+    /// The verifications not selected are added to the excluded verifications, so that they are
+    /// listed in the report. The exclusion has priority over the selection.
+    ///
+    /// # Error
+    /// See [VerificationSuite::new]. Additionally, all the problems of the selection are reported
+    /// (unknown ids, verifications of the other period or not implemented), and it is an error if
+    /// no verification is selected
+    pub fn new_with_selection(
+        period: &VerificationPeriod,
+        metadata_list: &'a VerificationMetaDataList,
+        exclusion: &[String],
+        selection: &VerificationSelection,
+        config: &'static Config,
+    ) -> Result<VerificationSuite<'a>, SuiteBuildError> {
+        let mut res = Self::new(period, metadata_list, exclusion, config)?;
+        res.select(selection, metadata_list)?;
+        Ok(res)
+    }
+
+    /// Suite with the verifications of the list, without the excluded verifications
+    fn from_list(
+        period: &VerificationPeriod,
+        mut all_verifs: VerificationList<'a>,
+        metadata_list: &'a VerificationMetaDataList,
+        exclusion: &[String],
+    ) -> Self {
         let all_ids: Vec<String> = all_verifs.0.iter().map(|v| v.id().clone()).collect();
         all_verifs.0.retain(|x| !exclusion.contains(x.id()));
         let mut excl: Vec<String> = exclusion.to_vec();
@@ -59,7 +498,152 @@ impl<'a> VerificationSuite<'a> {
         VerificationSuite {
             period: *period,
             list: Box::new(all_verifs),
+            metadata_list,
             exclusion: excl,
+            not_implemented: vec![],
+            vcs_selection: None,
+            bb_selection: None,
+            execution_policy: ExecutionPolicy::default(),
+            data_source: local_source(),
+            dataset_version: None,
+        }
+    }
+
+    /// Keep only the selected verifications (see [VerificationSelection])
+    ///
+    /// The verifications not selected are added to the excluded verifications. An empty selection
+    /// keeps all the verifications.
+    ///
+    /// # Error
+    /// See [VerificationSuite::new_with_selection]. The suite is not changed in case of error
+    pub fn select(
+        &mut self,
+        selection: &VerificationSelection,
+        metadata_list: &VerificationMetaDataList,
+    ) -> Result<(), SuiteBuildError> {
+        if selection.is_empty() {
+            return Ok(());
+        }
+        // The excluded verifications are implemented, but not in the list anymore
+        let mut implemented = self.collect_id();
+        implemented.extend(self.exclusion.iter().cloned());
+        selection.check(&self.period, metadata_list, &implemented)?;
+        if !self.list.0.iter().any(|v| selection.contains(v)) {
+            let mut error = SuiteBuildError::default();
+            error.push(format!(
+                "no verification of the {} is selected (excluded or not implemented)",
+                self.period
+            ));
+            return Err(error);
+        }
+        let (selected, not_selected): (Vec<_>, Vec<_>) =
+            self.list.0.drain(..).partition(|v| selection.contains(v));
+        self.list.0 = selected;
+        self.exclusion
+            .extend(not_selected.iter().map(|v| v.id().clone()));
+        self.exclusion.sort();
+        Ok(())
+    }
+
+    /// Set the execution policy (see [ExecutionPolicy])
+    pub fn set_execution_policy(&mut self, policy: ExecutionPolicy) {
+        self.execution_policy = policy
+    }
+
+    /// Execution policy of the suite
+    pub fn execution_policy(&self) -> ExecutionPolicy {
+        self.execution_policy
+    }
+
+    /// The verification whose errors stopped the run, according to the execution policy
+    ///
+    /// `None` if the policy does not stop the run or if all the verifications are finished
+    pub fn stopped_by(&self) -> Option<&Verification<'a, VerificationDirectory>> {
+        if self.list.0.iter().all(|v| v.is_finished()) {
+            return None;
+        }
+        self.list
+            .0
+            .iter()
+            .find(|v| v.is_finished() && self.execution_policy.must_stop(v.result()))
+    }
+
+    /// Read the files of the dataset from the given source (per default on the disk)
+    pub fn set_data_source(&mut self, source: Arc<dyn DataSource>) {
+        self.data_source = source
+    }
+
+    /// Source of the files of the dataset
+    pub fn data_source(&self) -> &Arc<dyn DataSource> {
+        &self.data_source
+    }
+
+    /// Set the version of the dataset, given to the verifications in their context
+    pub fn set_dataset_version(&mut self, version: Option<DatasetVersion>) {
+        self.dataset_version = version
+    }
+
+    /// Version of the dataset, `None` if it is not known
+    pub fn dataset_version(&self) -> Option<DatasetVersion> {
+        self.dataset_version
+    }
+
+    /// Restrict the verifications to the VCS directories with the given names
+    ///
+    /// Used for a partial delivery, where only some VCS directories changed
+    pub fn set_vcs_selection(&mut self, names: Vec<String>) {
+        self.vcs_selection = Some(names)
+    }
+
+    /// Selected VCS directories, `None` if all are selected
+    pub fn vcs_selection(&self) -> Option<&Vec<String>> {
+        self.vcs_selection.as_ref()
+    }
+
+    /// Restrict the verifications to the ballot box directories with the given names
+    ///
+    /// Used for an incremental verification of the tally, where only some ballot boxes are new
+    /// or changed
+    pub fn set_bb_selection(&mut self, names: Vec<String>) {
+        self.bb_selection = Some(names)
+    }
+
+    /// Selected ballot box directories, `None` if all are selected
+    pub fn bb_selection(&self) -> Option<&Vec<String>> {
+        self.bb_selection.as_ref()
+    }
+
+    /// The [VerificationDirectory] to verify for the verifications of the given scope,
+    /// according to the period and to the VCS and ballot box selections
+    ///
+    /// The ballot box selection applies only to the scope [VerificationScope::BallotBox]
+    pub fn verification_directory(
+        &self,
+        dir_path: &Path,
+        scope: VerificationScope,
+    ) -> VerificationDirectory {
+        let mut res = VerificationDirectory::new_with_source(
+            &self.period,
+            dir_path,
+            self.data_source.clone(),
+        );
+        if let Some(names) = &self.vcs_selection {
+            res.restrict_vcs_directories(names)
+        }
+        if let (VerificationScope::BallotBox, Some(names)) = (scope, &self.bb_selection) {
+            res.restrict_bb_directories(names)
+        }
+        res
+    }
+
+    /// The directories to verify for all the verifications of the suite (see [SuiteDirectories])
+    pub fn verification_directories(&self, dir_path: &Path) -> SuiteDirectories {
+        SuiteDirectories {
+            all: self.verification_directory(dir_path, VerificationScope::Dataset),
+            selected: self
+                .bb_selection
+                .as_ref()
+                .map(|_| self.verification_directory(dir_path, VerificationScope::BallotBox)),
         }
     }
 
@@ -105,6 +689,46 @@ impl<'a> VerificationSuite<'a> {
         self.exclusion.len()
     }
 
+    /// List of the verifications of the period that are not implemented
+    pub fn not_implemented(&self) -> &Vec<String> {
+        &self.not_implemented
+    }
+
+    /// List of the blocking verifications excluded or not implemented
+    ///
+    /// The run cannot be complete without them, even if all the verifications run are ok
+    pub fn blocking_not_run(&self) -> Vec<String> {
+        self.exclusion
+            .iter()
+            .chain(self.not_implemented.iter())
+            .filter(|id| {
+                self.metadata_list
+                    .meta_data_from_id(id)
+                    .map_or(true, |m| m.impact() == VerificationImpact::Blocking)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Summary statistics of the results of the verifications (see [VerificationSuiteResult])
+    pub fn suite_result(&self) -> VerificationSuiteResult {
+        let mut res = VerificationSuiteResult {
+            excluded: self.len_excluded(),
+            not_implemented: self.not_implemented.len(),
+            ..VerificationSuiteResult::default()
+        };
+        for v in self.list.0.iter() {
+            res.push(
+                v.id(),
+                &v.meta_data().category().to_string(),
+                ResultStatus::of(v),
+                v.duration(),
+                v.failures().len(),
+            );
+        }
+        res
+    }
+
     /// List of all verifications for a category
     ///
     /// The excluded verifications are not collected
@@ -129,6 +753,13 @@ impl<'a> VerificationSuite<'a> {
         list
     }
 
+    /// Query the results of the verifications (see [ResultQuery])
+    ///
+    /// The excluded verifications are not queried
+    pub fn query<'v>(&'v self, query: &ResultQuery) -> Vec<ResultView<'v, 'a>> {
+        self.list.query(query)
+    }
+
     /// Find a verification with id
     ///
     /// The excluded verifications are not searchable
@@ -136,30 +767,93 @@ impl<'a> VerificationSuite<'a> {
     pub fn find_by_id(&self, id: &str) -> Option<&Verification<'a, VerificationDirectory>> {
         self.list.0.iter().find(|&v| v.meta_data().id() == id)
     }
+
+    /// Run only the verification with the given id
+    ///
+    /// The verification runs also if it has already run, replacing the previous result. The
+    /// VCS selection of the suite is not applied to `dir`.
+    ///
+    /// # Error
+    /// If the verification is not in the suite (not implemented, excluded or of another period)
+    pub fn run_single(
+        &mut self,
+        id: &str,
+        directories: &SuiteDirectories,
+    ) -> anyhow::Result<&VerificationResult> {
+        let (period, dataset_version) = (self.period, self.dataset_version);
+        let v = self
+            .list
+            .0
+            .iter_mut()
+            .find(|v| v.meta_data().id() == id)
+            .ok_or_else(|| {
+                anyhow!(format!(
+                    "Verification {} not found in the suite for the {} (not implemented or excluded)",
+                    id, period
+                ))
+            })?;
+        v.run_with_dataset_version(directories.get(v.meta_data().scope()), dataset_version);
+        Ok(v.result())
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::*;
-    use crate::config::test::CONFIG_TEST;
+    use super::{
+        super::{
+            result::{
+                create_verification_error, create_verification_failure, VerificationEvent,
+                VerificationResult, VerificationResultTrait,
+            },
+            VerificationContext,
+        },
+        *,
+    };
+    use crate::{
+        config::test::{test_dataset_setup_path, test_dataset_tally_path, CONFIG_TEST},
+        file_structure::{
+            setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+            tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+            VerificationDirectoryTrait,
+        },
+    };
+    use log::debug;
 
-    const EXPECTED_IMPL_SETUP_VERIF: usize = 24;
+    const EXPECTED_IMPL_SETUP_VERIF: usize = 36;
     const IMPL_SETUP_TESTS: &[&str] = &[
-        "01.01", "02.01", "02.03", "02.04", "02.05", "02.06", "02.07", "03.01", "03.02", "03.03",
-        "03.04", "03.05", "03.06", "03.07", "03.08", "03.09", "03.13", "03.15", "04.01", "05.01",
-        "05.02", "05.03", "05.04", "05.21",
-    ];
-    const MISSING_SETUP_TESTS: &[&str] = &[
-        "02.02", "02.08", "03.10", "03.11", "03.12", "03.14", "05.22",
+        "01.01", "02.01", "02.02", "02.03", "02.04", "02.05", "03.01", "03.02", "03.03", "03.04",
+        "03.05", "03.06", "03.07", "03.08", "03.09", "03.10", "03.11", "03.12", "03.13", "03.14",
+        "03.15", "04.01", "05.01", "05.02", "05.03", "05.04", "05.21", "X.01.01", "X.01.02",
+        "X.02.01", "X.03.01", "X.03.02", "X.03.03", "X.04.01", "X.04.02", "X.05.01",
     ];
+    const MISSING_SETUP_TESTS: &[&str] = &[];
 
-    const EXPECTED_IMPL_TALLY_VERIF: usize = 2;
-    const IMPL_TALLY_TESTS: &[&str] = &["06.01", "09.01"];
+    const EXPECTED_IMPL_TALLY_VERIF: usize = 22;
+    const IMPL_TALLY_TESTS: &[&str] = &[
+        "06.01", "07.05", "07.06", "07.07", "08.02", "08.05", "08.09", "08.11", "09.01", "X.06.01",
+        "X.07.01", "X.08.01", "X.08.02", "X.08.03", "X.09.01", "X.09.02", "X.10.01", "X.10.02",
+        "X.10.03", "X.10.04", "X.10.05", "X.10.06",
+    ];
     const MISSING_TALLY_TESTS: &[&str] = &[
-        "07.01", "07.02", "07.03", "07.04", "07.05", "07.06", "07.07", "08.01", "08.02", "08.03",
-        "08.04", "08.05", "08.06", "08.07", "08.08", "08.09", "08.10", "08.11", "10.01", "10.02",
+        "07.01", "07.02", "07.03", "07.04", "08.01", "08.03", "08.04", "08.06", "08.07", "08.08",
+        "08.10", "10.01", "10.02",
     ];
 
+    #[test]
+    fn test_verification_list_covered() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        assert_eq!(
+            metadata_list.len(),
+            IMPL_SETUP_TESTS.len()
+                + MISSING_SETUP_TESTS.len()
+                + IMPL_TALLY_TESTS.len()
+                + MISSING_TALLY_TESTS.len()
+        );
+        assert_eq!(IMPL_SETUP_TESTS.len(), EXPECTED_IMPL_SETUP_VERIF);
+        assert_eq!(IMPL_TALLY_TESTS.len(), EXPECTED_IMPL_TALLY_VERIF);
+    }
+
     #[test]
     fn test_setup_verifications() {
         let metadata_list =
@@ -169,13 +863,15 @@ mod test {
             &metadata_list,
             &[],
             &CONFIG_TEST,
-        );
+        )
+        .unwrap();
         assert_eq!(verifs.len(), EXPECTED_IMPL_SETUP_VERIF);
         assert_eq!(verifs.collect_id(), IMPL_SETUP_TESTS);
         assert_eq!(
-            get_not_implemented_verifications_id(VerificationPeriod::Setup, &CONFIG_TEST),
+            get_not_implemented_verifications_id(VerificationPeriod::Setup, &CONFIG_TEST).unwrap(),
             MISSING_SETUP_TESTS
         );
+        assert_eq!(verifs.not_implemented().clone(), MISSING_SETUP_TESTS);
     }
 
     #[test]
@@ -187,15 +883,224 @@ mod test {
             &metadata_list,
             &[],
             &CONFIG_TEST,
-        );
+        )
+        .unwrap();
         assert_eq!(verifs.len(), EXPECTED_IMPL_TALLY_VERIF);
         assert_eq!(verifs.collect_id(), IMPL_TALLY_TESTS);
         assert_eq!(
-            get_not_implemented_verifications_id(VerificationPeriod::Tally, &CONFIG_TEST),
+            get_not_implemented_verifications_id(VerificationPeriod::Tally, &CONFIG_TEST).unwrap(),
             MISSING_TALLY_TESTS
         );
     }
 
+    #[test]
+    fn test_query_verification_metadata() {
+        let exclusion = vec!["01.01".to_string()];
+        let res = query_verification_metadata(
+            &MetaDataQuery::new().with_implemented(false),
+            &exclusion,
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert_eq!(
+            res.len(),
+            MISSING_SETUP_TESTS.len() + MISSING_TALLY_TESTS.len()
+        );
+        let res = query_verification_metadata(
+            &MetaDataQuery::new()
+                .with_period(VerificationPeriod::Tally)
+                .with_implemented(true),
+            &exclusion,
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert_eq!(
+            res.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            IMPL_TALLY_TESTS
+        );
+        let res = query_verification_metadata(
+            &MetaDataQuery::new().with_excluded(true),
+            &exclusion,
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].id, "01.01");
+        assert!(res[0].implemented);
+    }
+
+    #[test]
+    fn test_vcs_selection() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut verifs = VerificationSuite::new(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &[],
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        let path = test_dataset_setup_path();
+        let all = verifs.verification_directory(&path, VerificationScope::Dataset);
+        let names: Vec<String> = all
+            .unwrap_setup()
+            .vcs_directories()
+            .iter()
+            .map(|d| d.get_name())
+            .collect();
+        assert!(names.len() > 1);
+        assert!(verifs.vcs_selection().is_none());
+        verifs.set_vcs_selection(vec![names[0].clone()]);
+        let selected = verifs.verification_directory(&path, VerificationScope::Dataset);
+        assert_eq!(selected.unwrap_setup().vcs_directories().len(), 1);
+        assert_eq!(
+            selected.unwrap_setup().vcs_directories()[0].get_name(),
+            names[0]
+        );
+    }
+
+    #[test]
+    fn test_bb_selection() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut verifs = VerificationSuite::new(
+            &VerificationPeriod::Tally,
+            &metadata_list,
+            &[],
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        let path = test_dataset_tally_path();
+        let all = verifs.verification_directory(&path, VerificationScope::BallotBox);
+        let names: Vec<String> = all
+            .tally()
+            .unwrap()
+            .bb_directories()
+            .iter()
+            .map(|d| d.get_name())
+            .collect();
+        assert!(!names.is_empty());
+        assert!(verifs.bb_selection().is_none());
+        verifs.set_bb_selection(vec![names[0].clone()]);
+        let selected = verifs.verification_directory(&path, VerificationScope::BallotBox);
+        assert_eq!(selected.tally().unwrap().bb_directories().len(), 1);
+        assert_eq!(
+            selected.tally().unwrap().bb_directories()[0].get_name(),
+            names[0]
+        );
+        verifs.set_bb_selection(vec![]);
+        let selected = verifs.verification_directory(&path, VerificationScope::BallotBox);
+        assert!(selected.tally().unwrap().bb_directories().is_empty());
+        assert_eq!(
+            selected.unwrap_setup().vcs_directories().len(),
+            all.unwrap_setup().vcs_directories().len()
+        );
+        // The verifications of the whole dataset verify all the ballot boxes
+        let directories = verifs.verification_directories(&path);
+        assert!(directories
+            .get(VerificationScope::BallotBox)
+            .tally()
+            .unwrap()
+            .bb_directories()
+            .is_empty());
+        assert_eq!(
+            directories
+                .get(VerificationScope::Dataset)
+                .tally()
+                .unwrap()
+                .bb_directories()
+                .len(),
+            names.len()
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        fn ok(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            VerificationResult::new()
+        }
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let new_verif = || {
+            Verification::new(
+                "01.01",
+                "VerifySetupCompleteness",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )
+            .unwrap()
+        };
+        let tally_verif = Verification::new(
+            "X.08.01",
+            "VerifyEch0222ConfirmedVotesConsistency",
+            ok,
+            &metadata_list,
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        let list = VerificationList(vec![new_verif(), tally_verif]);
+        assert!(list
+            .check(&VerificationPeriod::Setup)
+            .unwrap_err()
+            .problems()[0]
+            .contains("X.08.01 of the tally is not applicable for the setup"));
+        assert!(list.check(&VerificationPeriod::Tally).is_err());
+        assert!(VerificationList(vec![new_verif()])
+            .check(&VerificationPeriod::Setup)
+            .is_ok());
+        let list = VerificationList(vec![new_verif(), new_verif()]);
+        assert_eq!(
+            list.check(&VerificationPeriod::Setup)
+                .unwrap_err()
+                .problems()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_build_errors() {
+        fn ok(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            VerificationResult::new()
+        }
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let list = VerificationList::from_results(vec![
+            Verification::new(
+                "01.01",
+                "VerifySetupCompleteness",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            ),
+            Verification::new("99.01", "VerifyUnknown", ok, &metadata_list, &CONFIG_TEST),
+            Verification::new("99.02", "VerifyUnknown", ok, &metadata_list, &CONFIG_TEST),
+        ]);
+        let err = list.err().unwrap();
+        assert_eq!(err.problems().len(), 2);
+        let res = VerificationList::concat(vec![
+            VerificationList::from_results(vec![Verification::new(
+                "01.01",
+                "VerifySetupCompleteness",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )]),
+            Err(err),
+            VerificationList::from_results(vec![Verification::new(
+                "99.03",
+                "VerifyUnknown",
+                ok,
+                &metadata_list,
+                &CONFIG_TEST,
+            )]),
+        ]);
+        let err = res.err().unwrap();
+        assert_eq!(err.problems().len(), 3);
+        assert!(err.to_string().contains("3 problem(s)"));
+    }
+
     #[test]
     fn test_with_exclusion() {
         let metadata_list =
@@ -205,19 +1110,33 @@ mod test {
             &metadata_list,
             &["02.01".to_string(), "05.01".to_string()],
             &CONFIG_TEST,
-        );
+        )
+        .unwrap();
         assert_eq!(verifs.len(), EXPECTED_IMPL_SETUP_VERIF - 2);
         assert_eq!(verifs.len_excluded(), 2);
         assert_eq!(
             verifs.exclusion,
             vec!["02.01".to_string(), "05.01".to_string()]
         );
+        assert_eq!(
+            verifs.blocking_not_run()[..2],
+            ["02.01".to_string(), "05.01".to_string()]
+        );
+        let verifs = VerificationSuite::new(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &["X.04.02".to_string()],
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert_eq!(verifs.blocking_not_run(), *verifs.not_implemented());
         let verifs = VerificationSuite::new(
             &VerificationPeriod::Setup,
             &metadata_list,
             &["toto".to_string()],
             &CONFIG_TEST,
-        );
+        )
+        .unwrap();
         assert_eq!(verifs.len(), EXPECTED_IMPL_SETUP_VERIF);
         assert_eq!(verifs.len_excluded(), 0);
         assert!(verifs.exclusion.is_empty());
@@ -226,7 +1145,8 @@ mod test {
             &metadata_list,
             &["02.01".to_string(), "05.01".to_string(), "toto".to_string()],
             &CONFIG_TEST,
-        );
+        )
+        .unwrap();
         assert_eq!(verifs.len(), EXPECTED_IMPL_SETUP_VERIF - 2);
         assert_eq!(verifs.len_excluded(), 2);
         assert_eq!(
@@ -234,4 +1154,171 @@ mod test {
             vec!["02.01".to_string(), "05.01".to_string()]
         );
     }
+
+    #[test]
+    fn test_with_selection() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let all = VerificationSuite::new(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &[],
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        let nb_authenticity = all
+            .get_verifications_for_category(VerificationCategory::Authenticity)
+            .len();
+        let selection =
+            VerificationSelection::from_values(&["authenticity".to_string(), "05.01".to_string()]);
+        let verifs = VerificationSuite::new_with_selection(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &["02.01".to_string()],
+            &selection,
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert_eq!(verifs.len(), nb_authenticity);
+        assert!(verifs.find_by_id("05.01").is_some());
+        assert!(verifs.find_by_id("02.01").is_none());
+        assert_eq!(
+            verifs.len_excluded(),
+            EXPECTED_IMPL_SETUP_VERIF - nb_authenticity
+        );
+        assert!(verifs.exclusion().contains(&"02.01".to_string()));
+        let verifs = VerificationSuite::new_with_selection(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &[],
+            &VerificationSelection::new(),
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert_eq!(verifs.len(), EXPECTED_IMPL_SETUP_VERIF);
+        // All the verifications of the setup are implemented: the unimplemented id is one of the
+        // tally
+        assert!(MISSING_TALLY_TESTS.contains(&"07.01"));
+        let err = VerificationSuite::new_with_selection(
+            &VerificationPeriod::Tally,
+            &metadata_list,
+            &[],
+            &VerificationSelection::from_values(&[
+                "99.99".to_string(),
+                "01.01".to_string(),
+                "07.01".to_string(),
+                "06.01".to_string(),
+            ]),
+            &CONFIG_TEST,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.problems().len(), 3);
+        assert!(err.problems()[0].starts_with("unknown verification id 99.99"));
+        assert!(err.problems()[1].contains("of the setup, not of the tally"));
+        assert_eq!(err.problems()[2], "verification 07.01 is not implemented");
+        let err = VerificationSuite::new_with_selection(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &["01.01".to_string()],
+            &VerificationSelection::new().with_id("01.01"),
+            &CONFIG_TEST,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.problems().len(), 1);
+    }
+
+    #[test]
+    fn test_run_single() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut verifs = VerificationSuite::new(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &["02.01".to_string()],
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        let dir = verifs.verification_directories(&test_dataset_setup_path());
+        assert!(verifs.run_single("05.04", &dir).unwrap().is_ok().unwrap());
+        assert!(verifs.find_by_id("05.04").unwrap().is_finished());
+        assert!(!verifs.find_by_id("05.03").unwrap().is_finished());
+        assert!(verifs.run_single("02.01", &dir).is_err());
+        assert!(verifs.run_single("06.01", &dir).is_err());
+    }
+
+    #[test]
+    fn test_suite_result() {
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut verifs = VerificationSuite::new(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &["02.01".to_string()],
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        verifs.list.0[0].restore(&[], &[]);
+        verifs.list.0[1].restore(&["error".to_string()], &[]);
+        verifs.list.0[2].restore(&[], &["f1".to_string(), "f2".to_string()]);
+        let res = verifs.suite_result();
+        assert_eq!((res.passed, res.errored, res.failed), (1, 1, 1));
+        assert_eq!(res.not_run, EXPECTED_IMPL_SETUP_VERIF - 4);
+        assert_eq!(res.total(), verifs.len());
+        assert_eq!(res.excluded, 1);
+        assert_eq!(res.not_implemented, MISSING_SETUP_TESTS.len());
+        let category = verifs.list.0[2].meta_data().category().to_string();
+        assert_eq!(res.failures_by_category[&category], 2);
+        assert!(res.slowest.is_empty());
+    }
+
+    #[test]
+    fn test_suite_result_slowest() {
+        let mut res = VerificationSuiteResult::default();
+        for i in [3, 1, 6, 2, 5, 0, 4] {
+            res.push(
+                &format!("0{}", i),
+                "consistency",
+                ResultStatus::Ok,
+                Some(HumanDuration(std::time::Duration::from_millis(i))),
+                0,
+            );
+        }
+        res.push("07", "evidence", ResultStatus::NotRun, None, 0);
+        assert_eq!(
+            res.slowest
+                .iter()
+                .map(|s| s.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["06", "05", "04", "03", "02"]
+        );
+        assert_eq!(res.total_duration.0.as_millis(), 21);
+        assert_eq!((res.passed, res.not_run), (7, 1));
+        assert_eq!(res.failures_by_category["evidence"], 0);
+    }
+
+    #[test]
+    fn test_execution_policy() {
+        let mut result = VerificationResult::new();
+        result.push(create_verification_failure!("failure"));
+        assert!(!ExecutionPolicy::FailFast.must_stop(&result));
+        result.push(create_verification_error!("error"));
+        assert!(ExecutionPolicy::FailFast.must_stop(&result));
+        assert!(!ExecutionPolicy::Continue.must_stop(&result));
+        let metadata_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut verifs = VerificationSuite::new(
+            &VerificationPeriod::Setup,
+            &metadata_list,
+            &[],
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert_eq!(verifs.execution_policy(), ExecutionPolicy::Continue);
+        verifs.list.0[1].restore(&["error".to_string()], &[]);
+        assert!(verifs.stopped_by().is_none());
+        verifs.set_execution_policy(ExecutionPolicy::FailFast);
+        assert_eq!(verifs.stopped_by().unwrap().id(), verifs.list.0[1].id());
+    }
 }
@@ -1,11 +1,214 @@
-use crate::{config::Config, verification::meta_data::VerificationMetaDataList};
-
-use super::super::suite::VerificationList;
+use super::super::{
+    direct_trust_certificates::verify_direct_trust_certificates,
+    result::{create_verification_error, VerificationEvent, VerificationResult},
+    suite::{SuiteBuildError, VerificationList},
+    verifications::Verification,
+    verify_signature_for_object, VerificationContext,
+};
+use crate::{
+    config::Config,
+    file_structure::{tally_directory::TallyDirectoryTrait, VerificationDirectoryTrait},
+    verification::meta_data::VerificationMetaDataList,
+};
+use anyhow::anyhow;
+use log::debug;
 
 pub fn get_verifications<'a>(
-    _metadata_list: &'a VerificationMetaDataList,
-    _config: &'static Config,
-) -> VerificationList<'a> {
-    let res = vec![];
-    VerificationList(res)
+    metadata_list: &'a VerificationMetaDataList,
+    config: &'static Config,
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
+        Verification::new(
+            "07.05",
+            "VerifySignatureTallyComponentDecrypt",
+            fn_0705_verify_signature_tally_component_decrypt,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "07.06",
+            "VerifySignatureTallyComponentEch0222",
+            fn_0706_verify_signature_tally_component_ech_0222,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "07.07",
+            "VerifySignatureTallyComponentEch0110",
+            fn_0707_verify_signature_tally_component_ech_0110,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.07.01",
+            "VerifyDirectTrustCertificates",
+            fn_x0701_verify_direct_trust_certificates,
+            metadata_list,
+            config,
+        ),
+    ])
+}
+
+fn fn_0705_verify_signature_tally_component_decrypt<D: VerificationDirectoryTrait>(
+    dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let decrypt = match tally_dir.e_voting_decrypt() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                format!("{} cannot be read", "e_voting_decrypt"),
+                e
+            ));
+            return result;
+        }
+    };
+    verify_signature_for_object(decrypt.as_ref(), context.config(), "e_voting_decrypt")
+}
+
+fn fn_0706_verify_signature_tally_component_ech_0222<D: VerificationDirectoryTrait>(
+    dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let ech_0222 = match tally_dir.ech_0222() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                format!("{} cannot be read", "ech_0222"),
+                e
+            ));
+            return result;
+        }
+    };
+    verify_signature_for_object(ech_0222.as_ref(), context.config(), "ech_0222")
+}
+
+fn fn_0707_verify_signature_tally_component_ech_0110<D: VerificationDirectoryTrait>(
+    dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let ech_0110 = match tally_dir.ech_0110() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                format!("{} cannot be read", "ech_0110"),
+                e
+            ));
+            return result;
+        }
+    };
+    verify_signature_for_object(ech_0110.as_ref(), context.config(), "ech_0110")
+}
+
+fn fn_x0701_verify_direct_trust_certificates<D: VerificationDirectoryTrait>(
+    _dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    verify_direct_trust_certificates(context.config(), &mut result);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_0705() {
+        let dir = get_verifier_dir();
+        let result = fn_0705_verify_signature_tally_component_decrypt(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_0706() {
+        let dir = get_verifier_dir();
+        let result = fn_0706_verify_signature_tally_component_ech_0222(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_0707() {
+        let dir = get_verifier_dir();
+        let result = fn_0707_verify_signature_tally_component_ech_0110(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_x0701() {
+        let dir = get_verifier_dir();
+        let result = fn_x0701_verify_direct_trust_certificates(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_0707_wrong_signature() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut ech_0110 = mock_dir.tally().unwrap().ech_0110().unwrap();
+        ech_0110.signature = mock_dir.tally().unwrap().ech_0222().unwrap().signature;
+        mock_dir.unwrap_tally_mut().mock_ech_0110(&Ok(&ech_0110));
+        let result = fn_0707_verify_signature_tally_component_ech_0110(
+            &mock_dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_failures().unwrap());
+    }
+
+    #[test]
+    fn test_0706_error() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        mock_dir
+            .unwrap_tally_mut()
+            .mock_ech_0222(&Err(anyhow!("test")));
+        let result = fn_0706_verify_signature_tally_component_ech_0222(
+            &mock_dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.has_errors().unwrap());
+    }
 }
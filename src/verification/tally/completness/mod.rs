@@ -1,16 +1,20 @@
 use crate::{
     config::Config,
     file_structure::{
+        setup_directory::SetupDirectoryTrait,
         tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
-        VerificationDirectoryTrait,
+        DirectoryNamesCheck, VerificationDirectoryTrait,
     },
     verification::meta_data::VerificationMetaDataList,
 };
 
 use super::super::{
-    result::{create_verification_failure, VerificationEvent, VerificationResult},
-    suite::VerificationList,
+    missing_file_failure,
+    result::{create_verification_error, VerificationEvent, VerificationResult},
+    setup::completness::push_directory_names_failures,
+    suite::{SuiteBuildError, VerificationList},
     verifications::Verification,
+    verify_file_content, VerificationContext,
 };
 use anyhow::anyhow;
 use log::debug;
@@ -18,63 +22,136 @@ use log::debug;
 pub fn get_verifications<'a>(
     metadata_list: &'a VerificationMetaDataList,
     config: &'static Config,
-) -> VerificationList<'a> {
-    VerificationList(vec![Verification::new(
-        "06.01",
-        "VerifyTallyCompleteness",
-        fn_0601_verify_tally_completeness,
-        metadata_list,
-        config,
-    )
-    .unwrap()])
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
+        Verification::new(
+            "06.01",
+            "VerifySetupCompleteness",
+            fn_0601_verify_tally_completeness,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.06.01",
+            "VerifyBallotBoxDirectoryNames",
+            fn_x0601_verify_bb_directory_names,
+            metadata_list,
+            config,
+        ),
+    ])
 }
 
 fn validate_bb_dir<B: BBDirectoryTrait>(dir: &B, result: &mut VerificationResult) {
-    if !dir.tally_component_shuffle_payload_file().exists() {
-        result.push(create_verification_failure!(
-            "tally_component_shuffle_payload does not exist"
-        ))
+    let mut missing = vec![];
+    if !dir.tally_component_votes_payload_file().exists() {
+        missing.push("tally_component_votes_payload");
     }
     if !dir.tally_component_shuffle_payload_file().exists() {
-        result.push(create_verification_failure!(
-            "tally_component_shuffle_payload does not exist"
-        ))
+        missing.push("tally_component_shuffle_payload");
     }
     if !dir
         .control_component_ballot_box_payload_group()
         .has_elements()
     {
-        result.push(create_verification_failure!(
-            "control_component_ballot_box_payload does not exist"
-        ))
+        missing.push("control_component_ballot_box_payload");
     }
     if !dir.control_component_shuffle_payload_group().has_elements() {
-        result.push(create_verification_failure!(
-            "control_component_shuffle_payload does not exist"
-        ))
+        missing.push("control_component_shuffle_payload");
+    }
+    for m in missing.iter() {
+        result.push(missing_file_failure(m))
+    }
+    verify_file_content(dir.tally_component_votes_payload_file(), result);
+    verify_file_content(dir.tally_component_shuffle_payload_file(), result);
+    for group in [
+        dir.control_component_ballot_box_payload_group(),
+        dir.control_component_shuffle_payload_group(),
+    ] {
+        for (_, f) in group.iter() {
+            verify_file_content(&f, result);
+        }
+    }
+    if !missing.is_empty() {
+        result.push_failed_item(
+            "ballot_box",
+            &dir.get_name(),
+            &format!("missing {}", missing.join(", ")),
+        )
     }
 }
 
 fn fn_0601_verify_tally_completeness<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
-    let tally_dir = dir.unwrap_tally();
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
     if !tally_dir.ech_0110_file().exists() {
-        result.push(create_verification_failure!("ech_0110 does not exist"))
+        result.push(missing_file_failure("ech_0110"))
     }
     if !tally_dir.ech_0222_file().exists() {
-        result.push(create_verification_failure!("ech_0222 does not exist"))
+        result.push(missing_file_failure("ech_0222"))
     }
     if !tally_dir.e_voting_decrypt_file().exists() {
-        result.push(create_verification_failure!(
-            "e_voting_decrypt does not exist"
-        ))
+        result.push(missing_file_failure("e_voting_decrypt"))
+    }
+    for f in [
+        tally_dir.ech_0110_file(),
+        tally_dir.ech_0222_file(),
+        tally_dir.e_voting_decrypt_file(),
+    ] {
+        verify_file_content(f, &mut result);
     }
     for d in tally_dir.bb_directories().iter() {
-        validate_bb_dir(d, result);
+        validate_bb_dir(d, &mut result);
     }
+    result
+}
+
+fn fn_x0601_verify_bb_directory_names<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let eec = match dir.unwrap_setup().election_event_context_payload() {
+        Ok(o) => o.election_event_context,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let names: Vec<String> = tally_dir
+        .bb_directories()
+        .iter()
+        .map(|d| d.get_name())
+        .collect();
+    let ids: Vec<String> = eec
+        .verification_card_set_contexts
+        .iter()
+        .map(|c| c.ballot_box_id.clone())
+        .collect();
+    push_directory_names_failures(
+        &DirectoryNamesCheck::new(&names, &ids),
+        "ballot_box",
+        &mut result,
+    );
+    result
 }
 
 #[cfg(test)]
@@ -85,8 +162,18 @@ mod test {
     #[test]
     fn test_ok() {
         let dir = get_test_verifier_tally_dir();
-        let mut result = VerificationResult::new();
-        fn_0601_verify_tally_completeness(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_0601_verify_tally_completeness(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_x0601_ok() {
+        let dir = get_test_verifier_tally_dir();
+        let result =
+            fn_x0601_verify_bb_directory_names(&dir, &VerificationContext::new(&CONFIG_TEST));
         assert!(result.is_ok().unwrap());
     }
 }
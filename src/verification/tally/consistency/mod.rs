@@ -1,11 +1,71 @@
+mod v0802_ciphertexts_consistency;
+mod v0805_ballot_box_ids_consistency;
+mod v0809_node_ids_consistency;
+mod v0811_encryption_group_consistency;
+mod x0801_ech0222_confirmed_votes_consistency;
+mod x0802_shuffle_payload_sizes_consistency;
+mod x0803_ballot_box_times_consistency;
+
 use crate::{config::Config, verification::meta_data::VerificationMetaDataList};
 
-use super::super::suite::VerificationList;
+use super::super::{
+    suite::{SuiteBuildError, VerificationList},
+    verifications::Verification,
+};
 
 pub fn get_verifications<'a>(
-    _metadata_list: &'a VerificationMetaDataList,
-    _config: &'static Config,
-) -> VerificationList<'a> {
-    let res = vec![];
-    VerificationList(res)
+    metadata_list: &'a VerificationMetaDataList,
+    config: &'static Config,
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
+        Verification::new(
+            "08.02",
+            "VerifyCiphertextsConsistency",
+            v0802_ciphertexts_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "08.05",
+            "VerifyBallotBoxIdsConsistency",
+            v0805_ballot_box_ids_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "08.09",
+            "VerifyNodeIdsConsistency",
+            v0809_node_ids_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "08.11",
+            "VerifyEncryptionGroupConsistency",
+            v0811_encryption_group_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.08.01",
+            "VerifyEch0222ConfirmedVotesConsistency",
+            x0801_ech0222_confirmed_votes_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.08.02",
+            "VerifyShufflePayloadSizesConsistency",
+            x0802_shuffle_payload_sizes_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.08.03",
+            "VerifyBallotBoxTimesConsistency",
+            x0803_ballot_box_times_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+    ])
 }
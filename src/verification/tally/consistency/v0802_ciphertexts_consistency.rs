@@ -0,0 +1,271 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, VerificationEvent,
+    VerificationResult,
+};
+use crate::{
+    data_structures::{
+        common_types::ExponentiatedEncryptedElement,
+        tally::control_component_shuffle_payload::ControlComponentShufflePayload,
+    },
+    file_structure::{
+        setup_directory::SetupDirectoryTrait,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+
+/// Order the shuffle payloads of a ballot box according to the mixing order of the context
+///
+/// The node ids of the payloads must be exactly the node ids of the mixing order.
+/// Return a failure message describing the difference else.
+fn order_shuffle_payloads(
+    mixing_order: &[usize],
+    payloads: Vec<Box<ControlComponentShufflePayload>>,
+) -> Result<Vec<Box<ControlComponentShufflePayload>>, String> {
+    let mut node_ids: Vec<usize> = payloads.iter().map(|p| p.node_id).collect();
+    let mut expected = mixing_order.to_vec();
+    node_ids.sort();
+    expected.sort();
+    if node_ids != expected {
+        return Err(format!(
+            "The shuffle payloads are from the nodes {:?}, but the mixing order of the context is {:?}",
+            payloads.iter().map(|p| p.node_id).collect::<Vec<_>>(),
+            mixing_order
+        ));
+    }
+    let mut payloads = payloads;
+    payloads.sort_by_key(|p| mixing_order.iter().position(|n| *n == p.node_id));
+    Ok(payloads)
+}
+
+/// The ciphertexts of the verifiable decryptions must be the partial decryptions of the shuffled
+/// ciphertexts: same number of ciphertexts and same gammas
+fn verify_decryptions_of_shuffle(
+    shuffled: &[ExponentiatedEncryptedElement],
+    decrypted: &[ExponentiatedEncryptedElement],
+) -> Option<String> {
+    if shuffled.len() != decrypted.len() {
+        return Some(format!(
+            "{} shuffled ciphertexts, but {} decrypted ciphertexts",
+            shuffled.len(),
+            decrypted.len()
+        ));
+    }
+    shuffled
+        .iter()
+        .zip(decrypted.iter())
+        .position(|(s, d)| s.gamma != d.gamma)
+        .map(|i| {
+            format!(
+                "the gamma of the decrypted ciphertext at pos {} is not the gamma of the shuffled ciphertext",
+                i
+            )
+        })
+}
+
+/// Verify the chain of the shuffles of a ballot box, following the mixing order
+///
+/// Each node shuffles the ciphertexts decrypted by the previous node in the mixing order, and the
+/// tally component shuffles the ciphertexts decrypted by the last node.
+fn verify_shuffle_chain<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    mixing_order: &[usize],
+    result: &mut VerificationResult,
+) {
+    let bb_name = bb_dir.get_name();
+    let mut payloads = vec![];
+    for (i, p) in bb_dir.control_component_shuffle_payload_iter() {
+        match p {
+            Ok(p) => payloads.push(p),
+            Err(e) => {
+                result.push(create_verification_error!(
+                    format!(
+                        "Cannot extract control_component_shuffle_payload for node {} in ballot box {}",
+                        i, bb_name
+                    ),
+                    e
+                ));
+                return;
+            }
+        }
+    }
+    let payloads = match order_shuffle_payloads(mixing_order, payloads) {
+        Ok(p) => p,
+        Err(msg) => {
+            result.push(create_verification_failure!(format!(
+                "{} in ballot box {}",
+                msg, bb_name
+            )));
+            result.push_failed_item("ballot_box", &bb_name, "shuffle order");
+            return;
+        }
+    };
+    let mut previous: Option<&ControlComponentShufflePayload> = None;
+    for p in payloads.iter() {
+        if let Some(msg) = verify_decryptions_of_shuffle(
+            &p.verifiable_shuffle.shuffled_ciphertexts,
+            &p.verifiable_decryptions.ciphertexts,
+        ) {
+            result.push(create_verification_failure!(format!(
+                "Node {} in ballot box {}: {}",
+                p.node_id, bb_name, msg
+            )));
+        }
+        if let Some(prev) = previous {
+            if p.verifiable_shuffle.shuffled_ciphertexts.len()
+                != prev.verifiable_decryptions.ciphertexts.len()
+            {
+                result.push(create_verification_failure!(format!(
+                    "Node {} shuffles {} ciphertexts, but the previous node {} in the mixing order decrypted {} ciphertexts in ballot box {}",
+                    p.node_id,
+                    p.verifiable_shuffle.shuffled_ciphertexts.len(),
+                    prev.node_id,
+                    prev.verifiable_decryptions.ciphertexts.len(),
+                    bb_name
+                )));
+            }
+        }
+        previous = Some(p.as_ref());
+    }
+    match bb_dir.tally_component_shuffle_payload() {
+        Ok(tc) => {
+            if let Some(last) = previous {
+                if tc.verifiable_shuffle.shuffled_ciphertexts.len()
+                    != last.verifiable_decryptions.ciphertexts.len()
+                {
+                    result.push(create_verification_failure!(format!(
+                        "The tally component shuffles {} ciphertexts, but the last node {} in the mixing order decrypted {} ciphertexts in ballot box {}",
+                        tc.verifiable_shuffle.shuffled_ciphertexts.len(),
+                        last.node_id,
+                        last.verifiable_decryptions.ciphertexts.len(),
+                        bb_name
+                    )));
+                }
+            }
+        }
+        Err(e) => result.push(create_verification_error!(
+            format!(
+                "Cannot extract tally_component_shuffle_payload in ballot box {}",
+                bb_name
+            ),
+            e
+        )),
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let mixing_order = match dir.unwrap_setup().election_event_context_payload() {
+        Ok(o) => o.election_event_context.mixing_order(),
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    debug!("Mixing order: {:?}", mixing_order);
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let mut bb_result = VerificationResult::new();
+        verify_shuffle_chain(bb_dir, &mixing_order, &mut bb_result);
+        bb_result.annotate(EntityKind::BallotBox, &bb_dir.get_name());
+        result.append(&mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_order_shuffle_payloads() {
+        let dir = get_verifier_dir();
+        let payloads: Vec<Box<ControlComponentShufflePayload>> =
+            dir.tally().unwrap().bb_directories()[0]
+                .control_component_shuffle_payload_iter()
+                .map(|(_, p)| p.unwrap())
+                .collect();
+        let ordered = order_shuffle_payloads(&[2, 1, 4, 3], payloads.clone()).unwrap();
+        assert_eq!(
+            ordered.iter().map(|p| p.node_id).collect::<Vec<_>>(),
+            vec![2, 1, 4, 3]
+        );
+        assert!(order_shuffle_payloads(&[1, 2, 3], payloads.clone()).is_err());
+        let mut payloads = payloads;
+        payloads[1].node_id = 1;
+        assert!(order_shuffle_payloads(&[1, 2, 3, 4], payloads).is_err());
+    }
+
+    #[test]
+    fn test_different_mixing_order() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut eec = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        eec.election_event_context.mixing_order = Some(vec![1, 2, 3, 5]);
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Ok(&eec));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert!(!result.failed_items().is_empty());
+    }
+
+    #[test]
+    fn test_wrong_decryptions() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let bb_dir = mock_dir
+            .tally()
+            .unwrap()
+            .bb_directories()
+            .iter()
+            .position(|d| {
+                d.control_component_shuffle_payload_iter()
+                    .any(|(_, p)| !p.unwrap().verifiable_decryptions.ciphertexts.is_empty())
+            })
+            .unwrap();
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[bb_dir]
+            .control_component_shuffle_payload_iter()
+            .find(|(i, _)| *i == 2)
+            .unwrap()
+            .1
+            .unwrap();
+        payload.verifiable_decryptions.ciphertexts.pop();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[bb_dir]
+            .mock_control_component_shuffle_payloads(2, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+    }
+}
@@ -0,0 +1,171 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, FailureCode,
+    VerificationEvent, VerificationResult,
+};
+use crate::{
+    file_structure::{
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+
+/// Collect the ballot box ids of all the payloads of the ballot box, with the name of the payload
+fn ballot_box_ids_of_bb<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    result: &mut VerificationResult,
+) -> Vec<(String, String)> {
+    let bb_name = bb_dir.get_name();
+    let mut ids = vec![];
+    for (i, p) in bb_dir.control_component_ballot_box_payload_iter() {
+        let name = format!("control_component_ballot_box_payload_{}", i);
+        match p {
+            Ok(p) => ids.push((name, p.ballot_box_id)),
+            Err(e) => result.push(create_verification_error!(
+                format!("{} in ballot box {} has wrong format", name, bb_name),
+                e
+            )),
+        }
+    }
+    for (i, p) in bb_dir.control_component_shuffle_payload_iter() {
+        let name = format!("control_component_shuffle_payload_{}", i);
+        match p {
+            Ok(p) => ids.push((name, p.ballot_box_id)),
+            Err(e) => result.push(create_verification_error!(
+                format!("{} in ballot box {} has wrong format", name, bb_name),
+                e
+            )),
+        }
+    }
+    match bb_dir.tally_component_shuffle_payload() {
+        Ok(p) => ids.push((
+            "tally_component_shuffle_payload".to_string(),
+            p.ballot_box_id,
+        )),
+        Err(e) => result.push(create_verification_error!(
+            format!(
+                "tally_component_shuffle_payload in ballot box {} has wrong format",
+                bb_name
+            ),
+            e
+        )),
+    }
+    match bb_dir.tally_component_votes_payload() {
+        Ok(p) => ids.push(("tally_component_votes_payload".to_string(), p.ballot_box_id)),
+        Err(e) => result.push(create_verification_error!(
+            format!(
+                "tally_component_votes_payload in ballot box {} has wrong format",
+                bb_name
+            ),
+            e
+        )),
+    }
+    ids
+}
+
+/// Verify that the ballot box ids are identical in all the payloads
+///
+/// The reference is the id of the most payloads, so that the failures give the payloads that
+/// differ
+fn verify_ids(ids: &[(String, String)], bb_name: &str, result: &mut VerificationResult) {
+    let reference = match ids
+        .iter()
+        .max_by_key(|(_, id)| ids.iter().filter(|(_, other)| other == id).count())
+    {
+        Some((_, id)) => id,
+        None => return,
+    };
+    debug!("Ballot box id of ballot box {}: {}", bb_name, reference);
+    for (name, id) in ids.iter().filter(|(_, id)| id != reference) {
+        result.push(
+            create_verification_failure!(format!(
+                "The ballot box id {} of {} is not the ballot box id {} of the other payloads in ballot box {}",
+                id, name, reference, bb_name
+            ))
+            .with_code(FailureCode::Inconsistency)
+            .with_file(name),
+        );
+        result.push_failed_item("file", &format!("{}/{}", bb_name, name), "ballot box id");
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let bb_name = bb_dir.get_name();
+        let mut bb_result = VerificationResult::new();
+        let ids = ballot_box_ids_of_bb(bb_dir, &mut bb_result);
+        verify_ids(&ids, &bb_name, &mut bb_result);
+        bb_result.annotate(EntityKind::BallotBox, &bb_name);
+        result.append(&mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_id_shuffle_payload() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[0]
+            .control_component_shuffle_payload_iter()
+            .find(|(i, _)| *i == 2)
+            .unwrap()
+            .1
+            .unwrap();
+        payload.ballot_box_id = "toto".to_string();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[0]
+            .mock_control_component_shuffle_payloads(2, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert!(result.failures()[0]
+            .to_string()
+            .contains("control_component_shuffle_payload_2"));
+        assert_eq!(result.failed_items().len(), 1);
+    }
+
+    #[test]
+    fn test_wrong_id_votes_payload() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[0]
+            .tally_component_votes_payload()
+            .unwrap();
+        payload.ballot_box_id = "toto".to_string();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[0]
+            .mock_tally_component_votes_payload(&Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+}
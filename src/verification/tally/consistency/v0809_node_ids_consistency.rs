@@ -0,0 +1,177 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, FailureCode,
+    VerificationEvent, VerificationResult,
+};
+use crate::{
+    file_structure::{
+        setup_directory::SetupDirectoryTrait,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+
+/// Verify that the node ids `found` in the payloads `what` cover the expected node ids, each one
+/// once
+fn verify_node_ids(
+    found: &[usize],
+    expected: &[usize],
+    what: &str,
+    bb_name: &str,
+    result: &mut VerificationResult,
+) {
+    let mut sorted = found.to_vec();
+    sorted.sort();
+    if sorted != expected {
+        result.push(
+            create_verification_failure!(format!(
+                "The node ids of the {} in ballot box {} are {:?}, but {:?} expected",
+                what, bb_name, found, expected
+            ))
+            .with_code(FailureCode::Inconsistency),
+        );
+        result.push_failed_item("ballot_box", bb_name, &format!("node ids of the {}", what));
+    }
+}
+
+/// Verify the node ids of the payloads of the control components of the ballot box
+fn verify_bb_dir<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    expected: &[usize],
+    result: &mut VerificationResult,
+) {
+    let bb_name = bb_dir.get_name();
+    let mut found = vec![];
+    for (i, p) in bb_dir.control_component_ballot_box_payload_iter() {
+        match p {
+            Ok(p) => found.push(p.node_id),
+            Err(e) => result.push(create_verification_error!(
+                format!(
+                    "Cannot extract control_component_ballot_box_payload_{} in ballot box {}",
+                    i, bb_name
+                ),
+                e
+            )),
+        }
+    }
+    verify_node_ids(
+        &found,
+        expected,
+        "control_component_ballot_box_payloads",
+        &bb_name,
+        result,
+    );
+    let mut found = vec![];
+    for (i, p) in bb_dir.control_component_shuffle_payload_iter() {
+        match p {
+            Ok(p) => found.push(p.node_id),
+            Err(e) => result.push(create_verification_error!(
+                format!(
+                    "Cannot extract control_component_shuffle_payload_{} in ballot box {}",
+                    i, bb_name
+                ),
+                e
+            )),
+        }
+    }
+    verify_node_ids(
+        &found,
+        expected,
+        "control_component_shuffle_payloads",
+        &bb_name,
+        result,
+    );
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    // The node ids of the control components are the node ids of the mixing order
+    let mut expected = match dir.unwrap_setup().election_event_context_payload() {
+        Ok(p) => p.election_event_context.mixing_order(),
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    expected.sort();
+    debug!("Node ids of the control components: {:?}", expected);
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let mut bb_result = VerificationResult::new();
+        verify_bb_dir(bb_dir, &expected, &mut bb_result);
+        bb_result.annotate(EntityKind::BallotBox, &bb_dir.get_name());
+        result.append(&mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_duplicate_node_id_ballot_box_payload() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[0]
+            .control_component_ballot_box_payload_iter()
+            .find(|(i, _)| *i == 2)
+            .unwrap()
+            .1
+            .unwrap();
+        payload.node_id = 1;
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[0]
+            .mock_control_component_ballot_box_payloads(2, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failed_items().len(), 1);
+    }
+
+    #[test]
+    fn test_wrong_mixing_order() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut eec = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        eec.election_event_context.mixing_order = Some(vec![1, 2, 3, 5]);
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Ok(&eec));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert_eq!(
+            result.failures().len(),
+            2 * mock_dir.tally().unwrap().bb_directories().len()
+        );
+    }
+}
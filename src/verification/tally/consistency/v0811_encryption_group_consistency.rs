@@ -0,0 +1,194 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, FailureCode,
+    VerificationEvent, VerificationResult,
+};
+use crate::{
+    file_structure::{
+        setup_directory::SetupDirectoryTrait,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use rust_ev_crypto_primitives::EncryptionParameters;
+
+/// Verify that the encryption group of the payload is the group of the election event context
+///
+/// The failure gives the elements of the group (p, q or g) that are different
+fn verify_encryption_group(
+    eg: &EncryptionParameters,
+    expected: &EncryptionParameters,
+    name: &str,
+    result: &mut VerificationResult,
+) {
+    let mut different = vec![];
+    if eg.p() != expected.p() {
+        different.push("p");
+    }
+    if eg.q() != expected.q() {
+        different.push("q");
+    }
+    if eg.g() != expected.g() {
+        different.push("g");
+    }
+    if !different.is_empty() {
+        result.push(
+            create_verification_failure!(format!(
+                "The encryption group in {} is not the group of the election event context ({} not equal)",
+                name,
+                different.join(", ")
+            ))
+            .with_code(FailureCode::Inconsistency)
+            .with_file(name),
+        );
+        result.push_failed_item("file", name, "encryption group");
+    }
+}
+
+/// Verify the encryption group of all the payloads of the ballot box
+fn verify_bb_dir<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    eg: &EncryptionParameters,
+    result: &mut VerificationResult,
+) {
+    let bb_name = bb_dir.get_name();
+    debug!("Verify the encryption groups in ballot box {}", bb_name);
+    for (i, p) in bb_dir.control_component_ballot_box_payload_iter() {
+        let name = format!("{}/control_component_ballot_box_payload_{}", bb_name, i);
+        match p {
+            Ok(p) => verify_encryption_group(&p.encryption_group, eg, &name, result),
+            Err(e) => result.push(create_verification_error!(
+                format!("{} has wrong format", name),
+                e
+            )),
+        }
+    }
+    for (i, p) in bb_dir.control_component_shuffle_payload_iter() {
+        let name = format!("{}/control_component_shuffle_payload_{}", bb_name, i);
+        match p {
+            Ok(p) => verify_encryption_group(&p.encryption_group, eg, &name, result),
+            Err(e) => result.push(create_verification_error!(
+                format!("{} has wrong format", name),
+                e
+            )),
+        }
+    }
+    let name = format!("{}/tally_component_shuffle_payload", bb_name);
+    match bb_dir.tally_component_shuffle_payload() {
+        Ok(p) => verify_encryption_group(&p.encryption_group, eg, &name, result),
+        Err(e) => result.push(create_verification_error!(
+            format!("{} has wrong format", name),
+            e
+        )),
+    }
+    let name = format!("{}/tally_component_votes_payload", bb_name);
+    match bb_dir.tally_component_votes_payload() {
+        Ok(p) => verify_encryption_group(&p.encryption_group, eg, &name, result),
+        Err(e) => result.push(create_verification_error!(
+            format!("{} has wrong format", name),
+            e
+        )),
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let eg = match dir.unwrap_setup().election_event_context_payload() {
+        Ok(p) => p.encryption_group,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "election_event_context_payload cannot be read",
+                e
+            ));
+            return result;
+        }
+    };
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let mut bb_result = VerificationResult::new();
+        verify_bb_dir(bb_dir, &eg, &mut bb_result);
+        bb_result.annotate(EntityKind::BallotBox, &bb_dir.get_name());
+        result.append_sub_result("ballot_box", &bb_dir.get_name(), &mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+    use rug::Integer;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+        assert_eq!(
+            result.sub_results_summary()[0].ok,
+            dir.tally().unwrap().bb_directories().len()
+        );
+    }
+
+    #[test]
+    fn test_wrong_ballot_box_payload() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let (_, p) = mock_dir.tally().unwrap().bb_directories()[0]
+            .control_component_ballot_box_payload_iter()
+            .next()
+            .unwrap();
+        let mut payload = p.unwrap();
+        payload.encryption_group.set_q(&Integer::from(5usize));
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[0]
+            .mock_control_component_ballot_box_payloads(payload.node_id, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert!(result.failures()[0].to_string().contains("(q not equal)"));
+        assert_eq!(result.failures()[0].code(), FailureCode::Inconsistency);
+        assert_eq!(result.failed_items().len(), 1);
+        assert_eq!(
+            result.failed_items()[0].id,
+            format!(
+                "{}/control_component_ballot_box_payload_{}",
+                mock_dir.tally().unwrap().bb_directories()[0].get_name(),
+                payload.node_id
+            )
+        );
+    }
+
+    #[test]
+    fn test_wrong_election_event_context() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut eec = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        eec.encryption_group.set_p(&Integer::from(1234usize));
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Ok(&eec));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.sub_results_summary()[0].ok, 0);
+    }
+}
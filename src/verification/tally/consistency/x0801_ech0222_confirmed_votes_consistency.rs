@@ -0,0 +1,433 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, VerificationEvent, VerificationResult,
+};
+use crate::{
+    file_structure::{
+        setup_directory::SetupDirectoryTrait,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Number of confirmed votes in the ballot box, according to the control components
+///
+/// All the control components must have the same number of confirmed votes. `None` if the
+/// number cannot be determined (the failure or the error is pushed in `result`)
+pub(super) fn confirmed_votes_in_bb<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    result: &mut VerificationResult,
+) -> Option<usize> {
+    let mut res: Option<usize> = None;
+    for (i, p) in bb_dir.control_component_ballot_box_payload_iter() {
+        match p {
+            Ok(p) => {
+                let nb = p.confirmed_encrypted_votes.len();
+                match res {
+                    Some(n) if n != nb => {
+                        result.push(create_verification_failure!(format!(
+                            "The number of confirmed votes {} of node {} is not the same as the other nodes ({}) in ballot box {}",
+                            nb,
+                            i,
+                            n,
+                            bb_dir.get_name()
+                        )));
+                        return None;
+                    }
+                    Some(_) => (),
+                    None => res = Some(nb),
+                }
+            }
+            Err(e) => {
+                result.push(create_verification_error!(
+                    format!(
+                        "Cannot extract control_component_ballot_box_payload for node {} in ballot box {}",
+                        i,
+                        bb_dir.get_name()
+                    ),
+                    e
+                ));
+                return None;
+            }
+        }
+    }
+    if res.is_none() {
+        result.push(create_verification_failure!(format!(
+            "No control_component_ballot_box_payload in ballot box {}",
+            bb_dir.get_name()
+        )));
+        result.push_failed_item(
+            "ballot_box",
+            &bb_dir.get_name(),
+            "no control_component_ballot_box_payload",
+        );
+    }
+    res
+}
+
+/// Ballot boxes sharing counting circles, with the counting circles of these ballot boxes
+///
+/// The eCH-0222 gives the cast votes per counting circle only, and a counting circle can vote in
+/// several ballot boxes. The cast votes are then compared for the ballot boxes connected through
+/// their counting circles. Usually, a group contains one ballot box.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct BallotBoxGroup {
+    ballot_boxes: BTreeSet<String>,
+    counting_circles: BTreeSet<String>,
+}
+
+impl BallotBoxGroup {
+    fn name(&self) -> String {
+        match self.ballot_boxes.is_empty() {
+            true => "no ballot box".to_string(),
+            false => format!(
+                "ballot box {}",
+                self.ballot_boxes
+                    .iter()
+                    .map(|bb| bb.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// Group the ballot boxes sharing counting circles
+///
+/// `counting_circles_per_bb` gives the counting circles of each ballot box (from the
+/// evoting-decrypt). The counting circles of the eCH-0222 in no ballot box form a last group
+/// without ballot box.
+fn group_ballot_boxes<'a>(
+    counting_circles_per_bb: &BTreeMap<String, BTreeSet<String>>,
+    ech_0222_counting_circles: impl Iterator<Item = &'a String>,
+) -> Vec<BallotBoxGroup> {
+    let mut groups: Vec<BallotBoxGroup> = vec![];
+    for (bb_id, cc_ids) in counting_circles_per_bb.iter() {
+        let mut group = BallotBoxGroup {
+            ballot_boxes: BTreeSet::from([bb_id.clone()]),
+            counting_circles: cc_ids.clone(),
+        };
+        let (connected, mut others): (Vec<_>, Vec<_>) = groups
+            .into_iter()
+            .partition(|g| !g.counting_circles.is_disjoint(cc_ids));
+        for g in connected {
+            group.ballot_boxes.extend(g.ballot_boxes);
+            group.counting_circles.extend(g.counting_circles);
+        }
+        others.push(group);
+        groups = others;
+    }
+    let unassigned: BTreeSet<String> = ech_0222_counting_circles
+        .filter(|cc| !groups.iter().any(|g| g.counting_circles.contains(*cc)))
+        .cloned()
+        .collect();
+    if !unassigned.is_empty() {
+        groups.push(BallotBoxGroup {
+            ballot_boxes: BTreeSet::new(),
+            counting_circles: unassigned,
+        });
+    }
+    groups
+}
+
+/// Verify that the number of cast votes in eCH-0222 equals the number of confirmed votes for the
+/// group of ballot boxes
+///
+/// A difference up to `tolerance` is accepted for each group (see
+/// [crate::config::StrictnessProfile::cast_votes_tolerance]).
+fn verify_cast_votes(
+    group: &BallotBoxGroup,
+    cast_votes_per_counting_circle: &BTreeMap<String, usize>,
+    confirmed_votes_per_bb: &BTreeMap<String, usize>,
+    tolerance: usize,
+    result: &mut VerificationResult,
+) {
+    let nb_cast_votes = |cc_id: &String| {
+        cast_votes_per_counting_circle
+            .get(cc_id)
+            .copied()
+            .unwrap_or_default()
+    };
+    let nb_confirmed_votes = |bb_id: &String| {
+        confirmed_votes_per_bb
+            .get(bb_id)
+            .copied()
+            .unwrap_or_default()
+    };
+    let total_cast_votes: usize = group.counting_circles.iter().map(nb_cast_votes).sum();
+    let total_confirmed_votes: usize = group.ballot_boxes.iter().map(nb_confirmed_votes).sum();
+    let diff = total_cast_votes.abs_diff(total_confirmed_votes);
+    if diff > 0 && diff <= tolerance {
+        debug!(
+            "The difference {} between the cast votes and the confirmed votes of {} is accepted (tolerance {})",
+            diff,
+            group.name(),
+            tolerance
+        );
+    }
+    if diff > tolerance {
+        result.push(create_verification_failure!(format!(
+            "The number of cast votes in eCH-0222 {} is not equal to the number of confirmed votes {} for {} (tolerance {})",
+            total_cast_votes,
+            total_confirmed_votes,
+            group.name(),
+            tolerance
+        )));
+        for bb_id in group.ballot_boxes.iter() {
+            result.push_failed_item(
+                "ballot_box",
+                bb_id,
+                &format!("{} confirmed votes", nb_confirmed_votes(bb_id)),
+            );
+        }
+        for cc_id in group.counting_circles.iter() {
+            result.push_failed_item(
+                "counting_circle",
+                cc_id,
+                &format!("{} cast votes in eCH-0222", nb_cast_votes(cc_id)),
+            );
+        }
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let cast_votes = match tally_dir
+        .ech_0222()
+        .map(|e| e.cast_votes_per_counting_circle())
+    {
+        Ok(c) => c,
+        Err(e) => {
+            result.push(create_verification_error!("Cannot extract the eCH-0222", e));
+            return result;
+        }
+    };
+    let decrypt = match tally_dir.e_voting_decrypt() {
+        Ok(d) => d,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract the evoting-decrypt",
+                e
+            ));
+            return result;
+        }
+    };
+    let eec = match dir.unwrap_setup().election_event_context_payload() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    // The ballot boxes of the evoting-decrypt are identified by the authorization (alias of the
+    // verification card set without `vcs_`)
+    let counting_circles_of_bb = |bb_id: &str| -> BTreeSet<String> {
+        eec.election_event_context
+            .verification_card_set_contexts
+            .iter()
+            .find(|c| c.ballot_box_id == bb_id)
+            .map(|c| {
+                c.verification_card_set_alias
+                    .strip_prefix("vcs_")
+                    .unwrap_or(&c.verification_card_set_alias)
+            })
+            .and_then(|id| decrypt.ballot_box(id))
+            .map(|bb| {
+                bb.counting_circles
+                    .iter()
+                    .map(|cc| cc.counting_circle_identification.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut confirmed_votes = BTreeMap::new();
+    let mut counting_circles_per_bb = BTreeMap::new();
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let bb_name = bb_dir.get_name();
+        if let Some(n) = confirmed_votes_in_bb(bb_dir, &mut result) {
+            debug!("{} confirmed votes in ballot box {}", n, bb_name);
+            confirmed_votes.insert(bb_name.clone(), n);
+        }
+        counting_circles_per_bb.insert(bb_name.clone(), counting_circles_of_bb(&bb_name));
+    }
+    for group in group_ballot_boxes(&counting_circles_per_bb, cast_votes.keys()) {
+        if group
+            .ballot_boxes
+            .iter()
+            .any(|bb| !confirmed_votes.contains_key(bb))
+        {
+            debug!(
+                "Cast votes of {} not verified: confirmed votes unknown",
+                group.name()
+            );
+            continue;
+        }
+        verify_cast_votes(
+            &group,
+            &cast_votes,
+            &confirmed_votes,
+            context.config().strictness().cast_votes_tolerance,
+            &mut result,
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    fn set(ids: &[&str]) -> BTreeSet<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_group_ballot_boxes() {
+        let cc_per_bb = BTreeMap::from([
+            ("bb1".to_string(), set(&["1"])),
+            ("bb2".to_string(), set(&["2", "3"])),
+            ("bb3".to_string(), set(&["3"])),
+            ("bb4".to_string(), set(&[])),
+        ]);
+        let ech_0222_ccs = set(&["1", "2", "3", "4"]);
+        let groups = group_ballot_boxes(&cc_per_bb, ech_0222_ccs.iter());
+        assert_eq!(groups.len(), 4);
+        assert!(groups.contains(&BallotBoxGroup {
+            ballot_boxes: set(&["bb2", "bb3"]),
+            counting_circles: set(&["2", "3"])
+        }));
+        assert!(groups.contains(&BallotBoxGroup {
+            ballot_boxes: set(&["bb4"]),
+            counting_circles: set(&[])
+        }));
+        assert_eq!(
+            groups.last().unwrap(),
+            &BallotBoxGroup {
+                ballot_boxes: set(&[]),
+                counting_circles: set(&["4"])
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_cast_votes() {
+        let cast_votes = BTreeMap::from([("1".to_string(), 2), ("2".to_string(), 1)]);
+        let group = BallotBoxGroup {
+            ballot_boxes: set(&["bb1", "bb2"]),
+            counting_circles: set(&["1", "2"]),
+        };
+        let mut result = VerificationResult::new();
+        verify_cast_votes(
+            &group,
+            &cast_votes,
+            &BTreeMap::from([("bb1".to_string(), 1), ("bb2".to_string(), 2)]),
+            0,
+            &mut result,
+        );
+        assert!(result.is_ok().unwrap());
+        let mut result = VerificationResult::new();
+        verify_cast_votes(
+            &group,
+            &cast_votes,
+            &BTreeMap::from([("bb1".to_string(), 1), ("bb2".to_string(), 1)]),
+            0,
+            &mut result,
+        );
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failed_items().len(), 4);
+        let mut result = VerificationResult::new();
+        verify_cast_votes(
+            &group,
+            &cast_votes,
+            &BTreeMap::from([("bb1".to_string(), 1), ("bb2".to_string(), 1)]),
+            1,
+            &mut result,
+        );
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_votes_in_wrong_ballot_box() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut ech_0222 = mock_dir.tally().unwrap().ech_0222().unwrap();
+        let from = ech_0222
+            .counting_circles
+            .iter()
+            .position(|cc| cc.number_of_cast_votes() > 0)
+            .unwrap();
+        let to = ech_0222
+            .counting_circles
+            .iter()
+            .position(|cc| cc.number_of_cast_votes() == 0)
+            .unwrap();
+        let cc = ech_0222.counting_circles[from].clone();
+        ech_0222.counting_circles[to].votes = cc.votes;
+        ech_0222.counting_circles[to].election_group_ballots = cc.election_group_ballots;
+        ech_0222.counting_circles[from].votes.clear();
+        ech_0222.counting_circles[from]
+            .election_group_ballots
+            .clear();
+        mock_dir.unwrap_tally_mut().mock_ech_0222(&Ok(&ech_0222));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 2);
+    }
+
+    #[test]
+    fn test_nodes_not_consistent() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let bb_dir = mock_dir
+            .tally()
+            .unwrap()
+            .bb_directories()
+            .iter()
+            .position(|d| {
+                d.control_component_ballot_box_payload_iter()
+                    .any(|(_, p)| !p.unwrap().confirmed_encrypted_votes.is_empty())
+            })
+            .unwrap();
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[bb_dir]
+            .control_component_ballot_box_payload_iter()
+            .find(|(i, _)| *i == 2)
+            .unwrap()
+            .1
+            .unwrap();
+        payload.confirmed_encrypted_votes.pop();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[bb_dir]
+            .mock_control_component_ballot_box_payloads(2, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+    }
+}
@@ -0,0 +1,234 @@
+use super::{
+    super::super::result::{
+        create_verification_error, create_verification_failure, EntityKind, FailureCode,
+        VerificationEvent, VerificationResult,
+    },
+    x0801_ech0222_confirmed_votes_consistency::confirmed_votes_in_bb,
+};
+use crate::{
+    file_structure::{
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+
+/// Number of ciphertexts mixed for the given number of confirmed votes
+///
+/// If there are less than two confirmed votes, two trivial ciphertexts are added before the
+/// mixing (see GetMixnetInitialCiphertexts)
+fn number_of_mixed_ciphertexts(confirmed_votes: usize) -> usize {
+    match confirmed_votes < 2 {
+        true => confirmed_votes + 2,
+        false => confirmed_votes,
+    }
+}
+
+/// Push a failure if `found` is not `expected`
+fn verify_size(
+    found: usize,
+    expected: usize,
+    what: &str,
+    bb_name: &str,
+    result: &mut VerificationResult,
+) {
+    if found != expected {
+        result.push(
+            create_verification_failure!(format!(
+                "{} {}, but {} expected in ballot box {}",
+                found, what, expected, bb_name
+            ))
+            .with_code(FailureCode::Inconsistency),
+        );
+        result.push_failed_item(
+            "ballot_box",
+            bb_name,
+            &format!("{} {} ({} expected)", found, what, expected),
+        );
+    }
+}
+
+/// Verify the sizes of the payloads of the shuffle of a ballot box
+///
+/// Each node and the tally component shuffle and decrypt the ciphertexts of the confirmed votes
+/// (with the trivial ciphertexts), and the tally component gives the votes of the confirmed votes.
+fn verify_sizes_of_bb<B: BBDirectoryTrait>(bb_dir: &B, result: &mut VerificationResult) {
+    let bb_name = bb_dir.get_name();
+    let confirmed_votes = match confirmed_votes_in_bb(bb_dir, result) {
+        Some(n) => n,
+        None => return,
+    };
+    let expected = number_of_mixed_ciphertexts(confirmed_votes);
+    debug!(
+        "{} confirmed votes and {} mixed ciphertexts in ballot box {}",
+        confirmed_votes, expected, bb_name
+    );
+    for (i, p) in bb_dir.control_component_shuffle_payload_iter() {
+        match p {
+            Ok(p) => {
+                for (found, what) in [
+                    (
+                        p.verifiable_shuffle.shuffled_ciphertexts.len(),
+                        "shuffled ciphertexts",
+                    ),
+                    (
+                        p.verifiable_decryptions.ciphertexts.len(),
+                        "decrypted ciphertexts",
+                    ),
+                    (
+                        p.verifiable_decryptions.decryption_proofs.len(),
+                        "decryption proofs",
+                    ),
+                ] {
+                    verify_size(
+                        found,
+                        expected,
+                        &format!("{} of node {}", what, p.node_id),
+                        &bb_name,
+                        result,
+                    );
+                }
+            }
+            Err(e) => result.push(create_verification_error!(
+                format!(
+                    "Cannot extract control_component_shuffle_payload for node {} in ballot box {}",
+                    i, bb_name
+                ),
+                e
+            )),
+        }
+    }
+    match bb_dir.tally_component_shuffle_payload() {
+        Ok(p) => {
+            for (found, what) in [
+                (
+                    p.verifiable_shuffle.shuffled_ciphertexts.len(),
+                    "shuffled ciphertexts of the tally component",
+                ),
+                (
+                    p.verifiable_plaintext_decryption.decrypted_votes.len(),
+                    "decrypted votes of the tally component",
+                ),
+                (
+                    p.verifiable_plaintext_decryption.decryption_proofs.len(),
+                    "decryption proofs of the tally component",
+                ),
+            ] {
+                verify_size(found, expected, what, &bb_name, result);
+            }
+        }
+        Err(e) => result.push(create_verification_error!(
+            format!(
+                "Cannot extract tally_component_shuffle_payload in ballot box {}",
+                bb_name
+            ),
+            e
+        )),
+    }
+    match bb_dir.tally_component_votes_payload() {
+        Ok(p) => verify_size(
+            p.votes.len(),
+            confirmed_votes,
+            "votes of the tally component",
+            &bb_name,
+            result,
+        ),
+        Err(e) => result.push(create_verification_error!(
+            format!(
+                "Cannot extract tally_component_votes_payload in ballot box {}",
+                bb_name
+            ),
+            e
+        )),
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let mut bb_result = VerificationResult::new();
+        verify_sizes_of_bb(bb_dir, &mut bb_result);
+        bb_result.annotate(EntityKind::BallotBox, &bb_dir.get_name());
+        result.append(&mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_number_of_mixed_ciphertexts() {
+        assert_eq!(number_of_mixed_ciphertexts(0), 2);
+        assert_eq!(number_of_mixed_ciphertexts(1), 3);
+        assert_eq!(number_of_mixed_ciphertexts(2), 2);
+        assert_eq!(number_of_mixed_ciphertexts(10), 10);
+    }
+
+    #[test]
+    fn test_dropped_ciphertext() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[0]
+            .control_component_shuffle_payload_iter()
+            .find(|(i, _)| *i == 3)
+            .unwrap()
+            .1
+            .unwrap();
+        payload.verifiable_shuffle.shuffled_ciphertexts.pop();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[0]
+            .mock_control_component_shuffle_payloads(3, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failed_items().len(), 1);
+    }
+
+    #[test]
+    fn test_dropped_vote() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let bb_dir = mock_dir
+            .tally()
+            .unwrap()
+            .bb_directories()
+            .iter()
+            .position(|d| !d.tally_component_votes_payload().unwrap().votes.is_empty())
+            .unwrap();
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[bb_dir]
+            .tally_component_votes_payload()
+            .unwrap();
+        payload.votes.pop();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[bb_dir]
+            .mock_tally_component_votes_payload(&Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+    }
+}
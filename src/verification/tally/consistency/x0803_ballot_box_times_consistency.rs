@@ -0,0 +1,292 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, FailureCode, VerificationEvent,
+    VerificationResult,
+};
+use crate::{
+    data_structures::setup::{
+        election_event_configuration::Authorization,
+        election_event_context_payload::VerificationCardSetContext,
+    },
+    file_structure::{
+        setup_directory::SetupDirectoryTrait,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use chrono::{Duration, NaiveDateTime};
+use log::debug;
+use std::collections::BTreeSet;
+
+/// Identification of the authorization of the verification card set (alias without `vcs_`)
+fn authorization_id(vcs: &VerificationCardSetContext) -> &str {
+    vcs.verification_card_set_alias
+        .strip_prefix("vcs_")
+        .unwrap_or(&vcs.verification_card_set_alias)
+}
+
+/// Verify that the times of the ballot box in the election event context are the dates of the
+/// authorization in the configuration
+fn verify_authorization_times(
+    authorization: &Authorization,
+    vcs: &VerificationCardSetContext,
+    result: &mut VerificationResult,
+) {
+    let comparisons = [
+        (
+            "opening time",
+            authorization.from_date.to_string(),
+            vcs.ballot_box_start_time.to_string(),
+        ),
+        (
+            "closing time",
+            authorization.to_date.to_string(),
+            vcs.ballot_box_finish_time.to_string(),
+        ),
+        (
+            "grace period",
+            authorization.grace_period.to_string(),
+            vcs.grace_period.to_string(),
+        ),
+        (
+            "test flag",
+            authorization.test.to_string(),
+            vcs.test_ballot_box.to_string(),
+        ),
+    ];
+    for (what, config, context) in comparisons {
+        if config != context {
+            result.push(
+                create_verification_failure!(format!(
+                    "The {} of ballot box {} is {} in the configuration, but {} in the election event context",
+                    what, vcs.ballot_box_id, config, context
+                ))
+                .with_code(FailureCode::Inconsistency),
+            );
+            result.push_failed_item("ballot_box", &vcs.ballot_box_id, what);
+        }
+    }
+}
+
+/// Verify that the ballot box is decrypted after its official closing (grace period included)
+///
+/// The test ballot boxes can be decrypted during the voting period
+fn verify_decryption_time(
+    authorization: &Authorization,
+    decrypted_at: &NaiveDateTime,
+    result: &mut VerificationResult,
+) {
+    if authorization.test {
+        return;
+    }
+    let closing = authorization.to_date + Duration::seconds(authorization.grace_period as i64);
+    if decrypted_at < &closing {
+        result.push(
+            create_verification_failure!(format!(
+                "The ballot box {} ({}) is decrypted at {}, before its closing at {} (grace period included)",
+                authorization.name, authorization.identification, decrypted_at, closing
+            ))
+            .with_code(FailureCode::Inconsistency),
+        );
+        result.push_failed_item(
+            "ballot_box",
+            &authorization.identification,
+            "decrypted before the closing",
+        );
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let setup_dir = dir.unwrap_setup();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let authorizations = match setup_dir
+        .election_event_configuration()
+        .and_then(|c| c.authorizations())
+    {
+        Ok(a) => a,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract the authorizations of the configuration",
+                e
+            ));
+            return result;
+        }
+    };
+    let eec = match setup_dir.election_event_context_payload() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let contexts = &eec.election_event_context.verification_card_set_contexts;
+    let find_authorization = |id: &str| authorizations.iter().find(|a| a.identification == id);
+    for vcs in contexts.iter() {
+        match find_authorization(authorization_id(vcs)) {
+            Some(a) => verify_authorization_times(a, vcs, &mut result),
+            None => result.push(
+                create_verification_failure!(format!(
+                    "No authorization {} in the configuration for the ballot box {}",
+                    authorization_id(vcs),
+                    vcs.ballot_box_id
+                ))
+                .with_code(FailureCode::Inconsistency),
+            ),
+        }
+    }
+
+    // The control component ballot box payloads and the decrypt file contain no time. The
+    // decryption time is the creation of the raw data (eCH-0222)
+    let decrypted_at = match tally_dir.ech_0222() {
+        Ok(e) => e.creation_date_time,
+        Err(e) => {
+            result.push(create_verification_error!("Cannot extract the eCH-0222", e));
+            return result;
+        }
+    };
+    let decrypted_at = match decrypted_at {
+        Some(d) => d,
+        None => {
+            result.push(
+                create_verification_failure!(
+                    "creationDateTime missing in the eCH-0222: the decryption time cannot be verified"
+                )
+                .with_code(FailureCode::Inconsistency),
+            );
+            return result;
+        }
+    };
+    let mut tallied: BTreeSet<String> = BTreeSet::new();
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let bb_name = bb_dir.get_name();
+        match contexts.iter().find(|c| c.ballot_box_id == bb_name) {
+            Some(vcs) => {
+                tallied.insert(authorization_id(vcs).to_string());
+            }
+            None => debug!("Ballot box {} not found in the context", bb_name),
+        }
+    }
+    match tally_dir.e_voting_decrypt() {
+        Ok(d) => tallied.extend(
+            d.ballot_boxes
+                .iter()
+                .map(|bb| bb.ballot_box_identification.clone()),
+        ),
+        Err(e) => result.push(create_verification_error!(
+            "Cannot extract the evoting-decrypt",
+            e
+        )),
+    }
+    debug!(
+        "{} ballot boxes decrypted at {}",
+        tallied.len(),
+        decrypted_at
+    );
+    for id in tallied.iter() {
+        match find_authorization(id) {
+            Some(a) => verify_decryption_time(a, &decrypted_at, &mut result),
+            None => debug!("Authorization {} not found in the configuration", id),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    fn authorization(test: bool) -> Authorization {
+        Authorization {
+            identification: "1234".to_string(),
+            name: "bb".to_string(),
+            test,
+            from_date: NaiveDateTime::parse_from_str("2024-02-01T07:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap(),
+            to_date: NaiveDateTime::parse_from_str("2024-02-15T12:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap(),
+            grace_period: 900,
+        }
+    }
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_verify_decryption_time() {
+        let datetime = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+        let mut result = VerificationResult::new();
+        verify_decryption_time(
+            &authorization(false),
+            &datetime("2024-02-15T12:15:00"),
+            &mut result,
+        );
+        verify_decryption_time(
+            &authorization(true),
+            &datetime("2024-02-10T12:00:00"),
+            &mut result,
+        );
+        assert!(result.is_ok().unwrap());
+        verify_decryption_time(
+            &authorization(false),
+            &datetime("2024-02-15T12:14:59"),
+            &mut result,
+        );
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failed_items().len(), 1);
+    }
+
+    #[test]
+    fn test_wrong_finish_time() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut eec = mock_dir
+            .unwrap_setup()
+            .election_event_context_payload()
+            .unwrap();
+        let vcs = &mut eec.election_event_context.verification_card_set_contexts[0];
+        vcs.ballot_box_finish_time -= Duration::days(1);
+        mock_dir
+            .unwrap_setup_mut()
+            .mock_election_event_context_payload(&Ok(&eec));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failed_items().len(), 1);
+    }
+
+    #[test]
+    fn test_missing_creation_date_time() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut ech_0222 = mock_dir.tally().unwrap().ech_0222().unwrap();
+        ech_0222.creation_date_time = None;
+        mock_dir.unwrap_tally_mut().mock_ech_0222(&Ok(&ech_0222));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+    }
+}
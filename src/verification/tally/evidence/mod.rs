@@ -1,11 +1,125 @@
-use crate::{config::Config, verification::meta_data::VerificationMetaDataList};
+mod x1001_plaintext_equality_proofs;
+mod x1002_vote_exponentiation_proofs;
+mod x1003_ech0110_plausibility;
+mod x1004_ech0110_ech0222_consistency;
+mod x1005_online_control_components_decryption_proofs;
+mod x1006_tally_control_component_decryption_proofs;
 
-use super::super::suite::VerificationList;
+use super::super::{
+    result::{create_verification_failure, VerificationEvent, VerificationResult},
+    suite::{SuiteBuildError, VerificationList},
+    verifications::Verification,
+};
+use crate::{
+    config::Config,
+    crypto_helpers::verify_decryption,
+    data_structures::common_types::{DecryptionProof, ExponentiatedEncryptedElement},
+    verification::meta_data::VerificationMetaDataList,
+};
+use anyhow::anyhow;
+use log::debug;
+use rayon::prelude::*;
+use rug::Integer;
+use rust_ev_crypto_primitives::EncryptionParameters;
 
 pub fn get_verifications<'a>(
-    _metadata_list: &'a VerificationMetaDataList,
-    _config: &'static Config,
-) -> VerificationList<'a> {
-    let res = vec![];
-    VerificationList(res)
+    metadata_list: &'a VerificationMetaDataList,
+    config: &'static Config,
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
+        Verification::new(
+            "X.10.01",
+            "VerifyPlaintextEqualityProofs",
+            x1001_plaintext_equality_proofs::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.10.02",
+            "VerifyVoteExponentiationProofs",
+            x1002_vote_exponentiation_proofs::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.10.03",
+            "VerifyEch0110Plausibility",
+            x1003_ech0110_plausibility::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.10.04",
+            "VerifyEch0110Ech0222Consistency",
+            x1004_ech0110_ech0222_consistency::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.10.05",
+            "VerifyOnlineControlComponentsDecryptionProofs",
+            x1005_online_control_components_decryption_proofs::fn_verification,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.10.06",
+            "VerifyTallyControlComponentDecryptionProofs",
+            x1006_tally_control_component_decryption_proofs::fn_verification,
+            metadata_list,
+            config,
+        ),
+    ])
+}
+
+/// Verify the decryption proofs of the ciphertexts (algorithm VerifyDecryptions)
+///
+/// `messages` are the decrypted messages of the ciphertexts. `name` describes the source of the
+/// proofs in the failures (e.g. the node and the ballot box).
+///
+/// Return `true` if all the proofs are valid
+#[allow(clippy::too_many_arguments)]
+fn verify_decryption_proofs(
+    eg: &EncryptionParameters,
+    ciphertexts: &[ExponentiatedEncryptedElement],
+    pk: &[Integer],
+    messages: &[Vec<Integer>],
+    proofs: &[DecryptionProof],
+    i_aux: &[String],
+    name: &str,
+    result: &mut VerificationResult,
+) -> bool {
+    if ciphertexts.len() != messages.len() || ciphertexts.len() != proofs.len() {
+        result.push(create_verification_failure!(format!(
+            "{} ciphertexts, {} decrypted messages and {} decryption proofs for {}",
+            ciphertexts.len(),
+            messages.len(),
+            proofs.len(),
+            name
+        )));
+        return false;
+    }
+    debug!("Verify {} decryption proofs for {}", proofs.len(), name);
+    let failures: Vec<VerificationEvent> = (0..proofs.len())
+        .into_par_iter()
+        .filter_map(|i| {
+            match verify_decryption(eg, &ciphertexts[i], pk, &messages[i], &proofs[i], i_aux) {
+                Ok(true) => None,
+                Ok(false) => Some(create_verification_failure!(format!(
+                    "The decryption proof at pos {} is not valid for {}",
+                    i, name
+                ))),
+                Err(e) => Some(create_verification_failure!(
+                    format!(
+                        "Cannot verify the decryption proof at pos {} for {}",
+                        i, name
+                    ),
+                    e
+                )),
+            }
+        })
+        .collect();
+    let ok = failures.is_empty();
+    failures.into_iter().for_each(|f| result.push(f));
+    ok
 }
@@ -0,0 +1,213 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, FailureCode,
+    VerificationEvent, VerificationResult,
+};
+use crate::{
+    crypto_helpers::{create_vote_i_aux, verify_plaintext_equality, PlaintextEqualityStatement},
+    data_structures::tally::control_component_ballot_box_payload::{
+        ConfirmedEncryptedVote, ControlComponentBallotBoxPayload,
+    },
+    file_structure::{
+        setup_directory::SetupDirectoryTrait,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use rayon::prelude::*;
+use rug::Integer;
+
+/// Verify the plaintext equality proof of one confirmed vote
+///
+/// Return the failure, if any
+fn verify_vote(
+    payload: &ControlComponentBallotBoxPayload,
+    vote: &ConfirmedEncryptedVote,
+    el_pk: &[Integer],
+    pk_ccr: &[Integer],
+) -> Option<VerificationEvent> {
+    let vc_id = &vote.context_ids.verification_card_id;
+    let eg = &payload.encryption_group;
+    let res = PlaintextEqualityStatement::for_vote(
+        eg,
+        &vote.exponentiated_encrypted_vote,
+        &vote.encrypted_partial_choice_return_codes,
+        el_pk,
+        pk_ccr,
+    )
+    .and_then(|statement| {
+        verify_plaintext_equality(
+            eg,
+            &statement,
+            &vote.plaintext_equality_proof,
+            &create_vote_i_aux(&payload.election_event_id, vc_id),
+        )
+    });
+    let failure = match res {
+        Ok(true) => return None,
+        Ok(false) => create_verification_failure!(format!(
+            "The plaintext equality proof of the vote of the verification card {} is not valid for node {} in ballot box {}",
+            vc_id, payload.node_id, payload.ballot_box_id
+        )),
+        Err(e) => create_verification_failure!(
+            format!(
+                "Cannot verify the plaintext equality proof of the vote of the verification card {} for node {} in ballot box {}",
+                vc_id, payload.node_id, payload.ballot_box_id
+            ),
+            e
+        ),
+    };
+    Some(
+        failure
+            .with_code(FailureCode::ProofInvalid)
+            .with_entity(EntityKind::VerificationCard, vc_id)
+            .with_entity(EntityKind::ControlComponent, payload.node_id),
+    )
+}
+
+/// Verify the plaintext equality proofs of the confirmed votes of all the control components in
+/// a ballot box
+///
+/// Each control component delivers its own list of the confirmed votes, so that the proofs are
+/// verified in every payload
+fn verify_bb_plaintext_equality_proofs<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    el_pk: &[Integer],
+    pk_ccr: &[Integer],
+    result: &mut VerificationResult,
+) {
+    for (i, p) in bb_dir.control_component_ballot_box_payload_iter() {
+        let payload = match p {
+            Ok(p) => p,
+            Err(e) => {
+                result.push(create_verification_error!(
+                    format!(
+                        "Cannot extract control_component_ballot_box_payload {} in ballot box {}",
+                        i,
+                        bb_dir.get_name()
+                    ),
+                    e
+                ));
+                continue;
+            }
+        };
+        debug!(
+            "Verify {} plaintext equality proofs of node {} in ballot box {}",
+            payload.confirmed_encrypted_votes.len(),
+            payload.node_id,
+            bb_dir.get_name()
+        );
+        let failures: Vec<VerificationEvent> = payload
+            .confirmed_encrypted_votes
+            .par_iter()
+            .filter_map(|vote| verify_vote(&payload, vote, el_pk, pk_ccr))
+            .collect();
+        for f in failures {
+            for e in f.entities().iter() {
+                if e.kind == EntityKind::VerificationCard {
+                    result.push_failed_item(
+                        "verification_card",
+                        &e.id,
+                        &format!("plaintext equality proof of node {}", payload.node_id),
+                    );
+                }
+            }
+            result.push(f);
+        }
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let keys = match dir.unwrap_setup().setup_component_public_keys_payload() {
+        Ok(o) => o.setup_component_public_keys,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract setup_component_public_keys_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let mut bb_result = VerificationResult::new();
+        verify_bb_plaintext_equality_proofs(
+            bb_dir,
+            &keys.election_public_key,
+            &keys.choice_return_codes_encryption_public_key,
+            &mut bb_result,
+        );
+        bb_result.annotate(EntityKind::BallotBox, &bb_dir.get_name());
+        result.append_sub_result("ballot_box", &bb_dir.get_name(), &mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+        assert_eq!(
+            result.sub_results_summary()[0].ok,
+            dir.tally().unwrap().bb_directories().len()
+        );
+    }
+
+    #[test]
+    fn test_wrong_proof() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let (pos, mut payload) = mock_dir
+            .tally()
+            .unwrap()
+            .bb_directories()
+            .iter()
+            .enumerate()
+            .find_map(|(pos, d)| {
+                let (_, p) = d
+                    .control_component_ballot_box_payload_iter()
+                    .next()
+                    .unwrap();
+                let p = p.unwrap();
+                (!p.confirmed_encrypted_votes.is_empty()).then_some((pos, p))
+            })
+            .unwrap();
+        payload.confirmed_encrypted_votes[0]
+            .plaintext_equality_proof
+            .e += 1;
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[pos]
+            .mock_control_component_ballot_box_payloads(payload.node_id, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        let vc_id = &payload.confirmed_encrypted_votes[0]
+            .context_ids
+            .verification_card_id;
+        assert_eq!(&result.failed_items()[0].id, vc_id);
+        assert_eq!(result.failures()[0].entities().len(), 3);
+    }
+}
@@ -0,0 +1,280 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, FailureCode,
+    VerificationEvent, VerificationResult, VerificationResultTrait,
+};
+use crate::{
+    batch_exponentiation::BatchExponentiation,
+    crypto_helpers::{
+        create_vote_i_aux, exponentiation_vote_bases_and_images, verify_exponentiation_with,
+    },
+    data_structures::tally::control_component_ballot_box_payload::{
+        ConfirmedEncryptedVote, ControlComponentBallotBoxPayload,
+    },
+    file_structure::{
+        setup_directory::{SetupDirectoryTrait, VCSDirectoryTrait},
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use rayon::prelude::*;
+use rug::Integer;
+use std::collections::HashMap;
+
+/// Verify the exponentiation proof of one confirmed vote
+///
+/// `k_id` is the public key of the verification card of the vote, `None` if the verification
+/// card is not found in the setup. The exponentiations are computed with `exp`. Return the
+/// failure, if any
+fn verify_vote(
+    exp: &BatchExponentiation,
+    payload: &ControlComponentBallotBoxPayload,
+    vote: &ConfirmedEncryptedVote,
+    k_id: Option<&Integer>,
+) -> Option<VerificationEvent> {
+    let vc_id = &vote.context_ids.verification_card_id;
+    let eg = &payload.encryption_group;
+    let failure = match k_id {
+        None => create_verification_failure!(format!(
+            "The verification card {} of the vote for node {} in ballot box {} is not in the setup",
+            vc_id, payload.node_id, payload.ballot_box_id
+        ))
+        .with_code(FailureCode::Inconsistency),
+        Some(k_id) => {
+            let res = exponentiation_vote_bases_and_images(
+                eg,
+                &vote.encrypted_vote,
+                k_id,
+                &vote.exponentiated_encrypted_vote,
+            )
+            .and_then(|(gs, ys)| {
+                verify_exponentiation_with(
+                    exp,
+                    eg,
+                    &gs,
+                    &ys,
+                    vote.exponentiation_proof.as_tuple(),
+                    &create_vote_i_aux(&payload.election_event_id, vc_id),
+                )
+            });
+            let failure = match res {
+                Ok(true) => return None,
+                Ok(false) => create_verification_failure!(format!(
+                    "The exponentiation proof of the vote of the verification card {} is not valid for node {} in ballot box {}",
+                    vc_id, payload.node_id, payload.ballot_box_id
+                )),
+                Err(e) => create_verification_failure!(
+                    format!(
+                        "Cannot verify the exponentiation proof of the vote of the verification card {} for node {} in ballot box {}",
+                        vc_id, payload.node_id, payload.ballot_box_id
+                    ),
+                    e
+                ),
+            };
+            failure.with_code(FailureCode::ProofInvalid)
+        }
+    };
+    Some(
+        failure
+            .with_entity(EntityKind::VerificationCard, vc_id)
+            .with_entity(EntityKind::ControlComponent, payload.node_id),
+    )
+}
+
+/// Verify the exponentiation proofs of the confirmed votes of all the control components in a
+/// ballot box
+///
+/// The result of each control component is recorded as sub-result, so that the report gives the
+/// number of nodes verified successfully
+fn verify_bb_exponentiation_proofs<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    keys: &HashMap<String, Integer>,
+    result: &mut VerificationResult,
+) {
+    let bb_name = bb_dir.get_name();
+    for (i, p) in bb_dir.control_component_ballot_box_payload_iter() {
+        let mut node_result = VerificationResult::new();
+        match p {
+            Ok(payload) => {
+                debug!(
+                    "Verify {} exponentiation proofs of node {} in ballot box {}",
+                    payload.confirmed_encrypted_votes.len(),
+                    payload.node_id,
+                    bb_name
+                );
+                let exp = BatchExponentiation::new(&payload.encryption_group);
+                let failures: Vec<VerificationEvent> = payload
+                    .confirmed_encrypted_votes
+                    .par_iter()
+                    .filter_map(|vote| {
+                        verify_vote(
+                            &exp,
+                            &payload,
+                            vote,
+                            keys.get(&vote.context_ids.verification_card_id),
+                        )
+                    })
+                    .collect();
+                for f in failures {
+                    for e in f.entities().iter() {
+                        if e.kind == EntityKind::VerificationCard {
+                            node_result.push_failed_item(
+                                "verification_card",
+                                &e.id,
+                                &format!("exponentiation proof of node {}", payload.node_id),
+                            );
+                        }
+                    }
+                    node_result.push(f);
+                }
+            }
+            Err(e) => node_result.push(create_verification_error!(
+                format!(
+                    "Cannot extract control_component_ballot_box_payload {} in ballot box {}",
+                    i, bb_name
+                ),
+                e
+            )),
+        }
+        result.append_sub_result("node", &format!("{}/{}", bb_name, i), &mut node_result);
+    }
+}
+
+/// Public keys of the verification cards of all the verification card sets, per verification
+/// card id
+fn verification_card_public_keys<S: SetupDirectoryTrait>(
+    setup_dir: &S,
+    result: &mut VerificationResult,
+) -> HashMap<String, Integer> {
+    let mut res = HashMap::new();
+    for vcs in setup_dir.vcs_directories().iter() {
+        match vcs.setup_component_tally_data_payload() {
+            Ok(p) => res.extend(
+                p.verification_card_ids
+                    .iter()
+                    .zip(p.verification_card_public_keys.iter())
+                    .filter_map(|(id, pk)| pk.first().map(|k| (id.clone(), k.clone()))),
+            ),
+            Err(e) => result.push(create_verification_error!(
+                format!(
+                    "Cannot extract setup_component_tally_data_payload in {}",
+                    vcs.get_name()
+                ),
+                e
+            )),
+        }
+    }
+    res
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let keys = verification_card_public_keys(dir.unwrap_setup(), &mut result);
+    if result.has_errors().unwrap_or(false) {
+        return result;
+    }
+    for bb_dir in tally_dir.bb_directories().iter() {
+        let mut bb_result = VerificationResult::new();
+        verify_bb_exponentiation_proofs(bb_dir, &keys, &mut bb_result);
+        bb_result.annotate(EntityKind::BallotBox, &bb_dir.get_name());
+        result.append_sub_result("ballot_box", &bb_dir.get_name(), &mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::super::super::VerificationPeriod, *};
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+        let summary = result.sub_results_summary();
+        let nodes = summary.iter().find(|s| s.kind == "node").unwrap();
+        assert_eq!(nodes.ok, nodes.total);
+        let bbs = summary.iter().find(|s| s.kind == "ballot_box").unwrap();
+        assert_eq!(bbs.ok, dir.tally().unwrap().bb_directories().len());
+    }
+
+    #[test]
+    fn test_wrong_proof() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let (pos, mut payload) = mock_dir
+            .tally()
+            .unwrap()
+            .bb_directories()
+            .iter()
+            .enumerate()
+            .find_map(|(pos, d)| {
+                let (_, p) = d
+                    .control_component_ballot_box_payload_iter()
+                    .next()
+                    .unwrap();
+                let p = p.unwrap();
+                (!p.confirmed_encrypted_votes.is_empty()).then_some((pos, p))
+            })
+            .unwrap();
+        payload.confirmed_encrypted_votes[0].exponentiation_proof.e += 1;
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[pos]
+            .mock_control_component_ballot_box_payloads(payload.node_id, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failures()[0].code(), FailureCode::ProofInvalid);
+        let vc_id = &payload.confirmed_encrypted_votes[0]
+            .context_ids
+            .verification_card_id;
+        assert_eq!(&result.failed_items()[0].id, vc_id);
+        let summary = result.sub_results_summary();
+        let nodes = summary.iter().find(|s| s.kind == "node").unwrap();
+        assert_eq!(nodes.ok, nodes.total - 1);
+    }
+
+    #[test]
+    fn test_unknown_verification_card() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let (pos, mut payload) = mock_dir
+            .tally()
+            .unwrap()
+            .bb_directories()
+            .iter()
+            .enumerate()
+            .find_map(|(pos, d)| {
+                let (_, p) = d
+                    .control_component_ballot_box_payload_iter()
+                    .next()
+                    .unwrap();
+                let p = p.unwrap();
+                (!p.confirmed_encrypted_votes.is_empty()).then_some((pos, p))
+            })
+            .unwrap();
+        payload.confirmed_encrypted_votes[0]
+            .context_ids
+            .verification_card_id = "unknown".to_string();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[pos]
+            .mock_control_component_ballot_box_payloads(payload.node_id, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failures()[0].code(), FailureCode::Inconsistency);
+    }
+}
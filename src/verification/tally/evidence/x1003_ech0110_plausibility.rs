@@ -0,0 +1,241 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, FailureCode, VerificationEvent,
+    VerificationResult,
+};
+use crate::{
+    data_structures::tally::ech_0110::{ResultBallot, ResultCountingCircle, ResultElectionGroup},
+    file_structure::{tally_directory::TallyDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+
+/// Counts of the ballots of a ballot of a vote or of an election group
+struct BallotCounts {
+    received: usize,
+    accounted: usize,
+    unaccounted: usize,
+    blank: usize,
+    invalid: usize,
+}
+
+impl From<&ResultBallot> for BallotCounts {
+    fn from(value: &ResultBallot) -> Self {
+        Self {
+            received: value.count_of_received_ballots,
+            accounted: value.count_of_accounted_ballots,
+            unaccounted: value.count_of_unaccounted_ballots,
+            blank: value.count_of_unaccounted_blank_ballots,
+            invalid: value.count_of_unaccounted_invalid_ballots,
+        }
+    }
+}
+
+impl From<&ResultElectionGroup> for BallotCounts {
+    fn from(value: &ResultElectionGroup) -> Self {
+        Self {
+            received: value.count_of_received_ballots,
+            accounted: value.count_of_accounted_ballots,
+            unaccounted: value.count_of_unaccounted_ballots,
+            blank: value.count_of_unaccounted_blank_ballots,
+            invalid: value.count_of_unaccounted_invalid_ballots,
+        }
+    }
+}
+
+/// Push a failure for the counting circle if `found` is not `expected`
+fn verify_count(
+    found: usize,
+    expected: usize,
+    what: &str,
+    cc_id: &str,
+    result: &mut VerificationResult,
+) {
+    if found != expected {
+        result.push(
+            create_verification_failure!(format!(
+                "{}: {} is not {} in counting circle {}",
+                what, found, expected, cc_id
+            ))
+            .with_code(FailureCode::Inconsistency),
+        );
+        result.push_failed_item("counting_circle", cc_id, what);
+    }
+}
+
+/// Verify the counts of the ballots `name` in the counting circle
+///
+/// The received ballots are the accounted and the unaccounted ballots, the unaccounted ballots
+/// are the blank and the invalid ballots, and each received ballot needs a received voting card
+fn verify_ballot_counts(
+    counts: &BallotCounts,
+    name: &str,
+    cc: &ResultCountingCircle,
+    result: &mut VerificationResult,
+) {
+    let cc_id = cc.counting_circle_id.as_str();
+    verify_count(
+        counts.received,
+        counts.accounted + counts.unaccounted,
+        &format!("received ballots of {} (accounted + unaccounted)", name),
+        cc_id,
+        result,
+    );
+    verify_count(
+        counts.unaccounted,
+        counts.blank + counts.invalid,
+        &format!("unaccounted ballots of {} (blank + invalid)", name),
+        cc_id,
+        result,
+    );
+    if counts.received > cc.count_of_received_valid_voting_cards {
+        result.push(
+            create_verification_failure!(format!(
+                "{} received ballots of {} are more than the {} received valid voting cards in counting circle {}",
+                counts.received, name, cc.count_of_received_valid_voting_cards, cc_id
+            ))
+            .with_code(FailureCode::Inconsistency),
+        );
+        result.push_failed_item(
+            "counting_circle",
+            cc_id,
+            &format!("received ballots of {} (voting cards)", name),
+        );
+    }
+}
+
+/// Verify the plausibility of the results of the counting circle
+///
+/// The answers of each question and of each tie break are counted for each accounted ballot
+fn verify_counting_circle(cc: &ResultCountingCircle, result: &mut VerificationResult) {
+    let cc_id = cc.counting_circle_id.as_str();
+    for ballot in cc.votes.iter().flat_map(|v| v.ballots.iter()) {
+        let name = format!("ballot {}", ballot.ballot_identification);
+        verify_ballot_counts(&BallotCounts::from(ballot), &name, cc, result);
+        for q in ballot.questions.iter() {
+            verify_count(
+                q.count_of_answer_yes
+                    + q.count_of_answer_no
+                    + q.count_of_answer_invalid
+                    + q.count_of_answer_empty,
+                ballot.count_of_accounted_ballots,
+                &format!(
+                    "answers to question {} of {} (accounted ballots)",
+                    q.question_identification, name
+                ),
+                cc_id,
+                result,
+            );
+        }
+        for t in ballot.tie_breaks.iter() {
+            verify_count(
+                t.count_in_favour_of.iter().map(|(_, n)| n).sum::<usize>()
+                    + t.count_of_answer_invalid
+                    + t.count_of_answer_empty,
+                ballot.count_of_accounted_ballots,
+                &format!(
+                    "answers to tie break {} of {} (accounted ballots)",
+                    t.question_identification, name
+                ),
+                cc_id,
+                result,
+            );
+        }
+    }
+    for group in cc.election_groups.iter() {
+        verify_ballot_counts(
+            &BallotCounts::from(group),
+            &format!("election group {}", group.election_group_identification),
+            cc,
+            result,
+        );
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let ech_0110 = match tally_dir.ech_0110() {
+        Ok(e) => e,
+        Err(e) => {
+            result.push(create_verification_error!("Cannot extract the eCH-0110", e));
+            return result;
+        }
+    };
+    for cc in ech_0110.counting_circles.iter() {
+        debug!(
+            "Verify the plausibility of the results of counting circle {}",
+            cc.counting_circle_id
+        );
+        verify_counting_circle(cc, &mut result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_unaccounted_ballots() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut ech_0110 = mock_dir.tally().unwrap().ech_0110().unwrap();
+        ech_0110.counting_circles[0].votes[0].ballots[0].count_of_unaccounted_blank_ballots += 1;
+        mock_dir.unwrap_tally_mut().mock_ech_0110(&Ok(&ech_0110));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert_eq!(result.failed_items().len(), 1);
+    }
+
+    #[test]
+    fn test_more_ballots_than_voting_cards() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut ech_0110 = mock_dir.tally().unwrap().ech_0110().unwrap();
+        let cc = ech_0110
+            .counting_circles
+            .iter_mut()
+            .find(|cc| cc.count_of_received_valid_voting_cards > 0)
+            .unwrap();
+        cc.count_of_received_valid_voting_cards = 0;
+        mock_dir.unwrap_tally_mut().mock_ech_0110(&Ok(&ech_0110));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_answers() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut ech_0110 = mock_dir.tally().unwrap().ech_0110().unwrap();
+        ech_0110.counting_circles[0].votes[0].ballots[0].questions[0].count_of_answer_yes += 1;
+        mock_dir.unwrap_tally_mut().mock_ech_0110(&Ok(&ech_0110));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert_eq!(result.failures().len(), 1);
+    }
+}
@@ -0,0 +1,189 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, FailureCode, VerificationEvent,
+    VerificationResult,
+};
+use crate::{
+    data_structures::tally::{ech_0110::ECH0110, ech_0222::ECH0222},
+    file_structure::{tally_directory::TallyDirectoryTrait, VerificationDirectoryTrait},
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Key of the received ballots: id of the counting circle and name of the ballot (ballot of a
+/// vote or election group)
+type BallotKey = (String, String);
+
+/// Number of received ballots per counting circle and ballot in the eCH-0110
+fn received_ballots_ech_0110(ech_0110: &ECH0110) -> BTreeMap<BallotKey, usize> {
+    let mut res = BTreeMap::new();
+    for cc in ech_0110.counting_circles.iter() {
+        for b in cc.votes.iter().flat_map(|v| v.ballots.iter()) {
+            *res.entry((
+                cc.counting_circle_id.clone(),
+                format!("ballot {}", b.ballot_identification),
+            ))
+            .or_default() += b.count_of_received_ballots;
+        }
+        for g in cc.election_groups.iter() {
+            *res.entry((
+                cc.counting_circle_id.clone(),
+                format!("election group {}", g.election_group_identification),
+            ))
+            .or_default() += g.count_of_received_ballots;
+        }
+    }
+    res
+}
+
+/// Number of raw ballots per counting circle and ballot in the eCH-0222
+fn raw_ballots_ech_0222(ech_0222: &ECH0222) -> BTreeMap<BallotKey, usize> {
+    let mut res = BTreeMap::new();
+    for cc in ech_0222.counting_circles.iter() {
+        for b in cc.votes.iter().flat_map(|v| v.ballots.iter()) {
+            *res.entry((
+                cc.counting_circle_id.clone(),
+                format!("ballot {}", b.ballot_identification),
+            ))
+            .or_default() += 1;
+        }
+        for g in cc.election_group_ballots.iter() {
+            *res.entry((
+                cc.counting_circle_id.clone(),
+                format!(
+                    "election group {}",
+                    g.election_group_identification.as_deref().unwrap_or("-")
+                ),
+            ))
+            .or_default() += 1;
+        }
+    }
+    res
+}
+
+/// Verify that the received ballots of the eCH-0110 are the raw ballots of the eCH-0222
+///
+/// A ballot missing in one of the files counts zero ballots
+fn verify_received_ballots(
+    received: &BTreeMap<BallotKey, usize>,
+    raw: &BTreeMap<BallotKey, usize>,
+    result: &mut VerificationResult,
+) {
+    let keys: BTreeSet<&BallotKey> = received.keys().chain(raw.keys()).collect();
+    for key @ (cc_id, name) in keys {
+        let nb_received = received.get(key).copied().unwrap_or_default();
+        let nb_raw = raw.get(key).copied().unwrap_or_default();
+        if nb_received != nb_raw {
+            result.push(
+                create_verification_failure!(format!(
+                    "{} received ballots of {} in eCH-0110, but {} raw ballots in eCH-0222 in counting circle {}",
+                    nb_received, name, nb_raw, cc_id
+                ))
+                .with_code(FailureCode::Inconsistency),
+            );
+            result.push_failed_item(
+                "counting_circle",
+                cc_id,
+                &format!("received ballots of {}", name),
+            );
+        }
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let received = match tally_dir.ech_0110() {
+        Ok(e) => received_ballots_ech_0110(&e),
+        Err(e) => {
+            result.push(create_verification_error!("Cannot extract the eCH-0110", e));
+            return result;
+        }
+    };
+    let raw = match tally_dir.ech_0222() {
+        Ok(e) => raw_ballots_ech_0222(&e),
+        Err(e) => {
+            result.push(create_verification_error!("Cannot extract the eCH-0222", e));
+            return result;
+        }
+    };
+    debug!(
+        "{} ballots in eCH-0110 and {} ballots in eCH-0222",
+        received.len(),
+        raw.len()
+    );
+    verify_received_ballots(&received, &raw, &mut result);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_verify_received_ballots() {
+        let key = |cc: &str, b: &str| (cc.to_string(), b.to_string());
+        let received = BTreeMap::from([(key("1", "ballot a"), 2), (key("2", "ballot a"), 0)]);
+        let mut result = VerificationResult::new();
+        verify_received_ballots(
+            &received,
+            &BTreeMap::from([(key("1", "ballot a"), 2)]),
+            &mut result,
+        );
+        assert!(result.is_ok().unwrap());
+        let mut result = VerificationResult::new();
+        verify_received_ballots(
+            &received,
+            &BTreeMap::from([(key("1", "ballot a"), 1), (key("3", "ballot a"), 1)]),
+            &mut result,
+        );
+        assert_eq!(result.failures().len(), 2);
+        assert_eq!(result.failed_items().len(), 2);
+    }
+
+    #[test]
+    fn test_missing_raw_ballot() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut ech_0222 = mock_dir.tally().unwrap().ech_0222().unwrap();
+        let cc = ech_0222
+            .counting_circles
+            .iter_mut()
+            .find(|cc| cc.votes.iter().any(|v| !v.ballots.is_empty()))
+            .unwrap();
+        cc.votes
+            .iter_mut()
+            .find(|v| !v.ballots.is_empty())
+            .unwrap()
+            .ballots
+            .pop();
+        mock_dir.unwrap_tally_mut().mock_ech_0222(&Ok(&ech_0222));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(!result.has_errors().unwrap());
+        assert_eq!(result.failures().len(), 1);
+    }
+}
@@ -0,0 +1,216 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, VerificationEvent,
+    VerificationResult,
+};
+use super::verify_decryption_proofs;
+use crate::{
+    crypto_helpers::DecryptionProofContext,
+    data_structures::setup::control_component_public_keys_payload::ControlComponentPublicKeys,
+    file_structure::{
+        setup_directory::SetupDirectoryTrait,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use rug::Integer;
+use rust_ev_crypto_primitives::EncryptionParameters;
+
+/// Verify the decryption proofs of the online control components in a ballot box
+///
+/// Each node j proves that it partially decrypted the shuffled ciphertexts with its key
+/// corresponding to the CCM_j election public key.
+///
+/// The shuffle arguments are not verified.
+fn verify_bb_online_decryptions<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    eg: &EncryptionParameters,
+    ee_id: &str,
+    cc_pks: &[ControlComponentPublicKeys],
+    result: &mut VerificationResult,
+) {
+    let bb_name = bb_dir.get_name();
+    for (i, p) in bb_dir.control_component_shuffle_payload_iter() {
+        let p = match p {
+            Ok(p) => p,
+            Err(e) => {
+                result.push(create_verification_error!(
+                    format!(
+                        "Cannot extract control_component_shuffle_payload for node {} in ballot box {}",
+                        i, bb_name
+                    ),
+                    e
+                ));
+                continue;
+            }
+        };
+        let j = p.node_id;
+        let pk = match cc_pks.iter().find(|pk| pk.node_id == j) {
+            Some(pk) => &pk.ccmj_election_public_key,
+            None => {
+                result.push(
+                    create_verification_failure!(format!(
+                        "No election public key for the node {} of the shuffle payload in ballot box {}",
+                        j, bb_name
+                    ))
+                    .with_entity(EntityKind::ControlComponent, j),
+                );
+                result.push_failed_item("ballot_box", &bb_name, "unknown node");
+                continue;
+            }
+        };
+        let i_aux =
+            DecryptionProofContext::OnlineControlComponent { node_id: j }.i_aux(ee_id, &bb_name);
+        let messages: Vec<Vec<Integer>> = p
+            .verifiable_decryptions
+            .ciphertexts
+            .iter()
+            .map(|c| c.phis.clone())
+            .collect();
+        let mut node_result = VerificationResult::new();
+        if !verify_decryption_proofs(
+            eg,
+            &p.verifiable_shuffle.shuffled_ciphertexts,
+            pk,
+            &messages,
+            &p.verifiable_decryptions.decryption_proofs,
+            &i_aux,
+            &format!("node {} in ballot box {}", j, bb_name),
+            &mut node_result,
+        ) {
+            result.push_failed_item("ballot_box", &bb_name, "decryption proof");
+        }
+        node_result.annotate(EntityKind::ControlComponent, &j.to_string());
+        result.append(&mut node_result);
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let setup_dir = dir.unwrap_setup();
+    let ee_context = match setup_dir.election_event_context_payload() {
+        Ok(o) => o,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let cc_pks = match setup_dir.setup_component_public_keys_payload() {
+        Ok(o) => {
+            o.setup_component_public_keys
+                .combined_control_component_public_keys
+        }
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract setup_component_public_keys_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let ee_id = &ee_context.election_event_context.election_event_id;
+    for bb_dir in tally_dir.bb_directories().iter() {
+        debug!(
+            "Verify online decryptions of ballot box {}",
+            bb_dir.get_name()
+        );
+        let mut bb_result = VerificationResult::new();
+        verify_bb_online_decryptions(
+            bb_dir,
+            &ee_context.encryption_group,
+            ee_id,
+            &cc_pks,
+            &mut bb_result,
+        );
+        bb_result.annotate(EntityKind::BallotBox, &bb_dir.get_name());
+        result.append_sub_result("ballot_box", &bb_dir.get_name(), &mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{
+            result::{Entity, VerificationResultTrait},
+            VerificationPeriod,
+        },
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+        assert_eq!(
+            result.sub_results_summary()[0].ok,
+            dir.tally().unwrap().bb_directories().len()
+        );
+    }
+
+    #[test]
+    fn test_wrong_proof() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[0]
+            .control_component_shuffle_payload_iter()
+            .find(|(i, _)| *i == 3)
+            .unwrap()
+            .1
+            .unwrap();
+        payload.verifiable_decryptions.decryption_proofs[0].e += 1;
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[0]
+            .mock_control_component_shuffle_payloads(3, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert!(!result.failed_items().is_empty());
+        assert_eq!(
+            result.failures()[0].entities(),
+            [
+                Entity::new(EntityKind::ControlComponent, payload.node_id),
+                Entity::new(
+                    EntityKind::BallotBox,
+                    mock_dir.tally().unwrap().bb_directories()[0].get_name()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrong_number_of_proofs() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[0]
+            .control_component_shuffle_payload_iter()
+            .find(|(i, _)| *i == 1)
+            .unwrap()
+            .1
+            .unwrap();
+        payload.verifiable_decryptions.decryption_proofs.pop();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[0]
+            .mock_control_component_shuffle_payloads(1, &Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+    }
+}
@@ -0,0 +1,168 @@
+use super::super::super::result::{
+    create_verification_error, create_verification_failure, EntityKind, VerificationEvent,
+    VerificationResult,
+};
+use super::verify_decryption_proofs;
+use crate::{
+    crypto_helpers::DecryptionProofContext,
+    file_structure::{
+        setup_directory::SetupDirectoryTrait,
+        tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
+        VerificationDirectoryTrait,
+    },
+    verification::VerificationContext,
+};
+use anyhow::anyhow;
+use log::debug;
+use rug::Integer;
+use rust_ev_crypto_primitives::EncryptionParameters;
+
+/// Verify the decryption proofs of the tally control component in a ballot box
+///
+/// The tally control component proves that the decrypted votes are the decryption of the
+/// shuffled ciphertexts with the key corresponding to the electoral board public key.
+///
+/// The shuffle argument is not verified.
+fn verify_bb_tally_decryptions<B: BBDirectoryTrait>(
+    bb_dir: &B,
+    eg: &EncryptionParameters,
+    ee_id: &str,
+    eb_pk: &[Integer],
+    result: &mut VerificationResult,
+) {
+    let bb_name = bb_dir.get_name();
+    let payload = match bb_dir.tally_component_shuffle_payload() {
+        Ok(p) => p,
+        Err(e) => {
+            result.push(create_verification_error!(
+                format!(
+                    "Cannot extract tally_component_shuffle_payload in ballot box {}",
+                    bb_name
+                ),
+                e
+            ));
+            return;
+        }
+    };
+    let decryption = &payload.verifiable_plaintext_decryption;
+    let messages = match decryption
+        .decrypted_votes
+        .iter()
+        .map(|v| v.message_as_integers())
+        .collect::<anyhow::Result<Vec<Vec<Integer>>>>()
+    {
+        Ok(m) => m,
+        Err(e) => {
+            result.push(create_verification_failure!(
+                format!(
+                    "Cannot decode the decrypted votes in ballot box {}",
+                    bb_name
+                ),
+                e
+            ));
+            result.push_failed_item("ballot_box", &bb_name, "decrypted votes");
+            return;
+        }
+    };
+    let i_aux = DecryptionProofContext::TallyControlComponent.i_aux(ee_id, &bb_name);
+    if !verify_decryption_proofs(
+        eg,
+        &payload.verifiable_shuffle.shuffled_ciphertexts,
+        eb_pk,
+        &messages,
+        &decryption.decryption_proofs,
+        &i_aux,
+        &format!("the tally control component in ballot box {}", bb_name),
+        result,
+    ) {
+        result.push_failed_item("ballot_box", &bb_name, "decryption proof");
+    }
+}
+
+pub(super) fn fn_verification<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    let setup_dir = dir.unwrap_setup();
+    let ee_context = match setup_dir.election_event_context_payload() {
+        Ok(o) => o,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract election_event_context_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let eb_pk = match setup_dir.setup_component_public_keys_payload() {
+        Ok(o) => o.setup_component_public_keys.electoral_board_public_key,
+        Err(e) => {
+            result.push(create_verification_error!(
+                "Cannot extract setup_component_public_keys_payload",
+                e
+            ));
+            return result;
+        }
+    };
+    let ee_id = &ee_context.election_event_context.election_event_id;
+    for bb_dir in tally_dir.bb_directories().iter() {
+        debug!(
+            "Verify tally decryptions of ballot box {}",
+            bb_dir.get_name()
+        );
+        let mut bb_result = VerificationResult::new();
+        verify_bb_tally_decryptions(
+            bb_dir,
+            &ee_context.encryption_group,
+            ee_id,
+            &eb_pk,
+            &mut bb_result,
+        );
+        bb_result.annotate(EntityKind::BallotBox, &bb_dir.get_name());
+        result.append_sub_result("ballot_box", &bb_dir.get_name(), &mut bb_result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::super::super::{result::VerificationResultTrait, VerificationPeriod},
+        *,
+    };
+    use crate::config::test::{
+        get_test_verifier_tally_dir as get_verifier_dir, test_dataset_tally_path, CONFIG_TEST,
+    };
+    use crate::file_structure::mock::MockVerificationDirectory;
+
+    #[test]
+    fn test_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_verification(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_decrypted_vote() {
+        let mut mock_dir =
+            MockVerificationDirectory::new(&VerificationPeriod::Tally, &test_dataset_tally_path());
+        let mut payload = mock_dir.tally().unwrap().bb_directories()[0]
+            .tally_component_shuffle_payload()
+            .unwrap();
+        payload.verifiable_plaintext_decryption.decrypted_votes[0].message[0] = "Aw==".to_string();
+        mock_dir.unwrap_tally_mut().bb_directories_mut()[0]
+            .mock_tally_component_shuffle_payload(&Ok(&payload));
+        let result = fn_verification(&mock_dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_failures().unwrap());
+        assert_eq!(result.failures().len(), 1);
+        assert!(!result.failed_items().is_empty());
+    }
+}
@@ -1,10 +1,17 @@
 use super::super::{
-    result::{create_verification_failure, VerificationEvent, VerificationResult},
-    suite::VerificationList,
+    duplicate_chunks::verify_no_duplicate_chunks,
+    result::{
+        create_verification_error, create_verification_failure, FailureCode, VerificationEvent,
+        VerificationResult,
+    },
+    suite::{SuiteBuildError, VerificationList},
+    validate_xml_file, validate_xml_str,
     verifications::Verification,
+    VerificationContext,
 };
 use crate::{
     config::Config,
+    data_structures::{tally::e_voting_decrypt::EVotingDecrypt, SchemaKind},
     file_structure::{
         tally_directory::{BBDirectoryTrait, TallyDirectoryTrait},
         VerificationDirectoryTrait,
@@ -17,15 +24,30 @@ use log::debug;
 pub fn get_verifications<'a>(
     metadata_list: &'a VerificationMetaDataList,
     config: &'static Config,
-) -> VerificationList<'a> {
-    VerificationList(vec![Verification::new(
-        "09.01",
-        "VerifyTallyIntegrity",
-        fn_0901_verify_tally_integrity,
-        metadata_list,
-        config,
-    )
-    .unwrap()])
+) -> Result<VerificationList<'a>, SuiteBuildError> {
+    VerificationList::from_results(vec![
+        Verification::new(
+            "09.01",
+            "VerifyTallyIntegrity",
+            fn_0901_verify_tally_integrity,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.09.02",
+            "VerifyTallyXmlSchemas",
+            fn_x0902_verify_tally_xml_schemas,
+            metadata_list,
+            config,
+        ),
+        Verification::new(
+            "X.09.01",
+            "VerifyTallyNoDuplicateChunks",
+            fn_x0901_verify_tally_no_duplicate_chunks,
+            metadata_list,
+            config,
+        ),
+    ])
 }
 
 fn validate_bb_dir<B: BBDirectoryTrait>(dir: &B, result: &mut VerificationResult) {
@@ -77,13 +99,96 @@ fn validate_bb_dir<B: BBDirectoryTrait>(dir: &B, result: &mut VerificationResult
 
 fn fn_0901_verify_tally_integrity<D: VerificationDirectoryTrait>(
     dir: &D,
-    _config: &'static Config,
-    result: &mut VerificationResult,
-) {
-    let setup_dir = dir.unwrap_tally();
-    for d in setup_dir.bb_directories().iter() {
-        validate_bb_dir(d, result);
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    for d in tally_dir.bb_directories().iter() {
+        validate_bb_dir(d, &mut result);
     }
+    result
+}
+
+fn fn_x0902_verify_tally_xml_schemas<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    // The XSD of the decrypt file depends on the version of the E-Voting system
+    match tally_dir.e_voting_decrypt_file().read_data() {
+        Ok(xml) => {
+            let version = EVotingDecrypt::schema_version(&xml);
+            debug!(
+                "evoting-decrypt validated with the schemas of version {}",
+                version
+            );
+            validate_xml_str(
+                &xml,
+                &SchemaKind::Decrypt,
+                version,
+                "evoting-decrypt",
+                &mut result,
+            )
+        }
+        Err(e) => result.push(
+            create_verification_error!("Cannot validate evoting-decrypt", e)
+                .with_code(FailureCode::ReadError)
+                .with_file("evoting-decrypt"),
+        ),
+    }
+    validate_xml_file(
+        tally_dir.ech_0110_file(),
+        &SchemaKind::Ech0110,
+        "eCH-0110",
+        &mut result,
+    );
+    validate_xml_file(
+        tally_dir.ech_0222_file(),
+        &SchemaKind::Ech0222,
+        "eCH-0222",
+        &mut result,
+    );
+    result
+}
+
+fn fn_x0901_verify_tally_no_duplicate_chunks<D: VerificationDirectoryTrait>(
+    dir: &D,
+    _context: &VerificationContext,
+) -> VerificationResult {
+    let mut result = VerificationResult::new();
+    let tally_dir = match dir.tally() {
+        Some(t) => t,
+        None => {
+            result.push(create_verification_error!("The tally directory is missing"));
+            return result;
+        }
+    };
+    for d in tally_dir.bb_directories().iter() {
+        verify_no_duplicate_chunks(
+            d.control_component_ballot_box_payload_group(),
+            &format!("{}/control_component_ballot_box_payload", d.get_name()),
+            &mut result,
+        );
+        verify_no_duplicate_chunks(
+            d.control_component_shuffle_payload_group(),
+            &format!("{}/control_component_shuffle_payload", d.get_name()),
+            &mut result,
+        );
+    }
+    result
 }
 
 #[cfg(test)]
@@ -92,13 +197,41 @@ mod test {
         super::super::result::{VerificationResult, VerificationResultTrait},
         *,
     };
-    use crate::config::test::{get_test_verifier_tally_dir as get_verifier_dir, CONFIG_TEST};
+    use crate::config::test::{
+        get_test_verifier_setup_dir, get_test_verifier_tally_dir as get_verifier_dir, CONFIG_TEST,
+    };
 
     #[test]
     fn test_ok() {
         let dir = get_verifier_dir();
-        let mut result = VerificationResult::new();
-        fn_0901_verify_tally_integrity(&dir, &CONFIG_TEST, &mut result);
+        let result = fn_0901_verify_tally_integrity(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.is_ok().unwrap());
+    }
+
+    #[test]
+    fn test_setup_directory() {
+        let dir = get_test_verifier_setup_dir();
+        let result = fn_0901_verify_tally_integrity(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.has_errors().unwrap());
+        assert!(result.errors_to_string()[0].contains("tally directory is missing"));
+    }
+
+    #[test]
+    fn test_x0901_ok() {
+        let dir = get_verifier_dir();
+        let result = fn_x0901_verify_tally_no_duplicate_chunks(
+            &dir,
+            &VerificationContext::new(&CONFIG_TEST),
+        );
         assert!(result.is_ok().unwrap());
     }
+
+    #[test]
+    fn test_x0902_ok() {
+        let dir = get_verifier_dir();
+        let result =
+            fn_x0902_verify_tally_xml_schemas(&dir, &VerificationContext::new(&CONFIG_TEST));
+        assert!(result.errors().is_empty(), "{:?}", result.errors());
+        assert!(result.failures().is_empty(), "{:?}", result.failures());
+    }
 }
@@ -1,16 +1,51 @@
 //! Module implementing the structure of a verification
 use super::{
     meta_data::{VerificationMetaData, VerificationMetaDataList},
-    result::{VerificationEvent, VerificationResult, VerificationResultTrait},
-    VerificationStatus,
+    result::{
+        FailedItem, FailureCode, SubResult, VerificationEvent, VerificationResult,
+        VerificationResultTrait,
+    },
+    VerificationContext, VerificationPeriod, VerificationStatus,
 };
 use crate::{
     config::Config,
-    file_structure::{VerificationDirectory, VerificationDirectoryTrait},
+    data_structures::dataset_version::DatasetVersion,
+    file_structure::{
+        file_access::with_recording, VerificationDirectory, VerificationDirectoryTrait,
+    },
+    human_format::HumanDuration,
+    memory_stats::{MemoryProbe, MemoryStats},
 };
-use anyhow::bail;
-use log::{info, warn};
-use std::time::{Duration, SystemTime};
+use anyhow::{anyhow, bail, Context};
+use log::{debug, info, warn};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+/// Observer of the run of the verifications, e.g. to render the progress in a frontend
+///
+/// The functions are called from the threads running the verifications. Per default, they
+/// do nothing.
+pub trait RunObserver: Send + Sync {
+    /// The verification `id` starts
+    fn on_start(&self, _id: &str) {}
+
+    /// The verification `id` is finished with the given result
+    fn on_finished(&self, _id: &str, _result: &VerificationResult) {}
+}
+
+impl RunObserver for Vec<Arc<dyn RunObserver>> {
+    fn on_start(&self, id: &str) {
+        self.iter().for_each(|o| o.on_start(id))
+    }
+
+    fn on_finished(&self, id: &str, result: &VerificationResult) {
+        self.iter().for_each(|o| o.on_finished(id, result))
+    }
+}
 
 /// Struct representing a verification
 #[allow(clippy::type_complexity)]
@@ -22,8 +57,10 @@ pub struct Verification<'a, D: VerificationDirectoryTrait> {
     /// The meta data is a reference to the metadata list loaded from json
     meta_data: &'a VerificationMetaData,
     status: VerificationStatus,
-    verification_fn: Box<dyn Fn(&D, &'static Config, &mut VerificationResult) + Send + Sync>,
+    verification_fn: Box<dyn Fn(&D, &VerificationContext) -> VerificationResult + Send + Sync>,
     duration: Option<Duration>,
+    /// Memory used by the verification (see [crate::memory_stats])
+    memory: Option<MemoryStats>,
     result: Box<VerificationResult>,
     config: &'static Config,
 }
@@ -37,14 +74,14 @@ impl<'a> Verification<'a, VerificationDirectory> {
     /// ```ignore
     /// fn fn_verification<D: VerificationDirectoryTrait>(
     ///    dir: &D,
-    ///    result: &mut VerificationResult,
-    /// ) {
+    ///    context: &VerificationContext,
+    /// ) -> VerificationResult {
     ///     ...
     /// }
     /// ```
     ///
-    /// The directory contains the directory where the folder setup and tally are located. The result [VerificationResult]
-    /// has to be changed according to the results of the verification (pushing errors and/or failures).
+    /// The directory contains the directory where the folder setup and tally are located. The function
+    /// returns the [VerificationResult] containing the errors and/or failures of the verification.
     /// The function is called by the method rust of the Verification.
     ///
     /// All the helpers functions called from `fn_verification` have also to take then traits as parameter
@@ -52,7 +89,7 @@ impl<'a> Verification<'a, VerificationDirectory> {
     pub fn new(
         id: &str,
         name: &str,
-        verification_fn: impl Fn(&VerificationDirectory, &'static Config, &mut VerificationResult)
+        verification_fn: impl Fn(&VerificationDirectory, &VerificationContext) -> VerificationResult
             + Send
             + Sync
             + 'static,
@@ -79,6 +116,7 @@ impl<'a> Verification<'a, VerificationDirectory> {
             status: VerificationStatus::Stopped,
             verification_fn: Box::new(verification_fn),
             duration: None,
+            memory: None,
             result: Box::new(VerificationResult::new()),
             config,
         })
@@ -88,13 +126,99 @@ impl<'a> Verification<'a, VerificationDirectory> {
         &self.id
     }
 
-    #[allow(dead_code)]
-    pub fn meta_data(&'a self) -> &'a VerificationMetaData {
+    pub fn meta_data(&self) -> &'a VerificationMetaData {
         self.meta_data
     }
 
+    /// Duration of the verification. `None` if not run
+    pub fn duration(&self) -> Option<HumanDuration> {
+        self.duration.map(HumanDuration)
+    }
+
+    /// Memory used by the verification. `None` if not run
+    pub fn memory(&self) -> Option<&MemoryStats> {
+        self.memory.as_ref()
+    }
+
+    /// Path of the csv file containing the failed items of the verification
+    pub fn failed_items_csv_path(&self) -> PathBuf {
+        self.config
+            .report_dir_path()
+            .join(format!("{}_failed_items.csv", self.id))
+    }
+
+    /// Export the failed items to the csv file given by [Self::failed_items_csv_path]
+    ///
+    /// Return the path of the file, or `None` if there is no failed item
+    pub fn export_failed_items(&self) -> anyhow::Result<Option<PathBuf>> {
+        if self.failed_items().is_empty() {
+            return Ok(None);
+        }
+        let path = self.failed_items_csv_path();
+        fs::create_dir_all(self.config.report_dir_path())
+            .context("Cannot create the report directory")?;
+        fs::write(&path, self.failed_items_to_csv())
+            .with_context(|| format!("Cannot write the failed items to {:?}", path))?;
+        info!(
+            "Failed items of verification {} ({}) exported to {:?}",
+            self.meta_data.name(),
+            self.meta_data.id(),
+            path
+        );
+        Ok(Some(path))
+    }
+
+    /// The verification can run on a dataset of the given period
+    pub fn is_applicable(&self, period: &VerificationPeriod) -> bool {
+        self.meta_data.period() == period
+    }
+
     /// Run the test.
+    ///
+    /// The accesses of the verification to the files are recorded (see [with_recording])
+    ///
+    /// If the verification is not applicable for the period of the directory (see
+    /// [Self::is_applicable]), the verification is not run and finishes with an error
+    ///
+    /// The version of the dataset is not given to the verification. Use
+    /// [Self::run_with_dataset_version] if it is known
     pub fn run(&mut self, directory: &VerificationDirectory) {
+        self.run_with_dataset_version(directory, None)
+    }
+
+    /// Run the test, with the version of the dataset in the context of the verification
+    ///
+    /// See [Self::run]
+    pub fn run_with_dataset_version(
+        &mut self,
+        directory: &VerificationDirectory,
+        dataset_version: Option<DatasetVersion>,
+    ) {
+        let period = directory.period();
+        if !self.is_applicable(&period) {
+            let mut result = VerificationResult::new();
+            result.push(
+                VerificationEvent::new_error(anyhow!(format!(
+                    "Verification {} ({}) of the {} is not applicable for a dataset of the {}",
+                    self.meta_data.name(),
+                    self.meta_data.id(),
+                    self.meta_data.period(),
+                    period
+                )))
+                .with_code(FailureCode::NotApplicable),
+            );
+            self.result = Box::new(result);
+            self.duration = None;
+            self.memory = None;
+            self.status = VerificationStatus::Finished;
+            warn!(
+                "Verification {} ({}) not run: not applicable for the {}",
+                self.meta_data.name(),
+                self.meta_data.id(),
+                period
+            );
+            return;
+        }
         self.status = VerificationStatus::Running;
         let start_time = SystemTime::now();
         info!(
@@ -102,33 +226,111 @@ impl<'a> Verification<'a, VerificationDirectory> {
             self.meta_data.name(),
             self.meta_data.id()
         );
-        (self.verification_fn)(directory, self.config, self.result.as_mut());
-        self.duration = Some(start_time.elapsed().unwrap());
+        let probe = MemoryProbe::start();
+        self.result = Box::new(with_recording(&self.id, || {
+            (self.verification_fn)(
+                directory,
+                &VerificationContext::new(self.config).with_dataset_version(dataset_version),
+            )
+        }));
+        let duration = start_time.elapsed().unwrap_or_default();
+        self.duration = Some(duration);
+        let memory = probe.stop();
+        debug!(
+            "Verification {} ({}) memory: {}",
+            self.meta_data.name(),
+            self.meta_data.id(),
+            memory
+        );
+        self.memory = Some(memory);
         self.status = VerificationStatus::Finished;
-        if self.is_ok().unwrap() {
+        if self.is_ok().unwrap_or(false) {
             info!(
-                "Verification {} ({}) finished successfully. Duration: {}s",
+                "Verification {} ({}) finished successfully. Duration: {}",
                 self.meta_data.name(),
                 self.meta_data.id(),
-                self.duration.unwrap().as_secs_f32()
+                HumanDuration(duration)
             );
         }
-        if self.has_errors().unwrap() {
+        if self.has_errors().unwrap_or(false) {
             warn!(
-                "Verification {} ({}) finished with errors. Duration: {}s",
+                "Verification {} ({}) finished with errors. Duration: {}",
                 self.meta_data.name(),
                 self.meta_data.id(),
-                self.duration.unwrap().as_secs_f32()
+                HumanDuration(duration)
             );
         }
-        if self.has_failures().unwrap() {
+        if self.has_failures().unwrap_or(false) {
             warn!(
-                "Verification {} ({}) finished with failures. Duration: {}s",
+                "Verification {} ({}) finished with failures. Duration: {}",
                 self.meta_data.name(),
                 self.meta_data.id(),
-                self.duration.unwrap().as_secs_f32()
+                HumanDuration(duration)
             );
+            if !self.meta_data.failure_guidance().is_empty() {
+                warn!(
+                    "Verification {} ({}): {}",
+                    self.meta_data.name(),
+                    self.meta_data.id(),
+                    self.meta_data.failure_guidance()
+                );
+            }
+        }
+    }
+
+    /// Run the verification (see [Self::run]), notifying the observer at the start and at the
+    /// end of the run
+    pub fn run_with_observer(
+        &mut self,
+        directory: &VerificationDirectory,
+        dataset_version: Option<DatasetVersion>,
+        observer: &dyn RunObserver,
+    ) {
+        observer.on_start(self.id());
+        self.run_with_dataset_version(directory, dataset_version);
+        observer.on_finished(self.id(), self.result());
+    }
+
+    /// Result of the verification (errors, failures and failed items)
+    ///
+    /// The result is empty if the verification has not run
+    pub fn result(&self) -> &VerificationResult {
+        &self.result
+    }
+
+    /// The verification has been run or restored
+    pub fn is_finished(&self) -> bool {
+        self.status == VerificationStatus::Finished
+    }
+
+    /// Restore the result of the verification from a previous run
+    ///
+    /// The verification is finished with the given errors and failures, without running it. The
+    /// duration, the memory and the failed items of the previous run are not restored.
+    pub fn restore(&mut self, errors: &[String], failures: &[String]) {
+        let mut result = VerificationResult::new();
+        for e in errors.iter() {
+            result.push(VerificationEvent::new_error(anyhow!(e.clone())));
         }
+        for f in failures.iter() {
+            result.push(VerificationEvent::new_failure(anyhow!(f.clone())));
+        }
+        self.result = Box::new(result);
+        self.duration = None;
+        self.memory = None;
+        self.status = VerificationStatus::Finished;
+        info!(
+            "Verification {} ({}) restored from a previous run",
+            self.meta_data.name(),
+            self.meta_data.id()
+        );
+    }
+
+    /// Documentation of the verification according to its result (see [VerificationMetaData::documentation])
+    ///
+    /// The guidance for the failures is given only if the verification has run and is not ok
+    pub fn documentation(&self) -> String {
+        self.meta_data.documentation(self.is_ok().unwrap_or(true))
     }
 }
 
@@ -180,15 +382,20 @@ impl<'a> VerificationResultTrait for Verification<'a, VerificationDirectory> {
     fn failures_to_string(&self) -> Vec<String> {
         self.result.failures_to_string()
     }
+
+    fn failed_items(&self) -> &Vec<FailedItem> {
+        self.result.failed_items()
+    }
+
+    fn sub_results(&self) -> &Vec<SubResult> {
+        self.result.sub_results()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::{
-        super::{
-            result::{create_verification_error, create_verification_failure},
-            VerificationPeriod,
-        },
+        super::result::{create_verification_error, create_verification_failure},
         *,
     };
     use crate::config::test::CONFIG_TEST;
@@ -198,7 +405,9 @@ mod test {
 
     #[test]
     fn test_creation() {
-        fn ok(_: &VerificationDirectory, _: &'static Config, _: &mut VerificationResult) {}
+        fn ok(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            VerificationResult::new()
+        }
         let md_list =
             VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
         assert!(Verification::new(
@@ -222,7 +431,9 @@ mod test {
 
     #[test]
     fn run_ok() {
-        fn ok(_: &VerificationDirectory, _: &'static Config, _: &mut VerificationResult) {}
+        fn ok(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            VerificationResult::new()
+        }
         let md_list =
             VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
         let mut verif = Verification::new(
@@ -247,12 +458,95 @@ mod test {
         assert!(!verif.has_failures().unwrap());
     }
 
+    #[test]
+    fn run_with_observer() {
+        struct Recorder(std::sync::Mutex<Vec<String>>);
+        impl RunObserver for Recorder {
+            fn on_start(&self, id: &str) {
+                self.0.lock().unwrap().push(format!("start {}", id))
+            }
+            fn on_finished(&self, id: &str, result: &VerificationResult) {
+                self.0.lock().unwrap().push(format!(
+                    "finished {} {}",
+                    id,
+                    result.has_errors().unwrap()
+                ))
+            }
+        }
+        fn error(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            let mut result = VerificationResult::new();
+            result.push(create_verification_error!("toto"));
+            result
+        }
+        let md_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut verif = Verification::new(
+            "01.01",
+            "VerifySetupCompleteness",
+            error,
+            &md_list,
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        let recorder = Arc::new(Recorder(std::sync::Mutex::new(vec![])));
+        let observers: Vec<Arc<dyn RunObserver>> = vec![recorder.clone(), recorder.clone()];
+        verif.run_with_observer(
+            &VerificationDirectory::new(&VerificationPeriod::Setup, Path::new(".")),
+            None,
+            &observers,
+        );
+        assert!(verif.is_finished());
+        assert_eq!(
+            recorder.0.lock().unwrap().clone(),
+            vec![
+                "start 01.01",
+                "start 01.01",
+                "finished 01.01 true",
+                "finished 01.01 true"
+            ]
+        );
+    }
+
+    #[test]
+    fn run_not_applicable() {
+        fn tally(dir: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            let _ = dir.tally().unwrap();
+            VerificationResult::new()
+        }
+        let md_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut verif = Verification::new(
+            "X.08.01",
+            "VerifyEch0222ConfirmedVotesConsistency",
+            tally,
+            &md_list,
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert!(verif.is_applicable(&VerificationPeriod::Tally));
+        assert!(!verif.is_applicable(&VerificationPeriod::Setup));
+        verif.run(&VerificationDirectory::new(
+            &VerificationPeriod::Setup,
+            Path::new("."),
+        ));
+        assert_eq!(verif.status, VerificationStatus::Finished);
+        assert!(verif.has_errors().unwrap());
+        assert!(verif.errors_to_string()[0].contains("not applicable"));
+        verif.run(&VerificationDirectory::new(
+            &VerificationPeriod::Tally,
+            Path::new("."),
+        ));
+        assert!(verif.is_ok().unwrap());
+    }
+
     #[test]
     fn run_error() {
-        fn error(_: &VerificationDirectory, _: &'static Config, result: &mut VerificationResult) {
+        fn error(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            let mut result = VerificationResult::new();
             result.push(create_verification_error!("toto"));
             result.push(create_verification_error!("toto2"));
             result.push(create_verification_failure!("toto3"));
+            result
         }
         let md_list =
             VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
@@ -282,9 +576,11 @@ mod test {
 
     #[test]
     fn run_failure() {
-        fn failure(_: &VerificationDirectory, _: &'static Config, result: &mut VerificationResult) {
+        fn failure(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            let mut result = VerificationResult::new();
             result.push(create_verification_failure!("toto"));
             result.push(create_verification_failure!("toto2"));
+            result
         }
         let md_list =
             VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
@@ -310,5 +606,31 @@ mod test {
         assert!(verif.has_failures().unwrap());
         assert_eq!(verif.errors().len(), 0);
         assert_eq!(verif.failures().len(), 2);
+        assert!(verif.memory().is_some());
+    }
+
+    #[test]
+    fn restore() {
+        fn ok(_: &VerificationDirectory, _: &VerificationContext) -> VerificationResult {
+            VerificationResult::new()
+        }
+        let md_list =
+            VerificationMetaDataList::load(CONFIG_TEST.get_verification_list_str()).unwrap();
+        let mut verif = Verification::new(
+            "01.01",
+            "VerifySetupCompleteness",
+            ok,
+            &md_list,
+            &CONFIG_TEST,
+        )
+        .unwrap();
+        assert!(!verif.is_finished());
+        verif.restore(&[], &["toto".to_string(), "toto2".to_string()]);
+        assert!(verif.is_finished());
+        assert!(!verif.has_errors().unwrap());
+        assert!(verif.has_failures().unwrap());
+        assert_eq!(verif.failures_to_string(), vec!["toto", "toto2"]);
+        assert!(verif.duration().is_none());
+        assert!(verif.memory().is_none());
     }
 }
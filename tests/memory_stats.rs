@@ -0,0 +1,24 @@
+//! Test of the measures of the heap
+//!
+//! The counting allocator is registered as global allocator of the test binary. The test is an
+//! integration test, so that the allocator of the unit tests of the library is not replaced.
+
+use rust_verifier::memory_stats::{is_heap_counted, CountingAllocator, MemoryProbe};
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[test]
+fn test_probe() {
+    let probe = MemoryProbe::start();
+    let v: Vec<u8> = vec![1; 1 << 20];
+    drop(v);
+    let stats = probe.stop();
+    assert!(is_heap_counted());
+    assert!(stats.peak_heap.unwrap().0 >= 1 << 20);
+    assert!(stats.allocated.unwrap().0 >= 1 << 20);
+    assert!(stats.allocations.unwrap() >= 1);
+    #[cfg(target_os = "linux")]
+    assert!(stats.rss.unwrap().0 > 0);
+    assert!(stats.to_string().starts_with("peak heap "));
+}
@@ -0,0 +1,35 @@
+//! Test of the override of the schemas
+//!
+//! The overrides are global to the process. The test is an integration test, so that it runs in
+//! its own process and does not affect the other tests (e.g. the service refuses the runs when a
+//! schema is overridden).
+
+#![cfg(feature = "native")]
+
+use rust_verifier::application_runner::{
+    embedded_xsd_text, override_schema, override_schema_from_file, xsd_text, SchemaKind,
+    SchemaResource, SchemaVersion,
+};
+use std::{env::temp_dir, fs};
+
+#[test]
+fn test_override() {
+    let embedded = embedded_xsd_text(SchemaKind::Ech0044, SchemaVersion::V1_2);
+    let path = temp_dir().join("rust_verifier_test_override_ech_0044.xsd");
+    let xsd = embedded.replacen("version=\"4.1\">", "version=\"4.2\">", 1);
+    fs::write(&path, &xsd).unwrap();
+    assert!(override_schema(SchemaKind::Ech0110, SchemaVersion::V1_2, &path).is_err());
+    assert_eq!(
+        override_schema_from_file(SchemaVersion::V1_2, &path).unwrap(),
+        SchemaKind::Ech0044
+    );
+    assert_eq!(xsd_text(SchemaKind::Ech0044, SchemaVersion::V1_2), xsd);
+    assert_eq!(
+        embedded_xsd_text(SchemaKind::Ech0044, SchemaVersion::V1_2),
+        embedded
+    );
+    let res = SchemaResource::new(SchemaKind::Ech0044, SchemaVersion::V1_2);
+    assert_eq!(res.overridden_by, Some(path.clone()));
+    assert_eq!(res.version, "4.2");
+    fs::remove_file(&path).unwrap();
+}